@@ -0,0 +1,17 @@
+//! Event decoding
+
+use crate::rpc::RpcClient;
+use ammv2_client::events::parse_logs;
+use anyhow::Result;
+
+/// Print the program's events in the transaction `signature`
+pub fn decode(rpc: &RpcClient, signature: &str) -> Result<()> {
+    let events = parse_logs(&rpc.get_transaction_logs(signature)?);
+    if events.is_empty() {
+        println!("No ammv2 events in {}", signature);
+    }
+    for event in events {
+        println!("{:#?}", event);
+    }
+    Ok(())
+}
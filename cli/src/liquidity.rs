@@ -0,0 +1,152 @@
+//! Deposits and withdrawals
+
+use crate::pool::{if_exists, PoolInfo};
+use crate::rpc::RpcClient;
+use ammv2_client::{pda, quote};
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::{system_program, InstructionData};
+use anchor_spl::associated_token::{self, spl_associated_token_account};
+use anchor_spl::memo;
+use anyhow::Result;
+use solana_keypair::Keypair;
+use solana_signer::Signer;
+
+/// Deposit `amount_a` of token A with up to `max_amount_b` of token B
+pub fn deposit(
+    rpc: &RpcClient,
+    user: &Keypair,
+    pool_address: Pubkey,
+    amount_a: u64,
+    max_amount_b: u64,
+    client_order_id: Option<u64>,
+) -> Result<()> {
+    let info = PoolInfo::fetch(rpc, pool_address)?;
+    let pool = &info.state;
+    let quote = quote::quote_deposit(
+        pool,
+        info.token_a_mint.decimals,
+        info.token_b_mint.decimals,
+        amount_a,
+        max_amount_b,
+    )?;
+    println!(
+        "Depositing {} A and {} B for {} LP tokens",
+        quote.token_a_amount, quote.token_b_amount, quote.lp_tokens
+    );
+
+    let owner = user.pubkey();
+    // A position's LP tokens are minted into and burned from its LP vault
+    let liquidity_position = if_exists(rpc, pda::liquidity_position(&pool_address, &owner).0)?;
+    let accounts = ammv2::accounts::DepositLiquidity {
+        liquidity_pool: pool_address,
+        amm_config: pda::amm_config().0,
+        pool_authority: pda::pool_authority(&pool_address).0,
+        token_a_mint: pool.token_a_mint,
+        token_b_mint: pool.token_b_mint,
+        token_a_vault: pda::token_a_vault(&pool_address).0,
+        token_b_vault: pda::token_b_vault(&pool_address).0,
+        lp_token_mint: pda::lp_token_mint(&pool_address).0,
+        sub_account: None,
+        user: owner,
+        lp_token_owner: owner,
+        token_program: info.lp_token_program,
+        token_a_program: info.token_a_program,
+        token_b_program: info.token_b_program,
+        associated_token_program: associated_token::ID,
+        system_program: system_program::ID,
+        user_token_a_account: info.user_token_account(&owner, &pool.token_a_mint),
+        user_token_b_account: info.user_token_account(&owner, &pool.token_b_mint),
+        user_lp_token_account: info.user_lp_token_account(&owner),
+        lp_whitelist_entry: if_exists(rpc, pda::lp_whitelist_entry(&pool_address, &owner).0)?,
+        liquidity_position,
+        position_lp_vault: liquidity_position.map(|position| pda::position_lp_vault(&position).0),
+    };
+    let data = ammv2::instruction::DepositLiquidity {
+        token_a_amount: amount_a,
+        token_b_amount: max_amount_b,
+        client_order_id,
+    };
+    let signature = rpc.send(
+        &[Instruction {
+            program_id: ammv2::ID,
+            accounts: accounts.to_account_metas(None),
+            data: data.data(),
+        }],
+        user,
+    )?;
+
+    println!("Signature: {}", signature);
+    Ok(())
+}
+
+/// Burn `lp_amount` LP tokens for a share of both reserves, creating the
+/// user's token accounts when they do not exist yet
+pub fn withdraw(
+    rpc: &RpcClient,
+    user: &Keypair,
+    pool_address: Pubkey,
+    lp_amount: u64,
+    client_order_id: Option<u64>,
+) -> Result<()> {
+    let info = PoolInfo::fetch(rpc, pool_address)?;
+    let pool = &info.state;
+    let quote = quote::quote_withdraw(pool, lp_amount)?;
+    println!(
+        "Burning {} LP tokens for {} A and {} B",
+        lp_amount, quote.token_a_amount, quote.token_b_amount
+    );
+
+    let owner = user.pubkey();
+    let mut instructions = Vec::new();
+    for (mint, token_program) in [
+        (pool.token_a_mint, info.token_a_program),
+        (pool.token_b_mint, info.token_b_program),
+    ] {
+        instructions.push(
+            spl_associated_token_account::instruction::create_associated_token_account_idempotent(
+                &owner,
+                &owner,
+                &mint,
+                &token_program,
+            ),
+        );
+    }
+
+    let liquidity_position = if_exists(rpc, pda::liquidity_position(&pool_address, &owner).0)?;
+    let accounts = ammv2::accounts::WithdrawLiquidity {
+        liquidity_pool: pool_address,
+        pool_authority: pda::pool_authority(&pool_address).0,
+        token_a_mint: pool.token_a_mint,
+        token_b_mint: pool.token_b_mint,
+        token_a_vault: pda::token_a_vault(&pool_address).0,
+        token_b_vault: pda::token_b_vault(&pool_address).0,
+        lp_token_mint: pda::lp_token_mint(&pool_address).0,
+        sub_account: None,
+        user: owner,
+        lp_token_owner: owner,
+        token_program: info.lp_token_program,
+        token_a_program: info.token_a_program,
+        token_b_program: info.token_b_program,
+        user_token_a_account: info.user_token_account(&owner, &pool.token_a_mint),
+        user_token_b_account: info.user_token_account(&owner, &pool.token_b_mint),
+        user_lp_token_account: info.user_lp_token_account(&owner),
+        memo_program: Some(memo::ID),
+        liquidity_position,
+        position_lp_vault: liquidity_position.map(|position| pda::position_lp_vault(&position).0),
+    };
+    let data = ammv2::instruction::WithdrawLiquidity {
+        lp_tokens_to_burn: lp_amount,
+        note: None,
+        client_order_id,
+    };
+    instructions.push(Instruction {
+        program_id: ammv2::ID,
+        accounts: accounts.to_account_metas(None),
+        data: data.data(),
+    });
+    let signature = rpc.send(&instructions, user)?;
+
+    println!("Signature: {}", signature);
+    Ok(())
+}
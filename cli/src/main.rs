@@ -0,0 +1,297 @@
+//! `ammv2`: create pools, move liquidity, swap, inspect pool state and decode
+//! events against any cluster.
+//!
+//! Token amounts are raw base units throughout, as the program takes them.
+
+mod events;
+mod liquidity;
+mod pool;
+mod rpc;
+mod swap;
+
+use anchor_lang::prelude::Pubkey;
+use anyhow::{anyhow, Result};
+use clap::{Parser, Subcommand};
+use rpc::RpcClient;
+use solana_keypair::{read_keypair_file, Keypair};
+use std::path::PathBuf;
+
+#[derive(Parser)]
+#[command(
+    name = "ammv2",
+    version,
+    about = "Pool operations for the ammv2 program"
+)]
+struct Cli {
+    /// JSON-RPC URL, or one of the monikers mainnet-beta, devnet, testnet, localhost
+    #[arg(
+        long,
+        short = 'u',
+        env = "AMMV2_URL",
+        default_value = "localhost",
+        global = true
+    )]
+    url: String,
+
+    /// Keypair that signs and pays for transactions [default: ~/.config/solana/id.json]
+    #[arg(long, short = 'k', env = "AMMV2_KEYPAIR", global = true)]
+    keypair: Option<PathBuf>,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Create a pool for a mint pair; the signer becomes its admin
+    CreatePool {
+        /// Token A mint
+        #[arg(long)]
+        mint_a: Pubkey,
+
+        /// Token B mint
+        #[arg(long)]
+        mint_b: Pubkey,
+
+        /// Swap fee numerator
+        #[arg(long)]
+        fee_numerator: u64,
+
+        /// Swap fee denominator
+        #[arg(long)]
+        fee_denominator: u64,
+
+        /// URI of the LP token metadata
+        #[arg(long, default_value = "")]
+        lp_metadata_uri: String,
+
+        /// Slot until which only launch-whitelisted wallets may swap (0 = none)
+        #[arg(long, default_value_t = 0)]
+        launch_whitelist_end_slot: u64,
+    },
+
+    /// Print a pool's state, reserves and LP supply
+    ShowPool {
+        /// Pool address
+        pool: Pubkey,
+    },
+
+    /// Deposit liquidity at the pool ratio
+    Deposit {
+        /// Pool address
+        #[arg(long)]
+        pool: Pubkey,
+
+        /// Token A to deposit
+        #[arg(long)]
+        amount_a: u64,
+
+        /// Most token B to deposit; the pool ratio decides how much is taken
+        #[arg(long)]
+        max_amount_b: u64,
+
+        /// Order id echoed in the deposit event
+        #[arg(long)]
+        client_order_id: Option<u64>,
+    },
+
+    /// Burn LP tokens for a share of both reserves
+    Withdraw {
+        /// Pool address
+        #[arg(long)]
+        pool: Pubkey,
+
+        /// LP tokens to burn
+        #[arg(long)]
+        lp_amount: u64,
+
+        /// Order id echoed in the withdrawal event
+        #[arg(long)]
+        client_order_id: Option<u64>,
+    },
+
+    /// Swap an exact input amount
+    Swap {
+        /// Pool address
+        #[arg(long)]
+        pool: Pubkey,
+
+        /// Mint of the token sold
+        #[arg(long)]
+        input_mint: Pubkey,
+
+        /// Tokens to sell
+        #[arg(long)]
+        amount: u64,
+
+        /// Least output accepted [default: the quote less --slippage-bps]
+        #[arg(long)]
+        min_output: Option<u64>,
+
+        /// Slippage from the quote allowed when --min-output is not given
+        #[arg(long, default_value_t = 50)]
+        slippage_bps: u16,
+
+        /// Order id echoed in the swap event
+        #[arg(long)]
+        client_order_id: Option<u64>,
+    },
+
+    /// Decode the program's events in a confirmed transaction
+    DecodeEvents {
+        /// Transaction signature
+        signature: String,
+    },
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    let rpc = RpcClient::new(cluster_url(&cli.url));
+    let keypair = || load_keypair(cli.keypair.clone());
+
+    match cli.command {
+        Command::CreatePool {
+            mint_a,
+            mint_b,
+            fee_numerator,
+            fee_denominator,
+            lp_metadata_uri,
+            launch_whitelist_end_slot,
+        } => pool::create(
+            &rpc,
+            &keypair()?,
+            mint_a,
+            mint_b,
+            fee_numerator,
+            fee_denominator,
+            lp_metadata_uri,
+            launch_whitelist_end_slot,
+        ),
+        Command::ShowPool { pool } => pool::show(&rpc, pool),
+        Command::Deposit {
+            pool,
+            amount_a,
+            max_amount_b,
+            client_order_id,
+        } => liquidity::deposit(
+            &rpc,
+            &keypair()?,
+            pool,
+            amount_a,
+            max_amount_b,
+            client_order_id,
+        ),
+        Command::Withdraw {
+            pool,
+            lp_amount,
+            client_order_id,
+        } => liquidity::withdraw(&rpc, &keypair()?, pool, lp_amount, client_order_id),
+        Command::Swap {
+            pool,
+            input_mint,
+            amount,
+            min_output,
+            slippage_bps,
+            client_order_id,
+        } => swap::swap(
+            &rpc,
+            &keypair()?,
+            pool,
+            input_mint,
+            amount,
+            min_output,
+            slippage_bps,
+            client_order_id,
+        ),
+        Command::DecodeEvents { signature } => events::decode(&rpc, &signature),
+    }
+}
+
+/// JSON-RPC URL of a cluster moniker, or `url` itself
+fn cluster_url(url: &str) -> String {
+    match url {
+        "mainnet-beta" | "m" => "https://api.mainnet-beta.solana.com",
+        "devnet" | "d" => "https://api.devnet.solana.com",
+        "testnet" | "t" => "https://api.testnet.solana.com",
+        "localhost" | "l" => "http://127.0.0.1:8899",
+        url => url,
+    }
+    .to_string()
+}
+
+fn load_keypair(path: Option<PathBuf>) -> Result<Keypair> {
+    let path = match path {
+        Some(path) => path,
+        None => std::env::var_os("HOME")
+            .map(|home| PathBuf::from(home).join(".config/solana/id.json"))
+            .ok_or_else(|| anyhow!("HOME is not set; pass --keypair"))?,
+    };
+    read_keypair_file(&path)
+        .map_err(|err| anyhow!("Failed to read keypair {}: {}", path.display(), err))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn swap_arguments_parse_with_their_defaults() {
+        let pool = Pubkey::new_unique();
+        let mint = Pubkey::new_unique();
+        let cli = Cli::try_parse_from([
+            "ammv2",
+            "swap",
+            "--pool",
+            &pool.to_string(),
+            "--input-mint",
+            &mint.to_string(),
+            "--amount",
+            "1000",
+            "-u",
+            "devnet",
+        ])
+        .unwrap();
+
+        assert_eq!(cluster_url(&cli.url), "https://api.devnet.solana.com");
+        match cli.command {
+            Command::Swap {
+                pool: parsed_pool,
+                input_mint,
+                amount,
+                min_output,
+                slippage_bps,
+                client_order_id,
+            } => {
+                assert_eq!((parsed_pool, input_mint), (pool, mint));
+                assert_eq!(amount, 1_000);
+                assert_eq!((min_output, slippage_bps), (None, 50));
+                assert_eq!(client_order_id, None);
+            }
+            _ => panic!("expected the swap command"),
+        }
+    }
+
+    #[test]
+    fn malformed_arguments_are_rejected() {
+        let pool = Pubkey::new_unique().to_string();
+        assert!(Cli::try_parse_from(["ammv2", "show-pool", "not-a-pubkey"]).is_err());
+        assert!(Cli::try_parse_from(["ammv2", "withdraw", "--pool", &pool]).is_err());
+        assert!(
+            Cli::try_parse_from(["ammv2", "withdraw", "--pool", &pool, "--lp-amount", "-1"])
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn cluster_monikers_expand_and_urls_pass_through() {
+        assert_eq!(cluster_url("l"), "http://127.0.0.1:8899");
+        assert_eq!(
+            cluster_url("mainnet-beta"),
+            "https://api.mainnet-beta.solana.com"
+        );
+        assert_eq!(
+            cluster_url("https://rpc.example.com"),
+            "https://rpc.example.com"
+        );
+    }
+}
@@ -0,0 +1,244 @@
+//! Pool creation and inspection, and the on-chain pool state other commands build on
+
+use crate::rpc::RpcClient;
+use ammv2::state::{AmmConfig, LiquidityPool};
+use ammv2_client::{pda, state};
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::{system_program, InstructionData};
+use anchor_spl::associated_token::{self, get_associated_token_address_with_program_id};
+use anchor_spl::metadata;
+use anchor_spl::token_interface::{Mint, TokenAccount};
+use anyhow::{anyhow, Result};
+use solana_keypair::Keypair;
+use solana_signer::Signer;
+
+/// A pool with the mint and vault state its instructions are quoted against
+pub struct PoolInfo {
+    pub address: Pubkey,
+    pub state: LiquidityPool,
+    pub token_a_program: Pubkey,
+    pub token_b_program: Pubkey,
+    pub lp_token_program: Pubkey,
+    pub token_a_mint: Mint,
+    pub token_b_mint: Mint,
+    pub lp_token_mint: Mint,
+    pub vault_a_balance: u64,
+    pub vault_b_balance: u64,
+}
+
+impl PoolInfo {
+    /// Fetch the pool at `address` along with its mints and vaults
+    pub fn fetch(rpc: &RpcClient, address: Pubkey) -> Result<Self> {
+        let pool_account = rpc.require_account(&address, "Pool")?;
+        require_program_owned(&pool_account.owner, &address)?;
+        let pool = state::decode_pool(&pool_account.data)?;
+
+        let addresses = [
+            pool.token_a_mint,
+            pool.token_b_mint,
+            pda::lp_token_mint(&address).0,
+            pda::token_a_vault(&address).0,
+            pda::token_b_vault(&address).0,
+        ];
+        let mut accounts = rpc
+            .get_multiple_accounts(&addresses)?
+            .into_iter()
+            .zip(addresses)
+            .map(|(account, address)| {
+                account.ok_or_else(|| anyhow!("Pool account {} does not exist", address))
+            })
+            .collect::<Result<Vec<_>>>()?
+            .into_iter();
+        let mut next = || accounts.next().expect("one account per address");
+        let (mint_a, mint_b, lp_mint, vault_a, vault_b) = (next(), next(), next(), next(), next());
+
+        Ok(Self {
+            address,
+            state: pool,
+            token_a_program: mint_a.owner,
+            token_b_program: mint_b.owner,
+            lp_token_program: lp_mint.owner,
+            token_a_mint: Mint::try_deserialize(&mut &mint_a.data[..])?,
+            token_b_mint: Mint::try_deserialize(&mut &mint_b.data[..])?,
+            lp_token_mint: Mint::try_deserialize(&mut &lp_mint.data[..])?,
+            vault_a_balance: TokenAccount::try_deserialize(&mut &vault_a.data[..])?.amount,
+            vault_b_balance: TokenAccount::try_deserialize(&mut &vault_b.data[..])?.amount,
+        })
+    }
+
+    /// Token program of `mint`, one of the pool's two mints
+    pub fn token_program(&self, mint: &Pubkey) -> Pubkey {
+        if *mint == self.state.token_a_mint {
+            self.token_a_program
+        } else {
+            self.token_b_program
+        }
+    }
+
+    /// Associated token account of `owner` for `mint`, one of the pool's mints
+    pub fn user_token_account(&self, owner: &Pubkey, mint: &Pubkey) -> Pubkey {
+        get_associated_token_address_with_program_id(owner, mint, &self.token_program(mint))
+    }
+
+    /// Associated LP token account of `owner`
+    pub fn user_lp_token_account(&self, owner: &Pubkey) -> Pubkey {
+        get_associated_token_address_with_program_id(
+            owner,
+            &pda::lp_token_mint(&self.address).0,
+            &self.lp_token_program,
+        )
+    }
+}
+
+/// `address` if an account exists there, for optional instruction accounts
+pub fn if_exists(rpc: &RpcClient, address: Pubkey) -> Result<Option<Pubkey>> {
+    Ok(rpc.exists(&address)?.then_some(address))
+}
+
+fn require_program_owned(owner: &Pubkey, address: &Pubkey) -> Result<()> {
+    if *owner != ammv2::ID {
+        return Err(anyhow!("{} is not owned by the ammv2 program", address));
+    }
+    Ok(())
+}
+
+/// Create the pool of `mint_a` and `mint_b`, paid for by `payer`, which
+/// becomes the pool admin
+#[allow(clippy::too_many_arguments)]
+pub fn create(
+    rpc: &RpcClient,
+    payer: &Keypair,
+    mint_a: Pubkey,
+    mint_b: Pubkey,
+    fee_numerator: u64,
+    fee_denominator: u64,
+    lp_metadata_uri: String,
+    launch_whitelist_end_slot: u64,
+) -> Result<()> {
+    let token_a_program = rpc.require_account(&mint_a, "Mint")?.owner;
+    let token_b_program = rpc.require_account(&mint_b, "Mint")?.owner;
+    let amm_config_address = pda::amm_config().0;
+    let amm_config: AmmConfig = state::decode_account(
+        &rpc.require_account(&amm_config_address, "Global config")?
+            .data,
+    )?;
+
+    let liquidity_pool = pda::liquidity_pool(&mint_a, &mint_b).0;
+    let lp_token_mint = pda::lp_token_mint(&liquidity_pool).0;
+    let metadata_address = |mint: &Pubkey| {
+        Pubkey::find_program_address(
+            &[b"metadata", metadata::ID.as_ref(), mint.as_ref()],
+            &metadata::ID,
+        )
+        .0
+    };
+
+    let accounts = ammv2::accounts::InitializePool {
+        token_a_mint: mint_a,
+        token_b_mint: mint_b,
+        liquidity_pool,
+        oracle: pda::oracle(&liquidity_pool).0,
+        pool_authority: pda::pool_authority(&liquidity_pool).0,
+        token_a_vault: pda::token_a_vault(&liquidity_pool).0,
+        token_b_vault: pda::token_b_vault(&liquidity_pool).0,
+        lp_token_mint,
+        lp_token_metadata: metadata_address(&lp_token_mint),
+        token_a_metadata: if_exists(rpc, metadata_address(&mint_a))?,
+        token_b_metadata: if_exists(rpc, metadata_address(&mint_b))?,
+        token_a_mint_approval: if_exists(rpc, pda::unsafe_mint_approval(&mint_a).0)?,
+        token_b_mint_approval: if_exists(rpc, pda::unsafe_mint_approval(&mint_b).0)?,
+        amm_config: amm_config_address,
+        treasury: amm_config.treasury,
+        payer: payer.pubkey(),
+        token_a_program,
+        token_b_program,
+        system_program: system_program::ID,
+        token_program: anchor_spl::token::ID,
+        associated_token_program: associated_token::ID,
+        token_metadata_program: metadata::ID,
+        rent: anchor_lang::solana_program::sysvar::rent::ID,
+    };
+    let data = ammv2::instruction::InitializePool {
+        fee_basis_points_numerator: fee_numerator,
+        fee_basis_points_denominator: fee_denominator,
+        lp_metadata_uri,
+        launch_whitelist_end_slot,
+    };
+    let signature = rpc.send(
+        &[Instruction {
+            program_id: ammv2::ID,
+            accounts: accounts.to_account_metas(None),
+            data: data.data(),
+        }],
+        payer,
+    )?;
+
+    println!("Pool: {}", liquidity_pool);
+    println!("LP token mint: {}", lp_token_mint);
+    println!("Signature: {}", signature);
+    Ok(())
+}
+
+/// Print the state of the pool at `address`
+pub fn show(rpc: &RpcClient, address: Pubkey) -> Result<()> {
+    let info = PoolInfo::fetch(rpc, address)?;
+    let pool = &info.state;
+
+    println!("Pool: {}", address);
+    println!("Layout version: {}", pool.version);
+    println!("Admin: {}", pool.admin);
+    println!(
+        "Token A: {} ({} decimals, program {})",
+        pool.token_a_mint, info.token_a_mint.decimals, info.token_a_program
+    );
+    println!(
+        "Token B: {} ({} decimals, program {})",
+        pool.token_b_mint, info.token_b_mint.decimals, info.token_b_program
+    );
+    println!(
+        "Vault balances: {} A / {} B",
+        info.vault_a_balance, info.vault_b_balance
+    );
+    println!(
+        "Tracked reserves: {} A / {} B",
+        pool.reserve_a, pool.reserve_b
+    );
+    println!(
+        "LP supply: {} (pool counter {})",
+        info.lp_token_mint.supply, pool.total_lp_tokens_issued
+    );
+    if pool.reserve_a > 0 {
+        let price = pool.reserve_b as f64 / pool.reserve_a as f64
+            * 10f64.powi(info.token_a_mint.decimals as i32 - info.token_b_mint.decimals as i32);
+        println!("Spot price: {} B per A", price);
+    }
+    if pool.dynamic_fee_max_bps > 0 {
+        println!(
+            "Fee: {} bps dynamic ({}-{} bps)",
+            pool.dynamic_fee_bps, pool.dynamic_fee_min_bps, pool.dynamic_fee_max_bps
+        );
+    } else {
+        println!("Fee: {}/{}", pool.fee_numerator, pool.fee_denominator);
+    }
+    println!(
+        "Fee charged on: {}",
+        if pool.fee_on_output != 0 {
+            "output"
+        } else {
+            "input"
+        }
+    );
+    println!(
+        "Protocol fee share: {} bps, creator fee share: {} bps, referral fee share: {} bps",
+        pool.protocol_fee_share_bps, pool.creator_fee_bps, pool.referral_fee_bps
+    );
+    println!(
+        "Keeper tip: {} (budget {}, funded by {} bps of protocol fees)",
+        pool.keeper_tip, pool.keeper_tip_budget, pool.keeper_tip_share_bps
+    );
+    println!("Oracle: {}", pool.oracle);
+    println!("Withdraw only: {}", pool.withdraw_only != 0);
+    println!("Disabled operations: {:#06b}", pool.disabled_operations);
+    Ok(())
+}
@@ -0,0 +1,191 @@
+//! Minimal JSON-RPC client for the handful of cluster calls the CLI makes
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::hash::Hash;
+use anchor_lang::solana_program::instruction::Instruction;
+use anyhow::{anyhow, bail, Context, Result};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use serde_json::{json, Value};
+use solana_keypair::Keypair;
+use solana_signer::Signer;
+use solana_transaction::Transaction;
+use std::str::FromStr;
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+
+/// Commitment every call reads at and every transaction is confirmed to
+const COMMITMENT: &str = "confirmed";
+
+/// How long `send` waits for a transaction to reach `COMMITMENT`
+const CONFIRM_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Raw account as returned by `getAccountInfo`
+pub struct AccountData {
+    /// Program owning the account
+    pub owner: Pubkey,
+
+    /// Account data, discriminator included
+    pub data: Vec<u8>,
+}
+
+pub struct RpcClient {
+    agent: ureq::Agent,
+    url: String,
+}
+
+impl RpcClient {
+    pub fn new(url: String) -> Self {
+        Self {
+            agent: ureq::Agent::new_with_defaults(),
+            url,
+        }
+    }
+
+    /// Call `method` and return its `result`, turning JSON-RPC errors into errors
+    fn call(&self, method: &str, params: Value) -> Result<Value> {
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": method,
+            "params": params,
+        });
+        let mut response: Value = self
+            .agent
+            .post(&self.url)
+            .send_json(&request)
+            .with_context(|| format!("{} request to {} failed", method, self.url))?
+            .into_body()
+            .read_json()?;
+        if let Some(error) = response.get("error") {
+            bail!("{} failed: {}", method, error);
+        }
+        Ok(response["result"].take())
+    }
+
+    /// Account at `address`, or `None` when it does not exist
+    pub fn get_account(&self, address: &Pubkey) -> Result<Option<AccountData>> {
+        let result = self.call(
+            "getAccountInfo",
+            json!([address.to_string(), { "encoding": "base64", "commitment": COMMITMENT }]),
+        )?;
+        parse_account(&result["value"])
+    }
+
+    /// Accounts at `addresses`, in order, `None` for those that do not exist
+    pub fn get_multiple_accounts(&self, addresses: &[Pubkey]) -> Result<Vec<Option<AccountData>>> {
+        let keys: Vec<String> = addresses.iter().map(Pubkey::to_string).collect();
+        let result = self.call(
+            "getMultipleAccounts",
+            json!([keys, { "encoding": "base64", "commitment": COMMITMENT }]),
+        )?;
+        result["value"]
+            .as_array()
+            .ok_or_else(|| anyhow!("getMultipleAccounts returned no accounts"))?
+            .iter()
+            .map(parse_account)
+            .collect()
+    }
+
+    /// Account at `address`, failing with `what` in the message when it is missing
+    pub fn require_account(&self, address: &Pubkey, what: &str) -> Result<AccountData> {
+        self.get_account(address)?
+            .ok_or_else(|| anyhow!("{} {} does not exist", what, address))
+    }
+
+    /// Whether an account exists at `address`
+    pub fn exists(&self, address: &Pubkey) -> Result<bool> {
+        Ok(self.get_account(address)?.is_some())
+    }
+
+    /// Current slot
+    pub fn get_slot(&self) -> Result<u64> {
+        self.call("getSlot", json!([{ "commitment": COMMITMENT }]))?
+            .as_u64()
+            .ok_or_else(|| anyhow!("getSlot returned no slot"))
+    }
+
+    fn get_latest_blockhash(&self) -> Result<Hash> {
+        let result = self.call("getLatestBlockhash", json!([{ "commitment": COMMITMENT }]))?;
+        let blockhash = result["value"]["blockhash"]
+            .as_str()
+            .ok_or_else(|| anyhow!("getLatestBlockhash returned no blockhash"))?;
+        Ok(Hash::from_str(blockhash)?)
+    }
+
+    /// Sign `instructions` with `payer`, send them as one transaction and wait
+    /// for confirmation. Returns the transaction signature.
+    pub fn send(&self, instructions: &[Instruction], payer: &Keypair) -> Result<String> {
+        let transaction = Transaction::new_signed_with_payer(
+            instructions,
+            Some(&payer.pubkey()),
+            &[payer],
+            self.get_latest_blockhash()?,
+        );
+        let encoded = BASE64.encode(bincode::serialize(&transaction)?);
+        let signature = self
+            .call(
+                "sendTransaction",
+                json!([encoded, { "encoding": "base64", "preflightCommitment": COMMITMENT }]),
+            )?
+            .as_str()
+            .ok_or_else(|| anyhow!("sendTransaction returned no signature"))?
+            .to_string();
+
+        let started = Instant::now();
+        while started.elapsed() < CONFIRM_TIMEOUT {
+            let result = self.call("getSignatureStatuses", json!([[signature]]))?;
+            let status = &result["value"][0];
+            if !status.is_null() {
+                if !status["err"].is_null() {
+                    bail!("Transaction {} failed: {}", signature, status["err"]);
+                }
+                if matches!(
+                    status["confirmationStatus"].as_str(),
+                    Some("confirmed" | "finalized")
+                ) {
+                    return Ok(signature);
+                }
+            }
+            sleep(Duration::from_millis(500));
+        }
+        bail!("Transaction {} was not confirmed in time", signature)
+    }
+
+    /// Log messages of a confirmed transaction
+    pub fn get_transaction_logs(&self, signature: &str) -> Result<Vec<String>> {
+        let result = self.call(
+            "getTransaction",
+            json!([signature, {
+                "encoding": "json",
+                "commitment": COMMITMENT,
+                "maxSupportedTransactionVersion": 0,
+            }]),
+        )?;
+        if result.is_null() {
+            bail!("Transaction {} not found", signature);
+        }
+        Ok(result["meta"]["logMessages"]
+            .as_array()
+            .ok_or_else(|| anyhow!("Transaction {} has no log messages", signature))?
+            .iter()
+            .filter_map(|line| line.as_str().map(str::to_string))
+            .collect())
+    }
+}
+
+fn parse_account(value: &Value) -> Result<Option<AccountData>> {
+    if value.is_null() {
+        return Ok(None);
+    }
+    let owner = value["owner"]
+        .as_str()
+        .ok_or_else(|| anyhow!("Account has no owner"))?;
+    let data = value["data"][0]
+        .as_str()
+        .ok_or_else(|| anyhow!("Account has no base64 data"))?;
+    Ok(Some(AccountData {
+        owner: Pubkey::from_str(owner)?,
+        data: BASE64.decode(data)?,
+    }))
+}
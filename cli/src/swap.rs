@@ -0,0 +1,148 @@
+//! Exact-input swaps
+
+use crate::pool::{if_exists, PoolInfo};
+use crate::rpc::RpcClient;
+use ammv2::math;
+use ammv2::state::BASIS_POINTS_DIVISOR;
+use ammv2_client::{pda, quote};
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::{system_program, InstructionData};
+use anchor_spl::associated_token;
+use anchor_spl::memo;
+use anyhow::{bail, Result};
+use solana_keypair::Keypair;
+use solana_signer::Signer;
+
+/// Swap `amount` of `input_mint` for the pool's other token. Without
+/// `min_output`, the least output accepted is the quote less `slippage_bps`.
+#[allow(clippy::too_many_arguments)]
+pub fn swap(
+    rpc: &RpcClient,
+    user: &Keypair,
+    pool_address: Pubkey,
+    input_mint: Pubkey,
+    amount: u64,
+    min_output: Option<u64>,
+    slippage_bps: u16,
+    client_order_id: Option<u64>,
+) -> Result<()> {
+    let info = PoolInfo::fetch(rpc, pool_address)?;
+    let pool = &info.state;
+    let (output_mint, input_vault, output_vault, input_balance, output_balance) =
+        if input_mint == pool.token_a_mint {
+            (
+                pool.token_b_mint,
+                pda::token_a_vault(&pool_address).0,
+                pda::token_b_vault(&pool_address).0,
+                info.vault_a_balance,
+                info.vault_b_balance,
+            )
+        } else if input_mint == pool.token_b_mint {
+            (
+                pool.token_a_mint,
+                pda::token_b_vault(&pool_address).0,
+                pda::token_a_vault(&pool_address).0,
+                info.vault_b_balance,
+                info.vault_a_balance,
+            )
+        } else {
+            bail!("{} is not one of the pool's mints", input_mint);
+        };
+
+    let quote = quote::quote_swap(pool, input_balance, output_balance, amount)?;
+    let minimum_output_amount = minimum_output(quote.output_amount, min_output, slippage_bps)?;
+    println!(
+        "Swapping {} for {} (fee {}), accepting at least {}",
+        amount, quote.output_amount, quote.fee_amount, minimum_output_amount
+    );
+
+    // Optional accounts, passed when the pool's configuration requires them
+    let owner = user.pubkey();
+    let slot = rpc.get_slot()?;
+    let fee_mint = pool.fee_mint(&input_mint, &output_mint);
+    let fee_recipient = pool.fee_recipient(&fee_mint);
+    let creator_fee_account = pool.creator_fee_account(&fee_mint);
+    let accounts = ammv2::accounts::SwapTokens {
+        liquidity_pool: pool_address,
+        amm_config: pda::amm_config().0,
+        pool_authority: pda::pool_authority(&pool_address).0,
+        input_token_vault: input_vault,
+        output_token_vault: output_vault,
+        input_token_mint: input_mint,
+        output_token_mint: output_mint,
+        user_input_token_account: info.user_token_account(&owner, &input_mint),
+        user: owner,
+        input_token_owner: owner,
+        input_token_program: info.token_program(&input_mint),
+        output_token_program: info.token_program(&output_mint),
+        associated_token_program: associated_token::ID,
+        system_program: system_program::ID,
+        user_output_token_account: info.user_token_account(&owner, &output_mint),
+        oracle: (pool.oracle != Pubkey::default()).then_some(pool.oracle),
+        insurance_vault: (pool.deviation_incident_active != 0 && pool.incident_surcharge_bps > 0)
+            .then(|| pda::insurance_vault(&pool_address, &input_mint).0),
+        protocol_fee_vault: (pool.protocol_fee_share_bps > 0 && fee_recipient == Pubkey::default())
+            .then(|| pda::protocol_fee_vault(&pool_address, &fee_mint).0),
+        fee_recipient: (fee_recipient != Pubkey::default()).then_some(fee_recipient),
+        creator_fee_account: (pool.creator_fee_bps > 0 && creator_fee_account != Pubkey::default())
+            .then_some(creator_fee_account),
+        trader_state: pool
+            .tracks_traders(slot)
+            .then(|| pda::trader_state(&pool_address, &owner).0),
+        launch_whitelist_entry: if pool.launch_whitelist_active(slot) {
+            if_exists(rpc, pda::launch_whitelist_entry(&pool_address, &owner).0)?
+        } else {
+            None
+        },
+        memo_program: Some(memo::ID),
+        volume_stats: (pool.has_volume_stats != 0).then(|| pda::volume_stats(&pool_address).0),
+        referrer: None,
+        referral_vault: None,
+    };
+    let data = ammv2::instruction::SwapTokens {
+        input_amount: amount,
+        minimum_output_amount,
+        slippage_bps: None,
+        allow_partial_fill: false,
+        price_limit: None,
+        client_order_id,
+    };
+    let signature = rpc.send(
+        &[Instruction {
+            program_id: ammv2::ID,
+            accounts: accounts.to_account_metas(None),
+            data: data.data(),
+        }],
+        user,
+    )?;
+
+    println!("Signature: {}", signature);
+    Ok(())
+}
+
+/// `min_output` if given, else `quoted_output` less `slippage_bps`
+fn minimum_output(quoted_output: u64, min_output: Option<u64>, slippage_bps: u16) -> Result<u64> {
+    Ok(match min_output {
+        Some(min_output) => min_output,
+        None => math::to_u64(math::mul_div_floor(
+            quoted_output as u128,
+            BASIS_POINTS_DIVISOR.saturating_sub(slippage_bps as u128),
+            BASIS_POINTS_DIVISOR,
+        )?)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn minimum_output_takes_the_slippage_off_the_quote_unless_given() {
+        assert_eq!(minimum_output(10_000, None, 50).unwrap(), 9_950);
+        assert_eq!(minimum_output(999, None, 50).unwrap(), 994);
+        assert_eq!(minimum_output(10_000, Some(9_999), 50).unwrap(), 9_999);
+        // Slippage beyond 100% accepts any output rather than underflowing
+        assert_eq!(minimum_output(10_000, None, u16::MAX).unwrap(), 0);
+    }
+}
@@ -0,0 +1,114 @@
+//! Decode the program's events from transaction logs
+
+use ammv2::events::*;
+use anchor_lang::prelude::*;
+use anchor_lang::Discriminator;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+
+/// Log prefix of the data `emit!` writes
+const PROGRAM_DATA: &str = "Program data: ";
+
+macro_rules! events {
+    ($($event:ident),+ $(,)?) => {
+        /// Any event the program emits
+        #[derive(Debug)]
+        pub enum AmmEvent {
+            $($event($event),)+
+        }
+
+        /// Decode one event from its `emit!` data, discriminator included.
+        /// Returns `None` for data that is not one of the program's events.
+        pub fn decode_event(data: &[u8]) -> Option<AmmEvent> {
+            $(
+                if let Some(mut body) = data.strip_prefix($event::DISCRIMINATOR) {
+                    return $event::deserialize(&mut body).ok().map(AmmEvent::$event);
+                }
+            )+
+            None
+        }
+    };
+}
+
+events!(
+    PoolStateDigest,
+    PoolParamsSnapshot,
+    LiquidityDeposited,
+    LiquidityWithdrawn,
+    PoolDonated,
+    LpSupplySynced,
+    PoolMigrated,
+    VerificationLevelChanged,
+    AmmConfigUpdated,
+    SwapExecuted,
+    FeesClaimed,
+    ParamChangeQueued,
+    ParamChangeExecuted,
+    LaunchPoolGraduated,
+);
+
+/// Events emitted by the program in a transaction's log messages, in order.
+/// Data logged while another program is executing, e.g. one the program
+/// invoked, is skipped.
+pub fn parse_logs<S: AsRef<str>>(logs: &[S]) -> Vec<AmmEvent> {
+    let invoke_prefix = format!("Program {} invoke", ammv2::ID);
+    let mut call_stack: Vec<bool> = Vec::new();
+    let mut events = Vec::new();
+    for line in logs.iter().map(AsRef::as_ref) {
+        if line.starts_with("Program ") && line.contains(" invoke [") {
+            call_stack.push(line.starts_with(&invoke_prefix));
+        } else if line.starts_with("Program ")
+            && (line.ends_with(" success") || line.contains(" failed"))
+        {
+            call_stack.pop();
+        } else if let Some(data) = line.strip_prefix(PROGRAM_DATA) {
+            if call_stack.last() == Some(&true) {
+                if let Some(event) = BASE64
+                    .decode(data)
+                    .ok()
+                    .and_then(|data| decode_event(&data))
+                {
+                    events.push(event);
+                }
+            }
+        }
+    }
+    events
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anchor_lang::Event;
+
+    #[test]
+    fn parses_only_the_programs_own_events() {
+        let synced = LpSupplySynced {
+            liquidity_pool: Pubkey::new_unique(),
+            event_seq: 7,
+            previous_lp_tokens_issued: 10,
+            lp_supply: 12,
+        };
+        let data = format!("{}{}", PROGRAM_DATA, BASE64.encode(synced.data()));
+        let other_program = Pubkey::new_unique();
+        let logs = [
+            format!("Program {} invoke [1]", ammv2::ID),
+            format!("Program {} invoke [2]", other_program),
+            data.clone(),
+            format!("Program {} success", other_program),
+            data,
+            format!("{}{}", PROGRAM_DATA, BASE64.encode([0u8; 16])),
+            format!("Program {} success", ammv2::ID),
+        ];
+
+        let events = parse_logs(&logs);
+        assert_eq!(events.len(), 1);
+        match &events[0] {
+            AmmEvent::LpSupplySynced(event) => {
+                assert_eq!(event.liquidity_pool, synced.liquidity_pool);
+                assert_eq!(event.lp_supply, 12);
+            }
+            event => panic!("unexpected event {event:?}"),
+        }
+    }
+}
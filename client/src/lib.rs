@@ -0,0 +1,13 @@
+//! Off-chain helpers for the ammv2 program: decode account state and events,
+//! derive the program's addresses and quote instructions before sending them.
+//!
+//! Quotes run the program's own `ammv2::math` routines in the same order as the
+//! instructions do, so a quote taken against fresh account data matches the
+//! on-chain result to the base unit.
+
+pub mod events;
+pub mod pda;
+pub mod quote;
+pub mod state;
+
+pub use ammv2::ID as PROGRAM_ID;
@@ -0,0 +1,202 @@
+//! Program derived addresses, each returned with its bump
+
+use anchor_lang::prelude::Pubkey;
+
+fn find(seeds: &[&[u8]]) -> (Pubkey, u8) {
+    Pubkey::find_program_address(seeds, &ammv2::ID)
+}
+
+/// Global `AmmConfig`
+pub fn amm_config() -> (Pubkey, u8) {
+    find(&[b"amm_config"])
+}
+
+/// Protocol treasury that collects pool creation fees
+pub fn treasury() -> (Pubkey, u8) {
+    find(&[b"treasury"])
+}
+
+/// Pool of a mint pair, in the order the pool was created with
+pub fn liquidity_pool(token_a_mint: &Pubkey, token_b_mint: &Pubkey) -> (Pubkey, u8) {
+    find(&[
+        b"liquidity_pool",
+        token_a_mint.as_ref(),
+        token_b_mint.as_ref(),
+    ])
+}
+
+/// Authority that owns the pool's vaults and mints its LP token
+pub fn pool_authority(liquidity_pool: &Pubkey) -> (Pubkey, u8) {
+    find(&[b"pool_authority", liquidity_pool.as_ref()])
+}
+
+/// Token A reserve vault
+pub fn token_a_vault(liquidity_pool: &Pubkey) -> (Pubkey, u8) {
+    find(&[b"token_a_vault", liquidity_pool.as_ref()])
+}
+
+/// Token B reserve vault
+pub fn token_b_vault(liquidity_pool: &Pubkey) -> (Pubkey, u8) {
+    find(&[b"token_b_vault", liquidity_pool.as_ref()])
+}
+
+/// LP token mint
+pub fn lp_token_mint(liquidity_pool: &Pubkey) -> (Pubkey, u8) {
+    find(&[b"lp_token_mint", liquidity_pool.as_ref()])
+}
+
+/// Price oracle
+pub fn oracle(liquidity_pool: &Pubkey) -> (Pubkey, u8) {
+    find(&[b"oracle", liquidity_pool.as_ref()])
+}
+
+/// Hourly volume stats
+pub fn volume_stats(liquidity_pool: &Pubkey) -> (Pubkey, u8) {
+    find(&[b"volume_stats", liquidity_pool.as_ref()])
+}
+
+/// Protocol fee vault for one of the pool's mints
+pub fn protocol_fee_vault(liquidity_pool: &Pubkey, mint: &Pubkey) -> (Pubkey, u8) {
+    find(&[
+        b"protocol_fee_vault",
+        liquidity_pool.as_ref(),
+        mint.as_ref(),
+    ])
+}
+
+/// Insurance vault for one of the pool's mints
+pub fn insurance_vault(liquidity_pool: &Pubkey, mint: &Pubkey) -> (Pubkey, u8) {
+    find(&[b"insurance_vault", liquidity_pool.as_ref(), mint.as_ref()])
+}
+
+/// Per-trader anti-sandwich state
+pub fn trader_state(liquidity_pool: &Pubkey, trader: &Pubkey) -> (Pubkey, u8) {
+    find(&[b"trader_state", liquidity_pool.as_ref(), trader.as_ref()])
+}
+
+/// LP whitelist entry of a wallet
+pub fn lp_whitelist_entry(liquidity_pool: &Pubkey, wallet: &Pubkey) -> (Pubkey, u8) {
+    find(&[b"lp_whitelist", liquidity_pool.as_ref(), wallet.as_ref()])
+}
+
+/// Launch window whitelist entry of a wallet
+pub fn launch_whitelist_entry(liquidity_pool: &Pubkey, wallet: &Pubkey) -> (Pubkey, u8) {
+    find(&[
+        b"launch_whitelist",
+        liquidity_pool.as_ref(),
+        wallet.as_ref(),
+    ])
+}
+
+/// Sub-account of an owner
+pub fn sub_account(owner: &Pubkey, sub_account_id: u16) -> (Pubkey, u8) {
+    find(&[
+        b"sub_account",
+        owner.as_ref(),
+        &sub_account_id.to_le_bytes(),
+    ])
+}
+
+/// DCA position of an owner
+pub fn dca_position(liquidity_pool: &Pubkey, owner: &Pubkey, dca_id: u16) -> (Pubkey, u8) {
+    find(&[
+        b"dca",
+        liquidity_pool.as_ref(),
+        owner.as_ref(),
+        &dca_id.to_le_bytes(),
+    ])
+}
+
+/// Escrow vault of a DCA position
+pub fn dca_vault(dca_position: &Pubkey) -> (Pubkey, u8) {
+    find(&[b"dca_vault", dca_position.as_ref()])
+}
+
+/// Limit order of an owner
+pub fn limit_order(liquidity_pool: &Pubkey, owner: &Pubkey, order_id: u64) -> (Pubkey, u8) {
+    find(&[
+        b"limit_order",
+        liquidity_pool.as_ref(),
+        owner.as_ref(),
+        &order_id.to_le_bytes(),
+    ])
+}
+
+/// Escrow vault of a limit order
+pub fn limit_order_vault(limit_order: &Pubkey) -> (Pubkey, u8) {
+    find(&[b"limit_order_vault", limit_order.as_ref()])
+}
+
+/// Fee-tracking liquidity position of an LP token owner
+pub fn liquidity_position(liquidity_pool: &Pubkey, owner: &Pubkey) -> (Pubkey, u8) {
+    find(&[
+        b"liquidity_position",
+        liquidity_pool.as_ref(),
+        owner.as_ref(),
+    ])
+}
+
+/// LP vault of a liquidity position
+pub fn position_lp_vault(liquidity_position: &Pubkey) -> (Pubkey, u8) {
+    find(&[b"position_lp_vault", liquidity_position.as_ref()])
+}
+
+/// LP token lock of an owner
+pub fn lp_lock(liquidity_pool: &Pubkey, owner: &Pubkey) -> (Pubkey, u8) {
+    find(&[b"lp_lock", liquidity_pool.as_ref(), owner.as_ref()])
+}
+
+/// Escrow vault of an owner's LP token lock
+pub fn lp_lock_vault(liquidity_pool: &Pubkey, owner: &Pubkey) -> (Pubkey, u8) {
+    find(&[b"lp_lock_vault", liquidity_pool.as_ref(), owner.as_ref()])
+}
+
+/// Treasury vault of a mint
+pub fn treasury_vault(mint: &Pubkey) -> (Pubkey, u8) {
+    find(&[b"treasury_vault", mint.as_ref()])
+}
+
+/// Withdrawal limits of a treasury balance, `Pubkey::default()` for lamports
+pub fn treasury_limits(mint: &Pubkey) -> (Pubkey, u8) {
+    find(&[b"treasury_limits", mint.as_ref()])
+}
+
+/// Bonding-curve launch of a token
+pub fn launch_pool(token_mint: &Pubkey) -> (Pubkey, u8) {
+    find(&[b"launch_pool", token_mint.as_ref()])
+}
+
+/// Launch vault holding the tokens left on the curve
+pub fn launch_token_vault(launch_pool: &Pubkey) -> (Pubkey, u8) {
+    find(&[b"launch_token_vault", launch_pool.as_ref()])
+}
+
+/// Launch vault holding the quote raised
+pub fn launch_quote_vault(launch_pool: &Pubkey) -> (Pubkey, u8) {
+    find(&[b"launch_quote_vault", launch_pool.as_ref()])
+}
+
+/// Launch vault holding the graduated pool's locked LP tokens
+pub fn launch_lp_vault(launch_pool: &Pubkey) -> (Pubkey, u8) {
+    find(&[b"launch_lp_vault", launch_pool.as_ref()])
+}
+
+/// Config authority's approval of a mint with a permanent delegate or close authority
+pub fn unsafe_mint_approval(mint: &Pubkey) -> (Pubkey, u8) {
+    find(&[b"unsafe_mint_approval", mint.as_ref()])
+}
+
+/// Referrer registered by `authority`
+pub fn referrer(authority: &Pubkey) -> (Pubkey, u8) {
+    find(&[b"referrer", authority.as_ref()])
+}
+
+/// Owner of the referral vaults
+pub fn referral_authority() -> (Pubkey, u8) {
+    find(&[b"referral_authority"])
+}
+
+/// Vault holding every referrer's unclaimed fees in `mint`
+pub fn referral_vault(mint: &Pubkey) -> (Pubkey, u8) {
+    find(&[b"referral_vault", mint.as_ref()])
+}
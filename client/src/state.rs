@@ -0,0 +1,24 @@
+use ammv2::state::LiquidityPool;
+use anchor_lang::error::ErrorCode;
+use anchor_lang::prelude::*;
+use anchor_lang::{AccountDeserialize, Discriminator};
+
+/// Decode a `LiquidityPool` from raw account data, discriminator included.
+/// RPC buffers carry no alignment guarantee, so the pool is copied out.
+pub fn decode_pool(data: &[u8]) -> Result<LiquidityPool> {
+    let discriminator = LiquidityPool::DISCRIMINATOR;
+    require!(
+        data.starts_with(discriminator),
+        ErrorCode::AccountDiscriminatorMismatch
+    );
+    let body = data
+        .get(discriminator.len()..discriminator.len() + std::mem::size_of::<LiquidityPool>())
+        .ok_or(ErrorCode::AccountDidNotDeserialize)?;
+    Ok(bytemuck::pod_read_unaligned(body))
+}
+
+/// Decode one of the program's Borsh accounts, e.g. `AmmConfig`, `PriceOracle`
+/// or `DcaPosition`, from raw account data
+pub fn decode_account<T: AccountDeserialize>(data: &[u8]) -> Result<T> {
+    T::try_deserialize(&mut &data[..])
+}
@@ -17,6 +17,306 @@ pub enum AmmError {
     #[msg("Invalid fee configuration")]
     InvalidFeeParameters,
 
-    #[msg("Division by zero in calculations")]
+    #[msg("Arithmetic overflow in calculations")]
     MathOverflow,
+
+    #[msg("Swap would move the price beyond the per-slot limit")]
+    PriceMovementExceeded,
+
+    #[msg("Signer is not the pool admin")]
+    Unauthorized,
+
+    #[msg("Invalid pool configuration parameter")]
+    InvalidPoolConfig,
+
+    #[msg("Trader state account is required for this pool")]
+    TraderStateRequired,
+
+    #[msg("Trader exceeded the per-slot volume limit")]
+    TraderVolumeExceeded,
+
+    #[msg("Opposite-direction trade in the same slot is not allowed")]
+    SameSlotReversal,
+
+    #[msg("State digest was already exported for this epoch")]
+    DigestAlreadyExported,
+
+    #[msg("Swap input exceeds the pool's maximum trade size")]
+    TradeTooLarge,
+
+    #[msg("Wallet must wait for the launch trade cooldown to elapse")]
+    TradeCooldownActive,
+
+    #[msg("Swap output rounds down to zero")]
+    OutputTooSmall,
+
+    #[msg("Swap would decrease the constant product invariant")]
+    InvariantViolated,
+
+    #[msg("Withdrawal note exceeds the maximum length")]
+    NoteTooLong,
+
+    #[msg("Memo program account is required to attach a note or transfer memo")]
+    MemoProgramRequired,
+
+    #[msg("Pool has no liquidity")]
+    PoolHasNoLiquidity,
+
+    #[msg("Pool already has an oracle")]
+    OracleAlreadyInitialized,
+
+    #[msg("Oracle account is required for this pool")]
+    OracleAccountRequired,
+
+    #[msg("Token account is not owned by the signer")]
+    InvalidTokenAccountOwner,
+
+    #[msg("Pool is in withdraw-only mode")]
+    PoolWithdrawOnly,
+
+    #[msg("Large withdrawals are timelocked while the pool is withdraw-only")]
+    WithdrawalTimelocked,
+
+    #[msg("Post-swap price deviates too far from the oracle TWAP")]
+    OracleDeviationExceeded,
+
+    #[msg("Insurance vault account is required during a deviation incident")]
+    InsuranceVaultRequired,
+
+    #[msg("Pool LP counter does not match the LP mint supply")]
+    LpSupplyMismatch,
+
+    #[msg("Pool ratio drifted beyond the allowed bound since the deposit was quoted")]
+    RatioDriftExceeded,
+
+    #[msg("Pool still holds reserves or outstanding LP tokens")]
+    PoolNotEmpty,
+
+    #[msg("Initial liquidity is below the pool's minimum")]
+    InitialLiquidityTooSmall,
+
+    #[msg("LP metadata URI is too long")]
+    MetadataUriTooLong,
+
+    #[msg("Stake amount must be greater than zero")]
+    InvalidStakeAmount,
+
+    #[msg("Reward mint is not paid out by this farm")]
+    UnknownFarmReward,
+
+    #[msg("Farm already has the maximum number of reward streams")]
+    FarmRewardSlotsFull,
+
+    #[msg("Invalid farm lock tier")]
+    InvalidLockTier,
+
+    #[msg("Stake is still locked")]
+    StakeLocked,
+
+    #[msg("Gauge epoch has not ended yet")]
+    GaugeEpochNotEnded,
+
+    #[msg("Gauge already checkpointed for this epoch")]
+    GaugeAlreadyCheckpointed,
+
+    #[msg("Votes exceed the governance tokens the voter has locked")]
+    InsufficientVotingPower,
+
+    #[msg("Only rewards paid in one of the pool's tokens can be compounded")]
+    CompoundRewardNotInPool,
+
+    #[msg("Protocol fee vault is required when the pool takes a protocol fee")]
+    ProtocolFeeVaultRequired,
+
+    #[msg("Pool has no buyback mint configured")]
+    BuybackDisabled,
+
+    #[msg("The pool's fee recipient account is required")]
+    FeeRecipientRequired,
+
+    #[msg("Program is paused")]
+    ProgramPaused,
+
+    #[msg("Operation is disabled for this pool")]
+    OperationDisabled,
+
+    #[msg("Wallet is not whitelisted to provide liquidity to this pool")]
+    NotWhitelisted,
+
+    #[msg("Only whitelisted wallets may swap during the launch window")]
+    NotLaunchWhitelisted,
+
+    #[msg("Invalid DCA schedule")]
+    InvalidDcaSchedule,
+
+    #[msg("DCA tranche is not due yet")]
+    DcaNotDue,
+
+    #[msg("Invalid limit order")]
+    InvalidLimitOrder,
+
+    #[msg("Limit order has expired")]
+    LimitOrderExpired,
+
+    #[msg("Pool price does not satisfy the limit price")]
+    LimitPriceNotReached,
+
+    #[msg("Swap input exceeds the delegate's approved amount")]
+    InsufficientDelegation,
+
+    #[msg("Division by zero in calculations")]
+    DivisionByZero,
+
+    #[msg("Amount is scaled to fewer decimals than it has")]
+    InvalidDecimals,
+
+    #[msg("Token B offered is below the amount the pool ratio requires")]
+    RatioMismatch,
+
+    #[msg("Insufficient LP tokens in the user's account")]
+    InsufficientLpTokens,
+
+    #[msg("Vault is not owned by the pool authority or holds the wrong mint")]
+    InvalidVaultAccount,
+
+    #[msg("DCA vault has no tokens left to swap")]
+    DcaVaultEmpty,
+
+    #[msg("Insurance vault holds less than the amount to deploy")]
+    InsufficientInsuranceFunds,
+
+    #[msg("Unstake amount exceeds the staked LP tokens")]
+    InsufficientStake,
+
+    #[msg("Slippage tolerance exceeds 100%")]
+    InvalidSlippageTolerance,
+
+    #[msg("Requested output is not less than the pool's output reserve")]
+    OutputExceedsReserve,
+
+    #[msg("Swap needs more input than the maximum allowed")]
+    MaxInputExceeded,
+
+    #[msg("Swap would move the pool price past the trader's price limit")]
+    PriceLimitExceeded,
+
+    #[msg("No part of the swap input fits within the pool's limits")]
+    NothingFillable,
+
+    #[msg("Liquidity position account is required for this pool")]
+    LiquidityPositionRequired,
+
+    #[msg("Withdrawals are blocked this soon after a deposit")]
+    JitWithdrawalBlocked,
+
+    #[msg("Position is still in its post-deposit withdrawal cooldown")]
+    WithdrawCooldownActive,
+
+    #[msg("Unlock time must be in the future and no earlier than the current lock's")]
+    InvalidUnlockTimestamp,
+
+    #[msg("LP tokens are still locked")]
+    LpTokensLocked,
+
+    #[msg("LP lock accounts are required to lock the creator's LP tokens")]
+    LpLockAccountsRequired,
+
+    #[msg("TWAP window must be positive")]
+    InvalidTwapWindow,
+
+    #[msg("Oracle has no price history to average over")]
+    OracleHistoryUnavailable,
+
+    #[msg("Governance account does not belong to the given realm")]
+    InvalidGovernanceAccount,
+
+    #[msg("Parameter is timelocked; queue the change instead")]
+    TimelockRequired,
+
+    #[msg("Queued parameter change is not executable yet")]
+    TimelockNotElapsed,
+
+    #[msg("Parameter change does not match the target account")]
+    InvalidParamChangeTarget,
+
+    #[msg("Treasury withdrawal exceeds the per-call or per-epoch limit")]
+    TreasuryWithdrawLimitExceeded,
+
+    #[msg("Treasury holds less than the amount to withdraw")]
+    InsufficientTreasuryBalance,
+
+    #[msg("Creator fee share exceeds the global cap")]
+    CreatorFeeTooHigh,
+
+    #[msg("Creator fee account is required for this pool")]
+    CreatorFeeAccountRequired,
+
+    #[msg("Launch curve, raise target and fee must all be nonzero")]
+    InvalidLaunchParameters,
+
+    #[msg("Launch pool has already graduated")]
+    LaunchGraduated,
+
+    #[msg("Launch pool has reached its target raise")]
+    LaunchTargetReached,
+
+    #[msg("Launch pool has not reached its target raise")]
+    LaunchTargetNotReached,
+
+    #[msg("Pool pair does not match the launch's token and quote mints")]
+    LaunchPairMismatch,
+
+    #[msg("Token program does not own the mint")]
+    InvalidTokenProgram,
+
+    #[msg("Mint has a permanent delegate or close authority and is not approved by the config authority")]
+    UnsafeMintExtension,
+
+    #[msg("Mint has a freeze authority, which the global config does not allow")]
+    FreezeAuthorityNotAllowed,
+
+    #[msg("Volume stats account is required for this pool")]
+    VolumeStatsAccountRequired,
+
+    #[msg("Swap price impact exceeds the pool's maximum")]
+    PriceImpactTooHigh,
+
+    #[msg("Average execution price is further below the expected price than the tolerance allows")]
+    PriceToleranceExceeded,
+
+    #[msg("Referral vault is required to pay a referrer")]
+    ReferralVaultRequired,
+
+    #[msg("Referrer holds unclaimed fees in too many mints")]
+    ReferralBalancesFull,
+
+    #[msg("No referral fees to claim")]
+    NoReferralFees,
+
+    #[msg("Donation must include token A or token B")]
+    InvalidDonationAmount,
+
+    #[msg("No protocol fees to harvest")]
+    NoProtocolFeesToHarvest,
+
+    #[msg("Pool burns its protocol fees through buyback")]
+    ProtocolFeesReservedForBuyback,
+
+    #[msg("Swap fee exceeds the program's maximum fee")]
+    FeeTooHigh,
+
+    #[msg("An account the parameter change refers to is missing or invalid")]
+    InvalidParamChangeAccount,
+
+    #[msg("LP tokens burned exceed those tracked by the liquidity position")]
+    BurnExceedsPosition,
+
+    #[msg("Liquidity position LP vault is missing or belongs to another position")]
+    InvalidPositionLpVault,
+
+    #[msg("A swap cannot name its own trader as the referrer")]
+    SelfReferral,
+
+    #[msg("Voting tokens stay locked until the epoch they voted in has ended")]
+    GaugeVotesLocked,
 }
@@ -0,0 +1,309 @@
+use crate::state::{ParamChange, VerificationLevel};
+use anchor_lang::prelude::*;
+
+/// Checkpoint of the full pool state, emitted at most once per epoch
+#[event]
+#[derive(Debug)]
+pub struct PoolStateDigest {
+    /// Pool the digest was computed for
+    pub liquidity_pool: Pubkey,
+
+    /// Per-pool sequence number, one above the pool's previous event
+    pub event_seq: u64,
+
+    /// Epoch the checkpoint belongs to
+    pub epoch: u64,
+
+    /// Slot at which the digest was computed
+    pub slot: u64,
+
+    /// SHA-256 over the serialized pool account, vault balances, LP supply,
+    /// and the latest oracle observation
+    pub digest: [u8; 32],
+
+    /// Token A vault balance
+    pub reserve_a: u64,
+
+    /// Token B vault balance
+    pub reserve_b: u64,
+
+    /// LP mint supply
+    pub lp_supply: u64,
+}
+
+/// Full set of pool parameters, emitted whenever any of them changes
+#[event]
+#[derive(Debug)]
+pub struct PoolParamsSnapshot {
+    /// Pool the parameters belong to
+    pub liquidity_pool: Pubkey,
+
+    /// Per-pool sequence number, one above the pool's previous event
+    pub event_seq: u64,
+
+    /// Slot from which these parameters are active
+    pub slot: u64,
+
+    pub admin: Pubkey,
+    pub fee_numerator: u64,
+    pub fee_denominator: u64,
+    pub max_price_change_bps_per_slot: u16,
+    pub max_trader_slot_volume_bps: u16,
+    pub block_same_slot_reversal: bool,
+    pub max_trade_bps_of_reserves: u16,
+    pub launch_protection_end_slot: u64,
+    pub launch_trade_cooldown_slots: u64,
+    pub withdraw_only: bool,
+    pub large_withdrawal_delay_secs: i64,
+    pub small_lp_withdrawal_threshold: u64,
+    pub deviation_twap_window_secs: i64,
+    pub max_oracle_deviation_bps: u16,
+    pub incident_deviation_bps: u16,
+    pub incident_surcharge_bps: u16,
+    pub verification_level: VerificationLevel,
+    pub min_initial_deposit_a: u64,
+    pub min_initial_deposit_b: u64,
+    pub protocol_fee_share_bps: u16,
+    pub buyback_mint: Pubkey,
+    pub fee_recipient_a: Pubkey,
+    pub fee_recipient_b: Pubkey,
+    pub disabled_operations: u16,
+    pub lp_whitelist_enabled: bool,
+    pub launch_whitelist_only: bool,
+    pub jit_window_slots: u64,
+    pub jit_penalty_bps: u16,
+    pub withdraw_cooldown_secs: i64,
+    pub oracle_checkpoint_reward: u64,
+    pub dynamic_fee_min_bps: u16,
+    pub dynamic_fee_max_bps: u16,
+    pub dynamic_fee_volatility_bps: u16,
+    pub fee_on_output: bool,
+    pub token_a_freezable: bool,
+    pub token_b_freezable: bool,
+    pub admin_realm: Pubkey,
+    pub creator: Pubkey,
+    pub creator_fee_bps: u16,
+    pub max_price_impact_bps: u16,
+    pub referral_fee_bps: u16,
+    pub keeper_tip: u64,
+    pub keeper_tip_share_bps: u16,
+}
+
+/// Liquidity added to a pool
+#[event]
+#[derive(Debug)]
+pub struct LiquidityDeposited {
+    pub liquidity_pool: Pubkey,
+
+    /// Per-pool sequence number, one above the pool's previous event
+    pub event_seq: u64,
+
+    pub user: Pubkey,
+    pub lp_tokens_minted: u64,
+    pub token_a_amount: u64,
+    pub token_b_amount: u64,
+
+    /// Caller's order id, echoed untouched
+    pub client_order_id: Option<u64>,
+}
+
+/// Liquidity removed from a pool
+#[event]
+#[derive(Debug)]
+pub struct LiquidityWithdrawn {
+    pub liquidity_pool: Pubkey,
+
+    /// Per-pool sequence number, one above the pool's previous event
+    pub event_seq: u64,
+
+    pub user: Pubkey,
+    pub lp_tokens_burned: u64,
+    pub token_a_amount: u64,
+    pub token_b_amount: u64,
+
+    /// Optional beneficiary note, also recorded through the memo program
+    pub note: Option<String>,
+
+    /// Caller's order id, echoed untouched
+    pub client_order_id: Option<u64>,
+}
+
+/// Tokens donated into a pool's reserves without minting LP tokens
+#[event]
+#[derive(Debug)]
+pub struct PoolDonated {
+    pub liquidity_pool: Pubkey,
+
+    /// Per-pool sequence number, one above the pool's previous event
+    pub event_seq: u64,
+
+    pub donor: Pubkey,
+
+    /// Amounts the vaults received, net of any transfer fee
+    pub token_a_amount: u64,
+    pub token_b_amount: u64,
+
+    /// Liquidity per LP token after the donation
+    pub fee_growth_per_lp: u128,
+}
+
+/// Pool LP counter re-synced to the LP mint supply
+#[event]
+#[derive(Debug)]
+pub struct LpSupplySynced {
+    /// Pool that was re-synced
+    pub liquidity_pool: Pubkey,
+
+    /// Per-pool sequence number, one above the pool's previous event
+    pub event_seq: u64,
+
+    /// Counter value before the sync
+    pub previous_lp_tokens_issued: u64,
+
+    /// LP mint supply the counter now matches
+    pub lp_supply: u64,
+}
+
+/// Pool account migrated to the current layout version
+#[event]
+#[derive(Debug)]
+pub struct PoolMigrated {
+    /// Pool that was migrated
+    pub liquidity_pool: Pubkey,
+
+    /// Per-pool sequence number, one above the pool's previous event
+    pub event_seq: u64,
+
+    /// Layout version before the migration
+    pub from_version: u8,
+
+    /// Layout version after the migration
+    pub to_version: u8,
+}
+
+/// Verification level of a pool changed
+#[event]
+#[derive(Debug)]
+pub struct VerificationLevelChanged {
+    /// Pool whose level changed
+    pub liquidity_pool: Pubkey,
+
+    /// Per-pool sequence number, one above the pool's previous event
+    pub event_seq: u64,
+
+    /// Level before the change
+    pub previous_level: VerificationLevel,
+
+    /// Level after the change
+    pub new_level: VerificationLevel,
+}
+
+/// Global configuration, emitted whenever any of it changes
+#[event]
+#[derive(Debug)]
+pub struct AmmConfigUpdated {
+    pub authority: Pubkey,
+    pub treasury: Pubkey,
+    pub pool_creation_fee_lamports: u64,
+    pub paused: bool,
+    pub param_change_delay_secs: i64,
+    pub max_creator_fee_bps: u16,
+    pub reject_freeze_authority: bool,
+}
+
+/// A swap through `swap_tokens` or `swap_exact_output`
+#[event]
+#[derive(Debug)]
+pub struct SwapExecuted {
+    pub liquidity_pool: Pubkey,
+
+    /// Per-pool sequence number, one above the pool's previous event
+    pub event_seq: u64,
+
+    /// Signer of the swap
+    pub user: Pubkey,
+
+    /// Mint of the token sold
+    pub input_mint: Pubkey,
+
+    /// Input actually swapped, below the requested amount on a partial fill
+    pub input_amount: u64,
+
+    /// Tokens paid out
+    pub output_amount: u64,
+
+    /// Pool fee taken from the input
+    pub fee_amount: u64,
+
+    /// Whether part of the requested input was left unswapped
+    pub partial_fill: bool,
+
+    /// Token A reserve after the swap and its fee payouts
+    pub reserve_a: u64,
+
+    /// Token B reserve after the swap and its fee payouts
+    pub reserve_b: u64,
+
+    /// LP tokens outstanding
+    pub lp_supply: u64,
+
+    /// Spot price after the swap, token B per token A as Q64.64 bits
+    pub spot_price: u128,
+
+    /// Caller's order id, echoed untouched
+    pub client_order_id: Option<u64>,
+}
+
+/// Swap fees paid out to a liquidity position
+#[event]
+#[derive(Debug)]
+pub struct FeesClaimed {
+    pub liquidity_pool: Pubkey,
+
+    /// Per-pool sequence number, one above the pool's previous event
+    pub event_seq: u64,
+
+    pub owner: Pubkey,
+    pub lp_tokens_burned: u64,
+    pub token_a_amount: u64,
+    pub token_b_amount: u64,
+}
+
+/// Parameter change queued behind the timelock
+#[event]
+#[derive(Debug)]
+pub struct ParamChangeQueued {
+    /// Global config or pool the change applies to
+    pub target: Pubkey,
+    pub change_id: u64,
+    pub proposer: Pubkey,
+    pub change: ParamChange,
+
+    /// Unix timestamp from which the change can be executed
+    pub eta: i64,
+}
+
+/// Queued parameter change that took effect
+#[event]
+#[derive(Debug)]
+pub struct ParamChangeExecuted {
+    pub target: Pubkey,
+    pub change_id: u64,
+    pub change: ParamChange,
+}
+
+/// Bonding-curve launch that seeded its constant-product pool
+#[event]
+#[derive(Debug)]
+pub struct LaunchPoolGraduated {
+    pub launch_pool: Pubkey,
+    pub liquidity_pool: Pubkey,
+    pub quote_amount: u64,
+    pub token_amount: u64,
+
+    /// LP tokens minted into the launch's LP vault, locked for good
+    pub lp_tokens_locked: u64,
+
+    /// Unsold tokens left over from the curve, burned
+    pub tokens_burned: u64,
+}
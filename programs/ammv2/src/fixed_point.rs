@@ -0,0 +1,245 @@
+//! Q64.64 fixed-point math for prices and other ratios
+
+use crate::errors::AmmError;
+use crate::math;
+use anchor_lang::prelude::*;
+
+/// Unsigned Q64.64 fixed-point number: the high 64 bits of the u128 are the
+/// integer part, the low 64 bits the fraction. A ratio of two u64 token
+/// amounts always fits, at a resolution of 2^-64.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Q64x64(u128);
+
+impl Q64x64 {
+    /// Number of fractional bits
+    pub const FRACTIONAL_BITS: u32 = 64;
+
+    /// 1.0
+    pub const ONE: Self = Self(1 << Self::FRACTIONAL_BITS);
+
+    /// Smallest positive value, 2^-64
+    pub const EPSILON: Self = Self(1);
+
+    /// Reinterpret raw bits, e.g. a price stored in an account or passed as an
+    /// instruction argument
+    pub const fn from_bits(bits: u128) -> Self {
+        Self(bits)
+    }
+
+    /// Raw bits, for storage and instruction data
+    pub const fn to_bits(self) -> u128 {
+        self.0
+    }
+
+    /// `value` as a fixed-point number, exact
+    pub const fn from_int(value: u64) -> Self {
+        Self((value as u128) << Self::FRACTIONAL_BITS)
+    }
+
+    /// `numerator / denominator`, rounded down
+    pub fn from_ratio(numerator: u128, denominator: u128) -> Result<Self> {
+        Ok(Self(mul_div_floor(numerator, Self::ONE.0, denominator)?))
+    }
+
+    /// `self * other`, rounded down
+    pub fn checked_mul(self, other: Self) -> Result<Self> {
+        Ok(Self(mul_div_floor(self.0, other.0, Self::ONE.0)?))
+    }
+
+    /// `self / other`, rounded down
+    pub fn checked_div(self, other: Self) -> Result<Self> {
+        Ok(Self(mul_div_floor(self.0, Self::ONE.0, other.0)?))
+    }
+
+    /// `self * value` as an integer, rounded down
+    pub fn mul_floor(self, value: u128) -> Result<u128> {
+        mul_div_floor(self.0, value, Self::ONE.0)
+    }
+
+    /// `self * value` as an integer, rounded up
+    pub fn mul_ceil(self, value: u128) -> Result<u128> {
+        mul_div_ceil(self.0, value, Self::ONE.0)
+    }
+
+    /// Square root, rounded down. Exact to the last bit below 2^64; larger
+    /// values keep 32 + (leading zero bits / 2) fractional bits.
+    pub fn sqrt(self) -> Result<Self> {
+        // sqrt(bits / 2^64) * 2^64 = sqrt(bits * 2^64), with the shift split
+        // between the radicand and the root so the radicand fits a u128
+        let shift = (self.0.leading_zeros() & !1).min(Self::FRACTIONAL_BITS);
+        let root = math::checked_sqrt(self.0 << shift)?;
+        Ok(Self(root << ((Self::FRACTIONAL_BITS - shift) / 2)))
+    }
+
+    /// `|self - other|`
+    pub fn abs_diff(self, other: Self) -> Self {
+        Self(self.0.abs_diff(other.0))
+    }
+}
+
+/// `a * b / denominator`, rounded down, through a 256-bit intermediate product
+/// when `a * b` overflows a u128. Fails only when the quotient itself does.
+pub fn mul_div_floor(a: u128, b: u128, denominator: u128) -> Result<u128> {
+    mul_div(a, b, denominator, false)
+}
+
+/// `a * b / denominator`, rounded up, through a 256-bit intermediate product
+pub fn mul_div_ceil(a: u128, b: u128, denominator: u128) -> Result<u128> {
+    mul_div(a, b, denominator, true)
+}
+
+fn mul_div(a: u128, b: u128, denominator: u128, round_up: bool) -> Result<u128> {
+    require!(denominator > 0, AmmError::DivisionByZero);
+    let (quotient, remainder) = match a.checked_mul(b) {
+        Some(product) => (product / denominator, product % denominator),
+        None => {
+            let (high, low) = full_mul(a, b);
+            div_wide(high, low, denominator).ok_or(AmmError::MathOverflow)?
+        }
+    };
+    if round_up && remainder > 0 {
+        quotient
+            .checked_add(1)
+            .ok_or(error!(AmmError::MathOverflow))
+    } else {
+        Ok(quotient)
+    }
+}
+
+/// 256-bit product of `a` and `b` as (high, low) halves
+fn full_mul(a: u128, b: u128) -> (u128, u128) {
+    const MASK: u128 = u64::MAX as u128;
+    let (a_high, a_low) = (a >> 64, a & MASK);
+    let (b_high, b_low) = (b >> 64, b & MASK);
+
+    let low_low = a_low * b_low;
+    let low_high = a_low * b_high;
+    let high_low = a_high * b_low;
+    let high_high = a_high * b_high;
+
+    // Three 64-bit terms, so the middle sum cannot overflow
+    let middle = (low_low >> 64) + (low_high & MASK) + (high_low & MASK);
+    let low = (low_low & MASK) | (middle << 64);
+    let high = high_high + (low_high >> 64) + (high_low >> 64) + (middle >> 64);
+    (high, low)
+}
+
+/// (quotient, remainder) of the 256-bit `high:low` by `denominator`, or `None`
+/// when the quotient does not fit a u128
+fn div_wide(high: u128, low: u128, denominator: u128) -> Option<(u128, u128)> {
+    if high >= denominator {
+        return None;
+    }
+
+    // Restoring long division, one bit of the low half at a time. The
+    // remainder stays below the denominator, so a shift overflows at most one bit.
+    let mut remainder = high;
+    let mut quotient = 0u128;
+    for bit in (0..128).rev() {
+        let carry = remainder >> 127;
+        remainder = (remainder << 1) | ((low >> bit) & 1);
+        if carry == 1 || remainder >= denominator {
+            remainder = remainder.wrapping_sub(denominator);
+            quotient |= 1 << bit;
+        }
+    }
+    Some((quotient, remainder))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ratios_of_token_amounts_are_exact_to_the_last_bit() {
+        assert_eq!(Q64x64::from_ratio(3, 2).unwrap().to_bits(), 3 << 63);
+        assert_eq!(
+            Q64x64::from_ratio(1, u64::MAX as u128).unwrap(),
+            Q64x64::EPSILON
+        );
+        assert_eq!(
+            Q64x64::from_ratio(u64::MAX as u128, 1).unwrap(),
+            Q64x64::from_int(u64::MAX)
+        );
+        // A 1e12 integer scale would round this price to zero
+        assert!(Q64x64::from_ratio(1, 10u128.pow(15)).unwrap() > Q64x64::default());
+    }
+
+    #[test]
+    fn mul_and_div_round_down() {
+        let third = Q64x64::from_ratio(1, 3).unwrap();
+        assert_eq!(third.mul_floor(3).unwrap(), 0);
+        assert_eq!(third.mul_ceil(3).unwrap(), 1);
+        assert_eq!(
+            Q64x64::from_int(6)
+                .checked_div(Q64x64::from_int(4))
+                .unwrap(),
+            Q64x64::from_ratio(3, 2).unwrap()
+        );
+        assert_eq!(
+            Q64x64::from_ratio(3, 2)
+                .unwrap()
+                .checked_mul(Q64x64::from_int(4))
+                .unwrap(),
+            Q64x64::from_int(6)
+        );
+        assert!(Q64x64::from_int(u64::MAX)
+            .checked_mul(Q64x64::from_int(2))
+            .is_err());
+    }
+
+    #[test]
+    fn wide_mul_div_matches_narrow_and_handles_overflowing_products() {
+        for (a, b, denominator) in [(7u128, 3u128, 2u128), (1 << 100, 1 << 20, 3), (0, 5, 1)] {
+            assert_eq!(
+                mul_div_floor(a, b, denominator).unwrap(),
+                math::mul_div_floor(a, b, denominator).unwrap()
+            );
+            assert_eq!(
+                mul_div_ceil(a, b, denominator).unwrap(),
+                math::mul_div_ceil(a, b, denominator).unwrap()
+            );
+        }
+
+        assert_eq!(
+            mul_div_floor(u128::MAX, u128::MAX, u128::MAX).unwrap(),
+            u128::MAX
+        );
+        assert_eq!(mul_div_floor(u128::MAX, 3, 5).unwrap(), u128::MAX / 5 * 3);
+        assert_eq!(mul_div_ceil(1 << 127, 3, 1 << 64).unwrap(), 3 << 63);
+        assert_eq!(
+            mul_div_floor(u128::MAX, 2, 1).unwrap_err(),
+            AmmError::MathOverflow.into()
+        );
+        assert_eq!(
+            mul_div_floor(1, 1, 0).unwrap_err(),
+            AmmError::DivisionByZero.into()
+        );
+    }
+
+    #[test]
+    fn sqrt_is_floor_of_exact_root() {
+        assert_eq!(Q64x64::from_int(4).sqrt().unwrap(), Q64x64::from_int(2));
+        assert_eq!(Q64x64::default().sqrt().unwrap(), Q64x64::default());
+        assert_eq!(
+            Q64x64::from_ratio(1, 4).unwrap().sqrt().unwrap(),
+            Q64x64::from_ratio(1, 2).unwrap()
+        );
+
+        for value in [
+            Q64x64::EPSILON,
+            Q64x64::from_ratio(2, 1).unwrap(),
+            Q64x64::from_ratio(1, 3).unwrap(),
+            Q64x64::from_int(u64::MAX),
+            Q64x64::from_bits(u128::MAX),
+        ] {
+            let root = value.sqrt().unwrap();
+            assert!(root.checked_mul(root).unwrap() <= value);
+            // Within 2^-32 of the exact root
+            let above = Q64x64::from_bits(root.to_bits() + (1 << 32));
+            assert!(above
+                .checked_mul(above)
+                .map_or(true, |square| square > value));
+        }
+    }
+}
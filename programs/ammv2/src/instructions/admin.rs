@@ -0,0 +1,745 @@
+use crate::errors::AmmError;
+use crate::events::VerificationLevelChanged;
+use crate::state::{
+    AmmConfig, LiquidityPool, VerificationLevel, BASIS_POINTS_DIVISOR, MAX_JIT_PENALTY_BPS,
+    POOL_OPS_ALL,
+};
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::TokenAccount;
+
+/// Configure the per-slot price movement circuit breaker. Goes through
+/// `queue_param_change` instead while the global timelock is on.
+pub fn set_circuit_breaker(
+    ctx: Context<UpdatePoolConfig>,
+    max_price_change_bps_per_slot: u16,
+) -> Result<()> {
+    ctx.accounts.amm_config.check_no_timelock()?;
+    require!(
+        (max_price_change_bps_per_slot as u128) <= BASIS_POINTS_DIVISOR,
+        AmmError::InvalidPoolConfig
+    );
+
+    let pool = &mut ctx.accounts.liquidity_pool.load_mut()?;
+    pool.max_price_change_bps_per_slot = max_price_change_bps_per_slot;
+
+    log!(
+        "Circuit breaker set to {} bps per slot",
+        max_price_change_bps_per_slot
+    );
+
+    emit!(pool.params_snapshot(ctx.accounts.liquidity_pool.key())?);
+    Ok(())
+}
+
+/// Configure per-trader, per-slot anti-sandwich limits. Goes through
+/// `queue_param_change` instead while the global timelock is on.
+pub fn set_anti_sandwich_limits(
+    ctx: Context<UpdatePoolConfig>,
+    max_trader_slot_volume_bps: u16,
+    block_same_slot_reversal: bool,
+) -> Result<()> {
+    ctx.accounts.amm_config.check_no_timelock()?;
+    require!(
+        (max_trader_slot_volume_bps as u128) <= BASIS_POINTS_DIVISOR,
+        AmmError::InvalidPoolConfig
+    );
+
+    let pool = &mut ctx.accounts.liquidity_pool.load_mut()?;
+    pool.max_trader_slot_volume_bps = max_trader_slot_volume_bps;
+    pool.block_same_slot_reversal = block_same_slot_reversal as u8;
+
+    log!(
+        "Anti-sandwich limits set - Max volume: {} bps, Block reversal: {}",
+        max_trader_slot_volume_bps,
+        block_same_slot_reversal
+    );
+
+    emit!(pool.params_snapshot(ctx.accounts.liquidity_pool.key())?);
+    Ok(())
+}
+
+/// Cap a single swap's input to a share of the input reserve. Goes through
+/// `queue_param_change` instead while the global timelock is on.
+pub fn set_max_trade_size(
+    ctx: Context<UpdatePoolConfig>,
+    max_trade_bps_of_reserves: u16,
+) -> Result<()> {
+    ctx.accounts.amm_config.check_no_timelock()?;
+    require!(
+        (max_trade_bps_of_reserves as u128) <= BASIS_POINTS_DIVISOR,
+        AmmError::InvalidPoolConfig
+    );
+
+    let pool = &mut ctx.accounts.liquidity_pool.load_mut()?;
+    pool.max_trade_bps_of_reserves = max_trade_bps_of_reserves;
+
+    log!(
+        "Max trade size set to {} bps of reserves",
+        max_trade_bps_of_reserves
+    );
+
+    emit!(pool.params_snapshot(ctx.accounts.liquidity_pool.key())?);
+    Ok(())
+}
+
+/// Cap the spot price move of a single swap. Goes through `queue_param_change`
+/// instead while the global timelock is on.
+pub fn set_max_price_impact(
+    ctx: Context<UpdatePoolConfig>,
+    max_price_impact_bps: u16,
+) -> Result<()> {
+    ctx.accounts.amm_config.check_no_timelock()?;
+    require!(
+        (max_price_impact_bps as u128) <= BASIS_POINTS_DIVISOR,
+        AmmError::InvalidPoolConfig
+    );
+
+    let pool = &mut ctx.accounts.liquidity_pool.load_mut()?;
+    pool.max_price_impact_bps = max_price_impact_bps;
+
+    log!("Max price impact set to {} bps", max_price_impact_bps);
+
+    emit!(pool.params_snapshot(ctx.accounts.liquidity_pool.key())?);
+    Ok(())
+}
+
+/// Set the share of swap fees accrued to referrers. Goes through
+/// `queue_param_change` instead while the global timelock is on.
+pub fn set_referral_fee(ctx: Context<UpdatePoolConfig>, referral_fee_bps: u16) -> Result<()> {
+    ctx.accounts.amm_config.check_no_timelock()?;
+    require!(
+        (referral_fee_bps as u128) <= BASIS_POINTS_DIVISOR,
+        AmmError::InvalidPoolConfig
+    );
+
+    let pool = &mut ctx.accounts.liquidity_pool.load_mut()?;
+    pool.referral_fee_bps = referral_fee_bps;
+
+    log!("Referral fee set to {} bps of fees", referral_fee_bps);
+
+    emit!(pool.params_snapshot(ctx.accounts.liquidity_pool.key())?);
+    Ok(())
+}
+
+/// Set the token A tip paid to crank callers and the share of token A protocol
+/// fees that funds it. Goes through `queue_param_change` instead while the
+/// global timelock is on.
+pub fn set_keeper_tips(
+    ctx: Context<UpdatePoolConfig>,
+    keeper_tip: u64,
+    keeper_tip_share_bps: u16,
+) -> Result<()> {
+    ctx.accounts.amm_config.check_no_timelock()?;
+    require!(
+        (keeper_tip_share_bps as u128) <= BASIS_POINTS_DIVISOR,
+        AmmError::InvalidPoolConfig
+    );
+
+    let pool = &mut ctx.accounts.liquidity_pool.load_mut()?;
+    pool.keeper_tip = keeper_tip;
+    pool.keeper_tip_share_bps = keeper_tip_share_bps;
+
+    log!(
+        "Keeper tip set to {} funded by {} bps of protocol fees (budget {})",
+        keeper_tip,
+        keeper_tip_share_bps,
+        pool.keeper_tip_budget
+    );
+
+    emit!(pool.params_snapshot(ctx.accounts.liquidity_pool.key())?);
+    Ok(())
+}
+
+/// Configure the launch-protection window and per-wallet trade cooldown. Goes
+/// through `queue_param_change` instead while the global timelock is on.
+pub fn set_launch_protection(
+    ctx: Context<UpdatePoolConfig>,
+    launch_protection_end_slot: u64,
+    launch_trade_cooldown_slots: u64,
+) -> Result<()> {
+    ctx.accounts.amm_config.check_no_timelock()?;
+    let pool = &mut ctx.accounts.liquidity_pool.load_mut()?;
+    pool.launch_protection_end_slot = launch_protection_end_slot;
+    pool.launch_trade_cooldown_slots = launch_trade_cooldown_slots;
+
+    log!(
+        "Launch protection until slot {} with {} slot cooldown",
+        launch_protection_end_slot,
+        launch_trade_cooldown_slots
+    );
+
+    emit!(pool.params_snapshot(ctx.accounts.liquidity_pool.key())?);
+    Ok(())
+}
+
+/// Configure the penalty on withdrawals made within `jit_window_slots` of a
+/// deposit, capped at `MAX_JIT_PENALTY_BPS` so a position can always exit. Goes
+/// through `queue_param_change` instead while the global timelock is on.
+pub fn set_jit_protection(
+    ctx: Context<UpdatePoolConfig>,
+    jit_window_slots: u64,
+    jit_penalty_bps: u16,
+) -> Result<()> {
+    ctx.accounts.amm_config.check_no_timelock()?;
+    require!(
+        jit_penalty_bps <= MAX_JIT_PENALTY_BPS,
+        AmmError::InvalidPoolConfig
+    );
+
+    let pool = &mut ctx.accounts.liquidity_pool.load_mut()?;
+    pool.jit_window_slots = jit_window_slots;
+    pool.jit_penalty_bps = jit_penalty_bps;
+
+    log!(
+        "JIT window: {} slots, Penalty: {} bps",
+        jit_window_slots,
+        jit_penalty_bps
+    );
+
+    emit!(pool.params_snapshot(ctx.accounts.liquidity_pool.key())?);
+    Ok(())
+}
+
+/// Lock a position's withdrawals for `withdraw_cooldown_secs` after each
+/// deposit. Goes through `queue_param_change` instead while the global
+/// timelock is on.
+pub fn set_withdraw_cooldown(
+    ctx: Context<UpdatePoolConfig>,
+    withdraw_cooldown_secs: i64,
+) -> Result<()> {
+    ctx.accounts.amm_config.check_no_timelock()?;
+    require!(withdraw_cooldown_secs >= 0, AmmError::InvalidPoolConfig);
+
+    let pool = &mut ctx.accounts.liquidity_pool.load_mut()?;
+    pool.withdraw_cooldown_secs = withdraw_cooldown_secs;
+
+    log!("Withdrawal cooldown set to {}s", withdraw_cooldown_secs);
+
+    emit!(pool.params_snapshot(ctx.accounts.liquidity_pool.key())?);
+    Ok(())
+}
+
+/// Pay `oracle_checkpoint_reward` of token A to whoever checkpoints a stale
+/// oracle. Goes through `queue_param_change` instead while the global timelock
+/// is on.
+pub fn set_oracle_checkpoint_reward(
+    ctx: Context<UpdatePoolConfig>,
+    oracle_checkpoint_reward: u64,
+) -> Result<()> {
+    ctx.accounts.amm_config.check_no_timelock()?;
+    let pool = &mut ctx.accounts.liquidity_pool.load_mut()?;
+    pool.oracle_checkpoint_reward = oracle_checkpoint_reward;
+
+    log!(
+        "Oracle checkpoint reward set to {}",
+        oracle_checkpoint_reward
+    );
+
+    emit!(pool.params_snapshot(ctx.accounts.liquidity_pool.key())?);
+    Ok(())
+}
+
+/// Change the pool's fixed swap fee. Goes through `queue_param_change`
+/// instead while the global timelock is on.
+pub fn update_fee(
+    ctx: Context<UpdatePoolConfig>,
+    fee_numerator: u64,
+    fee_denominator: u64,
+) -> Result<()> {
+    ctx.accounts.amm_config.check_no_timelock()?;
+    LiquidityPool::validate_fee(fee_numerator, fee_denominator)?;
+
+    let pool = &mut ctx.accounts.liquidity_pool.load_mut()?;
+    pool.fee_numerator = fee_numerator;
+    pool.fee_denominator = fee_denominator;
+
+    log!("Fee set to {}/{}", fee_numerator, fee_denominator);
+
+    emit!(pool.params_snapshot(ctx.accounts.liquidity_pool.key())?);
+    Ok(())
+}
+
+/// Hand the pool's admin authority to an SPL Governance account of `realm`.
+/// Admin instructions then need a passed proposal, executed by the governance
+/// program signing as the governance PDA. Goes through `queue_param_change`
+/// instead while the global timelock is on.
+pub fn set_governance_admin(ctx: Context<SetGovernanceAdmin>) -> Result<()> {
+    ctx.accounts.amm_config.check_no_timelock()?;
+    let pool = &mut ctx.accounts.liquidity_pool.load_mut()?;
+    apply_governance_admin(pool, &ctx.accounts.realm, &ctx.accounts.governance)?;
+
+    emit!(pool.params_snapshot(ctx.accounts.liquidity_pool.key())?);
+    Ok(())
+}
+
+/// Make `governance`, checked to be a governance account of `realm`, the pool admin
+pub(crate) fn apply_governance_admin(
+    pool: &mut LiquidityPool,
+    realm: &AccountInfo,
+    governance: &AccountInfo,
+) -> Result<()> {
+    // Both belong to the same governance program deployment, and governance
+    // accounts store their realm right after the account type byte
+    let data = governance.try_borrow_data()?;
+    require!(
+        governance.owner == realm.owner
+            && *realm.owner != System::id()
+            && data.get(1..33) == Some(realm.key().as_ref()),
+        AmmError::InvalidGovernanceAccount
+    );
+
+    pool.admin = governance.key();
+    pool.admin_realm = realm.key();
+
+    log!(
+        "Admin handed to governance {} of realm {}",
+        pool.admin,
+        pool.admin_realm
+    );
+    Ok(())
+}
+
+/// Switch the swap fee to a dynamic fee between `dynamic_fee_min_bps` and
+/// `dynamic_fee_max_bps`, scaled by the oracle's realized volatility up to
+/// `dynamic_fee_volatility_bps`. A zero maximum returns to the fixed fee.
+/// Goes through `queue_param_change` instead while the global timelock is on.
+pub fn set_dynamic_fee(
+    ctx: Context<UpdatePoolConfig>,
+    dynamic_fee_min_bps: u16,
+    dynamic_fee_max_bps: u16,
+    dynamic_fee_volatility_bps: u16,
+) -> Result<()> {
+    ctx.accounts.amm_config.check_no_timelock()?;
+    let pool = &mut ctx.accounts.liquidity_pool.load_mut()?;
+    apply_dynamic_fee(
+        pool,
+        dynamic_fee_min_bps,
+        dynamic_fee_max_bps,
+        dynamic_fee_volatility_bps,
+    )?;
+
+    emit!(pool.params_snapshot(ctx.accounts.liquidity_pool.key())?);
+    Ok(())
+}
+
+/// Set the pool's dynamic fee bounds, which need an oracle to scale against
+pub(crate) fn apply_dynamic_fee(
+    pool: &mut LiquidityPool,
+    dynamic_fee_min_bps: u16,
+    dynamic_fee_max_bps: u16,
+    dynamic_fee_volatility_bps: u16,
+) -> Result<()> {
+    LiquidityPool::validate_dynamic_fee(
+        dynamic_fee_min_bps,
+        dynamic_fee_max_bps,
+        dynamic_fee_volatility_bps,
+    )?;
+    if dynamic_fee_max_bps > 0 {
+        require!(
+            pool.oracle != Pubkey::default(),
+            AmmError::OracleAccountRequired
+        );
+    }
+
+    pool.dynamic_fee_min_bps = dynamic_fee_min_bps;
+    pool.dynamic_fee_max_bps = dynamic_fee_max_bps;
+    pool.dynamic_fee_volatility_bps = dynamic_fee_volatility_bps;
+    // Start from the minimum; the next oracle observation sets the real fee
+    pool.dynamic_fee_bps = dynamic_fee_min_bps;
+
+    log!(
+        "Dynamic fee: {}-{} bps, Max at volatility: {} bps",
+        dynamic_fee_min_bps,
+        dynamic_fee_max_bps,
+        dynamic_fee_volatility_bps
+    );
+    Ok(())
+}
+
+/// Charge the swap fee on the output token instead of the input token. The
+/// protocol and creator shares follow the fee into the output mint. Goes
+/// through `queue_param_change` instead while the global timelock is on.
+pub fn set_fee_on_output(ctx: Context<UpdatePoolConfig>, fee_on_output: bool) -> Result<()> {
+    ctx.accounts.amm_config.check_no_timelock()?;
+    let pool = &mut ctx.accounts.liquidity_pool.load_mut()?;
+    pool.fee_on_output = fee_on_output as u8;
+
+    log!("Fee on output: {}", fee_on_output);
+
+    emit!(pool.params_snapshot(ctx.accounts.liquidity_pool.key())?);
+    Ok(())
+}
+
+/// Enter or leave emergency withdraw-only mode. Goes through
+/// `queue_param_change` instead while the global timelock is on.
+pub fn set_withdraw_only(
+    ctx: Context<UpdatePoolConfig>,
+    withdraw_only: bool,
+    large_withdrawal_delay_secs: i64,
+    small_lp_withdrawal_threshold: u64,
+) -> Result<()> {
+    ctx.accounts.amm_config.check_no_timelock()?;
+    let pool = &mut ctx.accounts.liquidity_pool.load_mut()?;
+    apply_withdraw_only(
+        pool,
+        withdraw_only,
+        large_withdrawal_delay_secs,
+        small_lp_withdrawal_threshold,
+    )?;
+
+    emit!(pool.params_snapshot(ctx.accounts.liquidity_pool.key())?);
+    Ok(())
+}
+
+/// Set the pool's withdraw-only mode, dating its start when it turns on
+pub(crate) fn apply_withdraw_only(
+    pool: &mut LiquidityPool,
+    withdraw_only: bool,
+    large_withdrawal_delay_secs: i64,
+    small_lp_withdrawal_threshold: u64,
+) -> Result<()> {
+    require!(
+        large_withdrawal_delay_secs >= 0,
+        AmmError::InvalidPoolConfig
+    );
+
+    if withdraw_only && pool.withdraw_only == 0 {
+        pool.withdraw_only_since = Clock::get()?.unix_timestamp;
+    }
+    pool.withdraw_only = withdraw_only as u8;
+    pool.large_withdrawal_delay_secs = large_withdrawal_delay_secs;
+    pool.small_lp_withdrawal_threshold = small_lp_withdrawal_threshold;
+
+    log!(
+        "Withdraw-only: {}, Large withdrawal delay: {}s, Small LP threshold: {}",
+        withdraw_only,
+        large_withdrawal_delay_secs,
+        small_lp_withdrawal_threshold
+    );
+    Ok(())
+}
+
+/// Configure the oracle deviation guard and its insurance surcharge. Goes
+/// through `queue_param_change` instead while the global timelock is on.
+pub fn set_deviation_guard(
+    ctx: Context<UpdatePoolConfig>,
+    deviation_twap_window_secs: i64,
+    max_oracle_deviation_bps: u16,
+    incident_deviation_bps: u16,
+    incident_surcharge_bps: u16,
+) -> Result<()> {
+    ctx.accounts.amm_config.check_no_timelock()?;
+    let pool = &mut ctx.accounts.liquidity_pool.load_mut()?;
+    apply_deviation_guard(
+        pool,
+        deviation_twap_window_secs,
+        max_oracle_deviation_bps,
+        incident_deviation_bps,
+        incident_surcharge_bps,
+    )?;
+
+    emit!(pool.params_snapshot(ctx.accounts.liquidity_pool.key())?);
+    Ok(())
+}
+
+/// Set the pool's deviation guard, clearing any incident once the band is off
+pub(crate) fn apply_deviation_guard(
+    pool: &mut LiquidityPool,
+    deviation_twap_window_secs: i64,
+    max_oracle_deviation_bps: u16,
+    incident_deviation_bps: u16,
+    incident_surcharge_bps: u16,
+) -> Result<()> {
+    LiquidityPool::validate_deviation_guard(
+        deviation_twap_window_secs,
+        max_oracle_deviation_bps,
+        incident_deviation_bps,
+        incident_surcharge_bps,
+    )?;
+
+    pool.deviation_twap_window_secs = deviation_twap_window_secs;
+    pool.max_oracle_deviation_bps = max_oracle_deviation_bps;
+    pool.incident_deviation_bps = incident_deviation_bps;
+    pool.incident_surcharge_bps = incident_surcharge_bps;
+    if incident_deviation_bps == 0 {
+        pool.deviation_incident_active = 0;
+    }
+
+    log!(
+        "Deviation guard set - Window: {}s, Max: {} bps, Incident: {} bps, Surcharge: {} bps",
+        deviation_twap_window_secs,
+        max_oracle_deviation_bps,
+        incident_deviation_bps,
+        incident_surcharge_bps
+    );
+    Ok(())
+}
+
+/// Set the minimum amounts the pool's first deposit must provide. Goes through
+/// `queue_param_change` instead while the global timelock is on.
+pub fn set_min_initial_liquidity(
+    ctx: Context<UpdatePoolConfig>,
+    min_initial_deposit_a: u64,
+    min_initial_deposit_b: u64,
+) -> Result<()> {
+    ctx.accounts.amm_config.check_no_timelock()?;
+    let pool = &mut ctx.accounts.liquidity_pool.load_mut()?;
+    pool.min_initial_deposit_a = min_initial_deposit_a;
+    pool.min_initial_deposit_b = min_initial_deposit_b;
+
+    log!(
+        "Minimum initial liquidity set - Token A: {}, Token B: {}",
+        min_initial_deposit_a,
+        min_initial_deposit_b
+    );
+
+    emit!(pool.params_snapshot(ctx.accounts.liquidity_pool.key())?);
+    Ok(())
+}
+
+/// Take a share of swap fees for the protocol, optionally bought back into one
+/// of the pool's tokens and burned. Goes through `queue_param_change` instead
+/// while the global timelock is on.
+pub fn set_protocol_fee(
+    ctx: Context<UpdatePoolConfig>,
+    protocol_fee_share_bps: u16,
+    buyback_mint: Option<Pubkey>,
+) -> Result<()> {
+    ctx.accounts.amm_config.check_no_timelock()?;
+    let pool = &mut ctx.accounts.liquidity_pool.load_mut()?;
+    apply_protocol_fee(pool, protocol_fee_share_bps, buyback_mint)?;
+
+    emit!(pool.params_snapshot(ctx.accounts.liquidity_pool.key())?);
+    Ok(())
+}
+
+/// Set the pool's protocol fee share and buyback mint, one of the pool's tokens
+pub(crate) fn apply_protocol_fee(
+    pool: &mut LiquidityPool,
+    protocol_fee_share_bps: u16,
+    buyback_mint: Option<Pubkey>,
+) -> Result<()> {
+    require!(
+        (protocol_fee_share_bps as u128) <= BASIS_POINTS_DIVISOR,
+        AmmError::InvalidPoolConfig
+    );
+    if let Some(buyback_mint) = buyback_mint {
+        require!(
+            buyback_mint == pool.token_a_mint || buyback_mint == pool.token_b_mint,
+            AmmError::InvalidPoolConfig
+        );
+    }
+    pool.protocol_fee_share_bps = protocol_fee_share_bps;
+    pool.buyback_mint = buyback_mint.unwrap_or_default();
+
+    log!(
+        "Protocol fee set - Share: {} bps of fees, Buyback mint: {}",
+        protocol_fee_share_bps,
+        pool.buyback_mint
+    );
+    Ok(())
+}
+
+/// Set the share of swap fees the pool's creator earns, up to the global cap,
+/// and the creator's token accounts it is paid into. Goes through
+/// `queue_param_change` instead while the global timelock is on.
+pub fn set_creator_fee(ctx: Context<SetCreatorFee>, creator_fee_bps: u16) -> Result<()> {
+    ctx.accounts.amm_config.check_no_timelock()?;
+    require!(
+        creator_fee_bps <= ctx.accounts.amm_config.max_creator_fee_bps,
+        AmmError::CreatorFeeTooHigh
+    );
+
+    let pool = &mut ctx.accounts.liquidity_pool.load_mut()?;
+    pool.creator_fee_bps = creator_fee_bps;
+    pool.creator_fee_account_a = ctx.accounts.creator_fee_account_a.key();
+    pool.creator_fee_account_b = ctx.accounts.creator_fee_account_b.key();
+
+    log!(
+        "Creator fee set - Share: {} bps of fees, Token A: {}, Token B: {}",
+        creator_fee_bps,
+        pool.creator_fee_account_a,
+        pool.creator_fee_account_b
+    );
+
+    emit!(pool.params_snapshot(ctx.accounts.liquidity_pool.key())?);
+    Ok(())
+}
+
+/// Route the protocol fee share to the admin's own token accounts instead of
+/// the pool's protocol fee vaults. Omitting an account restores the vault.
+/// Goes through `queue_param_change` instead while the global timelock is on.
+pub fn set_fee_recipients(ctx: Context<SetFeeRecipients>) -> Result<()> {
+    ctx.accounts.amm_config.check_no_timelock()?;
+    let pool = &mut ctx.accounts.liquidity_pool.load_mut()?;
+    pool.fee_recipient_a = ctx
+        .accounts
+        .fee_recipient_a
+        .as_ref()
+        .map(|account| account.key())
+        .unwrap_or_default();
+    pool.fee_recipient_b = ctx
+        .accounts
+        .fee_recipient_b
+        .as_ref()
+        .map(|account| account.key())
+        .unwrap_or_default();
+
+    log!(
+        "Fee recipients set - Token A: {}, Token B: {}",
+        pool.fee_recipient_a,
+        pool.fee_recipient_b
+    );
+
+    emit!(pool.params_snapshot(ctx.accounts.liquidity_pool.key())?);
+    Ok(())
+}
+
+/// Switch individual operations off or back on, e.g. to open deposits before
+/// trading. Goes through `queue_param_change` instead while the global
+/// timelock is on.
+pub fn set_disabled_operations(
+    ctx: Context<UpdatePoolConfig>,
+    disabled_operations: u16,
+) -> Result<()> {
+    ctx.accounts.amm_config.check_no_timelock()?;
+    require!(
+        disabled_operations & !POOL_OPS_ALL == 0,
+        AmmError::InvalidPoolConfig
+    );
+
+    let pool = &mut ctx.accounts.liquidity_pool.load_mut()?;
+    pool.disabled_operations = disabled_operations;
+
+    log!("Disabled operations set to {:#06b}", disabled_operations);
+
+    emit!(pool.params_snapshot(ctx.accounts.liquidity_pool.key())?);
+    Ok(())
+}
+
+/// Restrict deposits to whitelisted wallets, or open them to everyone again.
+/// Goes through `queue_param_change` instead while the global timelock is on.
+pub fn set_lp_whitelist_enabled(ctx: Context<UpdatePoolConfig>, enabled: bool) -> Result<()> {
+    ctx.accounts.amm_config.check_no_timelock()?;
+    let pool = &mut ctx.accounts.liquidity_pool.load_mut()?;
+    pool.lp_whitelist_enabled = enabled as u8;
+
+    log!("LP whitelist enabled: {}", enabled);
+
+    emit!(pool.params_snapshot(ctx.accounts.liquidity_pool.key())?);
+    Ok(())
+}
+
+/// Assign the pool's verification level. Goes through `queue_param_change`
+/// instead while the global timelock is on.
+pub fn set_verification_level(
+    ctx: Context<UpdatePoolConfig>,
+    verification_level: VerificationLevel,
+) -> Result<()> {
+    ctx.accounts.amm_config.check_no_timelock()?;
+    let pool = &mut ctx.accounts.liquidity_pool.load_mut()?;
+    apply_verification_level(pool, ctx.accounts.liquidity_pool.key(), verification_level)?;
+
+    emit!(pool.params_snapshot(ctx.accounts.liquidity_pool.key())?);
+    Ok(())
+}
+
+/// Set the pool's verification level and announce the change
+pub(crate) fn apply_verification_level(
+    pool: &mut LiquidityPool,
+    pool_key: Pubkey,
+    verification_level: VerificationLevel,
+) -> Result<()> {
+    let previous_level = VerificationLevel::from_u8(pool.verification_level)?;
+    pool.verification_level = verification_level as u8;
+
+    log!("Verification level set to {:?}", verification_level);
+
+    emit!(VerificationLevelChanged {
+        liquidity_pool: pool_key,
+        event_seq: pool.next_event_seq(),
+        previous_level,
+        new_level: verification_level,
+    });
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct UpdatePoolConfig<'info> {
+    /// Pool state account
+    #[account(mut, has_one = admin @ AmmError::Unauthorized)]
+    pub liquidity_pool: AccountLoader<'info, LiquidityPool>,
+
+    /// Global config, checked for the parameter timelock
+    #[account(seeds = [b"amm_config"], bump)]
+    pub amm_config: Box<Account<'info, AmmConfig>>,
+
+    /// Pool admin
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetGovernanceAdmin<'info> {
+    /// Pool state account
+    #[account(mut, has_one = admin @ AmmError::Unauthorized)]
+    pub liquidity_pool: AccountLoader<'info, LiquidityPool>,
+
+    /// Global config, checked for the parameter timelock
+    #[account(seeds = [b"amm_config"], bump)]
+    pub amm_config: Box<Account<'info, AmmConfig>>,
+
+    /// Current pool admin, possibly a governance account itself
+    pub admin: Signer<'info>,
+
+    /// SPL Governance realm
+    /// CHECK: only its address and owning program are used
+    pub realm: UncheckedAccount<'info>,
+
+    /// Governance account of the realm that becomes the admin
+    /// CHECK: checked against the realm in the handler
+    pub governance: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetCreatorFee<'info> {
+    /// Pool state account
+    #[account(mut, has_one = creator @ AmmError::Unauthorized)]
+    pub liquidity_pool: AccountLoader<'info, LiquidityPool>,
+
+    /// Global config, holding the creator fee cap and the parameter timelock
+    #[account(seeds = [b"amm_config"], bump)]
+    pub amm_config: Box<Account<'info, AmmConfig>>,
+
+    /// Wallet that initialized the pool
+    pub creator: Signer<'info>,
+
+    /// Token A account to receive the creator's fee share
+    #[account(constraint = creator_fee_account_a.mint == liquidity_pool.load()?.token_a_mint)]
+    pub creator_fee_account_a: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Token B account to receive the creator's fee share
+    #[account(constraint = creator_fee_account_b.mint == liquidity_pool.load()?.token_b_mint)]
+    pub creator_fee_account_b: Box<InterfaceAccount<'info, TokenAccount>>,
+}
+
+#[derive(Accounts)]
+pub struct SetFeeRecipients<'info> {
+    /// Pool state account
+    #[account(mut, has_one = admin @ AmmError::Unauthorized)]
+    pub liquidity_pool: AccountLoader<'info, LiquidityPool>,
+
+    /// Global config, checked for the parameter timelock
+    #[account(seeds = [b"amm_config"], bump)]
+    pub amm_config: Box<Account<'info, AmmConfig>>,
+
+    /// Pool admin
+    pub admin: Signer<'info>,
+
+    /// Token A account to receive the protocol fee share
+    #[account(constraint = fee_recipient_a.mint == liquidity_pool.load()?.token_a_mint)]
+    pub fee_recipient_a: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    /// Token B account to receive the protocol fee share
+    #[account(constraint = fee_recipient_b.mint == liquidity_pool.load()?.token_b_mint)]
+    pub fee_recipient_b: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+}
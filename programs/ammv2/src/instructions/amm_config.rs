@@ -0,0 +1,227 @@
+use crate::errors::AmmError;
+use crate::state::{AmmConfig, UnsafeMintApproval, BASIS_POINTS_DIVISOR};
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::bpf_loader_upgradeable;
+use anchor_lang::system_program::{self, Transfer};
+
+/// Create the global config. Only the program's upgrade authority can, so the
+/// config authority cannot be claimed by whoever calls first after deployment.
+pub fn initialize(
+    ctx: Context<InitializeAmmConfig>,
+    pool_creation_fee_lamports: u64,
+) -> Result<()> {
+    // Keep the treasury rent-exempt so fees of any size can be sent to it
+    let treasury_rent = Rent::get()?.minimum_balance(0);
+    let treasury_top_up = treasury_rent.saturating_sub(ctx.accounts.treasury.lamports());
+    if treasury_top_up > 0 {
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.authority.to_account_info(),
+                    to: ctx.accounts.treasury.to_account_info(),
+                },
+            ),
+            treasury_top_up,
+        )?;
+    }
+
+    let config = &mut ctx.accounts.amm_config;
+    config.authority = ctx.accounts.authority.key();
+    config.treasury = ctx.accounts.treasury.key();
+    config.pool_creation_fee_lamports = pool_creation_fee_lamports;
+    config.paused = false;
+
+    log!(
+        "AMM config initialized - Treasury: {}, Pool creation fee: {} lamports",
+        config.treasury,
+        pool_creation_fee_lamports
+    );
+
+    emit!(config.snapshot());
+    Ok(())
+}
+
+/// Set the lamport fee charged on `initialize_pool`
+pub fn set_pool_creation_fee(
+    ctx: Context<UpdateAmmConfig>,
+    pool_creation_fee_lamports: u64,
+) -> Result<()> {
+    let config = &mut ctx.accounts.amm_config;
+    config.check_no_timelock()?;
+    config.pool_creation_fee_lamports = pool_creation_fee_lamports;
+
+    log!(
+        "Pool creation fee set to {} lamports",
+        pool_creation_fee_lamports
+    );
+
+    emit!(config.snapshot());
+    Ok(())
+}
+
+/// Cap the share of swap fees pool creators may take for themselves
+pub fn set_max_creator_fee(ctx: Context<UpdateAmmConfig>, max_creator_fee_bps: u16) -> Result<()> {
+    require!(
+        (max_creator_fee_bps as u128) <= BASIS_POINTS_DIVISOR,
+        AmmError::CreatorFeeTooHigh
+    );
+    let config = &mut ctx.accounts.amm_config;
+    config.check_no_timelock()?;
+    config.max_creator_fee_bps = max_creator_fee_bps;
+
+    log!("Max creator fee set to {} bps", max_creator_fee_bps);
+
+    emit!(config.snapshot());
+    Ok(())
+}
+
+/// Refuse or allow new pools over mints with an active freeze authority.
+/// Pools created under either policy record which of their mints are freezable.
+pub fn set_reject_freeze_authority(ctx: Context<UpdateAmmConfig>, reject: bool) -> Result<()> {
+    let config = &mut ctx.accounts.amm_config;
+    config.check_no_timelock()?;
+    config.reject_freeze_authority = reject;
+
+    log!("Reject freeze authority: {}", reject);
+
+    emit!(config.snapshot());
+    Ok(())
+}
+
+/// Pause or resume pool creation, deposits, swaps and staking across every pool.
+/// Pausing takes effect immediately; resuming goes through
+/// `queue_param_change` while the global timelock is on.
+pub fn set_paused(ctx: Context<UpdateAmmConfig>, paused: bool) -> Result<()> {
+    let config = &mut ctx.accounts.amm_config;
+    if !paused {
+        config.check_no_timelock()?;
+    }
+    config.paused = paused;
+
+    log!("Program paused: {}", paused);
+
+    emit!(config.snapshot());
+    Ok(())
+}
+
+/// Allow pools over `mint` even though it has a permanent delegate or close
+/// authority that could drain or brick the pool's vault
+pub fn approve_unsafe_mint(ctx: Context<ApproveUnsafeMint>, mint: Pubkey) -> Result<()> {
+    ctx.accounts.unsafe_mint_approval.mint = mint;
+
+    log!("Unsafe mint approved: {}", mint);
+    Ok(())
+}
+
+/// Withdraw an unsafe mint approval; existing pools are not affected
+pub fn revoke_unsafe_mint(ctx: Context<RevokeUnsafeMint>) -> Result<()> {
+    log!(
+        "Unsafe mint approval revoked: {}",
+        ctx.accounts.unsafe_mint_approval.mint
+    );
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct InitializeAmmConfig<'info> {
+    /// Global config singleton
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + AmmConfig::INIT_SPACE,
+        seeds = [b"amm_config"],
+        bump,
+    )]
+    pub amm_config: Box<Account<'info, AmmConfig>>,
+
+    /// Protocol treasury PDA
+    #[account(
+        mut,
+        seeds = [b"treasury"],
+        bump
+    )]
+    pub treasury: SystemAccount<'info>,
+
+    /// Program upgrade authority; pays for the config and becomes its authority
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    /// The program's ProgramData account, holding its upgrade authority
+    #[account(
+        seeds = [crate::ID.as_ref()],
+        bump,
+        seeds::program = bpf_loader_upgradeable::ID,
+        constraint = program_data.upgrade_authority_address == Some(authority.key()) @ AmmError::Unauthorized
+    )]
+    pub program_data: Account<'info, ProgramData>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateAmmConfig<'info> {
+    /// Global config singleton
+    #[account(
+        mut,
+        seeds = [b"amm_config"],
+        bump,
+        has_one = authority @ AmmError::Unauthorized,
+    )]
+    pub amm_config: Box<Account<'info, AmmConfig>>,
+
+    /// Config authority
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(mint: Pubkey)]
+pub struct ApproveUnsafeMint<'info> {
+    /// Global config singleton
+    #[account(
+        seeds = [b"amm_config"],
+        bump,
+        has_one = authority @ AmmError::Unauthorized,
+    )]
+    pub amm_config: Box<Account<'info, AmmConfig>>,
+
+    /// Approval of the mint
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + UnsafeMintApproval::INIT_SPACE,
+        seeds = [b"unsafe_mint_approval", mint.as_ref()],
+        bump,
+    )]
+    pub unsafe_mint_approval: Box<Account<'info, UnsafeMintApproval>>,
+
+    /// Config authority, pays for the approval
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RevokeUnsafeMint<'info> {
+    /// Global config singleton
+    #[account(
+        seeds = [b"amm_config"],
+        bump,
+        has_one = authority @ AmmError::Unauthorized,
+    )]
+    pub amm_config: Box<Account<'info, AmmConfig>>,
+
+    /// Approval being revoked
+    #[account(
+        mut,
+        close = authority,
+        seeds = [b"unsafe_mint_approval", unsafe_mint_approval.mint.as_ref()],
+        bump,
+    )]
+    pub unsafe_mint_approval: Box<Account<'info, UnsafeMintApproval>>,
+
+    /// Config authority, receives the approval's rent
+    #[account(mut)]
+    pub authority: Signer<'info>,
+}
@@ -0,0 +1,133 @@
+use crate::errors::AmmError;
+use crate::state::{LiquidityPool, PriceOracle};
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{
+    self, spl_token_2022::instruction::AuthorityType, CloseAccount, Mint, SetAuthority,
+    TokenAccount, TokenInterface,
+};
+
+/// Close an empty pool: close both vaults, revoke the LP mint authority, and
+/// close the pool and oracle accounts, returning all rent to `recipient`
+pub fn process(ctx: Context<ClosePool>) -> Result<()> {
+    {
+        let pool = ctx.accounts.liquidity_pool.load()?;
+        require!(pool.total_lp_tokens_issued == 0, AmmError::PoolNotEmpty);
+        require!(
+            pool.oracle == Pubkey::default() || ctx.accounts.oracle.is_some(),
+            AmmError::OracleAccountRequired
+        );
+    }
+
+    let pool_key = ctx.accounts.liquidity_pool.key();
+    let authority_bump = ctx.accounts.liquidity_pool.load()?.authority_bump;
+    let authority_seeds = &[b"pool_authority", pool_key.as_ref(), &[authority_bump]];
+    let signer_seeds = &[&authority_seeds[..]];
+
+    for (vault, token_program) in [
+        (&ctx.accounts.token_a_vault, &ctx.accounts.token_a_program),
+        (&ctx.accounts.token_b_vault, &ctx.accounts.token_b_program),
+    ] {
+        token_interface::close_account(CpiContext::new_with_signer(
+            token_program.to_account_info(),
+            CloseAccount {
+                account: vault.to_account_info(),
+                destination: ctx.accounts.recipient.to_account_info(),
+                authority: ctx.accounts.pool_authority.to_account_info(),
+            },
+            signer_seeds,
+        ))?;
+    }
+
+    // Mints cannot be closed, so make sure no LP can ever be minted again
+    token_interface::set_authority(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            SetAuthority {
+                current_authority: ctx.accounts.pool_authority.to_account_info(),
+                account_or_mint: ctx.accounts.lp_token_mint.to_account_info(),
+            },
+            signer_seeds,
+        ),
+        AuthorityType::MintTokens,
+        None,
+    )?;
+
+    log!(
+        "Pool {} closed, rent returned to {}",
+        pool_key,
+        ctx.accounts.recipient.key()
+    );
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct ClosePool<'info> {
+    /// Pool state account, closed to the recipient
+    #[account(
+        mut,
+        has_one = admin @ AmmError::Unauthorized,
+        close = recipient,
+    )]
+    pub liquidity_pool: AccountLoader<'info, LiquidityPool>,
+
+    /// Pool oracle, closed alongside the pool when it has one
+    #[account(
+        mut,
+        address = liquidity_pool.load()?.oracle @ AmmError::OracleAccountRequired,
+        close = recipient,
+    )]
+    pub oracle: Option<Box<Account<'info, PriceOracle>>>,
+
+    /// Pool authority PDA
+    #[account(
+        seeds = [b"pool_authority", liquidity_pool.key().as_ref()],
+        bump = liquidity_pool.load()?.authority_bump
+    )]
+    pub pool_authority: SystemAccount<'info>,
+
+    /// Token A vault, must be empty
+    #[account(
+        mut,
+        seeds = [b"token_a_vault", liquidity_pool.key().as_ref()],
+        bump = liquidity_pool.load()?.token_a_vault_bump,
+        constraint = token_a_vault.amount == 0 @ AmmError::PoolNotEmpty,
+    )]
+    pub token_a_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Token B vault, must be empty
+    #[account(
+        mut,
+        seeds = [b"token_b_vault", liquidity_pool.key().as_ref()],
+        bump = liquidity_pool.load()?.token_b_vault_bump,
+        constraint = token_b_vault.amount == 0 @ AmmError::PoolNotEmpty,
+    )]
+    pub token_b_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// LP token mint, must have no supply
+    #[account(
+        mut,
+        seeds = [b"lp_token_mint", liquidity_pool.key().as_ref()],
+        bump = liquidity_pool.load()?.lp_token_mint_bump,
+        constraint = lp_token_mint.supply == 0 @ AmmError::PoolNotEmpty,
+    )]
+    pub lp_token_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    /// Pool admin
+    pub admin: Signer<'info>,
+
+    /// Receives the rent of every closed account
+    /// CHECK: any account may receive lamports
+    #[account(mut)]
+    pub recipient: UncheckedAccount<'info>,
+
+    /// Token program of the LP mint
+    pub token_program: Interface<'info, TokenInterface>,
+
+    /// Token program of token A, the owner of its vault
+    #[account(address = *token_a_vault.to_account_info().owner @ AmmError::InvalidTokenProgram)]
+    pub token_a_program: Interface<'info, TokenInterface>,
+
+    /// Token program of token B, the owner of its vault
+    #[account(address = *token_b_vault.to_account_info().owner @ AmmError::InvalidTokenProgram)]
+    pub token_b_program: Interface<'info, TokenInterface>,
+}
@@ -0,0 +1,247 @@
+use crate::errors::AmmError;
+use crate::math;
+use crate::state::{
+    AmmConfig, Farm, LiquidityPool, StakePosition, BASIS_POINTS_DIVISOR, POOL_OP_DEPOSIT,
+    POOL_OP_SWAP,
+};
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{
+    self, Mint, MintTo, TokenAccount, TokenInterface, TransferChecked,
+};
+
+/// Claim a position's rewards paid in one of the pool's own tokens, swap half
+/// into the paired token through the pool, deposit both halves as liquidity and
+/// restake the LP tokens, all without the rewards leaving the program.
+///
+/// The swapped-out tokens never leave the vault: the whole reward moves into the
+/// pool in one transfer, and LP is minted for the post-swap deposit.
+pub fn process(ctx: Context<Compound>) -> Result<()> {
+    ctx.accounts.amm_config.check_not_paused()?;
+    let reward_mint = ctx.accounts.reward_mint.key();
+    let now = Clock::get()?.unix_timestamp;
+
+    let farm = &mut ctx.accounts.farm;
+    let index = farm.reward_index(&reward_mint)?;
+    farm.accrue(now)?;
+
+    let position = &mut ctx.accounts.stake_position;
+    position.settle(farm)?;
+
+    let payout = position.pending_rewards[index].min(ctx.accounts.farm_reward_vault.amount);
+    require!(payout > 0, AmmError::InvalidStakeAmount);
+
+    let pool = &mut ctx.accounts.liquidity_pool.load_mut()?;
+    require!(pool.withdraw_only == 0, AmmError::PoolWithdrawOnly);
+    pool.check_operation_enabled(POOL_OP_SWAP | POOL_OP_DEPOSIT)?;
+    pool.check_lp_supply(ctx.accounts.lp_token_mint.supply)?;
+
+    let reward_is_token_a = reward_mint == pool.token_a_mint;
+    require!(
+        reward_is_token_a || reward_mint == pool.token_b_mint,
+        AmmError::CompoundRewardNotInPool
+    );
+    let (reward_vault, paired_vault) = if reward_is_token_a {
+        (&ctx.accounts.token_a_vault, &ctx.accounts.token_b_vault)
+    } else {
+        (&ctx.accounts.token_b_vault, &ctx.accounts.token_a_vault)
+    };
+    let reward_reserve = reward_vault.amount as u128;
+    let paired_reserve = paired_vault.amount as u128;
+    require!(
+        reward_reserve > 0 && paired_reserve > 0,
+        AmmError::PoolHasNoLiquidity
+    );
+
+    // Swap half of the reward into the paired token
+    let swap_input = (payout / 2) as u128;
+    if pool.max_trade_bps_of_reserves > 0 {
+        let max_input = math::mul_div_floor(
+            reward_reserve,
+            pool.max_trade_bps_of_reserves as u128,
+            BASIS_POINTS_DIVISOR,
+        )?;
+        require!(swap_input <= max_input, AmmError::TradeTooLarge);
+    }
+    let (_, paired_output) = pool.swap_amounts(reward_reserve, paired_reserve, swap_input)?;
+    require!(paired_output > 0, AmmError::OutputTooSmall);
+
+    let post_swap_reward_reserve = reward_reserve
+        .checked_add(swap_input)
+        .ok_or(AmmError::MathOverflow)?;
+    let post_swap_paired_reserve = paired_reserve - paired_output;
+
+    if pool.max_price_change_bps_per_slot > 0 {
+        let (pre_swap_price, post_swap_price) = if reward_is_token_a {
+            (
+                LiquidityPool::spot_price(reward_reserve, paired_reserve)?,
+                LiquidityPool::spot_price(post_swap_reward_reserve, post_swap_paired_reserve)?,
+            )
+        } else {
+            (
+                LiquidityPool::spot_price(paired_reserve, reward_reserve)?,
+                LiquidityPool::spot_price(post_swap_paired_reserve, post_swap_reward_reserve)?,
+            )
+        };
+        pool.check_price_movement(Clock::get()?.slot, pre_swap_price, post_swap_price)?;
+    }
+
+    // Deposit the other half with the swap output at the post-swap ratio, rounded down
+    let deposit_reward = payout as u128 - swap_input;
+    let total_lp_supply = pool.total_lp_tokens_issued as u128;
+    let lp_tokens_to_mint = math::to_u64(
+        math::mul_div_floor(deposit_reward, total_lp_supply, post_swap_reward_reserve)?.min(
+            math::mul_div_floor(paired_output, total_lp_supply, post_swap_paired_reserve)?,
+        ),
+    )?;
+    require!(lp_tokens_to_mint > 0, AmmError::InvalidLpTokenAmount);
+
+    pool.total_lp_tokens_issued = pool
+        .total_lp_tokens_issued
+        .checked_add(lp_tokens_to_mint)
+        .ok_or(AmmError::MathOverflow)?;
+
+    // Move the whole reward into the pool
+    let signer = farm.signer();
+    let farm_seeds = signer.seeds();
+    token_interface::transfer_checked(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            TransferChecked {
+                from: ctx.accounts.farm_reward_vault.to_account_info(),
+                mint: ctx.accounts.reward_mint.to_account_info(),
+                to: reward_vault.to_account_info(),
+                authority: farm.to_account_info(),
+            },
+            &[&farm_seeds[..]],
+        ),
+        payout,
+        ctx.accounts.reward_mint.decimals,
+    )?;
+
+    // Mint the LP straight into the farm's LP vault
+    let pool_key = ctx.accounts.liquidity_pool.key();
+    let authority_bump = pool.authority_bump;
+    let authority_seeds = &[b"pool_authority", pool_key.as_ref(), &[authority_bump]];
+    token_interface::mint_to(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            MintTo {
+                mint: ctx.accounts.lp_token_mint.to_account_info(),
+                to: ctx.accounts.farm_lp_vault.to_account_info(),
+                authority: ctx.accounts.pool_authority.to_account_info(),
+            },
+            &[&authority_seeds[..]],
+        ),
+        lp_tokens_to_mint,
+    )?;
+
+    ctx.accounts.token_a_vault.reload()?;
+    ctx.accounts.token_b_vault.reload()?;
+    pool.sync_reserves(
+        ctx.accounts.token_a_vault.amount,
+        ctx.accounts.token_b_vault.amount,
+    );
+
+    position.pending_rewards[index] -= payout;
+    let staked = position
+        .amount
+        .checked_add(lp_tokens_to_mint)
+        .ok_or(AmmError::MathOverflow)?;
+    let boost_bps = position.effective_boost_bps(now);
+    position.update_stake(farm, staked, boost_bps)?;
+
+    log!(
+        "Compounded {} reward tokens - Swapped: {} for {}, LP restaked: {}",
+        payout,
+        swap_input,
+        paired_output,
+        lp_tokens_to_mint
+    );
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct Compound<'info> {
+    /// Farm state
+    #[account(
+        mut,
+        seeds = [b"farm", farm.liquidity_pool.as_ref()],
+        bump = farm.bump
+    )]
+    pub farm: Box<Account<'info, Farm>>,
+
+    /// The owner's stake in the farm
+    #[account(
+        mut,
+        seeds = [b"stake_position", farm.key().as_ref(), owner.key().as_ref()],
+        bump
+    )]
+    pub stake_position: Box<Account<'info, StakePosition>>,
+
+    /// Pool the farm belongs to
+    #[account(mut, address = farm.liquidity_pool)]
+    pub liquidity_pool: AccountLoader<'info, LiquidityPool>,
+
+    /// Global config, checked for the emergency pause
+    #[account(seeds = [b"amm_config"], bump)]
+    pub amm_config: Box<Account<'info, AmmConfig>>,
+
+    /// Pool authority PDA
+    #[account(
+        seeds = [b"pool_authority", liquidity_pool.key().as_ref()],
+        bump = liquidity_pool.load()?.authority_bump
+    )]
+    pub pool_authority: SystemAccount<'info>,
+
+    /// Reward token being compounded, one of the pool's two tokens
+    pub reward_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    /// Vault holding the compounded stream's rewards
+    #[account(
+        mut,
+        seeds = [
+            b"farm_reward_vault",
+            farm.key().as_ref(),
+            reward_mint.key().as_ref()
+        ],
+        bump
+    )]
+    pub farm_reward_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Token A vault
+    #[account(
+        mut,
+        seeds = [b"token_a_vault", liquidity_pool.key().as_ref()],
+        bump = liquidity_pool.load()?.token_a_vault_bump
+    )]
+    pub token_a_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Token B vault
+    #[account(
+        mut,
+        seeds = [b"token_b_vault", liquidity_pool.key().as_ref()],
+        bump = liquidity_pool.load()?.token_b_vault_bump
+    )]
+    pub token_b_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Pool LP token mint
+    #[account(
+        mut,
+        seeds = [b"lp_token_mint", liquidity_pool.key().as_ref()],
+        bump = liquidity_pool.load()?.lp_token_mint_bump
+    )]
+    pub lp_token_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    /// Vault holding the staked LP tokens
+    #[account(
+        mut,
+        seeds = [b"farm_lp_vault", farm.key().as_ref()],
+        bump
+    )]
+    pub farm_lp_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Staker
+    pub owner: Signer<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
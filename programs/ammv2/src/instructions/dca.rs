@@ -0,0 +1,470 @@
+use crate::errors::AmmError;
+use crate::instructions::keeper_tip::{pay_keeper_tip, KeeperTipAccounts};
+use crate::math;
+use crate::state::{AmmConfig, DcaPosition, LiquidityPool, MAX_KEEPER_TIP_LAMPORTS};
+use anchor_lang::prelude::*;
+use anchor_lang::system_program::{self, Transfer};
+use anchor_spl::token_interface::{
+    self, CloseAccount, Mint, TokenAccount, TokenInterface, TransferChecked,
+};
+
+/// Escrow `total_amount` of the input token and schedule it to be swapped
+/// through the pool `amount_per_interval` at a time. Keeper tips for every
+/// tranche are escrowed on the position up front.
+pub fn open(
+    ctx: Context<OpenDca>,
+    dca_id: u16,
+    total_amount: u64,
+    amount_per_interval: u64,
+    interval_secs: i64,
+    min_output_per_interval: u64,
+    keeper_tip_lamports: u64,
+) -> Result<()> {
+    ctx.accounts.amm_config.check_not_paused()?;
+    require!(
+        total_amount > 0
+            && amount_per_interval > 0
+            && interval_secs > 0
+            && keeper_tip_lamports <= MAX_KEEPER_TIP_LAMPORTS,
+        AmmError::InvalidDcaSchedule
+    );
+
+    let input_mint = ctx.accounts.input_mint.key();
+    let output_mint = ctx.accounts.output_mint.key();
+    {
+        let pool = ctx.accounts.liquidity_pool.load()?;
+        require!(
+            (input_mint == pool.token_a_mint && output_mint == pool.token_b_mint)
+                || (input_mint == pool.token_b_mint && output_mint == pool.token_a_mint),
+            AmmError::InvalidDcaSchedule
+        );
+    }
+
+    token_interface::transfer_checked(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            TransferChecked {
+                from: ctx.accounts.owner_input_token_account.to_account_info(),
+                mint: ctx.accounts.input_mint.to_account_info(),
+                to: ctx.accounts.dca_vault.to_account_info(),
+                authority: ctx.accounts.owner.to_account_info(),
+            },
+        ),
+        total_amount,
+        ctx.accounts.input_mint.decimals,
+    )?;
+
+    let tranches = total_amount.div_ceil(amount_per_interval);
+    let tips = tranches
+        .checked_mul(keeper_tip_lamports)
+        .ok_or(AmmError::MathOverflow)?;
+    if tips > 0 {
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.owner.to_account_info(),
+                    to: ctx.accounts.dca_position.to_account_info(),
+                },
+            ),
+            tips,
+        )?;
+    }
+
+    let position = &mut ctx.accounts.dca_position;
+    position.liquidity_pool = ctx.accounts.liquidity_pool.key();
+    position.owner = ctx.accounts.owner.key();
+    position.input_mint = input_mint;
+    position.output_mint = output_mint;
+    position.amount_per_interval = amount_per_interval;
+    position.interval_secs = interval_secs;
+    position.next_execution_timestamp = Clock::get()?.unix_timestamp;
+    position.min_output_per_interval = min_output_per_interval;
+    position.keeper_tip_lamports = keeper_tip_lamports;
+    position.dca_id = dca_id;
+    position.bump = ctx.bumps.dca_position;
+
+    log!(
+        "DCA opened - Total: {}, Per interval: {}, Interval: {}s, Tranches: {}",
+        total_amount,
+        amount_per_interval,
+        interval_secs,
+        tranches
+    );
+    Ok(())
+}
+
+/// Swap the next due tranche of a DCA schedule through the pool and pay the
+/// keeper its tip. Anyone may crank.
+pub fn execute(ctx: Context<ExecuteDca>) -> Result<()> {
+    ctx.accounts.amm_config.check_not_paused()?;
+    let clock = Clock::get()?;
+    let position = &ctx.accounts.dca_position;
+    require!(
+        clock.unix_timestamp >= position.next_execution_timestamp,
+        AmmError::DcaNotDue
+    );
+
+    let tranche = position
+        .amount_per_interval
+        .min(ctx.accounts.dca_vault.amount);
+    require!(tranche > 0, AmmError::DcaVaultEmpty);
+
+    let pool = &mut ctx.accounts.liquidity_pool.load_mut()?;
+    let input_is_token_a = position.input_mint == pool.token_a_mint;
+    let (input_vault, output_vault, input_mint, output_mint) = if input_is_token_a {
+        (
+            &ctx.accounts.token_a_vault,
+            &ctx.accounts.token_b_vault,
+            &ctx.accounts.token_a_mint,
+            &ctx.accounts.token_b_mint,
+        )
+    } else {
+        (
+            &ctx.accounts.token_b_vault,
+            &ctx.accounts.token_a_vault,
+            &ctx.accounts.token_b_mint,
+            &ctx.accounts.token_a_mint,
+        )
+    };
+
+    let output_amount = pool.apply_keeper_swap(
+        clock.slot,
+        input_is_token_a,
+        input_vault.amount as u128,
+        output_vault.amount as u128,
+        tranche as u128,
+    )?;
+
+    // A short final tranche is held to a proportional minimum
+    if position.min_output_per_interval > 0 {
+        let min_output = math::mul_div_ceil(
+            position.min_output_per_interval as u128,
+            tranche as u128,
+            position.amount_per_interval as u128,
+        )?;
+        require!(
+            output_amount as u128 >= min_output,
+            AmmError::SlippageExceeded
+        );
+    }
+
+    let signer = position.signer();
+    let dca_seeds = signer.seeds();
+    token_interface::transfer_checked(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            TransferChecked {
+                from: ctx.accounts.dca_vault.to_account_info(),
+                mint: input_mint.to_account_info(),
+                to: input_vault.to_account_info(),
+                authority: position.to_account_info(),
+            },
+            &[&dca_seeds[..]],
+        ),
+        tranche,
+        input_mint.decimals,
+    )?;
+
+    let pool_key = ctx.accounts.liquidity_pool.key();
+    let authority_bump = pool.authority_bump;
+    let authority_seeds = &[b"pool_authority", pool_key.as_ref(), &[authority_bump]];
+    token_interface::transfer_checked(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            TransferChecked {
+                from: output_vault.to_account_info(),
+                mint: output_mint.to_account_info(),
+                to: ctx.accounts.owner_output_token_account.to_account_info(),
+                authority: ctx.accounts.pool_authority.to_account_info(),
+            },
+            &[&authority_seeds[..]],
+        ),
+        output_amount,
+        output_mint.decimals,
+    )?;
+
+    ctx.accounts.token_a_vault.reload()?;
+    ctx.accounts.token_b_vault.reload()?;
+    pool.sync_reserves(
+        ctx.accounts.token_a_vault.amount,
+        ctx.accounts.token_b_vault.amount,
+    );
+
+    // The pool's keeper tip, paid in token A on top of the owner's lamports
+    let keeper_tip = pool.keeper_tip;
+    let pool_tip = pay_keeper_tip(
+        pool,
+        &pool_key,
+        keeper_tip,
+        KeeperTipAccounts {
+            pool_authority: &ctx.accounts.pool_authority,
+            token_a_mint: Some(&ctx.accounts.token_a_mint),
+            protocol_fee_vault_a: ctx.accounts.protocol_fee_vault_a.as_deref(),
+            keeper_token_a_account: ctx.accounts.keeper_token_a_account.as_deref(),
+            token_program: Some(&ctx.accounts.token_program),
+        },
+    )?;
+
+    // Tips come out of the lamports escrowed above the position's rent
+    let position_info = ctx.accounts.dca_position.to_account_info();
+    let rent_exempt = Rent::get()?.minimum_balance(position_info.data_len());
+    let tip = ctx
+        .accounts
+        .dca_position
+        .keeper_tip_lamports
+        .min(position_info.lamports().saturating_sub(rent_exempt));
+    if tip > 0 {
+        **position_info.try_borrow_mut_lamports()? -= tip;
+        **ctx.accounts.keeper.try_borrow_mut_lamports()? += tip;
+    }
+
+    let position = &mut ctx.accounts.dca_position;
+    position.next_execution_timestamp = clock
+        .unix_timestamp
+        .checked_add(position.interval_secs)
+        .ok_or(AmmError::MathOverflow)?;
+
+    log!(
+        "DCA tranche executed - Input: {}, Output: {}, Keeper tip: {}, Pool tip: {}",
+        tranche,
+        output_amount,
+        tip,
+        pool_tip
+    );
+    Ok(())
+}
+
+/// Cancel a DCA schedule, returning the unswapped input and unused tips to the owner
+pub fn close(ctx: Context<CloseDca>) -> Result<()> {
+    let remaining = ctx.accounts.dca_vault.amount;
+    let signer = ctx.accounts.dca_position.signer();
+    let dca_seeds = signer.seeds();
+    let signer_seeds = &[&dca_seeds[..]];
+
+    if remaining > 0 {
+        token_interface::transfer_checked(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.dca_vault.to_account_info(),
+                    mint: ctx.accounts.input_mint.to_account_info(),
+                    to: ctx.accounts.owner_input_token_account.to_account_info(),
+                    authority: ctx.accounts.dca_position.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            remaining,
+            ctx.accounts.input_mint.decimals,
+        )?;
+    }
+
+    token_interface::close_account(CpiContext::new_with_signer(
+        ctx.accounts.token_program.to_account_info(),
+        CloseAccount {
+            account: ctx.accounts.dca_vault.to_account_info(),
+            destination: ctx.accounts.owner.to_account_info(),
+            authority: ctx.accounts.dca_position.to_account_info(),
+        },
+        signer_seeds,
+    ))?;
+
+    log!("DCA closed - Returned: {}", remaining);
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(dca_id: u16)]
+pub struct OpenDca<'info> {
+    /// Pool the schedule swaps through
+    pub liquidity_pool: AccountLoader<'info, LiquidityPool>,
+
+    /// Global config, checked for the emergency pause
+    #[account(seeds = [b"amm_config"], bump)]
+    pub amm_config: Box<Account<'info, AmmConfig>>,
+
+    /// New DCA schedule
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + DcaPosition::INIT_SPACE,
+        seeds = [
+            b"dca",
+            liquidity_pool.key().as_ref(),
+            owner.key().as_ref(),
+            &dca_id.to_le_bytes()
+        ],
+        bump,
+    )]
+    pub dca_position: Box<Account<'info, DcaPosition>>,
+
+    /// Token sold each interval
+    pub input_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    /// Token bought each interval
+    pub output_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    /// Escrow of the unswapped input
+    #[account(
+        init,
+        payer = owner,
+        seeds = [b"dca_vault", dca_position.key().as_ref()],
+        bump,
+        token::mint = input_mint,
+        token::authority = dca_position,
+    )]
+    pub dca_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Owner's input token account (source)
+    #[account(
+        mut,
+        constraint = owner_input_token_account.mint == input_mint.key(),
+        constraint = owner_input_token_account.owner == owner.key() @ AmmError::InvalidTokenAccountOwner
+    )]
+    pub owner_input_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Schedule owner, funds the escrow, the keeper tips and the accounts
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ExecuteDca<'info> {
+    /// DCA schedule being cranked
+    #[account(
+        mut,
+        seeds = [
+            b"dca",
+            liquidity_pool.key().as_ref(),
+            dca_position.owner.as_ref(),
+            &dca_position.dca_id.to_le_bytes()
+        ],
+        bump = dca_position.bump,
+    )]
+    pub dca_position: Box<Account<'info, DcaPosition>>,
+
+    /// Escrow of the unswapped input
+    #[account(
+        mut,
+        seeds = [b"dca_vault", dca_position.key().as_ref()],
+        bump
+    )]
+    pub dca_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Pool the schedule swaps through
+    #[account(mut, address = dca_position.liquidity_pool)]
+    pub liquidity_pool: AccountLoader<'info, LiquidityPool>,
+
+    /// Global config, checked for the emergency pause
+    #[account(seeds = [b"amm_config"], bump)]
+    pub amm_config: Box<Account<'info, AmmConfig>>,
+
+    /// Pool authority PDA
+    #[account(
+        seeds = [b"pool_authority", liquidity_pool.key().as_ref()],
+        bump = liquidity_pool.load()?.authority_bump
+    )]
+    pub pool_authority: SystemAccount<'info>,
+
+    /// Token A mint
+    #[account(address = liquidity_pool.load()?.token_a_mint)]
+    pub token_a_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    /// Token B mint
+    #[account(address = liquidity_pool.load()?.token_b_mint)]
+    pub token_b_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    /// Token A vault
+    #[account(
+        mut,
+        seeds = [b"token_a_vault", liquidity_pool.key().as_ref()],
+        bump = liquidity_pool.load()?.token_a_vault_bump
+    )]
+    pub token_a_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Token B vault
+    #[account(
+        mut,
+        seeds = [b"token_b_vault", liquidity_pool.key().as_ref()],
+        bump = liquidity_pool.load()?.token_b_vault_bump
+    )]
+    pub token_b_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Owner's output token account (destination)
+    #[account(
+        mut,
+        constraint = owner_output_token_account.mint == dca_position.output_mint,
+        constraint = owner_output_token_account.owner == dca_position.owner @ AmmError::InvalidTokenAccountOwner
+    )]
+    pub owner_output_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Keeper cranking the schedule, receives the tip
+    #[account(mut)]
+    pub keeper: Signer<'info>,
+
+    /// Protocol fee vault for token A, holds the pool's keeper tip budget;
+    /// pass it with the keeper's token A account to collect the pool tip
+    #[account(
+        mut,
+        seeds = [
+            b"protocol_fee_vault",
+            liquidity_pool.key().as_ref(),
+            token_a_mint.key().as_ref()
+        ],
+        bump,
+    )]
+    pub protocol_fee_vault_a: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    /// Keeper's token A account receiving the pool tip
+    #[account(mut, constraint = keeper_token_a_account.mint == token_a_mint.key())]
+    pub keeper_token_a_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct CloseDca<'info> {
+    /// DCA schedule being cancelled
+    #[account(
+        mut,
+        close = owner,
+        has_one = owner @ AmmError::Unauthorized,
+        seeds = [
+            b"dca",
+            dca_position.liquidity_pool.as_ref(),
+            owner.key().as_ref(),
+            &dca_position.dca_id.to_le_bytes()
+        ],
+        bump = dca_position.bump,
+    )]
+    pub dca_position: Box<Account<'info, DcaPosition>>,
+
+    /// Escrow of the unswapped input
+    #[account(
+        mut,
+        seeds = [b"dca_vault", dca_position.key().as_ref()],
+        bump
+    )]
+    pub dca_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Token sold by the schedule
+    #[account(address = dca_position.input_mint)]
+    pub input_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    /// Owner's input token account (destination)
+    #[account(
+        mut,
+        constraint = owner_input_token_account.mint == input_mint.key(),
+        constraint = owner_input_token_account.owner == owner.key() @ AmmError::InvalidTokenAccountOwner
+    )]
+    pub owner_input_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Schedule owner, receives the remaining input, tips and rent
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
@@ -0,0 +1,162 @@
+use crate::errors::AmmError;
+use crate::events::PoolDonated;
+use crate::state::{AmmConfig, LiquidityPool};
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{self, Mint, TokenAccount, TokenInterface, TransferChecked};
+
+/// Donate tokens into the pool's reserves without minting LP tokens. The
+/// donation accrues to current LPs, raising the liquidity behind each LP
+/// token. Unlike a plain transfer into a vault, it is counted in the tracked
+/// reserves right away and so cannot be skimmed.
+pub fn process(ctx: Context<DonateToPool>, token_a_amount: u64, token_b_amount: u64) -> Result<()> {
+    ctx.accounts.amm_config.check_not_paused()?;
+    require!(
+        token_a_amount > 0 || token_b_amount > 0,
+        AmmError::InvalidDonationAmount
+    );
+    {
+        // An empty pool would hand the donation to its first depositor
+        let pool = ctx.accounts.liquidity_pool.load()?;
+        require!(
+            pool.total_lp_tokens_issued > 0 && pool.reserve_a > 0 && pool.reserve_b > 0,
+            AmmError::PoolHasNoLiquidity
+        );
+    }
+
+    let vault_a_before = ctx.accounts.token_a_vault.amount;
+    let vault_b_before = ctx.accounts.token_b_vault.amount;
+
+    let accounts = &ctx.accounts;
+    for (source, mint, vault, token_program, amount) in [
+        (
+            &accounts.donor_token_a_account,
+            &accounts.token_a_mint,
+            &accounts.token_a_vault,
+            &accounts.token_a_program,
+            token_a_amount,
+        ),
+        (
+            &accounts.donor_token_b_account,
+            &accounts.token_b_mint,
+            &accounts.token_b_vault,
+            &accounts.token_b_program,
+            token_b_amount,
+        ),
+    ] {
+        if amount > 0 {
+            token_interface::transfer_checked(
+                CpiContext::new(
+                    token_program.to_account_info(),
+                    TransferChecked {
+                        from: source.to_account_info(),
+                        mint: mint.to_account_info(),
+                        to: vault.to_account_info(),
+                        authority: accounts.donor.to_account_info(),
+                    },
+                ),
+                amount,
+                mint.decimals,
+            )?;
+        }
+    }
+
+    // Count what the vaults received, net of any transfer fee, and leave
+    // untracked balances from earlier plain transfers to skim
+    ctx.accounts.token_a_vault.reload()?;
+    ctx.accounts.token_b_vault.reload()?;
+    let received_a = ctx.accounts.token_a_vault.amount - vault_a_before;
+    let received_b = ctx.accounts.token_b_vault.amount - vault_b_before;
+
+    let pool_key = ctx.accounts.liquidity_pool.key();
+    let pool = &mut ctx.accounts.liquidity_pool.load_mut()?;
+    let reserve_a = pool
+        .reserve_a
+        .checked_add(received_a)
+        .ok_or(AmmError::MathOverflow)?;
+    let reserve_b = pool
+        .reserve_b
+        .checked_add(received_b)
+        .ok_or(AmmError::MathOverflow)?;
+    pool.sync_reserves(reserve_a, reserve_b);
+
+    log!(
+        "Donated to pool - Token A: {}, Token B: {}, Reserves: {} / {}",
+        received_a,
+        received_b,
+        reserve_a,
+        reserve_b
+    );
+
+    emit!(PoolDonated {
+        liquidity_pool: pool_key,
+        event_seq: pool.next_event_seq(),
+        donor: ctx.accounts.donor.key(),
+        token_a_amount: received_a,
+        token_b_amount: received_b,
+        fee_growth_per_lp: pool.fee_growth_per_lp,
+    });
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct DonateToPool<'info> {
+    /// Pool state account
+    #[account(mut)]
+    pub liquidity_pool: AccountLoader<'info, LiquidityPool>,
+
+    /// Global config, checked for the emergency pause
+    #[account(seeds = [b"amm_config"], bump)]
+    pub amm_config: Box<Account<'info, AmmConfig>>,
+
+    /// Token A mint
+    #[account(address = liquidity_pool.load()?.token_a_mint)]
+    pub token_a_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    /// Token B mint
+    #[account(address = liquidity_pool.load()?.token_b_mint)]
+    pub token_b_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    /// Token A vault
+    #[account(
+        mut,
+        seeds = [b"token_a_vault", liquidity_pool.key().as_ref()],
+        bump = liquidity_pool.load()?.token_a_vault_bump
+    )]
+    pub token_a_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Token B vault
+    #[account(
+        mut,
+        seeds = [b"token_b_vault", liquidity_pool.key().as_ref()],
+        bump = liquidity_pool.load()?.token_b_vault_bump
+    )]
+    pub token_b_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Donor's token A account
+    #[account(
+        mut,
+        constraint = donor_token_a_account.mint == token_a_mint.key(),
+        constraint = donor_token_a_account.owner == donor.key() @ AmmError::InvalidTokenAccountOwner
+    )]
+    pub donor_token_a_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Donor's token B account
+    #[account(
+        mut,
+        constraint = donor_token_b_account.mint == token_b_mint.key(),
+        constraint = donor_token_b_account.owner == donor.key() @ AmmError::InvalidTokenAccountOwner
+    )]
+    pub donor_token_b_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Anyone may donate
+    pub donor: Signer<'info>,
+
+    /// Token program of token A
+    #[account(address = *token_a_mint.to_account_info().owner @ AmmError::InvalidTokenProgram)]
+    pub token_a_program: Interface<'info, TokenInterface>,
+
+    /// Token program of token B
+    #[account(address = *token_b_mint.to_account_info().owner @ AmmError::InvalidTokenProgram)]
+    pub token_b_program: Interface<'info, TokenInterface>,
+}
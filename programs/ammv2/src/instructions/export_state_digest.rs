@@ -0,0 +1,95 @@
+use crate::errors::AmmError;
+use crate::events::PoolStateDigest;
+use crate::state::{LiquidityPool, PriceOracle};
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::hash::hashv;
+use anchor_spl::token_interface::{Mint, TokenAccount};
+
+/// Emit a digest of the full pool state for the current epoch
+pub fn process(ctx: Context<ExportStateDigest>) -> Result<()> {
+    let clock = Clock::get()?;
+    let pool = &mut ctx.accounts.liquidity_pool.load_mut()?;
+
+    // One checkpoint per epoch keeps the digest stream deterministic
+    require!(
+        clock.epoch >= pool.next_digest_epoch,
+        AmmError::DigestAlreadyExported
+    );
+    pool.next_digest_epoch = clock.epoch.checked_add(1).ok_or(AmmError::MathOverflow)?;
+    // Taken before hashing so the digest covers its own sequence number
+    let event_seq = pool.next_event_seq();
+
+    let reserve_a = ctx.accounts.token_a_vault.amount;
+    let reserve_b = ctx.accounts.token_b_vault.amount;
+    let lp_supply = ctx.accounts.lp_token_mint.supply;
+
+    let pool_data = bytemuck::bytes_of::<LiquidityPool>(pool);
+
+    // Latest oracle observation, when the pool has an oracle
+    let mut oracle_tail = Vec::new();
+    if pool.oracle != Pubkey::default() {
+        let oracle = ctx
+            .accounts
+            .oracle
+            .as_ref()
+            .ok_or(AmmError::OracleAccountRequired)?;
+        if let Some(latest) = oracle.latest() {
+            latest.serialize(&mut oracle_tail)?;
+        }
+    }
+
+    let digest = hashv(&[
+        pool_data,
+        &reserve_a.to_le_bytes(),
+        &reserve_b.to_le_bytes(),
+        &lp_supply.to_le_bytes(),
+        &oracle_tail,
+    ])
+    .to_bytes();
+
+    emit!(PoolStateDigest {
+        liquidity_pool: ctx.accounts.liquidity_pool.key(),
+        event_seq,
+        epoch: clock.epoch,
+        slot: clock.slot,
+        digest,
+        reserve_a,
+        reserve_b,
+        lp_supply,
+    });
+
+    log!("State digest exported for epoch {}", clock.epoch);
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct ExportStateDigest<'info> {
+    /// Pool state account
+    #[account(mut)]
+    pub liquidity_pool: AccountLoader<'info, LiquidityPool>,
+
+    /// Token A vault
+    #[account(
+        seeds = [b"token_a_vault", liquidity_pool.key().as_ref()],
+        bump = liquidity_pool.load()?.token_a_vault_bump
+    )]
+    pub token_a_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Token B vault
+    #[account(
+        seeds = [b"token_b_vault", liquidity_pool.key().as_ref()],
+        bump = liquidity_pool.load()?.token_b_vault_bump
+    )]
+    pub token_b_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// LP token mint
+    #[account(
+        seeds = [b"lp_token_mint", liquidity_pool.key().as_ref()],
+        bump = liquidity_pool.load()?.lp_token_mint_bump
+    )]
+    pub lp_token_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    /// Pool price oracle, required once the pool has one
+    #[account(address = liquidity_pool.load()?.oracle @ AmmError::OracleAccountRequired)]
+    pub oracle: Option<Box<Account<'info, PriceOracle>>>,
+}
@@ -0,0 +1,449 @@
+use crate::errors::AmmError;
+use crate::state::{AmmConfig, Farm, FarmReward, LiquidityPool, StakePosition, FARM_LOCK_TIERS};
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{self, Mint, TokenAccount, TokenInterface, TransferChecked},
+};
+
+/// Create the LP staking farm of a pool with its first reward stream.
+/// Rewards are funded by transferring reward tokens into the stream's vault.
+pub fn initialize(ctx: Context<InitializeFarm>, reward_per_second: u64) -> Result<()> {
+    let farm = &mut ctx.accounts.farm;
+    farm.liquidity_pool = ctx.accounts.liquidity_pool.key();
+    farm.last_update_timestamp = Clock::get()?.unix_timestamp;
+    farm.total_staked = 0;
+    farm.total_boosted = 0;
+    farm.bump = ctx.bumps.farm;
+    farm.rewards = Default::default();
+    farm.rewards[0] = FarmReward {
+        reward_mint: ctx.accounts.reward_mint.key(),
+        reward_per_second,
+        reward_per_share: 0,
+    };
+
+    log!(
+        "Farm initialized - Reward mint: {}, Rate: {} per second",
+        ctx.accounts.reward_mint.key(),
+        reward_per_second
+    );
+    Ok(())
+}
+
+/// Add another reward stream to the farm, e.g. a partner co-incentive
+pub fn add_reward(ctx: Context<AddFarmReward>, reward_per_second: u64) -> Result<()> {
+    let reward_mint = ctx.accounts.reward_mint.key();
+    let farm = &mut ctx.accounts.farm;
+    farm.accrue(Clock::get()?.unix_timestamp)?;
+
+    let slot = farm
+        .rewards
+        .iter_mut()
+        .find(|reward| !reward.is_active())
+        .ok_or(AmmError::FarmRewardSlotsFull)?;
+    *slot = FarmReward {
+        reward_mint,
+        reward_per_second,
+        reward_per_share: 0,
+    };
+
+    log!(
+        "Farm reward added - Reward mint: {}, Rate: {} per second",
+        reward_mint,
+        reward_per_second
+    );
+    Ok(())
+}
+
+/// Change one reward stream's emission rate; emissions up to now accrue at the old rate
+pub fn set_emission_rate(
+    ctx: Context<UpdateFarm>,
+    reward_mint: Pubkey,
+    reward_per_second: u64,
+) -> Result<()> {
+    let farm = &mut ctx.accounts.farm;
+    let index = farm.reward_index(&reward_mint)?;
+    farm.accrue(Clock::get()?.unix_timestamp)?;
+    farm.rewards[index].reward_per_second = reward_per_second;
+
+    log!(
+        "Farm emission rate for {} set to {} per second",
+        reward_mint,
+        reward_per_second
+    );
+    Ok(())
+}
+
+/// Stake LP tokens in the farm, optionally locking the whole position for a
+/// boosted share of rewards. A lock can be extended but never shortened.
+pub fn stake(ctx: Context<ManageStake>, amount: u64, lock_tier: u8) -> Result<()> {
+    ctx.accounts.amm_config.check_not_paused()?;
+    require!(amount > 0, AmmError::InvalidStakeAmount);
+    require!(
+        ctx.accounts.user_lp_token_account.amount >= amount,
+        AmmError::InsufficientLpTokens
+    );
+    let (lock_secs, tier_boost_bps) = *FARM_LOCK_TIERS
+        .get(lock_tier as usize)
+        .ok_or(AmmError::InvalidLockTier)?;
+
+    let now = Clock::get()?.unix_timestamp;
+    let farm = &mut ctx.accounts.farm;
+    farm.accrue(now)?;
+
+    let position = &mut ctx.accounts.stake_position;
+    position.farm = farm.key();
+    position.owner = ctx.accounts.owner.key();
+    position.settle(farm)?;
+
+    let boost_bps = position.effective_boost_bps(now).max(tier_boost_bps);
+    position.lock_end_timestamp = position
+        .lock_end_timestamp
+        .max(now.checked_add(lock_secs).ok_or(AmmError::MathOverflow)?);
+
+    token_interface::transfer_checked(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            TransferChecked {
+                from: ctx.accounts.user_lp_token_account.to_account_info(),
+                mint: ctx.accounts.lp_token_mint.to_account_info(),
+                to: ctx.accounts.farm_lp_vault.to_account_info(),
+                authority: ctx.accounts.owner.to_account_info(),
+            },
+        ),
+        amount,
+        ctx.accounts.lp_token_mint.decimals,
+    )?;
+
+    let staked = position
+        .amount
+        .checked_add(amount)
+        .ok_or(AmmError::MathOverflow)?;
+    position.update_stake(farm, staked, boost_bps)?;
+
+    log!(
+        "Staked {} LP tokens, position now {} - Boost: {} bps, Locked until: {}",
+        amount,
+        staked,
+        boost_bps,
+        position.lock_end_timestamp
+    );
+    Ok(())
+}
+
+/// Unstake LP tokens from the farm. Rewards earned so far stay claimable.
+pub fn unstake(ctx: Context<ManageStake>, amount: u64) -> Result<()> {
+    require!(amount > 0, AmmError::InvalidStakeAmount);
+    require!(
+        ctx.accounts.stake_position.amount >= amount,
+        AmmError::InsufficientStake
+    );
+
+    let now = Clock::get()?.unix_timestamp;
+    require!(
+        now >= ctx.accounts.stake_position.lock_end_timestamp,
+        AmmError::StakeLocked
+    );
+
+    let farm = &mut ctx.accounts.farm;
+    farm.accrue(now)?;
+
+    let position = &mut ctx.accounts.stake_position;
+    position.settle(farm)?;
+
+    let signer = farm.signer();
+    let farm_seeds = signer.seeds();
+    let signer_seeds = &[&farm_seeds[..]];
+
+    token_interface::transfer_checked(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            TransferChecked {
+                from: ctx.accounts.farm_lp_vault.to_account_info(),
+                mint: ctx.accounts.lp_token_mint.to_account_info(),
+                to: ctx.accounts.user_lp_token_account.to_account_info(),
+                authority: farm.to_account_info(),
+            },
+            signer_seeds,
+        ),
+        amount,
+        ctx.accounts.lp_token_mint.decimals,
+    )?;
+
+    let staked = position.amount - amount;
+    let boost_bps = position.effective_boost_bps(now);
+    position.update_stake(farm, staked, boost_bps)?;
+
+    log!("Unstaked {} LP tokens, position now {}", amount, staked);
+    Ok(())
+}
+
+/// Pay out the position's earned rewards of one stream, up to what its vault holds
+pub fn claim(ctx: Context<ClaimRewards>) -> Result<()> {
+    let farm = &mut ctx.accounts.farm;
+    let index = farm.reward_index(&ctx.accounts.reward_mint.key())?;
+    let now = Clock::get()?.unix_timestamp;
+    farm.accrue(now)?;
+
+    let position = &mut ctx.accounts.stake_position;
+    position.settle(farm)?;
+    // Drop an expired lock's boost so it stops diluting other stakers
+    let (staked, boost_bps) = (position.amount, position.effective_boost_bps(now));
+    position.update_stake(farm, staked, boost_bps)?;
+
+    // An underfunded vault pays what it can; the rest stays pending
+    let payout = position.pending_rewards[index].min(ctx.accounts.farm_reward_vault.amount);
+    if payout > 0 {
+        let signer = farm.signer();
+        let farm_seeds = signer.seeds();
+        let signer_seeds = &[&farm_seeds[..]];
+
+        token_interface::transfer_checked(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.farm_reward_vault.to_account_info(),
+                    mint: ctx.accounts.reward_mint.to_account_info(),
+                    to: ctx.accounts.user_reward_token_account.to_account_info(),
+                    authority: farm.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            payout,
+            ctx.accounts.reward_mint.decimals,
+        )?;
+        position.pending_rewards[index] -= payout;
+    }
+
+    log!(
+        "Claimed {} reward tokens, {} still pending",
+        payout,
+        position.pending_rewards[index]
+    );
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct InitializeFarm<'info> {
+    /// Pool whose LP tokens the farm accepts
+    #[account(has_one = admin @ AmmError::Unauthorized)]
+    pub liquidity_pool: AccountLoader<'info, LiquidityPool>,
+
+    /// Farm state, one per pool
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + Farm::INIT_SPACE,
+        seeds = [b"farm", liquidity_pool.key().as_ref()],
+        bump
+    )]
+    pub farm: Box<Account<'info, Farm>>,
+
+    /// Pool LP token mint
+    #[account(
+        seeds = [b"lp_token_mint", liquidity_pool.key().as_ref()],
+        bump = liquidity_pool.load()?.lp_token_mint_bump
+    )]
+    pub lp_token_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    /// Token paid out by the first reward stream
+    pub reward_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    /// Vault holding the staked LP tokens
+    #[account(
+        init,
+        payer = admin,
+        seeds = [b"farm_lp_vault", farm.key().as_ref()],
+        bump,
+        token::mint = lp_token_mint,
+        token::authority = farm,
+    )]
+    pub farm_lp_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Vault holding the first stream's rewards
+    #[account(
+        init,
+        payer = admin,
+        seeds = [
+            b"farm_reward_vault",
+            farm.key().as_ref(),
+            reward_mint.key().as_ref()
+        ],
+        bump,
+        token::mint = reward_mint,
+        token::authority = farm,
+    )]
+    pub farm_reward_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Pool admin, pays for the accounts
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct AddFarmReward<'info> {
+    /// Pool the farm belongs to
+    #[account(has_one = admin @ AmmError::Unauthorized)]
+    pub liquidity_pool: AccountLoader<'info, LiquidityPool>,
+
+    /// Farm state
+    #[account(
+        mut,
+        seeds = [b"farm", liquidity_pool.key().as_ref()],
+        bump = farm.bump
+    )]
+    pub farm: Box<Account<'info, Farm>>,
+
+    /// Token paid out by the new stream
+    pub reward_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    /// Vault holding the new stream's rewards
+    #[account(
+        init,
+        payer = admin,
+        seeds = [
+            b"farm_reward_vault",
+            farm.key().as_ref(),
+            reward_mint.key().as_ref()
+        ],
+        bump,
+        token::mint = reward_mint,
+        token::authority = farm,
+    )]
+    pub farm_reward_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Pool admin, pays for the vault
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateFarm<'info> {
+    /// Pool the farm belongs to
+    #[account(has_one = admin @ AmmError::Unauthorized)]
+    pub liquidity_pool: AccountLoader<'info, LiquidityPool>,
+
+    /// Farm state
+    #[account(
+        mut,
+        seeds = [b"farm", liquidity_pool.key().as_ref()],
+        bump = farm.bump
+    )]
+    pub farm: Box<Account<'info, Farm>>,
+
+    /// Pool admin
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ManageStake<'info> {
+    /// Global config, checked for the emergency pause
+    #[account(seeds = [b"amm_config"], bump)]
+    pub amm_config: Box<Account<'info, AmmConfig>>,
+
+    /// Farm state
+    #[account(
+        mut,
+        seeds = [b"farm", farm.liquidity_pool.as_ref()],
+        bump = farm.bump
+    )]
+    pub farm: Box<Account<'info, Farm>>,
+
+    /// The owner's stake in the farm, created on first stake
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = 8 + StakePosition::INIT_SPACE,
+        seeds = [b"stake_position", farm.key().as_ref(), owner.key().as_ref()],
+        bump
+    )]
+    pub stake_position: Box<Account<'info, StakePosition>>,
+
+    /// Pool LP token mint
+    #[account(
+        seeds = [b"lp_token_mint", farm.liquidity_pool.as_ref()],
+        bump
+    )]
+    pub lp_token_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    /// Vault holding the staked LP tokens
+    #[account(
+        mut,
+        seeds = [b"farm_lp_vault", farm.key().as_ref()],
+        bump
+    )]
+    pub farm_lp_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Owner's LP token account
+    #[account(
+        mut,
+        constraint = user_lp_token_account.mint == lp_token_mint.key(),
+        constraint = user_lp_token_account.owner == owner.key() @ AmmError::InvalidTokenAccountOwner
+    )]
+    pub user_lp_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Staker
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimRewards<'info> {
+    /// Farm state
+    #[account(
+        mut,
+        seeds = [b"farm", farm.liquidity_pool.as_ref()],
+        bump = farm.bump
+    )]
+    pub farm: Box<Account<'info, Farm>>,
+
+    /// The owner's stake in the farm
+    #[account(
+        mut,
+        seeds = [b"stake_position", farm.key().as_ref(), owner.key().as_ref()],
+        bump
+    )]
+    pub stake_position: Box<Account<'info, StakePosition>>,
+
+    /// Token paid out by the claimed stream
+    pub reward_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    /// Vault holding the claimed stream's rewards
+    #[account(
+        mut,
+        seeds = [
+            b"farm_reward_vault",
+            farm.key().as_ref(),
+            reward_mint.key().as_ref()
+        ],
+        bump
+    )]
+    pub farm_reward_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Staker
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+
+    /// Owner's reward token account, created if it does not exist yet
+    #[account(
+        init_if_needed,
+        payer = owner,
+        associated_token::mint = reward_mint,
+        associated_token::authority = owner,
+        associated_token::token_program = token_program,
+    )]
+    pub user_reward_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+}
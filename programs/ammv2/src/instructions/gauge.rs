@@ -0,0 +1,472 @@
+use crate::errors::AmmError;
+use crate::math;
+use crate::state::{AmmConfig, Farm, Gauge, GaugeController, GaugeVoter};
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{self, Mint, TokenAccount, TokenInterface, TransferChecked};
+
+/// Create the gauge controller that splits `emission_per_second` of
+/// `reward_mint` across farms by governance token votes
+pub fn initialize_controller(
+    ctx: Context<InitializeGaugeController>,
+    emission_per_second: u64,
+    epoch_duration_secs: i64,
+) -> Result<()> {
+    require!(epoch_duration_secs > 0, AmmError::InvalidPoolConfig);
+
+    let controller = &mut ctx.accounts.gauge_controller;
+    controller.governance_mint = ctx.accounts.governance_mint.key();
+    controller.reward_mint = ctx.accounts.reward_mint.key();
+    controller.emission_per_second = emission_per_second;
+    controller.epoch_duration_secs = epoch_duration_secs;
+    controller.epoch = 0;
+    controller.epoch_start_timestamp = Clock::get()?.unix_timestamp;
+    controller.epoch_total_votes = 0;
+    controller.previous_epoch_total_votes = 0;
+
+    log!(
+        "Gauge controller initialized - Emission: {} per second, Epoch: {} seconds",
+        emission_per_second,
+        epoch_duration_secs
+    );
+    Ok(())
+}
+
+/// Register a farm for gauge emissions. The farm needs a reward stream paying
+/// the controller's reward mint.
+pub fn create_gauge(ctx: Context<CreateGauge>) -> Result<()> {
+    let controller = &ctx.accounts.gauge_controller;
+    ctx.accounts.farm.reward_index(&controller.reward_mint)?;
+
+    let gauge = &mut ctx.accounts.gauge;
+    gauge.farm = ctx.accounts.farm.key();
+    gauge.votes_epoch = controller.epoch;
+    gauge.votes = 0;
+    gauge.previous_votes = 0;
+    gauge.checkpointed_epoch = controller.epoch;
+
+    log!("Gauge created for farm {}", gauge.farm);
+    Ok(())
+}
+
+/// Escrow `amount` governance tokens in the voter's vault, adding them to its
+/// voting power from the current epoch on
+pub fn lock_votes(ctx: Context<LockGaugeVotes>, amount: u64) -> Result<()> {
+    require!(
+        ctx.accounts.voter_token_account.amount >= amount,
+        AmmError::InsufficientBalance
+    );
+
+    token_interface::transfer_checked(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            TransferChecked {
+                from: ctx.accounts.voter_token_account.to_account_info(),
+                mint: ctx.accounts.governance_mint.to_account_info(),
+                to: ctx.accounts.gauge_voter_vault.to_account_info(),
+                authority: ctx.accounts.voter.to_account_info(),
+            },
+        ),
+        amount,
+        ctx.accounts.governance_mint.decimals,
+    )?;
+
+    // Credit what reached the vault, in case the mint charges a transfer fee
+    let vault_balance = ctx.accounts.gauge_voter_vault.amount;
+    ctx.accounts.gauge_voter_vault.reload()?;
+    let received = ctx
+        .accounts
+        .gauge_voter_vault
+        .amount
+        .checked_sub(vault_balance)
+        .ok_or(AmmError::MathOverflow)?;
+
+    let voter = &mut ctx.accounts.gauge_voter;
+    if voter.voter == Pubkey::default() {
+        voter.voter = ctx.accounts.voter.key();
+        voter.epoch = ctx.accounts.gauge_controller.epoch;
+        voter.bump = ctx.bumps.gauge_voter;
+    }
+    voter.locked = voter
+        .locked
+        .checked_add(received)
+        .ok_or(AmmError::MathOverflow)?;
+
+    log!(
+        "Locked {} voting tokens, voting power now {}",
+        received,
+        voter.locked
+    );
+    Ok(())
+}
+
+/// Return `amount` escrowed governance tokens to the voter. Tokens that voted
+/// in the current epoch stay locked until it ends.
+pub fn unlock_votes(ctx: Context<UnlockGaugeVotes>, amount: u64) -> Result<()> {
+    let voter = &mut ctx.accounts.gauge_voter;
+    require!(
+        !voter.voted_in(ctx.accounts.gauge_controller.epoch),
+        AmmError::GaugeVotesLocked
+    );
+    voter.locked = voter
+        .locked
+        .checked_sub(amount)
+        .ok_or(AmmError::InsufficientBalance)?;
+
+    let signer = voter.signer();
+    let voter_seeds = signer.seeds();
+    token_interface::transfer_checked(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            TransferChecked {
+                from: ctx.accounts.gauge_voter_vault.to_account_info(),
+                mint: ctx.accounts.governance_mint.to_account_info(),
+                to: ctx.accounts.voter_token_account.to_account_info(),
+                authority: ctx.accounts.gauge_voter.to_account_info(),
+            },
+            &[&voter_seeds[..]],
+        ),
+        amount,
+        ctx.accounts.governance_mint.decimals,
+    )?;
+
+    log!(
+        "Unlocked {} voting tokens, voting power now {}",
+        amount,
+        ctx.accounts.gauge_voter.locked
+    );
+    Ok(())
+}
+
+/// Vote for a gauge in the current epoch. A wallet's votes across all gauges
+/// are capped by the governance tokens it has locked, which then stay locked
+/// until the epoch ends.
+pub fn vote(ctx: Context<VoteGauge>, votes: u64) -> Result<()> {
+    let controller = &mut ctx.accounts.gauge_controller;
+    let voter = &mut ctx.accounts.gauge_voter;
+    if voter.epoch != controller.epoch {
+        voter.epoch = controller.epoch;
+        voter.used_votes = 0;
+    }
+
+    voter.used_votes = voter
+        .used_votes
+        .checked_add(votes)
+        .ok_or(AmmError::MathOverflow)?;
+    require!(
+        voter.used_votes <= voter.locked,
+        AmmError::InsufficientVotingPower
+    );
+
+    let gauge = &mut ctx.accounts.gauge;
+    gauge.roll(controller.epoch);
+    gauge.votes = gauge
+        .votes
+        .checked_add(votes)
+        .ok_or(AmmError::MathOverflow)?;
+    controller.epoch_total_votes = controller
+        .epoch_total_votes
+        .checked_add(votes)
+        .ok_or(AmmError::MathOverflow)?;
+
+    log!(
+        "Voted {} for gauge {} in epoch {}",
+        votes,
+        gauge.key(),
+        controller.epoch
+    );
+    Ok(())
+}
+
+/// Close the current voting epoch once it has run its full duration
+pub fn advance_epoch(ctx: Context<AdvanceGaugeEpoch>) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    let controller = &mut ctx.accounts.gauge_controller;
+    require!(controller.epoch_ended(now), AmmError::GaugeEpochNotEnded);
+
+    controller.previous_epoch_total_votes = controller.epoch_total_votes;
+    controller.epoch_total_votes = 0;
+    controller.epoch = controller
+        .epoch
+        .checked_add(1)
+        .ok_or(AmmError::MathOverflow)?;
+    controller.epoch_start_timestamp = now;
+
+    log!(
+        "Gauge epoch {} started, previous epoch votes: {}",
+        controller.epoch,
+        controller.previous_epoch_total_votes
+    );
+    Ok(())
+}
+
+/// Apply the last epoch's vote share to the gauge's farm emission rate
+pub fn checkpoint(ctx: Context<CheckpointGauge>) -> Result<()> {
+    let controller = &ctx.accounts.gauge_controller;
+    let gauge = &mut ctx.accounts.gauge;
+    require!(
+        gauge.checkpointed_epoch < controller.epoch,
+        AmmError::GaugeAlreadyCheckpointed
+    );
+    gauge.roll(controller.epoch);
+
+    let rate = if controller.previous_epoch_total_votes == 0 {
+        0
+    } else {
+        math::to_u64(math::mul_div_floor(
+            controller.emission_per_second as u128,
+            gauge.previous_votes as u128,
+            controller.previous_epoch_total_votes as u128,
+        )?)?
+    };
+
+    let farm = &mut ctx.accounts.farm;
+    let index = farm.reward_index(&controller.reward_mint)?;
+    farm.accrue(Clock::get()?.unix_timestamp)?;
+    farm.rewards[index].reward_per_second = rate;
+    gauge.checkpointed_epoch = controller.epoch;
+
+    log!(
+        "Gauge checkpointed for epoch {} - Votes: {}, Rate: {} per second",
+        controller.epoch,
+        gauge.previous_votes,
+        rate
+    );
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct InitializeGaugeController<'info> {
+    /// Global config; its authority sets up gauges
+    #[account(
+        seeds = [b"amm_config"],
+        bump,
+        has_one = authority @ AmmError::Unauthorized,
+    )]
+    pub amm_config: Box<Account<'info, AmmConfig>>,
+
+    /// Gauge controller singleton
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + GaugeController::INIT_SPACE,
+        seeds = [b"gauge_controller"],
+        bump,
+    )]
+    pub gauge_controller: Box<Account<'info, GaugeController>>,
+
+    /// Governance/escrow token used for voting
+    pub governance_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    /// Token emitted through gauge-directed farm streams
+    pub reward_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    /// Config authority, pays for the controller
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CreateGauge<'info> {
+    /// Global config; its authority decides which farms get gauges
+    #[account(
+        seeds = [b"amm_config"],
+        bump,
+        has_one = authority @ AmmError::Unauthorized,
+    )]
+    pub amm_config: Box<Account<'info, AmmConfig>>,
+
+    /// Gauge controller singleton
+    #[account(
+        seeds = [b"gauge_controller"],
+        bump,
+    )]
+    pub gauge_controller: Box<Account<'info, GaugeController>>,
+
+    /// Farm receiving gauge emissions
+    #[account(
+        seeds = [b"farm", farm.liquidity_pool.as_ref()],
+        bump = farm.bump
+    )]
+    pub farm: Box<Account<'info, Farm>>,
+
+    /// Gauge of the farm
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + Gauge::INIT_SPACE,
+        seeds = [b"gauge", farm.key().as_ref()],
+        bump,
+    )]
+    pub gauge: Box<Account<'info, Gauge>>,
+
+    /// Config authority, pays for the gauge
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct VoteGauge<'info> {
+    /// Gauge controller singleton
+    #[account(
+        mut,
+        seeds = [b"gauge_controller"],
+        bump,
+    )]
+    pub gauge_controller: Box<Account<'info, GaugeController>>,
+
+    /// Gauge voted for
+    #[account(
+        mut,
+        seeds = [b"gauge", gauge.farm.as_ref()],
+        bump,
+    )]
+    pub gauge: Box<Account<'info, Gauge>>,
+
+    /// The voter's locked tokens and spent voting power
+    #[account(
+        mut,
+        seeds = [b"gauge_voter", voter.key().as_ref()],
+        bump = gauge_voter.bump,
+        has_one = voter @ AmmError::Unauthorized,
+    )]
+    pub gauge_voter: Box<Account<'info, GaugeVoter>>,
+
+    /// Voter
+    pub voter: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct LockGaugeVotes<'info> {
+    /// Gauge controller singleton
+    #[account(
+        seeds = [b"gauge_controller"],
+        bump,
+    )]
+    pub gauge_controller: Box<Account<'info, GaugeController>>,
+
+    /// The voter's locked tokens and spent voting power, created on first lock
+    #[account(
+        init_if_needed,
+        payer = voter,
+        space = 8 + GaugeVoter::INIT_SPACE,
+        seeds = [b"gauge_voter", voter.key().as_ref()],
+        bump,
+    )]
+    pub gauge_voter: Box<Account<'info, GaugeVoter>>,
+
+    /// Escrow of the voter's locked governance tokens
+    #[account(
+        init_if_needed,
+        payer = voter,
+        seeds = [b"gauge_voter_vault", voter.key().as_ref()],
+        bump,
+        token::mint = governance_mint,
+        token::authority = gauge_voter,
+        token::token_program = token_program,
+    )]
+    pub gauge_voter_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Governance token mint
+    #[account(address = gauge_controller.governance_mint)]
+    pub governance_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    /// Voter's governance token account (source)
+    #[account(
+        mut,
+        constraint = voter_token_account.mint == governance_mint.key(),
+        constraint = voter_token_account.owner == voter.key() @ AmmError::InvalidTokenAccountOwner
+    )]
+    pub voter_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Voter, pays for the voter account and its vault
+    #[account(mut)]
+    pub voter: Signer<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UnlockGaugeVotes<'info> {
+    /// Gauge controller singleton
+    #[account(
+        seeds = [b"gauge_controller"],
+        bump,
+    )]
+    pub gauge_controller: Box<Account<'info, GaugeController>>,
+
+    /// The voter's locked tokens and spent voting power
+    #[account(
+        mut,
+        seeds = [b"gauge_voter", voter.key().as_ref()],
+        bump = gauge_voter.bump,
+        has_one = voter @ AmmError::Unauthorized,
+    )]
+    pub gauge_voter: Box<Account<'info, GaugeVoter>>,
+
+    /// Escrow of the voter's locked governance tokens
+    #[account(
+        mut,
+        seeds = [b"gauge_voter_vault", voter.key().as_ref()],
+        bump,
+    )]
+    pub gauge_voter_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Governance token mint
+    #[account(address = gauge_controller.governance_mint)]
+    pub governance_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    /// Voter's governance token account (destination)
+    #[account(
+        mut,
+        constraint = voter_token_account.mint == governance_mint.key(),
+        constraint = voter_token_account.owner == voter.key() @ AmmError::InvalidTokenAccountOwner
+    )]
+    pub voter_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Voter
+    pub voter: Signer<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct AdvanceGaugeEpoch<'info> {
+    /// Gauge controller singleton
+    #[account(
+        mut,
+        seeds = [b"gauge_controller"],
+        bump,
+    )]
+    pub gauge_controller: Box<Account<'info, GaugeController>>,
+}
+
+#[derive(Accounts)]
+pub struct CheckpointGauge<'info> {
+    /// Gauge controller singleton
+    #[account(
+        seeds = [b"gauge_controller"],
+        bump,
+    )]
+    pub gauge_controller: Box<Account<'info, GaugeController>>,
+
+    /// Gauge being checkpointed
+    #[account(
+        mut,
+        seeds = [b"gauge", farm.key().as_ref()],
+        bump,
+    )]
+    pub gauge: Box<Account<'info, Gauge>>,
+
+    /// Farm whose stream rate is set
+    #[account(
+        mut,
+        seeds = [b"farm", farm.liquidity_pool.as_ref()],
+        bump = farm.bump
+    )]
+    pub farm: Box<Account<'info, Farm>>,
+}
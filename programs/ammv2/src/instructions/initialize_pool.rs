@@ -1,9 +1,28 @@
 use crate::errors::AmmError;
-use crate::state::LiquidityPool;
+use crate::math;
+use crate::state::{
+    AmmConfig, LiquidityPool, PoolBumps, PriceOracle, UnsafeMintApproval, LIQUIDITY_POOL_VERSION,
+};
 use anchor_lang::prelude::*;
+use anchor_lang::system_program::{self, Transfer};
 use anchor_spl::{
     associated_token::AssociatedToken,
-    token_interface::{Mint, TokenAccount, TokenInterface},
+    metadata::{
+        self,
+        mpl_token_metadata::{types::DataV2, MAX_NAME_LENGTH, MAX_SYMBOL_LENGTH, MAX_URI_LENGTH},
+        CreateMetadataAccountsV3, Metadata, MetadataAccount,
+    },
+    token_interface::{
+        spl_token_2022::{
+            self,
+            extension::{
+                mint_close_authority::MintCloseAuthority, permanent_delegate::PermanentDelegate,
+                BaseStateWithExtensions, StateWithExtensions,
+            },
+            state::Mint as MintState,
+        },
+        Mint, TokenAccount, TokenInterface,
+    },
 };
 
 /// Initialize a new AMM liquidity pool
@@ -11,26 +30,199 @@ pub fn process(
     ctx: Context<InitializePool>,
     fee_numerator: u64,
     fee_denominator: u64,
+    lp_metadata_uri: String,
+    launch_whitelist_end_slot: u64,
 ) -> Result<()> {
-    require!(fee_denominator > 0, AmmError::InvalidFeeParameters);
+    initialize(
+        ctx.accounts,
+        &ctx.bumps,
+        fee_numerator,
+        fee_denominator,
+        lp_metadata_uri,
+        launch_whitelist_end_slot,
+    )
+}
+
+/// Pool setup shared by `initialize_pool` and `initialize_pool_with_liquidity`.
+/// A nonzero `launch_whitelist_end_slot` opens the pool with whitelist-only
+/// swaps until that slot.
+pub fn initialize(
+    accounts: &mut InitializePool,
+    bumps: &InitializePoolBumps,
+    fee_numerator: u64,
+    fee_denominator: u64,
+    lp_metadata_uri: String,
+    launch_whitelist_end_slot: u64,
+) -> Result<()> {
+    accounts.amm_config.check_not_paused()?;
+    LiquidityPool::validate_fee(fee_numerator, fee_denominator)?;
     require!(
-        fee_numerator < fee_denominator,
-        AmmError::InvalidFeeParameters
+        lp_metadata_uri.len() <= MAX_URI_LENGTH,
+        AmmError::MetadataUriTooLong
     );
 
-    let pool = &mut ctx.accounts.liquidity_pool;
+    // A permanent delegate can drain a vault and a close authority can brick
+    // it, so such mints need the config authority's explicit approval
+    for (mint, approval) in [
+        (&accounts.token_a_mint, &accounts.token_a_mint_approval),
+        (&accounts.token_b_mint, &accounts.token_b_mint_approval),
+    ] {
+        if has_unsafe_extension(&mint.to_account_info())? {
+            require!(approval.is_some(), AmmError::UnsafeMintExtension);
+            log!(
+                "Unsafe mint approved by the config authority: {}",
+                mint.key()
+            );
+        }
+    }
+
+    // A frozen vault bricks the pool, so the config may refuse freezable mints
+    let token_a_freezable = accounts.token_a_mint.freeze_authority.is_some();
+    let token_b_freezable = accounts.token_b_mint.freeze_authority.is_some();
+    if token_a_freezable || token_b_freezable {
+        require!(
+            !accounts.amm_config.reject_freeze_authority,
+            AmmError::FreezeAuthorityNotAllowed
+        );
+        log!(
+            "Freezable mints - Token A: {}, Token B: {}",
+            token_a_freezable,
+            token_b_freezable
+        );
+    }
+
+    // Anti-spam creation fee, paid to the protocol treasury
+    let creation_fee = accounts.amm_config.pool_creation_fee_lamports;
+    if creation_fee > 0 {
+        system_program::transfer(
+            CpiContext::new(
+                accounts.system_program.to_account_info(),
+                Transfer {
+                    from: accounts.payer.to_account_info(),
+                    to: accounts.treasury.to_account_info(),
+                },
+            ),
+            creation_fee,
+        )?;
+        log!("Pool creation fee paid: {} lamports", creation_fee);
+    }
+
+    // Wallet-visible LP metadata named after the pair, e.g. "AMMv2 LP USDC-SOL"
+    let pair = format!(
+        "{}-{}",
+        pair_symbol(&accounts.token_a_metadata, &accounts.token_a_mint.key()),
+        pair_symbol(&accounts.token_b_metadata, &accounts.token_b_mint.key())
+    );
+    let lp_metadata = DataV2 {
+        name: truncated(&format!("AMMv2 LP {}", pair), MAX_NAME_LENGTH),
+        symbol: truncated(&pair, MAX_SYMBOL_LENGTH),
+        uri: lp_metadata_uri,
+        seller_fee_basis_points: 0,
+        creators: None,
+        collection: None,
+        uses: None,
+    };
+    log!("LP metadata: {} ({})", lp_metadata.name, lp_metadata.symbol);
+
+    let pool_key = accounts.liquidity_pool.key();
+    let authority_seeds = &[
+        b"pool_authority",
+        pool_key.as_ref(),
+        &[bumps.pool_authority],
+    ];
+    let signer_seeds = &[&authority_seeds[..]];
+    metadata::create_metadata_accounts_v3(
+        CpiContext::new_with_signer(
+            accounts.token_metadata_program.to_account_info(),
+            CreateMetadataAccountsV3 {
+                metadata: accounts.lp_token_metadata.to_account_info(),
+                mint: accounts.lp_token_mint.to_account_info(),
+                mint_authority: accounts.pool_authority.to_account_info(),
+                payer: accounts.payer.to_account_info(),
+                update_authority: accounts.pool_authority.to_account_info(),
+                system_program: accounts.system_program.to_account_info(),
+                rent: accounts.rent.to_account_info(),
+            },
+            signer_seeds,
+        ),
+        lp_metadata,
+        true,
+        true,
+        None,
+    )?;
+
+    let pool = &mut accounts.liquidity_pool.load_init()?;
+    pool.version = LIQUIDITY_POOL_VERSION;
     pool.fee_numerator = fee_numerator;
     pool.fee_denominator = fee_denominator;
     pool.total_lp_tokens_issued = 0;
+    pool.admin = accounts.payer.key();
+    pool.creator = accounts.payer.key();
+    pool.token_a_mint = accounts.token_a_mint.key();
+    pool.token_b_mint = accounts.token_b_mint.key();
+    pool.oracle = accounts.oracle.key();
+    pool.oracle_q64 = 1;
+    pool.token_a_freezable = token_a_freezable as u8;
+    pool.token_b_freezable = token_b_freezable as u8;
+    pool.set_bumps(PoolBumps {
+        authority: bumps.pool_authority,
+        token_a_vault: bumps.token_a_vault,
+        token_b_vault: bumps.token_b_vault,
+        lp_token_mint: bumps.lp_token_mint,
+    });
+    if launch_whitelist_end_slot > 0 {
+        pool.launch_whitelist_only = 1;
+        pool.launch_protection_end_slot = launch_whitelist_end_slot;
+        log!(
+            "Whitelist-only swaps until slot {}",
+            launch_whitelist_end_slot
+        );
+    }
+
+    // The first swap writes the first observation
+    let oracle = &mut accounts.oracle;
+    oracle.liquidity_pool = accounts.liquidity_pool.key();
 
-    msg!(
+    log!(
         "Pool initialized with fee: {}/{}",
         fee_numerator,
         fee_denominator
     );
+
+    emit!(pool.params_snapshot(accounts.liquidity_pool.key())?);
     Ok(())
 }
 
+/// Whether a Token-2022 mint has a permanent delegate or a close authority
+fn has_unsafe_extension(mint: &AccountInfo) -> Result<bool> {
+    if *mint.owner != spl_token_2022::ID {
+        return Ok(false);
+    }
+    let data = mint.try_borrow_data()?;
+    let state = StateWithExtensions::<MintState>::unpack(&data)?;
+    let permanent_delegate = state
+        .get_extension::<PermanentDelegate>()
+        .is_ok_and(|extension| Option::<Pubkey>::from(extension.delegate).is_some());
+    let close_authority = state
+        .get_extension::<MintCloseAuthority>()
+        .is_ok_and(|extension| Option::<Pubkey>::from(extension.close_authority).is_some());
+    Ok(permanent_delegate || close_authority)
+}
+
+/// Display symbol for one side of the pair: its metadata symbol, else a mint prefix
+fn pair_symbol(metadata: &Option<Box<Account<MetadataAccount>>>, mint: &Pubkey) -> String {
+    metadata
+        .as_ref()
+        .map(|metadata| metadata.symbol.trim_end_matches('\0').trim().to_string())
+        .filter(|symbol| !symbol.is_empty())
+        .unwrap_or_else(|| truncated(&mint.to_string(), 4))
+}
+
+/// First `max_chars` characters of `value`
+fn truncated(value: &str, max_chars: usize) -> String {
+    value.chars().take(max_chars).collect()
+}
+
 #[derive(Accounts)]
 pub struct InitializePool<'info> {
     /// First token mint in the trading pair
@@ -42,7 +234,7 @@ pub struct InitializePool<'info> {
     /// PDA account that stores pool configuration and state
     #[account(
         init,
-        space = 8 + LiquidityPool::ACCOUNT_SIZE,
+        space = 8 + LiquidityPool::INIT_SPACE,
         payer = payer,
         seeds = [
             b"liquidity_pool",
@@ -51,7 +243,17 @@ pub struct InitializePool<'info> {
         ],
         bump,
     )]
-    pub liquidity_pool: Box<Account<'info, LiquidityPool>>,
+    pub liquidity_pool: AccountLoader<'info, LiquidityPool>,
+
+    /// Price oracle for the pool
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + PriceOracle::INIT_SPACE,
+        seeds = [b"oracle", liquidity_pool.key().as_ref()],
+        bump,
+    )]
+    pub oracle: Box<Account<'info, PriceOracle>>,
 
     /// PDA authority that controls the pool's vaults and LP token minting
     #[account(
@@ -68,6 +270,7 @@ pub struct InitializePool<'info> {
         bump,
         token::mint = token_a_mint,
         token::authority = pool_authority,
+        token::token_program = token_a_program,
     )]
     pub token_a_vault: Box<InterfaceAccount<'info, TokenAccount>>,
 
@@ -79,26 +282,78 @@ pub struct InitializePool<'info> {
         bump,
         token::mint = token_b_mint,
         token::authority = pool_authority,
+        token::token_program = token_b_program,
     )]
     pub token_b_vault: Box<InterfaceAccount<'info, TokenAccount>>,
 
-    /// LP token mint - represents shares of the liquidity pool
+    /// LP token mint - represents shares of the liquidity pool, with decimals
+    /// derived from the pair
     #[account(
         init,
         payer = payer,
         seeds = [b"lp_token_mint", liquidity_pool.key().as_ref()],
         bump,
-        mint::decimals = 9,
+        mint::decimals = math::lp_decimals(token_a_mint.decimals, token_b_mint.decimals),
         mint::authority = pool_authority,
     )]
     pub lp_token_mint: Box<InterfaceAccount<'info, Mint>>,
 
-    /// Account that pays for initialization
+    /// Metaplex metadata of the LP mint, created here
+    /// CHECK: address is the Metaplex metadata PDA of the LP mint
+    #[account(
+        mut,
+        seeds = [
+            b"metadata",
+            token_metadata_program.key().as_ref(),
+            lp_token_mint.key().as_ref()
+        ],
+        seeds::program = token_metadata_program.key(),
+        bump
+    )]
+    pub lp_token_metadata: UncheckedAccount<'info>,
+
+    /// Metaplex metadata of token A, used for the LP name when present
+    #[account(constraint = token_a_metadata.mint == token_a_mint.key())]
+    pub token_a_metadata: Option<Box<Account<'info, MetadataAccount>>>,
+
+    /// Metaplex metadata of token B, used for the LP name when present
+    #[account(constraint = token_b_metadata.mint == token_b_mint.key())]
+    pub token_b_metadata: Option<Box<Account<'info, MetadataAccount>>>,
+
+    /// Config authority's approval of token A, required when it has a
+    /// permanent delegate or close authority
+    #[account(seeds = [b"unsafe_mint_approval", token_a_mint.key().as_ref()], bump)]
+    pub token_a_mint_approval: Option<Box<Account<'info, UnsafeMintApproval>>>,
+
+    /// Config authority's approval of token B, required when it has a
+    /// permanent delegate or close authority
+    #[account(seeds = [b"unsafe_mint_approval", token_b_mint.key().as_ref()], bump)]
+    pub token_b_mint_approval: Option<Box<Account<'info, UnsafeMintApproval>>>,
+
+    /// Global config holding the pool creation fee
+    #[account(seeds = [b"amm_config"], bump)]
+    pub amm_config: Box<Account<'info, AmmConfig>>,
+
+    /// Protocol treasury receiving the creation fee
+    #[account(mut, address = amm_config.treasury)]
+    pub treasury: SystemAccount<'info>,
+
+    /// Account that pays for initialization and becomes the pool admin
     #[account(mut)]
     pub payer: Signer<'info>,
 
-    /// Required system programs
+    /// Token program of token A, which may differ from token B's
+    #[account(address = *token_a_mint.to_account_info().owner @ AmmError::InvalidTokenProgram)]
+    pub token_a_program: Interface<'info, TokenInterface>,
+
+    /// Token program of token B
+    #[account(address = *token_b_mint.to_account_info().owner @ AmmError::InvalidTokenProgram)]
+    pub token_b_program: Interface<'info, TokenInterface>,
+
+    /// Required system programs; `token_program` owns the LP mint
     pub system_program: Program<'info, System>,
     pub token_program: Interface<'info, TokenInterface>,
     pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_metadata_program: Program<'info, Metadata>,
+    pub rent: Sysvar<'info, Rent>,
 }
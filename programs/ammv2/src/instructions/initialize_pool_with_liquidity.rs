@@ -0,0 +1,274 @@
+use crate::errors::AmmError;
+use crate::instructions::initialize_pool::{self, *};
+use crate::math;
+use crate::state::{LpLock, BASIS_POINTS_DIVISOR};
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::{
+        self, get_associated_token_address_with_program_id, AssociatedToken, Create,
+    },
+    token_interface::{self, MintTo, TokenAccount, TransferChecked},
+};
+
+/// Share of the creator's initial LP tokens to lock at pool creation
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CreatorLpLock {
+    /// Locked share of the minted LP tokens, in bps
+    pub lock_bps: u16,
+
+    /// Seconds from pool creation until the locked LP tokens can be unlocked
+    pub lock_duration_secs: i64,
+}
+
+/// Initialize a pool and make its first deposit atomically, so no one else can
+/// seed the pool at a bad price in between. With `creator_lock`, part of the
+/// creator's LP tokens is minted straight into the creator's `LpLock` and the
+/// lock is recorded on the pool, a guarantee frontends can show.
+#[allow(clippy::too_many_arguments)]
+pub fn process(
+    ctx: Context<InitializePoolWithLiquidity>,
+    fee_numerator: u64,
+    fee_denominator: u64,
+    token_a_amount: u64,
+    token_b_amount: u64,
+    lp_metadata_uri: String,
+    launch_whitelist_end_slot: u64,
+    creator_lock: Option<CreatorLpLock>,
+) -> Result<()> {
+    initialize_pool::initialize(
+        &mut ctx.accounts.initialize,
+        &ctx.bumps.initialize,
+        fee_numerator,
+        fee_denominator,
+        lp_metadata_uri,
+        launch_whitelist_end_slot,
+    )?;
+    // Anchor writes a new zero-copy account's discriminator on exit; write it
+    // now so the pool loads again for the deposit below
+    ctx.accounts
+        .initialize
+        .liquidity_pool
+        .exit(ctx.program_id)?;
+
+    let accounts = &ctx.accounts.initialize;
+    require!(
+        ctx.accounts.user_token_a_account.amount >= token_a_amount,
+        AmmError::InsufficientBalance
+    );
+    require!(
+        ctx.accounts.user_token_b_account.amount >= token_b_amount,
+        AmmError::InsufficientBalance
+    );
+
+    // LP tokens = geometric mean of the initial deposits
+    let lp_tokens_to_mint = math::to_u64(math::checked_sqrt(
+        (token_a_amount as u128)
+            .checked_mul(token_b_amount as u128)
+            .ok_or(AmmError::MathOverflow)?,
+    )?)?;
+    require!(lp_tokens_to_mint > 0, AmmError::InvalidLpTokenAmount);
+
+    let (lp_tokens_to_lock, unlock_timestamp) = match creator_lock {
+        Some(lock) => {
+            require!(
+                lock.lock_bps > 0
+                    && lock.lock_bps as u128 <= BASIS_POINTS_DIVISOR
+                    && lock.lock_duration_secs > 0,
+                AmmError::InvalidPoolConfig
+            );
+            let lp_tokens_to_lock = math::to_u64(math::mul_div_floor(
+                lp_tokens_to_mint as u128,
+                lock.lock_bps as u128,
+                BASIS_POINTS_DIVISOR,
+            )?)?;
+            require!(lp_tokens_to_lock > 0, AmmError::InvalidLpTokenAmount);
+            let unlock_timestamp = Clock::get()?
+                .unix_timestamp
+                .checked_add(lock.lock_duration_secs)
+                .ok_or(AmmError::MathOverflow)?;
+            (lp_tokens_to_lock, unlock_timestamp)
+        }
+        None => (0, 0),
+    };
+
+    accounts.liquidity_pool.load_mut()?.total_lp_tokens_issued = lp_tokens_to_mint;
+
+    // Transfer token A from creator to vault
+    token_interface::transfer_checked(
+        CpiContext::new(
+            accounts.token_a_program.to_account_info(),
+            TransferChecked {
+                from: ctx.accounts.user_token_a_account.to_account_info(),
+                mint: accounts.token_a_mint.to_account_info(),
+                to: accounts.token_a_vault.to_account_info(),
+                authority: accounts.payer.to_account_info(),
+            },
+        ),
+        token_a_amount,
+        accounts.token_a_mint.decimals,
+    )?;
+
+    // Transfer token B from creator to vault
+    token_interface::transfer_checked(
+        CpiContext::new(
+            accounts.token_b_program.to_account_info(),
+            TransferChecked {
+                from: ctx.accounts.user_token_b_account.to_account_info(),
+                mint: accounts.token_b_mint.to_account_info(),
+                to: accounts.token_b_vault.to_account_info(),
+                authority: accounts.payer.to_account_info(),
+            },
+        ),
+        token_b_amount,
+        accounts.token_b_mint.decimals,
+    )?;
+
+    // The LP mint is new, so the creator's LP account has to be created here
+    associated_token::create(CpiContext::new(
+        ctx.accounts.associated_token_program.to_account_info(),
+        Create {
+            payer: accounts.payer.to_account_info(),
+            associated_token: ctx.accounts.user_lp_token_account.to_account_info(),
+            authority: accounts.payer.to_account_info(),
+            mint: accounts.lp_token_mint.to_account_info(),
+            system_program: accounts.system_program.to_account_info(),
+            token_program: accounts.token_program.to_account_info(),
+        },
+    ))?;
+
+    // Mint LP tokens to creator
+    let pool_key = accounts.liquidity_pool.key();
+    let authority_bump = ctx.bumps.initialize.pool_authority;
+    let authority_seeds = &[b"pool_authority", pool_key.as_ref(), &[authority_bump]];
+    let signer_seeds = &[&authority_seeds[..]];
+
+    token_interface::mint_to(
+        CpiContext::new_with_signer(
+            accounts.token_program.to_account_info(),
+            MintTo {
+                mint: accounts.lp_token_mint.to_account_info(),
+                to: ctx.accounts.user_lp_token_account.to_account_info(),
+                authority: accounts.pool_authority.to_account_info(),
+            },
+            signer_seeds,
+        ),
+        lp_tokens_to_mint - lp_tokens_to_lock,
+    )?;
+
+    if lp_tokens_to_lock > 0 {
+        let (Some(lp_lock), Some(lp_lock_vault)) =
+            (&mut ctx.accounts.lp_lock, &ctx.accounts.lp_lock_vault)
+        else {
+            return err!(AmmError::LpLockAccountsRequired);
+        };
+
+        token_interface::mint_to(
+            CpiContext::new_with_signer(
+                accounts.token_program.to_account_info(),
+                MintTo {
+                    mint: accounts.lp_token_mint.to_account_info(),
+                    to: lp_lock_vault.to_account_info(),
+                    authority: accounts.pool_authority.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            lp_tokens_to_lock,
+        )?;
+
+        lp_lock.liquidity_pool = pool_key;
+        lp_lock.owner = accounts.payer.key();
+        lp_lock.amount = lp_tokens_to_lock;
+        lp_lock.unlock_timestamp = unlock_timestamp;
+        lp_lock.locked_at = Clock::get()?.unix_timestamp;
+        lp_lock.bump = ctx.bumps.lp_lock.ok_or(AmmError::LpLockAccountsRequired)?;
+
+        let pool = &mut accounts.liquidity_pool.load_mut()?;
+        pool.creator_locked_lp = lp_tokens_to_lock;
+        pool.creator_lock_until = unlock_timestamp;
+    }
+
+    let accounts = &mut ctx.accounts.initialize;
+    accounts.token_a_vault.reload()?;
+    accounts.token_b_vault.reload()?;
+    accounts
+        .liquidity_pool
+        .load_mut()?
+        .sync_reserves(accounts.token_a_vault.amount, accounts.token_b_vault.amount);
+
+    log!(
+        "Initial liquidity added - Token A: {}, Token B: {}, LP tokens: {}, Locked: {}",
+        token_a_amount,
+        token_b_amount,
+        lp_tokens_to_mint,
+        lp_tokens_to_lock
+    );
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct InitializePoolWithLiquidity<'info> {
+    /// Every account `initialize_pool` creates
+    pub initialize: InitializePool<'info>,
+
+    /// Creator's token A account (source)
+    #[account(
+        mut,
+        constraint = user_token_a_account.mint == initialize.token_a_mint.key(),
+        constraint = user_token_a_account.owner == initialize.payer.key() @ AmmError::InvalidTokenAccountOwner
+    )]
+    pub user_token_a_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Creator's token B account (source)
+    #[account(
+        mut,
+        constraint = user_token_b_account.mint == initialize.token_b_mint.key(),
+        constraint = user_token_b_account.owner == initialize.payer.key() @ AmmError::InvalidTokenAccountOwner
+    )]
+    pub user_token_b_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Creator's LP token associated account, created by this instruction
+    /// CHECK: address is the creator's ATA for the new LP mint
+    #[account(
+        mut,
+        address = get_associated_token_address_with_program_id(
+            &initialize.payer.key(),
+            &initialize.lp_token_mint.key(),
+            &initialize.token_program.key(),
+        )
+    )]
+    pub user_lp_token_account: UncheckedAccount<'info>,
+
+    /// Creator's LP lock, only needed with `creator_lock`
+    #[account(
+        init,
+        payer = initialize.payer,
+        space = 8 + LpLock::INIT_SPACE,
+        seeds = [
+            b"lp_lock",
+            initialize.liquidity_pool.key().as_ref(),
+            initialize.payer.key().as_ref()
+        ],
+        bump,
+    )]
+    pub lp_lock: Option<Box<Account<'info, LpLock>>>,
+
+    /// Escrow of the creator's locked LP tokens, only needed with `creator_lock`
+    #[account(
+        init,
+        payer = initialize.payer,
+        seeds = [
+            b"lp_lock_vault",
+            initialize.liquidity_pool.key().as_ref(),
+            initialize.payer.key().as_ref()
+        ],
+        bump,
+        token::mint = initialize.lp_token_mint,
+        token::authority = lp_lock,
+        token::token_program = initialize.token_program,
+    )]
+    pub lp_lock_vault: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
@@ -0,0 +1,201 @@
+use crate::errors::AmmError;
+use crate::state::LiquidityPool;
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{self, Mint, TokenAccount, TokenInterface, TransferChecked};
+
+/// Create the pool's insurance fund vaults, funded by deviation-incident surcharges
+pub fn initialize(ctx: Context<InitializeInsuranceFund>) -> Result<()> {
+    log!(
+        "Insurance fund initialized - Vault A: {}, Vault B: {}",
+        ctx.accounts.insurance_vault_a.key(),
+        ctx.accounts.insurance_vault_b.key()
+    );
+    Ok(())
+}
+
+/// Move insurance fund tokens into the pool reserves, e.g. to cover a depeg loss
+pub fn deploy(ctx: Context<DeployInsuranceFund>, amount_a: u64, amount_b: u64) -> Result<()> {
+    require!(
+        ctx.accounts.insurance_vault_a.amount >= amount_a
+            && ctx.accounts.insurance_vault_b.amount >= amount_b,
+        AmmError::InsufficientInsuranceFunds
+    );
+
+    let pool_key = ctx.accounts.liquidity_pool.key();
+    let authority_bump = ctx.accounts.liquidity_pool.load()?.authority_bump;
+    let authority_seeds = &[b"pool_authority", pool_key.as_ref(), &[authority_bump]];
+    let signer_seeds = &[&authority_seeds[..]];
+
+    if amount_a > 0 {
+        token_interface::transfer_checked(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.insurance_vault_a.to_account_info(),
+                    mint: ctx.accounts.token_a_mint.to_account_info(),
+                    to: ctx.accounts.token_a_vault.to_account_info(),
+                    authority: ctx.accounts.pool_authority.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            amount_a,
+            ctx.accounts.token_a_mint.decimals,
+        )?;
+    }
+
+    if amount_b > 0 {
+        token_interface::transfer_checked(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.insurance_vault_b.to_account_info(),
+                    mint: ctx.accounts.token_b_mint.to_account_info(),
+                    to: ctx.accounts.token_b_vault.to_account_info(),
+                    authority: ctx.accounts.pool_authority.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            amount_b,
+            ctx.accounts.token_b_mint.decimals,
+        )?;
+    }
+
+    ctx.accounts.token_a_vault.reload()?;
+    ctx.accounts.token_b_vault.reload()?;
+    ctx.accounts.liquidity_pool.load_mut()?.sync_reserves(
+        ctx.accounts.token_a_vault.amount,
+        ctx.accounts.token_b_vault.amount,
+    );
+
+    log!(
+        "Insurance fund deployed - Token A: {}, Token B: {}",
+        amount_a,
+        amount_b
+    );
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct InitializeInsuranceFund<'info> {
+    /// Pool state account
+    pub liquidity_pool: AccountLoader<'info, LiquidityPool>,
+
+    /// Pool authority PDA
+    #[account(
+        seeds = [b"pool_authority", liquidity_pool.key().as_ref()],
+        bump = liquidity_pool.load()?.authority_bump
+    )]
+    pub pool_authority: SystemAccount<'info>,
+
+    /// Token A mint
+    #[account(address = liquidity_pool.load()?.token_a_mint)]
+    pub token_a_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    /// Token B mint
+    #[account(address = liquidity_pool.load()?.token_b_mint)]
+    pub token_b_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    /// Insurance vault for token A
+    #[account(
+        init,
+        payer = payer,
+        seeds = [
+            b"insurance_vault",
+            liquidity_pool.key().as_ref(),
+            token_a_mint.key().as_ref()
+        ],
+        bump,
+        token::mint = token_a_mint,
+        token::authority = pool_authority,
+    )]
+    pub insurance_vault_a: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Insurance vault for token B
+    #[account(
+        init,
+        payer = payer,
+        seeds = [
+            b"insurance_vault",
+            liquidity_pool.key().as_ref(),
+            token_b_mint.key().as_ref()
+        ],
+        bump,
+        token::mint = token_b_mint,
+        token::authority = pool_authority,
+    )]
+    pub insurance_vault_b: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Anyone may create the vaults and pay for the accounts
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct DeployInsuranceFund<'info> {
+    /// Pool state account
+    #[account(mut, has_one = admin @ AmmError::Unauthorized)]
+    pub liquidity_pool: AccountLoader<'info, LiquidityPool>,
+
+    /// Pool authority PDA
+    #[account(
+        seeds = [b"pool_authority", liquidity_pool.key().as_ref()],
+        bump = liquidity_pool.load()?.authority_bump
+    )]
+    pub pool_authority: SystemAccount<'info>,
+
+    /// Token A mint
+    #[account(address = liquidity_pool.load()?.token_a_mint)]
+    pub token_a_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    /// Token B mint
+    #[account(address = liquidity_pool.load()?.token_b_mint)]
+    pub token_b_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    /// Insurance vault for token A
+    #[account(
+        mut,
+        seeds = [
+            b"insurance_vault",
+            liquidity_pool.key().as_ref(),
+            token_a_mint.key().as_ref()
+        ],
+        bump,
+    )]
+    pub insurance_vault_a: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Insurance vault for token B
+    #[account(
+        mut,
+        seeds = [
+            b"insurance_vault",
+            liquidity_pool.key().as_ref(),
+            token_b_mint.key().as_ref()
+        ],
+        bump,
+    )]
+    pub insurance_vault_b: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Token A vault
+    #[account(
+        mut,
+        seeds = [b"token_a_vault", liquidity_pool.key().as_ref()],
+        bump = liquidity_pool.load()?.token_a_vault_bump
+    )]
+    pub token_a_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Token B vault
+    #[account(
+        mut,
+        seeds = [b"token_b_vault", liquidity_pool.key().as_ref()],
+        bump = liquidity_pool.load()?.token_b_vault_bump
+    )]
+    pub token_b_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Pool admin
+    pub admin: Signer<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
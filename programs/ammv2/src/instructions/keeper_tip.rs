@@ -0,0 +1,63 @@
+use crate::state::LiquidityPool;
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{self, Mint, TokenAccount, TokenInterface, TransferChecked};
+
+/// Accounts a crank takes to pay its caller a keeper tip. They are optional on
+/// every crank: a caller that leaves any of them out forgoes the tip.
+pub struct KeeperTipAccounts<'a, 'info> {
+    /// Pool authority PDA, owner of the protocol fee vault
+    pub pool_authority: &'a AccountInfo<'info>,
+
+    /// Token A mint
+    pub token_a_mint: Option<&'a InterfaceAccount<'info, Mint>>,
+
+    /// Protocol fee vault for token A, which holds the tip budget
+    pub protocol_fee_vault_a: Option<&'a InterfaceAccount<'info, TokenAccount>>,
+
+    /// Caller's token A account receiving the tip
+    pub keeper_token_a_account: Option<&'a InterfaceAccount<'info, TokenAccount>>,
+
+    /// Token program of token A
+    pub token_program: Option<&'a Interface<'info, TokenInterface>>,
+}
+
+/// Pay up to `amount` of token A out of the pool's keeper tip budget, the
+/// shared reward for permissionless cranks. An exhausted budget pays what is
+/// left. Returns the tip paid.
+pub fn pay_keeper_tip(
+    pool: &mut LiquidityPool,
+    pool_key: &Pubkey,
+    amount: u64,
+    accounts: KeeperTipAccounts,
+) -> Result<u64> {
+    let (Some(mint), Some(fee_vault), Some(destination), Some(token_program)) = (
+        accounts.token_a_mint,
+        accounts.protocol_fee_vault_a,
+        accounts.keeper_token_a_account,
+        accounts.token_program,
+    ) else {
+        return Ok(0);
+    };
+
+    let tip = pool.take_keeper_tip(amount.min(fee_vault.amount));
+    if tip == 0 {
+        return Ok(0);
+    }
+
+    let authority_seeds = &[b"pool_authority", pool_key.as_ref(), &[pool.authority_bump]];
+    token_interface::transfer_checked(
+        CpiContext::new_with_signer(
+            token_program.to_account_info(),
+            TransferChecked {
+                from: fee_vault.to_account_info(),
+                mint: mint.to_account_info(),
+                to: destination.to_account_info(),
+                authority: accounts.pool_authority.clone(),
+            },
+            &[&authority_seeds[..]],
+        ),
+        tip,
+        mint.decimals,
+    )?;
+    Ok(tip)
+}
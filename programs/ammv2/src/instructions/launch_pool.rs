@@ -0,0 +1,452 @@
+use crate::errors::AmmError;
+use crate::events::LaunchPoolGraduated;
+use crate::instructions::initialize_pool::{self, *};
+use crate::math;
+use crate::state::{LaunchPool, LiquidityPool};
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{
+    self, Burn, Mint, MintTo, TokenAccount, TokenInterface, TransferChecked,
+};
+
+/// Put `token_amount` of the creator's tokens on a bonding curve. The curve is
+/// a constant product against `virtual_quote_reserve` plus the quote raised,
+/// so the opening price is `virtual_quote_reserve / token_amount` and rises
+/// with every buy. Buys stop once `target_raise` is met.
+pub fn create(
+    ctx: Context<CreateLaunchPool>,
+    token_amount: u64,
+    virtual_quote_reserve: u64,
+    target_raise: u64,
+    fee_numerator: u64,
+    fee_denominator: u64,
+) -> Result<()> {
+    require!(
+        token_amount > 0 && virtual_quote_reserve > 0 && target_raise > 0,
+        AmmError::InvalidLaunchParameters
+    );
+    // Checked here so graduation cannot fail on the fee
+    LiquidityPool::validate_fee(fee_numerator, fee_denominator)?;
+
+    token_interface::transfer_checked(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            TransferChecked {
+                from: ctx.accounts.creator_token_account.to_account_info(),
+                mint: ctx.accounts.token_mint.to_account_info(),
+                to: ctx.accounts.token_vault.to_account_info(),
+                authority: ctx.accounts.creator.to_account_info(),
+            },
+        ),
+        token_amount,
+        ctx.accounts.token_mint.decimals,
+    )?;
+
+    let launch = &mut ctx.accounts.launch_pool;
+    launch.creator = ctx.accounts.creator.key();
+    launch.token_mint = ctx.accounts.token_mint.key();
+    launch.quote_mint = ctx.accounts.quote_mint.key();
+    launch.virtual_quote_reserve = virtual_quote_reserve;
+    launch.token_reserve = token_amount;
+    launch.target_raise = target_raise;
+    launch.fee_numerator = fee_numerator;
+    launch.fee_denominator = fee_denominator;
+    launch.bump = ctx.bumps.launch_pool;
+
+    log!(
+        "Launch pool created - Tokens: {}, Virtual quote: {}, Target raise: {}",
+        token_amount,
+        virtual_quote_reserve,
+        target_raise
+    );
+    Ok(())
+}
+
+/// Buy launch tokens with up to `quote_amount`. A buy that would overshoot the
+/// target raise is trimmed to it, and the rest of the quote stays with the buyer.
+pub fn buy(
+    ctx: Context<BuyLaunchPool>,
+    quote_amount: u64,
+    minimum_token_amount: u64,
+) -> Result<()> {
+    let (quote_used, tokens_out) = ctx.accounts.launch_pool.buy_quote(quote_amount)?;
+    require!(
+        tokens_out >= minimum_token_amount,
+        AmmError::SlippageExceeded
+    );
+
+    token_interface::transfer_checked(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            TransferChecked {
+                from: ctx.accounts.buyer_quote_account.to_account_info(),
+                mint: ctx.accounts.quote_mint.to_account_info(),
+                to: ctx.accounts.quote_vault.to_account_info(),
+                authority: ctx.accounts.buyer.to_account_info(),
+            },
+        ),
+        quote_used,
+        ctx.accounts.quote_mint.decimals,
+    )?;
+
+    let signer = ctx.accounts.launch_pool.signer();
+    let launch_seeds = signer.seeds();
+    let signer_seeds = &[&launch_seeds[..]];
+    token_interface::transfer_checked(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            TransferChecked {
+                from: ctx.accounts.token_vault.to_account_info(),
+                mint: ctx.accounts.token_mint.to_account_info(),
+                to: ctx.accounts.buyer_token_account.to_account_info(),
+                authority: ctx.accounts.launch_pool.to_account_info(),
+            },
+            signer_seeds,
+        ),
+        tokens_out,
+        ctx.accounts.token_mint.decimals,
+    )?;
+
+    let launch = &mut ctx.accounts.launch_pool;
+    launch.quote_raised = launch
+        .quote_raised
+        .checked_add(quote_used)
+        .ok_or(AmmError::MathOverflow)?;
+    launch.token_reserve = launch
+        .token_reserve
+        .checked_sub(tokens_out)
+        .ok_or(AmmError::MathOverflow)?;
+
+    log!(
+        "Launch buy - Quote: {}, Tokens: {}, Raised: {}/{}",
+        quote_used,
+        tokens_out,
+        launch.quote_raised,
+        launch.target_raise
+    );
+    Ok(())
+}
+
+/// Once the target raise is met, create the launch's constant-product pool and
+/// seed it with the raise and the tokens that match it at the curve's closing
+/// price, in one instruction. The LP tokens are locked in the launch's LP vault
+/// for good, and the unsold tokens left over are burned. Anyone may graduate a
+/// launch; the creator becomes the pool admin.
+pub fn graduate(ctx: Context<GraduateLaunchPool>, lp_metadata_uri: String) -> Result<()> {
+    let launch = &ctx.accounts.launch_pool;
+    require!(!launch.graduated(), AmmError::LaunchGraduated);
+    require!(
+        launch.quote_raised >= launch.target_raise,
+        AmmError::LaunchTargetNotReached
+    );
+    let token_is_a = ctx.accounts.initialize.token_a_mint.key() == launch.token_mint;
+    let (token_mint, quote_mint) = if token_is_a {
+        (
+            &ctx.accounts.initialize.token_a_mint,
+            &ctx.accounts.initialize.token_b_mint,
+        )
+    } else {
+        (
+            &ctx.accounts.initialize.token_b_mint,
+            &ctx.accounts.initialize.token_a_mint,
+        )
+    };
+    require!(
+        token_mint.key() == launch.token_mint && quote_mint.key() == launch.quote_mint,
+        AmmError::LaunchPairMismatch
+    );
+
+    let creator = launch.creator;
+    let quote_amount = launch.quote_raised;
+    let token_amount = launch.graduation_token_amount()?;
+    let lp_tokens = math::to_u64(math::checked_sqrt(
+        (quote_amount as u128)
+            .checked_mul(token_amount as u128)
+            .ok_or(AmmError::MathOverflow)?,
+    )?)?;
+    require!(lp_tokens > 0, AmmError::InvalidLpTokenAmount);
+
+    initialize_pool::initialize(
+        &mut ctx.accounts.initialize,
+        &ctx.bumps.initialize,
+        launch.fee_numerator,
+        launch.fee_denominator,
+        lp_metadata_uri,
+        0,
+    )?;
+
+    let accounts = &ctx.accounts;
+    let initialize = &accounts.initialize;
+    let (token_pool_vault, quote_pool_vault) = if token_is_a {
+        (&initialize.token_a_vault, &initialize.token_b_vault)
+    } else {
+        (&initialize.token_b_vault, &initialize.token_a_vault)
+    };
+    let (token_mint, quote_mint) = if token_is_a {
+        (&initialize.token_a_mint, &initialize.token_b_mint)
+    } else {
+        (&initialize.token_b_mint, &initialize.token_a_mint)
+    };
+    let (token_mint_program, quote_mint_program) = if token_is_a {
+        (&initialize.token_a_program, &initialize.token_b_program)
+    } else {
+        (&initialize.token_b_program, &initialize.token_a_program)
+    };
+
+    let signer = accounts.launch_pool.signer();
+    let launch_seeds = signer.seeds();
+    let launch_signer_seeds = &[&launch_seeds[..]];
+    for (from, mint, to, token_program, amount) in [
+        (
+            &accounts.token_vault,
+            token_mint,
+            token_pool_vault,
+            token_mint_program,
+            token_amount,
+        ),
+        (
+            &accounts.quote_vault,
+            quote_mint,
+            quote_pool_vault,
+            quote_mint_program,
+            quote_amount,
+        ),
+    ] {
+        token_interface::transfer_checked(
+            CpiContext::new_with_signer(
+                token_program.to_account_info(),
+                TransferChecked {
+                    from: from.to_account_info(),
+                    mint: mint.to_account_info(),
+                    to: to.to_account_info(),
+                    authority: accounts.launch_pool.to_account_info(),
+                },
+                launch_signer_seeds,
+            ),
+            amount,
+            mint.decimals,
+        )?;
+    }
+
+    let tokens_burned = accounts.token_vault.amount - token_amount;
+    if tokens_burned > 0 {
+        token_interface::burn(
+            CpiContext::new_with_signer(
+                token_mint_program.to_account_info(),
+                Burn {
+                    mint: token_mint.to_account_info(),
+                    from: accounts.token_vault.to_account_info(),
+                    authority: accounts.launch_pool.to_account_info(),
+                },
+                launch_signer_seeds,
+            ),
+            tokens_burned,
+        )?;
+    }
+
+    let pool_key = initialize.liquidity_pool.key();
+    let authority_seeds = &[
+        b"pool_authority",
+        pool_key.as_ref(),
+        &[ctx.bumps.initialize.pool_authority],
+    ];
+    token_interface::mint_to(
+        CpiContext::new_with_signer(
+            initialize.token_program.to_account_info(),
+            MintTo {
+                mint: initialize.lp_token_mint.to_account_info(),
+                to: accounts.launch_lp_vault.to_account_info(),
+                authority: initialize.pool_authority.to_account_info(),
+            },
+            &[&authority_seeds[..]],
+        ),
+        lp_tokens,
+    )?;
+
+    let accounts = &mut ctx.accounts.initialize;
+    accounts.token_a_vault.reload()?;
+    accounts.token_b_vault.reload()?;
+    let pool = &mut accounts.liquidity_pool.load_mut()?;
+    pool.admin = creator;
+    pool.creator = creator;
+    pool.total_lp_tokens_issued = lp_tokens;
+    pool.sync_reserves(accounts.token_a_vault.amount, accounts.token_b_vault.amount);
+
+    let launch = &mut ctx.accounts.launch_pool;
+    launch.liquidity_pool = pool_key;
+    launch.token_reserve = 0;
+
+    log!(
+        "Launch graduated - Quote: {}, Tokens: {}, LP locked: {}, Burned: {}",
+        quote_amount,
+        token_amount,
+        lp_tokens,
+        tokens_burned
+    );
+
+    emit!(LaunchPoolGraduated {
+        launch_pool: launch.key(),
+        liquidity_pool: pool_key,
+        quote_amount,
+        token_amount,
+        lp_tokens_locked: lp_tokens,
+        tokens_burned,
+    });
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct CreateLaunchPool<'info> {
+    /// Launch of the token, one per mint
+    #[account(
+        init,
+        payer = creator,
+        space = 8 + LaunchPool::INIT_SPACE,
+        seeds = [b"launch_pool", token_mint.key().as_ref()],
+        bump,
+    )]
+    pub launch_pool: Box<Account<'info, LaunchPool>>,
+
+    /// Token being launched
+    pub token_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    /// Token buyers pay in
+    #[account(constraint = quote_mint.key() != token_mint.key() @ AmmError::InvalidLaunchParameters)]
+    pub quote_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    /// Launch's token vault, holding what is left on the curve
+    #[account(
+        init,
+        payer = creator,
+        seeds = [b"launch_token_vault", launch_pool.key().as_ref()],
+        bump,
+        token::mint = token_mint,
+        token::authority = launch_pool,
+        token::token_program = token_program,
+    )]
+    pub token_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Launch's quote vault, holding the raise
+    #[account(
+        init,
+        payer = creator,
+        seeds = [b"launch_quote_vault", launch_pool.key().as_ref()],
+        bump,
+        token::mint = quote_mint,
+        token::authority = launch_pool,
+        token::token_program = token_program,
+    )]
+    pub quote_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Creator's token account (source)
+    #[account(
+        mut,
+        constraint = creator_token_account.mint == token_mint.key(),
+        constraint = creator_token_account.owner == creator.key() @ AmmError::InvalidTokenAccountOwner
+    )]
+    pub creator_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Launch creator, pays for the accounts
+    #[account(mut)]
+    pub creator: Signer<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct BuyLaunchPool<'info> {
+    /// Launch being bought into
+    #[account(
+        mut,
+        seeds = [b"launch_pool", launch_pool.token_mint.as_ref()],
+        bump = launch_pool.bump,
+    )]
+    pub launch_pool: Box<Account<'info, LaunchPool>>,
+
+    /// Token being launched
+    #[account(address = launch_pool.token_mint)]
+    pub token_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    /// Token buyers pay in
+    #[account(address = launch_pool.quote_mint)]
+    pub quote_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    /// Launch's token vault
+    #[account(
+        mut,
+        seeds = [b"launch_token_vault", launch_pool.key().as_ref()],
+        bump,
+    )]
+    pub token_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Launch's quote vault
+    #[account(
+        mut,
+        seeds = [b"launch_quote_vault", launch_pool.key().as_ref()],
+        bump,
+    )]
+    pub quote_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Buyer's quote token account (source)
+    #[account(
+        mut,
+        constraint = buyer_quote_account.mint == quote_mint.key(),
+        constraint = buyer_quote_account.owner == buyer.key() @ AmmError::InvalidTokenAccountOwner
+    )]
+    pub buyer_quote_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Buyer's launch token account (destination)
+    #[account(mut, constraint = buyer_token_account.mint == token_mint.key())]
+    pub buyer_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub buyer: Signer<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct GraduateLaunchPool<'info> {
+    /// Every account `initialize_pool` creates; the payer may be anyone
+    pub initialize: InitializePool<'info>,
+
+    /// Launch that graduates
+    #[account(
+        mut,
+        seeds = [b"launch_pool", launch_pool.token_mint.as_ref()],
+        bump = launch_pool.bump,
+    )]
+    pub launch_pool: Box<Account<'info, LaunchPool>>,
+
+    /// Launch's token vault
+    #[account(
+        mut,
+        seeds = [b"launch_token_vault", launch_pool.key().as_ref()],
+        bump,
+    )]
+    pub token_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Launch's quote vault
+    #[account(
+        mut,
+        seeds = [b"launch_quote_vault", launch_pool.key().as_ref()],
+        bump,
+    )]
+    pub quote_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Holds the graduated pool's LP tokens; nothing can move them out
+    #[account(
+        init,
+        payer = initialize.payer,
+        seeds = [b"launch_lp_vault", launch_pool.key().as_ref()],
+        bump,
+        token::mint = initialize.lp_token_mint,
+        token::authority = launch_pool,
+        token::token_program = token_program,
+    )]
+    pub launch_lp_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Token program of the launch and the new pool
+    #[account(address = initialize.token_program.key())]
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
@@ -0,0 +1,454 @@
+use crate::errors::AmmError;
+use crate::instructions::keeper_tip::{pay_keeper_tip, KeeperTipAccounts};
+use crate::state::{AmmConfig, LimitOrder, LiquidityPool, MAX_KEEPER_TIP_LAMPORTS};
+use anchor_lang::prelude::*;
+use anchor_lang::system_program::{self, Transfer};
+use anchor_spl::token_interface::{
+    self, CloseAccount, Mint, TokenAccount, TokenInterface, TransferChecked,
+};
+
+/// Escrow `input_amount` to be swapped once the pool pays at least
+/// `min_output_amount` for it, with the keeper tip escrowed up front
+pub fn place(
+    ctx: Context<PlaceLimitOrder>,
+    order_id: u64,
+    input_amount: u64,
+    min_output_amount: u64,
+    expiry_timestamp: i64,
+    keeper_tip_lamports: u64,
+) -> Result<()> {
+    ctx.accounts.amm_config.check_not_paused()?;
+    require!(
+        input_amount > 0
+            && min_output_amount > 0
+            && expiry_timestamp > Clock::get()?.unix_timestamp
+            && keeper_tip_lamports <= MAX_KEEPER_TIP_LAMPORTS,
+        AmmError::InvalidLimitOrder
+    );
+
+    let input_mint = ctx.accounts.input_mint.key();
+    let output_mint = ctx.accounts.output_mint.key();
+    {
+        let pool = ctx.accounts.liquidity_pool.load()?;
+        require!(
+            (input_mint == pool.token_a_mint && output_mint == pool.token_b_mint)
+                || (input_mint == pool.token_b_mint && output_mint == pool.token_a_mint),
+            AmmError::InvalidLimitOrder
+        );
+    }
+
+    token_interface::transfer_checked(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            TransferChecked {
+                from: ctx.accounts.owner_input_token_account.to_account_info(),
+                mint: ctx.accounts.input_mint.to_account_info(),
+                to: ctx.accounts.order_vault.to_account_info(),
+                authority: ctx.accounts.owner.to_account_info(),
+            },
+        ),
+        input_amount,
+        ctx.accounts.input_mint.decimals,
+    )?;
+
+    if keeper_tip_lamports > 0 {
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.owner.to_account_info(),
+                    to: ctx.accounts.limit_order.to_account_info(),
+                },
+            ),
+            keeper_tip_lamports,
+        )?;
+    }
+
+    let order = &mut ctx.accounts.limit_order;
+    order.liquidity_pool = ctx.accounts.liquidity_pool.key();
+    order.owner = ctx.accounts.owner.key();
+    order.input_mint = input_mint;
+    order.output_mint = output_mint;
+    order.input_amount = input_amount;
+    order.min_output_amount = min_output_amount;
+    order.expiry_timestamp = expiry_timestamp;
+    order.keeper_tip_lamports = keeper_tip_lamports;
+    order.order_id = order_id;
+    order.bump = ctx.bumps.limit_order;
+
+    log!(
+        "Limit order placed - Input: {}, Min output: {}, Expiry: {}",
+        input_amount,
+        min_output_amount,
+        expiry_timestamp
+    );
+    Ok(())
+}
+
+/// Fill a limit order in full once the pool price satisfies it, paying the
+/// keeper its tip. The order and its vault are closed to the owner.
+pub fn fill(ctx: Context<FillLimitOrder>) -> Result<()> {
+    ctx.accounts.amm_config.check_not_paused()?;
+    let clock = Clock::get()?;
+    let order = &ctx.accounts.limit_order;
+    require!(
+        clock.unix_timestamp <= order.expiry_timestamp,
+        AmmError::LimitOrderExpired
+    );
+
+    let pool = &mut ctx.accounts.liquidity_pool.load_mut()?;
+    let input_is_token_a = order.input_mint == pool.token_a_mint;
+    let (input_vault, output_vault, input_mint, output_mint) = if input_is_token_a {
+        (
+            &ctx.accounts.token_a_vault,
+            &ctx.accounts.token_b_vault,
+            &ctx.accounts.token_a_mint,
+            &ctx.accounts.token_b_mint,
+        )
+    } else {
+        (
+            &ctx.accounts.token_b_vault,
+            &ctx.accounts.token_a_vault,
+            &ctx.accounts.token_b_mint,
+            &ctx.accounts.token_a_mint,
+        )
+    };
+
+    let output_amount = pool.apply_keeper_swap(
+        clock.slot,
+        input_is_token_a,
+        input_vault.amount as u128,
+        output_vault.amount as u128,
+        order.input_amount as u128,
+    )?;
+    require!(
+        output_amount >= order.min_output_amount,
+        AmmError::LimitPriceNotReached
+    );
+
+    let signer = order.signer();
+    let order_seeds = signer.seeds();
+    let order_signer_seeds = &[&order_seeds[..]];
+    token_interface::transfer_checked(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            TransferChecked {
+                from: ctx.accounts.order_vault.to_account_info(),
+                mint: input_mint.to_account_info(),
+                to: input_vault.to_account_info(),
+                authority: order.to_account_info(),
+            },
+            order_signer_seeds,
+        ),
+        order.input_amount,
+        input_mint.decimals,
+    )?;
+
+    let pool_key = ctx.accounts.liquidity_pool.key();
+    let authority_bump = pool.authority_bump;
+    let authority_seeds = &[b"pool_authority", pool_key.as_ref(), &[authority_bump]];
+    token_interface::transfer_checked(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            TransferChecked {
+                from: output_vault.to_account_info(),
+                mint: output_mint.to_account_info(),
+                to: ctx.accounts.owner_output_token_account.to_account_info(),
+                authority: ctx.accounts.pool_authority.to_account_info(),
+            },
+            &[&authority_seeds[..]],
+        ),
+        output_amount,
+        output_mint.decimals,
+    )?;
+
+    token_interface::close_account(CpiContext::new_with_signer(
+        ctx.accounts.token_program.to_account_info(),
+        CloseAccount {
+            account: ctx.accounts.order_vault.to_account_info(),
+            destination: ctx.accounts.owner.to_account_info(),
+            authority: order.to_account_info(),
+        },
+        order_signer_seeds,
+    ))?;
+
+    ctx.accounts.token_a_vault.reload()?;
+    ctx.accounts.token_b_vault.reload()?;
+    pool.sync_reserves(
+        ctx.accounts.token_a_vault.amount,
+        ctx.accounts.token_b_vault.amount,
+    );
+
+    // The pool's keeper tip, paid in token A on top of the owner's lamports
+    let keeper_tip = pool.keeper_tip;
+    let pool_tip = pay_keeper_tip(
+        pool,
+        &pool_key,
+        keeper_tip,
+        KeeperTipAccounts {
+            pool_authority: &ctx.accounts.pool_authority,
+            token_a_mint: Some(&ctx.accounts.token_a_mint),
+            protocol_fee_vault_a: ctx.accounts.protocol_fee_vault_a.as_deref(),
+            keeper_token_a_account: ctx.accounts.keeper_token_a_account.as_deref(),
+            token_program: Some(&ctx.accounts.token_program),
+        },
+    )?;
+
+    let tip = ctx.accounts.limit_order.keeper_tip_lamports;
+    if tip > 0 {
+        **ctx
+            .accounts
+            .limit_order
+            .to_account_info()
+            .try_borrow_mut_lamports()? -= tip;
+        **ctx.accounts.keeper.try_borrow_mut_lamports()? += tip;
+    }
+
+    log!(
+        "Limit order filled - Input: {}, Output: {}, Keeper tip: {}, Pool tip: {}",
+        ctx.accounts.limit_order.input_amount,
+        output_amount,
+        tip,
+        pool_tip
+    );
+    Ok(())
+}
+
+/// Cancel an unfilled limit order, returning the input, tip and rent to the owner
+pub fn cancel(ctx: Context<CancelLimitOrder>) -> Result<()> {
+    let remaining = ctx.accounts.order_vault.amount;
+    let signer = ctx.accounts.limit_order.signer();
+    let order_seeds = signer.seeds();
+    let signer_seeds = &[&order_seeds[..]];
+
+    token_interface::transfer_checked(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            TransferChecked {
+                from: ctx.accounts.order_vault.to_account_info(),
+                mint: ctx.accounts.input_mint.to_account_info(),
+                to: ctx.accounts.owner_input_token_account.to_account_info(),
+                authority: ctx.accounts.limit_order.to_account_info(),
+            },
+            signer_seeds,
+        ),
+        remaining,
+        ctx.accounts.input_mint.decimals,
+    )?;
+
+    token_interface::close_account(CpiContext::new_with_signer(
+        ctx.accounts.token_program.to_account_info(),
+        CloseAccount {
+            account: ctx.accounts.order_vault.to_account_info(),
+            destination: ctx.accounts.owner.to_account_info(),
+            authority: ctx.accounts.limit_order.to_account_info(),
+        },
+        signer_seeds,
+    ))?;
+
+    log!("Limit order cancelled - Returned: {}", remaining);
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(order_id: u64)]
+pub struct PlaceLimitOrder<'info> {
+    /// Pool the order fills against
+    pub liquidity_pool: AccountLoader<'info, LiquidityPool>,
+
+    /// Global config, checked for the emergency pause
+    #[account(seeds = [b"amm_config"], bump)]
+    pub amm_config: Box<Account<'info, AmmConfig>>,
+
+    /// New limit order
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + LimitOrder::INIT_SPACE,
+        seeds = [
+            b"limit_order",
+            liquidity_pool.key().as_ref(),
+            owner.key().as_ref(),
+            &order_id.to_le_bytes()
+        ],
+        bump,
+    )]
+    pub limit_order: Box<Account<'info, LimitOrder>>,
+
+    /// Token sold
+    pub input_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    /// Token bought
+    pub output_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    /// Escrow of the order's input
+    #[account(
+        init,
+        payer = owner,
+        seeds = [b"limit_order_vault", limit_order.key().as_ref()],
+        bump,
+        token::mint = input_mint,
+        token::authority = limit_order,
+    )]
+    pub order_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Owner's input token account (source)
+    #[account(
+        mut,
+        constraint = owner_input_token_account.mint == input_mint.key(),
+        constraint = owner_input_token_account.owner == owner.key() @ AmmError::InvalidTokenAccountOwner
+    )]
+    pub owner_input_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Order owner, funds the escrow, the keeper tip and the accounts
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct FillLimitOrder<'info> {
+    /// Order being filled, closed to the owner
+    #[account(
+        mut,
+        close = owner,
+        has_one = owner @ AmmError::Unauthorized,
+        seeds = [
+            b"limit_order",
+            liquidity_pool.key().as_ref(),
+            owner.key().as_ref(),
+            &limit_order.order_id.to_le_bytes()
+        ],
+        bump = limit_order.bump,
+    )]
+    pub limit_order: Box<Account<'info, LimitOrder>>,
+
+    /// Escrow of the order's input
+    #[account(
+        mut,
+        seeds = [b"limit_order_vault", limit_order.key().as_ref()],
+        bump
+    )]
+    pub order_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Pool the order fills against
+    #[account(mut, address = limit_order.liquidity_pool)]
+    pub liquidity_pool: AccountLoader<'info, LiquidityPool>,
+
+    /// Global config, checked for the emergency pause
+    #[account(seeds = [b"amm_config"], bump)]
+    pub amm_config: Box<Account<'info, AmmConfig>>,
+
+    /// Pool authority PDA
+    #[account(
+        seeds = [b"pool_authority", liquidity_pool.key().as_ref()],
+        bump = liquidity_pool.load()?.authority_bump
+    )]
+    pub pool_authority: SystemAccount<'info>,
+
+    /// Token A mint
+    #[account(address = liquidity_pool.load()?.token_a_mint)]
+    pub token_a_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    /// Token B mint
+    #[account(address = liquidity_pool.load()?.token_b_mint)]
+    pub token_b_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    /// Token A vault
+    #[account(
+        mut,
+        seeds = [b"token_a_vault", liquidity_pool.key().as_ref()],
+        bump = liquidity_pool.load()?.token_a_vault_bump
+    )]
+    pub token_a_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Token B vault
+    #[account(
+        mut,
+        seeds = [b"token_b_vault", liquidity_pool.key().as_ref()],
+        bump = liquidity_pool.load()?.token_b_vault_bump
+    )]
+    pub token_b_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Owner's output token account (destination)
+    #[account(
+        mut,
+        constraint = owner_output_token_account.mint == limit_order.output_mint,
+        constraint = owner_output_token_account.owner == owner.key() @ AmmError::InvalidTokenAccountOwner
+    )]
+    pub owner_output_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Order owner, receives the order and vault rent
+    /// CHECK: must match the order's owner
+    #[account(mut)]
+    pub owner: UncheckedAccount<'info>,
+
+    /// Keeper filling the order, receives the tip
+    #[account(mut)]
+    pub keeper: Signer<'info>,
+
+    /// Protocol fee vault for token A, holds the pool's keeper tip budget;
+    /// pass it with the keeper's token A account to collect the pool tip
+    #[account(
+        mut,
+        seeds = [
+            b"protocol_fee_vault",
+            liquidity_pool.key().as_ref(),
+            token_a_mint.key().as_ref()
+        ],
+        bump,
+    )]
+    pub protocol_fee_vault_a: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    /// Keeper's token A account receiving the pool tip
+    #[account(mut, constraint = keeper_token_a_account.mint == token_a_mint.key())]
+    pub keeper_token_a_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct CancelLimitOrder<'info> {
+    /// Order being cancelled
+    #[account(
+        mut,
+        close = owner,
+        has_one = owner @ AmmError::Unauthorized,
+        seeds = [
+            b"limit_order",
+            limit_order.liquidity_pool.as_ref(),
+            owner.key().as_ref(),
+            &limit_order.order_id.to_le_bytes()
+        ],
+        bump = limit_order.bump,
+    )]
+    pub limit_order: Box<Account<'info, LimitOrder>>,
+
+    /// Escrow of the order's input
+    #[account(
+        mut,
+        seeds = [b"limit_order_vault", limit_order.key().as_ref()],
+        bump
+    )]
+    pub order_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Token sold by the order
+    #[account(address = limit_order.input_mint)]
+    pub input_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    /// Owner's input token account (destination)
+    #[account(
+        mut,
+        constraint = owner_input_token_account.mint == input_mint.key(),
+        constraint = owner_input_token_account.owner == owner.key() @ AmmError::InvalidTokenAccountOwner
+    )]
+    pub owner_input_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Order owner, receives the input, tip and rent
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
@@ -0,0 +1,430 @@
+use crate::errors::AmmError;
+use crate::events::FeesClaimed;
+use crate::instructions::memo_transfer::memo_if_required;
+use crate::math;
+use crate::state::{LiquidityPool, LiquidityPosition, BASIS_POINTS_DIVISOR};
+use anchor_lang::prelude::*;
+use anchor_spl::memo::Memo;
+use anchor_spl::token_interface::{
+    self, Burn, Mint, TokenAccount, TokenInterface, TransferChecked,
+};
+
+/// Open an empty fee-tracking position and its LP vault. Deposits that pass the
+/// position mint into the vault, and only the position can burn from it, so
+/// LP the owner already holds or receives from another wallet never joins it:
+/// its fees, withdraw-only size, deposit history and withdrawal cooldown all
+/// describe liquidity it actually provided.
+pub fn open(ctx: Context<OpenPosition>) -> Result<()> {
+    let pool = ctx.accounts.liquidity_pool.load()?;
+    let position = &mut ctx.accounts.liquidity_position;
+    position.liquidity_pool = ctx.accounts.liquidity_pool.key();
+    position.owner = ctx.accounts.owner.key();
+    position.fee_growth_checkpoint = pool.fee_growth_per_lp;
+    position.bump = ctx.bumps.liquidity_position;
+    position.lp_vault_bump = ctx.bumps.position_lp_vault;
+    position.opened_at = Clock::get()?.unix_timestamp;
+    position.entry_reserve_a = pool.reserve_a;
+    position.entry_reserve_b = pool.reserve_b;
+    position.entry_fee_growth = pool.fee_growth_per_lp;
+
+    log!("Liquidity position opened - Owner: {}", position.owner);
+    Ok(())
+}
+
+/// Pay out the fees a position has earned. Fees accrue as LP tokens in
+/// `fees_owed_lp`; those are burned for their share of the tracked reserves,
+/// while the position's principal liquidity stays in the pool. The burn is a
+/// withdrawal, so it goes through the same checks as one, the withdraw
+/// cooldown included.
+pub fn claim_fees(ctx: Context<ClaimFees>) -> Result<()> {
+    let pool_key = ctx.accounts.liquidity_pool.key();
+    let pool = &mut ctx.accounts.liquidity_pool.load_mut()?;
+    pool.check_lp_supply(ctx.accounts.lp_token_mint.supply)?;
+
+    let position = &mut ctx.accounts.liquidity_position;
+    position.settle(pool.fee_growth_per_lp)?;
+    let lp_tokens_to_burn = position.fees_owed_lp;
+    if lp_tokens_to_burn == 0 {
+        log!("No fees to claim");
+        return Ok(());
+    }
+    let clock = Clock::get()?;
+    pool.check_withdrawal_allowed(clock.unix_timestamp, Some(position.tracked_lp()))?;
+    let payout_bps = pool.position_payout_bps(
+        Some(position),
+        lp_tokens_to_burn,
+        clock.unix_timestamp,
+        clock.slot,
+    )?;
+    require!(
+        ctx.accounts.position_lp_vault.amount >= lp_tokens_to_burn,
+        AmmError::InsufficientLpTokens
+    );
+
+    // Price the payout on the tracked reserves, so tokens sent straight to a
+    // vault stay skimmable instead of being claimed as fees
+    let (token_a_amount, token_b_amount) = fee_payout(
+        lp_tokens_to_burn,
+        pool.reserve_a,
+        pool.reserve_b,
+        pool.total_lp_tokens_issued,
+        payout_bps,
+    )?;
+
+    // Burn the fee LP out of the position's vault before anything leaves the
+    // pool vaults, as withdraw does
+    let owner_key = ctx.accounts.owner.key();
+    let position_seeds = &[
+        b"liquidity_position",
+        pool_key.as_ref(),
+        owner_key.as_ref(),
+        &[position.bump],
+    ];
+    let lp_balance = ctx.accounts.position_lp_vault.amount;
+    token_interface::burn(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            Burn {
+                mint: ctx.accounts.lp_token_mint.to_account_info(),
+                from: ctx.accounts.position_lp_vault.to_account_info(),
+                authority: position.to_account_info(),
+            },
+            &[&position_seeds[..]],
+        ),
+        lp_tokens_to_burn,
+    )?;
+    pool.total_lp_tokens_issued = pool
+        .total_lp_tokens_issued
+        .checked_sub(lp_tokens_to_burn)
+        .ok_or(AmmError::MathOverflow)?;
+
+    // The burn must have taken exactly the accrued fee LP off the vault and
+    // the mint supply
+    ctx.accounts.position_lp_vault.reload()?;
+    ctx.accounts.lp_token_mint.reload()?;
+    require!(
+        lp_balance.checked_sub(ctx.accounts.position_lp_vault.amount) == Some(lp_tokens_to_burn),
+        AmmError::InsufficientLpTokens
+    );
+    pool.check_lp_supply(ctx.accounts.lp_token_mint.supply)?;
+
+    let authority_bump = pool.authority_bump;
+    let authority_seeds = &[b"pool_authority", pool_key.as_ref(), &[authority_bump]];
+    let signer_seeds = &[&authority_seeds[..]];
+
+    let accounts = &ctx.accounts;
+    for (vault, mint, destination, token_program, amount) in [
+        (
+            &accounts.token_a_vault,
+            &accounts.token_a_mint,
+            &accounts.owner_token_a_account,
+            &accounts.token_a_program,
+            token_a_amount,
+        ),
+        (
+            &accounts.token_b_vault,
+            &accounts.token_b_mint,
+            &accounts.owner_token_b_account,
+            &accounts.token_b_program,
+            token_b_amount,
+        ),
+    ] {
+        if amount > 0 {
+            memo_if_required(
+                &destination.to_account_info(),
+                &accounts.memo_program,
+                "ammv2 claim fees",
+            )?;
+            token_interface::transfer_checked(
+                CpiContext::new_with_signer(
+                    token_program.to_account_info(),
+                    TransferChecked {
+                        from: vault.to_account_info(),
+                        mint: mint.to_account_info(),
+                        to: destination.to_account_info(),
+                        authority: accounts.pool_authority.to_account_info(),
+                    },
+                    signer_seeds,
+                ),
+                amount,
+                mint.decimals,
+            )?;
+        }
+    }
+
+    ctx.accounts
+        .liquidity_position
+        .record_fee_claim(token_a_amount, token_b_amount);
+
+    // The payout leaves the reserves; any untracked vault surplus stays skimmable
+    let reserve_a = pool
+        .reserve_a
+        .checked_sub(token_a_amount)
+        .ok_or(AmmError::MathOverflow)?;
+    let reserve_b = pool
+        .reserve_b
+        .checked_sub(token_b_amount)
+        .ok_or(AmmError::MathOverflow)?;
+    pool.sync_reserves(reserve_a, reserve_b);
+
+    log!(
+        "Fees claimed - Token A: {}, Token B: {}, LP tokens burned: {}",
+        token_a_amount,
+        token_b_amount,
+        lp_tokens_to_burn
+    );
+
+    emit!(FeesClaimed {
+        liquidity_pool: pool_key,
+        event_seq: pool.next_event_seq(),
+        owner: ctx.accounts.owner.key(),
+        lp_tokens_burned: lp_tokens_to_burn,
+        token_a_amount,
+        token_b_amount,
+    });
+    Ok(())
+}
+
+/// Claimable fees returned by `pending_fees`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct PendingFees {
+    /// LP tokens worth of fees `claim_fees` would burn
+    pub fees_owed_lp: u64,
+
+    /// Token A `claim_fees` would pay out
+    pub token_a_amount: u64,
+
+    /// Token B `claim_fees` would pay out
+    pub token_b_amount: u64,
+}
+
+/// Compute what `claim_fees` would pay the position right now, including fees
+/// accrued since its last checkpoint, without changing any state. The result
+/// is returned as instruction return data.
+pub fn pending_fees(ctx: Context<QueryPendingFees>) -> Result<PendingFees> {
+    let pool = ctx.accounts.liquidity_pool.load()?;
+    let mut position = (*ctx.accounts.liquidity_position).clone();
+    position.settle(pool.fee_growth_per_lp)?;
+
+    let (token_a_amount, token_b_amount) = if position.fees_owed_lp > 0 {
+        let clock = Clock::get()?;
+        let payout_bps = pool.position_payout_bps(
+            Some(&position),
+            position.fees_owed_lp,
+            clock.unix_timestamp,
+            clock.slot,
+        )?;
+        fee_payout(
+            position.fees_owed_lp,
+            pool.reserve_a,
+            pool.reserve_b,
+            pool.total_lp_tokens_issued,
+            payout_bps,
+        )?
+    } else {
+        (0, 0)
+    };
+
+    Ok(PendingFees {
+        fees_owed_lp: position.fees_owed_lp,
+        token_a_amount,
+        token_b_amount,
+    })
+}
+
+/// Tokens paid for burning `lp_tokens` of accrued fees: the same proportional
+/// payout as a withdrawal, less any JIT penalty, rounded down in the pool's favor
+fn fee_payout(
+    lp_tokens: u64,
+    reserve_a: u64,
+    reserve_b: u64,
+    total_lp_supply: u64,
+    payout_bps: u128,
+) -> Result<(u64, u64)> {
+    let payout = |balance: u64| -> Result<u64> {
+        math::to_u64(math::mul_div_floor(
+            math::mul_div_floor(lp_tokens as u128, balance as u128, total_lp_supply as u128)?,
+            payout_bps,
+            BASIS_POINTS_DIVISOR,
+        )?)
+    };
+    Ok((payout(reserve_a)?, payout(reserve_b)?))
+}
+
+#[derive(Accounts)]
+pub struct OpenPosition<'info> {
+    /// Pool state account
+    pub liquidity_pool: AccountLoader<'info, LiquidityPool>,
+
+    /// The owner's position in the pool
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + LiquidityPosition::INIT_SPACE,
+        seeds = [
+            b"liquidity_position",
+            liquidity_pool.key().as_ref(),
+            owner.key().as_ref()
+        ],
+        bump,
+    )]
+    pub liquidity_position: Box<Account<'info, LiquidityPosition>>,
+
+    /// LP token mint
+    #[account(
+        seeds = [b"lp_token_mint", liquidity_pool.key().as_ref()],
+        bump = liquidity_pool.load()?.lp_token_mint_bump
+    )]
+    pub lp_token_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    /// Holds the LP tokens minted through the position
+    #[account(
+        init,
+        payer = owner,
+        seeds = [b"position_lp_vault", liquidity_position.key().as_ref()],
+        bump,
+        token::mint = lp_token_mint,
+        token::authority = liquidity_position,
+        token::token_program = token_program,
+    )]
+    pub position_lp_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Position owner, pays for the position and its LP vault
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    /// Token program of the LP mint
+    pub token_program: Interface<'info, TokenInterface>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimFees<'info> {
+    /// Pool state account
+    #[account(mut)]
+    pub liquidity_pool: AccountLoader<'info, LiquidityPool>,
+
+    /// Pool authority PDA
+    #[account(
+        seeds = [b"pool_authority", liquidity_pool.key().as_ref()],
+        bump = liquidity_pool.load()?.authority_bump
+    )]
+    pub pool_authority: SystemAccount<'info>,
+
+    /// Token A mint
+    #[account(address = liquidity_pool.load()?.token_a_mint)]
+    pub token_a_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    /// Token B mint
+    #[account(address = liquidity_pool.load()?.token_b_mint)]
+    pub token_b_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    /// Token A vault
+    #[account(
+        mut,
+        seeds = [b"token_a_vault", liquidity_pool.key().as_ref()],
+        bump = liquidity_pool.load()?.token_a_vault_bump
+    )]
+    pub token_a_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Token B vault
+    #[account(
+        mut,
+        seeds = [b"token_b_vault", liquidity_pool.key().as_ref()],
+        bump = liquidity_pool.load()?.token_b_vault_bump
+    )]
+    pub token_b_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// LP token mint
+    #[account(
+        mut,
+        seeds = [b"lp_token_mint", liquidity_pool.key().as_ref()],
+        bump = liquidity_pool.load()?.lp_token_mint_bump
+    )]
+    pub lp_token_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    /// The owner's position in the pool
+    #[account(
+        mut,
+        seeds = [
+            b"liquidity_position",
+            liquidity_pool.key().as_ref(),
+            owner.key().as_ref()
+        ],
+        bump = liquidity_position.bump,
+    )]
+    pub liquidity_position: Box<Account<'info, LiquidityPosition>>,
+
+    /// Position owner
+    pub owner: Signer<'info>,
+
+    /// Owner's token A account (destination)
+    #[account(
+        mut,
+        constraint = owner_token_a_account.mint == token_a_mint.key(),
+        constraint = owner_token_a_account.owner == owner.key() @ AmmError::InvalidTokenAccountOwner
+    )]
+    pub owner_token_a_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Owner's token B account (destination)
+    #[account(
+        mut,
+        constraint = owner_token_b_account.mint == token_b_mint.key(),
+        constraint = owner_token_b_account.owner == owner.key() @ AmmError::InvalidTokenAccountOwner
+    )]
+    pub owner_token_b_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// The position's LP vault, the accrued fee LP is burned from it
+    #[account(
+        mut,
+        seeds = [b"position_lp_vault", liquidity_position.key().as_ref()],
+        bump = liquidity_position.lp_vault_bump
+    )]
+    pub position_lp_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Token program of the LP mint
+    pub token_program: Interface<'info, TokenInterface>,
+
+    /// Memo program, required when a destination requires transfer memos
+    pub memo_program: Option<Program<'info, Memo>>,
+
+    /// Token program of token A
+    #[account(address = *token_a_mint.to_account_info().owner @ AmmError::InvalidTokenProgram)]
+    pub token_a_program: Interface<'info, TokenInterface>,
+
+    /// Token program of token B
+    #[account(address = *token_b_mint.to_account_info().owner @ AmmError::InvalidTokenProgram)]
+    pub token_b_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct QueryPendingFees<'info> {
+    /// Pool state account
+    pub liquidity_pool: AccountLoader<'info, LiquidityPool>,
+
+    /// Position to value
+    #[account(
+        seeds = [
+            b"liquidity_position",
+            liquidity_pool.key().as_ref(),
+            liquidity_position.owner.as_ref()
+        ],
+        bump = liquidity_position.bump,
+    )]
+    pub liquidity_position: Box<Account<'info, LiquidityPosition>>,
+
+    /// Token A vault
+    #[account(
+        seeds = [b"token_a_vault", liquidity_pool.key().as_ref()],
+        bump = liquidity_pool.load()?.token_a_vault_bump
+    )]
+    pub token_a_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Token B vault
+    #[account(
+        seeds = [b"token_b_vault", liquidity_pool.key().as_ref()],
+        bump = liquidity_pool.load()?.token_b_vault_bump
+    )]
+    pub token_b_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+}
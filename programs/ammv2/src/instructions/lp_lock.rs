@@ -0,0 +1,196 @@
+use crate::errors::AmmError;
+use crate::state::{LiquidityPool, LpLock};
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{
+    self, CloseAccount, Mint, TokenAccount, TokenInterface, TransferChecked,
+};
+
+/// Escrow `amount` LP tokens until `unlock_timestamp`. Locking again tops up
+/// the owner's lock and may extend it, but never brings the unlock forward.
+pub fn lock(ctx: Context<LockLpTokens>, amount: u64, unlock_timestamp: i64) -> Result<()> {
+    require!(amount > 0, AmmError::InvalidLpTokenAmount);
+    require!(
+        ctx.accounts.owner_lp_token_account.amount >= amount,
+        AmmError::InsufficientLpTokens
+    );
+
+    let now = Clock::get()?.unix_timestamp;
+    let lp_lock = &mut ctx.accounts.lp_lock;
+    require!(
+        unlock_timestamp > now && unlock_timestamp >= lp_lock.unlock_timestamp,
+        AmmError::InvalidUnlockTimestamp
+    );
+
+    token_interface::transfer_checked(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            TransferChecked {
+                from: ctx.accounts.owner_lp_token_account.to_account_info(),
+                mint: ctx.accounts.lp_token_mint.to_account_info(),
+                to: ctx.accounts.lp_lock_vault.to_account_info(),
+                authority: ctx.accounts.owner.to_account_info(),
+            },
+        ),
+        amount,
+        ctx.accounts.lp_token_mint.decimals,
+    )?;
+
+    if lp_lock.amount == 0 {
+        lp_lock.liquidity_pool = ctx.accounts.liquidity_pool.key();
+        lp_lock.owner = ctx.accounts.owner.key();
+        lp_lock.locked_at = now;
+        lp_lock.bump = ctx.bumps.lp_lock;
+    }
+    lp_lock.amount = lp_lock
+        .amount
+        .checked_add(amount)
+        .ok_or(AmmError::MathOverflow)?;
+    lp_lock.unlock_timestamp = unlock_timestamp;
+
+    log!(
+        "Locked {} LP tokens, lock now {} - Unlocks at: {}",
+        amount,
+        lp_lock.amount,
+        unlock_timestamp
+    );
+    Ok(())
+}
+
+/// Return every locked LP token to the owner once the lock has expired,
+/// closing the lock and its vault
+pub fn unlock(ctx: Context<UnlockLpTokens>) -> Result<()> {
+    require!(
+        Clock::get()?.unix_timestamp >= ctx.accounts.lp_lock.unlock_timestamp,
+        AmmError::LpTokensLocked
+    );
+
+    let amount = ctx.accounts.lp_lock_vault.amount;
+    let signer = ctx.accounts.lp_lock.signer();
+    let lock_seeds = signer.seeds();
+    let signer_seeds = &[&lock_seeds[..]];
+
+    if amount > 0 {
+        token_interface::transfer_checked(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.lp_lock_vault.to_account_info(),
+                    mint: ctx.accounts.lp_token_mint.to_account_info(),
+                    to: ctx.accounts.owner_lp_token_account.to_account_info(),
+                    authority: ctx.accounts.lp_lock.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            amount,
+            ctx.accounts.lp_token_mint.decimals,
+        )?;
+    }
+
+    token_interface::close_account(CpiContext::new_with_signer(
+        ctx.accounts.token_program.to_account_info(),
+        CloseAccount {
+            account: ctx.accounts.lp_lock_vault.to_account_info(),
+            destination: ctx.accounts.owner.to_account_info(),
+            authority: ctx.accounts.lp_lock.to_account_info(),
+        },
+        signer_seeds,
+    ))?;
+
+    log!("Unlocked {} LP tokens", amount);
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct LockLpTokens<'info> {
+    /// Pool whose LP tokens are locked
+    pub liquidity_pool: AccountLoader<'info, LiquidityPool>,
+
+    /// The owner's lock in the pool, created on first lock
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = 8 + LpLock::INIT_SPACE,
+        seeds = [b"lp_lock", liquidity_pool.key().as_ref(), owner.key().as_ref()],
+        bump,
+    )]
+    pub lp_lock: Box<Account<'info, LpLock>>,
+
+    /// Escrow of the locked LP tokens
+    #[account(
+        init_if_needed,
+        payer = owner,
+        seeds = [b"lp_lock_vault", liquidity_pool.key().as_ref(), owner.key().as_ref()],
+        bump,
+        token::mint = lp_token_mint,
+        token::authority = lp_lock,
+    )]
+    pub lp_lock_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Pool LP token mint
+    #[account(
+        seeds = [b"lp_token_mint", liquidity_pool.key().as_ref()],
+        bump = liquidity_pool.load()?.lp_token_mint_bump
+    )]
+    pub lp_token_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    /// Owner's LP token account (source)
+    #[account(
+        mut,
+        constraint = owner_lp_token_account.mint == lp_token_mint.key(),
+        constraint = owner_lp_token_account.owner == owner.key() @ AmmError::InvalidTokenAccountOwner
+    )]
+    pub owner_lp_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// LP token owner, pays for the lock and its vault
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UnlockLpTokens<'info> {
+    /// The owner's expired lock, closed to the owner
+    #[account(
+        mut,
+        close = owner,
+        has_one = owner @ AmmError::Unauthorized,
+        seeds = [b"lp_lock", lp_lock.liquidity_pool.as_ref(), owner.key().as_ref()],
+        bump = lp_lock.bump,
+    )]
+    pub lp_lock: Box<Account<'info, LpLock>>,
+
+    /// Escrow of the locked LP tokens
+    #[account(
+        mut,
+        seeds = [
+            b"lp_lock_vault",
+            lp_lock.liquidity_pool.as_ref(),
+            owner.key().as_ref()
+        ],
+        bump
+    )]
+    pub lp_lock_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Pool LP token mint
+    #[account(
+        seeds = [b"lp_token_mint", lp_lock.liquidity_pool.as_ref()],
+        bump
+    )]
+    pub lp_token_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    /// Owner's LP token account (destination)
+    #[account(
+        mut,
+        constraint = owner_lp_token_account.mint == lp_token_mint.key(),
+        constraint = owner_lp_token_account.owner == owner.key() @ AmmError::InvalidTokenAccountOwner
+    )]
+    pub owner_lp_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Lock owner, receives the LP tokens and rent
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
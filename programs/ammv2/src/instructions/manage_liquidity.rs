@@ -1,16 +1,44 @@
 use crate::errors::AmmError;
-use crate::state::LiquidityPool;
+use crate::events::{LiquidityDeposited, LiquidityWithdrawn};
+use crate::instructions::memo_transfer::memo_if_required;
+use crate::math;
+use crate::state::{
+    AmmConfig, LiquidityPool, LiquidityPosition, LpWhitelistEntry, SubAccount,
+    BASIS_POINTS_DIVISOR, POOL_OP_DEPOSIT,
+};
 use anchor_lang::prelude::*;
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::memo::{self, BuildMemo, Memo};
 use anchor_spl::token_interface::{
-    self, Burn, Mint, MintTo, TokenAccount, TokenInterface, Transfer,
+    self, Burn, Mint, MintTo, TokenAccount, TokenInterface, TransferChecked,
 };
 
+/// Maximum length in bytes of a withdrawal note
+pub const MAX_WITHDRAWAL_NOTE_LEN: usize = 256;
+
 /// Add liquidity to the pool
 pub fn deposit(
-    ctx: Context<ManageLiquidity>,
+    ctx: Context<DepositLiquidity>,
     token_a_amount: u64,
     token_b_amount: u64,
+    client_order_id: Option<u64>,
 ) -> Result<()> {
+    ctx.accounts.amm_config.check_not_paused()?;
+    {
+        let pool = ctx.accounts.liquidity_pool.load()?;
+        require!(pool.withdraw_only == 0, AmmError::PoolWithdrawOnly);
+        pool.check_operation_enabled(POOL_OP_DEPOSIT)?;
+        require!(
+            pool.lp_whitelist_enabled == 0 || ctx.accounts.lp_whitelist_entry.is_some(),
+            AmmError::NotWhitelisted
+        );
+        // The JIT penalty and withdrawal cooldown key off the position's last deposit
+        require!(
+            !pool.tracks_positions() || ctx.accounts.liquidity_position.is_some(),
+            AmmError::LiquidityPositionRequired
+        );
+    }
+
     // Verify user has sufficient balance
     require!(
         ctx.accounts.user_token_a_account.amount >= token_a_amount,
@@ -21,64 +49,127 @@ pub fn deposit(
         AmmError::InsufficientBalance
     );
 
-    let vault_a_balance = ctx.accounts.token_a_vault.amount;
-    let vault_b_balance = ctx.accounts.token_b_vault.amount;
-    let pool = &mut ctx.accounts.liquidity_pool;
+    // Token accounts held by a sub-account move with the sub-account PDA as signer
+    let user_authority = user_token_authority(&ctx.accounts.sub_account, &ctx.accounts.user)?;
+    let sub_account_signer = ctx.accounts.sub_account.as_ref().map(|sub| sub.signer());
+    let sub_account_seeds = sub_account_signer.as_ref().map(|signer| signer.seeds());
+    let user_signer = sub_account_seeds.as_ref().map(|seeds| [&seeds[..]]);
+    let user_signer_seeds: &[&[&[u8]]] = user_signer.as_ref().map_or(&[], |signer| &signer[..]);
+
+    // Deposits are priced on the tracked reserves: tokens sent straight to a
+    // vault are left to skim rather than setting the ratio or the LP price
+    let vault_a_before = ctx.accounts.token_a_vault.amount;
+    let vault_b_before = ctx.accounts.token_b_vault.amount;
+    let pool = &mut ctx.accounts.liquidity_pool.load_mut()?;
+    pool.check_lp_supply(ctx.accounts.lp_token_mint.supply)?;
+    let reserve_a = pool.reserve_a;
+    let reserve_b = pool.reserve_b;
 
     let actual_token_a_deposit = token_a_amount;
-    let actual_token_b_deposit: u64;
-    let lp_tokens_to_mint: u64;
+    let is_initial_deposit = pool.total_lp_tokens_issued == 0;
 
-    msg!(
-        "Current vault balances - Token A: {}, Token B: {}",
-        vault_a_balance,
-        vault_b_balance
+    log!(
+        "Current reserves - Token A: {}, Token B: {}",
+        reserve_a,
+        reserve_b
     );
 
-    // Initial liquidity deposit (pool is empty)
-    if vault_a_balance == 0 && vault_b_balance == 0 {
-        msg!(
+    // Initial liquidity deposit (no LP tokens outstanding)
+    let actual_token_b_deposit = if is_initial_deposit {
+        log!(
             "Initial deposit - Token A: {}, Token B: {}",
             token_a_amount,
             token_b_amount
         );
-
-        // For first deposit, LP tokens = geometric mean of deposits (divided by 2 via bit shift)
-        lp_tokens_to_mint = (token_a_amount + token_b_amount) >> 1;
-        actual_token_b_deposit = token_b_amount;
+        token_b_amount
     } else {
-        // Subsequent deposits must maintain pool ratio
-        // Calculate required token B based on token A deposit and current pool ratio
-        let exchange_rate_b_per_a = (vault_b_balance as u128)
-            .checked_div(vault_a_balance as u128)
-            .ok_or(AmmError::MathOverflow)?;
-
-        let required_token_b = (token_a_amount as u128)
-            .checked_mul(exchange_rate_b_per_a)
-            .ok_or(AmmError::MathOverflow)? as u64;
-
-        msg!(
-            "Exchange rate (B/A): {}, Required Token B: {}",
-            exchange_rate_b_per_a,
-            required_token_b
-        );
+        // Subsequent deposits must maintain pool ratio, computed at the pair's
+        // common precision. Required token B is rounded up at every step so the
+        // depositor never underpays.
+        let decimals_a = ctx.accounts.token_a_mint.decimals;
+        let decimals_b = ctx.accounts.token_b_mint.decimals;
+        let common_decimals = decimals_a.max(decimals_b);
+        let required_token_b_normalized = math::mul_div_ceil(
+            math::normalize(token_a_amount as u128, decimals_a, common_decimals)?,
+            math::normalize(reserve_b as u128, decimals_b, common_decimals)?,
+            math::normalize(reserve_a as u128, decimals_a, common_decimals)?,
+        )?;
+        let required_token_b = math::to_u64(math::denormalize_ceil(
+            required_token_b_normalized,
+            decimals_b,
+            common_decimals,
+        )?)?;
+
+        log!("Required Token B: {}", required_token_b);
+
+        require!(required_token_b <= token_b_amount, AmmError::RatioMismatch);
+
+        required_token_b
+    };
 
-        require!(
-            required_token_b <= token_b_amount,
-            AmmError::InsufficientBalance
-        );
+    // Transfer token A from user to vault
+    token_interface::transfer_checked(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_a_program.to_account_info(),
+            TransferChecked {
+                from: ctx.accounts.user_token_a_account.to_account_info(),
+                mint: ctx.accounts.token_a_mint.to_account_info(),
+                to: ctx.accounts.token_a_vault.to_account_info(),
+                authority: user_authority.clone(),
+            },
+            user_signer_seeds,
+        ),
+        actual_token_a_deposit,
+        ctx.accounts.token_a_mint.decimals,
+    )?;
+
+    // Transfer token B from user to vault
+    token_interface::transfer_checked(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_b_program.to_account_info(),
+            TransferChecked {
+                from: ctx.accounts.user_token_b_account.to_account_info(),
+                mint: ctx.accounts.token_b_mint.to_account_info(),
+                to: ctx.accounts.token_b_vault.to_account_info(),
+                authority: user_authority,
+            },
+            user_signer_seeds,
+        ),
+        actual_token_b_deposit,
+        ctx.accounts.token_b_mint.decimals,
+    )?;
 
-        actual_token_b_deposit = required_token_b;
+    // LP is priced on what the vaults received, net of any transfer fee
+    ctx.accounts.token_a_vault.reload()?;
+    ctx.accounts.token_b_vault.reload()?;
+    let received_a = ctx.accounts.token_a_vault.amount - vault_a_before;
+    let received_b = ctx.accounts.token_b_vault.amount - vault_b_before;
 
-        // LP tokens minted proportional to share of pool
-        // LP_mint = (deposit_B * total_LP) / vault_B_balance
-        lp_tokens_to_mint = (actual_token_b_deposit as u128)
-            .checked_mul(pool.total_lp_tokens_issued as u128)
-            .and_then(|v| v.checked_div(vault_b_balance as u128))
-            .ok_or(AmmError::MathOverflow)? as u64;
+    let lp_tokens_to_mint = if is_initial_deposit {
+        // Dust-seeded pools are trivial to manipulate
+        require!(
+            received_a >= pool.min_initial_deposit_a && received_b >= pool.min_initial_deposit_b,
+            AmmError::InitialLiquidityTooSmall
+        );
 
-        msg!("LP tokens to mint: {}", lp_tokens_to_mint);
-    }
+        // For first deposit, LP tokens = geometric mean of deposits
+        math::to_u64(math::checked_sqrt(
+            (received_a as u128)
+                .checked_mul(received_b as u128)
+                .ok_or(AmmError::MathOverflow)?,
+        )?)?
+    } else {
+        // LP tokens minted proportional to the smaller share contributed, rounded down
+        // LP_mint = min(received_A * total_LP / reserve_A, received_B * total_LP / reserve_B)
+        let total_lp_supply = pool.total_lp_tokens_issued as u128;
+        let lp_for_token_a =
+            math::mul_div_floor(received_a as u128, total_lp_supply, reserve_a as u128)?;
+        let lp_for_token_b =
+            math::mul_div_floor(received_b as u128, total_lp_supply, reserve_b as u128)?;
+        math::to_u64(lp_for_token_a.min(lp_for_token_b))?
+    };
+
+    log!("LP tokens to mint: {}", lp_tokens_to_mint);
 
     require!(lp_tokens_to_mint > 0, AmmError::InvalidLpTokenAmount);
 
@@ -87,19 +178,33 @@ pub fn deposit(
         .total_lp_tokens_issued
         .checked_add(lp_tokens_to_mint)
         .ok_or(AmmError::MathOverflow)?;
+    let reserve_a = reserve_a
+        .checked_add(received_a)
+        .ok_or(AmmError::MathOverflow)?;
+    let reserve_b = reserve_b
+        .checked_add(received_b)
+        .ok_or(AmmError::MathOverflow)?;
+    pool.sync_reserves(reserve_a, reserve_b);
 
     // Mint LP tokens to user
     let pool_key = ctx.accounts.liquidity_pool.key();
-    let authority_bump = ctx.bumps.pool_authority;
+    let authority_bump = pool.authority_bump;
     let authority_seeds = &[b"pool_authority", pool_key.as_ref(), &[authority_bump]];
     let signer_seeds = &[&authority_seeds[..]];
 
+    // LP minted through a position goes to its vault, where it stays tracked
+    let lp_destination = match &ctx.accounts.liquidity_position {
+        Some(position) => {
+            position_lp_vault(position, &mut ctx.accounts.position_lp_vault)?.to_account_info()
+        }
+        None => ctx.accounts.user_lp_token_account.to_account_info(),
+    };
     token_interface::mint_to(
         CpiContext::new_with_signer(
             ctx.accounts.token_program.to_account_info(),
             MintTo {
                 mint: ctx.accounts.lp_token_mint.to_account_info(),
-                to: ctx.accounts.user_lp_token_account.to_account_info(),
+                to: lp_destination,
                 authority: ctx.accounts.pool_authority.to_account_info(),
             },
             signer_seeds,
@@ -107,192 +212,589 @@ pub fn deposit(
         lp_tokens_to_mint,
     )?;
 
-    // Transfer token A from user to vault
-    token_interface::transfer(
-        CpiContext::new(
-            ctx.accounts.token_program.to_account_info(),
-            anchor_spl::token_interface::Transfer {
-                from: ctx.accounts.user_token_a_account.to_account_info(),
-                to: ctx.accounts.token_a_vault.to_account_info(),
-                authority: ctx.accounts.user.to_account_info(),
-            },
-        ),
-        actual_token_a_deposit,
-    )?;
-
-    // Transfer token B from user to vault
-    token_interface::transfer(
-        CpiContext::new(
-            ctx.accounts.token_program.to_account_info(),
-            anchor_spl::token_interface::Transfer {
-                from: ctx.accounts.user_token_b_account.to_account_info(),
-                to: ctx.accounts.token_b_vault.to_account_info(),
-                authority: ctx.accounts.user.to_account_info(),
-            },
-        ),
-        actual_token_b_deposit,
-    )?;
+    if let Some(position) = ctx.accounts.liquidity_position.as_mut() {
+        position.settle(pool.fee_growth_per_lp)?;
+        position.record_deposit(
+            lp_tokens_to_mint,
+            actual_token_a_deposit,
+            actual_token_b_deposit,
+        )?;
+        let clock = Clock::get()?;
+        position.last_deposit_slot = clock.slot;
+        position.last_deposit_timestamp = clock.unix_timestamp;
+    }
 
-    msg!(
+    log!(
         "Liquidity added - Token A: {}, Token B: {}, LP tokens: {}",
         actual_token_a_deposit,
         actual_token_b_deposit,
         lp_tokens_to_mint
     );
 
+    emit!(LiquidityDeposited {
+        liquidity_pool: pool_key,
+        event_seq: pool.next_event_seq(),
+        user: ctx.accounts.user.key(),
+        lp_tokens_minted: lp_tokens_to_mint,
+        token_a_amount: actual_token_a_deposit,
+        token_b_amount: actual_token_b_deposit,
+        client_order_id,
+    });
+
     Ok(())
 }
 
-/// Remove liquidity from the pool
-pub fn withdraw(ctx: Context<ManageLiquidity>, lp_tokens_to_burn: u64) -> Result<()> {
-    // Verify user has sufficient LP tokens
+/// Add a large deposit quoted at `total_a : total_b`, reverting if the pool
+/// ratio has drifted more than `max_ratio_drift_bps` from that quote
+pub fn deposit_chunked(
+    ctx: Context<DepositLiquidity>,
+    total_a: u64,
+    total_b: u64,
+    max_ratio_drift_bps: u16,
+    client_order_id: Option<u64>,
+) -> Result<()> {
     require!(
-        ctx.accounts.user_lp_token_account.amount >= lp_tokens_to_burn,
-        AmmError::InsufficientBalance
+        (max_ratio_drift_bps as u128) <= BASIS_POINTS_DIVISOR,
+        AmmError::InvalidPoolConfig
     );
+    require!(total_a > 0 && total_b > 0, AmmError::InvalidLpTokenAmount);
+
+    // The first deposit sets the ratio, so there is nothing to drift from
+    let (reserve_a, reserve_b) = {
+        let pool = ctx.accounts.liquidity_pool.load()?;
+        (pool.reserve_a as u128, pool.reserve_b as u128)
+    };
+    if reserve_a > 0 && reserve_b > 0 {
+        let quoted_price = LiquidityPool::spot_price(total_a as u128, total_b as u128)?;
+        let current_price = LiquidityPool::spot_price(reserve_a, reserve_b)?;
+        let drift_bps = LiquidityPool::deviation_bps(current_price, quoted_price)?;
+
+        log!(
+            "Pool ratio drift since quote: {} bps (max {})",
+            drift_bps,
+            max_ratio_drift_bps
+        );
+        require!(
+            drift_bps <= max_ratio_drift_bps as u128,
+            AmmError::RatioDriftExceeded
+        );
+    }
+
+    deposit(ctx, total_a, total_b, client_order_id)
+}
+
+/// Remove liquidity from the pool
+pub fn withdraw(
+    ctx: Context<WithdrawLiquidity>,
+    lp_tokens_to_burn: u64,
+    note: Option<String>,
+    client_order_id: Option<u64>,
+) -> Result<()> {
+    if let Some(note) = &note {
+        require!(note.len() <= MAX_WITHDRAWAL_NOTE_LEN, AmmError::NoteTooLong);
+    }
+
+    // Token accounts held by a sub-account move with the sub-account PDA as signer
+    let user_authority = user_token_authority(&ctx.accounts.sub_account, &ctx.accounts.user)?;
+    let sub_account_signer = ctx.accounts.sub_account.as_ref().map(|sub| sub.signer());
+    let sub_account_seeds = sub_account_signer.as_ref().map(|signer| signer.seeds());
+    let user_signer = sub_account_seeds.as_ref().map(|seeds| [&seeds[..]]);
+    let user_signer_seeds: &[&[&[u8]]] = user_signer.as_ref().map_or(&[], |signer| &signer[..]);
 
     let pool_key = ctx.accounts.liquidity_pool.key();
-    let pool = &mut ctx.accounts.liquidity_pool;
+
+    // A position's LP is burned out of its vault with the position as signer;
+    // untracked LP is burned from the user's account
+    let lp_token_owner = ctx.accounts.lp_token_owner.key();
+    let position_bump = [ctx
+        .accounts
+        .liquidity_position
+        .as_ref()
+        .map_or(0, |position| position.bump)];
+    let position_seeds = &[
+        b"liquidity_position",
+        pool_key.as_ref(),
+        lp_token_owner.as_ref(),
+        &position_bump,
+    ];
+    let position_signer = [&position_seeds[..]];
+    let (lp_source, lp_authority, lp_signer_seeds) = match &ctx.accounts.liquidity_position {
+        Some(position) => (
+            position_lp_vault(position, &mut ctx.accounts.position_lp_vault)?,
+            position.to_account_info(),
+            &position_signer[..],
+        ),
+        None => (
+            &mut ctx.accounts.user_lp_token_account,
+            user_authority,
+            user_signer_seeds,
+        ),
+    };
+    require!(
+        lp_source.amount >= lp_tokens_to_burn,
+        AmmError::InsufficientLpTokens
+    );
+
+    let pool = &mut ctx.accounts.liquidity_pool.load_mut()?;
+    pool.check_lp_supply(ctx.accounts.lp_token_mint.supply)?;
     require!(
         pool.total_lp_tokens_issued >= lp_tokens_to_burn,
         AmmError::ExcessiveBurnAmount
     );
 
-    let vault_a_balance = ctx.accounts.token_a_vault.amount as u128;
-    let vault_b_balance = ctx.accounts.token_b_vault.amount as u128;
     let burn_amount = lp_tokens_to_burn as u128;
     let total_lp_supply = pool.total_lp_tokens_issued as u128;
 
-    // Calculate proportional withdrawal amounts
-    // withdrawn_A = (LP_burned * vault_A) / total_LP
-    let token_a_withdrawal = burn_amount
-        .checked_mul(vault_a_balance)
-        .and_then(|v| v.checked_div(total_lp_supply))
-        .ok_or(AmmError::MathOverflow)? as u64;
+    // Withdraw-only mode holds large withdrawals back; the small-LP lane is
+    // sized by the position backing the burn
+    let clock = Clock::get()?;
+    let position_lp = ctx
+        .accounts
+        .liquidity_position
+        .as_ref()
+        .map(|position| position.tracked_lp())
+        .filter(|position_lp| *position_lp >= lp_tokens_to_burn);
+    pool.check_withdrawal_allowed(clock.unix_timestamp, position_lp)?;
+
+    // Withdrawing a position right after a deposit waits out the cooldown and
+    // pays the JIT penalty into the pool
+    let payout_bps = pool.position_payout_bps(
+        ctx.accounts
+            .liquidity_position
+            .as_deref()
+            .map(|position| &**position),
+        lp_tokens_to_burn,
+        clock.unix_timestamp,
+        clock.slot,
+    )?;
+    let jit_penalty_bps = BASIS_POINTS_DIVISOR - payout_bps;
+
+    // Burn the LP tokens before anything leaves the vaults, so no CPI made
+    // during the payout can observe LP tokens backed by tokens already paid
+    let lp_balance = lp_source.amount;
+    token_interface::burn(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            Burn {
+                mint: ctx.accounts.lp_token_mint.to_account_info(),
+                from: lp_source.to_account_info(),
+                authority: lp_authority,
+            },
+            lp_signer_seeds,
+        ),
+        lp_tokens_to_burn,
+    )?;
+    pool.total_lp_tokens_issued = pool
+        .total_lp_tokens_issued
+        .checked_sub(lp_tokens_to_burn)
+        .ok_or(AmmError::MathOverflow)?;
 
-    let token_b_withdrawal = burn_amount
-        .checked_mul(vault_b_balance)
-        .and_then(|v| v.checked_div(total_lp_supply))
-        .ok_or(AmmError::MathOverflow)? as u64;
+    // The burn must have taken exactly the requested LP tokens off the account
+    // and the mint supply
+    lp_source.reload()?;
+    ctx.accounts.lp_token_mint.reload()?;
+    require!(
+        lp_balance.checked_sub(lp_source.amount) == Some(lp_tokens_to_burn),
+        AmmError::InsufficientLpTokens
+    );
+    pool.check_lp_supply(ctx.accounts.lp_token_mint.supply)?;
+
+    // Price the payout on the tracked reserves, so tokens sent straight to a
+    // vault stay skimmable instead of being paid to whoever withdraws next
+    let reserve_a = pool.reserve_a as u128;
+    let reserve_b = pool.reserve_b as u128;
+
+    // Calculate proportional withdrawal amounts, rounded down in the pool's favor
+    // withdrawn_A = (LP_burned * reserve_A) / total_LP * (1 - penalty)
+    let token_a_withdrawal = math::to_u64(math::mul_div_floor(
+        math::mul_div_floor(burn_amount, reserve_a, total_lp_supply)?,
+        payout_bps,
+        BASIS_POINTS_DIVISOR,
+    )?)?;
+
+    let token_b_withdrawal = math::to_u64(math::mul_div_floor(
+        math::mul_div_floor(burn_amount, reserve_b, total_lp_supply)?,
+        payout_bps,
+        BASIS_POINTS_DIVISOR,
+    )?)?;
+    if jit_penalty_bps > 0 {
+        log!("JIT withdrawal penalty: {} bps", jit_penalty_bps);
+    }
 
     // Setup PDA signer
-
-    let authority_bump = ctx.bumps.pool_authority;
+    let authority_bump = pool.authority_bump;
     let authority_seeds = &[b"pool_authority", pool_key.as_ref(), &[authority_bump]];
     let signer_seeds = &[&authority_seeds[..]];
 
     // Transfer token A from vault to user
-    token_interface::transfer(
+    memo_if_required(
+        &ctx.accounts.user_token_a_account.to_account_info(),
+        &ctx.accounts.memo_program,
+        "ammv2 withdraw",
+    )?;
+    token_interface::transfer_checked(
         CpiContext::new_with_signer(
-            ctx.accounts.token_program.to_account_info(),
-            anchor_spl::token_interface::Transfer {
+            ctx.accounts.token_a_program.to_account_info(),
+            TransferChecked {
                 from: ctx.accounts.token_a_vault.to_account_info(),
+                mint: ctx.accounts.token_a_mint.to_account_info(),
                 to: ctx.accounts.user_token_a_account.to_account_info(),
                 authority: ctx.accounts.pool_authority.to_account_info(),
             },
             signer_seeds,
         ),
         token_a_withdrawal,
+        ctx.accounts.token_a_mint.decimals,
     )?;
 
     // Transfer token B from vault to user
-    token_interface::transfer(
+    memo_if_required(
+        &ctx.accounts.user_token_b_account.to_account_info(),
+        &ctx.accounts.memo_program,
+        "ammv2 withdraw",
+    )?;
+    token_interface::transfer_checked(
         CpiContext::new_with_signer(
-            ctx.accounts.token_program.to_account_info(),
-            anchor_spl::token_interface::Transfer {
+            ctx.accounts.token_b_program.to_account_info(),
+            TransferChecked {
                 from: ctx.accounts.token_b_vault.to_account_info(),
+                mint: ctx.accounts.token_b_mint.to_account_info(),
                 to: ctx.accounts.user_token_b_account.to_account_info(),
                 authority: ctx.accounts.pool_authority.to_account_info(),
             },
             signer_seeds,
         ),
         token_b_withdrawal,
+        ctx.accounts.token_b_mint.decimals,
     )?;
 
-    // Burn LP tokens
-    token_interface::burn(
-        CpiContext::new(
-            ctx.accounts.token_program.to_account_info(),
-            Burn {
-                mint: ctx.accounts.lp_token_mint.to_account_info(),
-                from: ctx.accounts.user_lp_token_account.to_account_info(),
-                authority: ctx.accounts.user.to_account_info(),
-            },
-        ),
-        lp_tokens_to_burn,
-    )?;
-
-    // Update pool state
-    pool.total_lp_tokens_issued = pool
-        .total_lp_tokens_issued
-        .checked_sub(lp_tokens_to_burn)
+    // The payout leaves the reserves; any untracked vault surplus stays skimmable
+    let reserve_a = pool
+        .reserve_a
+        .checked_sub(token_a_withdrawal)
+        .ok_or(AmmError::MathOverflow)?;
+    let reserve_b = pool
+        .reserve_b
+        .checked_sub(token_b_withdrawal)
         .ok_or(AmmError::MathOverflow)?;
+    pool.sync_reserves(reserve_a, reserve_b);
 
-    msg!(
+    if let Some(position) = ctx.accounts.liquidity_position.as_mut() {
+        position.settle(pool.fee_growth_per_lp)?;
+        position.record_withdrawal(lp_tokens_to_burn, token_a_withdrawal, token_b_withdrawal);
+    }
+
+    log!(
         "Liquidity removed - Token A: {}, Token B: {}, LP tokens burned: {}",
         token_a_withdrawal,
         token_b_withdrawal,
         lp_tokens_to_burn
     );
 
+    // Forward the beneficiary note to the memo program for on-chain bookkeeping
+    if let Some(note) = &note {
+        let memo_program = ctx
+            .accounts
+            .memo_program
+            .as_ref()
+            .ok_or(AmmError::MemoProgramRequired)?;
+        memo::build_memo(
+            CpiContext::new(memo_program.to_account_info(), BuildMemo {}),
+            note.as_bytes(),
+        )?;
+    }
+
+    emit!(LiquidityWithdrawn {
+        liquidity_pool: pool_key,
+        event_seq: pool.next_event_seq(),
+        user: ctx.accounts.user.key(),
+        lp_tokens_burned: lp_tokens_to_burn,
+        token_a_amount: token_a_withdrawal,
+        token_b_amount: token_b_withdrawal,
+        note,
+        client_order_id,
+    });
+
     Ok(())
 }
 
 #[derive(Accounts)]
-pub struct ManageLiquidity<'info> {
+pub struct DepositLiquidity<'info> {
     /// Pool state account
     #[account(mut)]
-    pub liquidity_pool: Box<Account<'info, LiquidityPool>>,
+    pub liquidity_pool: AccountLoader<'info, LiquidityPool>,
 
-    /// Pool authority PDA
+    /// Global config, checked for the emergency pause
+    #[account(seeds = [b"amm_config"], bump)]
+    pub amm_config: Box<Account<'info, AmmConfig>>,
+
+    /// Pool authority PDA, mints the LP tokens
     #[account(
         seeds = [b"pool_authority", liquidity_pool.key().as_ref()],
-        bump
+        bump = liquidity_pool.load()?.authority_bump
     )]
     pub pool_authority: SystemAccount<'info>,
 
+    /// Token A mint
+    #[account(address = liquidity_pool.load()?.token_a_mint)]
+    pub token_a_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    /// Token B mint
+    #[account(address = liquidity_pool.load()?.token_b_mint)]
+    pub token_b_mint: Box<InterfaceAccount<'info, Mint>>,
+
     /// Token A vault - must match user's token A mint
     #[account(
         mut,
-        constraint = token_a_vault.mint == user_token_a_account.mint,
+        constraint = token_a_vault.mint == user_token_a_account.mint @ AmmError::InvalidVaultAccount,
         seeds = [b"token_a_vault", liquidity_pool.key().as_ref()],
-        bump
+        bump = liquidity_pool.load()?.token_a_vault_bump
     )]
     pub token_a_vault: Box<InterfaceAccount<'info, TokenAccount>>,
 
     /// Token B vault - must match user's token B mint
     #[account(
         mut,
-        constraint = token_b_vault.mint == user_token_b_account.mint,
+        constraint = token_b_vault.mint == user_token_b_account.mint @ AmmError::InvalidVaultAccount,
         seeds = [b"token_b_vault", liquidity_pool.key().as_ref()],
-        bump
+        bump = liquidity_pool.load()?.token_b_vault_bump
     )]
     pub token_b_vault: Box<InterfaceAccount<'info, TokenAccount>>,
 
     /// LP token mint
     #[account(
         mut,
-        constraint = lp_token_mint.key() == user_lp_token_account.mint,
         seeds = [b"lp_token_mint", liquidity_pool.key().as_ref()],
-        bump
+        bump = liquidity_pool.load()?.lp_token_mint_bump
     )]
     pub lp_token_mint: Box<InterfaceAccount<'info, Mint>>,
 
-    /// User's token A account
+    /// Optional sub-account that owns the user token accounts
+    pub sub_account: Option<Box<Account<'info, SubAccount>>>,
+
+    /// User authority (sub-account owner or delegate when a sub-account is used);
+    /// pays for the LP token account when it does not exist yet
     #[account(mut)]
+    pub user: Signer<'info>,
+
+    /// Owner of the user token accounts: the user, or the sub-account when one is used
+    /// CHECK: only its address is used, as the LP token account authority
+    #[account(address = token_owner(&sub_account, &user) @ AmmError::InvalidTokenAccountOwner)]
+    pub lp_token_owner: UncheckedAccount<'info>,
+
+    /// Token program of the LP mint
+    pub token_program: Interface<'info, TokenInterface>,
+
+    /// Token program of token A
+    #[account(address = *token_a_mint.to_account_info().owner @ AmmError::InvalidTokenProgram)]
+    pub token_a_program: Interface<'info, TokenInterface>,
+
+    /// Token program of token B
+    #[account(address = *token_b_mint.to_account_info().owner @ AmmError::InvalidTokenProgram)]
+    pub token_b_program: Interface<'info, TokenInterface>,
+
+    /// Required for creating the LP token account
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+
+    /// User's token A account, owned by the user (or the sub-account)
+    #[account(
+        mut,
+        constraint = user_token_a_account.owner == token_owner(&sub_account, &user) @ AmmError::InvalidTokenAccountOwner
+    )]
     pub user_token_a_account: Box<InterfaceAccount<'info, TokenAccount>>,
 
-    /// User's token B account
-    #[account(mut)]
+    /// User's token B account, owned by the user (or the sub-account)
+    #[account(
+        mut,
+        constraint = user_token_b_account.owner == token_owner(&sub_account, &user) @ AmmError::InvalidTokenAccountOwner
+    )]
     pub user_token_b_account: Box<InterfaceAccount<'info, TokenAccount>>,
 
-    /// User's LP token account
-    #[account(mut)]
+    /// LP token associated account of the user (or the sub-account), created if needed
+    #[account(
+        init_if_needed,
+        payer = user,
+        associated_token::mint = lp_token_mint,
+        associated_token::authority = lp_token_owner,
+        associated_token::token_program = token_program,
+    )]
     pub user_lp_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
 
-    /// User authority
+    /// Whitelist entry of the LP token owner, required to deposit into a permissioned pool
+    #[account(
+        seeds = [
+            b"lp_whitelist",
+            liquidity_pool.key().as_ref(),
+            lp_token_owner.key().as_ref()
+        ],
+        bump,
+    )]
+    pub lp_whitelist_entry: Option<Box<Account<'info, LpWhitelistEntry>>>,
+
+    /// Fee-tracking position of the LP token owner; the LP tokens go to its vault
+    #[account(
+        mut,
+        seeds = [
+            b"liquidity_position",
+            liquidity_pool.key().as_ref(),
+            lp_token_owner.key().as_ref()
+        ],
+        bump = liquidity_position.bump,
+    )]
+    pub liquidity_position: Option<Box<Account<'info, LiquidityPosition>>>,
+
+    /// LP vault of the position, required with it
+    #[account(mut)]
+    pub position_lp_vault: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+}
+
+/// Withdrawals stay open while the program is paused, so the config is not needed
+#[derive(Accounts)]
+pub struct WithdrawLiquidity<'info> {
+    /// Pool state account
+    #[account(mut)]
+    pub liquidity_pool: AccountLoader<'info, LiquidityPool>,
+
+    /// Pool authority PDA, pays out of the vaults
+    #[account(
+        seeds = [b"pool_authority", liquidity_pool.key().as_ref()],
+        bump = liquidity_pool.load()?.authority_bump
+    )]
+    pub pool_authority: SystemAccount<'info>,
+
+    /// Token A mint
+    #[account(address = liquidity_pool.load()?.token_a_mint)]
+    pub token_a_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    /// Token B mint
+    #[account(address = liquidity_pool.load()?.token_b_mint)]
+    pub token_b_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    /// Token A vault - must match user's token A mint
+    #[account(
+        mut,
+        constraint = token_a_vault.mint == user_token_a_account.mint @ AmmError::InvalidVaultAccount,
+        seeds = [b"token_a_vault", liquidity_pool.key().as_ref()],
+        bump = liquidity_pool.load()?.token_a_vault_bump
+    )]
+    pub token_a_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Token B vault - must match user's token B mint
+    #[account(
+        mut,
+        constraint = token_b_vault.mint == user_token_b_account.mint @ AmmError::InvalidVaultAccount,
+        seeds = [b"token_b_vault", liquidity_pool.key().as_ref()],
+        bump = liquidity_pool.load()?.token_b_vault_bump
+    )]
+    pub token_b_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// LP token mint
+    #[account(
+        mut,
+        seeds = [b"lp_token_mint", liquidity_pool.key().as_ref()],
+        bump = liquidity_pool.load()?.lp_token_mint_bump
+    )]
+    pub lp_token_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    /// Optional sub-account that owns the user token accounts
+    pub sub_account: Option<Box<Account<'info, SubAccount>>>,
+
+    /// User authority (sub-account owner or delegate when a sub-account is used)
     pub user: Signer<'info>,
 
-    /// Token program
+    /// Owner of the user token accounts: the user, or the sub-account when one is used
+    /// CHECK: only its address is used, as the LP token account authority
+    #[account(address = token_owner(&sub_account, &user) @ AmmError::InvalidTokenAccountOwner)]
+    pub lp_token_owner: UncheckedAccount<'info>,
+
+    /// Token program of the LP mint
     pub token_program: Interface<'info, TokenInterface>,
+
+    /// Token program of token A
+    #[account(address = *token_a_mint.to_account_info().owner @ AmmError::InvalidTokenProgram)]
+    pub token_a_program: Interface<'info, TokenInterface>,
+
+    /// Token program of token B
+    #[account(address = *token_b_mint.to_account_info().owner @ AmmError::InvalidTokenProgram)]
+    pub token_b_program: Interface<'info, TokenInterface>,
+
+    /// User's token A account, owned by the user (or the sub-account)
+    #[account(
+        mut,
+        constraint = user_token_a_account.owner == token_owner(&sub_account, &user) @ AmmError::InvalidTokenAccountOwner
+    )]
+    pub user_token_a_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// User's token B account, owned by the user (or the sub-account)
+    #[account(
+        mut,
+        constraint = user_token_b_account.owner == token_owner(&sub_account, &user) @ AmmError::InvalidTokenAccountOwner
+    )]
+    pub user_token_b_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// LP token associated account of the user (or the sub-account), burned
+    /// from when no position is passed
+    #[account(
+        mut,
+        associated_token::mint = lp_token_mint,
+        associated_token::authority = lp_token_owner,
+        associated_token::token_program = token_program,
+    )]
+    pub user_lp_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Memo program, required when a withdrawal carries a note or pays into an
+    /// account that requires transfer memos
+    pub memo_program: Option<Program<'info, Memo>>,
+
+    /// Fee-tracking position of the LP token owner; the LP tokens are burned from its vault
+    #[account(
+        mut,
+        seeds = [
+            b"liquidity_position",
+            liquidity_pool.key().as_ref(),
+            lp_token_owner.key().as_ref()
+        ],
+        bump = liquidity_position.bump,
+    )]
+    pub liquidity_position: Option<Box<Account<'info, LiquidityPosition>>>,
+
+    /// LP vault of the position, required with it
+    #[account(mut)]
+    pub position_lp_vault: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+}
+
+/// Expected owner of the user token accounts
+fn token_owner(sub_account: &Option<Box<Account<SubAccount>>>, user: &Signer) -> Pubkey {
+    sub_account
+        .as_ref()
+        .map_or(user.key(), |sub_account| sub_account.key())
+}
+
+/// LP vault passed with `position`, which must be the position's own
+fn position_lp_vault<'a, 'info>(
+    position: &Account<'info, LiquidityPosition>,
+    vault: &'a mut Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+) -> Result<&'a mut Box<InterfaceAccount<'info, TokenAccount>>> {
+    let vault = vault.as_mut().ok_or(AmmError::InvalidPositionLpVault)?;
+    require_keys_eq!(
+        vault.key(),
+        position.lp_vault_address(&position.key())?,
+        AmmError::InvalidPositionLpVault
+    );
+    Ok(vault)
+}
+
+/// Authority over the user token accounts: the signer, or the sub-account acting for it
+fn user_token_authority<'info>(
+    sub_account: &Option<Box<Account<'info, SubAccount>>>,
+    user: &Signer<'info>,
+) -> Result<AccountInfo<'info>> {
+    match sub_account {
+        Some(sub_account) => {
+            require!(sub_account.is_authorized(user.key), AmmError::Unauthorized);
+            Ok(sub_account.to_account_info())
+        }
+        None => Ok(user.to_account_info()),
+    }
 }
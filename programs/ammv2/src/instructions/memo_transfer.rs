@@ -0,0 +1,37 @@
+use crate::errors::AmmError;
+use anchor_lang::prelude::*;
+use anchor_spl::memo::{self, BuildMemo, Memo};
+use anchor_spl::token_interface::spl_token_2022::{
+    self,
+    extension::{memo_transfer::memo_required, StateWithExtensions},
+    state::Account as TokenAccountState,
+};
+
+/// Whether `account` is a Token-2022 account that rejects incoming transfers
+/// not preceded by a memo
+pub fn requires_memo(account: &AccountInfo) -> Result<bool> {
+    if *account.owner != spl_token_2022::ID {
+        return Ok(false);
+    }
+    let data = account.try_borrow_data()?;
+    let state = StateWithExtensions::<TokenAccountState>::unpack(&data)?;
+    Ok(memo_required(&state))
+}
+
+/// Log `memo` through the memo program when `destination` requires incoming
+/// transfer memos. Call it right before the transfer: Token-2022 only accepts
+/// a memo that is the transfer's immediately preceding sibling instruction.
+pub fn memo_if_required<'info>(
+    destination: &AccountInfo<'info>,
+    memo_program: &Option<Program<'info, Memo>>,
+    memo: &str,
+) -> Result<()> {
+    if !requires_memo(destination)? {
+        return Ok(());
+    }
+    let memo_program = memo_program.as_ref().ok_or(AmmError::MemoProgramRequired)?;
+    memo::build_memo(
+        CpiContext::new(memo_program.to_account_info(), BuildMemo {}),
+        memo.as_bytes(),
+    )
+}
@@ -0,0 +1,76 @@
+use crate::events::PoolMigrated;
+use crate::state::{LiquidityPool, PoolBumps, LIQUIDITY_POOL_VERSION};
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::TokenAccount;
+
+/// Grow a pool account to the current layout size and bump its version
+pub fn process(ctx: Context<MigratePool>) -> Result<()> {
+    let pool = &mut ctx.accounts.liquidity_pool.load_mut()?;
+    if pool.version >= LIQUIDITY_POOL_VERSION {
+        log!("Pool already at version {}", pool.version);
+        return Ok(());
+    }
+
+    // Searched once here so later instructions can use the stored bumps
+    let pool_key = ctx.accounts.liquidity_pool.key();
+    let (_, authority_bump) =
+        Pubkey::find_program_address(&[b"pool_authority", pool_key.as_ref()], ctx.program_id);
+    let (_, lp_token_mint_bump) =
+        Pubkey::find_program_address(&[b"lp_token_mint", pool_key.as_ref()], ctx.program_id);
+    let from_version = pool.migrate(
+        ctx.accounts.token_a_vault.amount,
+        ctx.accounts.token_b_vault.amount,
+        PoolBumps {
+            authority: authority_bump,
+            token_a_vault: ctx.bumps.token_a_vault,
+            token_b_vault: ctx.bumps.token_b_vault,
+            lp_token_mint: lp_token_mint_bump,
+        },
+    );
+
+    log!(
+        "Pool migrated from version {} to {}",
+        from_version,
+        pool.version
+    );
+
+    emit!(PoolMigrated {
+        liquidity_pool: ctx.accounts.liquidity_pool.key(),
+        event_seq: pool.next_event_seq(),
+        from_version,
+        to_version: pool.version,
+    });
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct MigratePool<'info> {
+    /// Pool state account, reallocated to the current layout size
+    #[account(
+        mut,
+        realloc = 8 + LiquidityPool::INIT_SPACE,
+        realloc::payer = payer,
+        realloc::zero = true,
+    )]
+    pub liquidity_pool: AccountLoader<'info, LiquidityPool>,
+
+    /// Token A vault, seeds the tracked reserve
+    #[account(
+        seeds = [b"token_a_vault", liquidity_pool.key().as_ref()],
+        bump
+    )]
+    pub token_a_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Token B vault, seeds the tracked reserve
+    #[account(
+        seeds = [b"token_b_vault", liquidity_pool.key().as_ref()],
+        bump
+    )]
+    pub token_b_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Anyone may migrate a pool and pay for the extra rent
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
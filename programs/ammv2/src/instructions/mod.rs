@@ -1,9 +1,97 @@
+#![allow(ambiguous_glob_reexports)]
+
+pub mod admin;
+pub use admin::*;
+
+pub mod amm_config;
+pub use amm_config::*;
+
+pub mod close_pool;
+pub use close_pool::*;
+
+pub mod compound;
+pub use compound::*;
+
+pub mod dca;
+pub use dca::*;
+
+pub mod donate;
+pub use donate::*;
+
+pub mod export_state_digest;
+pub use export_state_digest::*;
+
+pub mod farm;
+pub use farm::*;
+
+pub mod gauge;
+pub use gauge::*;
+
 pub mod initialize_pool;
 pub use initialize_pool::*;
 
+pub mod initialize_pool_with_liquidity;
+pub use initialize_pool_with_liquidity::*;
+
+pub mod insurance;
+pub use insurance::*;
+
+pub mod keeper_tip;
+pub use keeper_tip::*;
+
+pub mod launch_pool;
+pub use launch_pool::*;
+
+pub mod limit_order;
+pub use limit_order::*;
+
+pub mod liquidity_position;
+pub use liquidity_position::*;
+
+pub mod lp_lock;
+pub use lp_lock::*;
+
 pub mod manage_liquidity;
 pub use manage_liquidity::*;
 
+pub mod memo_transfer;
+pub use memo_transfer::*;
+
+pub mod migrate_pool;
+pub use migrate_pool::*;
+
+pub mod oracle;
+pub use oracle::*;
+
+pub mod protocol_fee;
+pub use protocol_fee::*;
+
+pub mod quote;
+pub use quote::*;
+
+pub mod referral;
+pub use referral::*;
+
+pub mod skim;
+pub use skim::*;
+
+pub mod sub_account;
+pub use sub_account::*;
+
 pub mod swap;
 pub use swap::*;
 
+pub mod sync_lp_supply;
+pub use sync_lp_supply::*;
+
+pub mod timelock;
+pub use timelock::*;
+
+pub mod treasury;
+pub use treasury::*;
+
+pub mod volume_stats;
+pub use volume_stats::*;
+
+pub mod whitelist;
+pub use whitelist::*;
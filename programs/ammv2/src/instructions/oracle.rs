@@ -0,0 +1,254 @@
+use crate::errors::AmmError;
+use crate::instructions::keeper_tip::{pay_keeper_tip, KeeperTipAccounts};
+use crate::state::{
+    LiquidityPool, PriceOracle, ORACLE_BOOTSTRAP_WINDOW_SECS, ORACLE_CHECKPOINT_INTERVAL_SECS,
+};
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+
+/// Create the oracle for a pool that predates oracles, seeding the first
+/// observation from current reserves
+pub fn bootstrap(ctx: Context<BootstrapOracle>) -> Result<()> {
+    let reserve_a = ctx.accounts.token_a_vault.amount as u128;
+    let reserve_b = ctx.accounts.token_b_vault.amount as u128;
+    require!(reserve_a > 0 && reserve_b > 0, AmmError::PoolHasNoLiquidity);
+
+    let now = Clock::get()?.unix_timestamp;
+    let spot_price = LiquidityPool::spot_price(reserve_a, reserve_b)?;
+
+    let oracle = &mut ctx.accounts.oracle;
+    oracle.liquidity_pool = ctx.accounts.liquidity_pool.key();
+    oracle.low_confidence_until = now
+        .checked_add(ORACLE_BOOTSTRAP_WINDOW_SECS)
+        .ok_or(AmmError::MathOverflow)?;
+    oracle.record(now, spot_price)?;
+
+    let pool = &mut ctx.accounts.liquidity_pool.load_mut()?;
+    pool.oracle = oracle.key();
+    pool.oracle_q64 = 1;
+
+    log!(
+        "Oracle bootstrapped at price {} (low confidence until {})",
+        spot_price.to_bits(),
+        oracle.low_confidence_until
+    );
+    Ok(())
+}
+
+/// Record an observation from current reserves so quiet pools keep a fresh
+/// oracle. Anyone may crank; when the latest observation is at least
+/// `ORACLE_CHECKPOINT_INTERVAL_SECS` old the caller earns the pool's checkpoint
+/// reward as a keeper tip, if the tip accounts are passed.
+pub fn checkpoint(ctx: Context<CheckpointOracle>) -> Result<()> {
+    let reserve_a = ctx.accounts.token_a_vault.amount as u128;
+    let reserve_b = ctx.accounts.token_b_vault.amount as u128;
+    require!(reserve_a > 0 && reserve_b > 0, AmmError::PoolHasNoLiquidity);
+
+    let now = Clock::get()?.unix_timestamp;
+    let spot_price = LiquidityPool::spot_price(reserve_a, reserve_b)?;
+    let oracle = &mut ctx.accounts.oracle;
+    let stale = match oracle.latest() {
+        Some(latest) => now.saturating_sub(latest.timestamp) >= ORACLE_CHECKPOINT_INTERVAL_SECS,
+        None => true,
+    };
+    let pool = &mut ctx.accounts.liquidity_pool.load_mut()?;
+    pool.observe_price(oracle, now, spot_price)?;
+    let mut reward = 0;
+    let checkpoint_reward = pool.oracle_checkpoint_reward;
+    if stale && checkpoint_reward > 0 {
+        let accounts = &ctx.accounts;
+        reward = pay_keeper_tip(
+            pool,
+            &accounts.liquidity_pool.key(),
+            checkpoint_reward,
+            KeeperTipAccounts {
+                pool_authority: &accounts.pool_authority,
+                token_a_mint: accounts.token_a_mint.as_deref(),
+                protocol_fee_vault_a: accounts.protocol_fee_vault_a.as_deref(),
+                keeper_token_a_account: accounts.caller_token_a_account.as_deref(),
+                token_program: accounts.token_program.as_ref(),
+            },
+        )?;
+    }
+
+    log!(
+        "Oracle checkpoint at price {} - Reward: {}",
+        spot_price.to_bits(),
+        reward
+    );
+    Ok(())
+}
+
+/// Time-weighted average price returned by `read_twap`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct TwapReading {
+    /// Average spot price (token B per token A) over the window, as Q64.64 bits
+    pub twap_price: u128,
+
+    /// Current spot price, as Q64.64 bits
+    pub spot_price: u128,
+
+    /// Whether the window reaches into the oracle's bootstrap period
+    pub low_confidence: bool,
+}
+
+/// Compute the TWAP over (up to) the last `window_secs` from the oracle's
+/// observations, with the current reserves' price in effect since the latest
+/// one. The reading is returned as instruction return data for CPI callers.
+pub fn read_twap(ctx: Context<ReadTwap>, window_secs: i64) -> Result<TwapReading> {
+    require!(window_secs > 0, AmmError::InvalidTwapWindow);
+
+    let reserve_a = ctx.accounts.token_a_vault.amount as u128;
+    let reserve_b = ctx.accounts.token_b_vault.amount as u128;
+    require!(reserve_a > 0 && reserve_b > 0, AmmError::PoolHasNoLiquidity);
+
+    // An oracle still holding pre-Q64.64 accumulators has no usable history
+    require!(
+        ctx.accounts.liquidity_pool.load()?.oracle_q64 != 0,
+        AmmError::OracleHistoryUnavailable
+    );
+
+    let now = Clock::get()?.unix_timestamp;
+    let spot_price = LiquidityPool::spot_price(reserve_a, reserve_b)?;
+    let oracle = &ctx.accounts.oracle;
+    let twap_price = oracle
+        .twap(now, spot_price, window_secs)
+        .ok_or(AmmError::OracleHistoryUnavailable)?;
+
+    let reading = TwapReading {
+        twap_price: twap_price.to_bits(),
+        spot_price: spot_price.to_bits(),
+        low_confidence: now.saturating_sub(window_secs) < oracle.low_confidence_until,
+    };
+
+    log!(
+        "TWAP over {}s - Price: {}, Spot: {}, Low confidence: {}",
+        window_secs,
+        reading.twap_price,
+        reading.spot_price,
+        reading.low_confidence
+    );
+
+    Ok(reading)
+}
+
+#[derive(Accounts)]
+pub struct BootstrapOracle<'info> {
+    /// Pool without an oracle
+    #[account(
+        mut,
+        constraint = liquidity_pool.load()?.oracle == Pubkey::default() @ AmmError::OracleAlreadyInitialized
+    )]
+    pub liquidity_pool: AccountLoader<'info, LiquidityPool>,
+
+    /// Oracle PDA created for the pool
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + PriceOracle::INIT_SPACE,
+        seeds = [b"oracle", liquidity_pool.key().as_ref()],
+        bump,
+    )]
+    pub oracle: Box<Account<'info, PriceOracle>>,
+
+    /// Token A vault
+    #[account(
+        seeds = [b"token_a_vault", liquidity_pool.key().as_ref()],
+        bump = liquidity_pool.load()?.token_a_vault_bump
+    )]
+    pub token_a_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Token B vault
+    #[account(
+        seeds = [b"token_b_vault", liquidity_pool.key().as_ref()],
+        bump = liquidity_pool.load()?.token_b_vault_bump
+    )]
+    pub token_b_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Anyone may bootstrap the oracle and pay for the account
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ReadTwap<'info> {
+    /// Pool state account
+    pub liquidity_pool: AccountLoader<'info, LiquidityPool>,
+
+    /// Pool price oracle
+    #[account(address = liquidity_pool.load()?.oracle @ AmmError::OracleAccountRequired)]
+    pub oracle: Box<Account<'info, PriceOracle>>,
+
+    /// Token A vault
+    #[account(
+        seeds = [b"token_a_vault", liquidity_pool.key().as_ref()],
+        bump = liquidity_pool.load()?.token_a_vault_bump
+    )]
+    pub token_a_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Token B vault
+    #[account(
+        seeds = [b"token_b_vault", liquidity_pool.key().as_ref()],
+        bump = liquidity_pool.load()?.token_b_vault_bump
+    )]
+    pub token_b_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+}
+
+#[derive(Accounts)]
+pub struct CheckpointOracle<'info> {
+    /// Pool state account
+    #[account(mut)]
+    pub liquidity_pool: AccountLoader<'info, LiquidityPool>,
+
+    /// Pool price oracle
+    #[account(mut, address = liquidity_pool.load()?.oracle @ AmmError::OracleAccountRequired)]
+    pub oracle: Box<Account<'info, PriceOracle>>,
+
+    /// Pool authority PDA, signs the reward transfer
+    #[account(
+        seeds = [b"pool_authority", liquidity_pool.key().as_ref()],
+        bump = liquidity_pool.load()?.authority_bump
+    )]
+    pub pool_authority: SystemAccount<'info>,
+
+    /// Token A vault
+    #[account(
+        seeds = [b"token_a_vault", liquidity_pool.key().as_ref()],
+        bump = liquidity_pool.load()?.token_a_vault_bump
+    )]
+    pub token_a_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Token B vault
+    #[account(
+        seeds = [b"token_b_vault", liquidity_pool.key().as_ref()],
+        bump = liquidity_pool.load()?.token_b_vault_bump
+    )]
+    pub token_b_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Token A mint, only needed to collect the reward
+    #[account(address = liquidity_pool.load()?.token_a_mint)]
+    pub token_a_mint: Option<Box<InterfaceAccount<'info, Mint>>>,
+
+    /// Protocol fee vault for token A, holds the keeper tip budget
+    #[account(
+        mut,
+        seeds = [
+            b"protocol_fee_vault",
+            liquidity_pool.key().as_ref(),
+            liquidity_pool.load()?.token_a_mint.as_ref()
+        ],
+        bump,
+    )]
+    pub protocol_fee_vault_a: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    /// Caller's token A account receiving the reward
+    #[account(
+        mut,
+        constraint = caller_token_a_account.mint == liquidity_pool.load()?.token_a_mint
+    )]
+    pub caller_token_a_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+}
@@ -0,0 +1,430 @@
+use crate::errors::AmmError;
+use crate::instructions::keeper_tip::{pay_keeper_tip, KeeperTipAccounts};
+use crate::math;
+use crate::state::{AmmConfig, LiquidityPool, BASIS_POINTS_DIVISOR, POOL_OP_SWAP};
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{
+    self, Burn, Mint, TokenAccount, TokenInterface, TransferChecked,
+};
+
+/// Create the pool's protocol fee vaults, which collect the protocol share of swap fees
+pub fn initialize_vaults(ctx: Context<InitializeProtocolFeeVaults>) -> Result<()> {
+    log!(
+        "Protocol fee vaults initialized - Vault A: {}, Vault B: {}",
+        ctx.accounts.protocol_fee_vault_a.key(),
+        ctx.accounts.protocol_fee_vault_b.key()
+    );
+    Ok(())
+}
+
+/// Swap the protocol fees held in the non-buyback token into the buyback token
+/// through the pool, then burn everything bought along with the protocol fees
+/// already held in the buyback token. Sales are capped at the pool's max trade size,
+/// and the keeper tip budget is left in the token A fee vault.
+pub fn buyback_and_burn(ctx: Context<BuybackAndBurn>) -> Result<()> {
+    ctx.accounts.amm_config.check_not_paused()?;
+    let pool = &mut ctx.accounts.liquidity_pool.load_mut()?;
+    require!(
+        pool.buyback_mint != Pubkey::default(),
+        AmmError::BuybackDisabled
+    );
+    require!(pool.withdraw_only == 0, AmmError::PoolWithdrawOnly);
+    pool.check_operation_enabled(POOL_OP_SWAP)?;
+
+    let accounts = &ctx.accounts;
+    let buyback_is_token_a = pool.buyback_mint == pool.token_a_mint;
+    let (sell_fee_vault, sell_vault, sell_mint, buy_fee_vault, buy_vault, buy_mint) =
+        if buyback_is_token_a {
+            (
+                &accounts.protocol_fee_vault_b,
+                &accounts.token_b_vault,
+                &accounts.token_b_mint,
+                &accounts.protocol_fee_vault_a,
+                &accounts.token_a_vault,
+                &accounts.token_a_mint,
+            )
+        } else {
+            (
+                &accounts.protocol_fee_vault_a,
+                &accounts.token_a_vault,
+                &accounts.token_a_mint,
+                &accounts.protocol_fee_vault_b,
+                &accounts.token_b_vault,
+                &accounts.token_b_mint,
+            )
+        };
+
+    let sell_reserve = sell_vault.amount as u128;
+    let buy_reserve = buy_vault.amount as u128;
+    // The keeper tip budget stays in the token A fee vault
+    let mut sell_amount =
+        pool.unreserved_protocol_fees(&sell_mint.key(), sell_fee_vault.amount) as u128;
+    let held_buy_fees = pool.unreserved_protocol_fees(&buy_mint.key(), buy_fee_vault.amount);
+    if pool.max_trade_bps_of_reserves > 0 {
+        sell_amount = sell_amount.min(math::mul_div_floor(
+            sell_reserve,
+            pool.max_trade_bps_of_reserves as u128,
+            BASIS_POINTS_DIVISOR,
+        )?);
+    }
+
+    let pool_key = accounts.liquidity_pool.key();
+    let authority_bump = pool.authority_bump;
+    let authority_seeds = &[b"pool_authority", pool_key.as_ref(), &[authority_bump]];
+    let signer_seeds = &[&authority_seeds[..]];
+
+    // Buy through the pool at the constant product price, LP fee included
+    let mut bought = 0;
+    if sell_amount > 0 && sell_reserve > 0 && buy_reserve > 0 {
+        let (_, bought_u128) = pool.swap_amounts(sell_reserve, buy_reserve, sell_amount)?;
+        let new_buy_reserve = buy_reserve - bought_u128;
+        bought = math::to_u64(bought_u128)?;
+
+        if pool.max_price_change_bps_per_slot > 0 {
+            let final_sell_reserve = sell_reserve + sell_amount;
+            let (pre_price, post_price) = if buyback_is_token_a {
+                (
+                    LiquidityPool::spot_price(buy_reserve, sell_reserve)?,
+                    LiquidityPool::spot_price(new_buy_reserve, final_sell_reserve)?,
+                )
+            } else {
+                (
+                    LiquidityPool::spot_price(sell_reserve, buy_reserve)?,
+                    LiquidityPool::spot_price(final_sell_reserve, new_buy_reserve)?,
+                )
+            };
+            pool.check_price_movement(Clock::get()?.slot, pre_price, post_price)?;
+        }
+
+        token_interface::transfer_checked(
+            CpiContext::new_with_signer(
+                accounts.token_program.to_account_info(),
+                TransferChecked {
+                    from: sell_fee_vault.to_account_info(),
+                    mint: sell_mint.to_account_info(),
+                    to: sell_vault.to_account_info(),
+                    authority: accounts.pool_authority.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            math::to_u64(sell_amount)?,
+            sell_mint.decimals,
+        )?;
+    }
+
+    // Burn the bought tokens straight out of the reserve, then the held fees
+    for (from, amount) in [(buy_vault, bought), (buy_fee_vault, held_buy_fees)] {
+        if amount > 0 {
+            token_interface::burn(
+                CpiContext::new_with_signer(
+                    accounts.token_program.to_account_info(),
+                    Burn {
+                        mint: buy_mint.to_account_info(),
+                        from: from.to_account_info(),
+                        authority: accounts.pool_authority.to_account_info(),
+                    },
+                    signer_seeds,
+                ),
+                amount,
+            )?;
+        }
+    }
+
+    log!(
+        "Buyback and burn - Sold: {}, Bought: {}, Burned: {}",
+        sell_amount,
+        bought,
+        bought + held_buy_fees
+    );
+
+    ctx.accounts.token_a_vault.reload()?;
+    ctx.accounts.token_b_vault.reload()?;
+    pool.sync_reserves(
+        ctx.accounts.token_a_vault.amount,
+        ctx.accounts.token_b_vault.amount,
+    );
+    Ok(())
+}
+
+/// Move the protocol fees accrued in the pool's fee vaults to the treasury
+/// vaults, paying the caller the pool's keeper tip when it passes a token A
+/// account. The keeper tip budget stays in the token A fee vault.
+pub fn harvest(ctx: Context<HarvestProtocolFees>) -> Result<()> {
+    let pool_key = ctx.accounts.liquidity_pool.key();
+    let pool = &mut ctx.accounts.liquidity_pool.load_mut()?;
+    // Buyback pools burn their protocol fees instead
+    require!(
+        pool.buyback_mint == Pubkey::default(),
+        AmmError::ProtocolFeesReservedForBuyback
+    );
+
+    let accounts = &ctx.accounts;
+    let harvest_a =
+        pool.unreserved_protocol_fees(&pool.token_a_mint, accounts.protocol_fee_vault_a.amount);
+    let harvest_b =
+        pool.unreserved_protocol_fees(&pool.token_b_mint, accounts.protocol_fee_vault_b.amount);
+    require!(
+        harvest_a > 0 || harvest_b > 0,
+        AmmError::NoProtocolFeesToHarvest
+    );
+
+    let authority_seeds = &[b"pool_authority", pool_key.as_ref(), &[pool.authority_bump]];
+    let signer_seeds = &[&authority_seeds[..]];
+    for (fee_vault, mint, treasury_vault, token_program, amount) in [
+        (
+            &accounts.protocol_fee_vault_a,
+            &accounts.token_a_mint,
+            &accounts.treasury_vault_a,
+            &accounts.token_a_program,
+            harvest_a,
+        ),
+        (
+            &accounts.protocol_fee_vault_b,
+            &accounts.token_b_mint,
+            &accounts.treasury_vault_b,
+            &accounts.token_b_program,
+            harvest_b,
+        ),
+    ] {
+        if amount > 0 {
+            token_interface::transfer_checked(
+                CpiContext::new_with_signer(
+                    token_program.to_account_info(),
+                    TransferChecked {
+                        from: fee_vault.to_account_info(),
+                        mint: mint.to_account_info(),
+                        to: treasury_vault.to_account_info(),
+                        authority: accounts.pool_authority.to_account_info(),
+                    },
+                    signer_seeds,
+                ),
+                amount,
+                mint.decimals,
+            )?;
+        }
+    }
+
+    // Only the budget is left in the token A fee vault
+    ctx.accounts.protocol_fee_vault_a.reload()?;
+    let keeper_tip = pool.keeper_tip;
+    let tip = pay_keeper_tip(
+        pool,
+        &pool_key,
+        keeper_tip,
+        KeeperTipAccounts {
+            pool_authority: &ctx.accounts.pool_authority,
+            token_a_mint: Some(&ctx.accounts.token_a_mint),
+            protocol_fee_vault_a: Some(&ctx.accounts.protocol_fee_vault_a),
+            keeper_token_a_account: ctx.accounts.keeper_token_a_account.as_deref(),
+            token_program: Some(&ctx.accounts.token_a_program),
+        },
+    )?;
+
+    log!(
+        "Protocol fees harvested - Token A: {}, Token B: {}, Keeper tip: {}",
+        harvest_a,
+        harvest_b,
+        tip
+    );
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct InitializeProtocolFeeVaults<'info> {
+    /// Pool state account
+    pub liquidity_pool: AccountLoader<'info, LiquidityPool>,
+
+    /// Pool authority PDA
+    #[account(
+        seeds = [b"pool_authority", liquidity_pool.key().as_ref()],
+        bump = liquidity_pool.load()?.authority_bump
+    )]
+    pub pool_authority: SystemAccount<'info>,
+
+    /// Token A mint
+    #[account(address = liquidity_pool.load()?.token_a_mint)]
+    pub token_a_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    /// Token B mint
+    #[account(address = liquidity_pool.load()?.token_b_mint)]
+    pub token_b_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    /// Protocol fee vault for token A
+    #[account(
+        init,
+        payer = payer,
+        seeds = [
+            b"protocol_fee_vault",
+            liquidity_pool.key().as_ref(),
+            token_a_mint.key().as_ref()
+        ],
+        bump,
+        token::mint = token_a_mint,
+        token::authority = pool_authority,
+    )]
+    pub protocol_fee_vault_a: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Protocol fee vault for token B
+    #[account(
+        init,
+        payer = payer,
+        seeds = [
+            b"protocol_fee_vault",
+            liquidity_pool.key().as_ref(),
+            token_b_mint.key().as_ref()
+        ],
+        bump,
+        token::mint = token_b_mint,
+        token::authority = pool_authority,
+    )]
+    pub protocol_fee_vault_b: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Anyone may create the vaults and pay for the accounts
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct BuybackAndBurn<'info> {
+    /// Pool state account
+    #[account(mut)]
+    pub liquidity_pool: AccountLoader<'info, LiquidityPool>,
+
+    /// Global config, checked for the emergency pause
+    #[account(seeds = [b"amm_config"], bump)]
+    pub amm_config: Box<Account<'info, AmmConfig>>,
+
+    /// Pool authority PDA
+    #[account(
+        seeds = [b"pool_authority", liquidity_pool.key().as_ref()],
+        bump = liquidity_pool.load()?.authority_bump
+    )]
+    pub pool_authority: SystemAccount<'info>,
+
+    /// Token A mint
+    #[account(mut, address = liquidity_pool.load()?.token_a_mint)]
+    pub token_a_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    /// Token B mint
+    #[account(mut, address = liquidity_pool.load()?.token_b_mint)]
+    pub token_b_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    /// Token A vault
+    #[account(
+        mut,
+        seeds = [b"token_a_vault", liquidity_pool.key().as_ref()],
+        bump = liquidity_pool.load()?.token_a_vault_bump
+    )]
+    pub token_a_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Token B vault
+    #[account(
+        mut,
+        seeds = [b"token_b_vault", liquidity_pool.key().as_ref()],
+        bump = liquidity_pool.load()?.token_b_vault_bump
+    )]
+    pub token_b_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Protocol fee vault for token A
+    #[account(
+        mut,
+        seeds = [
+            b"protocol_fee_vault",
+            liquidity_pool.key().as_ref(),
+            token_a_mint.key().as_ref()
+        ],
+        bump,
+    )]
+    pub protocol_fee_vault_a: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Protocol fee vault for token B
+    #[account(
+        mut,
+        seeds = [
+            b"protocol_fee_vault",
+            liquidity_pool.key().as_ref(),
+            token_b_mint.key().as_ref()
+        ],
+        bump,
+    )]
+    pub protocol_fee_vault_b: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct HarvestProtocolFees<'info> {
+    /// Pool state account
+    #[account(mut)]
+    pub liquidity_pool: AccountLoader<'info, LiquidityPool>,
+
+    /// Pool authority PDA, owner of the protocol fee vaults
+    #[account(
+        seeds = [b"pool_authority", liquidity_pool.key().as_ref()],
+        bump = liquidity_pool.load()?.authority_bump
+    )]
+    pub pool_authority: SystemAccount<'info>,
+
+    /// Token A mint
+    #[account(address = liquidity_pool.load()?.token_a_mint)]
+    pub token_a_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    /// Token B mint
+    #[account(address = liquidity_pool.load()?.token_b_mint)]
+    pub token_b_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    /// Protocol fee vault for token A
+    #[account(
+        mut,
+        seeds = [
+            b"protocol_fee_vault",
+            liquidity_pool.key().as_ref(),
+            token_a_mint.key().as_ref()
+        ],
+        bump,
+    )]
+    pub protocol_fee_vault_a: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Protocol fee vault for token B
+    #[account(
+        mut,
+        seeds = [
+            b"protocol_fee_vault",
+            liquidity_pool.key().as_ref(),
+            token_b_mint.key().as_ref()
+        ],
+        bump,
+    )]
+    pub protocol_fee_vault_b: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Treasury vault of token A
+    #[account(
+        mut,
+        seeds = [b"treasury_vault", token_a_mint.key().as_ref()],
+        bump,
+    )]
+    pub treasury_vault_a: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Treasury vault of token B
+    #[account(
+        mut,
+        seeds = [b"treasury_vault", token_b_mint.key().as_ref()],
+        bump,
+    )]
+    pub treasury_vault_b: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Caller's token A account receiving the keeper tip
+    #[account(mut, constraint = keeper_token_a_account.mint == token_a_mint.key())]
+    pub keeper_token_a_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    /// Token program of token A
+    #[account(address = *token_a_mint.to_account_info().owner @ AmmError::InvalidTokenProgram)]
+    pub token_a_program: Interface<'info, TokenInterface>,
+
+    /// Token program of token B
+    #[account(address = *token_b_mint.to_account_info().owner @ AmmError::InvalidTokenProgram)]
+    pub token_b_program: Interface<'info, TokenInterface>,
+}
@@ -0,0 +1,208 @@
+use crate::errors::AmmError;
+use crate::fixed_point::{self, Q64x64};
+use crate::math;
+use crate::state::LiquidityPool;
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{
+    spl_token_2022::{
+        self,
+        extension::{
+            interest_bearing_mint::InterestBearingConfig, BaseStateWithExtensions,
+            StateWithExtensions,
+        },
+        state::Mint as MintState,
+    },
+    Mint, TokenAccount,
+};
+
+/// Program-computed rebalance numbers for keepers and vault strategies
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct RebalanceHint {
+    /// Current spot price (token B per token A), as Q64.64 bits
+    pub current_price: u128,
+
+    /// Requested target price, as Q64.64 bits
+    pub target_price: u128,
+
+    /// Distance of the current price from the target, in basis points of the target
+    pub deviation_bps: u128,
+
+    /// Direction of the suggested trade
+    pub a_to_b: bool,
+
+    /// Input (fee included) that moves the spot price to the target
+    pub suggested_input: u64,
+
+    /// Output the suggested input would receive at current reserves
+    pub expected_output: u64,
+}
+
+/// Quote the trade that moves the pool's spot price to `target_price`, given
+/// as Q64.64 bits.
+/// The hint is returned as instruction return data.
+pub fn rebalance_hint(ctx: Context<QuoteRebalance>, target_price: u128) -> Result<RebalanceHint> {
+    require!(target_price > 0, AmmError::InvalidPoolConfig);
+
+    let pool = &ctx.accounts.liquidity_pool.load()?;
+    let reserve_a = ctx.accounts.token_a_vault.amount as u128;
+    let reserve_b = ctx.accounts.token_b_vault.amount as u128;
+    require!(reserve_a > 0 && reserve_b > 0, AmmError::PoolHasNoLiquidity);
+
+    let target = Q64x64::from_bits(target_price);
+    let current_price = LiquidityPool::spot_price(reserve_a, reserve_b)?;
+    let deviation_bps = LiquidityPool::deviation_bps(current_price, target)?;
+
+    // On x * y = k the price b / a reaches the target at a' = sqrt(k) / sqrt(target)
+    let invariant = reserve_a
+        .checked_mul(reserve_b)
+        .ok_or(AmmError::MathOverflow)?;
+    let sqrt_invariant = math::checked_sqrt(invariant)?;
+    let target_reserve_a = fixed_point::mul_div_floor(
+        sqrt_invariant,
+        Q64x64::ONE.to_bits(),
+        target.sqrt()?.to_bits(),
+    )?;
+
+    let a_to_b = target_reserve_a > reserve_a;
+    let (input_reserve, output_reserve, net_input) = if a_to_b {
+        (reserve_a, reserve_b, target_reserve_a - reserve_a)
+    } else {
+        let target_reserve_b = math::mul_div_ceil(invariant, 1, target_reserve_a.max(1))?;
+        (
+            reserve_b,
+            reserve_a,
+            target_reserve_b.saturating_sub(reserve_b),
+        )
+    };
+
+    // Gross up so the net input after an input-side fee lands on the target; an
+    // output-side fee leaves the input as is and stays in the output reserve
+    let suggested_input = if pool.fee_on_output != 0 {
+        net_input
+    } else {
+        pool.amount_before_fee(net_input)?
+    };
+    let (_, expected_output) = pool.swap_amounts(input_reserve, output_reserve, suggested_input)?;
+
+    let hint = RebalanceHint {
+        current_price: current_price.to_bits(),
+        target_price,
+        deviation_bps,
+        a_to_b,
+        suggested_input: math::to_u64(suggested_input)?,
+        expected_output: math::to_u64(expected_output)?,
+    };
+
+    log!(
+        "Rebalance hint - Price: {}, Target: {}, A to B: {}, Input: {}, Output: {}",
+        hint.current_price,
+        hint.target_price,
+        hint.a_to_b,
+        hint.suggested_input,
+        hint.expected_output
+    );
+
+    Ok(hint)
+}
+
+/// Raw amounts of a pool's tokens in UI units, for display
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub struct UiAmounts {
+    /// Requested token A amount
+    pub token_a_ui_amount: String,
+
+    /// Requested token B amount
+    pub token_b_ui_amount: String,
+
+    /// Token A vault balance; the ratio of the two reserves is the UI price
+    pub reserve_a_ui_amount: String,
+
+    /// Token B vault balance
+    pub reserve_b_ui_amount: String,
+}
+
+/// UI representation of a raw `amount` of the mint in `mint_data` at
+/// `unix_timestamp`: scaled by the interest accrued on an interest-bearing
+/// Token-2022 mint, else by its decimals alone. Only for display: the curve
+/// and every quote work on raw amounts.
+pub fn ui_amount(mint_data: &[u8], amount: u64, unix_timestamp: i64) -> Result<String> {
+    let mint = StateWithExtensions::<MintState>::unpack(mint_data)?;
+    let decimals = mint.base.decimals;
+    match mint.get_extension::<InterestBearingConfig>() {
+        Ok(config) => Ok(config
+            .amount_to_ui_amount(amount, decimals, unix_timestamp)
+            .ok_or(AmmError::MathOverflow)?),
+        Err(_) => Ok(spl_token_2022::amount_to_ui_amount_string_trimmed(
+            amount, decimals,
+        )),
+    }
+}
+
+/// Convert raw token amounts and the pool's reserves to UI amounts at the
+/// current time, returned as return data
+pub fn ui_amounts(
+    ctx: Context<QuoteUiAmounts>,
+    token_a_amount: u64,
+    token_b_amount: u64,
+) -> Result<UiAmounts> {
+    let now = Clock::get()?.unix_timestamp;
+    let token_a_mint = ctx.accounts.token_a_mint.to_account_info();
+    let token_b_mint = ctx.accounts.token_b_mint.to_account_info();
+    let token_a_data = token_a_mint.try_borrow_data()?;
+    let token_b_data = token_b_mint.try_borrow_data()?;
+
+    Ok(UiAmounts {
+        token_a_ui_amount: ui_amount(&token_a_data, token_a_amount, now)?,
+        token_b_ui_amount: ui_amount(&token_b_data, token_b_amount, now)?,
+        reserve_a_ui_amount: ui_amount(&token_a_data, ctx.accounts.token_a_vault.amount, now)?,
+        reserve_b_ui_amount: ui_amount(&token_b_data, ctx.accounts.token_b_vault.amount, now)?,
+    })
+}
+
+#[derive(Accounts)]
+pub struct QuoteRebalance<'info> {
+    /// Pool state account
+    pub liquidity_pool: AccountLoader<'info, LiquidityPool>,
+
+    /// Token A vault
+    #[account(
+        seeds = [b"token_a_vault", liquidity_pool.key().as_ref()],
+        bump = liquidity_pool.load()?.token_a_vault_bump
+    )]
+    pub token_a_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Token B vault
+    #[account(
+        seeds = [b"token_b_vault", liquidity_pool.key().as_ref()],
+        bump = liquidity_pool.load()?.token_b_vault_bump
+    )]
+    pub token_b_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+}
+
+#[derive(Accounts)]
+pub struct QuoteUiAmounts<'info> {
+    /// Pool state account
+    pub liquidity_pool: AccountLoader<'info, LiquidityPool>,
+
+    /// Token A mint, whose interest rate scales its UI amounts
+    #[account(address = liquidity_pool.load()?.token_a_mint)]
+    pub token_a_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    /// Token B mint
+    #[account(address = liquidity_pool.load()?.token_b_mint)]
+    pub token_b_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    /// Token A vault
+    #[account(
+        seeds = [b"token_a_vault", liquidity_pool.key().as_ref()],
+        bump = liquidity_pool.load()?.token_a_vault_bump
+    )]
+    pub token_a_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Token B vault
+    #[account(
+        seeds = [b"token_b_vault", liquidity_pool.key().as_ref()],
+        bump = liquidity_pool.load()?.token_b_vault_bump
+    )]
+    pub token_b_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+}
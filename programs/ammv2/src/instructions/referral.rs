@@ -0,0 +1,140 @@
+use crate::errors::AmmError;
+use crate::state::Referrer;
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{self, Mint, TokenAccount, TokenInterface, TransferChecked};
+
+/// Register the signer as a referrer. Swaps that name the referrer accrue
+/// its share of the fee, in any pool that pays one.
+pub fn register(ctx: Context<RegisterReferrer>) -> Result<()> {
+    let referrer = &mut ctx.accounts.referrer;
+    referrer.authority = ctx.accounts.authority.key();
+    referrer.bump = ctx.bumps.referrer;
+
+    log!("Referrer registered - Authority: {}", referrer.authority);
+    Ok(())
+}
+
+/// Create the referral vault of a mint, which holds every referrer's
+/// unclaimed fees in that mint
+pub fn initialize_vault(ctx: Context<InitializeReferralVault>) -> Result<()> {
+    log!(
+        "Referral vault initialized - Mint: {}, Vault: {}",
+        ctx.accounts.mint.key(),
+        ctx.accounts.referral_vault.key()
+    );
+    Ok(())
+}
+
+/// Pay out the referrer's unclaimed fees in one mint
+pub fn claim(ctx: Context<ClaimReferralFees>) -> Result<()> {
+    let mint = ctx.accounts.mint.key();
+    let amount = ctx.accounts.referrer.take(&mint);
+    require!(amount > 0, AmmError::NoReferralFees);
+
+    let authority_seeds = &[
+        b"referral_authority".as_ref(),
+        &[ctx.bumps.referral_authority],
+    ];
+    let signer_seeds = &[&authority_seeds[..]];
+    token_interface::transfer_checked(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            TransferChecked {
+                from: ctx.accounts.referral_vault.to_account_info(),
+                mint: ctx.accounts.mint.to_account_info(),
+                to: ctx.accounts.destination.to_account_info(),
+                authority: ctx.accounts.referral_authority.to_account_info(),
+            },
+            signer_seeds,
+        ),
+        amount,
+        ctx.accounts.mint.decimals,
+    )?;
+
+    log!("Referral fees claimed - Mint: {}, Amount: {}", mint, amount);
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct RegisterReferrer<'info> {
+    /// Referrer of the signing wallet
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + Referrer::INIT_SPACE,
+        seeds = [b"referrer", authority.key().as_ref()],
+        bump,
+    )]
+    pub referrer: Box<Account<'info, Referrer>>,
+
+    /// Wallet claiming the referral fees, pays for the account
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeReferralVault<'info> {
+    /// Referral authority PDA, owner of the vaults
+    #[account(seeds = [b"referral_authority"], bump)]
+    pub referral_authority: SystemAccount<'info>,
+
+    /// Mint the vault holds
+    pub mint: Box<InterfaceAccount<'info, Mint>>,
+
+    /// Referral vault of the mint
+    #[account(
+        init,
+        payer = payer,
+        seeds = [b"referral_vault", mint.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = referral_authority,
+        token::token_program = token_program,
+    )]
+    pub referral_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Anyone may create the vault and pay for the account
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimReferralFees<'info> {
+    /// Referrer claiming its fees
+    #[account(
+        mut,
+        seeds = [b"referrer", authority.key().as_ref()],
+        bump = referrer.bump,
+        has_one = authority @ AmmError::Unauthorized,
+    )]
+    pub referrer: Box<Account<'info, Referrer>>,
+
+    /// Referrer's authority
+    pub authority: Signer<'info>,
+
+    /// Referral authority PDA, owner of the vaults
+    #[account(seeds = [b"referral_authority"], bump)]
+    pub referral_authority: SystemAccount<'info>,
+
+    /// Mint being claimed
+    pub mint: Box<InterfaceAccount<'info, Mint>>,
+
+    /// Referral vault of the mint
+    #[account(
+        mut,
+        seeds = [b"referral_vault", mint.key().as_ref()],
+        bump,
+    )]
+    pub referral_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Token account receiving the fees
+    #[account(mut, constraint = destination.mint == mint.key())]
+    pub destination: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
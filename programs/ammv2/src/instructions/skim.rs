@@ -0,0 +1,130 @@
+use crate::errors::AmmError;
+use crate::state::{AmmConfig, LiquidityPool};
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{self, Mint, TokenAccount, TokenInterface, TransferChecked};
+
+/// Sweep vault balances above the pool's tracked reserves, e.g. tokens sent
+/// straight to a vault, to the protocol treasury
+pub fn process(ctx: Context<Skim>) -> Result<()> {
+    let (excess_a, excess_b) = {
+        let pool = ctx.accounts.liquidity_pool.load()?;
+        (
+            ctx.accounts
+                .token_a_vault
+                .amount
+                .saturating_sub(pool.reserve_a),
+            ctx.accounts
+                .token_b_vault
+                .amount
+                .saturating_sub(pool.reserve_b),
+        )
+    };
+
+    let pool_key = ctx.accounts.liquidity_pool.key();
+    let authority_bump = ctx.accounts.liquidity_pool.load()?.authority_bump;
+    let authority_seeds = &[b"pool_authority", pool_key.as_ref(), &[authority_bump]];
+    let signer_seeds = &[&authority_seeds[..]];
+
+    let accounts = &ctx.accounts;
+    for (vault, mint, destination, token_program, amount) in [
+        (
+            &accounts.token_a_vault,
+            &accounts.token_a_mint,
+            &accounts.treasury_token_a_account,
+            &accounts.token_a_program,
+            excess_a,
+        ),
+        (
+            &accounts.token_b_vault,
+            &accounts.token_b_mint,
+            &accounts.treasury_token_b_account,
+            &accounts.token_b_program,
+            excess_b,
+        ),
+    ] {
+        if amount > 0 {
+            token_interface::transfer_checked(
+                CpiContext::new_with_signer(
+                    token_program.to_account_info(),
+                    TransferChecked {
+                        from: vault.to_account_info(),
+                        mint: mint.to_account_info(),
+                        to: destination.to_account_info(),
+                        authority: accounts.pool_authority.to_account_info(),
+                    },
+                    signer_seeds,
+                ),
+                amount,
+                mint.decimals,
+            )?;
+        }
+    }
+
+    log!("Skimmed - Token A: {}, Token B: {}", excess_a, excess_b);
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct Skim<'info> {
+    /// Pool state account
+    pub liquidity_pool: AccountLoader<'info, LiquidityPool>,
+
+    /// Global config, names the treasury
+    #[account(seeds = [b"amm_config"], bump)]
+    pub amm_config: Box<Account<'info, AmmConfig>>,
+
+    /// Pool authority PDA
+    #[account(
+        seeds = [b"pool_authority", liquidity_pool.key().as_ref()],
+        bump = liquidity_pool.load()?.authority_bump
+    )]
+    pub pool_authority: SystemAccount<'info>,
+
+    /// Token A mint
+    #[account(address = liquidity_pool.load()?.token_a_mint)]
+    pub token_a_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    /// Token B mint
+    #[account(address = liquidity_pool.load()?.token_b_mint)]
+    pub token_b_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    /// Token A vault
+    #[account(
+        mut,
+        seeds = [b"token_a_vault", liquidity_pool.key().as_ref()],
+        bump = liquidity_pool.load()?.token_a_vault_bump
+    )]
+    pub token_a_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Token B vault
+    #[account(
+        mut,
+        seeds = [b"token_b_vault", liquidity_pool.key().as_ref()],
+        bump = liquidity_pool.load()?.token_b_vault_bump
+    )]
+    pub token_b_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Treasury's token A account (destination)
+    #[account(
+        mut,
+        constraint = treasury_token_a_account.mint == token_a_mint.key(),
+        constraint = treasury_token_a_account.owner == amm_config.treasury @ AmmError::InvalidTokenAccountOwner
+    )]
+    pub treasury_token_a_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Treasury's token B account (destination)
+    #[account(
+        mut,
+        constraint = treasury_token_b_account.mint == token_b_mint.key(),
+        constraint = treasury_token_b_account.owner == amm_config.treasury @ AmmError::InvalidTokenAccountOwner
+    )]
+    pub treasury_token_b_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Token program of token A
+    #[account(address = *token_a_mint.to_account_info().owner @ AmmError::InvalidTokenProgram)]
+    pub token_a_program: Interface<'info, TokenInterface>,
+
+    /// Token program of token B
+    #[account(address = *token_b_mint.to_account_info().owner @ AmmError::InvalidTokenProgram)]
+    pub token_b_program: Interface<'info, TokenInterface>,
+}
@@ -0,0 +1,132 @@
+use crate::errors::AmmError;
+use crate::state::SubAccount;
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{self, Mint, TokenAccount, TokenInterface, TransferChecked};
+
+/// Create a new sub-account under the signing owner
+pub fn create(ctx: Context<CreateSubAccount>, sub_account_id: u16) -> Result<()> {
+    let sub_account = &mut ctx.accounts.sub_account;
+    sub_account.owner = ctx.accounts.owner.key();
+    sub_account.delegate = Pubkey::default();
+    sub_account.sub_account_id = sub_account_id;
+    sub_account.bump = ctx.bumps.sub_account;
+
+    log!(
+        "Sub-account {} created for {}",
+        sub_account_id,
+        sub_account.owner
+    );
+    Ok(())
+}
+
+/// Set or revoke the sub-account delegate
+pub fn set_delegate(ctx: Context<ManageSubAccount>, delegate: Option<Pubkey>) -> Result<()> {
+    let sub_account = &mut ctx.accounts.sub_account;
+    sub_account.delegate = delegate.unwrap_or_default();
+
+    log!("Sub-account delegate set to {}", sub_account.delegate);
+    Ok(())
+}
+
+/// Close an unused sub-account and return its rent to the owner
+pub fn close(_ctx: Context<CloseSubAccount>) -> Result<()> {
+    log!("Sub-account closed");
+    Ok(())
+}
+
+/// Move tokens held by the sub-account out to any destination (owner only)
+pub fn transfer_out(ctx: Context<SubAccountTransfer>, amount: u64) -> Result<()> {
+    let signer = ctx.accounts.sub_account.signer();
+    let sub_account_seeds = signer.seeds();
+    let signer_seeds = &[&sub_account_seeds[..]];
+
+    token_interface::transfer_checked(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            TransferChecked {
+                from: ctx.accounts.source_token_account.to_account_info(),
+                mint: ctx.accounts.mint.to_account_info(),
+                to: ctx.accounts.destination_token_account.to_account_info(),
+                authority: ctx.accounts.sub_account.to_account_info(),
+            },
+            signer_seeds,
+        ),
+        amount,
+        ctx.accounts.mint.decimals,
+    )?;
+
+    log!("Transferred {} tokens out of sub-account", amount);
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(sub_account_id: u16)]
+pub struct CreateSubAccount<'info> {
+    /// Sub-account PDA scoped to the owner and index
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + SubAccount::INIT_SPACE,
+        seeds = [
+            b"sub_account",
+            owner.key().as_ref(),
+            &sub_account_id.to_le_bytes()
+        ],
+        bump,
+    )]
+    pub sub_account: Box<Account<'info, SubAccount>>,
+
+    /// Master owner, pays for the account
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ManageSubAccount<'info> {
+    /// Sub-account being updated
+    #[account(mut, has_one = owner @ AmmError::Unauthorized)]
+    pub sub_account: Box<Account<'info, SubAccount>>,
+
+    /// Master owner
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CloseSubAccount<'info> {
+    /// Sub-account being closed
+    #[account(mut, has_one = owner @ AmmError::Unauthorized, close = owner)]
+    pub sub_account: Box<Account<'info, SubAccount>>,
+
+    /// Master owner, receives the rent
+    #[account(mut)]
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SubAccountTransfer<'info> {
+    /// Sub-account that owns the source tokens
+    #[account(has_one = owner @ AmmError::Unauthorized)]
+    pub sub_account: Box<Account<'info, SubAccount>>,
+
+    /// Master owner; delegates cannot move funds out
+    pub owner: Signer<'info>,
+
+    /// Mint of the tokens being moved
+    pub mint: Box<InterfaceAccount<'info, Mint>>,
+
+    /// Token account owned by the sub-account
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = sub_account,
+    )]
+    pub source_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Destination token account
+    #[account(mut, token::mint = mint)]
+    pub destination_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
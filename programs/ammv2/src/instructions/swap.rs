@@ -1,149 +1,939 @@
 use crate::errors::AmmError;
-use crate::state::LiquidityPool;
+use crate::events::SwapExecuted;
+use crate::fixed_point::Q64x64;
+use crate::instructions::memo_transfer::memo_if_required;
+use crate::math;
+use crate::state::{
+    AmmConfig, LaunchWhitelistEntry, LiquidityPool, PoolVolumeStats, PriceOracle, Referrer,
+    TraderState, BASIS_POINTS_DIVISOR, POOL_OP_SWAP,
+};
 use anchor_lang::prelude::*;
-use anchor_spl::token_interface::{self, TokenAccount, TokenInterface};
+use anchor_lang::solana_program::program_option::COption;
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::memo::Memo;
+use anchor_spl::token_interface::{self, Mint, TokenAccount, TokenInterface, TransferChecked};
 
-/// Swap tokens using constant product AMM formula (x * y = k)
+/// Amounts of an executed swap, returned to the caller as instruction return
+/// data so composing programs need not diff token balances
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SwapResult {
+    /// Input actually swapped, below the requested amount on a partial fill.
+    /// An incident surcharge is paid on top.
+    pub amount_in: u64,
+
+    /// Tokens paid out to the trader
+    pub amount_out: u64,
+
+    /// Pool fee, in the output token when the pool charges fees on output
+    pub fee_amount: u64,
+}
+
+/// Swap tokens using constant product AMM formula (x * y = k).
+///
+/// `slippage_bps` bounds the output against the pool's own price at execution
+/// time, so a client needn't quote fresh reserves. It limits price impact only;
+/// `minimum_output_amount` still guards against the price moving beforehand.
+///
+/// `price_limit` is the worst post-swap spot price (token B per token A, as
+/// Q64.64 bits) the trader accepts: a floor when selling token A, a ceiling
+/// when selling token B.
+///
+/// With `allow_partial_fill`, an input over the pool's trade size cap, circuit
+/// breaker or the price limit is trimmed to the largest amount they allow instead
+/// of failing; the rest stays with the user and `minimum_output_amount` applies to
+/// the filled part.
 pub fn process(
     ctx: Context<SwapTokens>,
     input_amount: u64,
     minimum_output_amount: u64,
-) -> Result<()> {
+    slippage_bps: Option<u16>,
+    allow_partial_fill: bool,
+    price_limit: Option<u128>,
+    client_order_id: Option<u64>,
+) -> Result<SwapResult> {
+    execute(
+        ctx,
+        input_amount,
+        minimum_output_amount,
+        SwapLimits {
+            slippage_bps,
+            price_limit: price_limit.map(Q64x64::from_bits),
+            allow_partial_fill,
+            ..Default::default()
+        },
+        SwapReserves::VaultBalances,
+        client_order_id,
+    )
+}
+
+/// Buy exactly `output_amount`, solving the input against the current vault
+/// balances. The input, plus any incident surcharge, is capped by `maximum_input_amount`.
+pub fn process_exact_output(
+    ctx: Context<SwapTokens>,
+    output_amount: u64,
+    maximum_input_amount: u64,
+    client_order_id: Option<u64>,
+) -> Result<SwapResult> {
+    let input_amount = {
+        let pool = ctx.accounts.liquidity_pool.load()?;
+        let input_vault_balance = ctx.accounts.input_token_vault.amount as u128;
+        let output_vault_balance = ctx.accounts.output_token_vault.amount as u128;
+        require!(
+            input_vault_balance > 0 && output_vault_balance > 0,
+            AmmError::PoolHasNoLiquidity
+        );
+        math::to_u64(pool.swap_input_for_output(
+            input_vault_balance,
+            output_vault_balance,
+            output_amount as u128,
+        )?)?
+    };
+    require!(
+        input_amount <= maximum_input_amount,
+        AmmError::MaxInputExceeded
+    );
+
+    log!("Exact output swap - Solved input: {}", input_amount);
+
+    execute(
+        ctx,
+        input_amount,
+        output_amount,
+        SwapLimits {
+            maximum_input_amount: Some(maximum_input_amount),
+            ..Default::default()
+        },
+        SwapReserves::VaultBalances,
+        client_order_id,
+    )
+}
+
+/// Swap `input_amount` if the average execution price (output tokens per input
+/// token, as Q64.64 bits) is at most `tolerance_bps` worse than `expected_price`.
+/// Unlike a raw minimum output, a miss reports how far the price moved.
+pub fn process_at_expected_price(
+    ctx: Context<SwapTokens>,
+    input_amount: u64,
+    expected_price: u128,
+    tolerance_bps: u16,
+    client_order_id: Option<u64>,
+) -> Result<SwapResult> {
+    require!(
+        (tolerance_bps as u128) <= BASIS_POINTS_DIVISOR,
+        AmmError::InvalidSlippageTolerance
+    );
+
+    execute(
+        ctx,
+        input_amount,
+        0,
+        SwapLimits {
+            expected_price: Some((Q64x64::from_bits(expected_price), tolerance_bps)),
+            ..Default::default()
+        },
+        SwapReserves::VaultBalances,
+        client_order_id,
+    )
+}
+
+/// `process` priced against the pool's tracked reserves instead of the vault
+/// balances. Tokens transferred straight into a vault, even earlier in the same
+/// transaction, cannot move the price; they stay surplus for `skim`.
+pub fn process_v2(
+    ctx: Context<SwapTokens>,
+    input_amount: u64,
+    minimum_output_amount: u64,
+    slippage_bps: Option<u16>,
+    allow_partial_fill: bool,
+    price_limit: Option<u128>,
+    client_order_id: Option<u64>,
+) -> Result<SwapResult> {
+    execute(
+        ctx,
+        input_amount,
+        minimum_output_amount,
+        SwapLimits {
+            slippage_bps,
+            price_limit: price_limit.map(Q64x64::from_bits),
+            allow_partial_fill,
+            ..Default::default()
+        },
+        SwapReserves::Tracked,
+        client_order_id,
+    )
+}
+
+/// Balances the curve prices a swap against
+enum SwapReserves {
+    /// Current vault balances, so any surplus moves the price; the surplus
+    /// itself stays out of the tracked reserves
+    VaultBalances,
+    /// `reserve_a` / `reserve_b` of the pool
+    Tracked,
+}
+
+/// Optional trader-side bounds on a swap
+#[derive(Default)]
+struct SwapLimits {
+    slippage_bps: Option<u16>,
+    maximum_input_amount: Option<u64>,
+    price_limit: Option<Q64x64>,
+    allow_partial_fill: bool,
+    /// Expected average execution price and the tolerated shortfall in bps
+    expected_price: Option<(Q64x64, u16)>,
+}
+
+fn execute(
+    ctx: Context<SwapTokens>,
+    input_amount: u64,
+    minimum_output_amount: u64,
+    limits: SwapLimits,
+    reserves: SwapReserves,
+    client_order_id: Option<u64>,
+) -> Result<SwapResult> {
+    ctx.accounts.amm_config.check_not_paused()?;
     // Verify user has sufficient input tokens
     require!(
         ctx.accounts.user_input_token_account.amount >= input_amount,
         AmmError::InsufficientBalance
     );
 
-    let pool = &ctx.accounts.liquidity_pool;
+    // A delegate may only spend what it was approved for
+    let input_account = &ctx.accounts.user_input_token_account;
+    if input_account.owner != ctx.accounts.user.key() {
+        require!(
+            input_account.delegated_amount >= input_amount,
+            AmmError::InsufficientDelegation
+        );
+    }
+
+    let pool = &mut ctx.accounts.liquidity_pool.load_mut()?;
+    require!(pool.withdraw_only == 0, AmmError::PoolWithdrawOnly);
+    pool.check_operation_enabled(POOL_OP_SWAP)?;
+
+    let input_is_token_a = ctx.accounts.input_token_vault.mint == pool.token_a_mint;
     let input_vault_balance = ctx.accounts.input_token_vault.amount as u128;
     let output_vault_balance = ctx.accounts.output_token_vault.amount as u128;
+    let (input_reserve, output_reserve) = match reserves {
+        SwapReserves::VaultBalances => (input_vault_balance, output_vault_balance),
+        SwapReserves::Tracked if input_is_token_a => {
+            (pool.reserve_a as u128, pool.reserve_b as u128)
+        }
+        SwapReserves::Tracked => (pool.reserve_b as u128, pool.reserve_a as u128),
+    };
+    // Fail early with a clear error rather than deep in the curve math
+    require!(
+        input_reserve > 0 && output_reserve > 0,
+        AmmError::PoolHasNoLiquidity
+    );
+    let clock = Clock::get()?;
+    let current_slot = clock.slot;
+
+    // Trim a partial fill to what the trade size cap and circuit breaker allow
+    let requested_input_amount = input_amount;
+    let input_amount = if limits.allow_partial_fill {
+        let fillable = math::to_u64(pool.fillable_input(
+            current_slot,
+            input_is_token_a,
+            input_reserve,
+            output_reserve,
+            input_amount as u128,
+            limits.price_limit,
+        )?)?;
+        require!(fillable > 0, AmmError::NothingFillable);
+        fillable
+    } else {
+        input_amount
+    };
     let input_amount_u128 = input_amount as u128;
 
-    // Calculate trading fee
-    let fee_amount = pool.calculate_fee(input_amount_u128)?;
-    let input_after_fee = input_amount_u128
-        .checked_sub(fee_amount)
-        .ok_or(AmmError::MathOverflow)?;
+    // Maximum trade size relative to the input reserve
+    if pool.max_trade_bps_of_reserves > 0 {
+        let max_input = math::mul_div_floor(
+            input_reserve,
+            pool.max_trade_bps_of_reserves as u128,
+            BASIS_POINTS_DIVISOR,
+        )?;
+        require!(input_amount_u128 <= max_input, AmmError::TradeTooLarge);
+    }
 
-    msg!(
-        "Swap details - Input: {}, Fee: {}, Net input: {}",
+    // Collect input tokens from user before paying anything out (including fee)
+    token_interface::transfer_checked(
+        CpiContext::new(
+            ctx.accounts.input_token_program.to_account_info(),
+            TransferChecked {
+                from: ctx.accounts.user_input_token_account.to_account_info(),
+                mint: ctx.accounts.input_token_mint.to_account_info(),
+                to: ctx.accounts.input_token_vault.to_account_info(),
+                authority: ctx.accounts.user.to_account_info(),
+            },
+        ),
         input_amount,
-        fee_amount,
-        input_after_fee
-    );
+        ctx.accounts.input_token_mint.decimals,
+    )?;
 
-    // Constant product formula: x * y = k
-    // Where k is the invariant that must be maintained
-    let invariant = input_vault_balance
-        .checked_mul(output_vault_balance)
+    // Price against what the vault actually received
+    ctx.accounts.input_token_vault.reload()?;
+    let input_received = (ctx.accounts.input_token_vault.amount as u128)
+        .checked_sub(input_vault_balance)
         .ok_or(AmmError::MathOverflow)?;
 
-    // New input vault balance after adding tokens
-    let new_input_vault_balance = input_vault_balance
-        .checked_add(input_after_fee)
+    // Constant product formula: x * y = k
+    // Where k is the invariant that must be maintained
+    let invariant = input_reserve
+        .checked_mul(output_reserve)
         .ok_or(AmmError::MathOverflow)?;
 
-    // Calculate new output vault balance to maintain invariant
-    let new_output_vault_balance = invariant
-        .checked_div(new_input_vault_balance)
-        .ok_or(AmmError::MathOverflow)?;
+    // Trading fee, taken from the input or the output, and the output amount:
+    // current balance - balance that keeps the invariant, rounded down in the
+    // pool's favor
+    let fee_on_output = pool.fee_on_output != 0;
+    let (fee_amount, output_amount) =
+        pool.swap_amounts(input_reserve, output_reserve, input_received)?;
+    let new_output_reserve = output_reserve - output_amount;
 
-    // Output amount = current balance - new balance
-    let output_amount = output_vault_balance
-        .checked_sub(new_output_vault_balance)
-        .ok_or(AmmError::MathOverflow)?;
+    log!(
+        "Swap details - Input: {}, Received: {}, Fee: {} (on {}), Output: {}",
+        input_amount,
+        input_received,
+        fee_amount,
+        if fee_on_output { "output" } else { "input" },
+        output_amount
+    );
 
-    msg!("Calculated output amount: {}", output_amount);
+    // Dust inputs must not be swallowed for a zero payout
+    require!(output_amount > 0, AmmError::OutputTooSmall);
 
     // Slippage protection
     require!(
         output_amount >= minimum_output_amount as u128,
         AmmError::SlippageExceeded
     );
+    if let Some(slippage_bps) = limits.slippage_bps {
+        let slippage_bps = slippage_bps as u128;
+        require!(
+            slippage_bps <= BASIS_POINTS_DIVISOR,
+            AmmError::InvalidSlippageTolerance
+        );
+        // Output at the pre-trade spot price, i.e. with no price impact
+        let spot_output = if fee_on_output {
+            let gross_output = math::mul_div_floor(input_received, output_reserve, input_reserve)?;
+            gross_output - pool.calculate_fee(gross_output)?
+        } else {
+            math::mul_div_floor(input_received - fee_amount, output_reserve, input_reserve)?
+        };
+        let minimum_output = math::mul_div_ceil(
+            spot_output,
+            BASIS_POINTS_DIVISOR - slippage_bps,
+            BASIS_POINTS_DIVISOR,
+        )?;
+        require!(output_amount >= minimum_output, AmmError::SlippageExceeded);
+    }
+    if let Some((expected_price, tolerance_bps)) = limits.expected_price {
+        let execution_price = Q64x64::from_ratio(output_amount, input_amount_u128)?;
+        if execution_price < expected_price {
+            let shortfall_bps = LiquidityPool::deviation_bps(execution_price, expected_price)?;
+            if shortfall_bps > tolerance_bps as u128 {
+                log!(
+                    "Price moved {}.{:02}% against the expected price, beyond the {}.{:02}% tolerance",
+                    shortfall_bps / 100,
+                    shortfall_bps % 100,
+                    tolerance_bps / 100,
+                    tolerance_bps % 100
+                );
+                return err!(AmmError::PriceToleranceExceeded);
+            }
+        }
+    }
+
+    // Pool-side cap on price impact, independent of the trader's minimum output
+    let final_input_reserve = input_reserve
+        .checked_add(input_received)
+        .ok_or(AmmError::MathOverflow)?;
+    pool.check_price_impact(
+        input_reserve,
+        output_reserve,
+        final_input_reserve,
+        new_output_reserve,
+    )?;
+
+    // Spot prices (token B per token A) before and after the trade
+    let (pre_swap_price, post_swap_price) = if input_is_token_a {
+        (
+            LiquidityPool::spot_price(input_reserve, output_reserve)?,
+            LiquidityPool::spot_price(final_input_reserve, new_output_reserve)?,
+        )
+    } else {
+        (
+            LiquidityPool::spot_price(output_reserve, input_reserve)?,
+            LiquidityPool::spot_price(new_output_reserve, final_input_reserve)?,
+        )
+    };
+
+    if let Some(price_limit) = limits.price_limit {
+        require!(
+            LiquidityPool::within_price_limit(input_is_token_a, post_swap_price, price_limit),
+            AmmError::PriceLimitExceeded
+        );
+    }
+
+    // Per-slot circuit breaker on the resulting spot price
+    if pool.max_price_change_bps_per_slot > 0 {
+        pool.check_price_movement(current_slot, pre_swap_price, post_swap_price)?;
+    }
+
+    // Snipe protection: only approved wallets trade during the launch window
+    require!(
+        !pool.launch_whitelist_active(current_slot)
+            || ctx.accounts.launch_whitelist_entry.is_some(),
+        AmmError::NotLaunchWhitelisted
+    );
+
+    // Per-trader anti-sandwich limits and launch cooldown
+    if pool.tracks_traders(current_slot) {
+        let trader_state = ctx
+            .accounts
+            .trader_state
+            .as_mut()
+            .ok_or(AmmError::TraderStateRequired)?;
+        trader_state.liquidity_pool = ctx.accounts.liquidity_pool.key();
+        trader_state.trader = ctx.accounts.user.key();
+
+        let volume_bps = math::to_u64(math::mul_div_ceil(
+            input_amount_u128,
+            BASIS_POINTS_DIVISOR,
+            input_reserve,
+        )?)?;
+        trader_state.record_swap(pool, current_slot, input_is_token_a, volume_bps)?;
+    }
+
+    // Accumulate the pre-swap price into the oracle
+    if pool.oracle != Pubkey::default() {
+        let oracle = ctx
+            .accounts
+            .oracle
+            .as_mut()
+            .ok_or(AmmError::OracleAccountRequired)?;
+        pool.observe_price(oracle, clock.unix_timestamp, pre_swap_price)?;
+
+        // Deviation guard against the oracle TWAP, with a surcharge during incidents
+        if pool.max_oracle_deviation_bps > 0 || pool.incident_deviation_bps > 0 {
+            if let Some(twap) = oracle.twap(
+                clock.unix_timestamp,
+                pre_swap_price,
+                pool.deviation_twap_window_secs,
+            ) {
+                let deviation_bps = LiquidityPool::deviation_bps(post_swap_price, twap)?;
+                if pool.max_oracle_deviation_bps > 0 {
+                    require!(
+                        deviation_bps <= pool.max_oracle_deviation_bps as u128,
+                        AmmError::OracleDeviationExceeded
+                    );
+                }
+                pool.deviation_incident_active = (pool.incident_deviation_bps > 0
+                    && deviation_bps > pool.incident_deviation_bps as u128)
+                    as u8;
+            }
+        }
+    }
+
+    // Flagged incidents route an extra surcharge to the insurance fund
+    if pool.deviation_incident_active != 0 && pool.incident_surcharge_bps > 0 {
+        let surcharge = math::to_u64(math::mul_div_ceil(
+            input_amount_u128,
+            pool.incident_surcharge_bps as u128,
+            BASIS_POINTS_DIVISOR,
+        )?)?;
+        let insurance_vault = ctx
+            .accounts
+            .insurance_vault
+            .as_ref()
+            .ok_or(AmmError::InsuranceVaultRequired)?;
+        let total_input = input_amount
+            .checked_add(surcharge)
+            .ok_or(AmmError::MathOverflow)?;
+        require!(
+            ctx.accounts.user_input_token_account.amount >= total_input,
+            AmmError::InsufficientBalance
+        );
+        if let Some(maximum_input_amount) = limits.maximum_input_amount {
+            require!(
+                total_input <= maximum_input_amount,
+                AmmError::MaxInputExceeded
+            );
+        }
+
+        token_interface::transfer_checked(
+            CpiContext::new(
+                ctx.accounts.input_token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.user_input_token_account.to_account_info(),
+                    mint: ctx.accounts.input_token_mint.to_account_info(),
+                    to: insurance_vault.to_account_info(),
+                    authority: ctx.accounts.user.to_account_info(),
+                },
+            ),
+            surcharge,
+            ctx.accounts.input_token_mint.decimals,
+        )?;
+
+        log!("Deviation incident surcharge: {}", surcharge);
+    }
 
     // Setup PDA signer
     let pool_key = ctx.accounts.liquidity_pool.key();
-    let authority_bump = ctx.bumps.pool_authority;
+    let authority_bump = pool.authority_bump;
     let authority_seeds = &[b"pool_authority", pool_key.as_ref(), &[authority_bump]];
     let signer_seeds = &[&authority_seeds[..]];
 
     // Transfer output tokens from vault to user
-    token_interface::transfer(
+    memo_if_required(
+        &ctx.accounts.user_output_token_account.to_account_info(),
+        &ctx.accounts.memo_program,
+        "ammv2 swap",
+    )?;
+    token_interface::transfer_checked(
         CpiContext::new_with_signer(
-            ctx.accounts.token_program.to_account_info(),
-            anchor_spl::token_interface::Transfer {
+            ctx.accounts.output_token_program.to_account_info(),
+            TransferChecked {
                 from: ctx.accounts.output_token_vault.to_account_info(),
+                mint: ctx.accounts.output_token_mint.to_account_info(),
                 to: ctx.accounts.user_output_token_account.to_account_info(),
                 authority: ctx.accounts.pool_authority.to_account_info(),
             },
             signer_seeds,
         ),
-        output_amount as u64,
+        math::to_u64(output_amount)?,
+        ctx.accounts.output_token_mint.decimals,
     )?;
 
-    // Transfer input tokens from user to vault (including fee)
-    token_interface::transfer(
-        CpiContext::new(
-            ctx.accounts.token_program.to_account_info(),
-            anchor_spl::token_interface::Transfer {
-                from: ctx.accounts.user_input_token_account.to_account_info(),
-                to: ctx.accounts.input_token_vault.to_account_info(),
-                authority: ctx.accounts.user.to_account_info(),
-            },
-        ),
-        input_amount,
+    // The protocol and creator shares of the fee leave the reserves, from the
+    // vault of the token the fee was charged in
+    let (fee_vault, fee_mint, fee_token_program) = if fee_on_output {
+        (
+            &ctx.accounts.output_token_vault,
+            &ctx.accounts.output_token_mint,
+            &ctx.accounts.output_token_program,
+        )
+    } else {
+        (
+            &ctx.accounts.input_token_vault,
+            &ctx.accounts.input_token_mint,
+            &ctx.accounts.input_token_program,
+        )
+    };
+
+    // The protocol share goes to the pool's fee recipient, or the protocol fee
+    // vault when none is set
+    let protocol_fee = math::to_u64(pool.protocol_fee(fee_amount)?)?;
+    if protocol_fee > 0 {
+        let to_fee_vault = pool.fee_recipient(&fee_mint.key()) == Pubkey::default();
+        let protocol_fee_destination = if !to_fee_vault {
+            ctx.accounts
+                .fee_recipient
+                .as_ref()
+                .ok_or(AmmError::FeeRecipientRequired)?
+        } else {
+            ctx.accounts
+                .protocol_fee_vault
+                .as_ref()
+                .ok_or(AmmError::ProtocolFeeVaultRequired)?
+        };
+
+        memo_if_required(
+            &protocol_fee_destination.to_account_info(),
+            &ctx.accounts.memo_program,
+            "ammv2 protocol fee",
+        )?;
+        token_interface::transfer_checked(
+            CpiContext::new_with_signer(
+                fee_token_program.to_account_info(),
+                TransferChecked {
+                    from: fee_vault.to_account_info(),
+                    mint: fee_mint.to_account_info(),
+                    to: protocol_fee_destination.to_account_info(),
+                    authority: ctx.accounts.pool_authority.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            protocol_fee,
+            fee_mint.decimals,
+        )?;
+
+        // Part of the token A fees kept in the vault funds keeper tips
+        if to_fee_vault && fee_mint.key() == pool.token_a_mint {
+            pool.fund_keeper_tips(protocol_fee)?;
+        }
+
+        log!("Protocol fee: {}", protocol_fee);
+    }
+
+    // The creator's share goes to the creator's own token account
+    let creator_fee = math::to_u64(pool.creator_fee(fee_amount, protocol_fee as u128)?)?;
+    if creator_fee > 0 {
+        let creator_fee_account = ctx
+            .accounts
+            .creator_fee_account
+            .as_ref()
+            .ok_or(AmmError::CreatorFeeAccountRequired)?;
+
+        memo_if_required(
+            &creator_fee_account.to_account_info(),
+            &ctx.accounts.memo_program,
+            "ammv2 creator fee",
+        )?;
+        token_interface::transfer_checked(
+            CpiContext::new_with_signer(
+                fee_token_program.to_account_info(),
+                TransferChecked {
+                    from: fee_vault.to_account_info(),
+                    mint: fee_mint.to_account_info(),
+                    to: creator_fee_account.to_account_info(),
+                    authority: ctx.accounts.pool_authority.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            creator_fee,
+            fee_mint.decimals,
+        )?;
+
+        log!("Creator fee: {}", creator_fee);
+    }
+
+    // A named referrer accrues its share in the referral vault, claimable later
+    let mut referral_fee = 0;
+    if let Some(referrer) = ctx.accounts.referrer.as_mut() {
+        referral_fee = math::to_u64(
+            pool.referral_fee(fee_amount, (protocol_fee as u128) + (creator_fee as u128))?,
+        )?;
+        if referral_fee > 0 {
+            let referral_vault = ctx
+                .accounts
+                .referral_vault
+                .as_mut()
+                .ok_or(AmmError::ReferralVaultRequired)?;
+            let vault_balance = referral_vault.amount;
+            token_interface::transfer_checked(
+                CpiContext::new_with_signer(
+                    fee_token_program.to_account_info(),
+                    TransferChecked {
+                        from: fee_vault.to_account_info(),
+                        mint: fee_mint.to_account_info(),
+                        to: referral_vault.to_account_info(),
+                        authority: ctx.accounts.pool_authority.to_account_info(),
+                    },
+                    signer_seeds,
+                ),
+                referral_fee,
+                fee_mint.decimals,
+            )?;
+
+            // Credit what arrived, net of any Token-2022 transfer fee, so the
+            // vault always covers every referrer's balance
+            referral_vault.reload()?;
+            let received = referral_vault
+                .amount
+                .checked_sub(vault_balance)
+                .ok_or(AmmError::MathOverflow)?;
+            referrer.accrue(fee_mint.key(), received)?;
+
+            log!(
+                "Referral fee: {} (referrer {})",
+                referral_fee,
+                referrer.key()
+            );
+        }
+    }
+
+    // What the swap actually moved in and out of each vault
+    ctx.accounts.input_token_vault.reload()?;
+    ctx.accounts.output_token_vault.reload()?;
+    let input_delta = (ctx.accounts.input_token_vault.amount as u128)
+        .checked_sub(input_vault_balance)
+        .ok_or(AmmError::MathOverflow)?;
+    let output_delta = output_vault_balance
+        .checked_sub(ctx.accounts.output_token_vault.amount as u128)
+        .ok_or(AmmError::MathOverflow)?;
+
+    // Defense in depth: the product of the balances the swap was priced
+    // against must never decrease
+    math::check_invariant(
+        invariant,
+        input_reserve + input_delta,
+        output_reserve
+            .checked_sub(output_delta)
+            .ok_or(AmmError::MathOverflow)?,
     )?;
 
-    msg!(
+    // The tracked reserves move by the same deltas, so a vault surplus above
+    // them is left for `skim` even when the swap was priced on vault balances
+    let (tracked_input, tracked_output) = if input_is_token_a {
+        (pool.reserve_a, pool.reserve_b)
+    } else {
+        (pool.reserve_b, pool.reserve_a)
+    };
+    let input_reserve_after = math::to_u64(tracked_input as u128 + input_delta)?;
+    let output_reserve_after = math::to_u64(
+        (tracked_output as u128)
+            .checked_sub(output_delta)
+            .ok_or(AmmError::MathOverflow)?,
+    )?;
+    if input_is_token_a {
+        pool.sync_reserves(input_reserve_after, output_reserve_after);
+    } else {
+        pool.sync_reserves(output_reserve_after, input_reserve_after);
+    }
+
+    if pool.has_volume_stats != 0 {
+        let volume_stats = ctx
+            .accounts
+            .volume_stats
+            .as_mut()
+            .ok_or(AmmError::VolumeStatsAccountRequired)?;
+        let lp_fee = math::to_u64(fee_amount)?
+            .saturating_sub(protocol_fee)
+            .saturating_sub(creator_fee)
+            .saturating_sub(referral_fee);
+        volume_stats.record(
+            clock.unix_timestamp,
+            input_is_token_a,
+            input_amount,
+            input_is_token_a != fee_on_output,
+            lp_fee,
+        );
+    }
+
+    log!(
         "Swap completed - Input: {}, Output: {}",
         input_amount,
         output_amount
     );
 
-    Ok(())
+    let result = SwapResult {
+        amount_in: input_amount,
+        amount_out: math::to_u64(output_amount)?,
+        fee_amount: math::to_u64(fee_amount)?,
+    };
+    emit!(SwapExecuted {
+        liquidity_pool: pool_key,
+        event_seq: pool.next_event_seq(),
+        user: ctx.accounts.user.key(),
+        input_mint: ctx.accounts.input_token_mint.key(),
+        input_amount,
+        output_amount: result.amount_out,
+        fee_amount: result.fee_amount,
+        partial_fill: input_amount < requested_input_amount,
+        reserve_a: pool.reserve_a,
+        reserve_b: pool.reserve_b,
+        lp_supply: pool.total_lp_tokens_issued,
+        spot_price: LiquidityPool::spot_price(pool.reserve_a as u128, pool.reserve_b as u128)?
+            .to_bits(),
+        client_order_id,
+    });
+
+    Ok(result)
 }
 
 #[derive(Accounts)]
 pub struct SwapTokens<'info> {
     /// Pool state account
     #[account(mut)]
-    pub liquidity_pool: Box<Account<'info, LiquidityPool>>,
+    pub liquidity_pool: AccountLoader<'info, LiquidityPool>,
+
+    /// Global config, checked for the emergency pause
+    #[account(seeds = [b"amm_config"], bump)]
+    pub amm_config: Box<Account<'info, AmmConfig>>,
 
     /// Pool authority PDA
     #[account(
         seeds = [b"pool_authority", liquidity_pool.key().as_ref()],
-        bump
+        bump = liquidity_pool.load()?.authority_bump
     )]
     pub pool_authority: SystemAccount<'info>,
 
-    /// Vault for input token (token being sold)
+    /// Pool vault of the input token (token being sold). Other token accounts
+    /// of the pool authority, such as insurance or protocol fee vaults, are rejected.
     #[account(
         mut,
-        constraint = input_token_vault.owner == pool_authority.key(),
-        constraint = input_token_vault.mint == user_input_token_account.mint,
+        seeds = [
+            liquidity_pool.load()?.vault_seeds(&input_token_vault.mint).0,
+            liquidity_pool.key().as_ref()
+        ],
+        bump = liquidity_pool.load()?.vault_seeds(&input_token_vault.mint).1,
+        constraint = input_token_vault.mint == user_input_token_account.mint @ AmmError::InvalidVaultAccount,
     )]
     pub input_token_vault: Box<InterfaceAccount<'info, TokenAccount>>,
 
-    /// Vault for output token (token being bought)
+    /// Pool vault of the output token (token being bought), the other token of the pair
     #[account(
         mut,
-        constraint = output_token_vault.owner == pool_authority.key(),
-        constraint = output_token_vault.mint == user_output_token_account.mint,
+        seeds = [
+            liquidity_pool.load()?.vault_seeds(&output_token_vault.mint).0,
+            liquidity_pool.key().as_ref()
+        ],
+        bump = liquidity_pool.load()?.vault_seeds(&output_token_vault.mint).1,
+        constraint = output_token_vault.mint != input_token_vault.mint @ AmmError::InvalidVaultAccount,
     )]
     pub output_token_vault: Box<InterfaceAccount<'info, TokenAccount>>,
 
-    /// User's input token account (source)
-    #[account(mut)]
+    /// Mint of the input token
+    #[account(address = input_token_vault.mint)]
+    pub input_token_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    /// Mint of the output token
+    #[account(address = output_token_vault.mint)]
+    pub output_token_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    /// User's input token account (source), owned by the user or delegated to it
+    #[account(
+        mut,
+        constraint = user_input_token_account.owner == user.key()
+            || user_input_token_account.delegate == COption::Some(user.key())
+            @ AmmError::InvalidTokenAccountOwner
+    )]
     pub user_input_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
 
-    /// User's output token account (destination)
+    /// User authority (the input account's owner or delegate), pays for the output
+    /// token and trader state accounts when first created
     #[account(mut)]
+    pub user: Signer<'info>,
+
+    /// Owner of the input token account, which `user` may be a delegate of
+    /// CHECK: address is the input token account's owner
+    #[account(address = user_input_token_account.owner @ AmmError::InvalidTokenAccountOwner)]
+    pub input_token_owner: UncheckedAccount<'info>,
+
+    /// Token program of the input mint
+    #[account(address = *input_token_mint.to_account_info().owner @ AmmError::InvalidTokenProgram)]
+    pub input_token_program: Interface<'info, TokenInterface>,
+
+    /// Token program of the output mint, which may differ from the input's
+    #[account(address = *output_token_mint.to_account_info().owner @ AmmError::InvalidTokenProgram)]
+    pub output_token_program: Interface<'info, TokenInterface>,
+
+    /// Required for creating the output token and trader state accounts
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+
+    /// Output token associated account (destination) of the input token
+    /// owner, so a delegate's swap pays out to the owner of the funds. Created
+    /// if needed.
+    #[account(
+        init_if_needed,
+        payer = user,
+        associated_token::mint = output_token_mint,
+        associated_token::authority = input_token_owner,
+        associated_token::token_program = output_token_program,
+    )]
     pub user_output_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
 
-    /// User authority
-    pub user: Signer<'info>,
+    /// Pool price oracle, required once the pool has one
+    #[account(mut, address = liquidity_pool.load()?.oracle @ AmmError::OracleAccountRequired)]
+    pub oracle: Option<Box<Account<'info, PriceOracle>>>,
+
+    /// Insurance fund vault for the input token, required during a deviation incident
+    #[account(
+        mut,
+        seeds = [
+            b"insurance_vault",
+            liquidity_pool.key().as_ref(),
+            input_token_mint.key().as_ref()
+        ],
+        bump,
+    )]
+    pub insurance_vault: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    /// Protocol fee vault for the fee token, required when the pool takes a protocol fee
+    #[account(
+        mut,
+        seeds = [
+            b"protocol_fee_vault",
+            liquidity_pool.key().as_ref(),
+            liquidity_pool
+                .load()?
+                .fee_mint(&input_token_mint.key(), &output_token_mint.key())
+                .as_ref()
+        ],
+        bump,
+    )]
+    pub protocol_fee_vault: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    /// Pool's fee recipient for the fee token, required when the admin set one
+    #[account(
+        mut,
+        address = {
+            let pool = liquidity_pool.load()?;
+            pool.fee_recipient(&pool.fee_mint(&input_token_mint.key(), &output_token_mint.key()))
+        } @ AmmError::FeeRecipientRequired
+    )]
+    pub fee_recipient: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    /// Creator's fee account for the fee token, required when the pool pays a creator fee
+    #[account(
+        mut,
+        address = {
+            let pool = liquidity_pool.load()?;
+            pool.creator_fee_account(&pool.fee_mint(&input_token_mint.key(), &output_token_mint.key()))
+        } @ AmmError::CreatorFeeAccountRequired
+    )]
+    pub creator_fee_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    /// Per-wallet activity record, required when the pool enforces trader limits
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + TraderState::INIT_SPACE,
+        seeds = [
+            b"trader_state",
+            liquidity_pool.key().as_ref(),
+            user.key().as_ref()
+        ],
+        bump,
+    )]
+    pub trader_state: Option<Box<Account<'info, TraderState>>>,
+
+    /// The user's launch whitelist entry, required during a whitelist-only launch window
+    #[account(
+        seeds = [
+            b"launch_whitelist",
+            liquidity_pool.key().as_ref(),
+            user.key().as_ref()
+        ],
+        bump,
+    )]
+    pub launch_whitelist_entry: Option<Box<Account<'info, LaunchWhitelistEntry>>>,
+
+    /// Memo program, required when a destination requires transfer memos
+    pub memo_program: Option<Program<'info, Memo>>,
 
-    /// Token program
-    pub token_program: Interface<'info, TokenInterface>,
+    /// Pool volume stats, required once the pool has them
+    #[account(
+        mut,
+        seeds = [b"volume_stats", liquidity_pool.key().as_ref()],
+        bump = volume_stats.bump,
+    )]
+    pub volume_stats: Option<Box<Account<'info, PoolVolumeStats>>>,
+
+    /// Frontend that referred the swap, accruing the pool's referral fee share.
+    /// The trader cannot refer itself to rebate its own fee.
+    #[account(
+        mut,
+        constraint = referrer.authority != user.key() @ AmmError::SelfReferral,
+        constraint = referrer.authority != input_token_owner.key() @ AmmError::SelfReferral,
+    )]
+    pub referrer: Option<Box<Account<'info, Referrer>>>,
+
+    /// Referral vault for the fee token, required when a referrer earns a fee
+    #[account(
+        mut,
+        seeds = [
+            b"referral_vault",
+            liquidity_pool
+                .load()?
+                .fee_mint(&input_token_mint.key(), &output_token_mint.key())
+                .as_ref()
+        ],
+        bump,
+    )]
+    pub referral_vault: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
 }
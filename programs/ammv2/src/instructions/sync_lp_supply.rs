@@ -0,0 +1,46 @@
+use crate::events::LpSupplySynced;
+use crate::state::LiquidityPool;
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::Mint;
+
+/// Reset the pool's LP counter to the LP mint supply if the two have drifted
+pub fn process(ctx: Context<SyncLpSupply>) -> Result<()> {
+    let lp_supply = ctx.accounts.lp_token_mint.supply;
+    let pool = &mut ctx.accounts.liquidity_pool.load_mut()?;
+    let previous_lp_tokens_issued = pool.total_lp_tokens_issued;
+
+    if previous_lp_tokens_issued == lp_supply {
+        log!("LP supply already in sync: {}", lp_supply);
+        return Ok(());
+    }
+
+    pool.total_lp_tokens_issued = lp_supply;
+
+    log!(
+        "LP supply synced - Previous: {}, Current: {}",
+        previous_lp_tokens_issued,
+        lp_supply
+    );
+
+    emit!(LpSupplySynced {
+        liquidity_pool: ctx.accounts.liquidity_pool.key(),
+        event_seq: pool.next_event_seq(),
+        previous_lp_tokens_issued,
+        lp_supply,
+    });
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct SyncLpSupply<'info> {
+    /// Pool state account
+    #[account(mut)]
+    pub liquidity_pool: AccountLoader<'info, LiquidityPool>,
+
+    /// LP token mint
+    #[account(
+        seeds = [b"lp_token_mint", liquidity_pool.key().as_ref()],
+        bump = liquidity_pool.load()?.lp_token_mint_bump
+    )]
+    pub lp_token_mint: Box<InterfaceAccount<'info, Mint>>,
+}
@@ -0,0 +1,385 @@
+use crate::errors::AmmError;
+use crate::events::{ParamChangeExecuted, ParamChangeQueued};
+use crate::instructions::admin::{
+    apply_deviation_guard, apply_dynamic_fee, apply_governance_admin, apply_protocol_fee,
+    apply_verification_level, apply_withdraw_only,
+};
+use crate::state::{AmmConfig, LiquidityPool, ParamChange, PendingParamChange};
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::TokenAccount;
+
+/// Queue a parameter change behind the global timelock. Config changes are
+/// queued by the config authority, pool changes by the pool's admin (or its
+/// creator, for the creator fee); either way the change is public on-chain
+/// until it executes.
+pub fn queue(ctx: Context<QueueParamChange>, change_id: u64, change: ParamChange) -> Result<()> {
+    change.validate()?;
+
+    let proposer = ctx.accounts.proposer.key();
+    let target = match &ctx.accounts.liquidity_pool {
+        Some(liquidity_pool) if change.targets_pool() => {
+            require_keys_eq!(
+                change.pool_proposer(&*liquidity_pool.load()?),
+                proposer,
+                AmmError::Unauthorized
+            );
+            liquidity_pool.key()
+        }
+        None if !change.targets_pool() => {
+            require_keys_eq!(
+                ctx.accounts.amm_config.authority,
+                proposer,
+                AmmError::Unauthorized
+            );
+            ctx.accounts.amm_config.key()
+        }
+        _ => return err!(AmmError::InvalidParamChangeTarget),
+    };
+
+    let now = Clock::get()?.unix_timestamp;
+    let pending = &mut ctx.accounts.pending_change;
+    pending.target = target;
+    pending.proposer = proposer;
+    pending.change_id = change_id;
+    pending.change = change;
+    pending.queued_at = now;
+    pending.eta = now
+        .checked_add(ctx.accounts.amm_config.param_change_delay_secs)
+        .ok_or(AmmError::MathOverflow)?;
+    pending.bump = ctx.bumps.pending_change;
+
+    log!(
+        "Parameter change {} queued - ETA: {}",
+        change_id,
+        pending.eta
+    );
+
+    emit!(ParamChangeQueued {
+        target,
+        change_id,
+        proposer,
+        change,
+        eta: pending.eta,
+    });
+    Ok(())
+}
+
+/// Apply a queued change once its timelock has elapsed. Anyone may execute;
+/// the proposer must still hold the role it queued the change with. Token
+/// accounts and governance accounts named by the change are passed as
+/// remaining accounts.
+pub fn execute<'info>(ctx: Context<'_, '_, 'info, 'info, ExecuteParamChange<'info>>) -> Result<()> {
+    let pending = &ctx.accounts.pending_change;
+    require!(
+        Clock::get()?.unix_timestamp >= pending.eta,
+        AmmError::TimelockNotElapsed
+    );
+
+    let change = pending.change;
+    if change.targets_pool() {
+        let liquidity_pool = ctx
+            .accounts
+            .liquidity_pool
+            .as_ref()
+            .ok_or(AmmError::InvalidParamChangeTarget)?;
+        require_keys_eq!(
+            liquidity_pool.key(),
+            pending.target,
+            AmmError::InvalidParamChangeTarget
+        );
+        let pool = &mut liquidity_pool.load_mut()?;
+        require_keys_eq!(
+            change.pool_proposer(pool),
+            pending.proposer,
+            AmmError::Unauthorized
+        );
+
+        let accounts = ctx.remaining_accounts;
+        match change {
+            ParamChange::PoolFee {
+                fee_numerator,
+                fee_denominator,
+            } => {
+                pool.fee_numerator = fee_numerator;
+                pool.fee_denominator = fee_denominator;
+            }
+            ParamChange::DynamicFee {
+                min_bps,
+                max_bps,
+                volatility_bps,
+            } => apply_dynamic_fee(pool, min_bps, max_bps, volatility_bps)?,
+            ParamChange::FeeOnOutput { fee_on_output } => pool.fee_on_output = fee_on_output as u8,
+            ParamChange::ProtocolFee {
+                share_bps,
+                buyback_mint,
+            } => apply_protocol_fee(
+                pool,
+                share_bps,
+                (buyback_mint != Pubkey::default()).then_some(buyback_mint),
+            )?,
+            ParamChange::FeeRecipients {
+                fee_recipient_a,
+                fee_recipient_b,
+            } => {
+                // A default key restores the protocol fee vault
+                if fee_recipient_a != Pubkey::default() {
+                    check_token_account(accounts, fee_recipient_a, pool.token_a_mint)?;
+                }
+                if fee_recipient_b != Pubkey::default() {
+                    check_token_account(accounts, fee_recipient_b, pool.token_b_mint)?;
+                }
+                pool.fee_recipient_a = fee_recipient_a;
+                pool.fee_recipient_b = fee_recipient_b;
+            }
+            ParamChange::CreatorFee {
+                bps,
+                creator_fee_account_a,
+                creator_fee_account_b,
+            } => {
+                // The cap may have been lowered while the change was queued
+                require!(
+                    bps <= ctx.accounts.amm_config.max_creator_fee_bps,
+                    AmmError::CreatorFeeTooHigh
+                );
+                check_token_account(accounts, creator_fee_account_a, pool.token_a_mint)?;
+                check_token_account(accounts, creator_fee_account_b, pool.token_b_mint)?;
+                pool.creator_fee_bps = bps;
+                pool.creator_fee_account_a = creator_fee_account_a;
+                pool.creator_fee_account_b = creator_fee_account_b;
+            }
+            ParamChange::GovernanceAdmin { realm, governance } => apply_governance_admin(
+                pool,
+                remaining_account(accounts, realm)?,
+                remaining_account(accounts, governance)?,
+            )?,
+            ParamChange::CircuitBreaker {
+                max_price_change_bps_per_slot,
+            } => pool.max_price_change_bps_per_slot = max_price_change_bps_per_slot,
+            ParamChange::AntiSandwichLimits {
+                max_trader_slot_volume_bps,
+                block_same_slot_reversal,
+            } => {
+                pool.max_trader_slot_volume_bps = max_trader_slot_volume_bps;
+                pool.block_same_slot_reversal = block_same_slot_reversal as u8;
+            }
+            ParamChange::MaxTradeSize {
+                max_trade_bps_of_reserves,
+            } => pool.max_trade_bps_of_reserves = max_trade_bps_of_reserves,
+            ParamChange::MaxPriceImpact {
+                max_price_impact_bps,
+            } => pool.max_price_impact_bps = max_price_impact_bps,
+            ParamChange::ReferralFee { bps } => pool.referral_fee_bps = bps,
+            ParamChange::KeeperTips {
+                keeper_tip,
+                share_bps,
+            } => {
+                pool.keeper_tip = keeper_tip;
+                pool.keeper_tip_share_bps = share_bps;
+            }
+            ParamChange::LaunchProtection {
+                end_slot,
+                trade_cooldown_slots,
+            } => {
+                pool.launch_protection_end_slot = end_slot;
+                pool.launch_trade_cooldown_slots = trade_cooldown_slots;
+            }
+            ParamChange::OracleCheckpointReward { reward } => {
+                pool.oracle_checkpoint_reward = reward
+            }
+            ParamChange::WithdrawOnly {
+                withdraw_only,
+                large_withdrawal_delay_secs,
+                small_lp_withdrawal_threshold,
+            } => apply_withdraw_only(
+                pool,
+                withdraw_only,
+                large_withdrawal_delay_secs,
+                small_lp_withdrawal_threshold,
+            )?,
+            ParamChange::DeviationGuard {
+                twap_window_secs,
+                max_deviation_bps,
+                incident_deviation_bps,
+                incident_surcharge_bps,
+            } => apply_deviation_guard(
+                pool,
+                twap_window_secs,
+                max_deviation_bps,
+                incident_deviation_bps,
+                incident_surcharge_bps,
+            )?,
+            ParamChange::MinInitialLiquidity { min_a, min_b } => {
+                pool.min_initial_deposit_a = min_a;
+                pool.min_initial_deposit_b = min_b;
+            }
+            ParamChange::DisabledOperations {
+                disabled_operations,
+            } => pool.disabled_operations = disabled_operations,
+            ParamChange::LpWhitelist { enabled } => pool.lp_whitelist_enabled = enabled as u8,
+            ParamChange::VerificationLevel { level } => {
+                apply_verification_level(pool, liquidity_pool.key(), level)?
+            }
+            ParamChange::WithdrawCooldown { secs } => pool.withdraw_cooldown_secs = secs,
+            ParamChange::JitProtection {
+                window_slots,
+                penalty_bps,
+            } => {
+                pool.jit_window_slots = window_slots;
+                pool.jit_penalty_bps = penalty_bps;
+            }
+            _ => return err!(AmmError::InvalidParamChangeTarget),
+        }
+        emit!(pool.params_snapshot(liquidity_pool.key())?);
+    } else {
+        let config = &mut ctx.accounts.amm_config;
+        require_keys_eq!(
+            config.key(),
+            pending.target,
+            AmmError::InvalidParamChangeTarget
+        );
+        require_keys_eq!(config.authority, pending.proposer, AmmError::Unauthorized);
+
+        match change {
+            ParamChange::PoolCreationFee { lamports } => {
+                config.pool_creation_fee_lamports = lamports
+            }
+            ParamChange::Paused { paused } => config.paused = paused,
+            ParamChange::ConfigAuthority { authority } => config.authority = authority,
+            ParamChange::ParamChangeDelay { delay_secs } => {
+                config.param_change_delay_secs = delay_secs
+            }
+            ParamChange::MaxCreatorFee { bps } => config.max_creator_fee_bps = bps,
+            ParamChange::RejectFreezeAuthority { reject } => {
+                config.reject_freeze_authority = reject
+            }
+            _ => return err!(AmmError::InvalidParamChangeTarget),
+        }
+        emit!(config.snapshot());
+    }
+
+    log!("Parameter change {} executed", pending.change_id);
+
+    emit!(ParamChangeExecuted {
+        target: pending.target,
+        change_id: pending.change_id,
+        change,
+    });
+    Ok(())
+}
+
+/// Find the account with `key` among the remaining accounts
+fn remaining_account<'a, 'info>(
+    accounts: &'a [AccountInfo<'info>],
+    key: Pubkey,
+) -> Result<&'a AccountInfo<'info>> {
+    accounts
+        .iter()
+        .find(|account| account.key() == key)
+        .ok_or_else(|| error!(AmmError::InvalidParamChangeAccount))
+}
+
+/// Check that the remaining account with `key` is a token account of `mint`
+fn check_token_account<'info>(
+    accounts: &'info [AccountInfo<'info>],
+    key: Pubkey,
+    mint: Pubkey,
+) -> Result<()> {
+    let token_account =
+        InterfaceAccount::<TokenAccount>::try_from(remaining_account(accounts, key)?)
+            .map_err(|_| error!(AmmError::InvalidParamChangeAccount))?;
+    require_keys_eq!(
+        token_account.mint,
+        mint,
+        AmmError::InvalidParamChangeAccount
+    );
+    Ok(())
+}
+
+/// Drop a queued change before it executes; the rent goes back to the proposer
+pub fn cancel(ctx: Context<CancelParamChange>) -> Result<()> {
+    log!(
+        "Parameter change {} cancelled",
+        ctx.accounts.pending_change.change_id
+    );
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(change_id: u64)]
+pub struct QueueParamChange<'info> {
+    /// Global config, holding the timelock delay
+    #[account(seeds = [b"amm_config"], bump)]
+    pub amm_config: Box<Account<'info, AmmConfig>>,
+
+    /// Pool the change applies to, for pool changes only
+    pub liquidity_pool: Option<AccountLoader<'info, LiquidityPool>>,
+
+    /// The queued change
+    #[account(
+        init,
+        payer = proposer,
+        space = 8 + PendingParamChange::INIT_SPACE,
+        seeds = [
+            b"param_change",
+            proposer.key().as_ref(),
+            &change_id.to_le_bytes()
+        ],
+        bump,
+    )]
+    pub pending_change: Box<Account<'info, PendingParamChange>>,
+
+    /// Config authority or pool admin, pays for the queued change
+    #[account(mut)]
+    pub proposer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ExecuteParamChange<'info> {
+    /// Global config
+    #[account(mut, seeds = [b"amm_config"], bump)]
+    pub amm_config: Box<Account<'info, AmmConfig>>,
+
+    /// Pool the change applies to, for pool changes only
+    #[account(mut)]
+    pub liquidity_pool: Option<AccountLoader<'info, LiquidityPool>>,
+
+    /// The queued change, closed once applied
+    #[account(
+        mut,
+        close = proposer,
+        seeds = [
+            b"param_change",
+            pending_change.proposer.as_ref(),
+            &pending_change.change_id.to_le_bytes()
+        ],
+        bump = pending_change.bump,
+    )]
+    pub pending_change: Box<Account<'info, PendingParamChange>>,
+
+    /// Proposer of the change, receives its rent
+    #[account(mut, address = pending_change.proposer)]
+    pub proposer: SystemAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CancelParamChange<'info> {
+    /// The queued change, closed to the proposer
+    #[account(
+        mut,
+        close = proposer,
+        has_one = proposer @ AmmError::Unauthorized,
+        seeds = [
+            b"param_change",
+            proposer.key().as_ref(),
+            &pending_change.change_id.to_le_bytes()
+        ],
+        bump = pending_change.bump,
+    )]
+    pub pending_change: Box<Account<'info, PendingParamChange>>,
+
+    /// Proposer of the change
+    #[account(mut)]
+    pub proposer: Signer<'info>,
+}
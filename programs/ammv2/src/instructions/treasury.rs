@@ -0,0 +1,238 @@
+use crate::errors::AmmError;
+use crate::state::{AmmConfig, TreasuryLimits};
+use anchor_lang::prelude::*;
+use anchor_lang::system_program::{self, Transfer};
+use anchor_spl::token_interface::{self, Mint, TokenAccount, TokenInterface, TransferChecked};
+
+/// Create the treasury vault of a mint. The vault is owned by the treasury PDA,
+/// so skimmed tokens and protocol fee shares can be paid into it.
+pub fn initialize_vault(ctx: Context<InitializeTreasuryVault>) -> Result<()> {
+    log!(
+        "Treasury vault initialized - Mint: {}, Vault: {}",
+        ctx.accounts.mint.key(),
+        ctx.accounts.treasury_vault.key()
+    );
+    Ok(())
+}
+
+/// Set the withdrawal limits of a mint's treasury vault, or of the treasury's
+/// lamports when `mint` is `Pubkey::default()`. A balance without limits
+/// cannot be withdrawn.
+pub fn set_limits(
+    ctx: Context<SetTreasuryLimits>,
+    mint: Pubkey,
+    max_withdraw_per_call: u64,
+    max_withdraw_per_epoch: u64,
+) -> Result<()> {
+    let limits = &mut ctx.accounts.treasury_limits;
+    limits.mint = mint;
+    limits.max_withdraw_per_call = max_withdraw_per_call;
+    limits.max_withdraw_per_epoch = max_withdraw_per_epoch;
+    limits.bump = ctx.bumps.treasury_limits;
+
+    log!(
+        "Treasury limits set - Mint: {}, Per call: {}, Per epoch: {}",
+        mint,
+        max_withdraw_per_call,
+        max_withdraw_per_epoch
+    );
+    Ok(())
+}
+
+/// Move `amount` out of a mint's treasury vault, within its limits
+pub fn withdraw(ctx: Context<WithdrawTreasury>, amount: u64) -> Result<()> {
+    require!(
+        ctx.accounts.treasury_vault.amount >= amount,
+        AmmError::InsufficientTreasuryBalance
+    );
+    ctx.accounts
+        .treasury_limits
+        .record_withdrawal(Clock::get()?.epoch, amount)?;
+
+    let treasury_seeds = &[b"treasury".as_ref(), &[ctx.bumps.treasury]];
+    let signer_seeds = &[&treasury_seeds[..]];
+    token_interface::transfer_checked(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            TransferChecked {
+                from: ctx.accounts.treasury_vault.to_account_info(),
+                mint: ctx.accounts.mint.to_account_info(),
+                to: ctx.accounts.destination.to_account_info(),
+                authority: ctx.accounts.treasury.to_account_info(),
+            },
+            signer_seeds,
+        ),
+        amount,
+        ctx.accounts.mint.decimals,
+    )?;
+
+    log!(
+        "Treasury withdrawal - Mint: {}, Amount: {}",
+        ctx.accounts.mint.key(),
+        amount
+    );
+    Ok(())
+}
+
+/// Move `amount` lamports, e.g. collected pool creation fees, out of the
+/// treasury PDA within its lamport limits. The PDA stays rent-exempt.
+pub fn withdraw_lamports(ctx: Context<WithdrawTreasuryLamports>, amount: u64) -> Result<()> {
+    let available = ctx
+        .accounts
+        .treasury
+        .lamports()
+        .saturating_sub(Rent::get()?.minimum_balance(0));
+    require!(available >= amount, AmmError::InsufficientTreasuryBalance);
+    ctx.accounts
+        .treasury_limits
+        .record_withdrawal(Clock::get()?.epoch, amount)?;
+
+    let treasury_seeds = &[b"treasury".as_ref(), &[ctx.bumps.treasury]];
+    let signer_seeds = &[&treasury_seeds[..]];
+    system_program::transfer(
+        CpiContext::new_with_signer(
+            ctx.accounts.system_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.treasury.to_account_info(),
+                to: ctx.accounts.destination.to_account_info(),
+            },
+            signer_seeds,
+        ),
+        amount,
+    )?;
+
+    log!("Treasury withdrawal - Lamports: {}", amount);
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct InitializeTreasuryVault<'info> {
+    /// Protocol treasury PDA, owner of the vault
+    #[account(seeds = [b"treasury"], bump)]
+    pub treasury: SystemAccount<'info>,
+
+    /// Mint the vault holds
+    pub mint: Box<InterfaceAccount<'info, Mint>>,
+
+    /// Treasury vault of the mint
+    #[account(
+        init,
+        payer = payer,
+        seeds = [b"treasury_vault", mint.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = treasury,
+        token::token_program = token_program,
+    )]
+    pub treasury_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Anyone may create the vault and pay for the account
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(mint: Pubkey)]
+pub struct SetTreasuryLimits<'info> {
+    /// Global config singleton
+    #[account(
+        seeds = [b"amm_config"],
+        bump,
+        has_one = authority @ AmmError::Unauthorized,
+    )]
+    pub amm_config: Box<Account<'info, AmmConfig>>,
+
+    /// Withdrawal limits of the mint
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + TreasuryLimits::INIT_SPACE,
+        seeds = [b"treasury_limits", mint.as_ref()],
+        bump,
+    )]
+    pub treasury_limits: Box<Account<'info, TreasuryLimits>>,
+
+    /// Config authority, pays for the limits account
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawTreasury<'info> {
+    /// Global config singleton
+    #[account(
+        seeds = [b"amm_config"],
+        bump,
+        has_one = authority @ AmmError::Unauthorized,
+    )]
+    pub amm_config: Box<Account<'info, AmmConfig>>,
+
+    /// Protocol treasury PDA, owner of the vault
+    #[account(seeds = [b"treasury"], bump)]
+    pub treasury: SystemAccount<'info>,
+
+    /// Withdrawal limits of the mint
+    #[account(
+        mut,
+        seeds = [b"treasury_limits", mint.key().as_ref()],
+        bump = treasury_limits.bump,
+    )]
+    pub treasury_limits: Box<Account<'info, TreasuryLimits>>,
+
+    /// Mint being withdrawn
+    pub mint: Box<InterfaceAccount<'info, Mint>>,
+
+    /// Treasury vault of the mint
+    #[account(
+        mut,
+        seeds = [b"treasury_vault", mint.key().as_ref()],
+        bump,
+    )]
+    pub treasury_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Token account receiving the withdrawal
+    #[account(mut, constraint = destination.mint == mint.key())]
+    pub destination: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Config authority
+    pub authority: Signer<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawTreasuryLamports<'info> {
+    /// Global config singleton
+    #[account(
+        seeds = [b"amm_config"],
+        bump,
+        has_one = authority @ AmmError::Unauthorized,
+    )]
+    pub amm_config: Box<Account<'info, AmmConfig>>,
+
+    /// Protocol treasury PDA holding the lamports
+    #[account(mut, seeds = [b"treasury"], bump)]
+    pub treasury: SystemAccount<'info>,
+
+    /// Lamport withdrawal limits, stored under the default pubkey
+    #[account(
+        mut,
+        seeds = [b"treasury_limits", Pubkey::default().as_ref()],
+        bump = treasury_limits.bump,
+    )]
+    pub treasury_limits: Box<Account<'info, TreasuryLimits>>,
+
+    /// Account receiving the lamports
+    #[account(mut)]
+    pub destination: SystemAccount<'info>,
+
+    /// Config authority
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
@@ -0,0 +1,137 @@
+use crate::errors::AmmError;
+use crate::math;
+use crate::state::{LiquidityPool, PoolVolumeStats, BASIS_POINTS_DIVISOR};
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::TokenAccount;
+
+/// Create the volume stats account of a pool. From then on every swap must
+/// pass it, so the hourly totals cover all of the pool's trading.
+pub fn initialize(ctx: Context<InitializeVolumeStats>) -> Result<()> {
+    let stats = &mut ctx.accounts.volume_stats;
+    stats.liquidity_pool = ctx.accounts.liquidity_pool.key();
+    stats.bump = ctx.bumps.volume_stats;
+
+    ctx.accounts.liquidity_pool.load_mut()?.has_volume_stats = 1;
+
+    log!("Volume stats initialized - Pool: {}", stats.liquidity_pool);
+    Ok(())
+}
+
+/// Trailing 24h totals returned by `read_volume_stats`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct VolumeStatsReading {
+    /// Token A sold into the pool
+    pub volume_a: u64,
+
+    /// Token B sold into the pool
+    pub volume_b: u64,
+
+    /// Swap fees in token A kept by LPs
+    pub lp_fees_a: u64,
+
+    /// Swap fees in token B kept by LPs
+    pub lp_fees_b: u64,
+
+    /// The 24h LP fees annualized against current reserves, in bps, valuing
+    /// token B at the spot price
+    pub fee_apr_bps: u64,
+}
+
+/// Sum the last 24 hours of swap volume and LP fees, and the fee APR they
+/// imply at current reserves. The reading is returned as instruction return
+/// data for CPI callers such as routers.
+pub fn read(ctx: Context<ReadVolumeStats>) -> Result<VolumeStatsReading> {
+    let now = Clock::get()?.unix_timestamp;
+    let mut reading = VolumeStatsReading {
+        volume_a: 0,
+        volume_b: 0,
+        lp_fees_a: 0,
+        lp_fees_b: 0,
+        fee_apr_bps: 0,
+    };
+    for bucket in ctx.accounts.volume_stats.recent_buckets(now) {
+        reading.volume_a = reading.volume_a.saturating_add(bucket.volume_a);
+        reading.volume_b = reading.volume_b.saturating_add(bucket.volume_b);
+        reading.lp_fees_a = reading.lp_fees_a.saturating_add(bucket.lp_fees_a);
+        reading.lp_fees_b = reading.lp_fees_b.saturating_add(bucket.lp_fees_b);
+    }
+
+    let reserve_a = ctx.accounts.token_a_vault.amount as u128;
+    let reserve_b = ctx.accounts.token_b_vault.amount as u128;
+    if reserve_a > 0 && reserve_b > 0 {
+        // Value everything in token A; the pool holds equal value on both sides
+        let fees_in_a = (reading.lp_fees_a as u128)
+            .checked_add(math::mul_div_floor(
+                reading.lp_fees_b as u128,
+                reserve_a,
+                reserve_b,
+            )?)
+            .ok_or(AmmError::MathOverflow)?;
+        let yearly_fees_in_a = fees_in_a.checked_mul(365).ok_or(AmmError::MathOverflow)?;
+        reading.fee_apr_bps = math::mul_div_floor(
+            yearly_fees_in_a,
+            BASIS_POINTS_DIVISOR,
+            reserve_a.checked_mul(2).ok_or(AmmError::MathOverflow)?,
+        )?
+        .min(u64::MAX as u128) as u64;
+    }
+
+    log!(
+        "24h volume - Token A: {}, Token B: {}, Fee APR: {} bps",
+        reading.volume_a,
+        reading.volume_b,
+        reading.fee_apr_bps
+    );
+
+    Ok(reading)
+}
+
+#[derive(Accounts)]
+pub struct InitializeVolumeStats<'info> {
+    /// Pool without volume stats
+    #[account(mut)]
+    pub liquidity_pool: AccountLoader<'info, LiquidityPool>,
+
+    /// Volume stats PDA created for the pool
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + PoolVolumeStats::INIT_SPACE,
+        seeds = [b"volume_stats", liquidity_pool.key().as_ref()],
+        bump,
+    )]
+    pub volume_stats: Box<Account<'info, PoolVolumeStats>>,
+
+    /// Anyone may create the stats account and pay for it
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ReadVolumeStats<'info> {
+    /// Pool state account
+    pub liquidity_pool: AccountLoader<'info, LiquidityPool>,
+
+    /// Pool volume stats
+    #[account(
+        seeds = [b"volume_stats", liquidity_pool.key().as_ref()],
+        bump = volume_stats.bump,
+    )]
+    pub volume_stats: Box<Account<'info, PoolVolumeStats>>,
+
+    /// Token A vault
+    #[account(
+        seeds = [b"token_a_vault", liquidity_pool.key().as_ref()],
+        bump = liquidity_pool.load()?.token_a_vault_bump
+    )]
+    pub token_a_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Token B vault
+    #[account(
+        seeds = [b"token_b_vault", liquidity_pool.key().as_ref()],
+        bump = liquidity_pool.load()?.token_b_vault_bump
+    )]
+    pub token_b_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+}
@@ -0,0 +1,137 @@
+use crate::errors::AmmError;
+use crate::state::{LaunchWhitelistEntry, LiquidityPool, LpWhitelistEntry};
+use anchor_lang::prelude::*;
+
+/// Approve `wallet` to provide liquidity while the pool's LP whitelist is enabled
+pub fn add_lp_whitelist(ctx: Context<AddLpWhitelist>, wallet: Pubkey) -> Result<()> {
+    let entry = &mut ctx.accounts.lp_whitelist_entry;
+    entry.liquidity_pool = ctx.accounts.liquidity_pool.key();
+    entry.wallet = wallet;
+
+    log!("LP whitelist - Added: {}", wallet);
+    Ok(())
+}
+
+/// Revoke a wallet's approval; the entry's rent goes back to the admin
+pub fn remove_lp_whitelist(ctx: Context<RemoveLpWhitelist>) -> Result<()> {
+    log!(
+        "LP whitelist - Removed: {}",
+        ctx.accounts.lp_whitelist_entry.wallet
+    );
+    Ok(())
+}
+
+/// Allow `wallet` to swap during the pool's whitelist-only launch window
+pub fn add_launch_whitelist(ctx: Context<AddLaunchWhitelist>, wallet: Pubkey) -> Result<()> {
+    let entry = &mut ctx.accounts.launch_whitelist_entry;
+    entry.liquidity_pool = ctx.accounts.liquidity_pool.key();
+    entry.wallet = wallet;
+
+    log!("Launch whitelist - Added: {}", wallet);
+    Ok(())
+}
+
+/// Revoke a wallet's launch window approval; the entry's rent goes back to the admin
+pub fn remove_launch_whitelist(ctx: Context<RemoveLaunchWhitelist>) -> Result<()> {
+    log!(
+        "Launch whitelist - Removed: {}",
+        ctx.accounts.launch_whitelist_entry.wallet
+    );
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(wallet: Pubkey)]
+pub struct AddLpWhitelist<'info> {
+    /// Pool state account
+    #[account(has_one = admin @ AmmError::Unauthorized)]
+    pub liquidity_pool: AccountLoader<'info, LiquidityPool>,
+
+    /// Whitelist entry of the approved wallet
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + LpWhitelistEntry::INIT_SPACE,
+        seeds = [b"lp_whitelist", liquidity_pool.key().as_ref(), wallet.as_ref()],
+        bump,
+    )]
+    pub lp_whitelist_entry: Box<Account<'info, LpWhitelistEntry>>,
+
+    /// Pool admin, pays for the entry
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RemoveLpWhitelist<'info> {
+    /// Pool state account
+    #[account(has_one = admin @ AmmError::Unauthorized)]
+    pub liquidity_pool: AccountLoader<'info, LiquidityPool>,
+
+    /// Whitelist entry being revoked
+    #[account(
+        mut,
+        close = admin,
+        seeds = [
+            b"lp_whitelist",
+            liquidity_pool.key().as_ref(),
+            lp_whitelist_entry.wallet.as_ref()
+        ],
+        bump,
+    )]
+    pub lp_whitelist_entry: Box<Account<'info, LpWhitelistEntry>>,
+
+    /// Pool admin, receives the entry's rent
+    #[account(mut)]
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(wallet: Pubkey)]
+pub struct AddLaunchWhitelist<'info> {
+    /// Pool state account
+    #[account(has_one = admin @ AmmError::Unauthorized)]
+    pub liquidity_pool: AccountLoader<'info, LiquidityPool>,
+
+    /// Launch whitelist entry of the approved wallet
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + LaunchWhitelistEntry::INIT_SPACE,
+        seeds = [b"launch_whitelist", liquidity_pool.key().as_ref(), wallet.as_ref()],
+        bump,
+    )]
+    pub launch_whitelist_entry: Box<Account<'info, LaunchWhitelistEntry>>,
+
+    /// Pool admin, pays for the entry
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RemoveLaunchWhitelist<'info> {
+    /// Pool state account
+    #[account(has_one = admin @ AmmError::Unauthorized)]
+    pub liquidity_pool: AccountLoader<'info, LiquidityPool>,
+
+    /// Launch whitelist entry being revoked
+    #[account(
+        mut,
+        close = admin,
+        seeds = [
+            b"launch_whitelist",
+            liquidity_pool.key().as_ref(),
+            launch_whitelist_entry.wallet.as_ref()
+        ],
+        bump,
+    )]
+    pub launch_whitelist_entry: Box<Account<'info, LaunchWhitelistEntry>>,
+
+    /// Pool admin, receives the entry's rent
+    #[account(mut)]
+    pub admin: Signer<'info>,
+}
@@ -1,13 +1,81 @@
+//! Constant product AMM with LP farms, keeper orders and protocol fees.
+//!
+//! Other Anchor programs can call every instruction through typed CPI builders
+//! by depending on this crate with the `cpi` feature, which also implies
+//! `no-entrypoint`:
+//!
+//! ```toml
+//! ammv2 = { version = "0.1.0", features = ["cpi"] }
+//! ```
+//!
+//! `ammv2::cpi::*` holds one builder per instruction, `ammv2::cpi::accounts::*`
+//! the matching account structs, and `ammv2::instruction::*` the serialized
+//! argument structs for building instructions off-chain:
+//!
+//! ```ignore
+//! let accounts = ammv2::cpi::accounts::SwapTokens {
+//!     liquidity_pool: ctx.accounts.liquidity_pool.to_account_info(),
+//!     // ...every other account of `SwapTokens`, `None` for unused optional ones
+//! };
+//! let swapped = ammv2::cpi::swap_tokens(
+//!     CpiContext::new(ctx.accounts.amm_program.to_account_info(), accounts),
+//!     input_amount,
+//!     minimum_output_amount,
+//!     None,
+//!     false,
+//!     None,
+//!     None,
+//! )?
+//! .get();
+//! ```
+//!
+//! Swaps return an `instructions::SwapResult` with the input swapped, the
+//! output paid and the fee, so callers need not diff token balances.
+//!
+//! Swaps and liquidity instructions take an optional `client_order_id`, echoed
+//! untouched in their events so off-chain systems can reconcile fills.
+//!
+//! Account state, events and errors are public in `state`, `events` and `errors`.
+//!
+//! Build with the `no-log` feature to compile out the informational `msg!` logs,
+//! which saves compute on multi-pool routes. Events are emitted either way.
+
+// Anchor 0.31 IDL instructions still call the deprecated `AccountInfo::realloc`
+#![allow(deprecated)]
+
 use anchor_lang::prelude::*;
 
+/// `msg!` that compiles to nothing under the `no-log` feature
+macro_rules! log {
+    ($($arg:tt)*) => {
+        if cfg!(not(feature = "no-log")) {
+            anchor_lang::prelude::msg!($($arg)*);
+        }
+    };
+}
+
 pub mod errors;
+pub mod events;
+pub mod fixed_point;
 pub mod instructions;
+pub mod math;
 pub mod state;
 
 use instructions::*;
+use state::{ParamChange, VerificationLevel};
 
 declare_id!("Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS");
 
+// Left out of CPI builds so a dependent program doesn't embed a second copy
+#[cfg(not(feature = "no-entrypoint"))]
+solana_security_txt::security_txt! {
+    name: "AMM V2",
+    project_url: "https://github.com/SimplyRohit/ammv2",
+    contacts: "link:https://github.com/SimplyRohit/ammv2/security/advisories/new",
+    policy: "https://github.com/SimplyRohit/ammv2/blob/main/SECURITY.md",
+    source_code: "https://github.com/SimplyRohit/ammv2"
+}
+
 #[program]
 pub mod amm_v2 {
     use super::*;
@@ -17,34 +85,831 @@ pub mod amm_v2 {
         ctx: Context<InitializePool>,
         fee_basis_points_numerator: u64,
         fee_basis_points_denominator: u64,
+        lp_metadata_uri: String,
+        launch_whitelist_end_slot: u64,
     ) -> Result<()> {
         instructions::initialize_pool::process(
             ctx,
             fee_basis_points_numerator,
             fee_basis_points_denominator,
+            lp_metadata_uri,
+            launch_whitelist_end_slot,
+        )
+    }
+
+    /// Initialize a pool and make its first deposit in one atomic instruction,
+    /// optionally locking part of the creator's LP tokens
+    #[allow(clippy::too_many_arguments)]
+    pub fn initialize_pool_with_liquidity(
+        ctx: Context<InitializePoolWithLiquidity>,
+        fee_numerator: u64,
+        fee_denominator: u64,
+        token_a_amount: u64,
+        token_b_amount: u64,
+        lp_metadata_uri: String,
+        launch_whitelist_end_slot: u64,
+        creator_lock: Option<CreatorLpLock>,
+    ) -> Result<()> {
+        instructions::initialize_pool_with_liquidity::process(
+            ctx,
+            fee_numerator,
+            fee_denominator,
+            token_a_amount,
+            token_b_amount,
+            lp_metadata_uri,
+            launch_whitelist_end_slot,
+            creator_lock,
         )
     }
 
+    /// Create the global config (program upgrade authority only)
+    pub fn initialize_amm_config(
+        ctx: Context<InitializeAmmConfig>,
+        pool_creation_fee_lamports: u64,
+    ) -> Result<()> {
+        instructions::amm_config::initialize(ctx, pool_creation_fee_lamports)
+    }
+
     /// Add liquidity to the pool and receive LP tokens
     pub fn deposit_liquidity(
-        ctx: Context<ManageLiquidity>,
+        ctx: Context<DepositLiquidity>,
         token_a_amount: u64,
         token_b_amount: u64,
+        client_order_id: Option<u64>,
+    ) -> Result<()> {
+        instructions::manage_liquidity::deposit(
+            ctx,
+            token_a_amount,
+            token_b_amount,
+            client_order_id,
+        )
+    }
+
+    /// Add a large deposit, reverting if the pool ratio drifted beyond a bound since it was quoted
+    pub fn deposit_chunked(
+        ctx: Context<DepositLiquidity>,
+        total_a: u64,
+        total_b: u64,
+        max_ratio_drift_bps: u16,
+        client_order_id: Option<u64>,
     ) -> Result<()> {
-        instructions::manage_liquidity::deposit(ctx, token_a_amount, token_b_amount)
+        instructions::manage_liquidity::deposit_chunked(
+            ctx,
+            total_a,
+            total_b,
+            max_ratio_drift_bps,
+            client_order_id,
+        )
     }
 
-    /// Remove liquidity from the pool by burning LP tokens
-    pub fn withdraw_liquidity(ctx: Context<ManageLiquidity>, lp_tokens_to_burn: u64) -> Result<()> {
-        instructions::manage_liquidity::withdraw(ctx, lp_tokens_to_burn)
+    /// Remove liquidity from the pool by burning LP tokens, with an optional memo note
+    pub fn withdraw_liquidity(
+        ctx: Context<WithdrawLiquidity>,
+        lp_tokens_to_burn: u64,
+        note: Option<String>,
+        client_order_id: Option<u64>,
+    ) -> Result<()> {
+        instructions::manage_liquidity::withdraw(ctx, lp_tokens_to_burn, note, client_order_id)
     }
 
-    /// Swap tokens using the constant product formula
+    /// Swap tokens using the constant product formula, returning the amounts swapped
     pub fn swap_tokens(
         ctx: Context<SwapTokens>,
         input_amount: u64,
         minimum_output_amount: u64,
+        slippage_bps: Option<u16>,
+        allow_partial_fill: bool,
+        price_limit: Option<u128>,
+        client_order_id: Option<u64>,
+    ) -> Result<SwapResult> {
+        instructions::swap::process(
+            ctx,
+            input_amount,
+            minimum_output_amount,
+            slippage_bps,
+            allow_partial_fill,
+            price_limit,
+            client_order_id,
+        )
+    }
+
+    /// Swap like `swap_tokens`, pricing against the pool's tracked reserves
+    /// rather than the vault balances
+    pub fn swap_v2(
+        ctx: Context<SwapTokens>,
+        input_amount: u64,
+        minimum_output_amount: u64,
+        slippage_bps: Option<u16>,
+        allow_partial_fill: bool,
+        price_limit: Option<u128>,
+        client_order_id: Option<u64>,
+    ) -> Result<SwapResult> {
+        instructions::swap::process_v2(
+            ctx,
+            input_amount,
+            minimum_output_amount,
+            slippage_bps,
+            allow_partial_fill,
+            price_limit,
+            client_order_id,
+        )
+    }
+
+    /// Swap for an exact output amount, paying at most `maximum_input_amount`
+    pub fn swap_exact_output(
+        ctx: Context<SwapTokens>,
+        output_amount: u64,
+        maximum_input_amount: u64,
+        client_order_id: Option<u64>,
+    ) -> Result<SwapResult> {
+        instructions::swap::process_exact_output(
+            ctx,
+            output_amount,
+            maximum_input_amount,
+            client_order_id,
+        )
+    }
+
+    /// Swap if the average execution price is within `tolerance_bps` of `expected_price`
+    pub fn swap_at_expected_price(
+        ctx: Context<SwapTokens>,
+        input_amount: u64,
+        expected_price: u128,
+        tolerance_bps: u16,
+        client_order_id: Option<u64>,
+    ) -> Result<SwapResult> {
+        instructions::swap::process_at_expected_price(
+            ctx,
+            input_amount,
+            expected_price,
+            tolerance_bps,
+            client_order_id,
+        )
+    }
+
+    /// Create a segregated sub-account under the signing owner
+    pub fn create_sub_account(ctx: Context<CreateSubAccount>, sub_account_id: u16) -> Result<()> {
+        instructions::sub_account::create(ctx, sub_account_id)
+    }
+
+    /// Set or revoke the delegate allowed to manage a sub-account's liquidity
+    pub fn set_sub_account_delegate(
+        ctx: Context<ManageSubAccount>,
+        delegate: Option<Pubkey>,
+    ) -> Result<()> {
+        instructions::sub_account::set_delegate(ctx, delegate)
+    }
+
+    /// Close a sub-account and reclaim its rent
+    pub fn close_sub_account(ctx: Context<CloseSubAccount>) -> Result<()> {
+        instructions::sub_account::close(ctx)
+    }
+
+    /// Move tokens held by a sub-account to another token account (owner only)
+    pub fn transfer_from_sub_account(ctx: Context<SubAccountTransfer>, amount: u64) -> Result<()> {
+        instructions::sub_account::transfer_out(ctx, amount)
+    }
+
+    /// Permissionlessly create the oracle of a pool that predates oracles
+    pub fn bootstrap_oracle(ctx: Context<BootstrapOracle>) -> Result<()> {
+        instructions::oracle::bootstrap(ctx)
+    }
+
+    /// Change the pool's fixed swap fee, up to `MAX_FEE_BPS`
+    pub fn update_fee(
+        ctx: Context<UpdatePoolConfig>,
+        fee_numerator: u64,
+        fee_denominator: u64,
+    ) -> Result<()> {
+        instructions::admin::update_fee(ctx, fee_numerator, fee_denominator)
+    }
+
+    /// Make an SPL Governance account of a realm the pool admin, so parameter
+    /// changes go through DAO proposals instead of a single key
+    pub fn set_governance_admin(ctx: Context<SetGovernanceAdmin>) -> Result<()> {
+        instructions::admin::set_governance_admin(ctx)
+    }
+
+    /// Scale the swap fee with the oracle's realized volatility (a zero maximum disables)
+    pub fn set_dynamic_fee(
+        ctx: Context<UpdatePoolConfig>,
+        dynamic_fee_min_bps: u16,
+        dynamic_fee_max_bps: u16,
+        dynamic_fee_volatility_bps: u16,
+    ) -> Result<()> {
+        instructions::admin::set_dynamic_fee(
+            ctx,
+            dynamic_fee_min_bps,
+            dynamic_fee_max_bps,
+            dynamic_fee_volatility_bps,
+        )
+    }
+
+    /// Charge the swap fee on the output token instead of the input token
+    pub fn set_fee_on_output(ctx: Context<UpdatePoolConfig>, fee_on_output: bool) -> Result<()> {
+        instructions::admin::set_fee_on_output(ctx, fee_on_output)
+    }
+
+    /// Permissionlessly record an oracle observation from current reserves,
+    /// paying the pool's checkpoint reward when the oracle was stale
+    pub fn checkpoint_oracle(ctx: Context<CheckpointOracle>) -> Result<()> {
+        instructions::oracle::checkpoint(ctx)
+    }
+
+    /// Reward in token A for checkpointing a stale oracle, paid from the keeper
+    /// tip budget (0 disables it)
+    pub fn set_oracle_checkpoint_reward(
+        ctx: Context<UpdatePoolConfig>,
+        oracle_checkpoint_reward: u64,
+    ) -> Result<()> {
+        instructions::admin::set_oracle_checkpoint_reward(ctx, oracle_checkpoint_reward)
+    }
+
+    /// Time-weighted average price over the last `window_secs`, returned as return data
+    pub fn read_twap(ctx: Context<ReadTwap>, window_secs: i64) -> Result<TwapReading> {
+        instructions::oracle::read_twap(ctx, window_secs)
+    }
+
+    /// Create a pool's hourly volume stats account, which swaps then keep updated
+    pub fn initialize_volume_stats(ctx: Context<InitializeVolumeStats>) -> Result<()> {
+        instructions::volume_stats::initialize(ctx)
+    }
+
+    /// Read the pool's trailing 24h volume, LP fees and fee APR as return data
+    pub fn read_volume_stats(ctx: Context<ReadVolumeStats>) -> Result<VolumeStatsReading> {
+        instructions::volume_stats::read(ctx)
+    }
+
+    /// Create the insurance fund vaults that collect deviation-incident surcharges
+    pub fn initialize_insurance_fund(ctx: Context<InitializeInsuranceFund>) -> Result<()> {
+        instructions::insurance::initialize(ctx)
+    }
+
+    /// Move insurance fund tokens into the pool reserves (admin only)
+    pub fn deploy_insurance_fund(
+        ctx: Context<DeployInsuranceFund>,
+        amount_a: u64,
+        amount_b: u64,
+    ) -> Result<()> {
+        instructions::insurance::deploy(ctx, amount_a, amount_b)
+    }
+
+    /// Quote the trade that moves the spot price to a target, returned as return data
+    pub fn quote_rebalance(
+        ctx: Context<QuoteRebalance>,
+        target_price: u128,
+    ) -> Result<RebalanceHint> {
+        instructions::quote::rebalance_hint(ctx, target_price)
+    }
+
+    /// Convert raw token amounts and the reserves to UI amounts, scaled by the
+    /// mints' interest rates, returned as return data
+    pub fn quote_ui_amounts(
+        ctx: Context<QuoteUiAmounts>,
+        token_a_amount: u64,
+        token_b_amount: u64,
+    ) -> Result<UiAmounts> {
+        instructions::quote::ui_amounts(ctx, token_a_amount, token_b_amount)
+    }
+
+    /// Permissionlessly re-sync the pool's LP counter to the LP mint supply
+    pub fn sync_lp_supply(ctx: Context<SyncLpSupply>) -> Result<()> {
+        instructions::sync_lp_supply::process(ctx)
+    }
+
+    /// Permissionlessly realloc an older pool to the current layout and bump its version
+    pub fn migrate_pool(ctx: Context<MigratePool>) -> Result<()> {
+        instructions::migrate_pool::process(ctx)
+    }
+
+    /// Close an empty pool and return its rent to a recipient (admin only)
+    pub fn close_pool(ctx: Context<ClosePool>) -> Result<()> {
+        instructions::close_pool::process(ctx)
+    }
+
+    /// Create the LP staking farm of a pool (admin only)
+    pub fn initialize_farm(ctx: Context<InitializeFarm>, reward_per_second: u64) -> Result<()> {
+        instructions::farm::initialize(ctx, reward_per_second)
+    }
+
+    /// Stake LP tokens in a pool's farm, optionally locked for a reward boost
+    pub fn stake_lp(ctx: Context<ManageStake>, amount: u64, lock_tier: u8) -> Result<()> {
+        instructions::farm::stake(ctx, amount, lock_tier)
+    }
+
+    /// Unstake LP tokens from a pool's farm
+    pub fn unstake_lp(ctx: Context<ManageStake>, amount: u64) -> Result<()> {
+        instructions::farm::unstake(ctx, amount)
+    }
+
+    /// Add another reward stream to a pool's farm (admin only)
+    pub fn add_farm_reward(ctx: Context<AddFarmReward>, reward_per_second: u64) -> Result<()> {
+        instructions::farm::add_reward(ctx, reward_per_second)
+    }
+
+    /// Claim the farm rewards of one reward mint earned by a stake position
+    pub fn claim_rewards(ctx: Context<ClaimRewards>) -> Result<()> {
+        instructions::farm::claim(ctx)
+    }
+
+    /// Claim farm rewards paid in a pool token, zap them into liquidity and restake the LP
+    pub fn compound(ctx: Context<Compound>) -> Result<()> {
+        instructions::compound::process(ctx)
+    }
+
+    /// Create the gauge controller directing farm emissions by vote (config authority only)
+    pub fn initialize_gauge_controller(
+        ctx: Context<InitializeGaugeController>,
+        emission_per_second: u64,
+        epoch_duration_secs: i64,
+    ) -> Result<()> {
+        instructions::gauge::initialize_controller(ctx, emission_per_second, epoch_duration_secs)
+    }
+
+    /// Register a farm for gauge emissions (config authority only)
+    pub fn create_gauge(ctx: Context<CreateGauge>) -> Result<()> {
+        instructions::gauge::create_gauge(ctx)
+    }
+
+    /// Escrow governance tokens as gauge voting power
+    pub fn lock_gauge_votes(ctx: Context<LockGaugeVotes>, amount: u64) -> Result<()> {
+        instructions::gauge::lock_votes(ctx, amount)
+    }
+
+    /// Withdraw escrowed governance tokens that have not voted this epoch
+    pub fn unlock_gauge_votes(ctx: Context<UnlockGaugeVotes>, amount: u64) -> Result<()> {
+        instructions::gauge::unlock_votes(ctx, amount)
+    }
+
+    /// Vote for a gauge with locked governance token voting power
+    pub fn vote_gauge(ctx: Context<VoteGauge>, votes: u64) -> Result<()> {
+        instructions::gauge::vote(ctx, votes)
+    }
+
+    /// Close the current gauge voting epoch once it has ended
+    pub fn advance_gauge_epoch(ctx: Context<AdvanceGaugeEpoch>) -> Result<()> {
+        instructions::gauge::advance_epoch(ctx)
+    }
+
+    /// Apply a gauge's vote share from the last epoch to its farm's emission rate
+    pub fn checkpoint_gauge(ctx: Context<CheckpointGauge>) -> Result<()> {
+        instructions::gauge::checkpoint(ctx)
+    }
+
+    /// Create the pool's protocol fee vaults
+    pub fn initialize_protocol_fee_vaults(ctx: Context<InitializeProtocolFeeVaults>) -> Result<()> {
+        instructions::protocol_fee::initialize_vaults(ctx)
+    }
+
+    /// Swap accrued protocol fees into the pool's buyback token and burn them
+    pub fn buyback_and_burn(ctx: Context<BuybackAndBurn>) -> Result<()> {
+        instructions::protocol_fee::buyback_and_burn(ctx)
+    }
+
+    /// Permissionlessly move accrued protocol fees to the treasury vaults,
+    /// paying the caller the pool's keeper tip
+    pub fn harvest_protocol_fees(ctx: Context<HarvestProtocolFees>) -> Result<()> {
+        instructions::protocol_fee::harvest(ctx)
+    }
+
+    /// Donate tokens into the pool's reserves without minting LP tokens
+    pub fn donate_to_pool(
+        ctx: Context<DonateToPool>,
+        token_a_amount: u64,
+        token_b_amount: u64,
+    ) -> Result<()> {
+        instructions::donate::process(ctx, token_a_amount, token_b_amount)
+    }
+
+    /// Permissionlessly sweep vault balances above the tracked reserves to the treasury
+    pub fn skim(ctx: Context<Skim>) -> Result<()> {
+        instructions::skim::process(ctx)
+    }
+
+    /// Escrow input tokens and schedule recurring swaps through the pool
+    pub fn open_dca(
+        ctx: Context<OpenDca>,
+        dca_id: u16,
+        total_amount: u64,
+        amount_per_interval: u64,
+        interval_secs: i64,
+        min_output_per_interval: u64,
+        keeper_tip_lamports: u64,
+    ) -> Result<()> {
+        instructions::dca::open(
+            ctx,
+            dca_id,
+            total_amount,
+            amount_per_interval,
+            interval_secs,
+            min_output_per_interval,
+            keeper_tip_lamports,
+        )
+    }
+
+    /// Permissionlessly swap the next due tranche of a DCA schedule, for a tip
+    pub fn execute_dca(ctx: Context<ExecuteDca>) -> Result<()> {
+        instructions::dca::execute(ctx)
+    }
+
+    /// Cancel a DCA schedule and return the unswapped input
+    pub fn close_dca(ctx: Context<CloseDca>) -> Result<()> {
+        instructions::dca::close(ctx)
+    }
+
+    /// Escrow input tokens to be swapped once the pool reaches a limit price
+    pub fn place_limit_order(
+        ctx: Context<PlaceLimitOrder>,
+        order_id: u64,
+        input_amount: u64,
+        min_output_amount: u64,
+        expiry_timestamp: i64,
+        keeper_tip_lamports: u64,
+    ) -> Result<()> {
+        instructions::limit_order::place(
+            ctx,
+            order_id,
+            input_amount,
+            min_output_amount,
+            expiry_timestamp,
+            keeper_tip_lamports,
+        )
+    }
+
+    /// Permissionlessly fill a limit order the pool price satisfies, for a tip
+    pub fn fill_limit_order(ctx: Context<FillLimitOrder>) -> Result<()> {
+        instructions::limit_order::fill(ctx)
+    }
+
+    /// Cancel an unfilled limit order and return the input
+    pub fn cancel_limit_order(ctx: Context<CancelLimitOrder>) -> Result<()> {
+        instructions::limit_order::cancel(ctx)
+    }
+
+    /// Open a position, whose vault holds the LP minted through it, to claim swap fees from
+    pub fn open_position(ctx: Context<OpenPosition>) -> Result<()> {
+        instructions::liquidity_position::open(ctx)
+    }
+
+    /// Pay out the fees a position has accrued without withdrawing its liquidity
+    pub fn claim_fees(ctx: Context<ClaimFees>) -> Result<()> {
+        instructions::liquidity_position::claim_fees(ctx)
+    }
+
+    /// Return the fees a position could claim now, without claiming them
+    pub fn pending_fees(ctx: Context<QueryPendingFees>) -> Result<PendingFees> {
+        instructions::liquidity_position::pending_fees(ctx)
+    }
+
+    /// Escrow LP tokens until `unlock_timestamp`; relocking tops up and may only extend
+    pub fn lock_lp_tokens(
+        ctx: Context<LockLpTokens>,
+        amount: u64,
+        unlock_timestamp: i64,
+    ) -> Result<()> {
+        instructions::lp_lock::lock(ctx, amount, unlock_timestamp)
+    }
+
+    /// Return all LP tokens of an expired lock to its owner
+    pub fn unlock_lp_tokens(ctx: Context<UnlockLpTokens>) -> Result<()> {
+        instructions::lp_lock::unlock(ctx)
+    }
+
+    /// Emit a digest of the full pool state, at most once per epoch
+    pub fn export_state_digest(ctx: Context<ExportStateDigest>) -> Result<()> {
+        instructions::export_state_digest::process(ctx)
+    }
+
+    /// Set the maximum spot price change allowed within one slot (0 disables)
+    pub fn set_circuit_breaker(
+        ctx: Context<UpdatePoolConfig>,
+        max_price_change_bps_per_slot: u16,
+    ) -> Result<()> {
+        instructions::admin::set_circuit_breaker(ctx, max_price_change_bps_per_slot)
+    }
+
+    /// Limit per-trader volume per slot and optionally block same-slot reversals
+    pub fn set_anti_sandwich_limits(
+        ctx: Context<UpdatePoolConfig>,
+        max_trader_slot_volume_bps: u16,
+        block_same_slot_reversal: bool,
+    ) -> Result<()> {
+        instructions::admin::set_anti_sandwich_limits(
+            ctx,
+            max_trader_slot_volume_bps,
+            block_same_slot_reversal,
+        )
+    }
+
+    /// Cap a single swap's input to a share of the input reserve (0 disables)
+    pub fn set_max_trade_size(
+        ctx: Context<UpdatePoolConfig>,
+        max_trade_bps_of_reserves: u16,
+    ) -> Result<()> {
+        instructions::admin::set_max_trade_size(ctx, max_trade_bps_of_reserves)
+    }
+
+    /// Reject swaps moving the spot price by more than this many bps (0 disables)
+    pub fn set_max_price_impact(
+        ctx: Context<UpdatePoolConfig>,
+        max_price_impact_bps: u16,
+    ) -> Result<()> {
+        instructions::admin::set_max_price_impact(ctx, max_price_impact_bps)
+    }
+
+    /// Share of swap fees, in bps of the fee, accrued to a swap's referrer (0 disables)
+    pub fn set_referral_fee(ctx: Context<UpdatePoolConfig>, referral_fee_bps: u16) -> Result<()> {
+        instructions::admin::set_referral_fee(ctx, referral_fee_bps)
+    }
+
+    /// Set the token A tip paid to crank callers and the share of protocol fees funding it
+    pub fn set_keeper_tips(
+        ctx: Context<UpdatePoolConfig>,
+        keeper_tip: u64,
+        keeper_tip_share_bps: u16,
+    ) -> Result<()> {
+        instructions::admin::set_keeper_tips(ctx, keeper_tip, keeper_tip_share_bps)
+    }
+
+    /// Enforce a per-wallet swap cooldown until the launch protection slot
+    pub fn set_launch_protection(
+        ctx: Context<UpdatePoolConfig>,
+        launch_protection_end_slot: u64,
+        launch_trade_cooldown_slots: u64,
+    ) -> Result<()> {
+        instructions::admin::set_launch_protection(
+            ctx,
+            launch_protection_end_slot,
+            launch_trade_cooldown_slots,
+        )
+    }
+
+    /// Penalize withdrawals within a window of slots after the position's last
+    /// deposit, by at most `MAX_JIT_PENALTY_BPS` (0 slots disables it)
+    pub fn set_jit_protection(
+        ctx: Context<UpdatePoolConfig>,
+        jit_window_slots: u64,
+        jit_penalty_bps: u16,
+    ) -> Result<()> {
+        instructions::admin::set_jit_protection(ctx, jit_window_slots, jit_penalty_bps)
+    }
+
+    /// Lock withdrawals for a cooldown after each deposit into a position (0 disables it)
+    pub fn set_withdraw_cooldown(
+        ctx: Context<UpdatePoolConfig>,
+        withdraw_cooldown_secs: i64,
+    ) -> Result<()> {
+        instructions::admin::set_withdraw_cooldown(ctx, withdraw_cooldown_secs)
+    }
+
+    /// Toggle emergency withdraw-only mode with a timelock for large positions
+    pub fn set_withdraw_only(
+        ctx: Context<UpdatePoolConfig>,
+        withdraw_only: bool,
+        large_withdrawal_delay_secs: i64,
+        small_lp_withdrawal_threshold: u64,
+    ) -> Result<()> {
+        instructions::admin::set_withdraw_only(
+            ctx,
+            withdraw_only,
+            large_withdrawal_delay_secs,
+            small_lp_withdrawal_threshold,
+        )
+    }
+
+    /// Bound the post-swap price's deviation from the oracle TWAP and set the
+    /// incident band that adds an insurance surcharge (0 disables each)
+    pub fn set_deviation_guard(
+        ctx: Context<UpdatePoolConfig>,
+        deviation_twap_window_secs: i64,
+        max_oracle_deviation_bps: u16,
+        incident_deviation_bps: u16,
+        incident_surcharge_bps: u16,
+    ) -> Result<()> {
+        instructions::admin::set_deviation_guard(
+            ctx,
+            deviation_twap_window_secs,
+            max_oracle_deviation_bps,
+            incident_deviation_bps,
+            incident_surcharge_bps,
+        )
+    }
+
+    /// Require the pool's first deposit to provide at least these amounts (0 disables)
+    pub fn set_min_initial_liquidity(
+        ctx: Context<UpdatePoolConfig>,
+        min_initial_deposit_a: u64,
+        min_initial_deposit_b: u64,
+    ) -> Result<()> {
+        instructions::admin::set_min_initial_liquidity(
+            ctx,
+            min_initial_deposit_a,
+            min_initial_deposit_b,
+        )
+    }
+
+    /// Assign the pool's verification level (unverified, community, verified)
+    pub fn set_verification_level(
+        ctx: Context<UpdatePoolConfig>,
+        verification_level: VerificationLevel,
+    ) -> Result<()> {
+        instructions::admin::set_verification_level(ctx, verification_level)
+    }
+
+    /// Set the lamport fee charged to pool creators (config authority only)
+    pub fn set_pool_creation_fee(
+        ctx: Context<UpdateAmmConfig>,
+        pool_creation_fee_lamports: u64,
+    ) -> Result<()> {
+        instructions::amm_config::set_pool_creation_fee(ctx, pool_creation_fee_lamports)
+    }
+
+    /// Change the emission rate of one of a farm's reward streams (admin only)
+    pub fn set_farm_emission_rate(
+        ctx: Context<UpdateFarm>,
+        reward_mint: Pubkey,
+        reward_per_second: u64,
+    ) -> Result<()> {
+        instructions::farm::set_emission_rate(ctx, reward_mint, reward_per_second)
+    }
+
+    /// Take a share of swap fees for the protocol, optionally bought back and burned
+    pub fn set_protocol_fee(
+        ctx: Context<UpdatePoolConfig>,
+        protocol_fee_share_bps: u16,
+        buyback_mint: Option<Pubkey>,
+    ) -> Result<()> {
+        instructions::admin::set_protocol_fee(ctx, protocol_fee_share_bps, buyback_mint)
+    }
+
+    /// Route the protocol fee share to admin-chosen token accounts
+    pub fn set_fee_recipients(ctx: Context<SetFeeRecipients>) -> Result<()> {
+        instructions::admin::set_fee_recipients(ctx)
+    }
+
+    /// Set the creator's share of swap fees and its token accounts (pool creator only)
+    pub fn set_creator_fee(ctx: Context<SetCreatorFee>, creator_fee_bps: u16) -> Result<()> {
+        instructions::admin::set_creator_fee(ctx, creator_fee_bps)
+    }
+
+    /// Disable individual pool operations by `POOL_OP_*` bit (admin only)
+    pub fn set_disabled_operations(
+        ctx: Context<UpdatePoolConfig>,
+        disabled_operations: u16,
+    ) -> Result<()> {
+        instructions::admin::set_disabled_operations(ctx, disabled_operations)
+    }
+
+    /// Restrict deposits to whitelisted wallets (admin only)
+    pub fn set_lp_whitelist_enabled(ctx: Context<UpdatePoolConfig>, enabled: bool) -> Result<()> {
+        instructions::admin::set_lp_whitelist_enabled(ctx, enabled)
+    }
+
+    /// Approve a wallet to provide liquidity to a permissioned pool (admin only)
+    pub fn add_lp_whitelist(ctx: Context<AddLpWhitelist>, wallet: Pubkey) -> Result<()> {
+        instructions::whitelist::add_lp_whitelist(ctx, wallet)
+    }
+
+    /// Revoke a wallet's liquidity approval (admin only)
+    pub fn remove_lp_whitelist(ctx: Context<RemoveLpWhitelist>) -> Result<()> {
+        instructions::whitelist::remove_lp_whitelist(ctx)
+    }
+
+    /// Allow a wallet to swap during the whitelist-only launch window (admin only)
+    pub fn add_launch_whitelist(ctx: Context<AddLaunchWhitelist>, wallet: Pubkey) -> Result<()> {
+        instructions::whitelist::add_launch_whitelist(ctx, wallet)
+    }
+
+    /// Revoke a wallet's launch window approval (admin only)
+    pub fn remove_launch_whitelist(ctx: Context<RemoveLaunchWhitelist>) -> Result<()> {
+        instructions::whitelist::remove_launch_whitelist(ctx)
+    }
+
+    /// Cap the share of swap fees pool creators may take (config authority only)
+    pub fn set_max_creator_fee(
+        ctx: Context<UpdateAmmConfig>,
+        max_creator_fee_bps: u16,
+    ) -> Result<()> {
+        instructions::amm_config::set_max_creator_fee(ctx, max_creator_fee_bps)
+    }
+
+    /// Pause or resume the program across every pool (config authority only)
+    pub fn set_paused(ctx: Context<UpdateAmmConfig>, paused: bool) -> Result<()> {
+        instructions::amm_config::set_paused(ctx, paused)
+    }
+
+    /// Refuse or allow new pools over mints with an active freeze authority
+    pub fn set_reject_freeze_authority(ctx: Context<UpdateAmmConfig>, reject: bool) -> Result<()> {
+        instructions::amm_config::set_reject_freeze_authority(ctx, reject)
+    }
+
+    /// Allow pools over a mint with a permanent delegate or close authority
+    pub fn approve_unsafe_mint(ctx: Context<ApproveUnsafeMint>, mint: Pubkey) -> Result<()> {
+        instructions::amm_config::approve_unsafe_mint(ctx, mint)
+    }
+
+    /// Revoke an unsafe mint approval
+    pub fn revoke_unsafe_mint(ctx: Context<RevokeUnsafeMint>) -> Result<()> {
+        instructions::amm_config::revoke_unsafe_mint(ctx)
+    }
+
+    /// Queue a config or pool parameter change behind the global timelock
+    pub fn queue_param_change(
+        ctx: Context<QueueParamChange>,
+        change_id: u64,
+        change: ParamChange,
+    ) -> Result<()> {
+        instructions::timelock::queue(ctx, change_id, change)
+    }
+
+    /// Permissionlessly apply a queued parameter change once its timelock has elapsed
+    pub fn execute_param_change<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ExecuteParamChange<'info>>,
+    ) -> Result<()> {
+        instructions::timelock::execute(ctx)
+    }
+
+    /// Drop a queued parameter change (proposer only)
+    pub fn cancel_param_change(ctx: Context<CancelParamChange>) -> Result<()> {
+        instructions::timelock::cancel(ctx)
+    }
+
+    /// Create the treasury vault of a mint, which collects protocol revenue in that token
+    pub fn initialize_treasury_vault(ctx: Context<InitializeTreasuryVault>) -> Result<()> {
+        instructions::treasury::initialize_vault(ctx)
+    }
+
+    /// Set the withdrawal limits of a treasury balance (config authority only)
+    pub fn set_treasury_limits(
+        ctx: Context<SetTreasuryLimits>,
+        mint: Pubkey,
+        max_withdraw_per_call: u64,
+        max_withdraw_per_epoch: u64,
+    ) -> Result<()> {
+        instructions::treasury::set_limits(ctx, mint, max_withdraw_per_call, max_withdraw_per_epoch)
+    }
+
+    /// Register the signer as a referrer that swaps can name to share the fee
+    pub fn register_referrer(ctx: Context<RegisterReferrer>) -> Result<()> {
+        instructions::referral::register(ctx)
+    }
+
+    /// Create the referral vault of a mint, which holds unclaimed referral fees
+    pub fn initialize_referral_vault(ctx: Context<InitializeReferralVault>) -> Result<()> {
+        instructions::referral::initialize_vault(ctx)
+    }
+
+    /// Pay out a referrer's accrued fees in one mint (referrer authority only)
+    pub fn claim_referral_fees(ctx: Context<ClaimReferralFees>) -> Result<()> {
+        instructions::referral::claim(ctx)
+    }
+
+    /// Withdraw tokens from a treasury vault within its limits (config authority only)
+    pub fn withdraw_treasury(ctx: Context<WithdrawTreasury>, amount: u64) -> Result<()> {
+        instructions::treasury::withdraw(ctx, amount)
+    }
+
+    /// Withdraw lamports from the treasury within its limits (config authority only)
+    pub fn withdraw_treasury_lamports(
+        ctx: Context<WithdrawTreasuryLamports>,
+        amount: u64,
+    ) -> Result<()> {
+        instructions::treasury::withdraw_lamports(ctx, amount)
+    }
+
+    /// Put a token on a virtual-reserve bonding curve priced in a quote token
+    pub fn create_launch_pool(
+        ctx: Context<CreateLaunchPool>,
+        token_amount: u64,
+        virtual_quote_reserve: u64,
+        target_raise: u64,
+        fee_numerator: u64,
+        fee_denominator: u64,
+    ) -> Result<()> {
+        instructions::launch_pool::create(
+            ctx,
+            token_amount,
+            virtual_quote_reserve,
+            target_raise,
+            fee_numerator,
+            fee_denominator,
+        )
+    }
+
+    /// Buy launch tokens on the bonding curve until the target raise is met
+    pub fn buy_launch_pool(
+        ctx: Context<BuyLaunchPool>,
+        quote_amount: u64,
+        minimum_token_amount: u64,
+    ) -> Result<()> {
+        instructions::launch_pool::buy(ctx, quote_amount, minimum_token_amount)
+    }
+
+    /// Seed a constant-product pool with a completed launch's raise, locking the LP tokens
+    pub fn graduate_launch_pool(
+        ctx: Context<GraduateLaunchPool>,
+        lp_metadata_uri: String,
     ) -> Result<()> {
-        instructions::swap::process(ctx, input_amount, minimum_output_amount)
+        instructions::launch_pool::graduate(ctx, lp_metadata_uri)
     }
 }
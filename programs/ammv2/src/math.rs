@@ -0,0 +1,331 @@
+use crate::errors::AmmError;
+use anchor_lang::prelude::*;
+
+/// `a * b / denominator`, rounded down
+pub fn mul_div_floor(a: u128, b: u128, denominator: u128) -> Result<u128> {
+    require!(denominator > 0, AmmError::DivisionByZero);
+    a.checked_mul(b)
+        .map(|product| product / denominator)
+        .ok_or(error!(AmmError::MathOverflow))
+}
+
+/// `a * b / denominator`, rounded up
+pub fn mul_div_ceil(a: u128, b: u128, denominator: u128) -> Result<u128> {
+    require!(denominator > 0, AmmError::DivisionByZero);
+    a.checked_mul(b)
+        .map(|product| product.div_ceil(denominator))
+        .ok_or(error!(AmmError::MathOverflow))
+}
+
+/// Integer square root, rounded down
+pub fn checked_sqrt(value: u128) -> Result<u128> {
+    if value < 2 {
+        return Ok(value);
+    }
+
+    // Newton's method from an initial guess that is always >= the root
+    let mut root = value;
+    let mut next = value >> 1;
+    while next < root {
+        root = next;
+        next = root
+            .checked_add(value / root)
+            .ok_or(AmmError::MathOverflow)?
+            >> 1;
+    }
+    Ok(root)
+}
+
+/// `10^exponent`
+fn pow10(exponent: u8) -> Result<u128> {
+    10u128
+        .checked_pow(exponent as u32)
+        .ok_or(error!(AmmError::MathOverflow))
+}
+
+/// Scale a token amount with `decimals` up to `common_decimals`.
+/// Pair math runs at the larger of the two mints' decimals so neither side is truncated.
+pub fn normalize(amount: u128, decimals: u8, common_decimals: u8) -> Result<u128> {
+    require!(decimals <= common_decimals, AmmError::InvalidDecimals);
+    amount
+        .checked_mul(pow10(common_decimals - decimals)?)
+        .ok_or(error!(AmmError::MathOverflow))
+}
+
+/// Scale a `common_decimals` amount back down to `decimals`, rounded down
+pub fn denormalize_floor(amount: u128, decimals: u8, common_decimals: u8) -> Result<u128> {
+    require!(decimals <= common_decimals, AmmError::InvalidDecimals);
+    Ok(amount / pow10(common_decimals - decimals)?)
+}
+
+/// Scale a `common_decimals` amount back down to `decimals`, rounded up
+pub fn denormalize_ceil(amount: u128, decimals: u8, common_decimals: u8) -> Result<u128> {
+    require!(decimals <= common_decimals, AmmError::InvalidDecimals);
+    Ok(amount.div_ceil(pow10(common_decimals - decimals)?))
+}
+
+/// LP mint decimals for a pair. LP supply starts at `sqrt(a * b)` base units,
+/// which scales with the mean of the two mints' decimals.
+pub fn lp_decimals(token_a_decimals: u8, token_b_decimals: u8) -> u8 {
+    ((token_a_decimals as u16 + token_b_decimals as u16) / 2) as u8
+}
+
+/// Narrow a u128 result back to a token amount
+pub fn to_u64(value: u128) -> Result<u64> {
+    u64::try_from(value).map_err(|_| error!(AmmError::MathOverflow))
+}
+
+/// Constant product output for `net_input` (pool fee already taken), rounded down.
+/// The new output reserve is rounded up so the invariant never decreases.
+pub fn swap_output(input_reserve: u128, output_reserve: u128, net_input: u128) -> Result<u128> {
+    let invariant = input_reserve
+        .checked_mul(output_reserve)
+        .ok_or(AmmError::MathOverflow)?;
+    let new_output_reserve = mul_div_ceil(
+        invariant,
+        1,
+        input_reserve
+            .checked_add(net_input)
+            .ok_or(AmmError::MathOverflow)?,
+    )?;
+    output_reserve
+        .checked_sub(new_output_reserve)
+        .ok_or(error!(AmmError::MathOverflow))
+}
+
+/// Least net input (pool fee excluded) for which `swap_output` pays at least `output`
+pub fn swap_input(input_reserve: u128, output_reserve: u128, output: u128) -> Result<u128> {
+    require!(output < output_reserve, AmmError::OutputExceedsReserve);
+    let invariant = input_reserve
+        .checked_mul(output_reserve)
+        .ok_or(AmmError::MathOverflow)?;
+    let new_input_reserve = mul_div_ceil(invariant, 1, output_reserve - output)?;
+    Ok(new_input_reserve.saturating_sub(input_reserve))
+}
+
+/// Fail closed unless the reserves a swap left behind keep at least the
+/// pre-swap product `invariant`
+pub fn check_invariant(
+    invariant: u128,
+    input_reserve_after: u128,
+    output_reserve_after: u128,
+) -> Result<()> {
+    let post_swap_invariant = input_reserve_after
+        .checked_mul(output_reserve_after)
+        .ok_or(AmmError::MathOverflow)?;
+    require!(
+        post_swap_invariant >= invariant,
+        AmmError::InvariantViolated
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    const RESERVES: [(u128, u128, u128); 4] = [
+        (1_000, 1_000, 1_000),
+        (7, 1_000_003, 997),
+        (123_456_789, 987_654_321, 350_000_000),
+        (1 << 40, 3 << 20, 1 << 30),
+    ];
+
+    const AMOUNTS: [u128; 6] = [1, 2, 3, 999, 65_537, 10_000_000];
+
+    #[test]
+    fn mul_div_rounds_in_requested_direction() {
+        assert_eq!(mul_div_floor(7, 3, 2).unwrap(), 10);
+        assert_eq!(mul_div_ceil(7, 3, 2).unwrap(), 11);
+        assert_eq!(mul_div_floor(6, 3, 2).unwrap(), 9);
+        assert_eq!(mul_div_ceil(6, 3, 2).unwrap(), 9);
+        assert_eq!(
+            mul_div_floor(1, 1, 0).unwrap_err(),
+            AmmError::DivisionByZero.into()
+        );
+        assert!(mul_div_ceil(u128::MAX, 2, 1).is_err());
+    }
+
+    #[test]
+    fn sqrt_is_floor_of_exact_root() {
+        for value in [
+            0u128,
+            1,
+            2,
+            3,
+            4,
+            15,
+            16,
+            17,
+            1 << 64,
+            u64::MAX as u128,
+            u128::MAX,
+        ] {
+            let root = checked_sqrt(value).unwrap();
+            assert!(root * root <= value);
+            assert!((root + 1)
+                .checked_mul(root + 1)
+                .is_none_or(|square| square > value));
+        }
+    }
+
+    #[test]
+    fn normalization_round_trips_with_safe_rounding() {
+        assert_eq!(normalize(1_500_000, 6, 9).unwrap(), 1_500_000_000);
+        assert_eq!(normalize(7, 9, 9).unwrap(), 7);
+        assert!(normalize(1, 9, 6).is_err());
+        assert!(normalize(u128::MAX, 0, 1).is_err());
+
+        for amount in AMOUNTS {
+            let scaled = normalize(amount, 6, 9).unwrap();
+            assert_eq!(denormalize_floor(scaled, 6, 9).unwrap(), amount);
+            assert_eq!(denormalize_ceil(scaled, 6, 9).unwrap(), amount);
+            assert_eq!(denormalize_floor(scaled + 1, 6, 9).unwrap(), amount);
+            assert_eq!(denormalize_ceil(scaled + 1, 6, 9).unwrap(), amount + 1);
+        }
+    }
+
+    #[test]
+    fn lp_decimals_follow_the_pair() {
+        assert_eq!(lp_decimals(9, 9), 9);
+        assert_eq!(lp_decimals(6, 9), 7);
+        assert_eq!(lp_decimals(6, 6), 6);
+        assert_eq!(lp_decimals(0, 0), 0);
+        assert_eq!(lp_decimals(u8::MAX, u8::MAX), u8::MAX);
+    }
+
+    #[test]
+    fn deposit_then_withdraw_never_returns_more() {
+        for (reserve_a, reserve_b, lp_supply) in RESERVES {
+            for deposit_a in AMOUNTS {
+                let deposit_b = mul_div_ceil(deposit_a, reserve_b, reserve_a).unwrap();
+                let minted = mul_div_floor(deposit_a, lp_supply, reserve_a)
+                    .unwrap()
+                    .min(mul_div_floor(deposit_b, lp_supply, reserve_b).unwrap());
+                if minted == 0 {
+                    continue;
+                }
+
+                let withdrawn_a =
+                    mul_div_floor(minted, reserve_a + deposit_a, lp_supply + minted).unwrap();
+                let withdrawn_b =
+                    mul_div_floor(minted, reserve_b + deposit_b, lp_supply + minted).unwrap();
+                assert!(withdrawn_a <= deposit_a);
+                assert!(withdrawn_b <= deposit_b);
+            }
+        }
+    }
+
+    #[test]
+    fn invariant_check_rejects_a_smaller_product() {
+        assert!(check_invariant(1_000 * 1_000, 1_100, 910).is_ok());
+        assert!(check_invariant(1_000 * 1_000, 1_000, 1_000).is_ok());
+        assert_eq!(
+            check_invariant(1_000 * 1_000, 1_100, 909).unwrap_err(),
+            AmmError::InvariantViolated.into()
+        );
+        assert!(check_invariant(0, u128::MAX, 2).is_err());
+    }
+
+    #[test]
+    fn swap_round_trip_never_returns_more() {
+        for (reserve_a, reserve_b, _) in RESERVES {
+            for input in AMOUNTS {
+                let invariant = reserve_a * reserve_b;
+                let new_b = mul_div_ceil(invariant, 1, reserve_a + input).unwrap();
+                let output_b = reserve_b - new_b;
+
+                let invariant = (reserve_a + input) * new_b;
+                let new_a = mul_div_ceil(invariant, 1, new_b + output_b).unwrap();
+                let output_a = (reserve_a + input) - new_a;
+                assert!(output_a <= input);
+            }
+        }
+    }
+
+    // Curve invariants over the full u64 range of vault balances and amounts
+    proptest! {
+        #[test]
+        fn swap_never_decreases_invariant(
+            input_reserve in 1..=u64::MAX,
+            output_reserve in 1..=u64::MAX,
+            input in any::<u64>(),
+            fee_bps in 0..=10_000u128,
+        ) {
+            let (input_reserve, output_reserve, input) =
+                (input_reserve as u128, output_reserve as u128, input as u128);
+            let net_input = input - mul_div_floor(input, fee_bps, 10_000).unwrap();
+            let output = swap_output(input_reserve, output_reserve, net_input).unwrap();
+            prop_assert!(output < output_reserve);
+
+            // x * y >= k  <=>  y >= ceil(k / x), without widening past u128
+            let invariant = input_reserve * output_reserve;
+            prop_assert!(
+                output_reserve - output >= invariant.div_ceil(input_reserve + input)
+            );
+        }
+
+        #[test]
+        fn swap_input_is_least_input_for_output(
+            input_reserve in 1..=u64::MAX,
+            output_reserve in 1..=u64::MAX,
+            share in any::<u64>(),
+        ) {
+            let (input_reserve, output_reserve) = (input_reserve as u128, output_reserve as u128);
+            let output = mul_div_floor(output_reserve - 1, share as u128, u64::MAX as u128).unwrap();
+            let net_input = swap_input(input_reserve, output_reserve, output).unwrap();
+            prop_assert!(swap_output(input_reserve, output_reserve, net_input).unwrap() >= output);
+            if net_input > 0 {
+                prop_assert!(
+                    swap_output(input_reserve, output_reserve, net_input - 1).unwrap() < output
+                );
+            }
+        }
+
+        #[test]
+        fn deposit_then_withdraw_never_returns_more_value(
+            reserve_a in 1..=u64::MAX >> 1,
+            reserve_b in 1..=u64::MAX >> 1,
+            lp_supply in 1..=u64::MAX >> 1,
+            share in any::<u64>(),
+        ) {
+            // Deposits of up to the whole pool keep vault balances and LP supply within u64
+            let (reserve_a, reserve_b, lp_supply) =
+                (reserve_a as u128, reserve_b as u128, lp_supply as u128);
+            let deposit_a = mul_div_floor(reserve_a, share as u128, u64::MAX as u128)
+                .unwrap()
+                .max(1);
+            let deposit_b = mul_div_ceil(deposit_a, reserve_b, reserve_a).unwrap();
+            let minted = mul_div_floor(deposit_a, lp_supply, reserve_a)
+                .unwrap()
+                .min(mul_div_floor(deposit_b, lp_supply, reserve_b).unwrap());
+            prop_assume!(minted > 0);
+
+            let withdrawn_a =
+                mul_div_floor(minted, reserve_a + deposit_a, lp_supply + minted).unwrap();
+            let withdrawn_b =
+                mul_div_floor(minted, reserve_b + deposit_b, lp_supply + minted).unwrap();
+            prop_assert!(withdrawn_a <= deposit_a);
+            prop_assert!(withdrawn_b <= deposit_b);
+        }
+
+        #[test]
+        fn math_errors_instead_of_panicking(
+            a in any::<u64>(),
+            b in any::<u64>(),
+            c in any::<u64>(),
+            decimals in any::<u8>(),
+            common_decimals in any::<u8>(),
+        ) {
+            let (a, b, c) = (a as u128, b as u128, c as u128);
+            prop_assert_eq!(mul_div_floor(a, b, c).is_ok(), c > 0);
+            prop_assert_eq!(mul_div_ceil(a, b, c).is_ok(), c > 0);
+            prop_assert_eq!(swap_output(a, b, c).is_ok(), a + c > 0);
+            prop_assert!(checked_sqrt(a * b).is_ok());
+            let _ = normalize(a, decimals, common_decimals);
+            let _ = denormalize_floor(a, decimals, common_decimals);
+            let _ = denormalize_ceil(a, decimals, common_decimals);
+        }
+    }
+}
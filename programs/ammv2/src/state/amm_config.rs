@@ -0,0 +1,71 @@
+use anchor_lang::prelude::*;
+
+use crate::errors::AmmError;
+use crate::events::AmmConfigUpdated;
+
+/// Program-wide configuration, a singleton PDA
+#[account]
+#[derive(InitSpace)]
+pub struct AmmConfig {
+    /// Authority allowed to update the global configuration
+    pub authority: Pubkey,
+
+    /// Treasury PDA that collects protocol fees
+    pub treasury: Pubkey,
+
+    /// Lamports charged to the creator of every new pool (0 = free)
+    pub pool_creation_fee_lamports: u64,
+
+    /// Emergency kill switch: pool creation, deposits, swaps and staking are
+    /// rejected across every pool while set; withdrawals stay open
+    pub paused: bool,
+
+    /// Seconds a queued parameter change waits before it can be executed.
+    /// While nonzero, timelocked parameters can only change through the queue.
+    pub param_change_delay_secs: i64,
+
+    /// Highest share of a pool's swap fees its creator may take, in bps of the fee
+    pub max_creator_fee_bps: u16,
+
+    /// Refuse new pools over a mint with an active freeze authority
+    pub reject_freeze_authority: bool,
+}
+
+impl AmmConfig {
+    /// Current values, for the `AmmConfigUpdated` event
+    pub fn snapshot(&self) -> AmmConfigUpdated {
+        AmmConfigUpdated {
+            authority: self.authority,
+            treasury: self.treasury,
+            pool_creation_fee_lamports: self.pool_creation_fee_lamports,
+            paused: self.paused,
+            param_change_delay_secs: self.param_change_delay_secs,
+            max_creator_fee_bps: self.max_creator_fee_bps,
+            reject_freeze_authority: self.reject_freeze_authority,
+        }
+    }
+
+    /// Reject the operation while the program is paused
+    pub fn check_not_paused(&self) -> Result<()> {
+        require!(!self.paused, AmmError::ProgramPaused);
+        Ok(())
+    }
+
+    /// Reject a direct change of a timelocked parameter while the timelock is on
+    pub fn check_no_timelock(&self) -> Result<()> {
+        require!(
+            self.param_change_delay_secs == 0,
+            AmmError::TimelockRequired
+        );
+        Ok(())
+    }
+}
+
+/// Config authority's approval to pool a mint with a permanent delegate or
+/// close authority, which `initialize_pool` rejects otherwise
+#[account]
+#[derive(InitSpace)]
+pub struct UnsafeMintApproval {
+    /// Approved mint
+    pub mint: Pubkey,
+}
@@ -0,0 +1,75 @@
+use anchor_lang::prelude::*;
+
+/// Maximum keeper tip per executed DCA tranche or filled limit order
+pub const MAX_KEEPER_TIP_LAMPORTS: u64 = 1_000_000;
+
+/// Recurring swap schedule: escrowed input is swapped through the pool one
+/// tranche per interval by permissionless keepers
+#[account]
+#[derive(InitSpace)]
+pub struct DcaPosition {
+    /// Pool the tranches are swapped through
+    pub liquidity_pool: Pubkey,
+
+    /// Wallet that funded the schedule and receives the output
+    pub owner: Pubkey,
+
+    /// Token sold each interval
+    pub input_mint: Pubkey,
+
+    /// Token bought each interval
+    pub output_mint: Pubkey,
+
+    /// Input swapped per tranche (the last tranche may be smaller)
+    pub amount_per_interval: u64,
+
+    /// Seconds between tranches
+    pub interval_secs: i64,
+
+    /// Earliest time the next tranche may execute
+    pub next_execution_timestamp: i64,
+
+    /// Minimum output a full tranche must return (0 = no minimum)
+    pub min_output_per_interval: u64,
+
+    /// Lamports paid to the keeper of each tranche, escrowed on this account
+    pub keeper_tip_lamports: u64,
+
+    /// Index distinguishing schedules of the same owner on one pool
+    pub dca_id: u16,
+
+    /// Canonical bump of the position PDA
+    pub bump: u8,
+}
+
+impl DcaPosition {
+    /// Seed material for signing as this position
+    pub fn signer(&self) -> DcaSigner {
+        DcaSigner {
+            liquidity_pool: self.liquidity_pool,
+            owner: self.owner,
+            dca_id: self.dca_id.to_le_bytes(),
+            bump: [self.bump],
+        }
+    }
+}
+
+/// Owned seed material for `invoke_signed` as a DCA position PDA
+pub struct DcaSigner {
+    liquidity_pool: Pubkey,
+    owner: Pubkey,
+    dca_id: [u8; 2],
+    bump: [u8; 1],
+}
+
+impl DcaSigner {
+    pub fn seeds(&self) -> [&[u8]; 5] {
+        [
+            b"dca",
+            self.liquidity_pool.as_ref(),
+            self.owner.as_ref(),
+            &self.dca_id,
+            &self.bump,
+        ]
+    }
+}
@@ -0,0 +1,213 @@
+use anchor_lang::prelude::*;
+
+use crate::errors::AmmError;
+use crate::math;
+use crate::state::BASIS_POINTS_DIVISOR;
+
+/// Fixed-point scale of a farm's accumulated reward per staked LP token
+pub const REWARD_PER_SHARE_PRECISION: u128 = 1_000_000_000_000;
+
+/// Maximum number of concurrent reward streams per farm
+pub const MAX_FARM_REWARDS: usize = 4;
+
+/// Farm lock tiers as (lock duration in seconds, reward boost in bps).
+/// Tier 0 is unlocked with no boost.
+pub const FARM_LOCK_TIERS: [(i64, u16); 4] = [
+    (0, 10_000),
+    (7 * 86_400, 12_500),
+    (28 * 86_400, 15_000),
+    (84 * 86_400, 25_000),
+];
+
+/// One reward stream of a farm
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Copy, Default)]
+pub struct FarmReward {
+    /// Token paid out by this stream (default = unused slot)
+    pub reward_mint: Pubkey,
+
+    /// Reward tokens emitted per second, shared by all stakers
+    pub reward_per_second: u64,
+
+    /// Rewards accumulated per staked LP token, scaled by `REWARD_PER_SHARE_PRECISION`
+    pub reward_per_share: u128,
+}
+
+impl FarmReward {
+    /// Whether this slot holds a reward stream
+    pub fn is_active(&self) -> bool {
+        self.reward_mint != Pubkey::default()
+    }
+}
+
+/// LP staking farm of one pool, emitting up to `MAX_FARM_REWARDS` reward tokens
+/// at fixed rates per second
+#[account]
+#[derive(Default, InitSpace)]
+pub struct Farm {
+    /// Pool whose LP tokens are staked
+    pub liquidity_pool: Pubkey,
+
+    /// Unix timestamp up to which every reward stream is accrued
+    pub last_update_timestamp: i64,
+
+    /// Total LP tokens staked in the farm
+    pub total_staked: u64,
+
+    /// Total boosted stake; rewards are shared in proportion to boosted stake
+    pub total_boosted: u64,
+
+    /// Canonical bump of the farm PDA
+    pub bump: u8,
+
+    /// Reward streams, each with its own vault
+    pub rewards: [FarmReward; MAX_FARM_REWARDS],
+}
+
+impl Farm {
+    /// Accrue emissions since the last update into every stream's `reward_per_share`.
+    /// Nothing accrues while the farm is empty.
+    pub fn accrue(&mut self, now: i64) -> Result<()> {
+        if now <= self.last_update_timestamp {
+            return Ok(());
+        }
+
+        if self.total_boosted > 0 {
+            let elapsed = (now - self.last_update_timestamp) as u128;
+            for reward in self.rewards.iter_mut().filter(|reward| reward.is_active()) {
+                let emitted = elapsed
+                    .checked_mul(reward.reward_per_second as u128)
+                    .ok_or(AmmError::MathOverflow)?;
+                reward.reward_per_share = reward
+                    .reward_per_share
+                    .checked_add(math::mul_div_floor(
+                        emitted,
+                        REWARD_PER_SHARE_PRECISION,
+                        self.total_boosted as u128,
+                    )?)
+                    .ok_or(AmmError::MathOverflow)?;
+            }
+        }
+        self.last_update_timestamp = now;
+        Ok(())
+    }
+
+    /// Rewards of stream `index` accumulated by `boosted_amount` of stake since it started
+    pub fn accumulated(&self, index: usize, boosted_amount: u64) -> Result<u128> {
+        math::mul_div_floor(
+            boosted_amount as u128,
+            self.rewards[index].reward_per_share,
+            REWARD_PER_SHARE_PRECISION,
+        )
+    }
+
+    /// Index of the active stream paying `reward_mint`
+    pub fn reward_index(&self, reward_mint: &Pubkey) -> Result<usize> {
+        self.rewards
+            .iter()
+            .position(|reward| reward.is_active() && reward.reward_mint == *reward_mint)
+            .ok_or(error!(AmmError::UnknownFarmReward))
+    }
+
+    /// Seed material for signing as this farm
+    pub fn signer(&self) -> FarmSigner {
+        FarmSigner {
+            liquidity_pool: self.liquidity_pool,
+            bump: [self.bump],
+        }
+    }
+}
+
+/// Owned seed material for `invoke_signed` as a farm PDA
+pub struct FarmSigner {
+    liquidity_pool: Pubkey,
+    bump: [u8; 1],
+}
+
+impl FarmSigner {
+    pub fn seeds(&self) -> [&[u8]; 3] {
+        [b"farm", self.liquidity_pool.as_ref(), &self.bump]
+    }
+}
+
+/// One wallet's staked LP tokens in a farm
+#[account]
+#[derive(Default, InitSpace)]
+pub struct StakePosition {
+    /// Farm the LP tokens are staked in
+    pub farm: Pubkey,
+
+    /// Wallet that staked and may unstake and claim
+    pub owner: Pubkey,
+
+    /// LP tokens staked
+    pub amount: u64,
+
+    /// Staked amount weighted by `boost_bps`, the position's share of rewards
+    pub boosted_amount: u64,
+
+    /// Reward boost of the position's lock tier, in bps (10_000 = 1x)
+    pub boost_bps: u16,
+
+    /// Unix timestamp before which the position cannot be unstaked
+    pub lock_end_timestamp: i64,
+
+    /// Per stream, `Farm::accumulated` at the last settlement, already accounted for
+    pub reward_debts: [u128; MAX_FARM_REWARDS],
+
+    /// Per stream, settled rewards not yet claimed
+    pub pending_rewards: [u64; MAX_FARM_REWARDS],
+}
+
+impl StakePosition {
+    /// Boost in effect at `now`; it falls back to 1x once the lock has ended
+    pub fn effective_boost_bps(&self, now: i64) -> u16 {
+        if now < self.lock_end_timestamp {
+            self.boost_bps
+        } else {
+            FARM_LOCK_TIERS[0].1
+        }
+    }
+
+    /// Move rewards earned since the last settlement into `pending_rewards`.
+    /// The farm must be accrued first.
+    pub fn settle(&mut self, farm: &Farm) -> Result<()> {
+        for index in 0..MAX_FARM_REWARDS {
+            let accumulated = farm.accumulated(index, self.boosted_amount)?;
+            let earned = math::to_u64(accumulated.saturating_sub(self.reward_debts[index]))?;
+            self.pending_rewards[index] = self.pending_rewards[index]
+                .checked_add(earned)
+                .ok_or(AmmError::MathOverflow)?;
+            self.reward_debts[index] = accumulated;
+        }
+        Ok(())
+    }
+
+    /// Change the staked amount and boost, keeping the farm totals in sync.
+    /// The position must be settled first.
+    pub fn update_stake(&mut self, farm: &mut Farm, amount: u64, boost_bps: u16) -> Result<()> {
+        let boosted_amount = math::to_u64(math::mul_div_floor(
+            amount as u128,
+            boost_bps as u128,
+            BASIS_POINTS_DIVISOR,
+        )?)?;
+
+        farm.total_staked = farm
+            .total_staked
+            .checked_sub(self.amount)
+            .and_then(|total| total.checked_add(amount))
+            .ok_or(AmmError::MathOverflow)?;
+        farm.total_boosted = farm
+            .total_boosted
+            .checked_sub(self.boosted_amount)
+            .and_then(|total| total.checked_add(boosted_amount))
+            .ok_or(AmmError::MathOverflow)?;
+
+        self.amount = amount;
+        self.boosted_amount = boosted_amount;
+        self.boost_bps = boost_bps;
+        for index in 0..MAX_FARM_REWARDS {
+            self.reward_debts[index] = farm.accumulated(index, boosted_amount)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,123 @@
+use anchor_lang::prelude::*;
+
+/// Singleton directing one protocol emission stream across farms by epoch votes
+#[account]
+#[derive(InitSpace)]
+pub struct GaugeController {
+    /// Governance token wallets escrow in their `GaugeVoter` to vote
+    pub governance_mint: Pubkey,
+
+    /// Reward mint of the farm streams the controller sets rates for
+    pub reward_mint: Pubkey,
+
+    /// Emissions per second split across gauges in proportion to their votes
+    pub emission_per_second: u64,
+
+    /// Length of a voting epoch in seconds
+    pub epoch_duration_secs: i64,
+
+    /// Current voting epoch
+    pub epoch: u64,
+
+    /// Unix timestamp the current epoch started at
+    pub epoch_start_timestamp: i64,
+
+    /// Votes cast in the current epoch across all gauges
+    pub epoch_total_votes: u64,
+
+    /// Votes cast in the previous epoch, the basis of current rates
+    pub previous_epoch_total_votes: u64,
+}
+
+impl GaugeController {
+    /// Whether the current epoch has run its full duration at `now`
+    pub fn epoch_ended(&self, now: i64) -> bool {
+        now >= self
+            .epoch_start_timestamp
+            .saturating_add(self.epoch_duration_secs)
+    }
+}
+
+/// One farm's share of the gauge controller's emissions
+#[account]
+#[derive(InitSpace)]
+pub struct Gauge {
+    /// Farm whose stream rate this gauge sets
+    pub farm: Pubkey,
+
+    /// Epoch `votes` were cast in
+    pub votes_epoch: u64,
+
+    /// Votes cast for this gauge in `votes_epoch`
+    pub votes: u64,
+
+    /// Votes cast for this gauge in the epoch before the controller's current one
+    pub previous_votes: u64,
+
+    /// Last controller epoch whose rate was applied to the farm
+    pub checkpointed_epoch: u64,
+}
+
+impl Gauge {
+    /// Roll the vote tally forward to the controller's current epoch
+    pub fn roll(&mut self, current_epoch: u64) {
+        if self.votes_epoch < current_epoch {
+            self.previous_votes = if self.votes_epoch + 1 == current_epoch {
+                self.votes
+            } else {
+                0
+            };
+            self.votes = 0;
+            self.votes_epoch = current_epoch;
+        }
+    }
+}
+
+/// Governance tokens a wallet has escrowed to vote with, and the voting power
+/// it has already spent in an epoch. Tokens that voted stay escrowed until
+/// that epoch has ended, so they cannot be moved to another wallet and vote twice.
+#[account]
+#[derive(InitSpace)]
+pub struct GaugeVoter {
+    /// Wallet casting the votes
+    pub voter: Pubkey,
+
+    /// Epoch `used_votes` refers to
+    pub epoch: u64,
+
+    /// Votes cast across all gauges in `epoch`
+    pub used_votes: u64,
+
+    /// Governance tokens held in the voter's vault, its voting power
+    pub locked: u64,
+
+    /// Canonical bump of the voter PDA
+    pub bump: u8,
+}
+
+impl GaugeVoter {
+    /// Whether the escrowed tokens voted in `current_epoch` and must stay locked
+    pub fn voted_in(&self, current_epoch: u64) -> bool {
+        self.epoch == current_epoch && self.used_votes > 0
+    }
+
+    /// Seed material for signing as this voter
+    pub fn signer(&self) -> GaugeVoterSigner {
+        GaugeVoterSigner {
+            voter: self.voter,
+            bump: [self.bump],
+        }
+    }
+}
+
+/// Owned seed material for `invoke_signed` as a gauge voter PDA
+pub struct GaugeVoterSigner {
+    voter: Pubkey,
+    bump: [u8; 1],
+}
+
+impl GaugeVoterSigner {
+    pub fn seeds(&self) -> [&[u8]; 3] {
+        [b"gauge_voter", self.voter.as_ref(), &self.bump]
+    }
+}
@@ -0,0 +1,102 @@
+use anchor_lang::prelude::*;
+
+use crate::errors::AmmError;
+use crate::math;
+
+/// Launch of a token on a virtual-reserve bonding curve. Buyers pay in the
+/// quote token until `target_raise` is met; `graduate` then seeds a
+/// constant-product pool with the raise.
+#[account]
+#[derive(InitSpace)]
+pub struct LaunchPool {
+    /// Wallet that created the launch, admin of the graduated pool
+    pub creator: Pubkey,
+
+    /// Token being launched
+    pub token_mint: Pubkey,
+
+    /// Token buyers pay in
+    pub quote_mint: Pubkey,
+
+    /// Pool the launch graduated into (default = still on the curve)
+    pub liquidity_pool: Pubkey,
+
+    /// Quote reserve the curve starts from; sets the opening price with `token_reserve`
+    pub virtual_quote_reserve: u64,
+
+    /// Tokens left on the curve
+    pub token_reserve: u64,
+
+    /// Quote paid in so far
+    pub quote_raised: u64,
+
+    /// Quote raise at which buys stop and the launch can graduate
+    pub target_raise: u64,
+
+    /// Fee numerator of the graduated pool
+    pub fee_numerator: u64,
+
+    /// Fee denominator of the graduated pool
+    pub fee_denominator: u64,
+
+    /// Canonical bump of the launch PDA
+    pub bump: u8,
+}
+
+impl LaunchPool {
+    /// Whether the launch has seeded its pool
+    pub fn graduated(&self) -> bool {
+        self.liquidity_pool != Pubkey::default()
+    }
+
+    /// Price a buy of up to `quote_amount` against the curve, trimmed to what
+    /// is left of the target raise. Returns (quote used, tokens out).
+    pub fn buy_quote(&self, quote_amount: u64) -> Result<(u64, u64)> {
+        require!(!self.graduated(), AmmError::LaunchGraduated);
+        let quote_used = quote_amount.min(self.target_raise.saturating_sub(self.quote_raised));
+        require!(quote_used > 0, AmmError::LaunchTargetReached);
+
+        let quote_reserve = (self.virtual_quote_reserve as u128)
+            .checked_add(self.quote_raised as u128)
+            .ok_or(AmmError::MathOverflow)?;
+        let tokens_out = math::to_u64(math::swap_output(
+            quote_reserve,
+            self.token_reserve as u128,
+            quote_used as u128,
+        )?)?;
+        require!(tokens_out > 0, AmmError::OutputTooSmall);
+        Ok((quote_used, tokens_out))
+    }
+
+    /// Tokens that pair with the raise at the curve's closing price, rounded down
+    pub fn graduation_token_amount(&self) -> Result<u64> {
+        let quote_reserve = (self.virtual_quote_reserve as u128)
+            .checked_add(self.quote_raised as u128)
+            .ok_or(AmmError::MathOverflow)?;
+        math::to_u64(math::mul_div_floor(
+            self.quote_raised as u128,
+            self.token_reserve as u128,
+            quote_reserve,
+        )?)
+    }
+
+    /// Seed material for signing as this launch
+    pub fn signer(&self) -> LaunchPoolSigner {
+        LaunchPoolSigner {
+            token_mint: self.token_mint,
+            bump: [self.bump],
+        }
+    }
+}
+
+/// Owned seed material for `invoke_signed` as a launch pool PDA
+pub struct LaunchPoolSigner {
+    token_mint: Pubkey,
+    bump: [u8; 1],
+}
+
+impl LaunchPoolSigner {
+    pub fn seeds(&self) -> [&[u8]; 3] {
+        [b"launch_pool", self.token_mint.as_ref(), &self.bump]
+    }
+}
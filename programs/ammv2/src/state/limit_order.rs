@@ -0,0 +1,69 @@
+use anchor_lang::prelude::*;
+
+/// Input escrowed to be swapped in full once the pool pays at least
+/// `min_output_amount`, filled by any keeper before expiry
+#[account]
+#[derive(InitSpace)]
+pub struct LimitOrder {
+    /// Pool the order fills against
+    pub liquidity_pool: Pubkey,
+
+    /// Wallet that placed the order and receives the output
+    pub owner: Pubkey,
+
+    /// Token sold
+    pub input_mint: Pubkey,
+
+    /// Token bought
+    pub output_mint: Pubkey,
+
+    /// Input swapped when the order fills
+    pub input_amount: u64,
+
+    /// Least output the fill must return; sets the limit price
+    pub min_output_amount: u64,
+
+    /// Unix timestamp after which the order can no longer fill
+    pub expiry_timestamp: i64,
+
+    /// Lamports paid to the keeper that fills the order, escrowed on this account
+    pub keeper_tip_lamports: u64,
+
+    /// Index distinguishing orders of the same owner on one pool
+    pub order_id: u64,
+
+    /// Canonical bump of the order PDA
+    pub bump: u8,
+}
+
+impl LimitOrder {
+    /// Seed material for signing as this order
+    pub fn signer(&self) -> LimitOrderSigner {
+        LimitOrderSigner {
+            liquidity_pool: self.liquidity_pool,
+            owner: self.owner,
+            order_id: self.order_id.to_le_bytes(),
+            bump: [self.bump],
+        }
+    }
+}
+
+/// Owned seed material for `invoke_signed` as a limit order PDA
+pub struct LimitOrderSigner {
+    liquidity_pool: Pubkey,
+    owner: Pubkey,
+    order_id: [u8; 8],
+    bump: [u8; 1],
+}
+
+impl LimitOrderSigner {
+    pub fn seeds(&self) -> [&[u8]; 5] {
+        [
+            b"limit_order",
+            self.liquidity_pool.as_ref(),
+            self.owner.as_ref(),
+            &self.order_id,
+            &self.bump,
+        ]
+    }
+}
@@ -0,0 +1,1043 @@
+use anchor_lang::prelude::*;
+
+use crate::errors::AmmError;
+use crate::events::PoolParamsSnapshot;
+use crate::fixed_point::{self, Q64x64};
+use crate::math;
+use crate::state::{LiquidityPosition, PriceOracle};
+
+/// Fixed-point scale of `LiquidityPool::fee_growth_per_lp`
+pub const FEE_GROWTH_PRECISION: u128 = 1_000_000_000_000;
+
+/// Denominator for all basis point parameters
+pub const BASIS_POINTS_DIVISOR: u128 = 10_000;
+
+/// Highest swap fee any pool may charge, in bps (10%)
+pub const MAX_FEE_BPS: u128 = 1_000;
+
+/// Highest JIT withdrawal penalty any pool may charge, in bps (10%)
+pub const MAX_JIT_PENALTY_BPS: u16 = 1_000;
+
+/// Layout version written to newly created pools
+pub const LIQUIDITY_POOL_VERSION: u8 = 11;
+
+/// Oracle intervals the dynamic fee measures realized volatility over
+pub const DYNAMIC_FEE_VOLATILITY_SAMPLES: usize = 16;
+
+/// `LiquidityPool::disabled_operations` bit rejecting swaps
+pub const POOL_OP_SWAP: u16 = 1 << 0;
+
+/// `LiquidityPool::disabled_operations` bit rejecting deposits
+pub const POOL_OP_DEPOSIT: u16 = 1 << 1;
+
+/// Every operation bit the admin may set; withdrawals cannot be disabled
+pub const POOL_OPS_ALL: u16 = POOL_OP_SWAP | POOL_OP_DEPOSIT;
+
+/// Stores the state of a liquidity pool.
+/// Zero-copy: fields are grouped by alignment so the layout has no implicit padding.
+#[account(zero_copy)]
+#[derive(InitSpace)]
+pub struct LiquidityPool {
+    /// Spot price at the first swap of `price_reference_slot`, as Q64.64 bits
+    pub slot_open_price: u128,
+
+    /// Authority allowed to update pool configuration
+    pub admin: Pubkey,
+
+    /// Mint of token A in the trading pair
+    pub token_a_mint: Pubkey,
+
+    /// Mint of token B in the trading pair
+    pub token_b_mint: Pubkey,
+
+    /// Price oracle account (default = pool predates oracles and has none yet)
+    pub oracle: Pubkey,
+
+    /// Total LP tokens minted to all liquidity providers
+    pub total_lp_tokens_issued: u64,
+
+    /// Numerator for fee calculation (e.g., 3 for 0.3% with denominator 1000)
+    pub fee_numerator: u64,
+
+    /// Denominator for fee calculation (e.g., 1000 for 0.3% fee)
+    pub fee_denominator: u64,
+
+    /// Slot in which `slot_open_price` was recorded
+    pub price_reference_slot: u64,
+
+    /// First epoch in which the next state digest may be exported
+    pub next_digest_epoch: u64,
+
+    /// Slot at which launch protection ends
+    pub launch_protection_end_slot: u64,
+
+    /// Minimum slots between swaps from one wallet during launch protection (0 = disabled)
+    pub launch_trade_cooldown_slots: u64,
+
+    /// Unix timestamp at which withdraw-only mode was entered
+    pub withdraw_only_since: i64,
+
+    /// Seconds large positions must wait after withdraw-only mode starts
+    pub large_withdrawal_delay_secs: i64,
+
+    /// LP balances below this skip the large-withdrawal timelock (0 = no carve-out)
+    pub small_lp_withdrawal_threshold: u64,
+
+    /// Oracle TWAP window the deviation guard compares against
+    pub deviation_twap_window_secs: i64,
+
+    /// Maximum spot price change allowed within a single slot (0 = disabled)
+    pub max_price_change_bps_per_slot: u16,
+
+    /// Maximum input a single trader may swap per slot, in bps of the input reserve (0 = disabled)
+    pub max_trader_slot_volume_bps: u16,
+
+    /// Maximum swap input in bps of the input reserve (0 = disabled)
+    pub max_trade_bps_of_reserves: u16,
+
+    /// Reject swaps leaving spot further than this from the oracle TWAP (0 = disabled)
+    pub max_oracle_deviation_bps: u16,
+
+    /// Deviation from the TWAP above which a depeg incident is flagged (0 = disabled)
+    pub incident_deviation_bps: u16,
+
+    /// Extra input charged into the insurance fund during an incident
+    pub incident_surcharge_bps: u16,
+
+    /// Reject opposite-direction trades from the same trader within one slot (0/1)
+    pub block_same_slot_reversal: u8,
+
+    /// Emergency mode: swaps and deposits are rejected, withdrawals stay open (0/1)
+    pub withdraw_only: u8,
+
+    /// Whether the most recent swap happened during a flagged deviation incident (0/1)
+    pub deviation_incident_active: u8,
+
+    /// Layout version of this account
+    pub version: u8,
+
+    /// `VerificationLevel` assigned by the admin, for routers and UIs
+    pub verification_level: u8,
+
+    /// Pads the fixed fields to a multiple of 16 bytes
+    pub _padding: [u8; 7],
+
+    /// Minimum token A the first deposit must provide (0 = no minimum)
+    pub min_initial_deposit_a: u64,
+
+    /// Minimum token B the first deposit must provide (0 = no minimum)
+    pub min_initial_deposit_b: u64,
+
+    /// Pool token the protocol fee share is bought back into and burned (default = disabled)
+    pub buyback_mint: Pubkey,
+
+    /// Share of each swap fee taken for the protocol, in bps of the fee (0 = all to LPs)
+    pub protocol_fee_share_bps: u16,
+
+    /// Keeps the fields below 8-byte aligned
+    pub _padding_2: [u8; 6],
+
+    /// Token A account receiving the protocol fee share (default = protocol fee vault)
+    pub fee_recipient_a: Pubkey,
+
+    /// Token B account receiving the protocol fee share (default = protocol fee vault)
+    pub fee_recipient_b: Pubkey,
+
+    /// `POOL_OP_*` bits of the operations the admin has switched off
+    pub disabled_operations: u16,
+
+    /// Keeps the fields below 8-byte aligned
+    pub _padding_3: [u8; 6],
+
+    /// Token A the pool accounts for; vault balance above this can be skimmed
+    pub reserve_a: u64,
+
+    /// Token B the pool accounts for; vault balance above this can be skimmed
+    pub reserve_b: u64,
+
+    /// Only wallets with an `LpWhitelistEntry` may deposit (0/1)
+    pub lp_whitelist_enabled: u8,
+
+    /// Only wallets with a `LaunchWhitelistEntry` may swap before
+    /// `launch_protection_end_slot` (0/1)
+    pub launch_whitelist_only: u8,
+
+    /// Canonical bump of the pool authority PDA
+    pub authority_bump: u8,
+
+    /// Canonical bump of the token A vault PDA
+    pub token_a_vault_bump: u8,
+
+    /// Canonical bump of the token B vault PDA
+    pub token_b_vault_bump: u8,
+
+    /// Canonical bump of the LP token mint PDA
+    pub lp_token_mint_bump: u8,
+
+    /// Share of a withdrawal kept by the pool when it falls within
+    /// `jit_window_slots` of the position's last deposit, at most `MAX_JIT_PENALTY_BPS`
+    pub jit_penalty_bps: u16,
+
+    /// Sequence number of the last event this pool emitted
+    pub event_seq: u64,
+
+    /// Pool liquidity, `sqrt(reserve_a * reserve_b)`, per LP token, scaled by
+    /// `FEE_GROWTH_PRECISION`. Anything that adds reserves without minting LP
+    /// raises it: swap fees, donations, JIT penalties and deployed insurance.
+    /// Positions claim all of it as fees.
+    pub fee_growth_per_lp: u128,
+
+    /// Slots after a deposit during which withdrawals pay the JIT penalty
+    /// (0 disables it)
+    pub jit_window_slots: u64,
+
+    /// Seconds after a deposit during which the position cannot withdraw
+    /// (0 disables the cooldown)
+    pub withdraw_cooldown_secs: i64,
+
+    /// LP tokens the creator locked in its `LpLock` at pool creation (0 = none)
+    pub creator_locked_lp: u64,
+
+    /// Unix timestamp until which the creator's initial LP tokens are locked
+    pub creator_lock_until: i64,
+
+    /// Token A paid from the keeper tip budget to whoever checkpoints a stale oracle (0 = none)
+    pub oracle_checkpoint_reward: u64,
+
+    /// Lowest swap fee of the dynamic fee mode, in bps
+    pub dynamic_fee_min_bps: u16,
+
+    /// Highest swap fee of the dynamic fee mode, in bps (0 = fixed fee)
+    pub dynamic_fee_max_bps: u16,
+
+    /// Realized volatility, in bps, at which the dynamic fee reaches its maximum
+    pub dynamic_fee_volatility_bps: u16,
+
+    /// Swap fee currently charged in dynamic fee mode, in bps
+    pub dynamic_fee_bps: u16,
+
+    /// SPL Governance realm whose governance account is the admin (default = plain key)
+    pub admin_realm: Pubkey,
+
+    /// Wallet that initialized the pool
+    pub creator: Pubkey,
+
+    /// Token A account receiving the creator's fee share
+    pub creator_fee_account_a: Pubkey,
+
+    /// Token B account receiving the creator's fee share
+    pub creator_fee_account_b: Pubkey,
+
+    /// Share of each swap fee paid to the creator, in bps of the fee (0 = none)
+    pub creator_fee_bps: u16,
+
+    /// Largest price impact a single swap may have, in bps of the pre-trade
+    /// spot price (0 = unlimited)
+    pub max_price_impact_bps: u16,
+
+    /// Share of each swap fee accrued to the swap's referrer, if it names one,
+    /// in bps of the fee (0 = none)
+    pub referral_fee_bps: u16,
+
+    /// Share of each token A protocol fee paid into the protocol fee vault
+    /// that is set aside as keeper tip budget, in bps of the protocol fee
+    pub keeper_tip_share_bps: u16,
+
+    /// Keeps the layout a multiple of 16 bytes
+    pub _padding_4: [u8; 8],
+
+    /// Charge swap fees in the output token instead of the input token (0/1)
+    pub fee_on_output: u8,
+
+    /// Token A had a freeze authority when the pool was created, so its vault
+    /// can be frozen (0/1; zero for pools created before it was recorded)
+    pub token_a_freezable: u8,
+
+    /// Token B had a freeze authority when the pool was created (0/1)
+    pub token_b_freezable: u8,
+
+    /// The pool has a volume stats account, which every swap must then update (0/1)
+    pub has_volume_stats: u8,
+
+    /// The oracle accumulates Q64.64 prices (0/1). Oracles from before the
+    /// switch are reset on their next observation.
+    pub oracle_q64: u8,
+
+    /// Keeps the fields below 8-byte aligned
+    pub _padding_5: [u8; 3],
+
+    /// Token A paid from the keeper tip budget to the caller of a rewarded
+    /// crank, e.g. a DCA tranche or limit order fill (0 = none)
+    pub keeper_tip: u64,
+
+    /// Token A held in the protocol fee vault for keeper tips. Protocol fee
+    /// payouts leave it in the vault.
+    pub keeper_tip_budget: u64,
+
+    /// Space for future fields without reallocating every pool
+    pub _reserved: [u8; 8],
+}
+
+/// Canonical bumps of a pool's PDAs, stored so account constraints skip the bump search
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PoolBumps {
+    pub authority: u8,
+    pub token_a_vault: u8,
+    pub token_b_vault: u8,
+    pub lp_token_mint: u8,
+}
+
+// `INIT_SPACE` sizes the account, so the zero-copy layout must not add hidden padding
+const _: () = assert!(LiquidityPool::INIT_SPACE == std::mem::size_of::<LiquidityPool>());
+
+impl LiquidityPool {
+    /// Step an older layout up to `LIQUIDITY_POOL_VERSION`, backfilling defaults
+    /// for fields introduced along the way. Returns the version migrated from.
+    pub fn migrate(&mut self, vault_a_balance: u64, vault_b_balance: u64, bumps: PoolBumps) -> u8 {
+        let previous_version = self.version;
+        // Version 1: versioned layout; every field it introduced defaults to zero
+        // Version 2: tracked reserves, starting from the current vault balances
+        if previous_version < 2 {
+            self.sync_reserves(vault_a_balance, vault_b_balance);
+        }
+        // Version 3: stored PDA bumps
+        if previous_version < 3 {
+            self.set_bumps(bumps);
+        }
+        // Version 4: event sequence numbers, counting from zero
+        // Version 5: fee growth, starting from the current liquidity per LP
+        if previous_version < 5 {
+            self.update_fee_growth();
+        }
+        // Version 6: withdrawal cooldown, off until the admin sets it
+        // Version 7: creator LP lock record, empty for pools created before it
+        // Version 8: dynamic fees, off until the admin enables them
+        // Version 9: governance admin realm, unset for pools with a plain admin key
+        // Version 10: creator fee share, off and with no recorded creator for older pools
+        // Version 11: keeper tips, off and with an empty budget for older pools
+        self.version = LIQUIDITY_POOL_VERSION;
+        previous_version
+    }
+
+    /// Sequence number for the next event this pool emits. Numbers are
+    /// consecutive, so indexers can spot events they missed.
+    pub fn next_event_seq(&mut self) -> u64 {
+        self.event_seq = self.event_seq.wrapping_add(1);
+        self.event_seq
+    }
+
+    /// Record the canonical bumps of the pool's PDAs
+    pub fn set_bumps(&mut self, bumps: PoolBumps) {
+        self.authority_bump = bumps.authority;
+        self.token_a_vault_bump = bumps.token_a_vault;
+        self.token_b_vault_bump = bumps.token_b_vault;
+        self.lp_token_mint_bump = bumps.lp_token_mint;
+    }
+
+    /// Record the vault balances after tokens moved as the pool's reserves
+    pub fn sync_reserves(&mut self, vault_a_balance: u64, vault_b_balance: u64) {
+        self.reserve_a = vault_a_balance;
+        self.reserve_b = vault_b_balance;
+        self.update_fee_growth();
+    }
+
+    /// Raise `fee_growth_per_lp` to the pool's current liquidity per LP token.
+    /// Deposit and withdrawal rounding can nudge the ratio by a unit either way,
+    /// so the accumulator only ever moves up.
+    pub fn update_fee_growth(&mut self) {
+        // sqrt(u64 * u64) * FEE_GROWTH_PRECISION stays far below u128::MAX;
+        // the only error left is an empty LP supply, which has nothing to track
+        let per_lp = math::checked_sqrt(self.reserve_a as u128 * self.reserve_b as u128).and_then(
+            |liquidity| {
+                math::mul_div_floor(
+                    liquidity,
+                    FEE_GROWTH_PRECISION,
+                    self.total_lp_tokens_issued as u128,
+                )
+            },
+        );
+        if let Ok(per_lp) = per_lp {
+            self.fee_growth_per_lp = self.fee_growth_per_lp.max(per_lp);
+        }
+    }
+
+    /// Reject a fixed swap fee of `fee_numerator / fee_denominator` that is not
+    /// a fraction below one or that exceeds `MAX_FEE_BPS`
+    pub fn validate_fee(fee_numerator: u64, fee_denominator: u64) -> Result<()> {
+        require!(
+            fee_denominator > 0 && fee_numerator < fee_denominator,
+            AmmError::InvalidFeeParameters
+        );
+        // Both sides fit a u128 with room to spare
+        require!(
+            fee_numerator as u128 * BASIS_POINTS_DIVISOR <= MAX_FEE_BPS * fee_denominator as u128,
+            AmmError::FeeTooHigh
+        );
+        Ok(())
+    }
+
+    /// Reject dynamic fee bounds with the minimum above the maximum, no
+    /// volatility scale, or a maximum above `MAX_FEE_BPS`. A zero maximum,
+    /// returning to the fixed fee, is always valid.
+    pub fn validate_dynamic_fee(min_bps: u16, max_bps: u16, volatility_bps: u16) -> Result<()> {
+        if max_bps > 0 {
+            require!(
+                min_bps <= max_bps && volatility_bps > 0,
+                AmmError::InvalidFeeParameters
+            );
+            require!((max_bps as u128) <= MAX_FEE_BPS, AmmError::FeeTooHigh);
+        }
+        Ok(())
+    }
+
+    /// Reject a negative TWAP window, bps above 100%, or an incident band not
+    /// strictly inside the hard deviation limit
+    pub fn validate_deviation_guard(
+        twap_window_secs: i64,
+        max_deviation_bps: u16,
+        incident_deviation_bps: u16,
+        incident_surcharge_bps: u16,
+    ) -> Result<()> {
+        require!(
+            twap_window_secs >= 0
+                && (max_deviation_bps as u128) <= BASIS_POINTS_DIVISOR
+                && (incident_deviation_bps as u128) <= BASIS_POINTS_DIVISOR
+                && (incident_surcharge_bps as u128) <= BASIS_POINTS_DIVISOR,
+            AmmError::InvalidPoolConfig
+        );
+        // Incidents are flagged inside the band, before the hard limit rejects
+        require!(
+            max_deviation_bps == 0 || incident_deviation_bps < max_deviation_bps,
+            AmmError::InvalidPoolConfig
+        );
+        Ok(())
+    }
+
+    /// Swap fee currently in effect as (numerator, denominator): the dynamic
+    /// fee when that mode is on, the fixed fee otherwise
+    pub fn fee_rate(&self) -> (u128, u128) {
+        if self.dynamic_fee_max_bps > 0 {
+            (self.dynamic_fee_bps as u128, BASIS_POINTS_DIVISOR)
+        } else {
+            (self.fee_numerator as u128, self.fee_denominator as u128)
+        }
+    }
+
+    /// Calculate the fee on a swap's input, or its output with `fee_on_output`.
+    /// Rounded up, so at a nonzero rate any nonzero amount pays at least one
+    /// base unit and splitting a trade into dust never dodges the fee.
+    pub fn calculate_fee(&self, amount: u128) -> Result<u128> {
+        let (fee_numerator, fee_denominator) = self.fee_rate();
+        math::mul_div_ceil(amount, fee_numerator, fee_denominator)
+    }
+
+    /// Least amount whose remainder after `calculate_fee` covers `net_amount`.
+    /// With the fee rounded up the remainder is `floor(amount * (1 - rate))`,
+    /// so this is exact.
+    pub fn amount_before_fee(&self, net_amount: u128) -> Result<u128> {
+        let (fee_numerator, fee_denominator) = self.fee_rate();
+        math::mul_div_ceil(
+            net_amount,
+            fee_denominator,
+            fee_denominator
+                .checked_sub(fee_numerator)
+                .ok_or(AmmError::MathOverflow)?,
+        )
+    }
+
+    /// Mint swap fees are charged in: the input mint, or the output mint with `fee_on_output`
+    pub fn fee_mint(&self, input_mint: &Pubkey, output_mint: &Pubkey) -> Pubkey {
+        if self.fee_on_output != 0 {
+            *output_mint
+        } else {
+            *input_mint
+        }
+    }
+
+    /// Seed prefix and canonical bump of the pool's vault for `mint`. Any mint
+    /// other than token A maps to the token B vault, whose address it then fails to match.
+    pub fn vault_seeds(&self, mint: &Pubkey) -> (&'static [u8], u8) {
+        if *mint == self.token_a_mint {
+            (b"token_a_vault", self.token_a_vault_bump)
+        } else {
+            (b"token_b_vault", self.token_b_vault_bump)
+        }
+    }
+
+    /// Fee and output of swapping `input_amount` against the given reserves, as
+    /// (fee, output). The fee comes off the input before pricing, or with
+    /// `fee_on_output` off the priced output; either way it stays in the reserves.
+    pub fn swap_amounts(
+        &self,
+        input_reserve: u128,
+        output_reserve: u128,
+        input_amount: u128,
+    ) -> Result<(u128, u128)> {
+        if self.fee_on_output != 0 {
+            let gross_output = math::swap_output(input_reserve, output_reserve, input_amount)?;
+            let fee_amount = self.calculate_fee(gross_output)?;
+            Ok((fee_amount, gross_output - fee_amount))
+        } else {
+            let fee_amount = self.calculate_fee(input_amount)?;
+            let input_after_fee = input_amount
+                .checked_sub(fee_amount)
+                .ok_or(AmmError::MathOverflow)?;
+            let output_amount = math::swap_output(input_reserve, output_reserve, input_after_fee)?;
+            Ok((fee_amount, output_amount))
+        }
+    }
+
+    /// Input for which `swap_amounts` pays at least `output_amount`, rounded up
+    pub fn swap_input_for_output(
+        &self,
+        input_reserve: u128,
+        output_reserve: u128,
+        output_amount: u128,
+    ) -> Result<u128> {
+        if self.fee_on_output != 0 {
+            let gross_output = self.amount_before_fee(output_amount)?;
+            math::swap_input(input_reserve, output_reserve, gross_output)
+        } else {
+            let net_input = math::swap_input(input_reserve, output_reserve, output_amount)?;
+            self.amount_before_fee(net_input)
+        }
+    }
+
+    /// Re-derive the dynamic fee from the oracle's realized volatility, scaling
+    /// linearly from the minimum fee at zero volatility to the maximum at
+    /// `dynamic_fee_volatility_bps`. No-op unless dynamic fees are on.
+    pub fn update_dynamic_fee(&mut self, oracle: &PriceOracle) -> Result<()> {
+        if self.dynamic_fee_max_bps == 0 {
+            return Ok(());
+        }
+        let reference = self.dynamic_fee_volatility_bps as u128;
+        let volatility = oracle
+            .realized_volatility_bps(DYNAMIC_FEE_VOLATILITY_SAMPLES)?
+            .min(reference);
+        let range = (self.dynamic_fee_max_bps - self.dynamic_fee_min_bps) as u128;
+        self.dynamic_fee_bps =
+            self.dynamic_fee_min_bps + math::mul_div_floor(range, volatility, reference)? as u16;
+        Ok(())
+    }
+
+    /// Spot price of token A denominated in token B, in raw units
+    pub fn spot_price(reserve_a: u128, reserve_b: u128) -> Result<Q64x64> {
+        Q64x64::from_ratio(reserve_b, reserve_a)
+    }
+
+    /// Record `spot_price` in the pool's oracle and refresh the dynamic fee
+    /// from it. An oracle still holding pre-Q64.64 accumulators is reset first
+    /// rather than mixing units.
+    pub fn observe_price(
+        &mut self,
+        oracle: &mut PriceOracle,
+        unix_timestamp: i64,
+        spot_price: Q64x64,
+    ) -> Result<()> {
+        if self.oracle_q64 == 0 {
+            oracle.reset(unix_timestamp)?;
+            self.oracle_q64 = 1;
+        }
+        oracle.record(unix_timestamp, spot_price)?;
+        self.update_dynamic_fee(oracle)
+    }
+
+    /// The pool's LP counter must always match the LP mint supply
+    pub fn check_lp_supply(&self, lp_mint_supply: u64) -> Result<()> {
+        require!(
+            self.total_lp_tokens_issued == lp_mint_supply,
+            AmmError::LpSupplyMismatch
+        );
+        Ok(())
+    }
+
+    /// Distance of `price` from `reference`, in basis points of the reference (rounded up)
+    pub fn deviation_bps(price: Q64x64, reference: Q64x64) -> Result<u128> {
+        fixed_point::mul_div_ceil(
+            price.abs_diff(reference).to_bits(),
+            BASIS_POINTS_DIVISOR,
+            reference.to_bits(),
+        )
+    }
+
+    /// Whether `post_swap_price` is on the trader's side of `price_limit`. Selling
+    /// token A lowers the price, so the limit is a floor; selling token B, a ceiling.
+    pub fn within_price_limit(
+        input_is_token_a: bool,
+        post_swap_price: Q64x64,
+        price_limit: Q64x64,
+    ) -> bool {
+        if input_is_token_a {
+            post_swap_price >= price_limit
+        } else {
+            post_swap_price <= price_limit
+        }
+    }
+
+    /// Largest part of `input_amount` within the max trade size, the per-slot
+    /// circuit breaker, the max price impact and the trader's `price_limit`, for
+    /// swaps that accept a partial fill
+    pub fn fillable_input(
+        &self,
+        slot: u64,
+        input_is_token_a: bool,
+        input_reserve: u128,
+        output_reserve: u128,
+        input_amount: u128,
+        price_limit: Option<Q64x64>,
+    ) -> Result<u128> {
+        let mut fillable = input_amount;
+        if self.max_trade_bps_of_reserves > 0 {
+            fillable = fillable.min(math::mul_div_floor(
+                input_reserve,
+                self.max_trade_bps_of_reserves as u128,
+                BASIS_POINTS_DIVISOR,
+            )?);
+        }
+        if self.max_price_change_bps_per_slot == 0
+            && self.max_price_impact_bps == 0
+            && price_limit.is_none()
+        {
+            return Ok(fillable);
+        }
+
+        let pre_swap_price = if input_is_token_a {
+            Self::spot_price(input_reserve, output_reserve)?
+        } else {
+            Self::spot_price(output_reserve, input_reserve)?
+        };
+        let reference_price = if self.price_reference_slot == slot {
+            Q64x64::from_bits(self.slot_open_price)
+        } else {
+            pre_swap_price
+        };
+        let allowed = |input: u128| -> Result<bool> {
+            let (_, output) = self.swap_amounts(input_reserve, output_reserve, input)?;
+            let new_output_reserve = output_reserve - output;
+            let new_input_reserve = input_reserve
+                .checked_add(input)
+                .ok_or(AmmError::MathOverflow)?;
+            let post_swap_price = if input_is_token_a {
+                Self::spot_price(new_input_reserve, new_output_reserve)?
+            } else {
+                Self::spot_price(new_output_reserve, new_input_reserve)?
+            };
+            let within_breaker = self.max_price_change_bps_per_slot == 0
+                || Self::deviation_bps(post_swap_price, reference_price)?
+                    <= self.max_price_change_bps_per_slot as u128;
+            let within_impact = self.max_price_impact_bps == 0
+                || Self::price_impact_bps(
+                    input_reserve,
+                    output_reserve,
+                    new_input_reserve,
+                    new_output_reserve,
+                )? <= self.max_price_impact_bps as u128;
+            let within_limit = price_limit.is_none_or(|price_limit| {
+                Self::within_price_limit(input_is_token_a, post_swap_price, price_limit)
+            });
+            Ok(within_breaker && within_impact && within_limit)
+        };
+
+        // The price moves monotonically with the input, so the allowed inputs
+        // are a prefix that bisection can bound
+        if !allowed(fillable)? {
+            let (mut low, mut high) = (0, fillable);
+            while high - low > 1 {
+                let mid = low + (high - low) / 2;
+                if allowed(mid)? {
+                    low = mid;
+                } else {
+                    high = mid;
+                }
+            }
+            fillable = low;
+        }
+        Ok(fillable)
+    }
+
+    /// Reject price moves larger than the per-slot circuit breaker allows.
+    /// The first swap in a slot records the pre-trade price as the reference.
+    pub fn check_price_movement(
+        &mut self,
+        slot: u64,
+        pre_swap_price: Q64x64,
+        post_swap_price: Q64x64,
+    ) -> Result<()> {
+        if self.price_reference_slot != slot {
+            self.price_reference_slot = slot;
+            self.slot_open_price = pre_swap_price.to_bits();
+        }
+
+        let price_change_bps =
+            Self::deviation_bps(post_swap_price, Q64x64::from_bits(self.slot_open_price))?;
+
+        require!(
+            price_change_bps <= self.max_price_change_bps_per_slot as u128,
+            AmmError::PriceMovementExceeded
+        );
+        Ok(())
+    }
+
+    /// Price impact of a swap in bps: how far the marginal rate, in output
+    /// tokens per input token, falls between the pre- and post-swap reserves
+    pub fn price_impact_bps(
+        input_reserve: u128,
+        output_reserve: u128,
+        new_input_reserve: u128,
+        new_output_reserve: u128,
+    ) -> Result<u128> {
+        Self::deviation_bps(
+            Self::spot_price(new_input_reserve, new_output_reserve)?,
+            Self::spot_price(input_reserve, output_reserve)?,
+        )
+    }
+
+    /// Reject a swap with a larger price impact than the pool allows, whatever
+    /// output the trader would accept
+    pub fn check_price_impact(
+        &self,
+        input_reserve: u128,
+        output_reserve: u128,
+        new_input_reserve: u128,
+        new_output_reserve: u128,
+    ) -> Result<()> {
+        if self.max_price_impact_bps > 0 {
+            let impact_bps = Self::price_impact_bps(
+                input_reserve,
+                output_reserve,
+                new_input_reserve,
+                new_output_reserve,
+            )?;
+            require!(
+                impact_bps <= self.max_price_impact_bps as u128,
+                AmmError::PriceImpactTooHigh
+            );
+        }
+        Ok(())
+    }
+
+    /// Snapshot of every configurable parameter, for the `PoolParamsSnapshot` event
+    pub fn params_snapshot(&mut self, liquidity_pool: Pubkey) -> Result<PoolParamsSnapshot> {
+        Ok(PoolParamsSnapshot {
+            liquidity_pool,
+            event_seq: self.next_event_seq(),
+            slot: Clock::get()?.slot,
+            admin: self.admin,
+            fee_numerator: self.fee_numerator,
+            fee_denominator: self.fee_denominator,
+            max_price_change_bps_per_slot: self.max_price_change_bps_per_slot,
+            max_trader_slot_volume_bps: self.max_trader_slot_volume_bps,
+            block_same_slot_reversal: self.block_same_slot_reversal != 0,
+            max_trade_bps_of_reserves: self.max_trade_bps_of_reserves,
+            launch_protection_end_slot: self.launch_protection_end_slot,
+            launch_trade_cooldown_slots: self.launch_trade_cooldown_slots,
+            withdraw_only: self.withdraw_only != 0,
+            large_withdrawal_delay_secs: self.large_withdrawal_delay_secs,
+            small_lp_withdrawal_threshold: self.small_lp_withdrawal_threshold,
+            deviation_twap_window_secs: self.deviation_twap_window_secs,
+            max_oracle_deviation_bps: self.max_oracle_deviation_bps,
+            incident_deviation_bps: self.incident_deviation_bps,
+            incident_surcharge_bps: self.incident_surcharge_bps,
+            verification_level: VerificationLevel::from_u8(self.verification_level)?,
+            min_initial_deposit_a: self.min_initial_deposit_a,
+            min_initial_deposit_b: self.min_initial_deposit_b,
+            protocol_fee_share_bps: self.protocol_fee_share_bps,
+            buyback_mint: self.buyback_mint,
+            fee_recipient_a: self.fee_recipient_a,
+            fee_recipient_b: self.fee_recipient_b,
+            disabled_operations: self.disabled_operations,
+            lp_whitelist_enabled: self.lp_whitelist_enabled != 0,
+            launch_whitelist_only: self.launch_whitelist_only != 0,
+            jit_window_slots: self.jit_window_slots,
+            jit_penalty_bps: self.jit_penalty_bps,
+            withdraw_cooldown_secs: self.withdraw_cooldown_secs,
+            oracle_checkpoint_reward: self.oracle_checkpoint_reward,
+            dynamic_fee_min_bps: self.dynamic_fee_min_bps,
+            dynamic_fee_max_bps: self.dynamic_fee_max_bps,
+            dynamic_fee_volatility_bps: self.dynamic_fee_volatility_bps,
+            fee_on_output: self.fee_on_output != 0,
+            token_a_freezable: self.token_a_freezable != 0,
+            token_b_freezable: self.token_b_freezable != 0,
+            admin_realm: self.admin_realm,
+            creator: self.creator,
+            creator_fee_bps: self.creator_fee_bps,
+            max_price_impact_bps: self.max_price_impact_bps,
+            referral_fee_bps: self.referral_fee_bps,
+            keeper_tip: self.keeper_tip,
+            keeper_tip_share_bps: self.keeper_tip_share_bps,
+        })
+    }
+
+    /// Protocol share of a swap fee, rounded down in the LPs' favor
+    pub fn protocol_fee(&self, fee_amount: u128) -> Result<u128> {
+        math::mul_div_floor(
+            fee_amount,
+            self.protocol_fee_share_bps as u128,
+            BASIS_POINTS_DIVISOR,
+        )
+    }
+
+    /// Creator share of a swap fee, rounded down in the LPs' favor and capped at
+    /// what the protocol share leaves
+    pub fn creator_fee(&self, fee_amount: u128, protocol_fee: u128) -> Result<u128> {
+        let creator_fee = math::mul_div_floor(
+            fee_amount,
+            self.creator_fee_bps as u128,
+            BASIS_POINTS_DIVISOR,
+        )?;
+        Ok(creator_fee.min(fee_amount.saturating_sub(protocol_fee)))
+    }
+
+    /// Referrer share of a swap fee, rounded down in the LPs' favor and capped
+    /// at what the protocol and creator shares leave
+    pub fn referral_fee(&self, fee_amount: u128, paid_out: u128) -> Result<u128> {
+        let referral_fee = math::mul_div_floor(
+            fee_amount,
+            self.referral_fee_bps as u128,
+            BASIS_POINTS_DIVISOR,
+        )?;
+        Ok(referral_fee.min(fee_amount.saturating_sub(paid_out)))
+    }
+
+    /// Set aside the keeper tip share of a token A protocol fee that was paid
+    /// into the protocol fee vault. Returns the amount added to the budget.
+    pub fn fund_keeper_tips(&mut self, protocol_fee: u64) -> Result<u64> {
+        let share = math::to_u64(math::mul_div_floor(
+            protocol_fee as u128,
+            self.keeper_tip_share_bps as u128,
+            BASIS_POINTS_DIVISOR,
+        )?)?;
+        self.keeper_tip_budget = self
+            .keeper_tip_budget
+            .checked_add(share)
+            .ok_or(AmmError::MathOverflow)?;
+        Ok(share)
+    }
+
+    /// Take up to `amount` out of the keeper tip budget, returning what was taken
+    pub fn take_keeper_tip(&mut self, amount: u64) -> u64 {
+        let tip = amount.min(self.keeper_tip_budget);
+        self.keeper_tip_budget -= tip;
+        tip
+    }
+
+    /// Protocol fees in `mint` free to pay out of a fee vault holding
+    /// `vault_amount`, i.e. excluding the keeper tip budget
+    pub fn unreserved_protocol_fees(&self, mint: &Pubkey, vault_amount: u64) -> u64 {
+        if *mint == self.token_a_mint {
+            vault_amount.saturating_sub(self.keeper_tip_budget)
+        } else {
+            vault_amount
+        }
+    }
+
+    /// Creator's account receiving the creator fee share paid in `mint`
+    pub fn creator_fee_account(&self, mint: &Pubkey) -> Pubkey {
+        if *mint == self.token_a_mint {
+            self.creator_fee_account_a
+        } else {
+            self.creator_fee_account_b
+        }
+    }
+
+    /// Account receiving the protocol fee share paid in `mint` (default = protocol fee vault)
+    pub fn fee_recipient(&self, mint: &Pubkey) -> Pubkey {
+        if *mint == self.token_a_mint {
+            self.fee_recipient_a
+        } else {
+            self.fee_recipient_b
+        }
+    }
+
+    /// Reject `operation` (a `POOL_OP_*` bit) if the admin has disabled it
+    pub fn check_operation_enabled(&self, operation: u16) -> Result<()> {
+        require!(
+            self.disabled_operations & operation == 0,
+            AmmError::OperationDisabled
+        );
+        Ok(())
+    }
+
+    /// Whether withdrawal limits key off the depositor's `LiquidityPosition`,
+    /// making the position mandatory for deposits
+    pub fn tracks_positions(&self) -> bool {
+        self.jit_window_slots > 0 || self.withdraw_cooldown_secs > 0
+    }
+
+    /// Reject withdrawals before the cooldown since the position's last deposit has passed
+    pub fn check_withdraw_cooldown(&self, now: i64, last_deposit_timestamp: i64) -> Result<()> {
+        require!(
+            now >= last_deposit_timestamp.saturating_add(self.withdraw_cooldown_secs),
+            AmmError::WithdrawCooldownActive
+        );
+        Ok(())
+    }
+
+    /// Penalty in basis points for withdrawing at `slot` from a position last
+    /// deposited into at `last_deposit_slot`. Deposit-swap-withdraw within the
+    /// window only skims fees from passive LPs, so it pays into the pool.
+    pub fn jit_penalty_bps(&self, slot: u64, last_deposit_slot: u64) -> u16 {
+        if self.jit_window_slots == 0
+            || slot >= last_deposit_slot.saturating_add(self.jit_window_slots)
+        {
+            return 0;
+        }
+        self.jit_penalty_bps
+    }
+
+    /// Share of the payout, in basis points, left after the JIT penalty for
+    /// burning `lp_tokens` at `now`/`slot`, once the withdraw cooldown has
+    /// passed. The burn must be covered by the LP `position` tracks, which sits
+    /// in its LP vault, so LP tokens minted elsewhere cannot be moved under an
+    /// older deposit. LP burned without a position is untracked - minted before
+    /// the pool tracked positions, or outside deposits - and is paid in full.
+    pub fn position_payout_bps(
+        &self,
+        position: Option<&LiquidityPosition>,
+        lp_tokens: u64,
+        now: i64,
+        slot: u64,
+    ) -> Result<u128> {
+        let position = match position {
+            Some(position) => position,
+            None => return Ok(BASIS_POINTS_DIVISOR),
+        };
+        require!(
+            lp_tokens <= position.tracked_lp(),
+            AmmError::BurnExceedsPosition
+        );
+        if !self.tracks_positions() {
+            return Ok(BASIS_POINTS_DIVISOR);
+        }
+        self.check_withdraw_cooldown(now, position.last_deposit_timestamp)?;
+        let penalty_bps = self.jit_penalty_bps(slot, position.last_deposit_slot);
+        Ok(BASIS_POINTS_DIVISOR - penalty_bps as u128)
+    }
+
+    /// In withdraw-only mode, withdrawals must wait out the large-withdrawal
+    /// delay unless they come from a position below the small-LP threshold.
+    /// `position_lp` is the LP tracked by the position the LP tokens are burned
+    /// from, `None` when no position covers the burn. A position only tracks LP
+    /// minted through it, so splitting LP tokens across accounts does not shrink it.
+    pub fn check_withdrawal_allowed(&self, now: i64, position_lp: Option<u64>) -> Result<()> {
+        if self.withdraw_only == 0 {
+            return Ok(());
+        }
+        if let Some(position_lp) = position_lp {
+            if self.small_lp_withdrawal_threshold > 0
+                && position_lp < self.small_lp_withdrawal_threshold
+            {
+                return Ok(());
+            }
+        }
+
+        let unlock_time = self
+            .withdraw_only_since
+            .checked_add(self.large_withdrawal_delay_secs)
+            .ok_or(AmmError::MathOverflow)?;
+        require!(now >= unlock_time, AmmError::WithdrawalTimelocked);
+        Ok(())
+    }
+
+    /// Whether the launch trade cooldown applies at `slot`
+    pub fn launch_cooldown_active(&self, slot: u64) -> bool {
+        self.launch_trade_cooldown_slots > 0 && slot < self.launch_protection_end_slot
+    }
+
+    /// Pool-side checks and pricing for swaps executed on a user's behalf by
+    /// keepers (DCA tranches, limit orders). Returns the output amount, rounded
+    /// down so the reserve product never decreases.
+    pub fn apply_keeper_swap(
+        &mut self,
+        slot: u64,
+        input_is_token_a: bool,
+        input_reserve: u128,
+        output_reserve: u128,
+        input_amount: u128,
+    ) -> Result<u64> {
+        require!(self.withdraw_only == 0, AmmError::PoolWithdrawOnly);
+        self.check_operation_enabled(POOL_OP_SWAP)?;
+        require!(
+            !self.launch_whitelist_active(slot),
+            AmmError::NotLaunchWhitelisted
+        );
+        require!(
+            input_reserve > 0 && output_reserve > 0,
+            AmmError::PoolHasNoLiquidity
+        );
+        if self.max_trade_bps_of_reserves > 0 {
+            let max_input = math::mul_div_floor(
+                input_reserve,
+                self.max_trade_bps_of_reserves as u128,
+                BASIS_POINTS_DIVISOR,
+            )?;
+            require!(input_amount <= max_input, AmmError::TradeTooLarge);
+        }
+
+        let (_, output_amount) = self.swap_amounts(input_reserve, output_reserve, input_amount)?;
+        require!(output_amount > 0, AmmError::OutputTooSmall);
+        let new_output_reserve = output_reserve - output_amount;
+
+        let final_input_reserve = input_reserve
+            .checked_add(input_amount)
+            .ok_or(AmmError::MathOverflow)?;
+        self.check_price_impact(
+            input_reserve,
+            output_reserve,
+            final_input_reserve,
+            new_output_reserve,
+        )?;
+
+        if self.max_price_change_bps_per_slot > 0 {
+            let (pre_swap_price, post_swap_price) = if input_is_token_a {
+                (
+                    Self::spot_price(input_reserve, output_reserve)?,
+                    Self::spot_price(final_input_reserve, new_output_reserve)?,
+                )
+            } else {
+                (
+                    Self::spot_price(output_reserve, input_reserve)?,
+                    Self::spot_price(new_output_reserve, final_input_reserve)?,
+                )
+            };
+            self.check_price_movement(slot, pre_swap_price, post_swap_price)?;
+        }
+
+        math::to_u64(output_amount)
+    }
+
+    /// Whether only launch-whitelisted wallets may swap at `slot`
+    pub fn launch_whitelist_active(&self, slot: u64) -> bool {
+        self.launch_whitelist_only != 0 && slot < self.launch_protection_end_slot
+    }
+
+    /// Whether swaps at `slot` must carry a `TraderState` account
+    pub fn tracks_traders(&self, slot: u64) -> bool {
+        self.max_trader_slot_volume_bps > 0
+            || self.block_same_slot_reversal != 0
+            || self.launch_cooldown_active(slot)
+    }
+}
+
+/// Trust tier of a pool, so routers and UIs can filter or warn on unverified pools
+#[derive(
+    AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Copy, Debug, Default, PartialEq, Eq,
+)]
+pub enum VerificationLevel {
+    #[default]
+    Unverified,
+    Community,
+    Verified,
+}
+
+impl VerificationLevel {
+    /// Decode the level stored on the zero-copy pool account
+    pub fn from_u8(value: u8) -> Result<Self> {
+        match value {
+            0 => Ok(Self::Unverified),
+            1 => Ok(Self::Community),
+            2 => Ok(Self::Verified),
+            _ => err!(AmmError::InvalidPoolConfig),
+        }
+    }
+}
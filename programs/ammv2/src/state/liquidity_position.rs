@@ -0,0 +1,140 @@
+use anchor_lang::prelude::*;
+
+use crate::errors::AmmError;
+use crate::math;
+
+/// LP tokens a wallet holds in a pool through the position's LP vault, so it
+/// can claim the fees they earn without withdrawing its liquidity. Also keeps the wallet's deposit history from
+/// the moment the position was opened, enough for a frontend to show PnL.
+#[account]
+#[derive(InitSpace)]
+pub struct LiquidityPosition {
+    /// Pool the position is in
+    pub liquidity_pool: Pubkey,
+
+    /// Owner of the position and the LP tokens in its vault
+    pub owner: Pubkey,
+
+    /// LP tokens backing the position's principal liquidity
+    pub lp_tokens: u64,
+
+    /// LP tokens worth of fees accrued and not yet claimed
+    pub fees_owed_lp: u64,
+
+    /// `fee_growth_per_lp` at which `lp_tokens` was last valued
+    pub fee_growth_checkpoint: u128,
+
+    /// PDA bump seed
+    pub bump: u8,
+
+    /// Unix timestamp the position was opened at
+    pub opened_at: i64,
+
+    /// Pool reserves when the position was opened, giving the entry price
+    pub entry_reserve_a: u64,
+    pub entry_reserve_b: u64,
+
+    /// `fee_growth_per_lp` when the position was opened
+    pub entry_fee_growth: u128,
+
+    /// Cumulative tokens deposited through the position
+    pub token_a_deposited: u64,
+    pub token_b_deposited: u64,
+
+    /// Cumulative tokens withdrawn through the position, fee claims excluded
+    pub token_a_withdrawn: u64,
+    pub token_b_withdrawn: u64,
+
+    /// Cumulative tokens paid out by `claim_fees`
+    pub token_a_fees_claimed: u64,
+    pub token_b_fees_claimed: u64,
+
+    /// Slot of the last deposit through the position, for the pool's JIT penalty
+    pub last_deposit_slot: u64,
+
+    /// Unix timestamp of the last deposit, for the pool's withdrawal cooldown
+    pub last_deposit_timestamp: i64,
+
+    /// Bump of the position's LP vault PDA
+    pub lp_vault_bump: u8,
+}
+
+impl LiquidityPosition {
+    /// Move the fees accrued since the checkpoint out of the principal into
+    /// `fees_owed_lp`. The principal keeps its liquidity, re-valued at `fee_growth_per_lp`.
+    pub fn settle(&mut self, fee_growth_per_lp: u128) -> Result<()> {
+        if self.fee_growth_checkpoint > 0 && fee_growth_per_lp > self.fee_growth_checkpoint {
+            // Rounded up so the principal is never shortchanged
+            let principal = math::to_u64(math::mul_div_ceil(
+                self.lp_tokens as u128,
+                self.fee_growth_checkpoint,
+                fee_growth_per_lp,
+            )?)?;
+            self.fees_owed_lp = self
+                .fees_owed_lp
+                .checked_add(self.lp_tokens - principal)
+                .ok_or(AmmError::MathOverflow)?;
+            self.lp_tokens = principal;
+        }
+        self.fee_growth_checkpoint = fee_growth_per_lp;
+        Ok(())
+    }
+
+    /// Add a deposit's LP tokens to the principal and the tokens to the history
+    pub fn record_deposit(
+        &mut self,
+        lp_tokens_minted: u64,
+        token_a_amount: u64,
+        token_b_amount: u64,
+    ) -> Result<()> {
+        self.lp_tokens = self
+            .lp_tokens
+            .checked_add(lp_tokens_minted)
+            .ok_or(AmmError::MathOverflow)?;
+        // History only, so it saturates rather than blocking the deposit
+        self.token_a_deposited = self.token_a_deposited.saturating_add(token_a_amount);
+        self.token_b_deposited = self.token_b_deposited.saturating_add(token_b_amount);
+        Ok(())
+    }
+
+    /// Take LP tokens burned in a withdrawal out of the position, principal first
+    pub fn record_withdrawal(
+        &mut self,
+        lp_tokens_burned: u64,
+        token_a_amount: u64,
+        token_b_amount: u64,
+    ) {
+        let from_principal = lp_tokens_burned.min(self.lp_tokens);
+        self.lp_tokens -= from_principal;
+        self.fees_owed_lp = self
+            .fees_owed_lp
+            .saturating_sub(lp_tokens_burned - from_principal);
+        self.token_a_withdrawn = self.token_a_withdrawn.saturating_add(token_a_amount);
+        self.token_b_withdrawn = self.token_b_withdrawn.saturating_add(token_b_amount);
+    }
+
+    /// Address of the LP vault of the position at `position`
+    pub fn lp_vault_address(&self, position: &Pubkey) -> Result<Pubkey> {
+        Pubkey::create_program_address(
+            &[
+                b"position_lp_vault",
+                position.as_ref(),
+                &[self.lp_vault_bump],
+            ],
+            &crate::ID,
+        )
+        .map_err(|_| error!(AmmError::InvalidPositionLpVault))
+    }
+
+    /// LP tokens the position accounts for, principal and accrued fees together
+    pub fn tracked_lp(&self) -> u64 {
+        self.lp_tokens.saturating_add(self.fees_owed_lp)
+    }
+
+    /// Clear the accrued fees once `claim_fees` has paid them out
+    pub fn record_fee_claim(&mut self, token_a_amount: u64, token_b_amount: u64) {
+        self.fees_owed_lp = 0;
+        self.token_a_fees_claimed = self.token_a_fees_claimed.saturating_add(token_a_amount);
+        self.token_b_fees_claimed = self.token_b_fees_claimed.saturating_add(token_b_amount);
+    }
+}
@@ -0,0 +1,53 @@
+use anchor_lang::prelude::*;
+
+/// LP tokens a wallet has escrowed until a fixed time, a verifiable liquidity lock
+#[account]
+#[derive(InitSpace)]
+pub struct LpLock {
+    /// Pool whose LP tokens are locked
+    pub liquidity_pool: Pubkey,
+
+    /// Wallet that locked the tokens and receives them on unlock
+    pub owner: Pubkey,
+
+    /// LP tokens held in the lock's vault
+    pub amount: u64,
+
+    /// Unix timestamp before which the tokens cannot be unlocked
+    pub unlock_timestamp: i64,
+
+    /// Unix timestamp of the first lock
+    pub locked_at: i64,
+
+    /// Canonical bump of the lock PDA
+    pub bump: u8,
+}
+
+impl LpLock {
+    /// Seed material for signing as this lock
+    pub fn signer(&self) -> LpLockSigner {
+        LpLockSigner {
+            liquidity_pool: self.liquidity_pool,
+            owner: self.owner,
+            bump: [self.bump],
+        }
+    }
+}
+
+/// Owned seed material for `invoke_signed` as an LP lock PDA
+pub struct LpLockSigner {
+    liquidity_pool: Pubkey,
+    owner: Pubkey,
+    bump: [u8; 1],
+}
+
+impl LpLockSigner {
+    pub fn seeds(&self) -> [&[u8]; 4] {
+        [
+            b"lp_lock",
+            self.liquidity_pool.as_ref(),
+            self.owner.as_ref(),
+            &self.bump,
+        ]
+    }
+}
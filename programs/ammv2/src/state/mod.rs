@@ -0,0 +1,50 @@
+pub mod amm_config;
+pub use amm_config::*;
+
+pub mod dca;
+pub use dca::*;
+
+pub mod farm;
+pub use farm::*;
+
+pub mod gauge;
+pub use gauge::*;
+
+pub mod launch_pool;
+pub use launch_pool::*;
+
+pub mod limit_order;
+pub use limit_order::*;
+
+pub mod liquidity_pool;
+pub use liquidity_pool::*;
+
+pub mod liquidity_position;
+pub use liquidity_position::*;
+
+pub mod lp_lock;
+pub use lp_lock::*;
+
+pub mod oracle;
+pub use oracle::*;
+
+pub mod referral;
+pub use referral::*;
+
+pub mod sub_account;
+pub use sub_account::*;
+
+pub mod timelock;
+pub use timelock::*;
+
+pub mod trader_state;
+pub use trader_state::*;
+
+pub mod treasury;
+pub use treasury::*;
+
+pub mod volume_stats;
+pub use volume_stats::*;
+
+pub mod whitelist;
+pub use whitelist::*;
@@ -0,0 +1,159 @@
+use anchor_lang::prelude::*;
+
+use crate::errors::AmmError;
+use crate::fixed_point::Q64x64;
+use crate::state::LiquidityPool;
+
+/// Number of observations kept in a pool's price oracle ring buffer
+pub const ORACLE_OBSERVATIONS: usize = 64;
+
+/// Seconds a bootstrapped oracle observation is treated as low confidence
+pub const ORACLE_BOOTSTRAP_WINDOW_SECS: i64 = 1_800;
+
+/// Minimum age of the latest oracle observation before `checkpoint_oracle` pays its reward
+pub const ORACLE_CHECKPOINT_INTERVAL_SECS: i64 = 300;
+
+/// A point on the time-weighted price accumulator
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Copy, Default)]
+pub struct Observation {
+    /// Unix timestamp of the observation
+    pub timestamp: i64,
+
+    /// Running sum of the Q64.64 spot price (token B per token A) bits times
+    /// elapsed seconds. Wraps on overflow; only differences between observations
+    /// are meaningful, and they hold while price * window stays below 2^64.
+    pub price_cumulative: u128,
+}
+
+/// Ring buffer of price observations for one pool
+#[account]
+#[derive(InitSpace)]
+pub struct PriceOracle {
+    /// Pool the oracle tracks
+    pub liquidity_pool: Pubkey,
+
+    /// Observations up to this timestamp were bootstrapped from a single reserve snapshot
+    pub low_confidence_until: i64,
+
+    /// Index of the most recent observation
+    pub observation_index: u16,
+
+    /// Number of populated observations
+    pub observation_count: u16,
+
+    /// Observation ring buffer
+    pub observations: [Observation; ORACLE_OBSERVATIONS],
+}
+
+impl PriceOracle {
+    /// Most recent observation, if any
+    pub fn latest(&self) -> Option<&Observation> {
+        (self.observation_count > 0).then(|| &self.observations[self.observation_index as usize])
+    }
+
+    /// Time-weighted average price over (up to) the last `window_secs`, treating
+    /// `current_price` as in effect since the latest observation.
+    /// Returns `None` when there is no elapsed history to average over.
+    pub fn twap(&self, now: i64, current_price: Q64x64, window_secs: i64) -> Option<Q64x64> {
+        let latest = *self.latest()?;
+        let target = now.saturating_sub(window_secs);
+
+        // Newest observation at or before the window start, else the oldest available
+        let mut start = latest;
+        let mut offset = 1;
+        while start.timestamp > target && offset < self.observation_count as usize {
+            let index = (self.observation_index as usize + ORACLE_OBSERVATIONS - offset)
+                % ORACLE_OBSERVATIONS;
+            start = self.observations[index];
+            offset += 1;
+        }
+
+        let window = now.checked_sub(start.timestamp)?;
+        if window <= 0 {
+            return None;
+        }
+
+        let since_latest = now.saturating_sub(latest.timestamp).max(0) as u128;
+        let cumulative_now = latest
+            .price_cumulative
+            .wrapping_add(current_price.to_bits().wrapping_mul(since_latest));
+        Some(Q64x64::from_bits(
+            cumulative_now.wrapping_sub(start.price_cumulative) / window as u128,
+        ))
+    }
+
+    /// Mean absolute change, in bps, between the average prices of consecutive
+    /// intervals over (up to) the last `samples` intervals between observations.
+    /// Zero until the oracle holds at least two intervals.
+    pub fn realized_volatility_bps(&self, samples: usize) -> Result<u128> {
+        let intervals = samples.min((self.observation_count as usize).saturating_sub(1));
+        if intervals < 2 {
+            return Ok(0);
+        }
+
+        let mut newer = self.observations[self.observation_index as usize];
+        let mut newer_price = None;
+        let mut total_change_bps = 0u128;
+        for offset in 1..=intervals {
+            let index = (self.observation_index as usize + ORACLE_OBSERVATIONS - offset)
+                % ORACLE_OBSERVATIONS;
+            let older = self.observations[index];
+            let elapsed = (newer.timestamp - older.timestamp).max(1) as u128;
+            let price = Q64x64::from_bits(
+                newer.price_cumulative.wrapping_sub(older.price_cumulative) / elapsed,
+            );
+            if let Some(newer_price) = newer_price {
+                total_change_bps = total_change_bps
+                    .checked_add(LiquidityPool::deviation_bps(
+                        newer_price,
+                        price.max(Q64x64::EPSILON),
+                    )?)
+                    .ok_or(AmmError::MathOverflow)?;
+            }
+            newer_price = Some(price);
+            newer = older;
+        }
+        Ok(total_change_bps / (intervals as u128 - 1))
+    }
+
+    /// Drop every observation; the history restarts at the next one, low
+    /// confidence for the bootstrap window
+    pub fn reset(&mut self, timestamp: i64) -> Result<()> {
+        self.observation_index = 0;
+        self.observation_count = 0;
+        self.low_confidence_until = timestamp
+            .checked_add(ORACLE_BOOTSTRAP_WINDOW_SECS)
+            .ok_or(AmmError::MathOverflow)?;
+        Ok(())
+    }
+
+    /// Accumulate the price in effect since the last observation and append a new one.
+    /// At most one observation is written per second.
+    pub fn record(&mut self, timestamp: i64, spot_price: Q64x64) -> Result<()> {
+        let Some(latest) = self.latest().copied() else {
+            self.observations[0] = Observation {
+                timestamp,
+                price_cumulative: 0,
+            };
+            self.observation_index = 0;
+            self.observation_count = 1;
+            return Ok(());
+        };
+
+        if timestamp <= latest.timestamp {
+            return Ok(());
+        }
+
+        let elapsed = (timestamp - latest.timestamp) as u128;
+        let next_index = (self.observation_index as usize + 1) % ORACLE_OBSERVATIONS;
+        self.observations[next_index] = Observation {
+            timestamp,
+            price_cumulative: latest
+                .price_cumulative
+                .wrapping_add(spot_price.to_bits().wrapping_mul(elapsed)),
+        };
+        self.observation_index = next_index as u16;
+        self.observation_count = (self.observation_count + 1).min(ORACLE_OBSERVATIONS as u16);
+        Ok(())
+    }
+}
@@ -0,0 +1,64 @@
+use anchor_lang::prelude::*;
+
+use crate::errors::AmmError;
+
+/// Number of mints a referrer can hold unclaimed fees in at once
+pub const MAX_REFERRAL_MINTS: usize = 8;
+
+/// Unclaimed referral fees in one mint
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Copy, Default)]
+pub struct ReferralBalance {
+    /// Mint of the fees (default = free slot)
+    pub mint: Pubkey,
+
+    /// Fees accrued since the last claim
+    pub amount: u64,
+}
+
+/// Frontend registered to earn a share of the swap fees it routes. The fees
+/// sit in the per-mint referral vaults until claimed.
+#[account]
+#[derive(InitSpace)]
+pub struct Referrer {
+    /// Wallet that registered the referrer and claims its fees
+    pub authority: Pubkey,
+
+    /// PDA bump
+    pub bump: u8,
+
+    /// Unclaimed fees, one slot per mint
+    pub balances: [ReferralBalance; MAX_REFERRAL_MINTS],
+}
+
+impl Referrer {
+    /// Credit `amount` of `mint`, taking a free slot for a mint without a balance
+    pub fn accrue(&mut self, mint: Pubkey, amount: u64) -> Result<()> {
+        let index = match self
+            .balances
+            .iter()
+            .position(|balance| balance.mint == mint)
+        {
+            Some(index) => index,
+            None => self
+                .balances
+                .iter()
+                .position(|balance| balance.amount == 0)
+                .ok_or(AmmError::ReferralBalancesFull)?,
+        };
+        let balance = &mut self.balances[index];
+        balance.mint = mint;
+        balance.amount = balance
+            .amount
+            .checked_add(amount)
+            .ok_or(AmmError::MathOverflow)?;
+        Ok(())
+    }
+
+    /// Clear and return the unclaimed balance of `mint`
+    pub fn take(&mut self, mint: &Pubkey) -> u64 {
+        self.balances
+            .iter_mut()
+            .find(|balance| balance.mint == *mint)
+            .map_or(0, |balance| std::mem::take(&mut balance.amount))
+    }
+}
@@ -0,0 +1,52 @@
+use anchor_lang::prelude::*;
+
+/// Segregated holding account under a master owner, with an optional delegate
+#[account]
+#[derive(Default, InitSpace)]
+pub struct SubAccount {
+    /// Master wallet that controls the sub-account
+    pub owner: Pubkey,
+
+    /// Wallet allowed to manage liquidity on the owner's behalf (default = none)
+    pub delegate: Pubkey,
+
+    /// Index distinguishing sub-accounts of the same owner
+    pub sub_account_id: u16,
+
+    /// Canonical bump of the sub-account PDA
+    pub bump: u8,
+}
+
+impl SubAccount {
+    /// Whether `signer` may act for this sub-account
+    pub fn is_authorized(&self, signer: &Pubkey) -> bool {
+        *signer == self.owner || (self.delegate != Pubkey::default() && *signer == self.delegate)
+    }
+
+    /// Seed material for signing as this sub-account
+    pub fn signer(&self) -> SubAccountSigner {
+        SubAccountSigner {
+            owner: self.owner,
+            sub_account_id: self.sub_account_id.to_le_bytes(),
+            bump: [self.bump],
+        }
+    }
+}
+
+/// Owned seed material for `invoke_signed` as a sub-account PDA
+pub struct SubAccountSigner {
+    owner: Pubkey,
+    sub_account_id: [u8; 2],
+    bump: [u8; 1],
+}
+
+impl SubAccountSigner {
+    pub fn seeds(&self) -> [&[u8]; 4] {
+        [
+            b"sub_account",
+            self.owner.as_ref(),
+            &self.sub_account_id,
+            &self.bump,
+        ]
+    }
+}
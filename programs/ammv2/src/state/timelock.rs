@@ -0,0 +1,278 @@
+use anchor_lang::prelude::*;
+
+use crate::errors::AmmError;
+use crate::state::{
+    LiquidityPool, VerificationLevel, BASIS_POINTS_DIVISOR, MAX_JIT_PENALTY_BPS, POOL_OPS_ALL,
+};
+
+/// Parameter change that goes through the timelock queue
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ParamChange {
+    /// Lamports charged on pool creation (config authority)
+    PoolCreationFee { lamports: u64 },
+
+    /// Program-wide pause flag (config authority)
+    Paused { paused: bool },
+
+    /// New config authority (config authority)
+    ConfigAuthority { authority: Pubkey },
+
+    /// Timelock delay itself (config authority)
+    ParamChangeDelay { delay_secs: i64 },
+
+    /// Cap on pool creators' fee share (config authority)
+    MaxCreatorFee { bps: u16 },
+
+    /// Policy on mints with a freeze authority (config authority)
+    RejectFreezeAuthority { reject: bool },
+
+    /// Fixed swap fee of a pool (pool admin)
+    PoolFee {
+        fee_numerator: u64,
+        fee_denominator: u64,
+    },
+
+    /// Dynamic swap fee bounds of a pool (pool admin)
+    DynamicFee {
+        min_bps: u16,
+        max_bps: u16,
+        volatility_bps: u16,
+    },
+
+    /// Whether a pool charges its swap fee on the output token (pool admin)
+    FeeOnOutput { fee_on_output: bool },
+
+    /// Protocol share of a pool's swap fees and its buyback mint, default for
+    /// none (pool admin)
+    ProtocolFee {
+        share_bps: u16,
+        buyback_mint: Pubkey,
+    },
+
+    /// Token accounts receiving a pool's protocol fee share, default for the
+    /// protocol fee vault (pool admin)
+    FeeRecipients {
+        fee_recipient_a: Pubkey,
+        fee_recipient_b: Pubkey,
+    },
+
+    /// Creator's share of a pool's swap fees and the accounts it is paid into
+    /// (pool creator)
+    CreatorFee {
+        bps: u16,
+        creator_fee_account_a: Pubkey,
+        creator_fee_account_b: Pubkey,
+    },
+
+    /// Governance account of `realm` that becomes a pool's admin (pool admin)
+    GovernanceAdmin { realm: Pubkey, governance: Pubkey },
+
+    /// Per-slot price movement circuit breaker of a pool (pool admin)
+    CircuitBreaker { max_price_change_bps_per_slot: u16 },
+
+    /// Per-trader, per-slot anti-sandwich limits of a pool (pool admin)
+    AntiSandwichLimits {
+        max_trader_slot_volume_bps: u16,
+        block_same_slot_reversal: bool,
+    },
+
+    /// Cap on a single swap's input as a share of the reserve (pool admin)
+    MaxTradeSize { max_trade_bps_of_reserves: u16 },
+
+    /// Cap on a single swap's spot price move (pool admin)
+    MaxPriceImpact { max_price_impact_bps: u16 },
+
+    /// Share of a pool's swap fees accrued to referrers (pool admin)
+    ReferralFee { bps: u16 },
+
+    /// Keeper tip of a pool and the protocol fee share funding it (pool admin)
+    KeeperTips { keeper_tip: u64, share_bps: u16 },
+
+    /// Launch protection window and per-wallet cooldown of a pool (pool admin)
+    LaunchProtection {
+        end_slot: u64,
+        trade_cooldown_slots: u64,
+    },
+
+    /// Token A reward for checkpointing a pool's stale oracle (pool admin)
+    OracleCheckpointReward { reward: u64 },
+
+    /// Emergency withdraw-only mode of a pool (pool admin)
+    WithdrawOnly {
+        withdraw_only: bool,
+        large_withdrawal_delay_secs: i64,
+        small_lp_withdrawal_threshold: u64,
+    },
+
+    /// Oracle deviation guard and insurance surcharge of a pool (pool admin)
+    DeviationGuard {
+        twap_window_secs: i64,
+        max_deviation_bps: u16,
+        incident_deviation_bps: u16,
+        incident_surcharge_bps: u16,
+    },
+
+    /// Minimum amounts of a pool's first deposit (pool admin)
+    MinInitialLiquidity { min_a: u64, min_b: u64 },
+
+    /// `POOL_OP_*` bits of a pool's disabled operations (pool admin)
+    DisabledOperations { disabled_operations: u16 },
+
+    /// Whether a pool's deposits are restricted to whitelisted wallets (pool admin)
+    LpWhitelist { enabled: bool },
+
+    /// Verification level of a pool (pool admin)
+    VerificationLevel { level: VerificationLevel },
+
+    /// Post-deposit withdrawal cooldown of a pool's positions (pool admin)
+    WithdrawCooldown { secs: i64 },
+
+    /// JIT withdrawal window and penalty of a pool's positions (pool admin)
+    JitProtection { window_slots: u64, penalty_bps: u16 },
+}
+
+impl ParamChange {
+    /// Whether the change applies to a pool rather than the global config
+    pub fn targets_pool(&self) -> bool {
+        matches!(
+            self,
+            Self::PoolFee { .. }
+                | Self::DynamicFee { .. }
+                | Self::FeeOnOutput { .. }
+                | Self::ProtocolFee { .. }
+                | Self::FeeRecipients { .. }
+                | Self::CreatorFee { .. }
+                | Self::GovernanceAdmin { .. }
+                | Self::CircuitBreaker { .. }
+                | Self::AntiSandwichLimits { .. }
+                | Self::MaxTradeSize { .. }
+                | Self::MaxPriceImpact { .. }
+                | Self::ReferralFee { .. }
+                | Self::KeeperTips { .. }
+                | Self::LaunchProtection { .. }
+                | Self::OracleCheckpointReward { .. }
+                | Self::WithdrawOnly { .. }
+                | Self::DeviationGuard { .. }
+                | Self::MinInitialLiquidity { .. }
+                | Self::DisabledOperations { .. }
+                | Self::LpWhitelist { .. }
+                | Self::VerificationLevel { .. }
+                | Self::WithdrawCooldown { .. }
+                | Self::JitProtection { .. }
+        )
+    }
+
+    /// Role that queues a pool change: the creator for its own fee share, the
+    /// admin for everything else
+    pub fn pool_proposer(&self, pool: &LiquidityPool) -> Pubkey {
+        match self {
+            Self::CreatorFee { .. } => pool.creator,
+            _ => pool.admin,
+        }
+    }
+
+    /// Reject values the direct setters would reject
+    pub fn validate(&self) -> Result<()> {
+        match *self {
+            Self::ParamChangeDelay { delay_secs } => {
+                require!(delay_secs >= 0, AmmError::InvalidPoolConfig)
+            }
+            Self::MaxCreatorFee { bps } => require!(
+                (bps as u128) <= BASIS_POINTS_DIVISOR,
+                AmmError::CreatorFeeTooHigh
+            ),
+            Self::PoolFee {
+                fee_numerator,
+                fee_denominator,
+            } => LiquidityPool::validate_fee(fee_numerator, fee_denominator)?,
+            Self::DynamicFee {
+                min_bps,
+                max_bps,
+                volatility_bps,
+            } => LiquidityPool::validate_dynamic_fee(min_bps, max_bps, volatility_bps)?,
+            Self::ProtocolFee { share_bps, .. } => require!(
+                (share_bps as u128) <= BASIS_POINTS_DIVISOR,
+                AmmError::InvalidPoolConfig
+            ),
+            Self::CreatorFee { bps, .. } => require!(
+                (bps as u128) <= BASIS_POINTS_DIVISOR,
+                AmmError::CreatorFeeTooHigh
+            ),
+            Self::CircuitBreaker {
+                max_price_change_bps_per_slot: bps,
+            }
+            | Self::AntiSandwichLimits {
+                max_trader_slot_volume_bps: bps,
+                ..
+            }
+            | Self::MaxTradeSize {
+                max_trade_bps_of_reserves: bps,
+            }
+            | Self::MaxPriceImpact {
+                max_price_impact_bps: bps,
+            }
+            | Self::ReferralFee { bps }
+            | Self::KeeperTips { share_bps: bps, .. } => require!(
+                (bps as u128) <= BASIS_POINTS_DIVISOR,
+                AmmError::InvalidPoolConfig
+            ),
+            Self::WithdrawOnly {
+                large_withdrawal_delay_secs,
+                ..
+            } => require!(
+                large_withdrawal_delay_secs >= 0,
+                AmmError::InvalidPoolConfig
+            ),
+            Self::WithdrawCooldown { secs } => require!(secs >= 0, AmmError::InvalidPoolConfig),
+            Self::JitProtection { penalty_bps, .. } => require!(
+                penalty_bps <= MAX_JIT_PENALTY_BPS,
+                AmmError::InvalidPoolConfig
+            ),
+            Self::DeviationGuard {
+                twap_window_secs,
+                max_deviation_bps,
+                incident_deviation_bps,
+                incident_surcharge_bps,
+            } => LiquidityPool::validate_deviation_guard(
+                twap_window_secs,
+                max_deviation_bps,
+                incident_deviation_bps,
+                incident_surcharge_bps,
+            )?,
+            Self::DisabledOperations {
+                disabled_operations,
+            } => require!(
+                disabled_operations & !POOL_OPS_ALL == 0,
+                AmmError::InvalidPoolConfig
+            ),
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+/// Parameter change queued behind the global timelock, public until executed
+#[account]
+#[derive(InitSpace)]
+pub struct PendingParamChange {
+    /// Global config or pool the change applies to
+    pub target: Pubkey,
+
+    /// Authority that queued the change, and must still hold that role to execute it
+    pub proposer: Pubkey,
+
+    /// Index distinguishing changes queued by the same target
+    pub change_id: u64,
+
+    /// The change itself
+    pub change: ParamChange,
+
+    /// Unix timestamp the change was queued at
+    pub queued_at: i64,
+
+    /// Unix timestamp from which the change can be executed
+    pub eta: i64,
+
+    /// Canonical bump of the change PDA
+    pub bump: u8,
+}
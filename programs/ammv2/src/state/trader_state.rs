@@ -0,0 +1,65 @@
+use anchor_lang::prelude::*;
+
+use crate::errors::AmmError;
+use crate::state::LiquidityPool;
+
+/// Per-wallet swap activity used by the anti-sandwich limits
+#[account]
+#[derive(Default, InitSpace)]
+pub struct TraderState {
+    /// Pool this record belongs to
+    pub liquidity_pool: Pubkey,
+
+    /// Wallet whose trades are tracked
+    pub trader: Pubkey,
+
+    /// Slot of the trader's most recent swap
+    pub last_trade_slot: u64,
+
+    /// Input volume swapped during `last_trade_slot`, in bps of the input reserve
+    pub slot_volume_bps: u64,
+
+    /// Direction of the most recent swap (true = token A in, token B out)
+    pub last_trade_a_to_b: bool,
+}
+
+impl TraderState {
+    /// Record a swap and enforce the pool's per-slot trader limits
+    pub fn record_swap(
+        &mut self,
+        pool: &LiquidityPool,
+        slot: u64,
+        a_to_b: bool,
+        volume_bps: u64,
+    ) -> Result<()> {
+        // A zero slot means the wallet has not traded on this pool yet
+        if pool.launch_cooldown_active(slot) && self.last_trade_slot != 0 {
+            let next_allowed_slot = self
+                .last_trade_slot
+                .checked_add(pool.launch_trade_cooldown_slots)
+                .ok_or(AmmError::MathOverflow)?;
+            require!(slot >= next_allowed_slot, AmmError::TradeCooldownActive);
+        }
+
+        if self.last_trade_slot != slot {
+            self.last_trade_slot = slot;
+            self.slot_volume_bps = 0;
+        } else if pool.block_same_slot_reversal != 0 {
+            require!(self.last_trade_a_to_b == a_to_b, AmmError::SameSlotReversal);
+        }
+
+        self.slot_volume_bps = self
+            .slot_volume_bps
+            .checked_add(volume_bps)
+            .ok_or(AmmError::MathOverflow)?;
+        self.last_trade_a_to_b = a_to_b;
+
+        if pool.max_trader_slot_volume_bps > 0 {
+            require!(
+                self.slot_volume_bps <= pool.max_trader_slot_volume_bps as u64,
+                AmmError::TraderVolumeExceeded
+            );
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,51 @@
+use anchor_lang::prelude::*;
+
+use crate::errors::AmmError;
+
+/// Withdrawal limits of one treasury balance: a mint's treasury vault, or the
+/// treasury PDA's own lamports under `Pubkey::default()`
+#[account]
+#[derive(InitSpace)]
+pub struct TreasuryLimits {
+    /// Mint the limits apply to, `Pubkey::default()` for lamports
+    pub mint: Pubkey,
+
+    /// Largest amount a single withdrawal may take (0 = no limit)
+    pub max_withdraw_per_call: u64,
+
+    /// Largest total amount withdrawn within one epoch (0 = no limit)
+    pub max_withdraw_per_epoch: u64,
+
+    /// Epoch `withdrawn_in_epoch` was counted in
+    pub epoch: u64,
+
+    /// Amount withdrawn so far in `epoch`
+    pub withdrawn_in_epoch: u64,
+
+    /// Canonical bump of the limits PDA
+    pub bump: u8,
+}
+
+impl TreasuryLimits {
+    /// Count a withdrawal of `amount` in `epoch`, rejecting it past either limit
+    pub fn record_withdrawal(&mut self, epoch: u64, amount: u64) -> Result<()> {
+        if self.epoch != epoch {
+            self.epoch = epoch;
+            self.withdrawn_in_epoch = 0;
+        }
+        require!(
+            self.max_withdraw_per_call == 0 || amount <= self.max_withdraw_per_call,
+            AmmError::TreasuryWithdrawLimitExceeded
+        );
+        let withdrawn = self
+            .withdrawn_in_epoch
+            .checked_add(amount)
+            .ok_or(AmmError::MathOverflow)?;
+        require!(
+            self.max_withdraw_per_epoch == 0 || withdrawn <= self.max_withdraw_per_epoch,
+            AmmError::TreasuryWithdrawLimitExceeded
+        );
+        self.withdrawn_in_epoch = withdrawn;
+        Ok(())
+    }
+}
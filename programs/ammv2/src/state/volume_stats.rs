@@ -0,0 +1,85 @@
+use anchor_lang::prelude::*;
+
+/// Number of hourly buckets kept in a pool's volume stats ring
+pub const VOLUME_STATS_BUCKETS: usize = 24;
+
+/// Length of one volume stats bucket
+pub const VOLUME_STATS_BUCKET_SECS: i64 = 3_600;
+
+/// Swap totals of one hour
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Copy, Default)]
+pub struct VolumeBucket {
+    /// Hour the totals belong to, as unix timestamp / `VOLUME_STATS_BUCKET_SECS`
+    pub hour: i64,
+
+    /// Token A sold into the pool
+    pub volume_a: u64,
+
+    /// Token B sold into the pool
+    pub volume_b: u64,
+
+    /// Swap fees in token A kept by LPs, net of the protocol and creator shares
+    pub lp_fees_a: u64,
+
+    /// Swap fees in token B kept by LPs
+    pub lp_fees_b: u64,
+}
+
+/// Hourly swap volume and fee totals of one pool, covering the last
+/// `VOLUME_STATS_BUCKETS` hours
+#[account]
+#[derive(InitSpace)]
+pub struct PoolVolumeStats {
+    /// Pool the stats track
+    pub liquidity_pool: Pubkey,
+
+    /// PDA bump
+    pub bump: u8,
+
+    /// Ring of hourly buckets, indexed by hour modulo `VOLUME_STATS_BUCKETS`
+    pub buckets: [VolumeBucket; VOLUME_STATS_BUCKETS],
+}
+
+impl PoolVolumeStats {
+    /// Add a swap of `input_amount` to the current hour's bucket, clearing the
+    /// bucket first when it still holds an older hour. Totals saturate rather
+    /// than fail the swap.
+    pub fn record(
+        &mut self,
+        unix_timestamp: i64,
+        input_is_token_a: bool,
+        input_amount: u64,
+        lp_fee_in_token_a: bool,
+        lp_fee: u64,
+    ) {
+        let hour = unix_timestamp.div_euclid(VOLUME_STATS_BUCKET_SECS);
+        let bucket = &mut self.buckets[hour.rem_euclid(VOLUME_STATS_BUCKETS as i64) as usize];
+        if bucket.hour != hour {
+            *bucket = VolumeBucket {
+                hour,
+                ..Default::default()
+            };
+        }
+
+        let volume = if input_is_token_a {
+            &mut bucket.volume_a
+        } else {
+            &mut bucket.volume_b
+        };
+        *volume = volume.saturating_add(input_amount);
+        let fees = if lp_fee_in_token_a {
+            &mut bucket.lp_fees_a
+        } else {
+            &mut bucket.lp_fees_b
+        };
+        *fees = fees.saturating_add(lp_fee);
+    }
+
+    /// Buckets of the current hour and the `VOLUME_STATS_BUCKETS - 1` before it
+    pub fn recent_buckets(&self, unix_timestamp: i64) -> impl Iterator<Item = &VolumeBucket> {
+        let hour = unix_timestamp.div_euclid(VOLUME_STATS_BUCKET_SECS);
+        self.buckets.iter().filter(move |bucket| {
+            hour - bucket.hour < VOLUME_STATS_BUCKETS as i64 && bucket.hour <= hour
+        })
+    }
+}
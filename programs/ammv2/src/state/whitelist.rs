@@ -0,0 +1,23 @@
+use anchor_lang::prelude::*;
+
+/// Marks a wallet as approved to provide liquidity to a permissioned pool
+#[account]
+#[derive(InitSpace)]
+pub struct LpWhitelistEntry {
+    /// Pool the approval applies to
+    pub liquidity_pool: Pubkey,
+
+    /// Approved LP token owner
+    pub wallet: Pubkey,
+}
+
+/// Marks a wallet as allowed to swap during a pool's whitelist-only launch window
+#[account]
+#[derive(InitSpace)]
+pub struct LaunchWhitelistEntry {
+    /// Pool the approval applies to
+    pub liquidity_pool: Pubkey,
+
+    /// Approved trader
+    pub wallet: Pubkey,
+}
@@ -0,0 +1,345 @@
+//! Compute units of the instructions aggregators route through, measured on
+//! the SBF build of the program in solana-program-test.
+//!
+//! Metering needs the compiled program, so the test is ignored by a plain
+//! `cargo test`; `anchor test` runs it. By hand, run it after `anchor build`,
+//! with the Metaplex metadata program `initialize_pool` calls dumped next to ours:
+//!
+//! ```text
+//! solana program dump -u m metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s \
+//!     target/deploy/mpl_token_metadata.so
+//! cargo test -p ammv2 --test compute_units -- --ignored
+//! ```
+
+// The system and upgradeable loader program ids come from deprecated
+// `solana_program` modules, as in the svm tests
+#![allow(deprecated)]
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::{
+    bpf_loader_upgradeable, instruction::Instruction, program_option::COption, program_pack::Pack,
+    rent::Rent, system_program, sysvar,
+};
+use anchor_lang::{InstructionData, ToAccountMetas};
+use anchor_spl::associated_token::{
+    get_associated_token_address_with_program_id, spl_associated_token_account,
+};
+use anchor_spl::metadata::mpl_token_metadata;
+use anchor_spl::token::spl_token;
+use solana_account::Account;
+use solana_keypair::Keypair;
+use solana_program_test::{BanksClient, ProgramTest};
+use solana_signer::Signer;
+use solana_transaction::Transaction;
+
+/// Compute units each instruction used when last measured, as printed by a
+/// run of this test. `None` fails the run until a measurement is recorded;
+/// re-measure in the change that needs more compute.
+const MEASURED_COMPUTE_UNITS: [(&str, Option<u64>); 4] = [
+    ("initialize_pool", None),
+    ("deposit_liquidity", None),
+    ("swap_tokens", None),
+    ("withdraw_liquidity", None),
+];
+
+/// Headroom over a measurement before it counts as a regression, in bps
+const COMPUTE_UNIT_MARGIN_BPS: u64 = 500;
+
+const FEE_NUMERATOR: u64 = 3;
+const FEE_DENOMINATOR: u64 = 1_000;
+const WALLET_TOKENS: u64 = 1_000_000_000;
+
+fn pda(seeds: &[&[u8]]) -> Pubkey {
+    Pubkey::find_program_address(seeds, &ammv2::ID).0
+}
+
+fn instruction(accounts: impl ToAccountMetas, data: impl InstructionData) -> Instruction {
+    Instruction {
+        program_id: ammv2::ID,
+        accounts: accounts.to_account_metas(None),
+        data: data.data(),
+    }
+}
+
+fn packed_account(data: Vec<u8>, owner: Pubkey) -> Account {
+    Account {
+        lamports: Rent::default().minimum_balance(data.len()),
+        data,
+        owner,
+        executable: false,
+        rent_epoch: 0,
+    }
+}
+
+fn mint_account(supply: u64) -> Account {
+    let mut data = vec![0; spl_token::state::Mint::LEN];
+    spl_token::state::Mint {
+        mint_authority: COption::Some(Pubkey::new_unique()),
+        supply,
+        decimals: 6,
+        is_initialized: true,
+        freeze_authority: COption::None,
+    }
+    .pack_into_slice(&mut data);
+    packed_account(data, spl_token::ID)
+}
+
+fn token_account(mint: Pubkey, owner: Pubkey, amount: u64) -> Account {
+    let mut data = vec![0; spl_token::state::Account::LEN];
+    spl_token::state::Account {
+        mint,
+        owner,
+        amount,
+        state: spl_token::state::AccountState::Initialized,
+        ..spl_token::state::Account::default()
+    }
+    .pack_into_slice(&mut data);
+    packed_account(data, spl_token::ID)
+}
+
+/// Send `ix` signed by `wallet` and return the compute units it consumed
+async fn compute_units(banks: &mut BanksClient, wallet: &Keypair, ix: Instruction) -> u64 {
+    let blockhash = banks.get_latest_blockhash().await.unwrap();
+    let transaction =
+        Transaction::new_signed_with_payer(&[ix], Some(&wallet.pubkey()), &[wallet], blockhash);
+    let outcome = banks
+        .process_transaction_with_metadata(transaction)
+        .await
+        .unwrap();
+    let metadata = outcome.metadata.expect("no transaction metadata");
+    if let Err(err) = outcome.result {
+        panic!("transaction failed: {err}\n{:#?}", metadata.log_messages);
+    }
+    metadata.compute_units_consumed
+}
+
+#[tokio::test]
+#[ignore = "needs the SBF build of the program; see the module docs"]
+async fn core_instructions_stay_within_their_measured_compute_units() {
+    if std::env::var_os("SBF_OUT_DIR").is_none() && std::env::var_os("BPF_OUT_DIR").is_none() {
+        std::env::set_var(
+            "SBF_OUT_DIR",
+            concat!(env!("CARGO_MANIFEST_DIR"), "/../../target/deploy"),
+        );
+    }
+    let mut program_test = ProgramTest::new("ammv2", ammv2::ID, None);
+    program_test.prefer_bpf(true);
+    program_test.add_program("mpl_token_metadata", mpl_token_metadata::ID, None);
+
+    // One wallet is config authority, pool creator and trader
+    let wallet = Keypair::new();
+    let owner = wallet.pubkey();
+    program_test.add_account(owner, Account::new(100_000_000_000, 0, &system_program::ID));
+    let (mint_a, mint_b) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let user_token_a =
+        get_associated_token_address_with_program_id(&owner, &mint_a, &spl_token::ID);
+    let user_token_b =
+        get_associated_token_address_with_program_id(&owner, &mint_b, &spl_token::ID);
+    for (mint, user_token) in [(mint_a, user_token_a), (mint_b, user_token_b)] {
+        program_test.add_account(mint, mint_account(WALLET_TOKENS));
+        program_test.add_account(user_token, token_account(mint, owner, WALLET_TOKENS));
+    }
+
+    // The ProgramData account naming the wallet as upgrade authority
+    let program_data =
+        Pubkey::find_program_address(&[ammv2::ID.as_ref()], &bpf_loader_upgradeable::ID).0;
+    let mut data = vec![3, 0, 0, 0];
+    data.extend_from_slice(&0u64.to_le_bytes());
+    data.push(1);
+    data.extend_from_slice(owner.as_ref());
+    program_test.add_account(
+        program_data,
+        packed_account(data, bpf_loader_upgradeable::ID),
+    );
+
+    let (mut banks, _, _) = program_test.start().await;
+
+    let config = pda(&[b"amm_config"]);
+    let treasury = pda(&[b"treasury"]);
+    let ix = instruction(
+        ammv2::accounts::InitializeAmmConfig {
+            amm_config: config,
+            treasury,
+            authority: owner,
+            program_data,
+            system_program: system_program::ID,
+        },
+        ammv2::instruction::InitializeAmmConfig {
+            pool_creation_fee_lamports: 0,
+        },
+    );
+    compute_units(&mut banks, &wallet, ix).await;
+
+    let pool = pda(&[b"liquidity_pool", mint_a.as_ref(), mint_b.as_ref()]);
+    let authority = pda(&[b"pool_authority", pool.as_ref()]);
+    let vault_a = pda(&[b"token_a_vault", pool.as_ref()]);
+    let vault_b = pda(&[b"token_b_vault", pool.as_ref()]);
+    let lp_mint = pda(&[b"lp_token_mint", pool.as_ref()]);
+    let user_lp = get_associated_token_address_with_program_id(&owner, &lp_mint, &spl_token::ID);
+    let mut measured = Vec::new();
+
+    let ix = instruction(
+        ammv2::accounts::InitializePool {
+            token_a_mint: mint_a,
+            token_b_mint: mint_b,
+            liquidity_pool: pool,
+            oracle: pda(&[b"oracle", pool.as_ref()]),
+            pool_authority: authority,
+            token_a_vault: vault_a,
+            token_b_vault: vault_b,
+            lp_token_mint: lp_mint,
+            lp_token_metadata: Pubkey::find_program_address(
+                &[
+                    b"metadata",
+                    mpl_token_metadata::ID.as_ref(),
+                    lp_mint.as_ref(),
+                ],
+                &mpl_token_metadata::ID,
+            )
+            .0,
+            token_a_metadata: None,
+            token_b_metadata: None,
+            token_a_mint_approval: None,
+            token_b_mint_approval: None,
+            amm_config: config,
+            treasury,
+            payer: owner,
+            token_a_program: spl_token::ID,
+            token_b_program: spl_token::ID,
+            system_program: system_program::ID,
+            token_program: spl_token::ID,
+            associated_token_program: spl_associated_token_account::ID,
+            token_metadata_program: mpl_token_metadata::ID,
+            rent: sysvar::rent::ID,
+        },
+        ammv2::instruction::InitializePool {
+            fee_basis_points_numerator: FEE_NUMERATOR,
+            fee_basis_points_denominator: FEE_DENOMINATOR,
+            lp_metadata_uri: String::new(),
+            launch_whitelist_end_slot: 0,
+        },
+    );
+    measured.push(compute_units(&mut banks, &wallet, ix).await);
+
+    let ix = instruction(
+        ammv2::accounts::DepositLiquidity {
+            liquidity_pool: pool,
+            amm_config: config,
+            pool_authority: authority,
+            token_a_mint: mint_a,
+            token_b_mint: mint_b,
+            token_a_vault: vault_a,
+            token_b_vault: vault_b,
+            lp_token_mint: lp_mint,
+            sub_account: None,
+            user: owner,
+            lp_token_owner: owner,
+            token_program: spl_token::ID,
+            token_a_program: spl_token::ID,
+            token_b_program: spl_token::ID,
+            associated_token_program: spl_associated_token_account::ID,
+            system_program: system_program::ID,
+            user_token_a_account: user_token_a,
+            user_token_b_account: user_token_b,
+            user_lp_token_account: user_lp,
+            lp_whitelist_entry: None,
+            liquidity_position: None,
+            position_lp_vault: None,
+        },
+        ammv2::instruction::DepositLiquidity {
+            token_a_amount: 100_000_000,
+            token_b_amount: 100_000_000,
+            client_order_id: None,
+        },
+    );
+    measured.push(compute_units(&mut banks, &wallet, ix).await);
+
+    let ix = instruction(
+        ammv2::accounts::SwapTokens {
+            liquidity_pool: pool,
+            amm_config: config,
+            pool_authority: authority,
+            input_token_vault: vault_a,
+            output_token_vault: vault_b,
+            input_token_mint: mint_a,
+            output_token_mint: mint_b,
+            user_input_token_account: user_token_a,
+            user: owner,
+            input_token_owner: owner,
+            input_token_program: spl_token::ID,
+            output_token_program: spl_token::ID,
+            associated_token_program: spl_associated_token_account::ID,
+            system_program: system_program::ID,
+            user_output_token_account: user_token_b,
+            oracle: Some(pda(&[b"oracle", pool.as_ref()])),
+            insurance_vault: None,
+            protocol_fee_vault: None,
+            fee_recipient: None,
+            creator_fee_account: None,
+            trader_state: None,
+            launch_whitelist_entry: None,
+            memo_program: None,
+            volume_stats: None,
+            referrer: None,
+            referral_vault: None,
+        },
+        ammv2::instruction::SwapTokens {
+            input_amount: 1_000_000,
+            minimum_output_amount: 0,
+            slippage_bps: None,
+            allow_partial_fill: false,
+            price_limit: None,
+            client_order_id: None,
+        },
+    );
+    measured.push(compute_units(&mut banks, &wallet, ix).await);
+
+    let ix = instruction(
+        ammv2::accounts::WithdrawLiquidity {
+            liquidity_pool: pool,
+            pool_authority: authority,
+            token_a_mint: mint_a,
+            token_b_mint: mint_b,
+            token_a_vault: vault_a,
+            token_b_vault: vault_b,
+            lp_token_mint: lp_mint,
+            sub_account: None,
+            user: owner,
+            lp_token_owner: owner,
+            token_program: spl_token::ID,
+            token_a_program: spl_token::ID,
+            token_b_program: spl_token::ID,
+            user_token_a_account: user_token_a,
+            user_token_b_account: user_token_b,
+            user_lp_token_account: user_lp,
+            memo_program: None,
+            liquidity_position: None,
+            position_lp_vault: None,
+        },
+        ammv2::instruction::WithdrawLiquidity {
+            lp_tokens_to_burn: 1_000_000,
+            note: None,
+            client_order_id: None,
+        },
+    );
+    measured.push(compute_units(&mut banks, &wallet, ix).await);
+
+    let mut failures = Vec::new();
+    for ((name, recorded), units) in MEASURED_COMPUTE_UNITS.into_iter().zip(measured) {
+        println!("{name}: {units} CU");
+        match recorded {
+            None => failures.push(format!(
+                "{name} has no measured compute units; record {units}"
+            )),
+            Some(recorded) => {
+                let budget = recorded + recorded * COMPUTE_UNIT_MARGIN_BPS / 10_000;
+                if units > budget {
+                    failures.push(format!(
+                        "{name} used {units} CU, over its measured {recorded} (budget {budget})"
+                    ));
+                }
+            }
+        }
+    }
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
@@ -0,0 +1,103 @@
+use ammv2::errors::AmmError;
+use ammv2::events::PoolParamsSnapshot;
+use ammv2::state::{ParamChange, POOL_OPS_ALL, POOL_OP_DEPOSIT, POOL_OP_SWAP};
+
+use crate::fixture::{assert_error, instruction, PoolFixture, Side};
+
+#[test]
+fn every_pool_parameter_change_emits_the_full_parameter_set() {
+    let mut fixture = PoolFixture::new();
+    let first = fixture
+        .configure(ammv2::instruction::SetMaxTradeSize {
+            max_trade_bps_of_reserves: 1_000,
+        })
+        .unwrap()
+        .events::<PoolParamsSnapshot>();
+    fixture.svm.warp(5, 2);
+    let second = fixture
+        .configure(ammv2::instruction::SetCircuitBreaker {
+            max_price_change_bps_per_slot: 500,
+        })
+        .unwrap()
+        .events::<PoolParamsSnapshot>();
+    assert_eq!((first.len(), second.len()), (1, 1));
+
+    // The second snapshot restates the earlier change, not just its own delta
+    let snapshot = &second[0];
+    assert_eq!(snapshot.liquidity_pool, fixture.pool);
+    assert_eq!(snapshot.event_seq, first[0].event_seq + 1);
+    assert_eq!(snapshot.slot, fixture.svm.clock().slot);
+    assert_eq!(snapshot.admin, fixture.admin);
+    assert_eq!(snapshot.max_trade_bps_of_reserves, 1_000);
+    assert_eq!(snapshot.max_price_change_bps_per_slot, 500);
+    assert_eq!(snapshot.fee_numerator, fixture.pool_state().fee_numerator);
+
+    // Timelocked changes are snapshotted when they apply
+    let admin = fixture.admin;
+    fixture
+        .queue_change(&admin, 1, ParamChange::LpWhitelist { enabled: true })
+        .unwrap();
+    let executed = fixture
+        .execute_change(&admin, 1)
+        .unwrap()
+        .events::<PoolParamsSnapshot>();
+    assert_eq!(executed.len(), 1);
+    assert!(executed[0].lp_whitelist_enabled);
+    assert_eq!(executed[0].max_trade_bps_of_reserves, 1_000);
+}
+
+#[test]
+fn pool_setters_reject_anyone_but_the_admin() {
+    let mut fixture = PoolFixture::new();
+    let stranger = fixture.user(0, 0).wallet;
+    let ix = instruction(
+        ammv2::accounts::UpdatePoolConfig {
+            admin: stranger,
+            ..fixture.pool_config_accounts()
+        },
+        ammv2::instruction::SetMaxTradeSize {
+            max_trade_bps_of_reserves: 1_000,
+        },
+    );
+    assert_error(fixture.svm.send(&[ix]), AmmError::Unauthorized);
+    assert_eq!(fixture.pool_state().max_trade_bps_of_reserves, 0);
+}
+
+#[test]
+fn disabled_operations_switch_off_swaps_and_deposits_independently() {
+    let mut fixture = PoolFixture::new();
+    let user = fixture.user(10_000_000, 10_000_000);
+    fixture.deposit(&user, 1_000_000, 1_000_000).unwrap();
+
+    fixture
+        .configure(ammv2::instruction::SetDisabledOperations {
+            disabled_operations: POOL_OP_SWAP,
+        })
+        .unwrap();
+    assert_error(
+        fixture.swap(&user, Side::AToB, 1_000, 0),
+        AmmError::OperationDisabled,
+    );
+    fixture.deposit(&user, 1_000, 1_000).unwrap();
+
+    fixture
+        .configure(ammv2::instruction::SetDisabledOperations {
+            disabled_operations: POOL_OP_DEPOSIT,
+        })
+        .unwrap();
+    assert_error(
+        fixture.deposit(&user, 1_000, 1_000),
+        AmmError::OperationDisabled,
+    );
+    fixture.swap(&user, Side::AToB, 1_000, 0).unwrap();
+    // Withdrawals can never be switched off
+    fixture.withdraw(&user, 1_000).unwrap();
+
+    assert_error(
+        fixture.configure(ammv2::instruction::SetDisabledOperations {
+            disabled_operations: POOL_OPS_ALL + 1,
+        }),
+        AmmError::InvalidPoolConfig,
+    );
+    assert_eq!(fixture.pool_state().disabled_operations, POOL_OP_DEPOSIT);
+}
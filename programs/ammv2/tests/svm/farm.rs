@@ -0,0 +1,154 @@
+use ammv2::errors::AmmError;
+use ammv2::state::StakePosition;
+use anchor_lang::prelude::{ProgramError, Pubkey};
+use anchor_lang::solana_program::system_program;
+use anchor_spl::token::spl_token;
+
+use crate::fixture::{
+    assert_error, create_mint, instruction, mint_to, token_balance, PoolFixture, User,
+};
+use crate::runtime::TransactionMeta;
+
+const REWARD_PER_SECOND: u64 = 1_000;
+const STAKE: u64 = 1_000_000;
+
+fn pda(seeds: &[&[u8]]) -> Pubkey {
+    Pubkey::find_program_address(seeds, &ammv2::ID).0
+}
+
+/// A seeded pool whose farm pays `REWARD_PER_SECOND` of the pool's token A, or
+/// of an unrelated mint, with a staker who staked `STAKE` LP tokens 100s ago
+fn staked_farm(reward_in_pool_token: bool) -> (PoolFixture, User, Pubkey, Pubkey) {
+    let mut fixture = PoolFixture::new();
+    let staker = fixture.user(10_000_000, 10_000_000);
+    fixture.deposit(&staker, 10_000_000, 10_000_000).unwrap();
+
+    let reward_mint = if reward_in_pool_token {
+        fixture.mint_a
+    } else {
+        create_mint(&mut fixture.svm, &spl_token::ID, 6)
+    };
+    let farm = pda(&[b"farm", fixture.pool.as_ref()]);
+    let ix = instruction(
+        ammv2::accounts::InitializeFarm {
+            liquidity_pool: fixture.pool,
+            farm,
+            lp_token_mint: fixture.lp_mint,
+            reward_mint,
+            farm_lp_vault: pda(&[b"farm_lp_vault", farm.as_ref()]),
+            farm_reward_vault: pda(&[b"farm_reward_vault", farm.as_ref(), reward_mint.as_ref()]),
+            admin: fixture.admin,
+            token_program: spl_token::ID,
+            system_program: system_program::ID,
+        },
+        ammv2::instruction::InitializeFarm {
+            reward_per_second: REWARD_PER_SECOND,
+        },
+    );
+    fixture.svm.send(&[ix]).unwrap();
+
+    let ix = instruction(
+        ammv2::accounts::ManageStake {
+            amm_config: fixture.config,
+            farm,
+            stake_position: pda(&[b"stake_position", farm.as_ref(), staker.wallet.as_ref()]),
+            lp_token_mint: fixture.lp_mint,
+            farm_lp_vault: pda(&[b"farm_lp_vault", farm.as_ref()]),
+            user_lp_token_account: staker.lp,
+            owner: staker.wallet,
+            token_program: spl_token::ID,
+            system_program: system_program::ID,
+        },
+        ammv2::instruction::StakeLp {
+            amount: STAKE,
+            lock_tier: 0,
+        },
+    );
+    fixture.svm.send(&[ix]).unwrap();
+
+    // Earn rewards for a while and fund the vault paying them out
+    fixture.svm.warp(100, 100);
+    let reward_vault = pda(&[b"farm_reward_vault", farm.as_ref(), reward_mint.as_ref()]);
+    mint_to(&mut fixture.svm, &reward_vault, 1_000_000);
+    (fixture, staker, farm, reward_mint)
+}
+
+fn compound(
+    fixture: &mut PoolFixture,
+    staker: &User,
+    farm: Pubkey,
+    reward_mint: Pubkey,
+) -> Result<TransactionMeta, ProgramError> {
+    let ix = instruction(
+        ammv2::accounts::Compound {
+            farm,
+            stake_position: pda(&[b"stake_position", farm.as_ref(), staker.wallet.as_ref()]),
+            liquidity_pool: fixture.pool,
+            amm_config: fixture.config,
+            pool_authority: fixture.authority,
+            reward_mint,
+            farm_reward_vault: pda(&[b"farm_reward_vault", farm.as_ref(), reward_mint.as_ref()]),
+            token_a_vault: fixture.vault_a,
+            token_b_vault: fixture.vault_b,
+            lp_token_mint: fixture.lp_mint,
+            farm_lp_vault: pda(&[b"farm_lp_vault", farm.as_ref()]),
+            owner: staker.wallet,
+            token_program: spl_token::ID,
+        },
+        ammv2::instruction::Compound {},
+    );
+    fixture.svm.send(&[ix])
+}
+
+#[test]
+fn compound_restakes_pool_token_rewards_as_liquidity() {
+    let (mut fixture, staker, farm, reward_mint) = staked_farm(true);
+    let position_address = pda(&[b"stake_position", farm.as_ref(), staker.wallet.as_ref()]);
+    let farm_lp_vault = pda(&[b"farm_lp_vault", farm.as_ref()]);
+    let reserve_a = token_balance(&fixture.svm, &fixture.vault_a);
+    let reserve_b = token_balance(&fixture.svm, &fixture.vault_b);
+    let lp_supply = fixture.pool_state().total_lp_tokens_issued;
+
+    compound(&mut fixture, &staker, farm, reward_mint).unwrap();
+
+    // 100s at 1_000/s; the half swapped to token B never leaves the pool, so
+    // only the token A reserve grows
+    let payout = 100 * REWARD_PER_SECOND;
+    let position: StakePosition = fixture.svm.get(&position_address);
+    let restaked = position.amount - STAKE;
+    assert!(restaked > 0);
+    assert_eq!(position.pending_rewards[0], 0);
+    assert_eq!(
+        token_balance(&fixture.svm, &farm_lp_vault),
+        STAKE + restaked
+    );
+    assert_eq!(
+        fixture.pool_state().total_lp_tokens_issued,
+        lp_supply + restaked
+    );
+    assert_eq!(
+        token_balance(&fixture.svm, &fixture.vault_a),
+        reserve_a + payout
+    );
+    assert_eq!(token_balance(&fixture.svm, &fixture.vault_b), reserve_b);
+    let pool = fixture.pool_state();
+    assert_eq!(
+        (pool.reserve_a, pool.reserve_b),
+        (reserve_a + payout, reserve_b)
+    );
+
+    // The settled rewards are gone; nothing is left to compound right away
+    assert_error(
+        compound(&mut fixture, &staker, farm, reward_mint),
+        AmmError::InvalidStakeAmount,
+    );
+}
+
+#[test]
+fn compound_rejects_rewards_outside_the_pool_pair() {
+    let (mut fixture, staker, farm, reward_mint) = staked_farm(false);
+    assert_error(
+        compound(&mut fixture, &staker, farm, reward_mint),
+        AmmError::CompoundRewardNotInPool,
+    );
+}
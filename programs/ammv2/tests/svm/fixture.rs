@@ -0,0 +1,805 @@
+//! A funded pool and its traders, with builders for the instructions most tests send
+
+use ammv2::state::{AmmConfig, LiquidityPool, LiquidityPosition, ParamChange, PendingParamChange};
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::{
+    bpf_loader_upgradeable, instruction::Instruction, program_error::ProgramError,
+    program_option::COption, program_pack::Pack, rent::Rent, system_program, sysvar,
+};
+use anchor_lang::{InstructionData, ToAccountMetas};
+use anchor_spl::associated_token::{
+    get_associated_token_address_with_program_id, spl_associated_token_account,
+};
+use anchor_spl::memo::spl_memo;
+use anchor_spl::metadata::mpl_token_metadata;
+use anchor_spl::token::spl_token;
+use anchor_spl::token_2022::spl_token_2022;
+use anchor_spl::token_2022::spl_token_2022::extension::transfer_fee::{
+    TransferFee, TransferFeeAmount, TransferFeeConfig,
+};
+use anchor_spl::token_2022::spl_token_2022::extension::{
+    BaseStateWithExtensions, BaseStateWithExtensionsMut, ExtensionType, StateWithExtensions,
+    StateWithExtensionsMut,
+};
+
+use crate::runtime::{Account, Svm, TransactionMeta};
+
+/// Lamports every new wallet starts with
+pub const WALLET_LAMPORTS: u64 = 100_000_000_000;
+
+/// Swap fee of the default pool, 0.3%
+pub const FEE_NUMERATOR: u64 = 3;
+pub const FEE_DENOMINATOR: u64 = 1_000;
+
+/// Assert that `result` failed with the program error `error`
+#[track_caller]
+pub fn assert_error<T: std::fmt::Debug>(
+    result: std::result::Result<T, ProgramError>,
+    error: impl Into<anchor_lang::error::Error>,
+) {
+    let expected = ProgramError::from(error.into());
+    match result {
+        Ok(value) => panic!("expected {expected:?}, the transaction succeeded: {value:?}"),
+        Err(actual) => assert_eq!(actual, expected),
+    }
+}
+
+/// A wallet with token accounts for both pool tokens and the LP mint
+#[derive(Clone, Copy, Debug)]
+pub struct User {
+    pub wallet: Pubkey,
+    pub token_a: Pubkey,
+    pub token_b: Pubkey,
+    pub lp: Pubkey,
+}
+
+/// Which way a swap trades
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Side {
+    AToB,
+    BToA,
+}
+
+/// One initialized pool over two fresh mints, with the global config in place
+pub struct PoolFixture {
+    pub svm: Svm,
+    /// Config authority, pool creator and pool admin
+    pub admin: Pubkey,
+    pub config: Pubkey,
+    pub treasury: Pubkey,
+    pub pool: Pubkey,
+    pub authority: Pubkey,
+    pub mint_a: Pubkey,
+    pub mint_b: Pubkey,
+    pub token_a_program: Pubkey,
+    pub token_b_program: Pubkey,
+    pub vault_a: Pubkey,
+    pub vault_b: Pubkey,
+    pub lp_mint: Pubkey,
+    pub oracle: Pubkey,
+}
+
+impl PoolFixture {
+    /// Pool of two 6-decimal legacy SPL tokens charging `FEE_NUMERATOR / FEE_DENOMINATOR`
+    pub fn new() -> Self {
+        Self::with_token_programs(spl_token::ID, spl_token::ID)
+    }
+
+    /// Pool whose tokens live under the given token programs
+    pub fn with_token_programs(token_a_program: Pubkey, token_b_program: Pubkey) -> Self {
+        let mut svm = Svm::new();
+        let mint_a = create_mint(&mut svm, &token_a_program, 6);
+        let mint_b = create_mint(&mut svm, &token_b_program, 6);
+        Self::with_mints(svm, mint_a, token_a_program, mint_b, token_b_program)
+    }
+
+    /// Pool of two legacy SPL tokens with the given decimals
+    pub fn with_decimals(decimals_a: u8, decimals_b: u8) -> Self {
+        let mut svm = Svm::new();
+        let mint_a = create_mint(&mut svm, &spl_token::ID, decimals_a);
+        let mint_b = create_mint(&mut svm, &spl_token::ID, decimals_b);
+        Self::with_mints(svm, mint_a, spl_token::ID, mint_b, spl_token::ID)
+    }
+
+    /// Pool whose token A is a Token-2022 mint charging `fee_bps` on transfers
+    pub fn with_transfer_fee_on_a(fee_bps: u16) -> Self {
+        let mut svm = Svm::new();
+        let mint_a = create_transfer_fee_mint(&mut svm, 6, fee_bps);
+        let mint_b = create_mint(&mut svm, &spl_token::ID, 6);
+        Self::with_mints(svm, mint_a, spl_token_2022::ID, mint_b, spl_token::ID)
+    }
+
+    /// Global config and two fresh 6-decimal legacy SPL mints, with the pool
+    /// over them not created yet
+    pub fn without_pool() -> Self {
+        let mut svm = Svm::new();
+        let mint_a = create_mint(&mut svm, &spl_token::ID, 6);
+        let mint_b = create_mint(&mut svm, &spl_token::ID, 6);
+        Self::uninitialized(svm, mint_a, spl_token::ID, mint_b, spl_token::ID)
+    }
+
+    fn with_mints(
+        svm: Svm,
+        mint_a: Pubkey,
+        token_a_program: Pubkey,
+        mint_b: Pubkey,
+        token_b_program: Pubkey,
+    ) -> Self {
+        let mut fixture =
+            Self::uninitialized(svm, mint_a, token_a_program, mint_b, token_b_program);
+        fixture.initialize_pool();
+        fixture
+    }
+
+    fn uninitialized(
+        mut svm: Svm,
+        mint_a: Pubkey,
+        token_a_program: Pubkey,
+        mint_b: Pubkey,
+        token_b_program: Pubkey,
+    ) -> Self {
+        let admin = Pubkey::new_unique();
+        svm.airdrop(&admin, WALLET_LAMPORTS);
+
+        // The pool PDA is derived from (token A, token B) in this order
+
+        let config = Pubkey::find_program_address(&[b"amm_config"], &ammv2::ID).0;
+        let treasury = Pubkey::find_program_address(&[b"treasury"], &ammv2::ID).0;
+        let pool = Pubkey::find_program_address(
+            &[b"liquidity_pool", mint_a.as_ref(), mint_b.as_ref()],
+            &ammv2::ID,
+        )
+        .0;
+        let pda =
+            |prefix: &[u8]| Pubkey::find_program_address(&[prefix, pool.as_ref()], &ammv2::ID).0;
+        let mut fixture = Self {
+            admin,
+            config,
+            treasury,
+            pool,
+            authority: pda(b"pool_authority"),
+            mint_a,
+            mint_b,
+            token_a_program,
+            token_b_program,
+            vault_a: pda(b"token_a_vault"),
+            vault_b: pda(b"token_b_vault"),
+            lp_mint: pda(b"lp_token_mint"),
+            oracle: pda(b"oracle"),
+            svm,
+        };
+        fixture.initialize_config();
+        fixture
+    }
+
+    fn initialize_config(&mut self) {
+        // The loader's ProgramData account naming `admin` as upgrade authority
+        let program_data =
+            Pubkey::find_program_address(&[ammv2::ID.as_ref()], &bpf_loader_upgradeable::ID).0;
+        let mut data = vec![3, 0, 0, 0];
+        data.extend_from_slice(&0u64.to_le_bytes());
+        data.push(1);
+        data.extend_from_slice(self.admin.as_ref());
+        self.svm.set_account(
+            program_data,
+            Account {
+                lamports: Rent::default().minimum_balance(data.len()),
+                data,
+                owner: bpf_loader_upgradeable::ID,
+                executable: false,
+            },
+        );
+
+        let ix = instruction(
+            ammv2::accounts::InitializeAmmConfig {
+                amm_config: self.config,
+                treasury: self.treasury,
+                authority: self.admin,
+                program_data,
+                system_program: system_program::ID,
+            },
+            ammv2::instruction::InitializeAmmConfig {
+                pool_creation_fee_lamports: 0,
+            },
+        );
+        self.svm.send(&[ix]).unwrap();
+    }
+
+    fn initialize_pool(&mut self) {
+        let ix = instruction(
+            self.initialize_pool_accounts(),
+            ammv2::instruction::InitializePool {
+                fee_basis_points_numerator: FEE_NUMERATOR,
+                fee_basis_points_denominator: FEE_DENOMINATOR,
+                lp_metadata_uri: String::new(),
+                launch_whitelist_end_slot: 0,
+            },
+        );
+        self.svm.send(&[ix]).unwrap();
+    }
+
+    /// Accounts creating the pool, with the admin as creator and payer
+    pub fn initialize_pool_accounts(&self) -> ammv2::accounts::InitializePool {
+        ammv2::accounts::InitializePool {
+            token_a_mint: self.mint_a,
+            token_b_mint: self.mint_b,
+            liquidity_pool: self.pool,
+            oracle: self.oracle,
+            pool_authority: self.authority,
+            token_a_vault: self.vault_a,
+            token_b_vault: self.vault_b,
+            lp_token_mint: self.lp_mint,
+            lp_token_metadata: Pubkey::find_program_address(
+                &[
+                    b"metadata",
+                    mpl_token_metadata::ID.as_ref(),
+                    self.lp_mint.as_ref(),
+                ],
+                &mpl_token_metadata::ID,
+            )
+            .0,
+            token_a_metadata: None,
+            token_b_metadata: None,
+            token_a_mint_approval: None,
+            token_b_mint_approval: None,
+            amm_config: self.config,
+            treasury: self.treasury,
+            payer: self.admin,
+            token_a_program: self.token_a_program,
+            token_b_program: self.token_b_program,
+            system_program: system_program::ID,
+            token_program: spl_token::ID,
+            associated_token_program: spl_associated_token_account::ID,
+            token_metadata_program: mpl_token_metadata::ID,
+            rent: sysvar::rent::ID,
+        }
+    }
+
+    /// A new wallet holding `amount_a` of token A and `amount_b` of token B
+    pub fn user(&mut self, amount_a: u64, amount_b: u64) -> User {
+        let wallet = Pubkey::new_unique();
+        self.svm.airdrop(&wallet, WALLET_LAMPORTS);
+        let token_a = create_token_account(
+            &mut self.svm,
+            &self.mint_a,
+            &self.token_a_program,
+            &wallet,
+            amount_a,
+        );
+        let token_b = create_token_account(
+            &mut self.svm,
+            &self.mint_b,
+            &self.token_b_program,
+            &wallet,
+            amount_b,
+        );
+        User {
+            wallet,
+            token_a,
+            token_b,
+            lp: get_associated_token_address_with_program_id(
+                &wallet,
+                &self.lp_mint,
+                &spl_token::ID,
+            ),
+        }
+    }
+
+    pub fn pool_state(&self) -> LiquidityPool {
+        self.svm.get_zero_copy(&self.pool)
+    }
+
+    pub fn config_state(&self) -> AmmConfig {
+        self.svm.get(&self.config)
+    }
+
+    /// Token balance of a token account, zero if it does not exist
+    pub fn balance(&self, token_account: &Pubkey) -> u64 {
+        token_balance(&self.svm, token_account)
+    }
+
+    /// The liquidity position PDA of `owner`
+    pub fn position_address(&self, owner: &Pubkey) -> Pubkey {
+        Pubkey::find_program_address(
+            &[b"liquidity_position", self.pool.as_ref(), owner.as_ref()],
+            &ammv2::ID,
+        )
+        .0
+    }
+
+    pub fn position(&self, owner: &Pubkey) -> LiquidityPosition {
+        self.svm.get(&self.position_address(owner))
+    }
+
+    /// The LP vault PDA of `owner`'s liquidity position
+    pub fn position_lp_vault(&self, owner: &Pubkey) -> Pubkey {
+        let position = self.position_address(owner);
+        Pubkey::find_program_address(&[b"position_lp_vault", position.as_ref()], &ammv2::ID).0
+    }
+
+    pub fn open_position(&mut self, user: &User) {
+        let ix = instruction(
+            ammv2::accounts::OpenPosition {
+                liquidity_pool: self.pool,
+                liquidity_position: self.position_address(&user.wallet),
+                lp_token_mint: self.lp_mint,
+                position_lp_vault: self.position_lp_vault(&user.wallet),
+                owner: user.wallet,
+                token_program: spl_token::ID,
+                system_program: system_program::ID,
+            },
+            ammv2::instruction::OpenPosition {},
+        );
+        self.svm.send(&[ix]).unwrap();
+    }
+
+    pub fn deposit_ix(
+        &self,
+        user: &User,
+        amount_a: u64,
+        amount_b: u64,
+        position: bool,
+    ) -> Instruction {
+        instruction(
+            ammv2::accounts::DepositLiquidity {
+                liquidity_pool: self.pool,
+                amm_config: self.config,
+                pool_authority: self.authority,
+                token_a_mint: self.mint_a,
+                token_b_mint: self.mint_b,
+                token_a_vault: self.vault_a,
+                token_b_vault: self.vault_b,
+                lp_token_mint: self.lp_mint,
+                sub_account: None,
+                user: user.wallet,
+                lp_token_owner: user.wallet,
+                token_program: spl_token::ID,
+                token_a_program: self.token_a_program,
+                token_b_program: self.token_b_program,
+                associated_token_program: spl_associated_token_account::ID,
+                system_program: system_program::ID,
+                user_token_a_account: user.token_a,
+                user_token_b_account: user.token_b,
+                user_lp_token_account: user.lp,
+                lp_whitelist_entry: None,
+                liquidity_position: position.then(|| self.position_address(&user.wallet)),
+                position_lp_vault: position.then(|| self.position_lp_vault(&user.wallet)),
+            },
+            ammv2::instruction::DepositLiquidity {
+                token_a_amount: amount_a,
+                token_b_amount: amount_b,
+                client_order_id: None,
+            },
+        )
+    }
+
+    pub fn deposit(
+        &mut self,
+        user: &User,
+        amount_a: u64,
+        amount_b: u64,
+    ) -> std::result::Result<TransactionMeta, ProgramError> {
+        let ix = self.deposit_ix(user, amount_a, amount_b, false);
+        self.svm.send(&[ix])
+    }
+
+    pub fn withdraw_ix(&self, user: &User, lp_tokens: u64, position: bool) -> Instruction {
+        instruction(
+            ammv2::accounts::WithdrawLiquidity {
+                liquidity_pool: self.pool,
+                pool_authority: self.authority,
+                token_a_mint: self.mint_a,
+                token_b_mint: self.mint_b,
+                token_a_vault: self.vault_a,
+                token_b_vault: self.vault_b,
+                lp_token_mint: self.lp_mint,
+                sub_account: None,
+                user: user.wallet,
+                lp_token_owner: user.wallet,
+                token_program: spl_token::ID,
+                token_a_program: self.token_a_program,
+                token_b_program: self.token_b_program,
+                user_token_a_account: user.token_a,
+                user_token_b_account: user.token_b,
+                user_lp_token_account: user.lp,
+                memo_program: None,
+                liquidity_position: position.then(|| self.position_address(&user.wallet)),
+                position_lp_vault: position.then(|| self.position_lp_vault(&user.wallet)),
+            },
+            ammv2::instruction::WithdrawLiquidity {
+                lp_tokens_to_burn: lp_tokens,
+                note: None,
+                client_order_id: None,
+            },
+        )
+    }
+
+    pub fn withdraw(
+        &mut self,
+        user: &User,
+        lp_tokens: u64,
+    ) -> std::result::Result<TransactionMeta, ProgramError> {
+        let ix = self.withdraw_ix(user, lp_tokens, false);
+        self.svm.send(&[ix])
+    }
+
+    /// Accounts of a plain swap by `user`, with every optional account unset
+    pub fn swap_accounts(&self, user: &User, side: Side) -> ammv2::accounts::SwapTokens {
+        let (input, output) = match side {
+            Side::AToB => (
+                (
+                    self.vault_a,
+                    self.mint_a,
+                    self.token_a_program,
+                    user.token_a,
+                ),
+                (
+                    self.vault_b,
+                    self.mint_b,
+                    self.token_b_program,
+                    user.token_b,
+                ),
+            ),
+            Side::BToA => (
+                (
+                    self.vault_b,
+                    self.mint_b,
+                    self.token_b_program,
+                    user.token_b,
+                ),
+                (
+                    self.vault_a,
+                    self.mint_a,
+                    self.token_a_program,
+                    user.token_a,
+                ),
+            ),
+        };
+        ammv2::accounts::SwapTokens {
+            liquidity_pool: self.pool,
+            amm_config: self.config,
+            pool_authority: self.authority,
+            input_token_vault: input.0,
+            output_token_vault: output.0,
+            input_token_mint: input.1,
+            output_token_mint: output.1,
+            user_input_token_account: input.3,
+            user: user.wallet,
+            input_token_owner: user.wallet,
+            input_token_program: input.2,
+            output_token_program: output.2,
+            associated_token_program: spl_associated_token_account::ID,
+            system_program: system_program::ID,
+            user_output_token_account: output.3,
+            oracle: Some(self.oracle),
+            insurance_vault: None,
+            protocol_fee_vault: None,
+            fee_recipient: None,
+            creator_fee_account: None,
+            trader_state: None,
+            launch_whitelist_entry: None,
+            memo_program: None,
+            volume_stats: None,
+            referrer: None,
+            referral_vault: None,
+        }
+    }
+
+    pub fn swap_ix(
+        &self,
+        user: &User,
+        side: Side,
+        input_amount: u64,
+        minimum_output: u64,
+    ) -> Instruction {
+        instruction(
+            self.swap_accounts(user, side),
+            ammv2::instruction::SwapTokens {
+                input_amount,
+                minimum_output_amount: minimum_output,
+                slippage_bps: None,
+                allow_partial_fill: false,
+                price_limit: None,
+                client_order_id: None,
+            },
+        )
+    }
+
+    pub fn swap(
+        &mut self,
+        user: &User,
+        side: Side,
+        input_amount: u64,
+        minimum_output: u64,
+    ) -> std::result::Result<TransactionMeta, ProgramError> {
+        let ix = self.swap_ix(user, side, input_amount, minimum_output);
+        self.svm.send(&[ix])
+    }
+
+    /// Queue `change` as `proposer`, against the pool if it targets one
+    pub fn queue_change(
+        &mut self,
+        proposer: &Pubkey,
+        change_id: u64,
+        change: ParamChange,
+    ) -> std::result::Result<TransactionMeta, ProgramError> {
+        let ix = instruction(
+            ammv2::accounts::QueueParamChange {
+                amm_config: self.config,
+                liquidity_pool: change.targets_pool().then_some(self.pool),
+                pending_change: self.pending_change_address(proposer, change_id),
+                proposer: *proposer,
+                system_program: system_program::ID,
+            },
+            ammv2::instruction::QueueParamChange { change_id, change },
+        );
+        self.svm.send(&[ix])
+    }
+
+    /// Execute the change `proposer` queued under `change_id`
+    pub fn execute_change(
+        &mut self,
+        proposer: &Pubkey,
+        change_id: u64,
+    ) -> std::result::Result<TransactionMeta, ProgramError> {
+        let pending_change = self.pending_change_address(proposer, change_id);
+        let targets_pool = self
+            .svm
+            .get::<PendingParamChange>(&pending_change)
+            .change
+            .targets_pool();
+        let ix = instruction(
+            ammv2::accounts::ExecuteParamChange {
+                amm_config: self.config,
+                liquidity_pool: targets_pool.then_some(self.pool),
+                pending_change,
+                proposer: *proposer,
+            },
+            ammv2::instruction::ExecuteParamChange {},
+        );
+        self.svm.send(&[ix])
+    }
+
+    pub fn pending_change_address(&self, proposer: &Pubkey, change_id: u64) -> Pubkey {
+        Pubkey::find_program_address(
+            &[b"param_change", proposer.as_ref(), &change_id.to_le_bytes()],
+            &ammv2::ID,
+        )
+        .0
+    }
+
+    /// Turn the global timelock on with `delay_secs`, applied straight away
+    /// while it is still off
+    pub fn enable_timelock(&mut self, delay_secs: i64) {
+        let admin = self.admin;
+        let change = ParamChange::ParamChangeDelay { delay_secs };
+        self.queue_change(&admin, u64::MAX, change).unwrap();
+        self.execute_change(&admin, u64::MAX).unwrap();
+    }
+
+    /// Accounts of a pool admin setter
+    pub fn pool_config_accounts(&self) -> ammv2::accounts::UpdatePoolConfig {
+        ammv2::accounts::UpdatePoolConfig {
+            liquidity_pool: self.pool,
+            amm_config: self.config,
+            admin: self.admin,
+        }
+    }
+
+    /// Send a pool admin setter signed by the admin
+    pub fn configure(
+        &mut self,
+        setter: impl InstructionData,
+    ) -> std::result::Result<TransactionMeta, ProgramError> {
+        let ix = instruction(self.pool_config_accounts(), setter);
+        self.svm.send(&[ix])
+    }
+}
+
+/// Instruction of the program under test
+pub fn instruction(accounts: impl ToAccountMetas, data: impl InstructionData) -> Instruction {
+    Instruction {
+        program_id: ammv2::ID,
+        accounts: accounts.to_account_metas(None),
+        data: data.data(),
+    }
+}
+
+/// An initialized mint with no supply and no freeze authority
+pub fn create_mint(svm: &mut Svm, token_program: &Pubkey, decimals: u8) -> Pubkey {
+    let mint = Pubkey::new_unique();
+    let mut data = vec![0; spl_token::state::Mint::LEN];
+    spl_token::state::Mint {
+        mint_authority: COption::Some(Pubkey::new_unique()),
+        supply: 0,
+        decimals,
+        is_initialized: true,
+        freeze_authority: COption::None,
+    }
+    .pack_into_slice(&mut data);
+    svm.set_account(
+        mint,
+        Account {
+            lamports: Rent::default().minimum_balance(data.len()),
+            data,
+            owner: *token_program,
+            executable: false,
+        },
+    );
+    mint
+}
+
+/// A Token-2022 mint with no supply whose transfers pay `fee_bps`, uncapped
+pub fn create_transfer_fee_mint(svm: &mut Svm, decimals: u8, fee_bps: u16) -> Pubkey {
+    let mint = Pubkey::new_unique();
+    let len = ExtensionType::try_calculate_account_len::<spl_token_2022::state::Mint>(&[
+        ExtensionType::TransferFeeConfig,
+    ])
+    .unwrap();
+    let mut data = vec![0; len];
+    let mut state =
+        StateWithExtensionsMut::<spl_token_2022::state::Mint>::unpack_uninitialized(&mut data)
+            .unwrap();
+    let fee = TransferFee {
+        epoch: 0.into(),
+        maximum_fee: u64::MAX.into(),
+        transfer_fee_basis_points: fee_bps.into(),
+    };
+    *state.init_extension::<TransferFeeConfig>(true).unwrap() = TransferFeeConfig {
+        older_transfer_fee: fee,
+        newer_transfer_fee: fee,
+        ..TransferFeeConfig::default()
+    };
+    state.base = spl_token_2022::state::Mint {
+        mint_authority: COption::Some(Pubkey::new_unique()),
+        supply: 0,
+        decimals,
+        is_initialized: true,
+        freeze_authority: COption::None,
+    };
+    state.pack_base();
+    state.init_account_type().unwrap();
+    svm.set_account(
+        mint,
+        Account {
+            lamports: Rent::default().minimum_balance(data.len()),
+            data,
+            owner: spl_token_2022::ID,
+            executable: false,
+        },
+    );
+    mint
+}
+
+/// The associated token account of `owner` for `mint`, holding `amount`
+/// freshly minted tokens, with whatever extensions the mint requires
+pub fn create_token_account(
+    svm: &mut Svm,
+    mint: &Pubkey,
+    token_program: &Pubkey,
+    owner: &Pubkey,
+    amount: u64,
+) -> Pubkey {
+    let address = get_associated_token_address_with_program_id(owner, mint, token_program);
+    let mint_data = &svm.account(mint).unwrap().data;
+    let extensions = if mint_data.len() > spl_token::state::Mint::LEN {
+        let mint_state =
+            StateWithExtensions::<spl_token_2022::state::Mint>::unpack(mint_data).unwrap();
+        ExtensionType::get_required_init_account_extensions(
+            &mint_state.get_extension_types().unwrap(),
+        )
+    } else {
+        Vec::new()
+    };
+    let base = spl_token_2022::state::Account {
+        mint: *mint,
+        owner: *owner,
+        amount,
+        state: spl_token_2022::state::AccountState::Initialized,
+        ..spl_token_2022::state::Account::default()
+    };
+    let data = if extensions.is_empty() {
+        let mut data = vec![0; spl_token::state::Account::LEN];
+        base.pack_into_slice(&mut data);
+        data
+    } else {
+        let len =
+            ExtensionType::try_calculate_account_len::<spl_token_2022::state::Account>(&extensions)
+                .unwrap();
+        let mut data = vec![0; len];
+        let mut state =
+            StateWithExtensionsMut::<spl_token_2022::state::Account>::unpack_uninitialized(
+                &mut data,
+            )
+            .unwrap();
+        for extension in &extensions {
+            assert_eq!(*extension, ExtensionType::TransferFeeAmount);
+            state.init_extension::<TransferFeeAmount>(true).unwrap();
+        }
+        state.base = base;
+        state.pack_base();
+        state.init_account_type().unwrap();
+        data
+    };
+    svm.set_account(
+        address,
+        Account {
+            lamports: Rent::default().minimum_balance(data.len()),
+            data,
+            owner: *token_program,
+            executable: false,
+        },
+    );
+    mint_supply(svm, mint, amount);
+    address
+}
+
+/// Add `amount` to the tokens of an existing token account
+pub fn mint_to(svm: &mut Svm, token_account: &Pubkey, amount: u64) {
+    let mut account = svm.account(token_account).unwrap().clone();
+    let data = &mut account.data[..spl_token::state::Account::LEN];
+    let mut state = spl_token::state::Account::unpack_from_slice(data).unwrap();
+    state.amount += amount;
+    state.pack_into_slice(data);
+    svm.set_account(*token_account, account);
+    mint_supply(svm, &state.mint, amount);
+}
+
+/// Move `amount` tokens between two token accounts of the same mint, as a
+/// transfer signed by the owner of `from` would
+pub fn transfer(svm: &mut Svm, from: &Pubkey, to: &Pubkey, amount: u64) {
+    for (token_account, credit) in [(from, false), (to, true)] {
+        let mut account = svm.account(token_account).unwrap().clone();
+        let data = &mut account.data[..spl_token::state::Account::LEN];
+        let mut state = spl_token::state::Account::unpack_from_slice(data).unwrap();
+        state.amount = if credit {
+            state.amount + amount
+        } else {
+            state.amount - amount
+        };
+        state.pack_into_slice(data);
+        svm.set_account(*token_account, account);
+    }
+}
+
+/// Let `delegate` spend `amount` of a token account's tokens
+pub fn approve(svm: &mut Svm, token_account: &Pubkey, delegate: &Pubkey, amount: u64) {
+    let mut account = svm.account(token_account).unwrap().clone();
+    let data = &mut account.data[..spl_token::state::Account::LEN];
+    let mut state = spl_token::state::Account::unpack_from_slice(data).unwrap();
+    state.delegate = COption::Some(*delegate);
+    state.delegated_amount = amount;
+    state.pack_into_slice(data);
+    svm.set_account(*token_account, account);
+}
+
+fn mint_supply(svm: &mut Svm, mint: &Pubkey, amount: u64) {
+    let mut account = svm.account(mint).unwrap().clone();
+    let data = &mut account.data[..spl_token::state::Mint::LEN];
+    let mut state = spl_token::state::Mint::unpack_from_slice(data).unwrap();
+    state.supply += amount;
+    state.pack_into_slice(data);
+    svm.set_account(*mint, account);
+}
+
+pub fn token_balance(svm: &Svm, token_account: &Pubkey) -> u64 {
+    svm.account(token_account).map_or(0, |account| {
+        spl_token::state::Account::unpack_from_slice(
+            &account.data[..spl_token::state::Account::LEN],
+        )
+        .unwrap()
+        .amount
+    })
+}
+
+pub fn mint_supply_of(svm: &Svm, mint: &Pubkey) -> u64 {
+    let account = svm.account(mint).unwrap();
+    spl_token::state::Mint::unpack_from_slice(&account.data[..spl_token::state::Mint::LEN])
+        .unwrap()
+        .supply
+}
+
+/// Memo program id, for instructions that take an optional memo program
+pub fn memo_program() -> Pubkey {
+    spl_memo::ID
+}
@@ -0,0 +1,254 @@
+use ammv2::errors::AmmError;
+use ammv2::state::{Farm, Gauge, GaugeController, GaugeVoter};
+use anchor_lang::prelude::{ProgramError, Pubkey};
+use anchor_lang::solana_program::system_program;
+use anchor_spl::token::spl_token;
+
+use crate::fixture::{
+    assert_error, create_mint, create_token_account, instruction, PoolFixture, WALLET_LAMPORTS,
+};
+use crate::runtime::TransactionMeta;
+
+const EMISSION_PER_SECOND: u64 = 1_000;
+const EPOCH_SECS: i64 = 86_400;
+
+/// A pool farm with a gauge, under a controller emitting `EMISSION_PER_SECOND`
+struct GaugeFixture {
+    fixture: PoolFixture,
+    governance_mint: Pubkey,
+    controller: Pubkey,
+    farm: Pubkey,
+    gauge: Pubkey,
+}
+
+/// A wallet holding governance tokens
+struct Voter {
+    wallet: Pubkey,
+    token_account: Pubkey,
+}
+
+fn pda(seeds: &[&[u8]]) -> Pubkey {
+    Pubkey::find_program_address(seeds, &ammv2::ID).0
+}
+
+impl GaugeFixture {
+    fn new() -> Self {
+        let mut fixture = PoolFixture::new();
+        let reward_mint = create_mint(&mut fixture.svm, &spl_token::ID, 6);
+        let governance_mint = create_mint(&mut fixture.svm, &spl_token::ID, 6);
+        let farm = pda(&[b"farm", fixture.pool.as_ref()]);
+        let ix = instruction(
+            ammv2::accounts::InitializeFarm {
+                liquidity_pool: fixture.pool,
+                farm,
+                lp_token_mint: fixture.lp_mint,
+                reward_mint,
+                farm_lp_vault: pda(&[b"farm_lp_vault", farm.as_ref()]),
+                farm_reward_vault: pda(&[
+                    b"farm_reward_vault",
+                    farm.as_ref(),
+                    reward_mint.as_ref(),
+                ]),
+                admin: fixture.admin,
+                token_program: spl_token::ID,
+                system_program: system_program::ID,
+            },
+            ammv2::instruction::InitializeFarm {
+                reward_per_second: 0,
+            },
+        );
+        fixture.svm.send(&[ix]).unwrap();
+
+        let controller = pda(&[b"gauge_controller"]);
+        let ix = instruction(
+            ammv2::accounts::InitializeGaugeController {
+                amm_config: fixture.config,
+                gauge_controller: controller,
+                governance_mint,
+                reward_mint,
+                authority: fixture.admin,
+                system_program: system_program::ID,
+            },
+            ammv2::instruction::InitializeGaugeController {
+                emission_per_second: EMISSION_PER_SECOND,
+                epoch_duration_secs: EPOCH_SECS,
+            },
+        );
+        fixture.svm.send(&[ix]).unwrap();
+
+        let gauge = pda(&[b"gauge", farm.as_ref()]);
+        let ix = instruction(
+            ammv2::accounts::CreateGauge {
+                amm_config: fixture.config,
+                gauge_controller: controller,
+                farm,
+                gauge,
+                authority: fixture.admin,
+                system_program: system_program::ID,
+            },
+            ammv2::instruction::CreateGauge {},
+        );
+        fixture.svm.send(&[ix]).unwrap();
+
+        Self {
+            fixture,
+            governance_mint,
+            controller,
+            farm,
+            gauge,
+        }
+    }
+
+    fn voter(&mut self, amount: u64) -> Voter {
+        let wallet = Pubkey::new_unique();
+        self.fixture.svm.airdrop(&wallet, WALLET_LAMPORTS);
+        let token_account = create_token_account(
+            &mut self.fixture.svm,
+            &self.governance_mint,
+            &spl_token::ID,
+            &wallet,
+            amount,
+        );
+        Voter {
+            wallet,
+            token_account,
+        }
+    }
+
+    fn voter_account(&self, voter: &Voter) -> GaugeVoter {
+        self.fixture
+            .svm
+            .get(&pda(&[b"gauge_voter", voter.wallet.as_ref()]))
+    }
+
+    fn lock(&mut self, voter: &Voter, amount: u64) -> Result<TransactionMeta, ProgramError> {
+        let ix = instruction(
+            ammv2::accounts::LockGaugeVotes {
+                gauge_controller: self.controller,
+                gauge_voter: pda(&[b"gauge_voter", voter.wallet.as_ref()]),
+                gauge_voter_vault: pda(&[b"gauge_voter_vault", voter.wallet.as_ref()]),
+                governance_mint: self.governance_mint,
+                voter_token_account: voter.token_account,
+                voter: voter.wallet,
+                token_program: spl_token::ID,
+                system_program: system_program::ID,
+            },
+            ammv2::instruction::LockGaugeVotes { amount },
+        );
+        self.fixture.svm.send(&[ix])
+    }
+
+    fn unlock(&mut self, voter: &Voter, amount: u64) -> Result<TransactionMeta, ProgramError> {
+        let ix = instruction(
+            ammv2::accounts::UnlockGaugeVotes {
+                gauge_controller: self.controller,
+                gauge_voter: pda(&[b"gauge_voter", voter.wallet.as_ref()]),
+                gauge_voter_vault: pda(&[b"gauge_voter_vault", voter.wallet.as_ref()]),
+                governance_mint: self.governance_mint,
+                voter_token_account: voter.token_account,
+                voter: voter.wallet,
+                token_program: spl_token::ID,
+            },
+            ammv2::instruction::UnlockGaugeVotes { amount },
+        );
+        self.fixture.svm.send(&[ix])
+    }
+
+    fn vote(&mut self, voter: &Voter, votes: u64) -> Result<TransactionMeta, ProgramError> {
+        let ix = instruction(
+            ammv2::accounts::VoteGauge {
+                gauge_controller: self.controller,
+                gauge: self.gauge,
+                gauge_voter: pda(&[b"gauge_voter", voter.wallet.as_ref()]),
+                voter: voter.wallet,
+            },
+            ammv2::instruction::VoteGauge { votes },
+        );
+        self.fixture.svm.send(&[ix])
+    }
+
+    /// Run out the current epoch and close it
+    fn advance_epoch(&mut self) {
+        self.fixture.svm.warp(1, EPOCH_SECS);
+        let ix = instruction(
+            ammv2::accounts::AdvanceGaugeEpoch {
+                gauge_controller: self.controller,
+            },
+            ammv2::instruction::AdvanceGaugeEpoch {},
+        );
+        self.fixture.svm.send(&[ix]).unwrap();
+    }
+
+    fn checkpoint(&mut self) -> Result<TransactionMeta, ProgramError> {
+        let ix = instruction(
+            ammv2::accounts::CheckpointGauge {
+                gauge_controller: self.controller,
+                gauge: self.gauge,
+                farm: self.farm,
+            },
+            ammv2::instruction::CheckpointGauge {},
+        );
+        self.fixture.svm.send(&[ix])
+    }
+}
+
+#[test]
+fn votes_and_re_votes_are_capped_by_the_locked_tokens() {
+    let mut gauges = GaugeFixture::new();
+    let alice = gauges.voter(1_000);
+    gauges.lock(&alice, 1_000).unwrap();
+    assert_eq!(gauges.fixture.balance(&alice.token_account), 0);
+
+    gauges.vote(&alice, 600).unwrap();
+    gauges.vote(&alice, 400).unwrap();
+    assert_error(gauges.vote(&alice, 1), AmmError::InsufficientVotingPower);
+
+    let gauge: Gauge = gauges.fixture.svm.get(&gauges.gauge);
+    assert_eq!(gauge.votes, 1_000);
+    let controller: GaugeController = gauges.fixture.svm.get(&gauges.controller);
+    assert_eq!(controller.epoch_total_votes, 1_000);
+    assert_eq!(gauges.voter_account(&alice).used_votes, 1_000);
+}
+
+#[test]
+fn voted_tokens_stay_locked_until_the_epoch_ends() {
+    let mut gauges = GaugeFixture::new();
+    let alice = gauges.voter(1_000);
+    gauges.lock(&alice, 1_000).unwrap();
+
+    // Tokens that have not voted yet can leave
+    gauges.unlock(&alice, 400).unwrap();
+    assert_eq!(gauges.fixture.balance(&alice.token_account), 400);
+
+    // Once they vote they cannot be moved to another wallet to vote again
+    gauges.vote(&alice, 600).unwrap();
+    assert_error(gauges.unlock(&alice, 600), AmmError::GaugeVotesLocked);
+
+    gauges.advance_epoch();
+    gauges.unlock(&alice, 600).unwrap();
+    assert_eq!(gauges.fixture.balance(&alice.token_account), 1_000);
+    assert_eq!(gauges.voter_account(&alice).locked, 0);
+}
+
+#[test]
+fn checkpoint_sets_the_farm_rate_from_the_last_epochs_votes() {
+    let mut gauges = GaugeFixture::new();
+    let alice = gauges.voter(1_000);
+    gauges.lock(&alice, 1_000).unwrap();
+    gauges.vote(&alice, 1_000).unwrap();
+
+    // The epoch being voted on has no rate to apply yet
+    assert_error(gauges.checkpoint(), AmmError::GaugeAlreadyCheckpointed);
+
+    gauges.advance_epoch();
+    gauges.checkpoint().unwrap();
+    let farm: Farm = gauges.fixture.svm.get(&gauges.farm);
+    assert_eq!(farm.rewards[0].reward_per_second, EMISSION_PER_SECOND);
+    assert_error(gauges.checkpoint(), AmmError::GaugeAlreadyCheckpointed);
+
+    // An epoch without votes turns the stream off
+    gauges.advance_epoch();
+    gauges.checkpoint().unwrap();
+    let farm: Farm = gauges.fixture.svm.get(&gauges.farm);
+    assert_eq!(farm.rewards[0].reward_per_second, 0);
+}
@@ -0,0 +1,142 @@
+use ammv2::errors::AmmError;
+use anchor_lang::prelude::{ProgramError, Pubkey};
+use anchor_lang::solana_program::system_program;
+
+use crate::fixture::{assert_error, instruction, PoolFixture, Side, User};
+use crate::runtime::TransactionMeta;
+
+/// Seconds of oracle history the deviation guard averages over
+const TWAP_WINDOW_SECS: i64 = 600;
+
+/// A seeded pool flagging incidents at 5% off the TWAP, rejecting swaps at
+/// 10%, and surcharging incident swaps 1% into its insurance fund
+fn insured_pool() -> (PoolFixture, Pubkey, Pubkey) {
+    let mut fixture = PoolFixture::new();
+    let provider = fixture.user(10_000_000, 10_000_000);
+    fixture.deposit(&provider, 10_000_000, 10_000_000).unwrap();
+    fixture
+        .configure(ammv2::instruction::SetDeviationGuard {
+            deviation_twap_window_secs: TWAP_WINDOW_SECS,
+            max_oracle_deviation_bps: 1_000,
+            incident_deviation_bps: 500,
+            incident_surcharge_bps: 100,
+        })
+        .unwrap();
+
+    let insurance_vault = |mint: &Pubkey| {
+        Pubkey::find_program_address(
+            &[b"insurance_vault", fixture.pool.as_ref(), mint.as_ref()],
+            &ammv2::ID,
+        )
+        .0
+    };
+    let (insurance_a, insurance_b) = (
+        insurance_vault(&fixture.mint_a),
+        insurance_vault(&fixture.mint_b),
+    );
+    let ix = instruction(
+        ammv2::accounts::InitializeInsuranceFund {
+            liquidity_pool: fixture.pool,
+            pool_authority: fixture.authority,
+            token_a_mint: fixture.mint_a,
+            token_b_mint: fixture.mint_b,
+            insurance_vault_a: insurance_a,
+            insurance_vault_b: insurance_b,
+            payer: fixture.admin,
+            token_program: fixture.token_a_program,
+            system_program: system_program::ID,
+        },
+        ammv2::instruction::InitializeInsuranceFund {},
+    );
+    fixture.svm.send(&[ix]).unwrap();
+
+    // Give the oracle a window of history at the seeded 1:1 price
+    let warmup = fixture.user(1_000, 0);
+    fixture.swap(&warmup, Side::AToB, 1_000, 0).unwrap();
+    fixture.svm.warp(1_500, TWAP_WINDOW_SECS);
+    (fixture, insurance_a, insurance_b)
+}
+
+fn insured_swap(
+    fixture: &mut PoolFixture,
+    trader: &User,
+    input_amount: u64,
+    insurance_vault: Pubkey,
+) -> std::result::Result<TransactionMeta, ProgramError> {
+    let mut accounts = fixture.swap_accounts(trader, Side::AToB);
+    accounts.insurance_vault = Some(insurance_vault);
+    let ix = instruction(
+        accounts,
+        ammv2::instruction::SwapTokens {
+            input_amount,
+            minimum_output_amount: 0,
+            slippage_bps: None,
+            allow_partial_fill: false,
+            price_limit: None,
+            client_order_id: None,
+        },
+    );
+    fixture.svm.send(&[ix])
+}
+
+#[test]
+fn swaps_inside_the_band_pay_no_surcharge() {
+    let (mut fixture, insurance_a, _) = insured_pool();
+    let trader = fixture.user(100_000, 0);
+
+    // 1% of the reserve moves the price about 2%
+    fixture.swap(&trader, Side::AToB, 100_000, 0).unwrap();
+    assert_eq!(fixture.balance(&trader.token_a), 0);
+    assert_eq!(fixture.pool_state().deviation_incident_active, 0);
+    assert_eq!(fixture.balance(&insurance_a), 0);
+}
+
+#[test]
+fn incident_swaps_fund_the_insurance_vault_until_the_admin_deploys_it() {
+    let (mut fixture, insurance_a, insurance_b) = insured_pool();
+
+    // 3% of the reserve moves the price about 6%, inside the 10% limit but
+    // past the 5% incident threshold, so 1% of the input is surcharged
+    let trader = fixture.user(303_000, 0);
+    assert_error(
+        fixture.swap(&trader, Side::AToB, 300_000, 0),
+        AmmError::InsuranceVaultRequired,
+    );
+    insured_swap(&mut fixture, &trader, 300_000, insurance_a).unwrap();
+    assert_eq!(fixture.pool_state().deviation_incident_active, 1);
+    assert_eq!(fixture.balance(&trader.token_a), 0);
+    assert_eq!(fixture.balance(&insurance_a), 3_000);
+    let pool = fixture.pool_state();
+    assert_eq!(pool.reserve_a, 10_000_000 + 1_000 + 300_000);
+    assert_eq!(fixture.balance(&fixture.vault_a), pool.reserve_a);
+
+    // Pushing past the hard limit is rejected outright
+    let whale = fixture.user(1_000_000, 0);
+    assert_error(
+        insured_swap(&mut fixture, &whale, 500_000, insurance_a),
+        AmmError::OracleDeviationExceeded,
+    );
+
+    // The reserve goes back into the pool on the admin's call
+    let ix = instruction(
+        ammv2::accounts::DeployInsuranceFund {
+            liquidity_pool: fixture.pool,
+            pool_authority: fixture.authority,
+            token_a_mint: fixture.mint_a,
+            token_b_mint: fixture.mint_b,
+            insurance_vault_a: insurance_a,
+            insurance_vault_b: insurance_b,
+            token_a_vault: fixture.vault_a,
+            token_b_vault: fixture.vault_b,
+            admin: fixture.admin,
+            token_program: fixture.token_a_program,
+        },
+        ammv2::instruction::DeployInsuranceFund {
+            amount_a: 3_000,
+            amount_b: 0,
+        },
+    );
+    fixture.svm.send(&[ix]).unwrap();
+    assert_eq!(fixture.balance(&insurance_a), 0);
+    assert_eq!(fixture.pool_state().reserve_a, pool.reserve_a + 3_000);
+}
@@ -0,0 +1,261 @@
+use ammv2::errors::AmmError;
+use ammv2::events::PoolMigrated;
+use ammv2::instructions::CreatorLpLock;
+use ammv2::state::{LiquidityPool, LIQUIDITY_POOL_VERSION};
+use anchor_lang::error::ErrorCode;
+use anchor_lang::prelude::{ProgramError, Pubkey};
+use anchor_lang::solana_program::{
+    instruction::Instruction, program_option::COption, program_pack::Pack, rent::Rent,
+    system_program,
+};
+use anchor_lang::Space;
+use anchor_spl::associated_token::{
+    get_associated_token_address_with_program_id, spl_associated_token_account,
+};
+use anchor_spl::token::spl_token;
+
+use crate::fixture::{
+    assert_error, create_token_account, instruction, mint_supply_of, PoolFixture, Side,
+    FEE_DENOMINATOR, FEE_NUMERATOR,
+};
+use crate::runtime::TransactionMeta;
+
+/// Bytes of the keeper tip fields added in the latest layout version
+const KEEPER_TIP_FIELDS_LEN: usize = 24;
+
+fn migrate(
+    fixture: &mut PoolFixture,
+    token_a_vault: Pubkey,
+) -> std::result::Result<TransactionMeta, ProgramError> {
+    let ix = instruction(
+        ammv2::accounts::MigratePool {
+            liquidity_pool: fixture.pool,
+            token_a_vault,
+            token_b_vault: fixture.vault_b,
+            payer: fixture.admin,
+            system_program: system_program::ID,
+        },
+        ammv2::instruction::MigratePool {},
+    );
+    fixture.svm.send(&[ix])
+}
+
+/// Rewrite the seeded pool as a version 1 account: shorter, with no tracked
+/// reserves, stored bumps or fee growth
+fn downgrade_to_version_1(fixture: &mut PoolFixture) {
+    let mut pool = fixture.pool_state();
+    pool.version = 1;
+    pool.reserve_a = 0;
+    pool.reserve_b = 0;
+    pool.authority_bump = 0;
+    pool.token_a_vault_bump = 0;
+    pool.token_b_vault_bump = 0;
+    pool.lp_token_mint_bump = 0;
+    pool.fee_growth_per_lp = 0;
+
+    let mut account = fixture.svm.account(&fixture.pool).unwrap().clone();
+    account.data[8..].copy_from_slice(bytemuck::bytes_of(&pool));
+    account
+        .data
+        .truncate(8 + LiquidityPool::INIT_SPACE - KEEPER_TIP_FIELDS_LEN);
+    account.lamports = Rent::default().minimum_balance(account.data.len());
+    fixture.svm.set_account(fixture.pool, account);
+}
+
+#[test]
+fn migrate_pool_reallocs_and_backfills_an_older_pool() {
+    let mut fixture = PoolFixture::new();
+    let provider = fixture.user(10_000_000, 10_000_000);
+    fixture.deposit(&provider, 10_000_000, 10_000_000).unwrap();
+    let current = fixture.pool_state();
+    let vault_a = fixture.vault_a;
+    downgrade_to_version_1(&mut fixture);
+
+    let meta = migrate(&mut fixture, vault_a).unwrap();
+    let event = &meta.events::<PoolMigrated>()[0];
+    assert_eq!(
+        (event.from_version, event.to_version),
+        (1, LIQUIDITY_POOL_VERSION)
+    );
+    let account = fixture.svm.account(&fixture.pool).unwrap();
+    assert_eq!(account.data.len(), 8 + LiquidityPool::INIT_SPACE);
+    assert_eq!(
+        account.lamports,
+        Rent::default().minimum_balance(account.data.len())
+    );
+    let pool = fixture.pool_state();
+    assert_eq!(pool.version, LIQUIDITY_POOL_VERSION);
+    assert_eq!((pool.reserve_a, pool.reserve_b), (10_000_000, 10_000_000));
+    assert_eq!(pool.authority_bump, current.authority_bump);
+    assert_eq!(pool.lp_token_mint_bump, current.lp_token_mint_bump);
+    assert_eq!(pool.fee_growth_per_lp, current.fee_growth_per_lp);
+    assert_eq!(pool.keeper_tip, 0);
+
+    // The migrated pool trades, and migrating again changes nothing
+    let trader = fixture.user(100_000, 0);
+    fixture.swap(&trader, Side::AToB, 100_000, 0).unwrap();
+    let meta = migrate(&mut fixture, vault_a).unwrap();
+    assert!(meta.events::<PoolMigrated>().is_empty());
+}
+
+#[test]
+fn migrate_pool_rejects_a_vault_at_the_wrong_address() {
+    let mut fixture = PoolFixture::new();
+    downgrade_to_version_1(&mut fixture);
+    let vault_b = fixture.vault_b;
+
+    // Token B's vault passed as token A's
+    assert_error(migrate(&mut fixture, vault_b), ErrorCode::ConstraintSeeds);
+    let account = fixture.svm.account(&fixture.pool).unwrap();
+    assert_eq!(
+        account.data.len(),
+        8 + LiquidityPool::INIT_SPACE - KEEPER_TIP_FIELDS_LEN
+    );
+}
+
+fn close_pool_ix(fixture: &PoolFixture, recipient: Pubkey) -> Instruction {
+    instruction(
+        ammv2::accounts::ClosePool {
+            liquidity_pool: fixture.pool,
+            oracle: Some(fixture.oracle),
+            pool_authority: fixture.authority,
+            token_a_vault: fixture.vault_a,
+            token_b_vault: fixture.vault_b,
+            lp_token_mint: fixture.lp_mint,
+            admin: fixture.admin,
+            recipient,
+            token_program: spl_token::ID,
+            token_a_program: fixture.token_a_program,
+            token_b_program: fixture.token_b_program,
+        },
+        ammv2::instruction::ClosePool {},
+    )
+}
+
+#[test]
+fn close_pool_returns_the_rent_of_an_emptied_pool() {
+    let mut fixture = PoolFixture::new();
+    let alice = fixture.user(1_000_000, 4_000_000);
+    fixture.deposit(&alice, 1_000_000, 4_000_000).unwrap();
+    let recipient = Pubkey::new_unique();
+
+    let ix = close_pool_ix(&fixture, recipient);
+    assert_error(fixture.svm.send(&[ix]), AmmError::PoolNotEmpty);
+
+    fixture.withdraw(&alice, 2_000_000).unwrap();
+    let closed = [
+        fixture.pool,
+        fixture.oracle,
+        fixture.vault_a,
+        fixture.vault_b,
+    ];
+    let rent: u64 = closed.iter().map(|key| fixture.svm.lamports(key)).sum();
+    let ix = close_pool_ix(&fixture, recipient);
+    fixture.svm.send(&[ix]).unwrap();
+
+    assert_eq!(fixture.svm.lamports(&recipient), rent);
+    for key in closed {
+        assert!(fixture.svm.account(&key).is_none());
+    }
+    let lp_mint = &fixture.svm.account(&fixture.lp_mint).unwrap().data;
+    let lp_mint = spl_token::state::Mint::unpack_from_slice(lp_mint).unwrap();
+    assert_eq!(lp_mint.mint_authority, COption::None);
+}
+
+/// `initialize_pool_with_liquidity` by the admin, locking `lock_bps` of its LP
+fn create_seeded_pool_ix(
+    fixture: &PoolFixture,
+    token_a_amount: u64,
+    token_b_amount: u64,
+    lock_bps: Option<u16>,
+) -> Instruction {
+    let pda = |prefix: &[u8]| {
+        Pubkey::find_program_address(
+            &[prefix, fixture.pool.as_ref(), fixture.admin.as_ref()],
+            &ammv2::ID,
+        )
+        .0
+    };
+    let admin_ata = |mint: &Pubkey| {
+        get_associated_token_address_with_program_id(&fixture.admin, mint, &spl_token::ID)
+    };
+    instruction(
+        ammv2::accounts::InitializePoolWithLiquidity {
+            initialize: fixture.initialize_pool_accounts(),
+            user_token_a_account: admin_ata(&fixture.mint_a),
+            user_token_b_account: admin_ata(&fixture.mint_b),
+            user_lp_token_account: admin_ata(&fixture.lp_mint),
+            lp_lock: lock_bps.map(|_| pda(b"lp_lock")),
+            lp_lock_vault: lock_bps.map(|_| pda(b"lp_lock_vault")),
+            associated_token_program: spl_associated_token_account::ID,
+            system_program: system_program::ID,
+        },
+        ammv2::instruction::InitializePoolWithLiquidity {
+            fee_numerator: FEE_NUMERATOR,
+            fee_denominator: FEE_DENOMINATOR,
+            token_a_amount,
+            token_b_amount,
+            lp_metadata_uri: String::new(),
+            launch_whitelist_end_slot: 0,
+            creator_lock: lock_bps.map(|lock_bps| CreatorLpLock {
+                lock_bps,
+                lock_duration_secs: 86_400,
+            }),
+        },
+    )
+}
+
+#[test]
+fn pool_is_created_and_seeded_in_one_instruction() {
+    let mut fixture = PoolFixture::without_pool();
+    let admin = fixture.admin;
+    let (mint_a, mint_b) = (fixture.mint_a, fixture.mint_b);
+    create_token_account(&mut fixture.svm, &mint_a, &spl_token::ID, &admin, 1_000_000);
+    create_token_account(&mut fixture.svm, &mint_b, &spl_token::ID, &admin, 4_000_000);
+
+    let ix = create_seeded_pool_ix(&fixture, 1_000_000, 4_000_000, Some(2_500));
+    fixture.svm.send(&[ix]).unwrap();
+
+    // sqrt(1e6 * 4e6) = 2e6, a quarter of it locked
+    let pool = fixture.pool_state();
+    assert_eq!((pool.reserve_a, pool.reserve_b), (1_000_000, 4_000_000));
+    assert_eq!(pool.total_lp_tokens_issued, 2_000_000);
+    assert_eq!(pool.creator_locked_lp, 500_000);
+    let admin_lp =
+        get_associated_token_address_with_program_id(&admin, &fixture.lp_mint, &spl_token::ID);
+    assert_eq!(fixture.balance(&admin_lp), 1_500_000);
+    let lp_lock_vault = Pubkey::find_program_address(
+        &[b"lp_lock_vault", fixture.pool.as_ref(), admin.as_ref()],
+        &ammv2::ID,
+    )
+    .0;
+    assert_eq!(fixture.balance(&lp_lock_vault), 500_000);
+    assert_eq!(mint_supply_of(&fixture.svm, &fixture.lp_mint), 2_000_000);
+
+    let trader = fixture.user(100_000, 0);
+    fixture.swap(&trader, Side::AToB, 100_000, 0).unwrap();
+}
+
+#[test]
+fn failed_seeding_leaves_no_pool_behind() {
+    let mut fixture = PoolFixture::without_pool();
+    let admin = fixture.admin;
+    let (mint_a, mint_b) = (fixture.mint_a, fixture.mint_b);
+    create_token_account(&mut fixture.svm, &mint_a, &spl_token::ID, &admin, 1_000_000);
+    create_token_account(&mut fixture.svm, &mint_b, &spl_token::ID, &admin, 3_999_999);
+
+    let ix = create_seeded_pool_ix(&fixture, 1_000_000, 4_000_000, None);
+    assert_error(fixture.svm.send(&[ix]), AmmError::InsufficientBalance);
+    assert!(fixture.svm.account(&fixture.pool).is_none());
+    assert!(fixture.svm.account(&fixture.vault_a).is_none());
+}
+
+#[test]
+fn lp_mint_decimals_follow_the_pair() {
+    for (decimals_a, decimals_b, lp_decimals) in [(6, 6, 6), (6, 9, 7), (9, 0, 4)] {
+        let fixture = PoolFixture::with_decimals(decimals_a, decimals_b);
+        let lp_mint = &fixture.svm.account(&fixture.lp_mint).unwrap().data;
+        let lp_mint = spl_token::state::Mint::unpack_from_slice(lp_mint).unwrap();
+        assert_eq!(lp_mint.decimals, lp_decimals, "{decimals_a}/{decimals_b}");
+    }
+}
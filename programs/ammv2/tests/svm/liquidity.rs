@@ -0,0 +1,268 @@
+use ammv2::errors::AmmError;
+use ammv2::events::LpSupplySynced;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::InstructionData;
+
+use crate::fixture::{assert_error, instruction, mint_supply_of, mint_to, PoolFixture, Side, User};
+
+#[test]
+fn first_deposit_mints_geometric_mean_and_prices_later_deposits_on_reserves() {
+    let mut fixture = PoolFixture::new();
+    let alice = fixture.user(1_000_000, 4_000_000);
+    fixture.deposit(&alice, 1_000_000, 4_000_000).unwrap();
+
+    // sqrt(1e6 * 4e6) = 2e6
+    let pool = fixture.pool_state();
+    assert_eq!(fixture.balance(&alice.lp), 2_000_000);
+    assert_eq!(pool.total_lp_tokens_issued, 2_000_000);
+    assert_eq!(mint_supply_of(&fixture.svm, &fixture.lp_mint), 2_000_000);
+    assert_eq!((pool.reserve_a, pool.reserve_b), (1_000_000, 4_000_000));
+    assert_eq!(fixture.balance(&fixture.vault_a), 1_000_000);
+    assert_eq!(fixture.balance(&fixture.vault_b), 4_000_000);
+
+    // A quarter of the reserves earns a quarter of the supply
+    let bob = fixture.user(250_000, 1_000_000);
+    fixture.deposit(&bob, 250_000, 1_000_000).unwrap();
+    assert_eq!(fixture.balance(&bob.lp), 500_000);
+    assert_eq!(fixture.pool_state().total_lp_tokens_issued, 2_500_000);
+}
+
+#[test]
+fn withdraw_pays_the_share_of_reserves_and_burns_the_lp() {
+    let mut fixture = PoolFixture::new();
+    let alice = fixture.user(1_000_000, 4_000_000);
+    fixture.deposit(&alice, 1_000_000, 4_000_000).unwrap();
+
+    fixture.withdraw(&alice, 500_000).unwrap();
+    assert_eq!(fixture.balance(&alice.lp), 1_500_000);
+    assert_eq!(fixture.balance(&alice.token_a), 250_000);
+    assert_eq!(fixture.balance(&alice.token_b), 1_000_000);
+    let pool = fixture.pool_state();
+    assert_eq!(pool.total_lp_tokens_issued, 1_500_000);
+    assert_eq!((pool.reserve_a, pool.reserve_b), (750_000, 3_000_000));
+    assert_eq!(mint_supply_of(&fixture.svm, &fixture.lp_mint), 1_500_000);
+}
+
+#[test]
+fn withdraw_rejects_burning_more_lp_than_held() {
+    let mut fixture = PoolFixture::new();
+    let alice = fixture.user(1_000_000, 4_000_000);
+    fixture.deposit(&alice, 1_000_000, 4_000_000).unwrap();
+
+    assert_error(
+        fixture.withdraw(&alice, 2_000_001),
+        AmmError::InsufficientLpTokens,
+    );
+    assert_eq!(fixture.balance(&alice.lp), 2_000_000);
+}
+
+#[test]
+fn withdrawing_the_whole_supply_burns_it_and_empties_the_vaults() {
+    let mut fixture = PoolFixture::new();
+    let alice = fixture.user(1_000_000, 4_000_000);
+    fixture.deposit(&alice, 1_000_000, 4_000_000).unwrap();
+
+    fixture.withdraw(&alice, 2_000_000).unwrap();
+    assert_eq!(fixture.balance(&alice.lp), 0);
+    assert_eq!(mint_supply_of(&fixture.svm, &fixture.lp_mint), 0);
+    assert_eq!(fixture.balance(&alice.token_a), 1_000_000);
+    assert_eq!(fixture.balance(&alice.token_b), 4_000_000);
+    assert_eq!(fixture.balance(&fixture.vault_a), 0);
+    assert_eq!(fixture.balance(&fixture.vault_b), 0);
+    let pool = fixture.pool_state();
+    assert_eq!(pool.total_lp_tokens_issued, 0);
+    assert_eq!((pool.reserve_a, pool.reserve_b), (0, 0));
+
+    // With its LP burned, the account can take nothing more out
+    assert_error(fixture.withdraw(&alice, 1), AmmError::InsufficientLpTokens);
+    assert_eq!(fixture.balance(&alice.token_a), 1_000_000);
+}
+
+#[test]
+fn first_deposit_rejects_an_empty_side() {
+    let mut fixture = PoolFixture::new();
+    let alice = fixture.user(1_000_000, 4_000_000);
+    assert_error(
+        fixture.deposit(&alice, 0, 4_000_000),
+        AmmError::InvalidLpTokenAmount,
+    );
+}
+
+#[test]
+fn deposit_prices_lp_on_what_the_vaults_received() {
+    // 1% of every token A transfer is withheld as a Token-2022 fee
+    let mut fixture = PoolFixture::with_transfer_fee_on_a(100);
+    let alice = fixture.user(1_000_000, 1_000_000);
+    fixture.deposit(&alice, 1_000_000, 1_000_000).unwrap();
+
+    // sqrt(990_000 * 1_000_000)
+    let supply = fixture.pool_state().total_lp_tokens_issued;
+    assert_eq!(supply, 994_987);
+    assert_eq!(fixture.pool_state().reserve_a, 990_000);
+
+    let bob = fixture.user(100_000, 200_000);
+    fixture.deposit(&bob, 100_000, 200_000).unwrap();
+    let pool = fixture.pool_state();
+    assert_eq!(pool.reserve_a, 990_000 + 99_000);
+    assert_eq!(fixture.balance(&fixture.vault_a), pool.reserve_a);
+    assert_eq!(
+        fixture.balance(&bob.lp),
+        99_000 * supply / 990_000,
+        "LP is priced on the 99_000 received, not the 100_000 sent"
+    );
+}
+
+#[test]
+fn deposit_and_withdraw_only_touch_the_signers_token_accounts() {
+    let mut fixture = PoolFixture::new();
+    let alice = fixture.user(1_000_000, 4_000_000);
+    let bob = fixture.user(1_000_000, 4_000_000);
+
+    // Alice cannot spend from, or be paid out to, Bob's accounts
+    let spend_bobs = User {
+        token_a: bob.token_a,
+        ..alice
+    };
+    assert_error(
+        fixture.deposit(&spend_bobs, 1_000_000, 4_000_000),
+        AmmError::InvalidTokenAccountOwner,
+    );
+    assert_eq!(fixture.balance(&bob.token_a), 1_000_000);
+
+    fixture.deposit(&alice, 1_000_000, 4_000_000).unwrap();
+    let pay_bob = User {
+        token_b: bob.token_b,
+        ..alice
+    };
+    assert_error(
+        fixture.withdraw(&pay_bob, 500_000),
+        AmmError::InvalidTokenAccountOwner,
+    );
+    fixture.withdraw(&alice, 500_000).unwrap();
+    assert_eq!(fixture.balance(&alice.token_b), 1_000_000);
+    assert_eq!(fixture.balance(&bob.token_b), 4_000_000);
+}
+
+#[test]
+fn lp_supply_drift_blocks_liquidity_until_synced() {
+    let mut fixture = PoolFixture::new();
+    let alice = fixture.user(1_000_000, 4_000_000);
+    fixture.deposit(&alice, 1_000_000, 4_000_000).unwrap();
+    let sync = instruction(
+        ammv2::accounts::SyncLpSupply {
+            liquidity_pool: fixture.pool,
+            lp_token_mint: fixture.lp_mint,
+        },
+        ammv2::instruction::SyncLpSupply {},
+    );
+    let in_sync = fixture.svm.send(std::slice::from_ref(&sync)).unwrap();
+    assert!(in_sync.events::<LpSupplySynced>().is_empty());
+
+    // LP minted outside the pool's accounting
+    let alice_lp = alice.lp;
+    mint_to(&mut fixture.svm, &alice_lp, 500_000);
+    let bob = fixture.user(250_000, 1_000_000);
+    assert_error(
+        fixture.deposit(&bob, 250_000, 1_000_000),
+        AmmError::LpSupplyMismatch,
+    );
+    assert_error(
+        fixture.withdraw(&alice, 100_000),
+        AmmError::LpSupplyMismatch,
+    );
+
+    let synced = fixture.svm.send(&[sync]).unwrap();
+    let event = &synced.events::<LpSupplySynced>()[0];
+    assert_eq!(event.previous_lp_tokens_issued, 2_000_000);
+    assert_eq!(event.lp_supply, 2_500_000);
+    assert_eq!(fixture.pool_state().total_lp_tokens_issued, 2_500_000);
+
+    // A quarter of the reserves now earns a quarter of the larger supply
+    fixture.deposit(&bob, 250_000, 1_000_000).unwrap();
+    assert_eq!(fixture.balance(&bob.lp), 625_000);
+}
+
+/// `deposit_chunked` of `user`, quoted at the `total_a : total_b` ratio
+fn chunked_deposit_ix(
+    fixture: &PoolFixture,
+    user: &User,
+    total_a: u64,
+    total_b: u64,
+    max_ratio_drift_bps: u16,
+) -> Instruction {
+    Instruction {
+        data: ammv2::instruction::DepositChunked {
+            total_a,
+            total_b,
+            max_ratio_drift_bps,
+            client_order_id: None,
+        }
+        .data(),
+        ..fixture.deposit_ix(user, total_a, total_b, false)
+    }
+}
+
+#[test]
+fn chunked_deposit_reverts_once_the_ratio_drifted_past_the_bound() {
+    let mut fixture = PoolFixture::new();
+    let alice = fixture.user(1_000_000, 4_000_000);
+    fixture.deposit(&alice, 1_000_000, 4_000_000).unwrap();
+
+    // Quoted at the pool's own ratio
+    let bob = fixture.user(500_000, 2_000_000);
+    let ix = chunked_deposit_ix(&fixture, &bob, 250_000, 1_000_000, 0);
+    fixture.svm.send(&[ix]).unwrap();
+    assert_eq!(fixture.balance(&bob.lp), 500_000);
+
+    // A swap moves the price about 2% away from Bob's next quote
+    let trader = fixture.user(12_500, 0);
+    fixture.swap(&trader, Side::AToB, 12_500, 0).unwrap();
+    let ix = chunked_deposit_ix(&fixture, &bob, 250_000, 1_000_000, 100);
+    assert_error(fixture.svm.send(&[ix]), AmmError::RatioDriftExceeded);
+    assert_eq!(fixture.balance(&bob.token_a), 250_000);
+
+    let ix = chunked_deposit_ix(&fixture, &bob, 250_000, 1_000_000, 300);
+    fixture.svm.send(&[ix]).unwrap();
+    assert!(fixture.balance(&bob.lp) > 500_000);
+}
+
+#[test]
+fn first_deposit_must_meet_the_minimum_initial_liquidity() {
+    let mut fixture = PoolFixture::new();
+    fixture
+        .configure(ammv2::instruction::SetMinInitialLiquidity {
+            min_initial_deposit_a: 1_000_000,
+            min_initial_deposit_b: 4_000_000,
+        })
+        .unwrap();
+    let alice = fixture.user(1_000_000, 4_000_000);
+
+    assert_error(
+        fixture.deposit(&alice, 1_000_000, 3_999_999),
+        AmmError::InitialLiquidityTooSmall,
+    );
+    assert_eq!(fixture.balance(&alice.token_b), 4_000_000);
+    fixture.deposit(&alice, 1_000_000, 4_000_000).unwrap();
+
+    // Only the seeding deposit is bound by the minimum
+    let bob = fixture.user(1_000, 4_000);
+    fixture.deposit(&bob, 1_000, 4_000).unwrap();
+    assert_eq!(fixture.balance(&bob.lp), 2_000);
+}
+
+#[test]
+fn mixed_decimal_deposits_require_the_ratio_rounded_up() {
+    // Just under 2 token B (9 decimals) per token A (6 decimals)
+    let mut fixture = PoolFixture::with_decimals(6, 9);
+    let alice = fixture.user(1_000_000, 1_999_999_999);
+    fixture.deposit(&alice, 1_000_000, 1_999_999_999).unwrap();
+
+    // 3 base units of A need 5_999.999997 of B, rounded up to 6_000
+    let bob = fixture.user(3, 10_000);
+    assert_error(fixture.deposit(&bob, 3, 5_999), AmmError::RatioMismatch);
+    fixture.deposit(&bob, 3, 10_000).unwrap();
+    assert_eq!(fixture.balance(&bob.token_a), 0);
+    assert_eq!(fixture.balance(&bob.token_b), 10_000 - 6_000);
+    let pool = fixture.pool_state();
+    assert_eq!((pool.reserve_a, pool.reserve_b), (1_000_003, 2_000_005_999));
+}
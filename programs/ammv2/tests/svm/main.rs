@@ -0,0 +1,22 @@
+//! End-to-end tests of the program, run in-process by `cargo test`. See
+//! `runtime` for how instructions and their CPIs execute.
+
+// The system and upgradeable loader program ids still come from deprecated
+// `solana_program` modules
+#![allow(deprecated)]
+
+mod admin;
+mod farm;
+// Not every test module uses every helper
+#[allow(dead_code)]
+mod fixture;
+mod gauge;
+mod insurance;
+mod lifecycle;
+mod liquidity;
+mod position;
+#[allow(dead_code)]
+mod runtime;
+mod swap;
+mod timelock;
+mod trader_limits;
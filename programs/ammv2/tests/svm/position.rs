@@ -0,0 +1,170 @@
+use ammv2::errors::AmmError;
+use ammv2::state::MAX_JIT_PENALTY_BPS;
+use anchor_lang::prelude::ProgramError;
+
+use crate::fixture::{assert_error, instruction, transfer, PoolFixture, User};
+
+const COOLDOWN_SECS: i64 = 3_600;
+
+fn set_withdraw_cooldown(fixture: &mut PoolFixture, withdraw_cooldown_secs: i64) {
+    let ix = instruction(
+        fixture.pool_config_accounts(),
+        ammv2::instruction::SetWithdrawCooldown {
+            withdraw_cooldown_secs,
+        },
+    );
+    fixture.svm.send(&[ix]).unwrap();
+}
+
+fn set_jit_protection(
+    fixture: &mut PoolFixture,
+    jit_window_slots: u64,
+    jit_penalty_bps: u16,
+) -> Result<(), ProgramError> {
+    let ix = instruction(
+        fixture.pool_config_accounts(),
+        ammv2::instruction::SetJitProtection {
+            jit_window_slots,
+            jit_penalty_bps,
+        },
+    );
+    fixture.svm.send(&[ix]).map(|_| ())
+}
+
+/// A wallet that opened a position and deposited through it
+fn position_user(fixture: &mut PoolFixture, amount_a: u64, amount_b: u64) -> User {
+    let user = fixture.user(amount_a, amount_b);
+    fixture.open_position(&user);
+    let ix = fixture.deposit_ix(&user, amount_a, amount_b, true);
+    fixture.svm.send(&[ix]).unwrap();
+    user
+}
+
+#[test]
+fn position_deposits_and_withdrawals_go_through_the_position_vault() {
+    let mut fixture = PoolFixture::new();
+    let alice = position_user(&mut fixture, 1_000_000, 4_000_000);
+
+    let vault = fixture.position_lp_vault(&alice.wallet);
+    assert_eq!(fixture.balance(&vault), 2_000_000);
+    assert_eq!(fixture.balance(&alice.lp), 0);
+    assert_eq!(fixture.position(&alice.wallet).lp_tokens, 2_000_000);
+
+    let ix = fixture.withdraw_ix(&alice, 500_000, true);
+    fixture.svm.send(&[ix]).unwrap();
+    assert_eq!(fixture.balance(&vault), 1_500_000);
+    assert_eq!(fixture.balance(&alice.token_a), 250_000);
+    assert_eq!(fixture.balance(&alice.token_b), 1_000_000);
+    assert_eq!(fixture.position(&alice.wallet).lp_tokens, 1_500_000);
+}
+
+#[test]
+fn cooldown_holds_position_withdrawals_after_each_deposit() {
+    let mut fixture = PoolFixture::new();
+    set_withdraw_cooldown(&mut fixture, COOLDOWN_SECS);
+    let alice = position_user(&mut fixture, 1_000_000, 4_000_000);
+
+    let ix = fixture.withdraw_ix(&alice, 1_000_000, true);
+    assert_error(
+        fixture.svm.send(std::slice::from_ref(&ix)),
+        AmmError::WithdrawCooldownActive,
+    );
+    fixture.svm.warp(1, COOLDOWN_SECS);
+    fixture.svm.send(&[ix]).unwrap();
+    assert_eq!(fixture.position(&alice.wallet).lp_tokens, 1_000_000);
+}
+
+#[test]
+fn untracked_lp_stays_withdrawable_once_the_pool_tracks_positions() {
+    let mut fixture = PoolFixture::new();
+    let bob = fixture.user(2_000_000, 8_000_000);
+    fixture.deposit(&bob, 1_000_000, 4_000_000).unwrap();
+    set_withdraw_cooldown(&mut fixture, COOLDOWN_SECS);
+
+    // New liquidity has to come in through a position...
+    assert_error(
+        fixture.deposit(&bob, 1_000_000, 4_000_000),
+        AmmError::LiquidityPositionRequired,
+    );
+
+    // ...while LP minted before the cooldown was set withdraws in full
+    fixture.withdraw(&bob, 2_000_000).unwrap();
+    assert_eq!(fixture.balance(&bob.lp), 0);
+    assert_eq!(fixture.balance(&bob.token_a), 2_000_000);
+    assert_eq!(fixture.balance(&bob.token_b), 8_000_000);
+}
+
+#[test]
+fn lp_moved_into_an_aged_position_is_not_tracked() {
+    let mut fixture = PoolFixture::new();
+    let bob = fixture.user(1_000_000, 4_000_000);
+    fixture.deposit(&bob, 1_000_000, 4_000_000).unwrap();
+    set_withdraw_cooldown(&mut fixture, COOLDOWN_SECS);
+    let alice = position_user(&mut fixture, 1_000_000, 4_000_000);
+    fixture.svm.warp(1, COOLDOWN_SECS);
+
+    // Bob's LP sent into Alice's vault does not extend what her aged position covers
+    let vault = fixture.position_lp_vault(&alice.wallet);
+    transfer(&mut fixture.svm, &bob.lp, &vault, 2_000_000);
+    let ix = fixture.withdraw_ix(&alice, 2_000_001, true);
+    assert_error(fixture.svm.send(&[ix]), AmmError::BurnExceedsPosition);
+
+    let ix = fixture.withdraw_ix(&alice, 2_000_000, true);
+    fixture.svm.send(&[ix]).unwrap();
+    assert_eq!(fixture.balance(&vault), 2_000_000);
+    assert_eq!(fixture.position(&alice.wallet).tracked_lp(), 0);
+}
+
+#[test]
+fn position_withdraw_rejects_another_positions_vault() {
+    let mut fixture = PoolFixture::new();
+    let alice = position_user(&mut fixture, 1_000_000, 4_000_000);
+    let bob = position_user(&mut fixture, 1_000_000, 4_000_000);
+
+    let alice_vault = fixture.position_lp_vault(&alice.wallet);
+    let bob_vault = fixture.position_lp_vault(&bob.wallet);
+    let mut ix = fixture.withdraw_ix(&alice, 1_000_000, true);
+    for meta in &mut ix.accounts {
+        if meta.pubkey == alice_vault {
+            meta.pubkey = bob_vault;
+        }
+    }
+    assert_error(fixture.svm.send(&[ix]), AmmError::InvalidPositionLpVault);
+    assert_eq!(fixture.balance(&bob_vault), 2_000_000);
+}
+
+#[test]
+fn jit_penalty_stays_in_the_pool_until_the_window_has_passed() {
+    let mut fixture = PoolFixture::new();
+    set_jit_protection(&mut fixture, 100, MAX_JIT_PENALTY_BPS).unwrap();
+    let alice = position_user(&mut fixture, 1_000_000, 4_000_000);
+
+    // Half the LP is worth 500_000 A and 2_000_000 B, less the 10% penalty
+    let ix = fixture.withdraw_ix(&alice, 1_000_000, true);
+    fixture.svm.send(&[ix]).unwrap();
+    assert_eq!(fixture.balance(&alice.token_a), 450_000);
+    assert_eq!(fixture.balance(&alice.token_b), 1_800_000);
+    let pool = fixture.pool_state();
+    assert_eq!((pool.reserve_a, pool.reserve_b), (550_000, 2_200_000));
+
+    // Past the window the rest, penalty included, comes out in full
+    fixture.svm.warp(100, 40);
+    let ix = fixture.withdraw_ix(&alice, 1_000_000, true);
+    fixture.svm.send(&[ix]).unwrap();
+    assert_eq!(fixture.balance(&alice.token_a), 1_000_000);
+    assert_eq!(fixture.balance(&alice.token_b), 4_000_000);
+}
+
+#[test]
+fn jit_penalty_is_capped_below_a_full_block() {
+    let mut fixture = PoolFixture::new();
+    assert_error(
+        set_jit_protection(&mut fixture, 100, MAX_JIT_PENALTY_BPS + 1),
+        AmmError::InvalidPoolConfig,
+    );
+    assert_error(
+        set_jit_protection(&mut fixture, 100, 10_000),
+        AmmError::InvalidPoolConfig,
+    );
+    assert_eq!(fixture.pool_state().jit_penalty_bps, 0);
+}
@@ -0,0 +1,669 @@
+//! Minimal in-process Solana runtime. Instructions run natively: the program's
+//! Anchor entry point is called directly, and its CPIs are dispatched through
+//! the syscall stubs to the real SPL Token, Token-2022 and associated token
+//! account processors, plus small stand-ins for the system, memo and Metaplex
+//! programs. A failed transaction leaves every account untouched.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::{
+    bpf_loader_upgradeable,
+    entrypoint::{ProgramResult, MAX_PERMITTED_DATA_INCREASE},
+    instruction::Instruction,
+    program_error::{ProgramError, INVALID_ARGUMENT, UNSUPPORTED_SYSVAR},
+    program_stubs::SyscallStubs,
+    rent::Rent,
+    system_program,
+};
+use anchor_lang::{AnchorDeserialize, Discriminator};
+use anchor_spl::associated_token::spl_associated_token_account;
+use anchor_spl::memo::spl_memo;
+use anchor_spl::metadata::mpl_token_metadata;
+use anchor_spl::token::spl_token;
+use anchor_spl::token_2022::spl_token_2022;
+
+/// Unix timestamp every test starts at
+pub const GENESIS_TIMESTAMP: i64 = 1_700_000_000;
+
+/// Stored state of one account
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Account {
+    pub lamports: u64,
+    pub data: Vec<u8>,
+    pub owner: Pubkey,
+    pub executable: bool,
+}
+
+/// What a successful transaction left behind
+#[derive(Debug, Default)]
+pub struct TransactionMeta {
+    pub logs: Vec<String>,
+    /// `sol_log_data` payloads, i.e. the Anchor events emitted
+    pub events: Vec<Vec<u8>>,
+    pub return_data: Option<(Pubkey, Vec<u8>)>,
+}
+
+impl TransactionMeta {
+    /// Every event of type `E` the transaction emitted, in order
+    pub fn events<E: AnchorDeserialize + Discriminator>(&self) -> Vec<E> {
+        self.events
+            .iter()
+            .filter_map(|data| data.strip_prefix(E::DISCRIMINATOR))
+            .map(|mut data| E::deserialize(&mut data).unwrap())
+            .collect()
+    }
+
+    /// The value an instruction returned with `set_return_data`
+    pub fn return_value<T: AnchorDeserialize>(&self) -> T {
+        let (_, data) = self.return_data.as_ref().expect("no return data");
+        T::try_from_slice(data).unwrap()
+    }
+}
+
+/// Per-thread execution state the syscall stubs read, so tests can run in parallel
+#[derive(Default)]
+struct ExecutionContext {
+    clock: Clock,
+    program_stack: Vec<Pubkey>,
+    return_data: Option<(Pubkey, Vec<u8>)>,
+    meta: TransactionMeta,
+}
+
+thread_local! {
+    static CONTEXT: RefCell<ExecutionContext> = RefCell::new(ExecutionContext::default());
+}
+
+struct Stubs;
+
+impl SyscallStubs for Stubs {
+    fn sol_log(&self, message: &str) {
+        CONTEXT.with(|context| context.borrow_mut().meta.logs.push(message.to_string()));
+    }
+
+    fn sol_log_compute_units(&self) {}
+
+    fn sol_remaining_compute_units(&self) -> u64 {
+        1_400_000
+    }
+
+    fn sol_invoke_signed(
+        &self,
+        instruction: &Instruction,
+        account_infos: &[AccountInfo],
+        signers_seeds: &[&[&[u8]]],
+    ) -> ProgramResult {
+        invoke(instruction, account_infos, signers_seeds)
+    }
+
+    fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+        let clock = CONTEXT.with(|context| context.borrow().clock.clone());
+        unsafe { *(var_addr as *mut Clock) = clock };
+        0
+    }
+
+    fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
+        unsafe { *(var_addr as *mut Rent) = Rent::default() };
+        0
+    }
+
+    fn sol_get_epoch_schedule_sysvar(&self, var_addr: *mut u8) -> u64 {
+        unsafe { *(var_addr as *mut EpochSchedule) = EpochSchedule::default() };
+        0
+    }
+
+    fn sol_get_sysvar(
+        &self,
+        sysvar_id_addr: *const u8,
+        var_addr: *mut u8,
+        offset: u64,
+        length: u64,
+    ) -> u64 {
+        let id = unsafe { *(sysvar_id_addr as *const Pubkey) };
+        let clock = CONTEXT.with(|context| context.borrow().clock.clone());
+        let Some(data) = sysvar_data(&id, &clock) else {
+            return UNSUPPORTED_SYSVAR;
+        };
+        let (offset, length) = (offset as usize, length as usize);
+        if offset + length > data.len() {
+            return INVALID_ARGUMENT;
+        }
+        unsafe {
+            std::ptr::copy_nonoverlapping(data[offset..].as_ptr(), var_addr, length);
+        }
+        0
+    }
+
+    fn sol_get_return_data(&self) -> Option<(Pubkey, Vec<u8>)> {
+        CONTEXT.with(|context| context.borrow().return_data.clone())
+    }
+
+    fn sol_set_return_data(&self, data: &[u8]) {
+        CONTEXT.with(|context| {
+            let mut context = context.borrow_mut();
+            let program_id = *context.program_stack.last().unwrap();
+            context.return_data = (!data.is_empty()).then(|| (program_id, data.to_vec()));
+        });
+    }
+
+    fn sol_log_data(&self, fields: &[&[u8]]) {
+        CONTEXT.with(|context| {
+            let mut context = context.borrow_mut();
+            context
+                .meta
+                .events
+                .extend(fields.iter().map(|field| field.to_vec()));
+        });
+    }
+
+    fn sol_get_stack_height(&self) -> u64 {
+        CONTEXT.with(|context| context.borrow().program_stack.len() as u64)
+    }
+}
+
+/// Serialized form of the sysvars the runtime provides
+fn sysvar_data(id: &Pubkey, clock: &Clock) -> Option<Vec<u8>> {
+    if *id == anchor_lang::solana_program::sysvar::clock::ID {
+        let mut data = Vec::with_capacity(40);
+        data.extend_from_slice(&clock.slot.to_le_bytes());
+        data.extend_from_slice(&clock.epoch_start_timestamp.to_le_bytes());
+        data.extend_from_slice(&clock.epoch.to_le_bytes());
+        data.extend_from_slice(&clock.leader_schedule_epoch.to_le_bytes());
+        data.extend_from_slice(&clock.unix_timestamp.to_le_bytes());
+        Some(data)
+    } else if *id == anchor_lang::solana_program::sysvar::rent::ID {
+        let rent = Rent::default();
+        let mut data = Vec::with_capacity(17);
+        data.extend_from_slice(&rent.lamports_per_byte_year.to_le_bytes());
+        data.extend_from_slice(&rent.exemption_threshold.to_le_bytes());
+        data.push(rent.burn_percent);
+        Some(data)
+    } else {
+        None
+    }
+}
+
+/// Dispatch a CPI, enforcing that every signer either signed the caller or is
+/// a PDA of the calling program derived from `signers_seeds`
+fn invoke(
+    instruction: &Instruction,
+    account_infos: &[AccountInfo],
+    signers_seeds: &[&[&[u8]]],
+) -> ProgramResult {
+    let caller = CONTEXT.with(|context| *context.borrow().program_stack.last().unwrap());
+    let pda_signers = signers_seeds
+        .iter()
+        .map(|seeds| Pubkey::create_program_address(seeds, &caller))
+        .collect::<std::result::Result<Vec<_>, _>>()
+        .map_err(|_| ProgramError::InvalidSeeds)?;
+
+    let mut callee_infos = Vec::with_capacity(instruction.accounts.len());
+    for meta in &instruction.accounts {
+        let info = account_infos
+            .iter()
+            .find(|info| *info.key == meta.pubkey)
+            .ok_or(ProgramError::NotEnoughAccountKeys)?;
+        if meta.is_signer && !info.is_signer && !pda_signers.contains(&meta.pubkey) {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+        if meta.is_writable && !info.is_writable {
+            return Err(ProgramError::InvalidArgument);
+        }
+        callee_infos.push(AccountInfo {
+            is_signer: meta.is_signer,
+            is_writable: meta.is_writable,
+            ..info.clone()
+        });
+    }
+
+    CONTEXT.with(|context| {
+        let mut context = context.borrow_mut();
+        context.program_stack.push(instruction.program_id);
+        context.return_data = None;
+    });
+    let result = process_native(&instruction.program_id, &callee_infos, &instruction.data);
+    CONTEXT.with(|context| context.borrow_mut().program_stack.pop());
+    result
+}
+
+/// Run one of the programs a CPI may target
+fn process_native(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    if *program_id == spl_token::ID {
+        spl_token::processor::Processor::process(program_id, accounts, data)
+    } else if *program_id == spl_token_2022::ID {
+        spl_token_2022::processor::Processor::process(program_id, accounts, data)
+    } else if *program_id == spl_associated_token_account::ID {
+        spl_associated_token_account::processor::process_instruction(program_id, accounts, data)
+    } else if *program_id == system_program::ID {
+        process_system(accounts, data)
+    } else if *program_id == spl_memo::ID {
+        Ok(())
+    } else if *program_id == mpl_token_metadata::ID {
+        process_metadata(accounts, data)
+    } else {
+        Err(ProgramError::IncorrectProgramId)
+    }
+}
+
+fn read_u64(data: &[u8], offset: usize) -> std::result::Result<u64, ProgramError> {
+    data.get(offset..offset + 8)
+        .map(|bytes| u64::from_le_bytes(bytes.try_into().unwrap()))
+        .ok_or(ProgramError::InvalidInstructionData)
+}
+
+fn read_pubkey(data: &[u8], offset: usize) -> std::result::Result<Pubkey, ProgramError> {
+    data.get(offset..offset + 32)
+        .map(|bytes| Pubkey::try_from(bytes).unwrap())
+        .ok_or(ProgramError::InvalidInstructionData)
+}
+
+fn move_lamports(from: &AccountInfo, to: &AccountInfo, lamports: u64) -> ProgramResult {
+    if !from.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if *from.owner != system_program::ID || !from.data_is_empty() {
+        return Err(ProgramError::InvalidArgument);
+    }
+    let balance = from.lamports();
+    if balance < lamports {
+        return Err(ProgramError::InsufficientFunds);
+    }
+    **from.try_borrow_mut_lamports()? = balance - lamports;
+    **to.try_borrow_mut_lamports()? += lamports;
+    Ok(())
+}
+
+fn allocate(account: &AccountInfo, space: u64) -> ProgramResult {
+    if !account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if *account.owner != system_program::ID || !account.data_is_empty() {
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+    account.resize(space as usize)
+}
+
+fn assign(account: &AccountInfo, owner: &Pubkey) -> ProgramResult {
+    if !account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    account.assign(owner);
+    Ok(())
+}
+
+/// The system program instructions Anchor and the SPL programs use
+fn process_system(accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    let tag = data
+        .get(..4)
+        .map(|bytes| u32::from_le_bytes(bytes.try_into().unwrap()))
+        .ok_or(ProgramError::InvalidInstructionData)?;
+    match tag {
+        // CreateAccount { lamports, space, owner }
+        0 => {
+            let (from, to) = (&accounts[0], &accounts[1]);
+            if to.lamports() > 0 {
+                return Err(ProgramError::AccountAlreadyInitialized);
+            }
+            move_lamports(from, to, read_u64(data, 4)?)?;
+            allocate(to, read_u64(data, 12)?)?;
+            assign(to, &read_pubkey(data, 20)?)
+        }
+        // Assign { owner }
+        1 => assign(&accounts[0], &read_pubkey(data, 4)?),
+        // Transfer { lamports }
+        2 => move_lamports(&accounts[0], &accounts[1], read_u64(data, 4)?),
+        // Allocate { space }
+        8 => allocate(&accounts[0], read_u64(data, 4)?),
+        _ => Err(ProgramError::InvalidInstructionData),
+    }
+}
+
+/// Stand-in for Metaplex `CreateMetadataAccountV3`: writes a `Metadata`
+/// account holding the name, symbol and URI
+fn process_metadata(accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    const CREATE_METADATA_ACCOUNT_V3: u8 = 33;
+    if data.first() != Some(&CREATE_METADATA_ACCOUNT_V3) {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let (metadata, mint, mint_authority, payer, update_authority) = (
+        &accounts[0],
+        &accounts[1],
+        &accounts[2],
+        &accounts[3],
+        &accounts[4],
+    );
+    if !mint_authority.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let mut args = &data[1..];
+    let name = String::deserialize(&mut args).map_err(|_| ProgramError::InvalidInstructionData)?;
+    let symbol =
+        String::deserialize(&mut args).map_err(|_| ProgramError::InvalidInstructionData)?;
+    let uri = String::deserialize(&mut args).map_err(|_| ProgramError::InvalidInstructionData)?;
+
+    let mut account_data = vec![mpl_token_metadata::types::Key::MetadataV1 as u8];
+    account_data.extend_from_slice(update_authority.key.as_ref());
+    account_data.extend_from_slice(mint.key.as_ref());
+    for field in [name, symbol, uri] {
+        field.serialize(&mut account_data).unwrap();
+    }
+    // Seller fee, no creators, not sold, mutable, then every optional field unset
+    account_data.extend_from_slice(&[0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0]);
+
+    let lamports = Rent::default().minimum_balance(account_data.len());
+    **payer.try_borrow_mut_lamports()? -= lamports;
+    **metadata.try_borrow_mut_lamports()? += lamports;
+    metadata.resize(account_data.len())?;
+    metadata
+        .try_borrow_mut_data()?
+        .copy_from_slice(&account_data);
+    metadata.assign(&mpl_token_metadata::ID);
+    Ok(())
+}
+
+/// One account laid out the way the loader serializes it, so `resize` and
+/// `assign` work on it: the original data length sits right before the key,
+/// the data length right before the data, and `MAX_PERMITTED_DATA_INCREASE`
+/// bytes of room follow the data.
+struct AccountRegion {
+    memory: Vec<u128>,
+}
+
+impl AccountRegion {
+    // Puts the data at 8 mod 16, so zero-copy state after the discriminator is
+    // 16-byte aligned as native `u128` fields require
+    const KEY: usize = 8;
+    const OWNER: usize = Self::KEY + 32;
+    const LAMPORTS: usize = Self::OWNER + 32;
+    const DATA_LEN: usize = Self::LAMPORTS + 8;
+    const DATA: usize = Self::DATA_LEN + 8;
+
+    fn new(key: &Pubkey, account: &Account) -> Self {
+        let len = Self::DATA + account.data.len() + MAX_PERMITTED_DATA_INCREASE;
+        let mut region = Self {
+            memory: vec![0; len.div_ceil(16)],
+        };
+        let bytes = region.bytes_mut();
+        bytes[Self::KEY - 4..Self::KEY].copy_from_slice(&(account.data.len() as u32).to_le_bytes());
+        bytes[Self::KEY..Self::OWNER].copy_from_slice(key.as_ref());
+        bytes[Self::OWNER..Self::LAMPORTS].copy_from_slice(account.owner.as_ref());
+        bytes[Self::LAMPORTS..Self::DATA_LEN].copy_from_slice(&account.lamports.to_le_bytes());
+        bytes[Self::DATA_LEN..Self::DATA]
+            .copy_from_slice(&(account.data.len() as u64).to_le_bytes());
+        bytes[Self::DATA..Self::DATA + account.data.len()].copy_from_slice(&account.data);
+        region
+    }
+
+    fn bytes_mut(&mut self) -> &mut [u8] {
+        bytemuck::cast_slice_mut(&mut self.memory)
+    }
+
+    /// An `AccountInfo` over this region. The region must outlive it.
+    unsafe fn account_info<'a>(
+        &mut self,
+        is_signer: bool,
+        is_writable: bool,
+        executable: bool,
+    ) -> AccountInfo<'a> {
+        let base = self.bytes_mut().as_mut_ptr();
+        let data_len = *(base.add(Self::DATA_LEN) as *const u64) as usize;
+        AccountInfo {
+            key: &*(base.add(Self::KEY) as *const Pubkey),
+            is_signer,
+            is_writable,
+            lamports: std::rc::Rc::new(RefCell::new(&mut *(base.add(Self::LAMPORTS) as *mut u64))),
+            data: std::rc::Rc::new(RefCell::new(std::slice::from_raw_parts_mut(
+                base.add(Self::DATA),
+                data_len,
+            ))),
+            owner: &*(base.add(Self::OWNER) as *const Pubkey),
+            executable,
+            rent_epoch: u64::MAX,
+        }
+    }
+
+    /// The account as the instruction left it
+    fn read(&mut self, executable: bool) -> Account {
+        let bytes = self.bytes_mut();
+        let data_len = u64::from_le_bytes(bytes[Self::DATA_LEN..Self::DATA].try_into().unwrap());
+        Account {
+            lamports: u64::from_le_bytes(bytes[Self::LAMPORTS..Self::DATA_LEN].try_into().unwrap()),
+            data: bytes[Self::DATA..Self::DATA + data_len as usize].to_vec(),
+            owner: Pubkey::try_from(&bytes[Self::OWNER..Self::LAMPORTS]).unwrap(),
+            executable,
+        }
+    }
+}
+
+/// Accounts, clock and the entry point of the program under test
+pub struct Svm {
+    accounts: HashMap<Pubkey, Account>,
+    clock: Clock,
+}
+
+impl Default for Svm {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Svm {
+    pub fn new() -> Self {
+        static INSTALL_STUBS: std::sync::Once = std::sync::Once::new();
+        INSTALL_STUBS.call_once(|| {
+            anchor_lang::solana_program::program_stubs::set_syscall_stubs(Box::new(Stubs));
+        });
+
+        let mut svm = Self {
+            accounts: HashMap::new(),
+            clock: Clock {
+                slot: 1,
+                unix_timestamp: GENESIS_TIMESTAMP,
+                epoch_start_timestamp: GENESIS_TIMESTAMP,
+                ..Clock::default()
+            },
+        };
+        for program_id in [
+            ammv2::ID,
+            system_program::ID,
+            spl_token::ID,
+            spl_token_2022::ID,
+            spl_associated_token_account::ID,
+            spl_memo::ID,
+            mpl_token_metadata::ID,
+            bpf_loader_upgradeable::ID,
+        ] {
+            svm.set_account(
+                program_id,
+                Account {
+                    lamports: 1,
+                    owner: bpf_loader_upgradeable::ID,
+                    executable: true,
+                    ..Account::default()
+                },
+            );
+        }
+        let clock = svm.clock.clone();
+        for sysvar in [
+            anchor_lang::solana_program::sysvar::clock::ID,
+            anchor_lang::solana_program::sysvar::rent::ID,
+        ] {
+            let data = sysvar_data(&sysvar, &clock).unwrap();
+            svm.set_account(
+                sysvar,
+                Account {
+                    lamports: Rent::default().minimum_balance(data.len()),
+                    data,
+                    owner: anchor_lang::solana_program::sysvar::ID,
+                    executable: false,
+                },
+            );
+        }
+        svm
+    }
+
+    pub fn account(&self, key: &Pubkey) -> Option<&Account> {
+        self.accounts.get(key)
+    }
+
+    pub fn set_account(&mut self, key: Pubkey, account: Account) {
+        self.accounts.insert(key, account);
+    }
+
+    pub fn lamports(&self, key: &Pubkey) -> u64 {
+        self.account(key).map_or(0, |account| account.lamports)
+    }
+
+    pub fn airdrop(&mut self, key: &Pubkey, lamports: u64) {
+        let account = self.accounts.entry(*key).or_default();
+        account.lamports += lamports;
+    }
+
+    /// Anchor account `T` stored at `key`
+    pub fn get<T: AccountDeserialize>(&self, key: &Pubkey) -> T {
+        let account = self
+            .account(key)
+            .unwrap_or_else(|| panic!("no account {key}"));
+        T::try_deserialize(&mut account.data.as_slice()).unwrap()
+    }
+
+    /// Zero-copy account `T` stored at `key`
+    pub fn get_zero_copy<T: bytemuck::Pod>(&self, key: &Pubkey) -> T {
+        let account = self
+            .account(key)
+            .unwrap_or_else(|| panic!("no account {key}"));
+        bytemuck::pod_read_unaligned(&account.data[8..8 + std::mem::size_of::<T>()])
+    }
+
+    pub fn clock(&self) -> &Clock {
+        &self.clock
+    }
+
+    /// Move the clock forward by `slots` slots and `secs` seconds
+    pub fn warp(&mut self, slots: u64, secs: i64) {
+        self.clock.slot += slots;
+        self.clock.unix_timestamp += secs;
+        let data = sysvar_data(&anchor_lang::solana_program::sysvar::clock::ID, &self.clock);
+        self.accounts
+            .get_mut(&anchor_lang::solana_program::sysvar::clock::ID)
+            .unwrap()
+            .data = data.unwrap();
+    }
+
+    /// Execute `instructions` atomically. Accounts marked as signers are
+    /// treated as having signed.
+    pub fn send(
+        &mut self,
+        instructions: &[Instruction],
+    ) -> std::result::Result<TransactionMeta, ProgramError> {
+        CONTEXT.with(|context| {
+            *context.borrow_mut() = ExecutionContext {
+                clock: self.clock.clone(),
+                ..ExecutionContext::default()
+            }
+        });
+        let mut accounts = self.accounts.clone();
+        for instruction in instructions {
+            Self::execute(&mut accounts, instruction)?;
+        }
+        self.accounts = accounts;
+        Ok(CONTEXT.with(|context| std::mem::take(&mut context.borrow_mut().meta)))
+    }
+
+    fn execute(
+        accounts: &mut HashMap<Pubkey, Account>,
+        instruction: &Instruction,
+    ) -> std::result::Result<(), ProgramError> {
+        assert_eq!(
+            instruction.program_id,
+            ammv2::ID,
+            "only the program under test runs at the top level"
+        );
+
+        // One region per distinct account, with the privileges of all its metas
+        let mut keys: Vec<Pubkey> = Vec::new();
+        let mut privileges: Vec<(bool, bool)> = Vec::new();
+        for meta in &instruction.accounts {
+            match keys.iter().position(|key| *key == meta.pubkey) {
+                Some(index) => {
+                    privileges[index].0 |= meta.is_signer;
+                    privileges[index].1 |= meta.is_writable;
+                }
+                None => {
+                    keys.push(meta.pubkey);
+                    privileges.push((meta.is_signer, meta.is_writable));
+                }
+            }
+        }
+        let stored: Vec<Account> = keys
+            .iter()
+            .map(|key| {
+                accounts.get(key).cloned().unwrap_or(Account {
+                    owner: system_program::ID,
+                    ..Account::default()
+                })
+            })
+            .collect();
+        let lamports_before: u128 = stored.iter().map(|account| account.lamports as u128).sum();
+        let mut regions: Vec<AccountRegion> = keys
+            .iter()
+            .zip(&stored)
+            .map(|(key, account)| AccountRegion::new(key, account))
+            .collect();
+
+        let result = {
+            let infos: Vec<AccountInfo> = regions
+                .iter_mut()
+                .zip(&privileges)
+                .zip(&stored)
+                .map(|((region, (is_signer, is_writable)), account)| unsafe {
+                    region.account_info(*is_signer, *is_writable, account.executable)
+                })
+                .collect();
+            let infos: Vec<AccountInfo> = instruction
+                .accounts
+                .iter()
+                .map(|meta| infos[keys.iter().position(|key| *key == meta.pubkey).unwrap()].clone())
+                .collect();
+            CONTEXT.with(|context| {
+                let mut context = context.borrow_mut();
+                context.program_stack.push(ammv2::ID);
+                context.return_data = None;
+            });
+            let result = ammv2::entry(&ammv2::ID, &infos, &instruction.data);
+            CONTEXT.with(|context| {
+                let mut context = context.borrow_mut();
+                context.program_stack.pop();
+                context.meta.return_data = context.return_data.take();
+            });
+            result
+        };
+        result?;
+
+        let mut lamports_after = 0u128;
+        for ((key, region), (account, (_, is_writable))) in keys
+            .iter()
+            .zip(&mut regions)
+            .zip(stored.iter().zip(&privileges))
+        {
+            let updated = region.read(account.executable);
+            lamports_after += updated.lamports as u128;
+            if updated == *account {
+                continue;
+            }
+            assert!(*is_writable, "read-only account {key} was modified");
+            if updated.lamports == 0 {
+                accounts.remove(key);
+            } else {
+                accounts.insert(*key, updated);
+            }
+        }
+        assert_eq!(
+            lamports_before, lamports_after,
+            "instruction created or destroyed lamports"
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,373 @@
+use ammv2::errors::AmmError;
+use ammv2::events::SwapExecuted;
+use ammv2::fixed_point::Q64x64;
+use ammv2::state::Referrer;
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::{instruction::Instruction, system_program};
+use anchor_spl::associated_token::get_associated_token_address_with_program_id;
+use anchor_spl::token::spl_token;
+
+use crate::fixture::{
+    approve, assert_error, instruction, mint_supply_of, mint_to, token_balance, PoolFixture, Side,
+    User, FEE_DENOMINATOR, FEE_NUMERATOR, WALLET_LAMPORTS,
+};
+
+/// Constant-product output of the default pool for `input` against the reserves
+fn expected_output(input: u64, reserve_in: u64, reserve_out: u64) -> u64 {
+    let input_after_fee =
+        input as u128 * (FEE_DENOMINATOR - FEE_NUMERATOR) as u128 / FEE_DENOMINATOR as u128;
+    (input_after_fee * reserve_out as u128 / (reserve_in as u128 + input_after_fee)) as u64
+}
+
+fn seeded_pool() -> PoolFixture {
+    let mut fixture = PoolFixture::new();
+    let provider = fixture.user(10_000_000, 10_000_000);
+    fixture.deposit(&provider, 10_000_000, 10_000_000).unwrap();
+    fixture
+}
+
+/// Register `wallet` as a referrer, returning its referrer account
+fn register_referrer(fixture: &mut PoolFixture, wallet: &Pubkey) -> Pubkey {
+    let referrer = Pubkey::find_program_address(&[b"referrer", wallet.as_ref()], &ammv2::ID).0;
+    let ix = instruction(
+        ammv2::accounts::RegisterReferrer {
+            referrer,
+            authority: *wallet,
+            system_program: system_program::ID,
+        },
+        ammv2::instruction::RegisterReferrer {},
+    );
+    fixture.svm.send(&[ix]).unwrap();
+    referrer
+}
+
+/// Pay referrers half the swap fee, accrued in a token A referral vault
+fn referral_pool() -> (PoolFixture, Pubkey) {
+    let mut fixture = seeded_pool();
+    let ix = instruction(
+        fixture.pool_config_accounts(),
+        ammv2::instruction::SetReferralFee {
+            referral_fee_bps: 5_000,
+        },
+    );
+    fixture.svm.send(&[ix]).unwrap();
+
+    let referral_vault =
+        Pubkey::find_program_address(&[b"referral_vault", fixture.mint_a.as_ref()], &ammv2::ID).0;
+    let ix = instruction(
+        ammv2::accounts::InitializeReferralVault {
+            referral_authority: Pubkey::find_program_address(&[b"referral_authority"], &ammv2::ID)
+                .0,
+            mint: fixture.mint_a,
+            referral_vault,
+            payer: fixture.admin,
+            token_program: fixture.token_a_program,
+            system_program: system_program::ID,
+        },
+        ammv2::instruction::InitializeReferralVault {},
+    );
+    fixture.svm.send(&[ix]).unwrap();
+    (fixture, referral_vault)
+}
+
+fn referred_swap_ix(
+    fixture: &PoolFixture,
+    trader: &User,
+    referrer: Pubkey,
+    referral_vault: Pubkey,
+) -> Instruction {
+    let mut accounts = fixture.swap_accounts(trader, Side::AToB);
+    accounts.referrer = Some(referrer);
+    accounts.referral_vault = Some(referral_vault);
+    instruction(
+        accounts,
+        ammv2::instruction::SwapTokens {
+            input_amount: 100_000,
+            minimum_output_amount: 0,
+            slippage_bps: None,
+            allow_partial_fill: false,
+            price_limit: None,
+            client_order_id: None,
+        },
+    )
+}
+
+#[test]
+fn swap_pays_the_constant_product_output_and_updates_reserves() {
+    let mut fixture = seeded_pool();
+    let trader = fixture.user(100_000, 0);
+    let output = expected_output(100_000, 10_000_000, 10_000_000);
+
+    fixture.swap(&trader, Side::AToB, 100_000, output).unwrap();
+    assert_eq!(fixture.balance(&trader.token_a), 0);
+    assert_eq!(fixture.balance(&trader.token_b), output);
+    let pool = fixture.pool_state();
+    assert_eq!(pool.reserve_a, 10_100_000);
+    assert_eq!(pool.reserve_b, 10_000_000 - output);
+    assert_eq!(fixture.balance(&fixture.vault_a), pool.reserve_a);
+    assert_eq!(fixture.balance(&fixture.vault_b), pool.reserve_b);
+}
+
+#[test]
+fn swap_rejects_output_below_the_minimum() {
+    let mut fixture = seeded_pool();
+    let trader = fixture.user(100_000, 0);
+    let output = expected_output(100_000, 10_000_000, 10_000_000);
+
+    assert_error(
+        fixture.swap(&trader, Side::AToB, 100_000, output + 1),
+        AmmError::SlippageExceeded,
+    );
+    assert_eq!(fixture.balance(&trader.token_a), 100_000);
+}
+
+#[test]
+fn swap_round_trip_never_profits() {
+    let mut fixture = seeded_pool();
+    let trader = fixture.user(1_000_000, 0);
+    fixture.swap(&trader, Side::AToB, 1_000_000, 0).unwrap();
+    let received = fixture.balance(&trader.token_b);
+    fixture.swap(&trader, Side::BToA, received, 0).unwrap();
+    assert!(fixture.balance(&trader.token_a) < 1_000_000);
+}
+
+#[test]
+fn delegate_swap_creates_and_pays_the_owners_output_account() {
+    let mut fixture = seeded_pool();
+    let owner = fixture.user(100_000, 0);
+    let delegate = Pubkey::new_unique();
+    fixture.svm.airdrop(&delegate, WALLET_LAMPORTS);
+    approve(&mut fixture.svm, &owner.token_a, &delegate, 100_000);
+    let owner_output = get_associated_token_address_with_program_id(
+        &owner.wallet,
+        &fixture.mint_b,
+        &spl_token::ID,
+    );
+    let delegate_output =
+        get_associated_token_address_with_program_id(&delegate, &fixture.mint_b, &spl_token::ID);
+    // The owner's output account is removed so the swap has to create it
+    fixture.svm.set_account(owner_output, Default::default());
+    let as_delegate = User {
+        wallet: delegate,
+        ..owner
+    };
+    let swap = |input_token_owner, user_output_token_account| {
+        instruction(
+            ammv2::accounts::SwapTokens {
+                input_token_owner,
+                user_output_token_account,
+                ..fixture.swap_accounts(&as_delegate, Side::AToB)
+            },
+            ammv2::instruction::SwapTokens {
+                input_amount: 100_000,
+                minimum_output_amount: 0,
+                slippage_bps: None,
+                allow_partial_fill: false,
+                price_limit: None,
+                client_order_id: None,
+            },
+        )
+    };
+
+    let to_delegate = swap(owner.wallet, delegate_output);
+    let claimed_by_delegate = swap(delegate, delegate_output);
+    let to_owner = swap(owner.wallet, owner_output);
+
+    // The output can go neither to the delegate nor be claimed under its name
+    assert!(fixture.svm.send(&[to_delegate]).is_err());
+    assert_error(
+        fixture.svm.send(&[claimed_by_delegate]),
+        AmmError::InvalidTokenAccountOwner,
+    );
+
+    fixture.svm.send(&[to_owner]).unwrap();
+    assert_eq!(fixture.balance(&owner.token_a), 0);
+    assert_eq!(
+        fixture.balance(&owner_output),
+        expected_output(100_000, 10_000_000, 10_000_000)
+    );
+}
+
+#[test]
+fn swap_priced_on_vault_balances_leaves_the_surplus_untracked() {
+    let mut fixture = seeded_pool();
+    let trader = fixture.user(100_000, 0);
+    let vault_a = fixture.vault_a;
+    mint_to(&mut fixture.svm, &vault_a, 50_000);
+
+    fixture.swap(&trader, Side::AToB, 100_000, 0).unwrap();
+    let output = fixture.balance(&trader.token_b);
+    assert_eq!(output, expected_output(100_000, 10_050_000, 10_000_000));
+    let pool = fixture.pool_state();
+    assert_eq!(pool.reserve_a, 10_100_000);
+    assert_eq!(pool.reserve_b, 10_000_000 - output);
+    assert_eq!(fixture.balance(&fixture.vault_a) - pool.reserve_a, 50_000);
+}
+
+#[test]
+fn referred_swap_accrues_the_referrer_share_of_the_fee() {
+    let (mut fixture, referral_vault) = referral_pool();
+    let frontend = fixture.user(0, 0).wallet;
+    let referrer = register_referrer(&mut fixture, &frontend);
+    let trader = fixture.user(100_000, 0);
+
+    let ix = referred_swap_ix(&fixture, &trader, referrer, referral_vault);
+    fixture.svm.send(&[ix]).unwrap();
+
+    // Half of the 300 token A fee on a 100_000 swap
+    let referrer_state: Referrer = fixture.svm.get(&referrer);
+    assert_eq!(referrer_state.balances[0].mint, fixture.mint_a);
+    assert_eq!(referrer_state.balances[0].amount, 150);
+    assert_eq!(fixture.balance(&referral_vault), 150);
+}
+
+#[test]
+fn swap_rejects_its_own_trader_as_referrer() {
+    let (mut fixture, referral_vault) = referral_pool();
+    let trader = fixture.user(100_000, 0);
+    let referrer = register_referrer(&mut fixture, &trader.wallet);
+
+    let ix = referred_swap_ix(&fixture, &trader, referrer, referral_vault);
+    assert_error(fixture.svm.send(&[ix]), AmmError::SelfReferral);
+    assert_eq!(fixture.balance(&trader.token_a), 100_000);
+    assert_eq!(fixture.balance(&referral_vault), 0);
+}
+
+#[test]
+fn swap_input_is_capped_to_a_share_of_the_input_reserve() {
+    let mut fixture = seeded_pool();
+    fixture
+        .configure(ammv2::instruction::SetMaxTradeSize {
+            max_trade_bps_of_reserves: 1_000,
+        })
+        .unwrap();
+    let trader = fixture.user(2_000_000, 0);
+
+    // 10% of the 10_000_000 token A reserve
+    assert_error(
+        fixture.swap(&trader, Side::AToB, 1_000_001, 0),
+        AmmError::TradeTooLarge,
+    );
+    assert_eq!(fixture.balance(&trader.token_a), 2_000_000);
+    fixture.swap(&trader, Side::AToB, 1_000_000, 0).unwrap();
+    assert_eq!(fixture.pool_state().reserve_a, 11_000_000);
+}
+
+#[test]
+fn dust_swap_rounding_to_no_output_is_rejected() {
+    let mut fixture = PoolFixture::new();
+    let provider = fixture.user(10_000_000, 1_000);
+    fixture.deposit(&provider, 10_000_000, 1_000).unwrap();
+    let trader = fixture.user(100_000, 0);
+
+    // 1_000 token A is worth a tenth of a token B
+    assert_error(
+        fixture.swap(&trader, Side::AToB, 1_000, 0),
+        AmmError::OutputTooSmall,
+    );
+    assert_eq!(fixture.balance(&trader.token_a), 100_000);
+    assert_eq!(fixture.pool_state().reserve_a, 10_000_000);
+
+    fixture.swap(&trader, Side::AToB, 100_000, 0).unwrap();
+    assert_eq!(
+        fixture.balance(&trader.token_b),
+        expected_output(100_000, 10_000_000, 1_000)
+    );
+    assert!(fixture.balance(&trader.token_b) > 0);
+}
+
+#[test]
+fn swaps_never_shrink_the_product_of_the_vault_balances() {
+    let mut fixture = seeded_pool();
+    fixture
+        .configure(ammv2::instruction::SetFeeOnOutput {
+            fee_on_output: true,
+        })
+        .unwrap();
+    let trader = fixture.user(5_000_000, 5_000_000);
+    let product = |fixture: &PoolFixture| {
+        fixture.balance(&fixture.vault_a) as u128 * fixture.balance(&fixture.vault_b) as u128
+    };
+
+    let mut previous = product(&fixture);
+    for (side, input) in [
+        (Side::AToB, 100),
+        (Side::AToB, 3_333_333),
+        (Side::BToA, 77),
+        (Side::BToA, 999_999),
+        (Side::AToB, 12_345),
+    ] {
+        fixture.swap(&trader, side, input, 0).unwrap();
+        let current = product(&fixture);
+        assert!(current >= previous, "{side:?} {input} shrank the product");
+        previous = current;
+    }
+}
+
+#[test]
+fn swap_rejects_an_input_account_neither_owned_nor_delegated() {
+    let mut fixture = seeded_pool();
+    let victim = fixture.user(100_000, 0);
+    let trader = fixture.user(0, 0);
+    let spend_victims = User {
+        token_a: victim.token_a,
+        ..trader
+    };
+
+    assert_error(
+        fixture.swap(&spend_victims, Side::AToB, 100_000, 0),
+        AmmError::InvalidTokenAccountOwner,
+    );
+    assert_eq!(fixture.balance(&victim.token_a), 100_000);
+}
+
+#[test]
+fn swap_event_carries_the_post_swap_pool_snapshot() {
+    let mut fixture = seeded_pool();
+    let trader = fixture.user(100_000, 0);
+    let output = expected_output(100_000, 10_000_000, 10_000_000);
+
+    let events = fixture
+        .swap(&trader, Side::AToB, 100_000, output)
+        .unwrap()
+        .events::<SwapExecuted>();
+    assert_eq!(events.len(), 1);
+    let event = &events[0];
+    assert_eq!(event.output_amount, output);
+    assert_eq!(
+        event.reserve_a,
+        token_balance(&fixture.svm, &fixture.vault_a)
+    );
+    assert_eq!(
+        event.reserve_b,
+        token_balance(&fixture.svm, &fixture.vault_b)
+    );
+    assert_eq!(
+        (event.reserve_a, event.reserve_b),
+        (10_100_000, 10_000_000 - output)
+    );
+    assert_eq!(
+        event.lp_supply,
+        mint_supply_of(&fixture.svm, &fixture.lp_mint)
+    );
+    assert_eq!(
+        event.spot_price,
+        Q64x64::from_ratio(event.reserve_b as u128, event.reserve_a as u128)
+            .unwrap()
+            .to_bits()
+    );
+
+    // Swapping straight back cannot recover the fees, so asking for the whole
+    // input reverts; nothing is emitted and the reserves stay as they were
+    let pool = fixture.pool_state();
+    assert_error(
+        fixture.swap(&trader, Side::BToA, output, 100_000),
+        AmmError::SlippageExceeded,
+    );
+    let after = fixture.pool_state();
+    assert_eq!(
+        (after.reserve_a, after.reserve_b),
+        (pool.reserve_a, pool.reserve_b)
+    );
+    assert_eq!(after.event_seq, pool.event_seq);
+}
@@ -0,0 +1,138 @@
+use ammv2::errors::AmmError;
+use ammv2::state::{ParamChange, VerificationLevel, MAX_JIT_PENALTY_BPS};
+
+use crate::fixture::{assert_error, instruction, PoolFixture};
+
+const DELAY_SECS: i64 = 86_400;
+
+#[test]
+fn pool_setters_apply_directly_while_the_timelock_is_off() {
+    let mut fixture = PoolFixture::new();
+    let ix = instruction(
+        fixture.pool_config_accounts(),
+        ammv2::instruction::SetCircuitBreaker {
+            max_price_change_bps_per_slot: 500,
+        },
+    );
+    fixture.svm.send(&[ix]).unwrap();
+    assert_eq!(fixture.pool_state().max_price_change_bps_per_slot, 500);
+}
+
+#[test]
+fn pool_setters_are_rejected_while_the_timelock_is_on() {
+    let mut fixture = PoolFixture::new();
+    fixture.enable_timelock(DELAY_SECS);
+
+    let setters = [
+        instruction(
+            fixture.pool_config_accounts(),
+            ammv2::instruction::SetCircuitBreaker {
+                max_price_change_bps_per_slot: 500,
+            },
+        ),
+        instruction(
+            fixture.pool_config_accounts(),
+            ammv2::instruction::SetWithdrawOnly {
+                withdraw_only: true,
+                large_withdrawal_delay_secs: 0,
+                small_lp_withdrawal_threshold: 0,
+            },
+        ),
+        instruction(
+            fixture.pool_config_accounts(),
+            ammv2::instruction::SetDisabledOperations {
+                disabled_operations: 1,
+            },
+        ),
+        instruction(
+            fixture.pool_config_accounts(),
+            ammv2::instruction::SetVerificationLevel {
+                verification_level: VerificationLevel::Verified,
+            },
+        ),
+        instruction(
+            fixture.pool_config_accounts(),
+            ammv2::instruction::SetOracleCheckpointReward {
+                oracle_checkpoint_reward: 1,
+            },
+        ),
+        instruction(
+            fixture.pool_config_accounts(),
+            ammv2::instruction::SetWithdrawCooldown {
+                withdraw_cooldown_secs: 60,
+            },
+        ),
+        instruction(
+            fixture.pool_config_accounts(),
+            ammv2::instruction::SetJitProtection {
+                jit_window_slots: 10,
+                jit_penalty_bps: 100,
+            },
+        ),
+    ];
+    for setter in setters {
+        assert_error(fixture.svm.send(&[setter]), AmmError::TimelockRequired);
+    }
+}
+
+#[test]
+fn queued_pool_change_applies_once_the_delay_has_elapsed() {
+    let mut fixture = PoolFixture::new();
+    fixture.enable_timelock(DELAY_SECS);
+    let admin = fixture.admin;
+    let change = ParamChange::DeviationGuard {
+        twap_window_secs: 600,
+        max_deviation_bps: 1_000,
+        incident_deviation_bps: 500,
+        incident_surcharge_bps: 50,
+    };
+    fixture.queue_change(&admin, 1, change).unwrap();
+
+    assert_error(
+        fixture.execute_change(&admin, 1),
+        AmmError::TimelockNotElapsed,
+    );
+    fixture.svm.warp(1, DELAY_SECS);
+    fixture.execute_change(&admin, 1).unwrap();
+    let pool = fixture.pool_state();
+    assert_eq!(pool.deviation_twap_window_secs, 600);
+    assert_eq!(pool.max_oracle_deviation_bps, 1_000);
+    assert_eq!(pool.incident_deviation_bps, 500);
+    assert_eq!(pool.incident_surcharge_bps, 50);
+}
+
+#[test]
+fn queue_rejects_invalid_pool_changes_and_other_proposers() {
+    let mut fixture = PoolFixture::new();
+    let admin = fixture.admin;
+    assert_error(
+        fixture.queue_change(
+            &admin,
+            1,
+            ParamChange::DeviationGuard {
+                twap_window_secs: 600,
+                max_deviation_bps: 500,
+                incident_deviation_bps: 500,
+                incident_surcharge_bps: 0,
+            },
+        ),
+        AmmError::InvalidPoolConfig,
+    );
+    assert_error(
+        fixture.queue_change(
+            &admin,
+            1,
+            ParamChange::JitProtection {
+                window_slots: 10,
+                penalty_bps: MAX_JIT_PENALTY_BPS + 1,
+            },
+        ),
+        AmmError::InvalidPoolConfig,
+    );
+
+    let stranger = fixture.user(0, 0).wallet;
+    assert_error(
+        fixture.queue_change(&stranger, 1, ParamChange::LpWhitelist { enabled: true }),
+        AmmError::Unauthorized,
+    );
+}
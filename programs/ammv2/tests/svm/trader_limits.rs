@@ -0,0 +1,156 @@
+use ammv2::errors::AmmError;
+use ammv2::state::TraderState;
+use anchor_lang::prelude::{ProgramError, Pubkey};
+
+use crate::fixture::{assert_error, instruction, PoolFixture, Side, User};
+use crate::runtime::TransactionMeta;
+
+fn seeded_pool() -> PoolFixture {
+    let mut fixture = PoolFixture::new();
+    let provider = fixture.user(10_000_000, 10_000_000);
+    fixture.deposit(&provider, 10_000_000, 10_000_000).unwrap();
+    fixture
+}
+
+fn trader_state_address(fixture: &PoolFixture, trader: &User) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            b"trader_state",
+            fixture.pool.as_ref(),
+            trader.wallet.as_ref(),
+        ],
+        &ammv2::ID,
+    )
+    .0
+}
+
+/// Swap `input_amount` with the trader's activity record attached
+fn tracked_swap(
+    fixture: &mut PoolFixture,
+    trader: &User,
+    side: Side,
+    input_amount: u64,
+) -> std::result::Result<TransactionMeta, ProgramError> {
+    let mut accounts = fixture.swap_accounts(trader, side);
+    accounts.trader_state = Some(trader_state_address(fixture, trader));
+    let ix = instruction(
+        accounts,
+        ammv2::instruction::SwapTokens {
+            input_amount,
+            minimum_output_amount: 0,
+            slippage_bps: None,
+            allow_partial_fill: false,
+            price_limit: None,
+            client_order_id: None,
+        },
+    );
+    fixture.svm.send(&[ix])
+}
+
+#[test]
+fn launch_cooldown_spaces_out_each_wallets_swaps() {
+    let mut fixture = seeded_pool();
+    fixture
+        .configure(ammv2::instruction::SetLaunchProtection {
+            launch_protection_end_slot: 100,
+            launch_trade_cooldown_slots: 10,
+        })
+        .unwrap();
+    let trader = fixture.user(1_000_000, 0);
+    let other = fixture.user(1_000_000, 0);
+
+    assert_error(
+        fixture.swap(&trader, Side::AToB, 10_000, 0),
+        AmmError::TraderStateRequired,
+    );
+    tracked_swap(&mut fixture, &trader, Side::AToB, 10_000).unwrap();
+    let slot = fixture.svm.clock().slot;
+    let state: TraderState = fixture.svm.get(&trader_state_address(&fixture, &trader));
+    assert_eq!(state.trader, trader.wallet);
+    assert_eq!(state.last_trade_slot, slot);
+
+    // The cooldown is per wallet
+    fixture.svm.warp(9, 4);
+    assert_error(
+        tracked_swap(&mut fixture, &trader, Side::AToB, 10_000),
+        AmmError::TradeCooldownActive,
+    );
+    tracked_swap(&mut fixture, &other, Side::AToB, 10_000).unwrap();
+    fixture.svm.warp(1, 1);
+    tracked_swap(&mut fixture, &trader, Side::AToB, 10_000).unwrap();
+}
+
+#[test]
+fn launch_cooldown_lifts_at_the_protection_end_slot() {
+    let mut fixture = seeded_pool();
+    fixture
+        .configure(ammv2::instruction::SetLaunchProtection {
+            launch_protection_end_slot: 50,
+            launch_trade_cooldown_slots: 1_000,
+        })
+        .unwrap();
+    let trader = fixture.user(1_000_000, 0);
+    tracked_swap(&mut fixture, &trader, Side::AToB, 10_000).unwrap();
+
+    fixture.svm.warp(50, 20);
+    fixture.swap(&trader, Side::AToB, 10_000, 0).unwrap();
+    fixture.swap(&trader, Side::AToB, 10_000, 0).unwrap();
+}
+
+#[test]
+fn slot_volume_cap_limits_each_wallet_per_slot() {
+    let mut fixture = seeded_pool();
+    fixture
+        .configure(ammv2::instruction::SetAntiSandwichLimits {
+            max_trader_slot_volume_bps: 100,
+            block_same_slot_reversal: false,
+        })
+        .unwrap();
+    let trader = fixture.user(1_000_000, 0);
+    let other = fixture.user(1_000_000, 0);
+
+    assert_error(
+        fixture.swap(&trader, Side::AToB, 10_000, 0),
+        AmmError::TraderStateRequired,
+    );
+    // 60 bps of the input reserve, then 50 bps more in the same slot
+    tracked_swap(&mut fixture, &trader, Side::AToB, 60_000).unwrap();
+    assert_error(
+        tracked_swap(&mut fixture, &trader, Side::AToB, 50_000),
+        AmmError::TraderVolumeExceeded,
+    );
+    tracked_swap(&mut fixture, &trader, Side::AToB, 30_000).unwrap();
+    tracked_swap(&mut fixture, &other, Side::AToB, 60_000).unwrap();
+
+    // The allowance refills every slot
+    fixture.svm.warp(1, 1);
+    tracked_swap(&mut fixture, &trader, Side::AToB, 50_000).unwrap();
+    let state: TraderState = fixture.svm.get(&trader_state_address(&fixture, &trader));
+    assert_eq!(state.last_trade_slot, fixture.svm.clock().slot);
+    assert_eq!(state.slot_volume_bps, 50);
+}
+
+#[test]
+fn same_slot_reversals_are_blocked_until_the_next_slot() {
+    let mut fixture = seeded_pool();
+    fixture
+        .configure(ammv2::instruction::SetAntiSandwichLimits {
+            max_trader_slot_volume_bps: 0,
+            block_same_slot_reversal: true,
+        })
+        .unwrap();
+    let trader = fixture.user(1_000_000, 1_000_000);
+    let other = fixture.user(0, 1_000_000);
+
+    tracked_swap(&mut fixture, &trader, Side::AToB, 100_000).unwrap();
+    tracked_swap(&mut fixture, &trader, Side::AToB, 100_000).unwrap();
+    assert_error(
+        tracked_swap(&mut fixture, &trader, Side::BToA, 100_000),
+        AmmError::SameSlotReversal,
+    );
+    // Only the wallet's own reversal is blocked
+    tracked_swap(&mut fixture, &other, Side::BToA, 100_000).unwrap();
+
+    fixture.svm.warp(1, 1);
+    tracked_swap(&mut fixture, &trader, Side::BToA, 100_000).unwrap();
+}
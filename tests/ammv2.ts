@@ -4,10 +4,21 @@ import { AmmV2 } from "../target/types/amm_v2";
 import { assert } from "chai";
 import {
   createMint,
+  approve,
   createAssociatedTokenAccount,
+  getOrCreateAssociatedTokenAccount,
   mintTo,
+  transfer,
   getAccount,
+  getAssociatedTokenAddressSync,
+  reallocate,
+  enableRequiredMemoTransfers,
+  ExtensionType,
+  getMintLen,
+  createInitializeMintInstruction,
+  createInitializePermanentDelegateInstruction,
   TOKEN_PROGRAM_ID,
+  TOKEN_2022_PROGRAM_ID,
   ASSOCIATED_TOKEN_PROGRAM_ID,
 } from "@solana/spl-token";
 import {
@@ -15,8 +26,14 @@ import {
   PublicKey,
   SystemProgram,
   LAMPORTS_PER_SOL,
+  SYSVAR_RENT_PUBKEY,
+  Transaction,
 } from "@solana/web3.js";
 
+const TOKEN_METADATA_PROGRAM_ID = new PublicKey(
+  "metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s"
+);
+
 interface PoolAccounts {
   authority: Keypair;
   payer: Keypair;
@@ -27,6 +44,7 @@ interface PoolAccounts {
   lpTokenMint: PublicKey;
   liquidityPool: PublicKey;
   poolAuthority: PublicKey;
+  oracle: PublicKey;
 }
 
 interface LiquidityProvider {
@@ -44,7 +62,11 @@ describe("AMM V2 Tests", () => {
   const connection = provider.connection;
 
   let pool: PoolAccounts;
+  let token2022Pool: PoolAccounts;
   const TOKEN_DECIMALS = 9;
+  const MEMO_PROGRAM_ID = new PublicKey(
+    "MemoSq4gqABAXKb96qnH8TysNcWxMyWCqXgDLGmfcHr"
+  );
 
   /**
    * Helper function to get token balance as a number
@@ -54,6 +76,16 @@ describe("AMM V2 Tests", () => {
     return Number(account.amount) / Math.pow(10, TOKEN_DECIMALS);
   }
 
+  /**
+   * LP vault of a liquidity position, holding the LP minted through it
+   */
+  function positionLpVault(liquidityPosition: PublicKey): PublicKey {
+    return PublicKey.findProgramAddressSync(
+      [Buffer.from("position_lp_vault"), liquidityPosition.toBuffer()],
+      program.programId
+    )[0];
+  }
+
   /**
    * Helper function to convert amount to proper token units
    */
@@ -171,24 +203,78 @@ describe("AMM V2 Tests", () => {
       program.programId
     );
 
+    const [oracle] = PublicKey.findProgramAddressSync(
+      [Buffer.from("oracle"), liquidityPool.toBuffer()],
+      program.programId
+    );
+
+    const [lpTokenMetadata] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("metadata"),
+        TOKEN_METADATA_PROGRAM_ID.toBuffer(),
+        lpTokenMint.toBuffer(),
+      ],
+      TOKEN_METADATA_PROGRAM_ID
+    );
+
+    // Global config shared by every pool, created once per cluster
+    const [ammConfig] = PublicKey.findProgramAddressSync(
+      [Buffer.from("amm_config")],
+      program.programId
+    );
+    const [treasury] = PublicKey.findProgramAddressSync(
+      [Buffer.from("treasury")],
+      program.programId
+    );
+
+    // Only the program's upgrade authority may create the config
+    const [programData] = PublicKey.findProgramAddressSync(
+      [program.programId.toBuffer()],
+      new PublicKey("BPFLoaderUpgradeab1e11111111111111111111111")
+    );
+    if ((await connection.getAccountInfo(ammConfig)) === null) {
+      await program.methods
+        .initializeAmmConfig(new anchor.BN(0))
+        .accounts({
+          ammConfig: ammConfig,
+          treasury: treasury,
+          authority: provider.wallet.publicKey,
+          programData,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+    }
+
     // Initialize pool with 0.01% fee (1/10000)
     const feeNumerator = new anchor.BN(1);
     const feeDenominator = new anchor.BN(10000);
 
     await program.methods
-      .initializePool(feeNumerator, feeDenominator)
+      .initializePool(feeNumerator, feeDenominator, "", new anchor.BN(0))
       .accounts({
         tokenAMint: tokenAMint,
         tokenBMint: tokenBMint,
         liquidityPool: liquidityPool,
+        oracle: oracle,
         poolAuthority: poolAuthority,
         tokenAVault: tokenAVault,
         tokenBVault: tokenBVault,
         lpTokenMint: lpTokenMint,
+        lpTokenMetadata: lpTokenMetadata,
+        tokenAMetadata: null,
+        tokenBMetadata: null,
+        tokenAMintApproval: null,
+        tokenBMintApproval: null,
+        ammConfig: ammConfig,
+        treasury: treasury,
         payer: provider.wallet.publicKey,
         systemProgram: SystemProgram.programId,
         tokenProgram: TOKEN_PROGRAM_ID,
+        tokenAProgram: TOKEN_PROGRAM_ID,
+        tokenBProgram: TOKEN_PROGRAM_ID,
         associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        tokenMetadataProgram: TOKEN_METADATA_PROGRAM_ID,
+        rent: SYSVAR_RENT_PUBKEY,
       })
       .rpc();
 
@@ -203,6 +289,7 @@ describe("AMM V2 Tests", () => {
       lpTokenMint: lpTokenMint,
       liquidityPool: liquidityPool,
       poolAuthority: poolAuthority,
+      oracle: oracle,
     };
 
     console.log("Pool initialized successfully");
@@ -226,18 +313,29 @@ describe("AMM V2 Tests", () => {
     const depositAmountB = toTokenAmount(50);
 
     await program.methods
-      .depositLiquidity(depositAmountA, depositAmountB)
+      .depositLiquidity(depositAmountA, depositAmountB, null)
       .accounts({
         liquidityPool: pool.liquidityPool,
         poolAuthority: pool.poolAuthority,
+        tokenAMint: pool.tokenAMint,
+        tokenBMint: pool.tokenBMint,
         tokenAVault: pool.tokenAVault,
         tokenBVault: pool.tokenBVault,
         lpTokenMint: pool.lpTokenMint,
         userTokenAAccount: tokenAAccount,
         userTokenBAccount: tokenBAccount,
         userLpTokenAccount: lpTokenAccount,
+        subAccount: null,
         user: lpSigner.publicKey,
+        lpTokenOwner: lpSigner.publicKey,
         tokenProgram: TOKEN_PROGRAM_ID,
+        tokenAProgram: TOKEN_PROGRAM_ID,
+        tokenBProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+        lpWhitelistEntry: null,
+        liquidityPosition: null,
+        positionLpVault: null,
       })
       .signers([lpSigner])
       .rpc();
@@ -288,18 +386,29 @@ describe("AMM V2 Tests", () => {
     const depositAmountB = toTokenAmount(50);
 
     await program.methods
-      .depositLiquidity(depositAmountA, depositAmountB)
+      .depositLiquidity(depositAmountA, depositAmountB, null)
       .accounts({
         liquidityPool: pool.liquidityPool,
         poolAuthority: pool.poolAuthority,
+        tokenAMint: pool.tokenAMint,
+        tokenBMint: pool.tokenBMint,
         tokenAVault: pool.tokenAVault,
         tokenBVault: pool.tokenBVault,
         lpTokenMint: pool.lpTokenMint,
         userTokenAAccount: tokenAAccount,
         userTokenBAccount: tokenBAccount,
         userLpTokenAccount: lpTokenAccount,
+        subAccount: null,
         user: lpSigner.publicKey,
+        lpTokenOwner: lpSigner.publicKey,
         tokenProgram: TOKEN_PROGRAM_ID,
+        tokenAProgram: TOKEN_PROGRAM_ID,
+        tokenBProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+        lpWhitelistEntry: null,
+        liquidityPosition: null,
+        positionLpVault: null,
       })
       .signers([lpSigner])
       .rpc();
@@ -330,27 +439,47 @@ describe("AMM V2 Tests", () => {
     console.log("Second liquidity deposit successful");
   });
 
+  let liquidityProvider3: LiquidityProvider;
+
   it("Adds third liquidity deposit with larger Token B amount", async () => {
     const lpSigner = Keypair.generate();
     const [tokenAAccount, tokenBAccount, lpTokenAccount] =
       await setupLiquidityProvider(lpSigner.publicKey, 100);
 
+    liquidityProvider3 = {
+      signer: lpSigner,
+      tokenAAccount: tokenAAccount,
+      tokenBAccount: tokenBAccount,
+      lpTokenAccount: lpTokenAccount,
+    };
+
     const depositAmountA = toTokenAmount(25);
     const depositAmountB = toTokenAmount(100); // More than needed
 
     await program.methods
-      .depositLiquidity(depositAmountA, depositAmountB)
+      .depositLiquidity(depositAmountA, depositAmountB, null)
       .accounts({
         liquidityPool: pool.liquidityPool,
         poolAuthority: pool.poolAuthority,
+        tokenAMint: pool.tokenAMint,
+        tokenBMint: pool.tokenBMint,
         tokenAVault: pool.tokenAVault,
         tokenBVault: pool.tokenBVault,
         lpTokenMint: pool.lpTokenMint,
         userTokenAAccount: tokenAAccount,
         userTokenBAccount: tokenBAccount,
         userLpTokenAccount: lpTokenAccount,
+        subAccount: null,
         user: lpSigner.publicKey,
+        lpTokenOwner: lpSigner.publicKey,
         tokenProgram: TOKEN_PROGRAM_ID,
+        tokenAProgram: TOKEN_PROGRAM_ID,
+        tokenBProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+        lpWhitelistEntry: null,
+        liquidityPosition: null,
+        positionLpVault: null,
       })
       .signers([lpSigner])
       .rpc();
@@ -380,7 +509,7 @@ describe("AMM V2 Tests", () => {
       liquidityProvider1.tokenBAccount
     );
     const beforeLpTokens = await getTokenBalance(
-      liquidityProvider1.lpTokenAccount
+      positionLpVault(liquidityPosition)
     );
 
     console.log("Before withdrawal - Token A:", beforeTokenA);
@@ -390,18 +519,27 @@ describe("AMM V2 Tests", () => {
     const burnAmount = toTokenAmount(50);
 
     await program.methods
-      .withdrawLiquidity(burnAmount)
+      .withdrawLiquidity(burnAmount, "Treasury proposal #1", null)
       .accounts({
         liquidityPool: pool.liquidityPool,
         poolAuthority: pool.poolAuthority,
+        tokenAMint: pool.tokenAMint,
+        tokenBMint: pool.tokenBMint,
         tokenAVault: pool.tokenAVault,
         tokenBVault: pool.tokenBVault,
         lpTokenMint: pool.lpTokenMint,
         userTokenAAccount: liquidityProvider1.tokenAAccount,
         userTokenBAccount: liquidityProvider1.tokenBAccount,
         userLpTokenAccount: liquidityProvider1.lpTokenAccount,
+        subAccount: null,
         user: liquidityProvider1.signer.publicKey,
+        lpTokenOwner: liquidityProvider1.signer.publicKey,
         tokenProgram: TOKEN_PROGRAM_ID,
+        tokenAProgram: TOKEN_PROGRAM_ID,
+        tokenBProgram: TOKEN_PROGRAM_ID,
+        memoProgram: MEMO_PROGRAM_ID,
+        liquidityPosition: null,
+        positionLpVault: null,
       })
       .signers([liquidityProvider1.signer])
       .rpc();
@@ -429,6 +567,85 @@ describe("AMM V2 Tests", () => {
     console.log("Liquidity removed successfully");
   });
 
+  it("Opens a fee-tracking liquidity position", async () => {
+    const [liquidityPosition] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("liquidity_position"),
+        pool.liquidityPool.toBuffer(),
+        liquidityProvider1.signer.publicKey.toBuffer(),
+      ],
+      program.programId
+    );
+
+    await program.methods
+      .openPosition()
+      .accounts({
+        liquidityPool: pool.liquidityPool,
+        liquidityPosition,
+        lpTokenMint: pool.lpTokenMint,
+        positionLpVault: positionLpVault(liquidityPosition),
+        owner: liquidityProvider1.signer.publicKey,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([liquidityProvider1.signer])
+      .rpc();
+
+    // The position starts empty: LP the owner already holds is not tracked
+    let position = await program.account.liquidityPosition.fetch(
+      liquidityPosition
+    );
+    assert.equal(position.lpTokens.toNumber(), 0);
+    assert.equal(position.feesOwedLp.toNumber(), 0);
+    assert(position.openedAt.toNumber() > 0, "Open time should be recorded");
+    assert(
+      position.entryReserveA.gtn(0) && position.entryReserveB.gtn(0),
+      "Entry reserves should be recorded"
+    );
+
+    await program.methods
+      .depositLiquidity(toTokenAmount(10), toTokenAmount(10), null)
+      .accounts({
+        liquidityPool: pool.liquidityPool,
+        poolAuthority: pool.poolAuthority,
+        tokenAMint: pool.tokenAMint,
+        tokenBMint: pool.tokenBMint,
+        tokenAVault: pool.tokenAVault,
+        tokenBVault: pool.tokenBVault,
+        lpTokenMint: pool.lpTokenMint,
+        userTokenAAccount: liquidityProvider1.tokenAAccount,
+        userTokenBAccount: liquidityProvider1.tokenBAccount,
+        userLpTokenAccount: liquidityProvider1.lpTokenAccount,
+        subAccount: null,
+        user: liquidityProvider1.signer.publicKey,
+        lpTokenOwner: liquidityProvider1.signer.publicKey,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        tokenAProgram: TOKEN_PROGRAM_ID,
+        tokenBProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+        memoProgram: null,
+        lpWhitelistEntry: null,
+        liquidityPosition,
+        positionLpVault: positionLpVault(liquidityPosition),
+      })
+      .signers([liquidityProvider1.signer])
+      .rpc();
+
+    // The LP minted through the position lands in its vault and is tracked
+    position = await program.account.liquidityPosition.fetch(
+      liquidityPosition
+    );
+    const vaultLp = await getAccount(
+      connection,
+      positionLpVault(liquidityPosition)
+    );
+    assert.equal(position.lpTokens.toString(), vaultLp.amount.toString());
+    assert(position.tokenADeposited.gtn(0), "Deposit should be recorded");
+
+    console.log("Liquidity position opened");
+  });
+
   it("Performs a token swap", async () => {
     const swapper = Keypair.generate();
 
@@ -467,18 +684,46 @@ describe("AMM V2 Tests", () => {
     // Swap 10 Token A for Token B
     const swapAmount = toTokenAmount(10);
     const minOutputAmount = new anchor.BN(0);
+    // Up to 50% below the pool's spot price, checked on-chain at execution
+    const slippageBps = 5000;
+    // Echoed untouched in the SwapExecuted event
+    const clientOrderId = new anchor.BN(42);
 
     await program.methods
-      .swapTokens(swapAmount, minOutputAmount)
+      .swapTokens(
+        swapAmount,
+        minOutputAmount,
+        slippageBps,
+        false,
+        null,
+        clientOrderId
+      )
       .accounts({
         liquidityPool: pool.liquidityPool,
         poolAuthority: pool.poolAuthority,
         inputTokenVault: pool.tokenAVault,
         outputTokenVault: pool.tokenBVault,
+        inputTokenMint: pool.tokenAMint,
+        outputTokenMint: pool.tokenBMint,
+        oracle: pool.oracle,
         userInputTokenAccount: tokenAAccount,
         userOutputTokenAccount: tokenBAccount,
+        insuranceVault: null,
+        protocolFeeVault: null,
+        feeRecipient: null,
+        creatorFeeAccount: null,
+        traderState: null,
+        launchWhitelistEntry: null,
+        memoProgram: null,
+        volumeStats: null,
+        referrer: null,
+        referralVault: null,
         user: swapper.publicKey,
-        tokenProgram: TOKEN_PROGRAM_ID,
+        inputTokenOwner: swapper.publicKey,
+        inputTokenProgram: TOKEN_PROGRAM_ID,
+        outputTokenProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
       })
       .signers([swapper])
       .rpc();
@@ -492,70 +737,3558 @@ describe("AMM V2 Tests", () => {
     assert(afterTokenA < beforeTokenA, "Token A should decrease");
     assert(afterTokenB > beforeTokenB, "Token B should increase");
 
+    // The swap returns (amount_in, amount_out, fee_amount) as return data
+    const { meta } = await connection.getTransaction(signature, {
+      commitment: "confirmed",
+      maxSupportedTransactionVersion: 0,
+    });
+    const returned = Buffer.from(meta.returnData.data[0], "base64");
+    const amountIn = new anchor.BN(returned.subarray(0, 8), "le");
+    const amountOut = new anchor.BN(returned.subarray(8, 16), "le");
+    assert(amountIn.eq(swapAmount), "Return data should hold the input");
+    assert.equal(
+      amountOut.toNumber() / Math.pow(10, TOKEN_DECIMALS),
+      afterTokenB - beforeTokenB
+    );
+
+    const parser = new anchor.EventParser(program.programId, program.coder);
+    const events = Array.from(parser.parseLogs(meta.logMessages));
+    const swapEvent = events.find((event) => event.name === "swapExecuted");
+    assert(swapEvent, "The swap should emit SwapExecuted");
+    assert(
+      swapEvent.data.clientOrderId.eq(clientOrderId),
+      "SwapExecuted should echo the client order id"
+    );
+
     console.log("Swap executed successfully");
   });
 
-  it("Removes liquidity after swap (with profit from fees)", async () => {
-    const beforeTokenA = await getTokenBalance(
-      liquidityProvider2.tokenAAccount
+  it("Pays the pool creator its share of swap fees", async () => {
+    const swapper = Keypair.generate();
+    const tokenAAccount = await createAssociatedTokenAccount(
+      connection,
+      pool.payer,
+      pool.tokenAMint,
+      swapper.publicKey
     );
-    const beforeTokenB = await getTokenBalance(
-      liquidityProvider2.tokenBAccount
+    const tokenBAccount = await createAssociatedTokenAccount(
+      connection,
+      pool.payer,
+      pool.tokenBMint,
+      swapper.publicKey
     );
-    const beforeLpTokens = await getTokenBalance(
-      liquidityProvider2.lpTokenAccount
+    await mintTo(
+      connection,
+      pool.payer,
+      pool.tokenAMint,
+      tokenAAccount,
+      pool.authority,
+      10 * Math.pow(10, TOKEN_DECIMALS)
     );
 
-    console.log("Before withdrawal - Token A:", beforeTokenA);
-    console.log("Before withdrawal - Token B:", beforeTokenB);
-    console.log("Before withdrawal - LP tokens:", beforeLpTokens);
+    // The payer of initialize_pool is the creator
+    const creatorTokenA = await getOrCreateAssociatedTokenAccount(
+      connection,
+      pool.payer,
+      pool.tokenAMint,
+      provider.wallet.publicKey
+    );
+    const creatorTokenB = await getOrCreateAssociatedTokenAccount(
+      connection,
+      pool.payer,
+      pool.tokenBMint,
+      provider.wallet.publicKey
+    );
+    const setMaxCreatorFee = (bps: number) =>
+      program.methods
+        .setMaxCreatorFee(bps)
+        .accounts({ authority: provider.wallet.publicKey })
+        .rpc();
+    const setCreatorFee = (bps: number) =>
+      program.methods
+        .setCreatorFee(bps)
+        .accounts({
+          liquidityPool: pool.liquidityPool,
+          creator: provider.wallet.publicKey,
+          creatorFeeAccountA: creatorTokenA.address,
+          creatorFeeAccountB: creatorTokenB.address,
+        })
+        .rpc();
 
-    const burnAmount = toTokenAmount(50);
+    await setMaxCreatorFee(1000);
+    try {
+      await setCreatorFee(1001);
+      assert.fail("A share above the global cap should be rejected");
+    } catch (err) {
+      assert.include(err.toString(), "CreatorFeeTooHigh");
+    }
+    await setCreatorFee(1000);
 
+    const creatorBefore = (await getAccount(connection, creatorTokenA.address))
+      .amount;
     await program.methods
-      .withdrawLiquidity(burnAmount)
+      .swapTokens(toTokenAmount(10), new anchor.BN(0), null, false, null, null)
       .accounts({
         liquidityPool: pool.liquidityPool,
         poolAuthority: pool.poolAuthority,
-        tokenAVault: pool.tokenAVault,
-        tokenBVault: pool.tokenBVault,
-        lpTokenMint: pool.lpTokenMint,
-        userTokenAAccount: liquidityProvider2.tokenAAccount,
-        userTokenBAccount: liquidityProvider2.tokenBAccount,
-        userLpTokenAccount: liquidityProvider2.lpTokenAccount,
-        user: liquidityProvider2.signer.publicKey,
+        inputTokenVault: pool.tokenAVault,
+        outputTokenVault: pool.tokenBVault,
+        inputTokenMint: pool.tokenAMint,
+        outputTokenMint: pool.tokenBMint,
+        oracle: pool.oracle,
+        userInputTokenAccount: tokenAAccount,
+        userOutputTokenAccount: tokenBAccount,
+        insuranceVault: null,
+        protocolFeeVault: null,
+        feeRecipient: null,
+        creatorFeeAccount: creatorTokenA.address,
+        traderState: null,
+        launchWhitelistEntry: null,
+        memoProgram: null,
+        volumeStats: null,
+        referrer: null,
+        referralVault: null,
+        user: swapper.publicKey,
+        inputTokenOwner: swapper.publicKey,
+        inputTokenProgram: TOKEN_PROGRAM_ID,
+        outputTokenProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([swapper])
+      .rpc();
+
+    // 10% of the 0.01% fee on 10 tokens
+    const creatorAfter = (await getAccount(connection, creatorTokenA.address))
+      .amount;
+    assert.equal(Number(creatorAfter - creatorBefore), 100_000);
+
+    await setCreatorFee(0);
+    await setMaxCreatorFee(0);
+
+    console.log("Creator fee paid on swap");
+  });
+
+  it("Accrues referral fees for the claim by the referrer", async () => {
+    const swapper = Keypair.generate();
+    const tokenAAccount = await createAssociatedTokenAccount(
+      connection,
+      pool.payer,
+      pool.tokenAMint,
+      swapper.publicKey
+    );
+    const tokenBAccount = await createAssociatedTokenAccount(
+      connection,
+      pool.payer,
+      pool.tokenBMint,
+      swapper.publicKey
+    );
+    await mintTo(
+      connection,
+      pool.payer,
+      pool.tokenAMint,
+      tokenAAccount,
+      pool.authority,
+      10 * Math.pow(10, TOKEN_DECIMALS)
+    );
+
+    const [referrer] = PublicKey.findProgramAddressSync(
+      [Buffer.from("referrer"), provider.wallet.publicKey.toBuffer()],
+      program.programId
+    );
+    const [referralVault] = PublicKey.findProgramAddressSync(
+      [Buffer.from("referral_vault"), pool.tokenAMint.toBuffer()],
+      program.programId
+    );
+    await program.methods
+      .registerReferrer()
+      .accounts({ authority: provider.wallet.publicKey })
+      .rpc();
+    await program.methods
+      .initializeReferralVault()
+      .accounts({
+        mint: pool.tokenAMint,
+        payer: provider.wallet.publicKey,
         tokenProgram: TOKEN_PROGRAM_ID,
       })
-      .signers([liquidityProvider2.signer])
       .rpc();
 
-    const afterTokenA = await getTokenBalance(liquidityProvider2.tokenAAccount);
-    const afterTokenB = await getTokenBalance(liquidityProvider2.tokenBAccount);
-    const afterLpTokens = await getTokenBalance(
-      liquidityProvider2.lpTokenAccount
+    const setReferralFee = (bps: number) =>
+      program.methods
+        .setReferralFee(bps)
+        .accounts({
+          liquidityPool: pool.liquidityPool,
+          admin: provider.wallet.publicKey,
+        })
+        .rpc();
+    await setReferralFee(5000);
+
+    await program.methods
+      .swapTokens(toTokenAmount(10), new anchor.BN(0), null, false, null, null)
+      .accounts({
+        liquidityPool: pool.liquidityPool,
+        poolAuthority: pool.poolAuthority,
+        inputTokenVault: pool.tokenAVault,
+        outputTokenVault: pool.tokenBVault,
+        inputTokenMint: pool.tokenAMint,
+        outputTokenMint: pool.tokenBMint,
+        oracle: pool.oracle,
+        userInputTokenAccount: tokenAAccount,
+        userOutputTokenAccount: tokenBAccount,
+        insuranceVault: null,
+        protocolFeeVault: null,
+        feeRecipient: null,
+        creatorFeeAccount: null,
+        traderState: null,
+        launchWhitelistEntry: null,
+        memoProgram: null,
+        volumeStats: null,
+        referrer,
+        referralVault,
+        user: swapper.publicKey,
+        inputTokenOwner: swapper.publicKey,
+        inputTokenProgram: TOKEN_PROGRAM_ID,
+        outputTokenProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([swapper])
+      .rpc();
+
+    // Half of the 0.01% fee on 10 tokens, held in the vault until claimed
+    const referrerState = await program.account.referrer.fetch(referrer);
+    const balance = referrerState.balances.find((entry) =>
+      entry.mint.equals(pool.tokenAMint)
     );
+    assert.equal(balance.amount.toNumber(), 500_000);
 
-    console.log("After withdrawal - Token A:", afterTokenA);
-    console.log("After withdrawal - Token B:", afterTokenB);
-    console.log("After withdrawal - LP tokens:", afterLpTokens);
+    const destination = await getOrCreateAssociatedTokenAccount(
+      connection,
+      pool.payer,
+      pool.tokenAMint,
+      provider.wallet.publicKey
+    );
+    const claim = () =>
+      program.methods
+        .claimReferralFees()
+        .accounts({
+          authority: provider.wallet.publicKey,
+          mint: pool.tokenAMint,
+          destination: destination.address,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .rpc();
+    const before = (await getAccount(connection, destination.address)).amount;
+    await claim();
+    const after = (await getAccount(connection, destination.address)).amount;
+    assert.equal(Number(after - before), 500_000);
 
-    assert(afterLpTokens < beforeLpTokens, "LP tokens should decrease");
-    assert(afterTokenA > beforeTokenA, "Token A balance should increase");
-    assert(afterTokenB > beforeTokenB, "Token B balance should increase");
+    try {
+      await claim();
+      assert.fail("A second claim should have nothing to pay");
+    } catch (err) {
+      assert.include(err.toString(), "NoReferralFees");
+    }
 
-    // Check for profit from swap fees
-    const tokenAGain = afterTokenA - beforeTokenA;
-    const tokenBGain = afterTokenB - beforeTokenB;
+    await setReferralFee(0);
 
-    console.log("Token A gained:", tokenAGain);
-    console.log("Token B gained:", tokenBGain);
+    console.log("Referral fee accrued and claimed");
+  });
 
-    // Should have earned more Token A (due to swap fee) and less Token B (impermanent loss)
-    assert(tokenAGain > 50, "Should earn profit in Token A from swap fees");
-    assert(tokenBGain < 50, "Should experience impermanent loss in Token B");
+  it("Charges the fee on the output token when configured", async () => {
+    const swapper = Keypair.generate();
+    const tokenAAccount = await createAssociatedTokenAccount(
+      connection,
+      pool.payer,
+      pool.tokenAMint,
+      swapper.publicKey
+    );
+    const tokenBAccount = await createAssociatedTokenAccount(
+      connection,
+      pool.payer,
+      pool.tokenBMint,
+      swapper.publicKey
+    );
+    await mintTo(
+      connection,
+      pool.payer,
+      pool.tokenAMint,
+      tokenAAccount,
+      pool.authority,
+      10 * Math.pow(10, TOKEN_DECIMALS)
+    );
 
-    console.log(
-      "Liquidity removed with profit from fees (impermanent loss visible)"
+    const creatorTokenA = await getOrCreateAssociatedTokenAccount(
+      connection,
+      pool.payer,
+      pool.tokenAMint,
+      provider.wallet.publicKey
+    );
+    const creatorTokenB = await getOrCreateAssociatedTokenAccount(
+      connection,
+      pool.payer,
+      pool.tokenBMint,
+      provider.wallet.publicKey
     );
+    const setCreatorFee = (bps: number) =>
+      program.methods
+        .setCreatorFee(bps)
+        .accounts({
+          liquidityPool: pool.liquidityPool,
+          creator: provider.wallet.publicKey,
+          creatorFeeAccountA: creatorTokenA.address,
+          creatorFeeAccountB: creatorTokenB.address,
+        })
+        .rpc();
+    const setFeeOnOutput = (feeOnOutput: boolean) =>
+      program.methods
+        .setFeeOnOutput(feeOnOutput)
+        .accounts({
+          liquidityPool: pool.liquidityPool,
+          admin: provider.wallet.publicKey,
+        })
+        .rpc();
+
+    await program.methods
+      .setMaxCreatorFee(1000)
+      .accounts({ authority: provider.wallet.publicKey })
+      .rpc();
+    await setCreatorFee(1000);
+    await setFeeOnOutput(true);
+    const poolState = await program.account.liquidityPool.fetch(
+      pool.liquidityPool
+    );
+    assert.equal(poolState.feeOnOutput, 1);
+
+    const creatorABefore = (await getAccount(connection, creatorTokenA.address))
+      .amount;
+    const creatorBBefore = (await getAccount(connection, creatorTokenB.address))
+      .amount;
+    await program.methods
+      .swapTokens(toTokenAmount(10), new anchor.BN(0), null, false, null, null)
+      .accounts({
+        liquidityPool: pool.liquidityPool,
+        poolAuthority: pool.poolAuthority,
+        inputTokenVault: pool.tokenAVault,
+        outputTokenVault: pool.tokenBVault,
+        inputTokenMint: pool.tokenAMint,
+        outputTokenMint: pool.tokenBMint,
+        oracle: pool.oracle,
+        userInputTokenAccount: tokenAAccount,
+        userOutputTokenAccount: tokenBAccount,
+        insuranceVault: null,
+        protocolFeeVault: null,
+        feeRecipient: null,
+        // The creator share follows the fee into the output mint
+        creatorFeeAccount: creatorTokenB.address,
+        traderState: null,
+        launchWhitelistEntry: null,
+        memoProgram: null,
+        volumeStats: null,
+        referrer: null,
+        referralVault: null,
+        user: swapper.publicKey,
+        inputTokenOwner: swapper.publicKey,
+        inputTokenProgram: TOKEN_PROGRAM_ID,
+        outputTokenProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([swapper])
+      .rpc();
+
+    const creatorAAfter = (await getAccount(connection, creatorTokenA.address))
+      .amount;
+    const creatorBAfter = (await getAccount(connection, creatorTokenB.address))
+      .amount;
+    assert.equal(creatorAAfter, creatorABefore);
+    assert(creatorBAfter > creatorBBefore, "Creator should be paid in token B");
+
+    await setFeeOnOutput(false);
+    await setCreatorFee(0);
+    await program.methods
+      .setMaxCreatorFee(0)
+      .accounts({ authority: provider.wallet.publicKey })
+      .rpc();
+  });
+
+  it("Swaps for an exact output within the maximum input", async () => {
+    const swapper = Keypair.generate();
+    const tokenAAccount = await createAssociatedTokenAccount(
+      connection,
+      pool.payer,
+      pool.tokenAMint,
+      swapper.publicKey
+    );
+    const tokenBAccount = await createAssociatedTokenAccount(
+      connection,
+      pool.payer,
+      pool.tokenBMint,
+      swapper.publicKey
+    );
+    await mintTo(
+      connection,
+      pool.payer,
+      pool.tokenAMint,
+      tokenAAccount,
+      pool.authority,
+      100 * Math.pow(10, TOKEN_DECIMALS)
+    );
+
+    const swapExactOutput = (
+      outputAmount: anchor.BN,
+      maximumInput: anchor.BN
+    ) =>
+      program.methods
+        .swapExactOutput(outputAmount, maximumInput, null)
+        .accounts({
+          liquidityPool: pool.liquidityPool,
+          poolAuthority: pool.poolAuthority,
+          inputTokenVault: pool.tokenAVault,
+          outputTokenVault: pool.tokenBVault,
+          inputTokenMint: pool.tokenAMint,
+          outputTokenMint: pool.tokenBMint,
+          oracle: pool.oracle,
+          userInputTokenAccount: tokenAAccount,
+          userOutputTokenAccount: tokenBAccount,
+          insuranceVault: null,
+          protocolFeeVault: null,
+          feeRecipient: null,
+          creatorFeeAccount: null,
+          traderState: null,
+          launchWhitelistEntry: null,
+          memoProgram: null,
+          volumeStats: null,
+          referrer: null,
+          referralVault: null,
+          user: swapper.publicKey,
+          inputTokenOwner: swapper.publicKey,
+          inputTokenProgram: TOKEN_PROGRAM_ID,
+          outputTokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([swapper])
+        .rpc();
+
+    // Buying 1 token B can't cost less than a single base unit of token A
+    try {
+      await swapExactOutput(toTokenAmount(1), new anchor.BN(1));
+      assert.fail("Swap above the maximum input should fail");
+    } catch (err) {
+      assert.include(err.toString(), "MaxInputExceeded");
+    }
+
+    await swapExactOutput(toTokenAmount(1), toTokenAmount(10));
+    assert.equal(await getTokenBalance(tokenBAccount), 1);
+    assert(
+      (await getTokenBalance(tokenAAccount)) >= 90,
+      "Input should stay within the maximum"
+    );
+
+    console.log("Exact output swap executed");
+  });
+
+  it("Swaps within a tolerance of the expected price", async () => {
+    const swapper = Keypair.generate();
+    const tokenAAccount = await createAssociatedTokenAccount(
+      connection,
+      pool.payer,
+      pool.tokenAMint,
+      swapper.publicKey
+    );
+    const tokenBAccount = await createAssociatedTokenAccount(
+      connection,
+      pool.payer,
+      pool.tokenBMint,
+      swapper.publicKey
+    );
+    await mintTo(
+      connection,
+      pool.payer,
+      pool.tokenAMint,
+      tokenAAccount,
+      pool.authority,
+      Math.pow(10, TOKEN_DECIMALS)
+    );
+
+    // Expect the current spot price; fee and price impact make it worse
+    const reserveA = (await getAccount(connection, pool.tokenAVault)).amount;
+    const reserveB = (await getAccount(connection, pool.tokenBVault)).amount;
+    const expectedPrice = new anchor.BN(reserveB.toString())
+      .shln(64)
+      .div(new anchor.BN(reserveA.toString()));
+
+    const swapAtExpectedPrice = (toleranceBps: number) =>
+      program.methods
+        .swapAtExpectedPrice(
+          toTokenAmount(1),
+          expectedPrice,
+          toleranceBps,
+          null
+        )
+        .accounts({
+          liquidityPool: pool.liquidityPool,
+          poolAuthority: pool.poolAuthority,
+          inputTokenVault: pool.tokenAVault,
+          outputTokenVault: pool.tokenBVault,
+          inputTokenMint: pool.tokenAMint,
+          outputTokenMint: pool.tokenBMint,
+          oracle: pool.oracle,
+          userInputTokenAccount: tokenAAccount,
+          userOutputTokenAccount: tokenBAccount,
+          insuranceVault: null,
+          protocolFeeVault: null,
+          feeRecipient: null,
+          creatorFeeAccount: null,
+          traderState: null,
+          launchWhitelistEntry: null,
+          memoProgram: null,
+          volumeStats: null,
+          referrer: null,
+          referralVault: null,
+          user: swapper.publicKey,
+          inputTokenOwner: swapper.publicKey,
+          inputTokenProgram: TOKEN_PROGRAM_ID,
+          outputTokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([swapper])
+        .rpc();
+
+    try {
+      await swapAtExpectedPrice(0);
+      assert.fail("Swap below the expected price should fail");
+    } catch (err) {
+      assert.include(err.toString(), "PriceToleranceExceeded");
+      assert(
+        err.logs.some((log: string) => log.includes("beyond the 0.00%")),
+        "Failure should report how far the price moved"
+      );
+    }
+
+    await swapAtExpectedPrice(500);
+    assert.equal(await getTokenBalance(tokenAAccount), 0);
+    assert(
+      (await getTokenBalance(tokenBAccount)) > 0,
+      "Swap should pay out"
+    );
+
+    console.log("Swap executed within the price tolerance");
+  });
+
+  it("Rejects swaps that trip the per-slot circuit breaker", async () => {
+    const swapper = Keypair.generate();
+    const tokenAAccount = await createAssociatedTokenAccount(
+      connection,
+      pool.payer,
+      pool.tokenAMint,
+      swapper.publicKey
+    );
+    const tokenBAccount = await createAssociatedTokenAccount(
+      connection,
+      pool.payer,
+      pool.tokenBMint,
+      swapper.publicKey
+    );
+    await mintTo(
+      connection,
+      pool.payer,
+      pool.tokenAMint,
+      tokenAAccount,
+      pool.authority,
+      10 * Math.pow(10, TOKEN_DECIMALS)
+    );
+
+    // Allow at most a 1% price move per slot
+    await program.methods
+      .setCircuitBreaker(100)
+      .accounts({
+        liquidityPool: pool.liquidityPool,
+        admin: provider.wallet.publicKey,
+      })
+      .rpc();
+
+    const swap = (allowPartialFill: boolean) =>
+      program.methods
+        .swapTokens(
+          toTokenAmount(10),
+          new anchor.BN(0),
+          null,
+          allowPartialFill,
+          null,
+          null
+        )
+        .accounts({
+          liquidityPool: pool.liquidityPool,
+          poolAuthority: pool.poolAuthority,
+          inputTokenVault: pool.tokenAVault,
+          outputTokenVault: pool.tokenBVault,
+          inputTokenMint: pool.tokenAMint,
+          outputTokenMint: pool.tokenBMint,
+          oracle: pool.oracle,
+          userInputTokenAccount: tokenAAccount,
+          userOutputTokenAccount: tokenBAccount,
+          insuranceVault: null,
+          protocolFeeVault: null,
+          feeRecipient: null,
+          creatorFeeAccount: null,
+          traderState: null,
+          launchWhitelistEntry: null,
+          memoProgram: null,
+          volumeStats: null,
+          referrer: null,
+          referralVault: null,
+          user: swapper.publicKey,
+          inputTokenOwner: swapper.publicKey,
+          inputTokenProgram: TOKEN_PROGRAM_ID,
+          outputTokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([swapper])
+        .rpc();
+
+    try {
+      await swap(false);
+      assert.fail("Swap should have been rejected by the circuit breaker");
+    } catch (err) {
+      assert.include(err.toString(), "PriceMovementExceeded");
+    }
+
+    // A partial fill swaps what the breaker allows and leaves the rest
+    await swap(true);
+    const remainingTokenA = await getTokenBalance(tokenAAccount);
+    assert(
+      remainingTokenA > 0 && remainingTokenA < 10,
+      "Only part of the input should be swapped"
+    );
+    assert(
+      (await getTokenBalance(tokenBAccount)) > 0,
+      "Filled part should pay out"
+    );
+
+    await program.methods
+      .setCircuitBreaker(0)
+      .accounts({
+        liquidityPool: pool.liquidityPool,
+        admin: provider.wallet.publicKey,
+      })
+      .rpc();
+
+    console.log("Circuit breaker rejected the oversized price move");
+  });
+
+  it("Rejects swaps past the pool's maximum price impact", async () => {
+    const swapper = Keypair.generate();
+    const tokenAAccount = await createAssociatedTokenAccount(
+      connection,
+      pool.payer,
+      pool.tokenAMint,
+      swapper.publicKey
+    );
+    const tokenBAccount = await createAssociatedTokenAccount(
+      connection,
+      pool.payer,
+      pool.tokenBMint,
+      swapper.publicKey
+    );
+    await mintTo(
+      connection,
+      pool.payer,
+      pool.tokenAMint,
+      tokenAAccount,
+      pool.authority,
+      10 * Math.pow(10, TOKEN_DECIMALS)
+    );
+
+    // Allow at most a 1% price impact per swap
+    await program.methods
+      .setMaxPriceImpact(100)
+      .accounts({
+        liquidityPool: pool.liquidityPool,
+        admin: provider.wallet.publicKey,
+      })
+      .rpc();
+
+    const swap = (allowPartialFill: boolean) =>
+      program.methods
+        .swapTokens(
+          toTokenAmount(10),
+          new anchor.BN(0),
+          null,
+          allowPartialFill,
+          null,
+          null
+        )
+        .accounts({
+          liquidityPool: pool.liquidityPool,
+          poolAuthority: pool.poolAuthority,
+          inputTokenVault: pool.tokenAVault,
+          outputTokenVault: pool.tokenBVault,
+          inputTokenMint: pool.tokenAMint,
+          outputTokenMint: pool.tokenBMint,
+          oracle: pool.oracle,
+          userInputTokenAccount: tokenAAccount,
+          userOutputTokenAccount: tokenBAccount,
+          insuranceVault: null,
+          protocolFeeVault: null,
+          feeRecipient: null,
+          creatorFeeAccount: null,
+          traderState: null,
+          launchWhitelistEntry: null,
+          memoProgram: null,
+          volumeStats: null,
+          referrer: null,
+          referralVault: null,
+          user: swapper.publicKey,
+          inputTokenOwner: swapper.publicKey,
+          inputTokenProgram: TOKEN_PROGRAM_ID,
+          outputTokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([swapper])
+        .rpc();
+
+    // A zero minimum output does not get a fat-fingered trade through
+    try {
+      await swap(false);
+      assert.fail("Swap should have been rejected for its price impact");
+    } catch (err) {
+      assert.include(err.toString(), "PriceImpactTooHigh");
+    }
+
+    // A partial fill swaps up to the impact limit
+    await swap(true);
+    const remainingTokenA = await getTokenBalance(tokenAAccount);
+    assert(
+      remainingTokenA > 0 && remainingTokenA < 10,
+      "Only part of the input should be swapped"
+    );
+
+    await program.methods
+      .setMaxPriceImpact(0)
+      .accounts({
+        liquidityPool: pool.liquidityPool,
+        admin: provider.wallet.publicKey,
+      })
+      .rpc();
+
+    console.log("Pool rejected the high-impact swap");
+  });
+
+  it("Stops a swap at the trader's price limit", async () => {
+    const swapper = Keypair.generate();
+    const tokenAAccount = await createAssociatedTokenAccount(
+      connection,
+      pool.payer,
+      pool.tokenAMint,
+      swapper.publicKey
+    );
+    const tokenBAccount = await createAssociatedTokenAccount(
+      connection,
+      pool.payer,
+      pool.tokenBMint,
+      swapper.publicKey
+    );
+    await mintTo(
+      connection,
+      pool.payer,
+      pool.tokenAMint,
+      tokenAAccount,
+      pool.authority,
+      10 * Math.pow(10, TOKEN_DECIMALS)
+    );
+
+    // Selling token A lowers its price; accept at most a 1% drop
+    const spotPrice = async () => {
+      const reserveA = (await getAccount(connection, pool.tokenAVault)).amount;
+      const reserveB = (await getAccount(connection, pool.tokenBVault)).amount;
+      // Q64.64, the format of on-chain prices
+      return new anchor.BN(reserveB.toString())
+        .shln(64)
+        .div(new anchor.BN(reserveA.toString()));
+    };
+    const priceLimit = (await spotPrice()).muln(99).divn(100);
+
+    const swap = (allowPartialFill: boolean) =>
+      program.methods
+        .swapTokens(
+          toTokenAmount(10),
+          new anchor.BN(0),
+          null,
+          allowPartialFill,
+          priceLimit,
+          null
+        )
+        .accounts({
+          liquidityPool: pool.liquidityPool,
+          poolAuthority: pool.poolAuthority,
+          inputTokenVault: pool.tokenAVault,
+          outputTokenVault: pool.tokenBVault,
+          inputTokenMint: pool.tokenAMint,
+          outputTokenMint: pool.tokenBMint,
+          oracle: pool.oracle,
+          userInputTokenAccount: tokenAAccount,
+          userOutputTokenAccount: tokenBAccount,
+          insuranceVault: null,
+          protocolFeeVault: null,
+          feeRecipient: null,
+          creatorFeeAccount: null,
+          traderState: null,
+          launchWhitelistEntry: null,
+          memoProgram: null,
+          volumeStats: null,
+          referrer: null,
+          referralVault: null,
+          user: swapper.publicKey,
+          inputTokenOwner: swapper.publicKey,
+          inputTokenProgram: TOKEN_PROGRAM_ID,
+          outputTokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([swapper])
+        .rpc();
+
+    try {
+      await swap(false);
+      assert.fail("Swap past the price limit should fail");
+    } catch (err) {
+      assert.include(err.toString(), "PriceLimitExceeded");
+    }
+
+    // A partial fill stops at the limit
+    await swap(true);
+    const remainingTokenA = await getTokenBalance(tokenAAccount);
+    assert(
+      remainingTokenA > 0 && remainingTokenA < 10,
+      "Only part of the input should be swapped"
+    );
+    assert(
+      (await spotPrice()).gte(priceLimit),
+      "Price should stay above the limit"
+    );
+
+    console.log("Swap stopped at the price limit");
+  });
+
+  it("Rejects swaps while the program is paused", async () => {
+    const swapper = Keypair.generate();
+    const tokenAAccount = await createAssociatedTokenAccount(
+      connection,
+      pool.payer,
+      pool.tokenAMint,
+      swapper.publicKey
+    );
+    const tokenBAccount = await createAssociatedTokenAccount(
+      connection,
+      pool.payer,
+      pool.tokenBMint,
+      swapper.publicKey
+    );
+    await mintTo(
+      connection,
+      pool.payer,
+      pool.tokenAMint,
+      tokenAAccount,
+      pool.authority,
+      1 * Math.pow(10, TOKEN_DECIMALS)
+    );
+
+    await program.methods
+      .setPaused(true)
+      .accounts({ authority: provider.wallet.publicKey })
+      .rpc();
+
+    try {
+      await program.methods
+        .swapTokens(toTokenAmount(1), new anchor.BN(0), null, false, null, null)
+        .accounts({
+          liquidityPool: pool.liquidityPool,
+          poolAuthority: pool.poolAuthority,
+          inputTokenVault: pool.tokenAVault,
+          outputTokenVault: pool.tokenBVault,
+          inputTokenMint: pool.tokenAMint,
+          outputTokenMint: pool.tokenBMint,
+          oracle: pool.oracle,
+          userInputTokenAccount: tokenAAccount,
+          userOutputTokenAccount: tokenBAccount,
+          insuranceVault: null,
+          protocolFeeVault: null,
+          feeRecipient: null,
+          creatorFeeAccount: null,
+          traderState: null,
+          launchWhitelistEntry: null,
+          memoProgram: null,
+          volumeStats: null,
+          referrer: null,
+          referralVault: null,
+          user: swapper.publicKey,
+          inputTokenOwner: swapper.publicKey,
+          inputTokenProgram: TOKEN_PROGRAM_ID,
+          outputTokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([swapper])
+        .rpc();
+      assert.fail("Swap should have been rejected while paused");
+    } catch (err) {
+      assert.include(err.toString(), "ProgramPaused");
+    }
+
+    await program.methods
+      .setPaused(false)
+      .accounts({ authority: provider.wallet.publicKey })
+      .rpc();
+
+    console.log("Pause rejected the swap");
+  });
+
+  it("Skims tokens sent straight to a vault into the treasury", async () => {
+    const [treasury] = PublicKey.findProgramAddressSync(
+      [Buffer.from("treasury")],
+      program.programId
+    );
+    const treasuryTokenA = await getOrCreateAssociatedTokenAccount(
+      connection,
+      pool.payer,
+      pool.tokenAMint,
+      treasury,
+      true
+    );
+    const treasuryTokenB = await getOrCreateAssociatedTokenAccount(
+      connection,
+      pool.payer,
+      pool.tokenBMint,
+      treasury,
+      true
+    );
+
+    // Donate 1 token A without going through the program
+    await mintTo(
+      connection,
+      pool.payer,
+      pool.tokenAMint,
+      pool.tokenAVault,
+      pool.authority,
+      1 * Math.pow(10, TOKEN_DECIMALS)
+    );
+    const vaultBefore = await getTokenBalance(pool.tokenAVault);
+
+    await program.methods
+      .skim()
+      .accounts({
+        liquidityPool: pool.liquidityPool,
+        poolAuthority: pool.poolAuthority,
+        tokenAMint: pool.tokenAMint,
+        tokenBMint: pool.tokenBMint,
+        tokenAVault: pool.tokenAVault,
+        tokenBVault: pool.tokenBVault,
+        treasuryTokenAAccount: treasuryTokenA.address,
+        treasuryTokenBAccount: treasuryTokenB.address,
+        tokenAProgram: TOKEN_PROGRAM_ID,
+        tokenBProgram: TOKEN_PROGRAM_ID,
+      })
+      .rpc();
+
+    assert.equal(await getTokenBalance(treasuryTokenA.address), 1);
+    assert.equal(await getTokenBalance(treasuryTokenB.address), 0);
+    assert.equal(await getTokenBalance(pool.tokenAVault), vaultBefore - 1);
+
+    console.log("Donated tokens skimmed to the treasury");
+  });
+
+  it("Prices swap_v2 against tracked reserves, leaving donations", async () => {
+    const swapper = Keypair.generate();
+    const tokenAAccount = await createAssociatedTokenAccount(
+      connection,
+      pool.payer,
+      pool.tokenAMint,
+      swapper.publicKey
+    );
+    const tokenBAccount = await createAssociatedTokenAccount(
+      connection,
+      pool.payer,
+      pool.tokenBMint,
+      swapper.publicKey
+    );
+    await mintTo(
+      connection,
+      pool.payer,
+      pool.tokenAMint,
+      tokenAAccount,
+      pool.authority,
+      1 * Math.pow(10, TOKEN_DECIMALS)
+    );
+
+    // Donate 1 token A without going through the program
+    await mintTo(
+      connection,
+      pool.payer,
+      pool.tokenAMint,
+      pool.tokenAVault,
+      pool.authority,
+      1 * Math.pow(10, TOKEN_DECIMALS)
+    );
+    const surplus = async () => {
+      const poolState = await program.account.liquidityPool.fetch(
+        pool.liquidityPool
+      );
+      const vault = await getAccount(connection, pool.tokenAVault);
+      return new anchor.BN(vault.amount.toString()).sub(poolState.reserveA);
+    };
+    const surplusBefore = await surplus();
+    assert(
+      surplusBefore.eq(toTokenAmount(1)),
+      "The donation should sit above the reserves"
+    );
+
+    await program.methods
+      .swapV2(toTokenAmount(1), new anchor.BN(0), null, false, null, null)
+      .accounts({
+        liquidityPool: pool.liquidityPool,
+        poolAuthority: pool.poolAuthority,
+        inputTokenVault: pool.tokenAVault,
+        outputTokenVault: pool.tokenBVault,
+        inputTokenMint: pool.tokenAMint,
+        outputTokenMint: pool.tokenBMint,
+        oracle: pool.oracle,
+        userInputTokenAccount: tokenAAccount,
+        userOutputTokenAccount: tokenBAccount,
+        insuranceVault: null,
+        protocolFeeVault: null,
+        feeRecipient: null,
+        creatorFeeAccount: null,
+        traderState: null,
+        launchWhitelistEntry: null,
+        memoProgram: null,
+        volumeStats: null,
+        referrer: null,
+        referralVault: null,
+        user: swapper.publicKey,
+        inputTokenOwner: swapper.publicKey,
+        inputTokenProgram: TOKEN_PROGRAM_ID,
+        outputTokenProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([swapper])
+      .rpc();
+
+    // The swap moved the reserves only by its own flows
+    assert((await surplus()).eq(surplusBefore), "Surplus should be untouched");
+    assert(
+      (await getTokenBalance(tokenBAccount)) > 0,
+      "Swapper should receive token B"
+    );
+
+    // The donation is still skimmable
+    const [treasury] = PublicKey.findProgramAddressSync(
+      [Buffer.from("treasury")],
+      program.programId
+    );
+    const treasuryTokenA = await getOrCreateAssociatedTokenAccount(
+      connection,
+      pool.payer,
+      pool.tokenAMint,
+      treasury,
+      true
+    );
+    const treasuryTokenB = await getOrCreateAssociatedTokenAccount(
+      connection,
+      pool.payer,
+      pool.tokenBMint,
+      treasury,
+      true
+    );
+    const treasuryBefore = await getTokenBalance(treasuryTokenA.address);
+    await program.methods
+      .skim()
+      .accounts({
+        liquidityPool: pool.liquidityPool,
+        poolAuthority: pool.poolAuthority,
+        tokenAMint: pool.tokenAMint,
+        tokenBMint: pool.tokenBMint,
+        tokenAVault: pool.tokenAVault,
+        tokenBVault: pool.tokenBVault,
+        treasuryTokenAAccount: treasuryTokenA.address,
+        treasuryTokenBAccount: treasuryTokenB.address,
+        tokenAProgram: TOKEN_PROGRAM_ID,
+        tokenBProgram: TOKEN_PROGRAM_ID,
+      })
+      .rpc();
+    assert.equal(
+      await getTokenBalance(treasuryTokenA.address),
+      treasuryBefore + 1
+    );
+
+    console.log("swap_v2 left the donation for skim");
+  });
+
+  it("Donates to the pool's reserves without minting LP tokens", async () => {
+    const donor = provider.wallet.publicKey;
+    const donorTokenA = await getOrCreateAssociatedTokenAccount(
+      connection,
+      pool.payer,
+      pool.tokenAMint,
+      donor
+    );
+    const donorTokenB = await getOrCreateAssociatedTokenAccount(
+      connection,
+      pool.payer,
+      pool.tokenBMint,
+      donor
+    );
+    for (const [mint, account] of [
+      [pool.tokenAMint, donorTokenA.address],
+      [pool.tokenBMint, donorTokenB.address],
+    ]) {
+      await mintTo(
+        connection,
+        pool.payer,
+        mint,
+        account,
+        pool.authority,
+        2 * Math.pow(10, TOKEN_DECIMALS)
+      );
+    }
+
+    const donate = (amountA: anchor.BN, amountB: anchor.BN) =>
+      program.methods
+        .donateToPool(amountA, amountB)
+        .accounts({
+          liquidityPool: pool.liquidityPool,
+          tokenAMint: pool.tokenAMint,
+          tokenBMint: pool.tokenBMint,
+          tokenAVault: pool.tokenAVault,
+          tokenBVault: pool.tokenBVault,
+          donorTokenAAccount: donorTokenA.address,
+          donorTokenBAccount: donorTokenB.address,
+          donor: donor,
+          tokenAProgram: TOKEN_PROGRAM_ID,
+          tokenBProgram: TOKEN_PROGRAM_ID,
+        })
+        .rpc();
+
+    try {
+      await donate(new anchor.BN(0), new anchor.BN(0));
+      assert.fail("An empty donation should be rejected");
+    } catch (err) {
+      assert.include(err.toString(), "InvalidDonationAmount");
+    }
+
+    const before = await program.account.liquidityPool.fetch(
+      pool.liquidityPool
+    );
+    await donate(toTokenAmount(2), toTokenAmount(2));
+    const after = await program.account.liquidityPool.fetch(pool.liquidityPool);
+
+    assert.equal(
+      after.reserveA.sub(before.reserveA).toString(),
+      toTokenAmount(2).toString()
+    );
+    assert.equal(
+      after.reserveB.sub(before.reserveB).toString(),
+      toTokenAmount(2).toString()
+    );
+    assert.equal(
+      after.totalLpTokensIssued.toString(),
+      before.totalLpTokensIssued.toString()
+    );
+    assert(
+      after.feeGrowthPerLp.gt(before.feeGrowthPerLp),
+      "Liquidity per LP token should grow"
+    );
+
+    console.log("Donation added to the reserves");
+  });
+
+  it("Withdraws from a treasury vault within its limits", async () => {
+    const [treasuryVault] = PublicKey.findProgramAddressSync(
+      [Buffer.from("treasury_vault"), pool.tokenAMint.toBuffer()],
+      program.programId
+    );
+    await program.methods
+      .initializeTreasuryVault()
+      .accounts({
+        mint: pool.tokenAMint,
+        payer: provider.wallet.publicKey,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .rpc();
+    await mintTo(
+      connection,
+      pool.payer,
+      pool.tokenAMint,
+      treasuryVault,
+      pool.authority,
+      3 * Math.pow(10, TOKEN_DECIMALS)
+    );
+
+    // At most 1 token per call and 1.5 tokens per epoch
+    await program.methods
+      .setTreasuryLimits(
+        pool.tokenAMint,
+        toTokenAmount(1),
+        toTokenAmount(1.5)
+      )
+      .accounts({ authority: provider.wallet.publicKey })
+      .rpc();
+
+    const destination = await getOrCreateAssociatedTokenAccount(
+      connection,
+      pool.payer,
+      pool.tokenAMint,
+      provider.wallet.publicKey
+    );
+    const destinationBefore = await getTokenBalance(destination.address);
+    const withdraw = (amount: number) =>
+      program.methods
+        .withdrawTreasury(toTokenAmount(amount))
+        .accounts({
+          mint: pool.tokenAMint,
+          destination: destination.address,
+          authority: provider.wallet.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .rpc();
+
+    await withdraw(1);
+    assert.equal(
+      await getTokenBalance(destination.address),
+      destinationBefore + 1
+    );
+
+    for (const amount of [2, 1]) {
+      try {
+        await withdraw(amount);
+        assert.fail("Withdrawal past the limits should have failed");
+      } catch (err) {
+        assert.include(err.toString(), "TreasuryWithdrawLimitExceeded");
+      }
+    }
+    assert.equal(await getTokenBalance(treasuryVault), 2);
+
+    console.log("Treasury withdrawal held to its limits");
+  });
+
+  it("Harvests protocol fees into the treasury and tips the caller", async () => {
+    const keeper = Keypair.generate();
+    await connection.confirmTransaction(
+      await connection.requestAirdrop(keeper.publicKey, LAMPORTS_PER_SOL)
+    );
+    const [swapperTokenA, swapperTokenB] = await setupLiquidityProvider(
+      keeper.publicKey,
+      10
+    );
+    const [protocolFeeVaultA] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("protocol_fee_vault"),
+        pool.liquidityPool.toBuffer(),
+        pool.tokenAMint.toBuffer(),
+      ],
+      program.programId
+    );
+    const [treasuryVaultA] = PublicKey.findProgramAddressSync(
+      [Buffer.from("treasury_vault"), pool.tokenAMint.toBuffer()],
+      program.programId
+    );
+    const rawBalance = async (account: PublicKey) =>
+      new anchor.BN((await getAccount(connection, account)).amount.toString());
+    const adminAccounts = {
+      liquidityPool: pool.liquidityPool,
+      admin: provider.wallet.publicKey,
+    };
+
+    await program.methods
+      .initializeProtocolFeeVaults()
+      .accounts({
+        liquidityPool: pool.liquidityPool,
+        tokenAMint: pool.tokenAMint,
+        tokenBMint: pool.tokenBMint,
+        payer: provider.wallet.publicKey,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .rpc();
+    // The token A treasury vault was created by the withdrawal test
+    await program.methods
+      .initializeTreasuryVault()
+      .accounts({
+        mint: pool.tokenBMint,
+        payer: provider.wallet.publicKey,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .rpc();
+
+    // Half the swap fee goes to the protocol, half of that funds keeper tips
+    await program.methods
+      .setProtocolFee(5000, null)
+      .accounts(adminAccounts)
+      .rpc();
+    await program.methods
+      .setKeeperTips(new anchor.BN(100), 5000)
+      .accounts(adminAccounts)
+      .rpc();
+
+    await program.methods
+      .swapTokens(toTokenAmount(5), new anchor.BN(0), null, false, null, null)
+      .accounts({
+        liquidityPool: pool.liquidityPool,
+        poolAuthority: pool.poolAuthority,
+        inputTokenVault: pool.tokenAVault,
+        outputTokenVault: pool.tokenBVault,
+        inputTokenMint: pool.tokenAMint,
+        outputTokenMint: pool.tokenBMint,
+        oracle: pool.oracle,
+        userInputTokenAccount: swapperTokenA,
+        userOutputTokenAccount: swapperTokenB,
+        insuranceVault: null,
+        protocolFeeVault: protocolFeeVaultA,
+        feeRecipient: null,
+        creatorFeeAccount: null,
+        traderState: null,
+        launchWhitelistEntry: null,
+        memoProgram: null,
+        volumeStats: null,
+        referrer: null,
+        referralVault: null,
+        user: keeper.publicKey,
+        inputTokenOwner: keeper.publicKey,
+        inputTokenProgram: TOKEN_PROGRAM_ID,
+        outputTokenProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([keeper])
+      .rpc();
+
+    const budget = (
+      await program.account.liquidityPool.fetch(pool.liquidityPool)
+    ).keeperTipBudget;
+    const accrued = await rawBalance(protocolFeeVaultA);
+    assert(budget.gtn(0), "Protocol fees should fund the tip budget");
+    const treasuryBefore = await rawBalance(treasuryVaultA);
+    const keeperBefore = await rawBalance(swapperTokenA);
+
+    const harvest = () =>
+      program.methods
+        .harvestProtocolFees()
+        .accounts({
+          liquidityPool: pool.liquidityPool,
+          tokenAMint: pool.tokenAMint,
+          tokenBMint: pool.tokenBMint,
+          keeperTokenAAccount: swapperTokenA,
+          tokenAProgram: TOKEN_PROGRAM_ID,
+          tokenBProgram: TOKEN_PROGRAM_ID,
+        })
+        .rpc();
+    await harvest();
+
+    // The treasury gets everything but the budget, which pays the tip
+    assert.equal(
+      (await rawBalance(treasuryVaultA)).sub(treasuryBefore).toString(),
+      accrued.sub(budget).toString()
+    );
+    assert.equal(
+      (await rawBalance(swapperTokenA)).sub(keeperBefore).toString(),
+      "100"
+    );
+    assert.equal(
+      (await rawBalance(protocolFeeVaultA)).toString(),
+      budget.subn(100).toString()
+    );
+
+    try {
+      await harvest();
+      assert.fail("Nothing is left to harvest");
+    } catch (err) {
+      assert.include(err.toString(), "NoProtocolFeesToHarvest");
+    }
+
+    await program.methods
+      .setProtocolFee(0, null)
+      .accounts(adminAccounts)
+      .rpc();
+    await program.methods
+      .setKeeperTips(new anchor.BN(0), 0)
+      .accounts(adminAccounts)
+      .rpc();
+
+    console.log("Protocol fees harvested into the treasury");
+  });
+
+  it("Rejects a protocol fee vault passed as a swap vault", async () => {
+    const attacker = Keypair.generate();
+    await connection.confirmTransaction(
+      await connection.requestAirdrop(attacker.publicKey, LAMPORTS_PER_SOL)
+    );
+    const [attackerTokenA, attackerTokenB] = await setupLiquidityProvider(
+      attacker.publicKey,
+      10
+    );
+    // Owned by the pool authority and fundable by anyone
+    const [protocolFeeVaultB] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("protocol_fee_vault"),
+        pool.liquidityPool.toBuffer(),
+        pool.tokenBMint.toBuffer(),
+      ],
+      program.programId
+    );
+    await mintTo(
+      connection,
+      pool.payer,
+      pool.tokenBMint,
+      protocolFeeVaultB,
+      pool.authority,
+      1_000 * Math.pow(10, TOKEN_DECIMALS)
+    );
+    const reservesBefore = await program.account.liquidityPool.fetch(
+      pool.liquidityPool
+    );
+
+    try {
+      await program.methods
+        .swapV2(toTokenAmount(1), new anchor.BN(0), null, false, null, null)
+        .accounts({
+          liquidityPool: pool.liquidityPool,
+          poolAuthority: pool.poolAuthority,
+          inputTokenVault: pool.tokenAVault,
+          outputTokenVault: protocolFeeVaultB,
+          inputTokenMint: pool.tokenAMint,
+          outputTokenMint: pool.tokenBMint,
+          oracle: pool.oracle,
+          userInputTokenAccount: attackerTokenA,
+          userOutputTokenAccount: attackerTokenB,
+          insuranceVault: null,
+          protocolFeeVault: null,
+          feeRecipient: null,
+          creatorFeeAccount: null,
+          traderState: null,
+          launchWhitelistEntry: null,
+          memoProgram: null,
+          volumeStats: null,
+          referrer: null,
+          referralVault: null,
+          user: attacker.publicKey,
+          inputTokenOwner: attacker.publicKey,
+          inputTokenProgram: TOKEN_PROGRAM_ID,
+          outputTokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([attacker])
+        .rpc();
+      assert.fail("Only the pool's own vaults may be swapped against");
+    } catch (err) {
+      assert.include(err.toString(), "ConstraintSeeds");
+    }
+
+    const reservesAfter = await program.account.liquidityPool.fetch(
+      pool.liquidityPool
+    );
+    assert.equal(
+      reservesAfter.reserveB.toString(),
+      reservesBefore.reserveB.toString()
+    );
+
+    console.log("Foreign vault rejected as a swap vault");
+  });
+
+  it("Executes a DCA tranche through a keeper and closes the schedule", async () => {
+    const owner = Keypair.generate();
+    const keeper = Keypair.generate();
+    for (const wallet of [owner, keeper]) {
+      await connection.confirmTransaction(
+        await connection.requestAirdrop(wallet.publicKey, LAMPORTS_PER_SOL)
+      );
+    }
+    const tokenAAccount = await createAssociatedTokenAccount(
+      connection,
+      pool.payer,
+      pool.tokenAMint,
+      owner.publicKey
+    );
+    const tokenBAccount = await createAssociatedTokenAccount(
+      connection,
+      pool.payer,
+      pool.tokenBMint,
+      owner.publicKey
+    );
+    await mintTo(
+      connection,
+      pool.payer,
+      pool.tokenAMint,
+      tokenAAccount,
+      pool.authority,
+      2 * Math.pow(10, TOKEN_DECIMALS)
+    );
+
+    const dcaId = 0;
+    const [dcaPosition] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("dca"),
+        pool.liquidityPool.toBuffer(),
+        owner.publicKey.toBuffer(),
+        new anchor.BN(dcaId).toArrayLike(Buffer, "le", 2),
+      ],
+      program.programId
+    );
+    const [dcaVault] = PublicKey.findProgramAddressSync(
+      [Buffer.from("dca_vault"), dcaPosition.toBuffer()],
+      program.programId
+    );
+
+    // Two tranches of 1 token A, one per hour, 5000 lamports per tranche to the keeper
+    await program.methods
+      .openDca(
+        dcaId,
+        toTokenAmount(2),
+        toTokenAmount(1),
+        new anchor.BN(3600),
+        new anchor.BN(0),
+        new anchor.BN(5000)
+      )
+      .accounts({
+        liquidityPool: pool.liquidityPool,
+        dcaPosition: dcaPosition,
+        inputMint: pool.tokenAMint,
+        outputMint: pool.tokenBMint,
+        dcaVault: dcaVault,
+        ownerInputTokenAccount: tokenAAccount,
+        owner: owner.publicKey,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([owner])
+      .rpc();
+
+    const keeperBefore = await connection.getBalance(keeper.publicKey);
+    const executeAccounts = {
+      dcaPosition: dcaPosition,
+      dcaVault: dcaVault,
+      liquidityPool: pool.liquidityPool,
+      poolAuthority: pool.poolAuthority,
+      tokenAMint: pool.tokenAMint,
+      tokenBMint: pool.tokenBMint,
+      tokenAVault: pool.tokenAVault,
+      tokenBVault: pool.tokenBVault,
+      ownerOutputTokenAccount: tokenBAccount,
+      keeper: keeper.publicKey,
+      protocolFeeVaultA: null,
+      keeperTokenAAccount: null,
+      tokenProgram: TOKEN_PROGRAM_ID,
+    };
+    await program.methods
+      .executeDca()
+      .accounts(executeAccounts)
+      .signers([keeper])
+      .rpc();
+
+    assert.equal(await getTokenBalance(dcaVault), 1);
+    assert(
+      (await getTokenBalance(tokenBAccount)) > 0,
+      "Owner should receive token B"
+    );
+
+    // The next tranche is not due for another hour
+    try {
+      await program.methods
+        .executeDca()
+        .accounts(executeAccounts)
+        .signers([keeper])
+        .rpc();
+      assert.fail("Second tranche should not be due yet");
+    } catch (err) {
+      assert.include(err.toString(), "DcaNotDue");
+    }
+
+    await program.methods
+      .closeDca()
+      .accounts({
+        dcaPosition: dcaPosition,
+        dcaVault: dcaVault,
+        inputMint: pool.tokenAMint,
+        ownerInputTokenAccount: tokenAAccount,
+        owner: owner.publicKey,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([owner])
+      .rpc();
+
+    assert.equal(await getTokenBalance(tokenAAccount), 1);
+    assert(
+      (await connection.getBalance(keeper.publicKey)) >= keeperBefore,
+      "Keeper tip should cover the transaction fee"
+    );
+
+    console.log("DCA tranche executed and schedule closed");
+  });
+
+  it("Fills a limit order once the pool price satisfies it", async () => {
+    const owner = Keypair.generate();
+    const keeper = Keypair.generate();
+    for (const wallet of [owner, keeper]) {
+      await connection.confirmTransaction(
+        await connection.requestAirdrop(wallet.publicKey, LAMPORTS_PER_SOL)
+      );
+    }
+    const tokenAAccount = await createAssociatedTokenAccount(
+      connection,
+      pool.payer,
+      pool.tokenAMint,
+      owner.publicKey
+    );
+    const tokenBAccount = await createAssociatedTokenAccount(
+      connection,
+      pool.payer,
+      pool.tokenBMint,
+      owner.publicKey
+    );
+    await mintTo(
+      connection,
+      pool.payer,
+      pool.tokenAMint,
+      tokenAAccount,
+      pool.authority,
+      2 * Math.pow(10, TOKEN_DECIMALS)
+    );
+
+    const orderAccounts = (orderId: number) => {
+      const [limitOrder] = PublicKey.findProgramAddressSync(
+        [
+          Buffer.from("limit_order"),
+          pool.liquidityPool.toBuffer(),
+          owner.publicKey.toBuffer(),
+          new anchor.BN(orderId).toArrayLike(Buffer, "le", 8),
+        ],
+        program.programId
+      );
+      const [orderVault] = PublicKey.findProgramAddressSync(
+        [Buffer.from("limit_order_vault"), limitOrder.toBuffer()],
+        program.programId
+      );
+      return { limitOrder, orderVault };
+    };
+    const placeOrder = async (orderId: number, minOutput: anchor.BN) => {
+      const { limitOrder, orderVault } = orderAccounts(orderId);
+      await program.methods
+        .placeLimitOrder(
+          new anchor.BN(orderId),
+          toTokenAmount(1),
+          minOutput,
+          new anchor.BN(Math.floor(Date.now() / 1000) + 3600),
+          new anchor.BN(5000)
+        )
+        .accounts({
+          liquidityPool: pool.liquidityPool,
+          limitOrder: limitOrder,
+          inputMint: pool.tokenAMint,
+          outputMint: pool.tokenBMint,
+          orderVault: orderVault,
+          ownerInputTokenAccount: tokenAAccount,
+          owner: owner.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([owner])
+        .rpc();
+    };
+    const fillOrder = async (orderId: number) => {
+      const { limitOrder, orderVault } = orderAccounts(orderId);
+      await program.methods
+        .fillLimitOrder()
+        .accounts({
+          limitOrder: limitOrder,
+          orderVault: orderVault,
+          liquidityPool: pool.liquidityPool,
+          poolAuthority: pool.poolAuthority,
+          tokenAMint: pool.tokenAMint,
+          tokenBMint: pool.tokenBMint,
+          tokenAVault: pool.tokenAVault,
+          tokenBVault: pool.tokenBVault,
+          ownerOutputTokenAccount: tokenBAccount,
+          owner: owner.publicKey,
+          keeper: keeper.publicKey,
+          protocolFeeVaultA: null,
+          keeperTokenAAccount: null,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([keeper])
+        .rpc();
+    };
+
+    // A limit far above the pool price cannot fill, and is cancelled
+    await placeOrder(0, toTokenAmount(1000));
+    try {
+      await fillOrder(0);
+      assert.fail("Order should not fill below its limit price");
+    } catch (err) {
+      assert.include(err.toString(), "LimitPriceNotReached");
+    }
+    await program.methods
+      .cancelLimitOrder()
+      .accounts({
+        limitOrder: orderAccounts(0).limitOrder,
+        orderVault: orderAccounts(0).orderVault,
+        inputMint: pool.tokenAMint,
+        ownerInputTokenAccount: tokenAAccount,
+        owner: owner.publicKey,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([owner])
+      .rpc();
+    assert.equal(await getTokenBalance(tokenAAccount), 2);
+
+    // A limit below the pool price fills right away
+    await placeOrder(1, new anchor.BN(1));
+    await fillOrder(1);
+    assert.equal(await getTokenBalance(tokenAAccount), 1);
+    assert(
+      (await getTokenBalance(tokenBAccount)) > 0,
+      "Owner should receive token B"
+    );
+    assert.isNull(
+      await connection.getAccountInfo(orderAccounts(1).limitOrder),
+      "Filled order should be closed"
+    );
+
+    console.log("Limit order filled by keeper");
+  });
+
+  it("Swaps with a delegate as the input authority", async () => {
+    const owner = Keypair.generate();
+    const delegate = Keypair.generate();
+    await connection.confirmTransaction(
+      await connection.requestAirdrop(delegate.publicKey, LAMPORTS_PER_SOL)
+    );
+    const ownerTokenAAccount = await createAssociatedTokenAccount(
+      connection,
+      pool.payer,
+      pool.tokenAMint,
+      owner.publicKey
+    );
+    await mintTo(
+      connection,
+      pool.payer,
+      pool.tokenAMint,
+      ownerTokenAAccount,
+      pool.authority,
+      2 * Math.pow(10, TOKEN_DECIMALS)
+    );
+    await approve(
+      connection,
+      pool.payer,
+      ownerTokenAAccount,
+      delegate.publicKey,
+      owner,
+      1 * Math.pow(10, TOKEN_DECIMALS)
+    );
+
+    // Neither output account exists yet; the swap creates the owner's
+    const ownerTokenBAccount = getAssociatedTokenAddressSync(
+      pool.tokenBMint,
+      owner.publicKey
+    );
+    const delegateTokenBAccount = getAssociatedTokenAddressSync(
+      pool.tokenBMint,
+      delegate.publicKey
+    );
+    const swapAsDelegate = (amount: anchor.BN, outputAccount: PublicKey) =>
+      program.methods
+        .swapTokens(amount, new anchor.BN(0), null, false, null, null)
+        .accounts({
+          liquidityPool: pool.liquidityPool,
+          poolAuthority: pool.poolAuthority,
+          inputTokenVault: pool.tokenAVault,
+          outputTokenVault: pool.tokenBVault,
+          inputTokenMint: pool.tokenAMint,
+          outputTokenMint: pool.tokenBMint,
+          oracle: pool.oracle,
+          userInputTokenAccount: ownerTokenAAccount,
+          userOutputTokenAccount: outputAccount,
+          insuranceVault: null,
+          protocolFeeVault: null,
+          feeRecipient: null,
+          creatorFeeAccount: null,
+          traderState: null,
+          launchWhitelistEntry: null,
+          memoProgram: null,
+          volumeStats: null,
+          referrer: null,
+          referralVault: null,
+          user: delegate.publicKey,
+          inputTokenOwner: owner.publicKey,
+          inputTokenProgram: TOKEN_PROGRAM_ID,
+          outputTokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([delegate])
+        .rpc();
+
+    // More than the approved amount is rejected
+    try {
+      await swapAsDelegate(toTokenAmount(2), ownerTokenBAccount);
+      assert.fail("Swap above the delegated amount should fail");
+    } catch (err) {
+      assert.include(err.toString(), "InsufficientDelegation");
+    }
+
+    // The output belongs to the owner of the input funds, not the delegate
+    try {
+      await swapAsDelegate(toTokenAmount(1), delegateTokenBAccount);
+      assert.fail("Paying the output to the delegate should fail");
+    } catch (err) {
+      assert.notInclude(err.toString(), "Paying the output");
+    }
+
+    await swapAsDelegate(toTokenAmount(1), ownerTokenBAccount);
+    assert.equal(await getTokenBalance(ownerTokenAAccount), 1);
+    assert(
+      (await getTokenBalance(ownerTokenBAccount)) > 0,
+      "Owner should receive the output"
+    );
+
+    console.log("Delegate swap executed");
+  });
+
+  it("Removes liquidity after swap (with profit from fees)", async () => {
+    const beforeTokenA = await getTokenBalance(
+      liquidityProvider2.tokenAAccount
+    );
+    const beforeTokenB = await getTokenBalance(
+      liquidityProvider2.tokenBAccount
+    );
+    const beforeLpTokens = await getTokenBalance(
+      liquidityProvider2.lpTokenAccount
+    );
+
+    console.log("Before withdrawal - Token A:", beforeTokenA);
+    console.log("Before withdrawal - Token B:", beforeTokenB);
+    console.log("Before withdrawal - LP tokens:", beforeLpTokens);
+
+    const burnAmount = toTokenAmount(50);
+
+    await program.methods
+      .withdrawLiquidity(burnAmount, null, null)
+      .accounts({
+        liquidityPool: pool.liquidityPool,
+        poolAuthority: pool.poolAuthority,
+        tokenAMint: pool.tokenAMint,
+        tokenBMint: pool.tokenBMint,
+        tokenAVault: pool.tokenAVault,
+        tokenBVault: pool.tokenBVault,
+        lpTokenMint: pool.lpTokenMint,
+        userTokenAAccount: liquidityProvider2.tokenAAccount,
+        userTokenBAccount: liquidityProvider2.tokenBAccount,
+        userLpTokenAccount: liquidityProvider2.lpTokenAccount,
+        subAccount: null,
+        user: liquidityProvider2.signer.publicKey,
+        lpTokenOwner: liquidityProvider2.signer.publicKey,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        tokenAProgram: TOKEN_PROGRAM_ID,
+        tokenBProgram: TOKEN_PROGRAM_ID,
+        memoProgram: null,
+        liquidityPosition: null,
+        positionLpVault: null,
+      })
+      .signers([liquidityProvider2.signer])
+      .rpc();
+
+    const afterTokenA = await getTokenBalance(liquidityProvider2.tokenAAccount);
+    const afterTokenB = await getTokenBalance(liquidityProvider2.tokenBAccount);
+    const afterLpTokens = await getTokenBalance(
+      liquidityProvider2.lpTokenAccount
+    );
+
+    console.log("After withdrawal - Token A:", afterTokenA);
+    console.log("After withdrawal - Token B:", afterTokenB);
+    console.log("After withdrawal - LP tokens:", afterLpTokens);
+
+    assert(afterLpTokens < beforeLpTokens, "LP tokens should decrease");
+    assert(afterTokenA > beforeTokenA, "Token A balance should increase");
+    assert(afterTokenB > beforeTokenB, "Token B balance should increase");
+
+    // Check for profit from swap fees
+    const tokenAGain = afterTokenA - beforeTokenA;
+    const tokenBGain = afterTokenB - beforeTokenB;
+
+    console.log("Token A gained:", tokenAGain);
+    console.log("Token B gained:", tokenBGain);
+
+    // Should have earned more Token A (due to swap fee) and less Token B (impermanent loss)
+    assert(tokenAGain > 50, "Should earn profit in Token A from swap fees");
+    assert(tokenBGain < 50, "Should experience impermanent loss in Token B");
+
+    console.log(
+      "Liquidity removed with profit from fees (impermanent loss visible)"
+    );
+  });
+
+  it("Claims the swap fees accrued to a liquidity position", async () => {
+    const [liquidityPosition] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("liquidity_position"),
+        pool.liquidityPool.toBuffer(),
+        liquidityProvider1.signer.publicKey.toBuffer(),
+      ],
+      program.programId
+    );
+    const beforeTokenA = await getTokenBalance(
+      liquidityProvider1.tokenAAccount
+    );
+    const beforeLpTokens = await getTokenBalance(
+      positionLpVault(liquidityPosition)
+    );
+
+    const pending = await program.methods
+      .pendingFees()
+      .accounts({
+        liquidityPool: pool.liquidityPool,
+        liquidityPosition,
+        tokenAVault: pool.tokenAVault,
+        tokenBVault: pool.tokenBVault,
+      })
+      .view();
+    assert(pending.feesOwedLp.gtn(0), "Position should have pending fees");
+
+    await program.methods
+      .claimFees()
+      .accounts({
+        liquidityPool: pool.liquidityPool,
+        poolAuthority: pool.poolAuthority,
+        tokenAMint: pool.tokenAMint,
+        tokenBMint: pool.tokenBMint,
+        tokenAVault: pool.tokenAVault,
+        tokenBVault: pool.tokenBVault,
+        lpTokenMint: pool.lpTokenMint,
+        liquidityPosition,
+        owner: liquidityProvider1.signer.publicKey,
+        ownerTokenAAccount: liquidityProvider1.tokenAAccount,
+        ownerTokenBAccount: liquidityProvider1.tokenBAccount,
+        positionLpVault: positionLpVault(liquidityPosition),
+        tokenProgram: TOKEN_PROGRAM_ID,
+        tokenAProgram: TOKEN_PROGRAM_ID,
+        tokenBProgram: TOKEN_PROGRAM_ID,
+        memoProgram: null,
+      })
+      .signers([liquidityProvider1.signer])
+      .rpc();
+
+    const position = await program.account.liquidityPosition.fetch(
+      liquidityPosition
+    );
+    assert.equal(position.feesOwedLp.toNumber(), 0);
+    assert(position.tokenAFeesClaimed.gtn(0), "Claim should be recorded");
+    assert(
+      position.tokenAFeesClaimed.eq(pending.tokenAAmount) &&
+        position.tokenBFeesClaimed.eq(pending.tokenBAmount),
+      "Claim should pay out the pending fees"
+    );
+    assert(
+      (await getTokenBalance(liquidityProvider1.tokenAAccount)) > beforeTokenA,
+      "Claim should pay out token A"
+    );
+    assert(
+      (await getTokenBalance(positionLpVault(liquidityPosition))) <
+        beforeLpTokens,
+      "Claim should burn the accrued fee LP"
+    );
+
+    console.log("Position fees claimed");
+  });
+
+  it("Penalizes withdrawals right after a deposit under JIT protection", async () => {
+    const [liquidityPosition] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("liquidity_position"),
+        pool.liquidityPool.toBuffer(),
+        liquidityProvider1.signer.publicKey.toBuffer(),
+      ],
+      program.programId
+    );
+    const liquidityAccounts = (position: PublicKey | null) => ({
+      liquidityPool: pool.liquidityPool,
+      poolAuthority: pool.poolAuthority,
+      tokenAMint: pool.tokenAMint,
+      tokenBMint: pool.tokenBMint,
+      tokenAVault: pool.tokenAVault,
+      tokenBVault: pool.tokenBVault,
+      lpTokenMint: pool.lpTokenMint,
+      userTokenAAccount: liquidityProvider1.tokenAAccount,
+      userTokenBAccount: liquidityProvider1.tokenBAccount,
+      userLpTokenAccount: liquidityProvider1.lpTokenAccount,
+      subAccount: null,
+      user: liquidityProvider1.signer.publicKey,
+      lpTokenOwner: liquidityProvider1.signer.publicKey,
+      tokenProgram: TOKEN_PROGRAM_ID,
+      tokenAProgram: TOKEN_PROGRAM_ID,
+      tokenBProgram: TOKEN_PROGRAM_ID,
+      associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+      systemProgram: SystemProgram.programId,
+      memoProgram: null,
+      lpWhitelistEntry: null,
+      liquidityPosition: position,
+      positionLpVault: position && positionLpVault(position),
+    });
+    const setJitProtection = (windowSlots: number, penaltyBps: number) =>
+      program.methods
+        .setJitProtection(new anchor.BN(windowSlots), penaltyBps)
+        .accounts({
+          liquidityPool: pool.liquidityPool,
+          admin: provider.wallet.publicKey,
+        })
+        .rpc();
+
+    // The penalty is capped so a position can always get out
+    try {
+      await setJitProtection(1000, 10_000);
+      assert.fail("A penalty above the cap should be rejected");
+    } catch (err) {
+      assert.include(err.toString(), "InvalidPoolConfig");
+    }
+
+    // Withdrawals within 1000 slots of a deposit leave 10% in the pool
+    await setJitProtection(1000, 1_000);
+
+    try {
+      await program.methods
+        .depositLiquidity(toTokenAmount(1), toTokenAmount(10), null)
+        .accounts(liquidityAccounts(null))
+        .signers([liquidityProvider1.signer])
+        .rpc();
+      assert.fail("Deposit without a position should fail");
+    } catch (err) {
+      assert.include(err.toString(), "LiquidityPositionRequired");
+    }
+
+    await program.methods
+      .depositLiquidity(toTokenAmount(1), toTokenAmount(10), null)
+      .accounts(liquidityAccounts(liquidityPosition))
+      .signers([liquidityProvider1.signer])
+      .rpc();
+
+    const quote = await program.account.liquidityPool.fetch(pool.liquidityPool);
+    const fullPayoutA = toTokenAmount(1)
+      .mul(quote.reserveA)
+      .div(quote.totalLpTokensIssued);
+    const beforeTokenA = await getAccount(
+      connection,
+      liquidityProvider1.tokenAAccount
+    );
+    await program.methods
+      .withdrawLiquidity(toTokenAmount(1), null, null)
+      .accounts(liquidityAccounts(liquidityPosition))
+      .signers([liquidityProvider1.signer])
+      .rpc();
+    const afterTokenA = await getAccount(
+      connection,
+      liquidityProvider1.tokenAAccount
+    );
+    assert.equal(
+      (afterTokenA.amount - beforeTokenA.amount).toString(),
+      fullPayoutA.muln(9).divn(10).toString()
+    );
+
+    await setJitProtection(0, 0);
+
+    console.log("JIT withdrawal penalized");
+  });
+
+  it("Holds withdrawals for the cooldown after a deposit", async () => {
+    const [liquidityPosition] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("liquidity_position"),
+        pool.liquidityPool.toBuffer(),
+        liquidityProvider1.signer.publicKey.toBuffer(),
+      ],
+      program.programId
+    );
+    const liquidityAccounts = {
+      liquidityPool: pool.liquidityPool,
+      poolAuthority: pool.poolAuthority,
+      tokenAMint: pool.tokenAMint,
+      tokenBMint: pool.tokenBMint,
+      tokenAVault: pool.tokenAVault,
+      tokenBVault: pool.tokenBVault,
+      lpTokenMint: pool.lpTokenMint,
+      userTokenAAccount: liquidityProvider1.tokenAAccount,
+      userTokenBAccount: liquidityProvider1.tokenBAccount,
+      userLpTokenAccount: liquidityProvider1.lpTokenAccount,
+      subAccount: null,
+      user: liquidityProvider1.signer.publicKey,
+      lpTokenOwner: liquidityProvider1.signer.publicKey,
+      tokenProgram: TOKEN_PROGRAM_ID,
+      tokenAProgram: TOKEN_PROGRAM_ID,
+      tokenBProgram: TOKEN_PROGRAM_ID,
+      associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+      systemProgram: SystemProgram.programId,
+      memoProgram: null,
+      lpWhitelistEntry: null,
+      liquidityPosition,
+      positionLpVault: positionLpVault(liquidityPosition),
+    };
+    const setWithdrawCooldown = (seconds: number) =>
+      program.methods
+        .setWithdrawCooldown(new anchor.BN(seconds))
+        .accounts({
+          liquidityPool: pool.liquidityPool,
+          admin: provider.wallet.publicKey,
+        })
+        .rpc();
+
+    // Lock withdrawals for an hour after each deposit
+    await setWithdrawCooldown(3600);
+
+    await program.methods
+      .depositLiquidity(toTokenAmount(1), toTokenAmount(10), null)
+      .accounts(liquidityAccounts)
+      .signers([liquidityProvider1.signer])
+      .rpc();
+
+    const position = await program.account.liquidityPosition.fetch(
+      liquidityPosition
+    );
+    assert.isTrue(position.lastDepositTimestamp.gtn(0));
+
+    try {
+      await program.methods
+        .withdrawLiquidity(toTokenAmount(1), null, null)
+        .accounts(liquidityAccounts)
+        .signers([liquidityProvider1.signer])
+        .rpc();
+      assert.fail("Withdrawal within the cooldown should fail");
+    } catch (err) {
+      assert.include(err.toString(), "WithdrawCooldownActive");
+    }
+
+    // The fresh LP sits in the position vault, out of the owner's reach; LP
+    // moved from the wallet to another position is not tracked by it
+    const hopper = Keypair.generate();
+    const airdropSig = await connection.requestAirdrop(
+      hopper.publicKey,
+      LAMPORTS_PER_SOL
+    );
+    await connection.confirmTransaction(airdropSig);
+    const [hopperTokenA, hopperTokenB, hopperLp] = await setupLiquidityProvider(
+      hopper.publicKey,
+      1
+    );
+    await transfer(
+      connection,
+      pool.payer,
+      liquidityProvider1.lpTokenAccount,
+      hopperLp,
+      liquidityProvider1.signer,
+      BigInt(toTokenAmount(1).toString())
+    );
+    const [hopperPosition] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("liquidity_position"),
+        pool.liquidityPool.toBuffer(),
+        hopper.publicKey.toBuffer(),
+      ],
+      program.programId
+    );
+    await program.methods
+      .openPosition()
+      .accounts({
+        liquidityPool: pool.liquidityPool,
+        liquidityPosition: hopperPosition,
+        lpTokenMint: pool.lpTokenMint,
+        positionLpVault: positionLpVault(hopperPosition),
+        owner: hopper.publicKey,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([hopper])
+      .rpc();
+
+    try {
+      await program.methods
+        .withdrawLiquidity(toTokenAmount(1), null, null)
+        .accounts({
+          ...liquidityAccounts,
+          userTokenAAccount: hopperTokenA,
+          userTokenBAccount: hopperTokenB,
+          userLpTokenAccount: hopperLp,
+          user: hopper.publicKey,
+          lpTokenOwner: hopper.publicKey,
+          liquidityPosition: hopperPosition,
+          positionLpVault: positionLpVault(hopperPosition),
+        })
+        .signers([hopper])
+        .rpc();
+      assert.fail("Withdrawing transferred LP should fail");
+    } catch (err) {
+      assert.include(err.toString(), "BurnExceedsPosition");
+    }
+
+    await setWithdrawCooldown(0);
+
+    console.log("Withdrawal held for the cooldown");
+  });
+
+  it("Holds large withdrawals in withdraw-only mode but lets small positions out", async () => {
+    const [liquidityPosition] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("liquidity_position"),
+        pool.liquidityPool.toBuffer(),
+        liquidityProvider1.signer.publicKey.toBuffer(),
+      ],
+      program.programId
+    );
+    const withdrawAccounts = (
+      lp: LiquidityProvider,
+      position: PublicKey | null
+    ) => ({
+      liquidityPool: pool.liquidityPool,
+      poolAuthority: pool.poolAuthority,
+      tokenAMint: pool.tokenAMint,
+      tokenBMint: pool.tokenBMint,
+      tokenAVault: pool.tokenAVault,
+      tokenBVault: pool.tokenBVault,
+      lpTokenMint: pool.lpTokenMint,
+      userTokenAAccount: lp.tokenAAccount,
+      userTokenBAccount: lp.tokenBAccount,
+      userLpTokenAccount: lp.lpTokenAccount,
+      subAccount: null,
+      user: lp.signer.publicKey,
+      lpTokenOwner: lp.signer.publicKey,
+      tokenProgram: TOKEN_PROGRAM_ID,
+      tokenAProgram: TOKEN_PROGRAM_ID,
+      tokenBProgram: TOKEN_PROGRAM_ID,
+      memoProgram: null,
+      liquidityPosition: position,
+      positionLpVault: position && positionLpVault(position),
+    });
+    const setWithdrawOnly = (
+      withdrawOnly: boolean,
+      delaySecs: number,
+      threshold: anchor.BN
+    ) =>
+      program.methods
+        .setWithdrawOnly(withdrawOnly, new anchor.BN(delaySecs), threshold)
+        .accounts({
+          liquidityPool: pool.liquidityPool,
+          admin: provider.wallet.publicKey,
+        })
+        .rpc();
+
+    // Only positions tracking less than the threshold skip the hour-long hold
+    const position = await program.account.liquidityPosition.fetch(
+      liquidityPosition
+    );
+    const positionLp = position.lpTokens.add(position.feesOwedLp);
+    await setWithdrawOnly(true, 3600, positionLp.addn(1));
+
+    // Without a position, even a tiny burn is held back: its size can't be told
+    try {
+      await program.methods
+        .withdrawLiquidity(new anchor.BN(1), null, null)
+        .accounts(withdrawAccounts(liquidityProvider2, null))
+        .signers([liquidityProvider2.signer])
+        .rpc();
+      assert.fail("Withdrawal outside a small position should be held");
+    } catch (err) {
+      assert.include(err.toString(), "WithdrawalTimelocked");
+    }
+
+    const beforeTokenA = await getTokenBalance(
+      liquidityProvider1.tokenAAccount
+    );
+    await program.methods
+      .withdrawLiquidity(positionLp.divn(2), null, null)
+      .accounts(withdrawAccounts(liquidityProvider1, liquidityPosition))
+      .signers([liquidityProvider1.signer])
+      .rpc();
+    assert(
+      (await getTokenBalance(liquidityProvider1.tokenAAccount)) > beforeTokenA,
+      "The small position should exit right away"
+    );
+
+    await setWithdrawOnly(false, 0, new anchor.BN(0));
+
+    console.log("Small position withdrew during withdraw-only mode");
+  });
+
+  it("Exports the pool state digest once per epoch", async () => {
+    const digestAccounts = {
+      liquidityPool: pool.liquidityPool,
+      tokenAVault: pool.tokenAVault,
+      tokenBVault: pool.tokenBVault,
+      lpTokenMint: pool.lpTokenMint,
+      oracle: pool.oracle,
+    };
+
+    await program.methods.exportStateDigest().accounts(digestAccounts).rpc();
+
+    try {
+      await program.methods.exportStateDigest().accounts(digestAccounts).rpc();
+      assert.fail("Second digest in the same epoch should be rejected");
+    } catch (err) {
+      assert.include(err.toString(), "DigestAlreadyExported");
+    }
+  });
+
+  it("Creates a sub-account and manages its delegate", async () => {
+    const owner = provider.wallet.publicKey;
+    const delegate = Keypair.generate().publicKey;
+    const subAccountId = 0;
+
+    const [subAccount] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("sub_account"),
+        owner.toBuffer(),
+        new anchor.BN(subAccountId).toArrayLike(Buffer, "le", 2),
+      ],
+      program.programId
+    );
+
+    await program.methods
+      .createSubAccount(subAccountId)
+      .accounts({
+        subAccount: subAccount,
+        owner: owner,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+
+    await program.methods
+      .setSubAccountDelegate(delegate)
+      .accounts({ subAccount: subAccount, owner: owner })
+      .rpc();
+
+    let state = await program.account.subAccount.fetch(subAccount);
+    assert(state.delegate.equals(delegate), "Delegate should be set");
+
+    await program.methods
+      .setSubAccountDelegate(null)
+      .accounts({ subAccount: subAccount, owner: owner })
+      .rpc();
+
+    state = await program.account.subAccount.fetch(subAccount);
+    assert(
+      state.delegate.equals(PublicKey.default),
+      "Delegate should be revoked"
+    );
+  });
+
+  it("Quotes a rebalance hint as return data", async () => {
+    const quoteAccounts = {
+      liquidityPool: pool.liquidityPool,
+      tokenAVault: pool.tokenAVault,
+      tokenBVault: pool.tokenBVault,
+    };
+
+    const current = await program.methods
+      .quoteRebalance(new anchor.BN(1))
+      .accounts(quoteAccounts)
+      .view();
+
+    // Doubling the price means selling token B into the pool
+    const hint = await program.methods
+      .quoteRebalance(current.currentPrice.muln(2))
+      .accounts(quoteAccounts)
+      .view();
+
+    assert.isFalse(hint.aToB, "Raising the price should sell token B");
+    assert(hint.suggestedInput.gtn(0), "Should suggest a non-zero trade");
+    assert(hint.expectedOutput.gtn(0), "Should quote a non-zero output");
+  });
+
+  it("Converts raw amounts to UI amounts as return data", async () => {
+    const amounts = await program.methods
+      .quoteUiAmounts(new anchor.BN(1_500_000_000), new anchor.BN(1))
+      .accounts({
+        liquidityPool: pool.liquidityPool,
+        tokenAMint: pool.tokenAMint,
+        tokenBMint: pool.tokenBMint,
+        tokenAVault: pool.tokenAVault,
+        tokenBVault: pool.tokenBVault,
+      })
+      .view();
+
+    // Mints without an interest rate scale by their decimals alone
+    assert.equal(amounts.tokenAUiAmount, "1.5");
+    assert.equal(
+      amounts.tokenBUiAmount,
+      `0.${"1".padStart(TOKEN_DECIMALS, "0")}`
+    );
+    assert(
+      Number(amounts.reserveAUiAmount) > 0,
+      "Reserve A should be non-zero"
+    );
+    assert(
+      Number(amounts.reserveBUiAmount) > 0,
+      "Reserve B should be non-zero"
+    );
+  });
+
+  it("Reads the oracle TWAP as return data", async () => {
+    const twapAccounts = {
+      liquidityPool: pool.liquidityPool,
+      oracle: pool.oracle,
+      tokenAVault: pool.tokenAVault,
+      tokenBVault: pool.tokenBVault,
+    };
+
+    const reading = await program.methods
+      .readTwap(new anchor.BN(3600))
+      .accounts(twapAccounts)
+      .view();
+
+    assert(reading.twapPrice.gtn(0), "TWAP should be non-zero");
+    assert(reading.spotPrice.gtn(0), "Spot price should be non-zero");
+
+    try {
+      await program.methods
+        .readTwap(new anchor.BN(0))
+        .accounts(twapAccounts)
+        .view();
+      assert.fail("An empty window should be rejected");
+    } catch (err) {
+      assert.include(err.toString(), "InvalidTwapWindow");
+    }
+  });
+
+  it("Checkpoints the oracle from current reserves", async () => {
+    const latestTimestamp = async () => {
+      const oracle = await program.account.priceOracle.fetch(pool.oracle);
+      return oracle.observations[oracle.observationIndex].timestamp.toNumber();
+    };
+    const before = await latestTimestamp();
+
+    // Observations are per second, so let the clock move past the latest one
+    await new Promise((resolve) => setTimeout(resolve, 1500));
+
+    await program.methods
+      .checkpointOracle()
+      .accounts({
+        liquidityPool: pool.liquidityPool,
+        oracle: pool.oracle,
+        poolAuthority: pool.poolAuthority,
+        tokenAVault: pool.tokenAVault,
+        tokenBVault: pool.tokenBVault,
+        tokenAMint: null,
+        protocolFeeVaultA: null,
+        callerTokenAAccount: null,
+        tokenProgram: null,
+      })
+      .rpc();
+
+    assert((await latestTimestamp()) > before, "Should record an observation");
+  });
+
+  it("Configures the keeper tip paid from protocol fees", async () => {
+    const setKeeperTips = (tip: number, shareBps: number) =>
+      program.methods
+        .setKeeperTips(new anchor.BN(tip), shareBps)
+        .accounts({
+          liquidityPool: pool.liquidityPool,
+          admin: provider.wallet.publicKey,
+        })
+        .rpc();
+
+    try {
+      await setKeeperTips(1000, 10001);
+      assert.fail("A share above 100% should be rejected");
+    } catch (err) {
+      assert.include(err.toString(), "InvalidPoolConfig");
+    }
+
+    await setKeeperTips(1000, 2000);
+    let poolAccount = await program.account.liquidityPool.fetch(
+      pool.liquidityPool
+    );
+    assert.equal(poolAccount.keeperTip.toNumber(), 1000);
+    assert.equal(poolAccount.keeperTipShareBps, 2000);
+
+    await setKeeperTips(0, 0);
+    poolAccount = await program.account.liquidityPool.fetch(pool.liquidityPool);
+    assert.equal(poolAccount.keeperTipShareBps, 0);
+  });
+
+  it("Switches the pool to volatility-based dynamic fees", async () => {
+    const setDynamicFee = (minBps: number, maxBps: number, volBps: number) =>
+      program.methods
+        .setDynamicFee(minBps, maxBps, volBps)
+        .accounts({
+          liquidityPool: pool.liquidityPool,
+          admin: provider.wallet.publicKey,
+        })
+        .rpc();
+
+    try {
+      await setDynamicFee(100, 50, 500);
+      assert.fail("A minimum above the maximum should be rejected");
+    } catch (err) {
+      assert.include(err.toString(), "InvalidFeeParameters");
+    }
+
+    try {
+      await setDynamicFee(10, 1001, 500);
+      assert.fail("A maximum above the fee cap should be rejected");
+    } catch (err) {
+      assert.include(err.toString(), "FeeTooHigh");
+    }
+
+    await setDynamicFee(10, 100, 500);
+    const poolState = await program.account.liquidityPool.fetch(
+      pool.liquidityPool
+    );
+    assert.equal(poolState.dynamicFeeMaxBps, 100);
+    assert.equal(poolState.dynamicFeeBps, 10);
+
+    await setDynamicFee(0, 0, 0);
+  });
+
+  it("Updates the fee and guards the governance admin handover", async () => {
+    const poolConfig = {
+      liquidityPool: pool.liquidityPool,
+      admin: provider.wallet.publicKey,
+    };
+
+    try {
+      await program.methods
+        .updateFee(new anchor.BN(1000), new anchor.BN(1000))
+        .accounts(poolConfig)
+        .rpc();
+      assert.fail("A 100% fee should be rejected");
+    } catch (err) {
+      assert.include(err.toString(), "InvalidFeeParameters");
+    }
+
+    try {
+      await program.methods
+        .updateFee(new anchor.BN(2000), new anchor.BN(10000))
+        .accounts(poolConfig)
+        .rpc();
+      assert.fail("A fee above the 10% cap should be rejected");
+    } catch (err) {
+      assert.include(err.toString(), "FeeTooHigh");
+    }
+
+    await program.methods
+      .updateFee(new anchor.BN(1), new anchor.BN(10000))
+      .accounts(poolConfig)
+      .rpc();
+
+    // Wallets are not governance accounts of a realm
+    try {
+      await program.methods
+        .setGovernanceAdmin()
+        .accounts({
+          ...poolConfig,
+          realm: Keypair.generate().publicKey,
+          governance: Keypair.generate().publicKey,
+        })
+        .rpc();
+      assert.fail("Non-governance accounts should be rejected");
+    } catch (err) {
+      assert.include(err.toString(), "InvalidGovernanceAccount");
+    }
+  });
+
+  it("Applies a queued pool fee change through the timelock", async () => {
+    const changeId = new anchor.BN(1);
+    const [pendingChange] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("param_change"),
+        provider.wallet.publicKey.toBuffer(),
+        changeId.toArrayLike(Buffer, "le", 8),
+      ],
+      program.programId
+    );
+
+    await program.methods
+      .queueParamChange(changeId, {
+        poolFee: {
+          feeNumerator: new anchor.BN(2),
+          feeDenominator: new anchor.BN(10000),
+        },
+      })
+      .accounts({
+        liquidityPool: pool.liquidityPool,
+        proposer: provider.wallet.publicKey,
+      })
+      .rpc();
+
+    const queued = await program.account.pendingParamChange.fetch(
+      pendingChange
+    );
+    assert.equal(queued.target.toBase58(), pool.liquidityPool.toBase58());
+
+    // The config's delay is zero, so the change is executable right away
+    await program.methods
+      .executeParamChange()
+      .accounts({
+        liquidityPool: pool.liquidityPool,
+        pendingChange,
+        proposer: provider.wallet.publicKey,
+      })
+      .rpc();
+
+    const poolState = await program.account.liquidityPool.fetch(
+      pool.liquidityPool
+    );
+    assert.equal(poolState.feeNumerator.toNumber(), 2);
+    assert.isNull(await connection.getAccountInfo(pendingChange));
+
+    await program.methods
+      .updateFee(new anchor.BN(1), new anchor.BN(10000))
+      .accounts({
+        liquidityPool: pool.liquidityPool,
+        admin: provider.wallet.publicKey,
+      })
+      .rpc();
+  });
+
+  it("Routes pool fee setters through the timelock while it is on", async () => {
+    const pendingChangeFor = (changeId: anchor.BN) =>
+      PublicKey.findProgramAddressSync(
+        [
+          Buffer.from("param_change"),
+          provider.wallet.publicKey.toBuffer(),
+          changeId.toArrayLike(Buffer, "le", 8),
+        ],
+        program.programId
+      )[0];
+    const queueAndExecute = async (
+      changeId: anchor.BN,
+      change: any,
+      liquidityPool: PublicKey | null,
+      waitMs: number
+    ) => {
+      await program.methods
+        .queueParamChange(changeId, change)
+        .accounts({ liquidityPool, proposer: provider.wallet.publicKey })
+        .rpc();
+      await new Promise((resolve) => setTimeout(resolve, waitMs));
+      await program.methods
+        .executeParamChange()
+        .accounts({
+          liquidityPool,
+          pendingChange: pendingChangeFor(changeId),
+          proposer: provider.wallet.publicKey,
+        })
+        .rpc();
+    };
+
+    await queueAndExecute(
+      new anchor.BN(2),
+      { paramChangeDelay: { delaySecs: new anchor.BN(1) } },
+      null,
+      0
+    );
+
+    try {
+      await program.methods
+        .setFeeOnOutput(true)
+        .accounts({
+          liquidityPool: pool.liquidityPool,
+          admin: provider.wallet.publicKey,
+        })
+        .rpc();
+      assert.fail("Setting the fee token directly should need the timelock");
+    } catch (err) {
+      assert.include(err.toString(), "TimelockRequired");
+    }
+
+    // An emergency pause skips the timelock, but resuming does not
+    await program.methods
+      .setPaused(true)
+      .accounts({ authority: provider.wallet.publicKey })
+      .rpc();
+    try {
+      await program.methods
+        .setPaused(false)
+        .accounts({ authority: provider.wallet.publicKey })
+        .rpc();
+      assert.fail("Resuming directly should need the timelock");
+    } catch (err) {
+      assert.include(err.toString(), "TimelockRequired");
+    }
+    await queueAndExecute(
+      new anchor.BN(6),
+      { paused: { paused: false } },
+      null,
+      3000
+    );
+    const [ammConfig] = PublicKey.findProgramAddressSync(
+      [Buffer.from("amm_config")],
+      program.programId
+    );
+    const config = await program.account.ammConfig.fetch(ammConfig);
+    assert.isFalse(config.paused);
+
+    await queueAndExecute(
+      new anchor.BN(3),
+      { feeOnOutput: { feeOnOutput: true } },
+      pool.liquidityPool,
+      3000
+    );
+    let poolState = await program.account.liquidityPool.fetch(
+      pool.liquidityPool
+    );
+    assert.equal(poolState.feeOnOutput, 1);
+
+    await queueAndExecute(
+      new anchor.BN(4),
+      { feeOnOutput: { feeOnOutput: false } },
+      pool.liquidityPool,
+      3000
+    );
+    await queueAndExecute(
+      new anchor.BN(5),
+      { paramChangeDelay: { delaySecs: new anchor.BN(0) } },
+      null,
+      3000
+    );
+    poolState = await program.account.liquidityPool.fetch(pool.liquidityPool);
+    assert.equal(poolState.feeOnOutput, 0);
+  });
+
+  it("Stakes LP tokens in the pool farm and claims rewards", async () => {
+    const staker = liquidityProvider3;
+    const airdropSig = await connection.requestAirdrop(
+      staker.signer.publicKey,
+      LAMPORTS_PER_SOL
+    );
+    await connection.confirmTransaction(airdropSig);
+
+    const [farm] = PublicKey.findProgramAddressSync(
+      [Buffer.from("farm"), pool.liquidityPool.toBuffer()],
+      program.programId
+    );
+    const [farmLpVault] = PublicKey.findProgramAddressSync(
+      [Buffer.from("farm_lp_vault"), farm.toBuffer()],
+      program.programId
+    );
+    const [farmRewardVault] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("farm_reward_vault"),
+        farm.toBuffer(),
+        pool.tokenBMint.toBuffer(),
+      ],
+      program.programId
+    );
+    const [stakePosition] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("stake_position"),
+        farm.toBuffer(),
+        staker.signer.publicKey.toBuffer(),
+      ],
+      program.programId
+    );
+
+    // Token B doubles as the reward token
+    await program.methods
+      .initializeFarm(toTokenAmount(0.001))
+      .accounts({
+        liquidityPool: pool.liquidityPool,
+        farm: farm,
+        lpTokenMint: pool.lpTokenMint,
+        rewardMint: pool.tokenBMint,
+        farmLpVault: farmLpVault,
+        farmRewardVault: farmRewardVault,
+        admin: pool.authority.publicKey,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([pool.authority])
+      .rpc();
+
+    await mintTo(
+      connection,
+      pool.payer,
+      pool.tokenBMint,
+      farmRewardVault,
+      pool.authority,
+      toTokenAmount(10).toNumber()
+    );
+
+    const stakeAccounts = {
+      farm: farm,
+      stakePosition: stakePosition,
+      lpTokenMint: pool.lpTokenMint,
+      farmLpVault: farmLpVault,
+      userLpTokenAccount: staker.lpTokenAccount,
+      owner: staker.signer.publicKey,
+      tokenProgram: TOKEN_PROGRAM_ID,
+      systemProgram: SystemProgram.programId,
+    };
+
+    await program.methods
+      .stakeLp(toTokenAmount(1), 0)
+      .accounts(stakeAccounts)
+      .signers([staker.signer])
+      .rpc();
+    assert((await getTokenBalance(farmLpVault)) === 1, "LP should be staked");
+
+    // Let some emissions accrue
+    await new Promise((resolve) => setTimeout(resolve, 2000));
+
+    await program.methods
+      .unstakeLp(toTokenAmount(1))
+      .accounts(stakeAccounts)
+      .signers([staker.signer])
+      .rpc();
+    assert((await getTokenBalance(farmLpVault)) === 0, "LP should be unstaked");
+
+    const userRewardAccount = staker.tokenBAccount;
+    const balanceBefore = await getTokenBalance(userRewardAccount);
+    await program.methods
+      .claimRewards()
+      .accounts({
+        farm: farm,
+        stakePosition: stakePosition,
+        rewardMint: pool.tokenBMint,
+        farmRewardVault: farmRewardVault,
+        owner: staker.signer.publicKey,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+        userRewardTokenAccount: userRewardAccount,
+      })
+      .signers([staker.signer])
+      .rpc();
+
+    const balanceAfter = await getTokenBalance(userRewardAccount);
+    console.log("Farm rewards claimed:", balanceAfter - balanceBefore);
+    assert(balanceAfter > balanceBefore, "Staker should earn rewards");
+  });
+
+  it("Locks LP tokens in escrow until the unlock time", async () => {
+    const locker = liquidityProvider3;
+    const [lpLock] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("lp_lock"),
+        pool.liquidityPool.toBuffer(),
+        locker.signer.publicKey.toBuffer(),
+      ],
+      program.programId
+    );
+    const [lpLockVault] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("lp_lock_vault"),
+        pool.liquidityPool.toBuffer(),
+        locker.signer.publicKey.toBuffer(),
+      ],
+      program.programId
+    );
+    const unlockTimestamp = Math.floor(Date.now() / 1000) + 3600;
+
+    await program.methods
+      .lockLpTokens(toTokenAmount(1), new anchor.BN(unlockTimestamp))
+      .accounts({
+        liquidityPool: pool.liquidityPool,
+        lpLock,
+        lpLockVault,
+        lpTokenMint: pool.lpTokenMint,
+        ownerLpTokenAccount: locker.lpTokenAccount,
+        owner: locker.signer.publicKey,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([locker.signer])
+      .rpc();
+
+    const lock = await program.account.lpLock.fetch(lpLock);
+    assert.equal(lock.amount.toString(), toTokenAmount(1).toString());
+    assert.equal(lock.unlockTimestamp.toNumber(), unlockTimestamp);
+    assert((await getTokenBalance(lpLockVault)) === 1, "LP should be locked");
+
+    try {
+      await program.methods
+        .unlockLpTokens()
+        .accounts({
+          lpLock,
+          lpLockVault,
+          lpTokenMint: pool.lpTokenMint,
+          ownerLpTokenAccount: locker.lpTokenAccount,
+          owner: locker.signer.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([locker.signer])
+        .rpc();
+      assert.fail("Unlock before the unlock time should fail");
+    } catch (err) {
+      assert.include(err.toString(), "LpTokensLocked");
+    }
+
+    console.log("LP tokens locked until", unlockTimestamp);
+  });
+
+  it("Sells a launch on its bonding curve and graduates it into a pool", async () => {
+    const creator = pool.payer;
+    const buyer = Keypair.generate();
+    await connection.confirmTransaction(
+      await connection.requestAirdrop(buyer.publicKey, 10 * LAMPORTS_PER_SOL)
+    );
+    const tokenMint = await createMint(
+      connection,
+      creator,
+      creator.publicKey,
+      null,
+      TOKEN_DECIMALS
+    );
+    const quoteMint = await createMint(
+      connection,
+      creator,
+      creator.publicKey,
+      null,
+      TOKEN_DECIMALS
+    );
+    const creatorTokens = await createAssociatedTokenAccount(
+      connection,
+      creator,
+      tokenMint,
+      creator.publicKey
+    );
+    await mintTo(
+      connection,
+      creator,
+      tokenMint,
+      creatorTokens,
+      creator,
+      1_000_000 * Math.pow(10, TOKEN_DECIMALS)
+    );
+    const buyerQuote = await createAssociatedTokenAccount(
+      connection,
+      creator,
+      quoteMint,
+      buyer.publicKey
+    );
+    await mintTo(
+      connection,
+      creator,
+      quoteMint,
+      buyerQuote,
+      creator,
+      1_000 * Math.pow(10, TOKEN_DECIMALS)
+    );
+    const buyerTokens = await createAssociatedTokenAccount(
+      connection,
+      creator,
+      tokenMint,
+      buyer.publicKey
+    );
+
+    // Opens at 1 quote per 1_000 tokens and graduates after raising 500 quote
+    const [launchPool] = PublicKey.findProgramAddressSync(
+      [Buffer.from("launch_pool"), tokenMint.toBuffer()],
+      program.programId
+    );
+    await program.methods
+      .createLaunchPool(
+        toTokenAmount(1_000_000),
+        toTokenAmount(1_000),
+        toTokenAmount(500),
+        new anchor.BN(3),
+        new anchor.BN(1000)
+      )
+      .accounts({
+        tokenMint,
+        quoteMint,
+        creatorTokenAccount: creatorTokens,
+        creator: creator.publicKey,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([creator])
+      .rpc();
+
+    // Offering more than the rest of the target only takes what it needs
+    await program.methods
+      .buyLaunchPool(toTokenAmount(1_000), new anchor.BN(0))
+      .accounts({
+        launchPool,
+        tokenMint,
+        quoteMint,
+        buyerQuoteAccount: buyerQuote,
+        buyerTokenAccount: buyerTokens,
+        buyer: buyer.publicKey,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([buyer])
+      .rpc();
+    assert.equal(await getTokenBalance(buyerQuote), 500);
+    assert.isAbove(await getTokenBalance(buyerTokens), 333_333);
+
+    const [liquidityPool] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("liquidity_pool"),
+        tokenMint.toBuffer(),
+        quoteMint.toBuffer(),
+      ],
+      program.programId
+    );
+    const [treasury] = PublicKey.findProgramAddressSync(
+      [Buffer.from("treasury")],
+      program.programId
+    );
+    await program.methods
+      .graduateLaunchPool("")
+      .accounts({
+        initialize: {
+          tokenAMint: tokenMint,
+          tokenBMint: quoteMint,
+          tokenAMetadata: null,
+          tokenBMetadata: null,
+          tokenAMintApproval: null,
+          tokenBMintApproval: null,
+          treasury,
+          payer: provider.wallet.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          tokenAProgram: TOKEN_PROGRAM_ID,
+          tokenBProgram: TOKEN_PROGRAM_ID,
+        },
+        launchPool,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .rpc();
+
+    const launch = await program.account.launchPool.fetch(launchPool);
+    assert.equal(launch.liquidityPool.toBase58(), liquidityPool.toBase58());
+    const poolState = await program.account.liquidityPool.fetch(liquidityPool);
+    assert.equal(poolState.admin.toBase58(), creator.publicKey.toBase58());
+    assert.equal(poolState.reserveB.toString(), toTokenAmount(500).toString());
+
+    console.log("Launch graduated into", liquidityPool.toBase58());
+  });
+
+  it("Pairs a legacy SPL mint with a Token-2022 mint", async () => {
+    const user = provider.wallet.publicKey;
+    const legacyMint = await createMint(
+      connection,
+      pool.authority,
+      pool.authority.publicKey,
+      null,
+      TOKEN_DECIMALS
+    );
+    const token2022Mint = await createMint(
+      connection,
+      pool.authority,
+      pool.authority.publicKey,
+      null,
+      TOKEN_DECIMALS,
+      undefined,
+      undefined,
+      TOKEN_2022_PROGRAM_ID
+    );
+
+    const [liquidityPool] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("liquidity_pool"),
+        legacyMint.toBuffer(),
+        token2022Mint.toBuffer(),
+      ],
+      program.programId
+    );
+    const [poolAuthority] = PublicKey.findProgramAddressSync(
+      [Buffer.from("pool_authority"), liquidityPool.toBuffer()],
+      program.programId
+    );
+    const [tokenAVault] = PublicKey.findProgramAddressSync(
+      [Buffer.from("token_a_vault"), liquidityPool.toBuffer()],
+      program.programId
+    );
+    const [tokenBVault] = PublicKey.findProgramAddressSync(
+      [Buffer.from("token_b_vault"), liquidityPool.toBuffer()],
+      program.programId
+    );
+    const [lpTokenMint] = PublicKey.findProgramAddressSync(
+      [Buffer.from("lp_token_mint"), liquidityPool.toBuffer()],
+      program.programId
+    );
+    const [oracle] = PublicKey.findProgramAddressSync(
+      [Buffer.from("oracle"), liquidityPool.toBuffer()],
+      program.programId
+    );
+    const [treasury] = PublicKey.findProgramAddressSync(
+      [Buffer.from("treasury")],
+      program.programId
+    );
+
+    const initializePool = (tokenBProgram: PublicKey) =>
+      program.methods
+        .initializePool(
+          new anchor.BN(3),
+          new anchor.BN(1000),
+          "",
+          new anchor.BN(0)
+        )
+        .accounts({
+          tokenAMint: legacyMint,
+          tokenBMint: token2022Mint,
+          tokenAMetadata: null,
+          tokenBMetadata: null,
+          tokenAMintApproval: null,
+          tokenBMintApproval: null,
+          treasury,
+          payer: user,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          tokenAProgram: TOKEN_PROGRAM_ID,
+          tokenBProgram,
+        })
+        .rpc();
+
+    try {
+      await initializePool(TOKEN_PROGRAM_ID);
+      assert.fail("A program that does not own the mint should be rejected");
+    } catch (err) {
+      assert.include(err.toString(), "InvalidTokenProgram");
+    }
+    await initializePool(TOKEN_2022_PROGRAM_ID);
+    token2022Pool = {
+      authority: pool.authority,
+      payer: pool.payer,
+      tokenAMint: legacyMint,
+      tokenBMint: token2022Mint,
+      tokenAVault,
+      tokenBVault,
+      lpTokenMint,
+      liquidityPool,
+      poolAuthority,
+      oracle,
+    };
+
+    const userTokenA = await createAssociatedTokenAccount(
+      connection,
+      pool.payer,
+      legacyMint,
+      user
+    );
+    const userTokenB = await createAssociatedTokenAccount(
+      connection,
+      pool.payer,
+      token2022Mint,
+      user,
+      undefined,
+      TOKEN_2022_PROGRAM_ID
+    );
+    await mintTo(
+      connection,
+      pool.payer,
+      legacyMint,
+      userTokenA,
+      pool.authority,
+      20 * Math.pow(10, TOKEN_DECIMALS)
+    );
+    await mintTo(
+      connection,
+      pool.payer,
+      token2022Mint,
+      userTokenB,
+      pool.authority,
+      20 * Math.pow(10, TOKEN_DECIMALS),
+      [],
+      undefined,
+      TOKEN_2022_PROGRAM_ID
+    );
+
+    await program.methods
+      .depositLiquidity(toTokenAmount(10), toTokenAmount(10), null)
+      .accounts({
+        liquidityPool,
+        poolAuthority,
+        tokenAMint: legacyMint,
+        tokenBMint: token2022Mint,
+        tokenAVault,
+        tokenBVault,
+        lpTokenMint,
+        userTokenAAccount: userTokenA,
+        userTokenBAccount: userTokenB,
+        userLpTokenAccount: getAssociatedTokenAddressSync(lpTokenMint, user),
+        subAccount: null,
+        user,
+        lpTokenOwner: user,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        tokenAProgram: TOKEN_PROGRAM_ID,
+        tokenBProgram: TOKEN_2022_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+        lpWhitelistEntry: null,
+        liquidityPosition: null,
+        positionLpVault: null,
+      })
+      .rpc();
+
+    const tokenBBefore = (
+      await getAccount(connection, userTokenB, undefined, TOKEN_2022_PROGRAM_ID)
+    ).amount;
+    await program.methods
+      .swapTokens(toTokenAmount(1), new anchor.BN(0), null, false, null, null)
+      .accounts({
+        liquidityPool,
+        poolAuthority,
+        inputTokenVault: tokenAVault,
+        outputTokenVault: tokenBVault,
+        inputTokenMint: legacyMint,
+        outputTokenMint: token2022Mint,
+        oracle,
+        userInputTokenAccount: userTokenA,
+        userOutputTokenAccount: userTokenB,
+        insuranceVault: null,
+        protocolFeeVault: null,
+        feeRecipient: null,
+        creatorFeeAccount: null,
+        traderState: null,
+        launchWhitelistEntry: null,
+        memoProgram: null,
+        volumeStats: null,
+        referrer: null,
+        referralVault: null,
+        user,
+        inputTokenOwner: user,
+        inputTokenProgram: TOKEN_PROGRAM_ID,
+        outputTokenProgram: TOKEN_2022_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+
+    const tokenBAfter = (
+      await getAccount(connection, userTokenB, undefined, TOKEN_2022_PROGRAM_ID)
+    ).amount;
+    assert(
+      tokenBAfter > tokenBBefore,
+      "Swap should pay out the Token-2022 side"
+    );
+  });
+
+  it("Pays out to Token-2022 accounts that require transfer memos", async () => {
+    const trader = Keypair.generate();
+    const traderTokenA = await createAssociatedTokenAccount(
+      connection,
+      token2022Pool.payer,
+      token2022Pool.tokenAMint,
+      trader.publicKey
+    );
+    const traderTokenB = await createAssociatedTokenAccount(
+      connection,
+      token2022Pool.payer,
+      token2022Pool.tokenBMint,
+      trader.publicKey,
+      undefined,
+      TOKEN_2022_PROGRAM_ID
+    );
+    await mintTo(
+      connection,
+      token2022Pool.payer,
+      token2022Pool.tokenAMint,
+      traderTokenA,
+      token2022Pool.authority,
+      2 * Math.pow(10, TOKEN_DECIMALS)
+    );
+    await reallocate(
+      connection,
+      token2022Pool.payer,
+      traderTokenB,
+      trader,
+      [ExtensionType.MemoTransfer],
+      [],
+      undefined,
+      TOKEN_2022_PROGRAM_ID
+    );
+    await enableRequiredMemoTransfers(
+      connection,
+      token2022Pool.payer,
+      traderTokenB,
+      trader,
+      [],
+      undefined,
+      TOKEN_2022_PROGRAM_ID
+    );
+
+    const swap = (memoProgram: PublicKey | null) =>
+      program.methods
+        .swapTokens(toTokenAmount(1), new anchor.BN(0), null, false, null, null)
+        .accounts({
+          liquidityPool: token2022Pool.liquidityPool,
+          poolAuthority: token2022Pool.poolAuthority,
+          inputTokenVault: token2022Pool.tokenAVault,
+          outputTokenVault: token2022Pool.tokenBVault,
+          inputTokenMint: token2022Pool.tokenAMint,
+          outputTokenMint: token2022Pool.tokenBMint,
+          oracle: token2022Pool.oracle,
+          userInputTokenAccount: traderTokenA,
+          userOutputTokenAccount: traderTokenB,
+          insuranceVault: null,
+          protocolFeeVault: null,
+          feeRecipient: null,
+          creatorFeeAccount: null,
+          traderState: null,
+          launchWhitelistEntry: null,
+          memoProgram,
+          volumeStats: null,
+          referrer: null,
+          referralVault: null,
+          user: trader.publicKey,
+          inputTokenOwner: trader.publicKey,
+          inputTokenProgram: TOKEN_PROGRAM_ID,
+          outputTokenProgram: TOKEN_2022_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([trader])
+        .rpc();
+
+    try {
+      await swap(null);
+      assert.fail("A memo-required destination needs the memo program");
+    } catch (err) {
+      assert.include(err.toString(), "MemoProgramRequired");
+    }
+
+    await swap(MEMO_PROGRAM_ID);
+    const received = (
+      await getAccount(
+        connection,
+        traderTokenB,
+        undefined,
+        TOKEN_2022_PROGRAM_ID
+      )
+    ).amount;
+    assert(received > BigInt(0), "Swap output should arrive with a memo");
+  });
+
+  it("Rejects permanent-delegate mints without config approval", async () => {
+    // Token-2022 mint whose permanent delegate could move tokens out of any vault
+    const mintKeypair = Keypair.generate();
+    const mintLen = getMintLen([ExtensionType.PermanentDelegate]);
+    await provider.sendAndConfirm(
+      new Transaction().add(
+        SystemProgram.createAccount({
+          fromPubkey: provider.wallet.publicKey,
+          newAccountPubkey: mintKeypair.publicKey,
+          space: mintLen,
+          lamports: await connection.getMinimumBalanceForRentExemption(
+            mintLen
+          ),
+          programId: TOKEN_2022_PROGRAM_ID,
+        }),
+        createInitializePermanentDelegateInstruction(
+          mintKeypair.publicKey,
+          pool.authority.publicKey,
+          TOKEN_2022_PROGRAM_ID
+        ),
+        createInitializeMintInstruction(
+          mintKeypair.publicKey,
+          TOKEN_DECIMALS,
+          pool.authority.publicKey,
+          null,
+          TOKEN_2022_PROGRAM_ID
+        )
+      ),
+      [mintKeypair]
+    );
+    const delegateMint = mintKeypair.publicKey;
+    const quoteMint = await createMint(
+      connection,
+      pool.authority,
+      pool.authority.publicKey,
+      null,
+      TOKEN_DECIMALS
+    );
+
+    const [treasury] = PublicKey.findProgramAddressSync(
+      [Buffer.from("treasury")],
+      program.programId
+    );
+    const [approval] = PublicKey.findProgramAddressSync(
+      [Buffer.from("unsafe_mint_approval"), delegateMint.toBuffer()],
+      program.programId
+    );
+    const initializePool = (tokenAMintApproval: PublicKey | null) =>
+      program.methods
+        .initializePool(
+          new anchor.BN(3),
+          new anchor.BN(1000),
+          "",
+          new anchor.BN(0)
+        )
+        .accounts({
+          tokenAMint: delegateMint,
+          tokenBMint: quoteMint,
+          tokenAMetadata: null,
+          tokenBMetadata: null,
+          tokenAMintApproval,
+          tokenBMintApproval: null,
+          treasury,
+          payer: provider.wallet.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          tokenAProgram: TOKEN_2022_PROGRAM_ID,
+          tokenBProgram: TOKEN_PROGRAM_ID,
+        })
+        .rpc();
+
+    try {
+      await initializePool(null);
+      assert.fail("A permanent-delegate mint should be rejected");
+    } catch (err) {
+      assert.include(err.toString(), "UnsafeMintExtension");
+    }
+
+    await program.methods
+      .approveUnsafeMint(delegateMint)
+      .accounts({ authority: provider.wallet.publicKey })
+      .rpc();
+    await initializePool(approval);
+
+    await program.methods
+      .revokeUnsafeMint()
+      .accounts({
+        unsafeMintApproval: approval,
+        authority: provider.wallet.publicKey,
+      })
+      .rpc();
+    assert.isNull(await connection.getAccountInfo(approval));
+  });
+
+  it("Applies the config's freeze authority policy at pool creation", async () => {
+    // The main pool's mints were created with a freeze authority
+    const poolState = await program.account.liquidityPool.fetch(
+      pool.liquidityPool
+    );
+    assert.equal(poolState.tokenAFreezable, 1);
+    assert.equal(poolState.tokenBFreezable, 1);
+
+    const freezableMint = await createMint(
+      connection,
+      pool.authority,
+      pool.authority.publicKey,
+      pool.authority.publicKey,
+      TOKEN_DECIMALS
+    );
+    const otherMint = await createMint(
+      connection,
+      pool.authority,
+      pool.authority.publicKey,
+      null,
+      TOKEN_DECIMALS
+    );
+    const [treasury] = PublicKey.findProgramAddressSync(
+      [Buffer.from("treasury")],
+      program.programId
+    );
+    const setRejectFreezeAuthority = (reject: boolean) =>
+      program.methods
+        .setRejectFreezeAuthority(reject)
+        .accounts({ authority: provider.wallet.publicKey })
+        .rpc();
+
+    await setRejectFreezeAuthority(true);
+    try {
+      await program.methods
+        .initializePool(
+          new anchor.BN(3),
+          new anchor.BN(1000),
+          "",
+          new anchor.BN(0)
+        )
+        .accounts({
+          tokenAMint: freezableMint,
+          tokenBMint: otherMint,
+          tokenAMetadata: null,
+          tokenBMetadata: null,
+          tokenAMintApproval: null,
+          tokenBMintApproval: null,
+          treasury,
+          payer: provider.wallet.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          tokenAProgram: TOKEN_PROGRAM_ID,
+          tokenBProgram: TOKEN_PROGRAM_ID,
+        })
+        .rpc();
+      assert.fail("A freezable mint should be rejected under the policy");
+    } catch (err) {
+      assert.include(err.toString(), "FreezeAuthorityNotAllowed");
+    }
+    await setRejectFreezeAuthority(false);
+  });
+
+  it("Tracks hourly swap volume once a pool has volume stats", async () => {
+    const user = provider.wallet.publicKey;
+    const [volumeStats] = PublicKey.findProgramAddressSync(
+      [Buffer.from("volume_stats"), token2022Pool.liquidityPool.toBuffer()],
+      program.programId
+    );
+
+    await program.methods
+      .initializeVolumeStats()
+      .accounts({
+        liquidityPool: token2022Pool.liquidityPool,
+        payer: user,
+      })
+      .rpc();
+
+    const swap = (stats: PublicKey | null) =>
+      program.methods
+        .swapTokens(toTokenAmount(1), new anchor.BN(0), null, false, null, null)
+        .accounts({
+          liquidityPool: token2022Pool.liquidityPool,
+          poolAuthority: token2022Pool.poolAuthority,
+          inputTokenVault: token2022Pool.tokenAVault,
+          outputTokenVault: token2022Pool.tokenBVault,
+          inputTokenMint: token2022Pool.tokenAMint,
+          outputTokenMint: token2022Pool.tokenBMint,
+          oracle: token2022Pool.oracle,
+          userInputTokenAccount: getAssociatedTokenAddressSync(
+            token2022Pool.tokenAMint,
+            user
+          ),
+          userOutputTokenAccount: getAssociatedTokenAddressSync(
+            token2022Pool.tokenBMint,
+            user,
+            false,
+            TOKEN_2022_PROGRAM_ID
+          ),
+          insuranceVault: null,
+          protocolFeeVault: null,
+          feeRecipient: null,
+          creatorFeeAccount: null,
+          traderState: null,
+          launchWhitelistEntry: null,
+          memoProgram: null,
+          volumeStats: stats,
+          referrer: null,
+          referralVault: null,
+          user,
+          inputTokenOwner: user,
+          inputTokenProgram: TOKEN_PROGRAM_ID,
+          outputTokenProgram: TOKEN_2022_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
+    try {
+      await swap(null);
+      assert.fail("Swaps should require the pool's volume stats");
+    } catch (err) {
+      assert.include(err.toString(), "VolumeStatsAccountRequired");
+    }
+    await swap(volumeStats);
+
+    const reading = await program.methods
+      .readVolumeStats()
+      .accounts({
+        liquidityPool: token2022Pool.liquidityPool,
+        volumeStats,
+        tokenAVault: token2022Pool.tokenAVault,
+        tokenBVault: token2022Pool.tokenBVault,
+      })
+      .view();
+
+    assert.equal(reading.volumeA.toString(), toTokenAmount(1).toString());
+    assert.equal(reading.volumeB.toNumber(), 0);
+    assert(reading.lpFeesA.gtn(0), "LP fees should be recorded");
+    assert(reading.feeAprBps.gtn(0), "Fees should imply a non-zero APR");
   });
 });